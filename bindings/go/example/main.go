@@ -0,0 +1,39 @@
+// Command example proves and verifies a proof through the Go bindings.
+package main
+
+import (
+	"fmt"
+	"log"
+
+	"github.com/amitduabits/zk-proof-system/bindings/go/zkproof"
+)
+
+func main() {
+	if err := zkproof.CheckABI(); err != nil {
+		log.Fatal(err)
+	}
+
+	// The note of key 5 holding 100, alone at leaf 0 of its tree
+	witness := &zkproof.DCIWitness{SecretKey: [32]byte{5}, Balance: 100}
+	proof, err := zkproof.Prove(witness)
+	if err != nil {
+		log.Fatalf("prove: %v", err)
+	}
+	fmt.Printf("proof created, %d bytes\n", len(proof))
+
+	ctx, err := zkproof.NewContext(zkproof.DCIK)
+	if err != nil {
+		log.Fatal(err)
+	}
+	defer ctx.Close()
+	verifier, err := zkproof.NewVerifier(ctx)
+	if err != nil {
+		log.Fatal(err)
+	}
+	defer verifier.Close()
+
+	if err := verifier.Verify(proof); err != nil {
+		log.Fatalf("verify: %v", err)
+	}
+	fmt.Println("proof verified")
+}
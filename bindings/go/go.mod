@@ -0,0 +1,3 @@
+module github.com/amitduabits/zk-proof-system/bindings/go
+
+go 1.21
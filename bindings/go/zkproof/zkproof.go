@@ -0,0 +1,206 @@
+// Package zkproof verifies ZK proofs through the Rust static library.
+//
+// Build the library first from the repository root:
+//
+//	cargo build -p zk-proof-bindings --profile staticlib
+package zkproof
+
+/*
+#cgo CFLAGS: -I${SRCDIR}
+#cgo LDFLAGS: -L${SRCDIR}/../../../target/staticlib -lzk_proof_bindings -lm -ldl -lpthread
+#include "zkproof.h"
+*/
+import "C"
+
+import (
+	"errors"
+	"fmt"
+	"runtime"
+	"unsafe"
+)
+
+// ABIVersion is the C API version this package was written against.
+const ABIVersion = 2
+
+// DCITreeDepth is the number of siblings in a DCIWitness path.
+const DCITreeDepth = 20
+
+// DCIK is the log2 row count DCI proofs from Prove are made at, DCI_K in
+// zk-proof-core.
+const DCIK = 11
+
+// ErrInvalidProof is returned when a proof is well-formed but does not verify.
+var ErrInvalidProof = errors.New("zkproof: verification failed")
+
+// Error carries a non-zero status code returned by the library and the
+// message it recorded for the failure.
+type Error struct {
+	Code    int32
+	Message string
+}
+
+func (e *Error) Error() string {
+	if e.Message == "" {
+		return fmt.Sprintf("zkproof: library returned status %d", e.Code)
+	}
+	return fmt.Sprintf("zkproof: %s (status %d)", e.Message, e.Code)
+}
+
+// statusError must run on the OS thread that made the failing call, since
+// the library keeps the last error message per thread.
+func statusError(code C.int32_t) error {
+	switch code {
+	case 0:
+		return nil
+	case 2:
+		return ErrInvalidProof
+	default:
+		return &Error{Code: int32(code), Message: lastErrorMessage()}
+	}
+}
+
+func lastErrorMessage() string {
+	n := C.zk_last_error_message(nil, 0)
+	if n == 0 {
+		return ""
+	}
+	buf := make([]byte, n+1)
+	C.zk_last_error_message((*C.char)(unsafe.Pointer(&buf[0])), C.size_t(len(buf)))
+	return string(buf[:n])
+}
+
+// CheckABI reports an error when the linked library speaks a different ABI.
+func CheckABI() error {
+	if v := uint32(C.zk_abi_version()); v != ABIVersion {
+		return fmt.Errorf("zkproof: library ABI version %d, expected %d", v, ABIVersion)
+	}
+	return nil
+}
+
+// Context owns the params and keys of the DCI circuit at one size. It is
+// safe for concurrent use.
+type Context struct {
+	handle *C.ZkContext
+}
+
+// NewContext generates params and keys for DCI proofs at 2^k rows, which can
+// take seconds. Pass DCIK to check proofs from Prove. Call Close when done;
+// a finalizer frees the handle if Close is never called.
+func NewContext(k uint32) (*Context, error) {
+	runtime.LockOSThread()
+	defer runtime.UnlockOSThread()
+	handle := C.zk_context_new(C.uint32_t(k), C.ZK_CIRCUIT_DCI)
+	if handle == nil {
+		return nil, fmt.Errorf("zkproof: creating context: %s", lastErrorMessage())
+	}
+	c := &Context{handle: handle}
+	runtime.SetFinalizer(c, (*Context).Close)
+	return c, nil
+}
+
+// Close releases the native handle. Verifiers made from the context stay
+// usable. It is safe to call more than once.
+func (c *Context) Close() {
+	if c.handle != nil {
+		C.zk_context_free(c.handle)
+		c.handle = nil
+	}
+	runtime.SetFinalizer(c, nil)
+}
+
+// Verifier owns a native verifier handle. It is safe for concurrent use.
+type Verifier struct {
+	handle *C.ZkVerifier
+}
+
+// NewVerifier allocates a verifier for the proofs of ctx. Call Close when
+// done; a finalizer frees the handle if Close is never called.
+func NewVerifier(ctx *Context) (*Verifier, error) {
+	if ctx == nil || ctx.handle == nil {
+		return nil, errors.New("zkproof: context is closed")
+	}
+	v := &Verifier{handle: C.zk_verifier_from_context(ctx.handle)}
+	runtime.KeepAlive(ctx)
+	runtime.SetFinalizer(v, (*Verifier).Close)
+	return v, nil
+}
+
+// Close releases the native handle. It is safe to call more than once.
+func (v *Verifier) Close() {
+	if v.handle != nil {
+		C.zk_verifier_free(v.handle)
+		v.handle = nil
+	}
+	runtime.SetFinalizer(v, nil)
+}
+
+// Verify checks a serialized proof.
+func (v *Verifier) Verify(proof []byte) error {
+	if v.handle == nil {
+		return errors.New("zkproof: verifier is closed")
+	}
+	if len(proof) == 0 {
+		return &Error{Code: 1, Message: "empty proof"}
+	}
+	runtime.LockOSThread()
+	defer runtime.UnlockOSThread()
+	code := C.zk_verifier_verify(
+		v.handle,
+		(*C.uint8_t)(unsafe.Pointer(&proof[0])),
+		C.size_t(len(proof)),
+	)
+	runtime.KeepAlive(proof)
+	return statusError(code)
+}
+
+// DCIWitness is the witness of a DCI spend. Field elements are 32-byte
+// little-endian canonical encodings.
+type DCIWitness struct {
+	// SecretKey owns the spent note.
+	SecretKey [32]byte
+	// Balance is the balance of the note.
+	Balance uint64
+	// LeafIndex is the position of the note in the tree.
+	LeafIndex uint64
+	// Siblings holds the sibling at each tree level, starting next to the
+	// leaf.
+	Siblings [DCITreeDepth][32]byte
+}
+
+// toC copies w into the ZkDciWitness layout of zkproof.h.
+func (w *DCIWitness) toC() C.ZkDciWitness {
+	var c C.ZkDciWitness
+	for i, b := range w.SecretKey {
+		c.secret_key[i] = C.uint8_t(b)
+	}
+	c.balance = C.uint64_t(w.Balance)
+	c.leaf_index = C.uint64_t(w.LeafIndex)
+	for i, sibling := range w.Siblings {
+		for j, b := range sibling {
+			c.siblings[i][j] = C.uint8_t(b)
+		}
+	}
+	return c
+}
+
+// Prove creates a DCI proof of w at DCIK rows. The proof starts with the
+// root and nullifier it proves. The returned slice is owned by Go.
+func Prove(w *DCIWitness) ([]byte, error) {
+	if w == nil {
+		return nil, &Error{Code: 1, Message: "nil witness"}
+	}
+	input := w.toC()
+	runtime.LockOSThread()
+	defer runtime.UnlockOSThread()
+	var out C.ZkBuffer
+	code := C.zk_prove_owned(
+		(*C.uint8_t)(unsafe.Pointer(&input)),
+		C.size_t(unsafe.Sizeof(input)),
+		&out,
+	)
+	if err := statusError(code); err != nil {
+		return nil, err
+	}
+	defer C.zk_buffer_free(out)
+	return C.GoBytes(unsafe.Pointer(out.data), C.int(out.len)), nil
+}
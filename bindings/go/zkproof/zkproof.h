@@ -0,0 +1,44 @@
+#pragma once
+
+/* Stable C API subset consumed by the Go package (ABI version 2). */
+
+#include <stddef.h>
+#include <stdint.h>
+
+/* Circuit type of a context proving DCI spends. */
+#define ZK_CIRCUIT_DCI 0
+
+/* Siblings in the path of a DCI witness. */
+#define ZK_DCI_TREE_DEPTH 20
+
+typedef struct ZkContext ZkContext;
+typedef struct ZkVerifier ZkVerifier;
+
+/* Field elements are 32-byte little-endian canonical encodings. */
+typedef struct ZkDciWitness {
+  uint8_t secret_key[32];
+  uint64_t balance;
+  uint64_t leaf_index;
+  uint8_t siblings[ZK_DCI_TREE_DEPTH][32];
+} ZkDciWitness;
+
+typedef struct ZkBuffer {
+  uint8_t *data;
+  size_t len;
+} ZkBuffer;
+
+uint32_t zk_abi_version(void);
+
+ZkContext *zk_context_new(uint32_t k, uint32_t circuit_type);
+void zk_context_free(ZkContext *context);
+int32_t zk_proof_size(const ZkContext *context, size_t *size);
+
+ZkVerifier *zk_verifier_new(void);
+ZkVerifier *zk_verifier_from_context(const ZkContext *context);
+void zk_verifier_free(ZkVerifier *verifier);
+int32_t zk_verifier_verify(const ZkVerifier *verifier, const uint8_t *proof, size_t proof_len);
+
+int32_t zk_prove_owned(const uint8_t *input, size_t input_len, ZkBuffer *out);
+void zk_buffer_free(ZkBuffer buffer);
+
+size_t zk_last_error_message(char *buf, size_t len);
@@ -0,0 +1,66 @@
+package zkproof
+
+import (
+	"errors"
+	"testing"
+)
+
+// witness is the note of key 5 holding 100 at leaf 0. Prove derives the
+// root from the siblings, so zero siblings open a tree holding only this
+// note.
+func witness() *DCIWitness {
+	return &DCIWitness{SecretKey: [32]byte{5}, Balance: 100}
+}
+
+func TestProveAndVerify(t *testing.T) {
+	if err := CheckABI(); err != nil {
+		t.Fatal(err)
+	}
+	proof, err := Prove(witness())
+	if err != nil {
+		t.Fatalf("prove: %v", err)
+	}
+
+	ctx, err := NewContext(DCIK)
+	if err != nil {
+		t.Fatalf("context: %v", err)
+	}
+	verifier, err := NewVerifier(ctx)
+	if err != nil {
+		t.Fatalf("verifier: %v", err)
+	}
+	defer verifier.Close()
+	// The verifier keeps the keys alive
+	ctx.Close()
+
+	if err := verifier.Verify(proof); err != nil {
+		t.Fatalf("verify: %v", err)
+	}
+
+	// Flip a byte of the nullifier
+	proof[32] ^= 1
+	if err := verifier.Verify(proof); !errors.Is(err, ErrInvalidProof) {
+		t.Fatalf("tampered proof: got %v, want ErrInvalidProof", err)
+	}
+}
+
+func TestProveRejectsNonCanonicalKey(t *testing.T) {
+	w := witness()
+	for i := range w.SecretKey {
+		w.SecretKey[i] = 0xff
+	}
+	var libErr *Error
+	if _, err := Prove(w); !errors.As(err, &libErr) || libErr.Code != 16 {
+		t.Fatalf("got %v, want a serialization error", err)
+	}
+	if _, err := Prove(nil); err == nil {
+		t.Fatal("proved a nil witness")
+	}
+}
+
+func TestClosedContext(t *testing.T) {
+	ctx := &Context{}
+	if _, err := NewVerifier(ctx); err == nil {
+		t.Fatal("verifier from a closed context")
+	}
+}
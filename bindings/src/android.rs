@@ -0,0 +1,188 @@
+//! JNI bindings for Android
+//!
+//! Exposes DCI proving and proof verification to the JVM through the
+//! following class:
+//!
+//! ```java
+//! package com.zkproof;
+//!
+//! public final class ZkProof {
+//!     static { System.loadLibrary("zk_proof_bindings"); }
+//!
+//!     public static native boolean verify(byte[] proof);
+//!     public static native byte[] prove(byte[] input);
+//! }
+//! ```
+//!
+//! `prove` takes a [`crate::ffi::ZkDciWitness`] in its C layout and proves
+//! it once with the DCI context shared by the process. `verify` checks its
+//! proofs against the same context, unless a Rust embedder installed another verifier with
+//! [`set_verifier`].
+//!
+//! Native work runs on a dedicated rayon pool whose worker threads are
+//! attached to the JVM as daemons when they start and detached when they
+//! exit, so the JVM never sees an unattached native thread and never waits
+//! on one during shutdown.
+
+use std::{ffi::c_void, sync::OnceLock};
+
+use jni::{
+    objects::{JByteArray, JClass},
+    sys::{jboolean, jbyteArray, jint, JNI_ERR, JNI_FALSE, JNI_TRUE, JNI_VERSION_1_6},
+    JNIEnv, JavaVM,
+};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use zk_proof_verifier::prelude::{Verifier, VerifierResult};
+
+/// Java exception thrown when a native call fails
+const EXCEPTION_CLASS: &str = "java/lang/IllegalStateException";
+
+static JAVA_VM: OnceLock<JavaVM> = OnceLock::new();
+static POOL: OnceLock<ThreadPool> = OnceLock::new();
+static VERIFIER: OnceLock<Box<dyn Verifier + Send + Sync>> = OnceLock::new();
+
+/// Install the verifier used by `ZkProof.verify` instead of the shared DCI
+/// context
+///
+/// Call from Rust before the JVM verifies anything. Returns `false` if a
+/// verifier was already installed.
+pub fn set_verifier(verifier: impl Verifier + Send + Sync + 'static) -> bool {
+    VERIFIER.set(Box::new(verifier)).is_ok()
+}
+
+/// Library entry point invoked by `System.loadLibrary`
+///
+/// Stores the `JavaVM` handle and builds the JVM-attached worker pool.
+#[no_mangle]
+pub extern "system" fn JNI_OnLoad(vm: JavaVM, _reserved: *mut c_void) -> jint {
+    // A second load of the same library keeps the original VM handle.
+    let _ = JAVA_VM.set(vm);
+
+    let pool = ThreadPoolBuilder::new()
+        .thread_name(|index| format!("zk-proof-worker-{index}"))
+        .start_handler(|_| attach_current_thread())
+        .exit_handler(|_| detach_current_thread())
+        .build();
+
+    match pool {
+        Ok(pool) => {
+            let _ = POOL.set(pool);
+            JNI_VERSION_1_6
+        }
+        Err(_) => JNI_ERR,
+    }
+}
+
+/// Attach the calling worker thread to the JVM as a daemon
+fn attach_current_thread() {
+    if let Some(vm) = JAVA_VM.get() {
+        // Failure leaves the thread unattached; it never calls back into Java.
+        let _ = vm.attach_current_thread_as_daemon();
+    }
+}
+
+/// Detach the calling worker thread from the JVM
+fn detach_current_thread() {
+    if let Some(vm) = JAVA_VM.get() {
+        // SAFETY: called from the rayon exit handler, after the worker has
+        // finished all jobs, so no local references or `AttachGuard`s remain.
+        unsafe { vm.detach_current_thread() };
+    }
+}
+
+/// Run `op` on the JVM-attached pool, or inline if `JNI_OnLoad` never ran
+fn run<R: Send>(op: impl FnOnce() -> R + Send) -> R {
+    match POOL.get() {
+        Some(pool) => pool.install(op),
+        None => op(),
+    }
+}
+
+/// Prove the serialized `ZkDciWitness` in `input` with the shared DCI
+/// context
+fn create_proof(input: &[u8]) -> Result<Vec<u8>, String> {
+    let context = crate::ffi::shared_context().map_err(|err| err.to_string())?;
+    // SAFETY: `input` is a live slice of `input.len()` bytes.
+    unsafe { crate::ffi::create(context, input.as_ptr(), input.len()) }.map_err(|status| {
+        crate::with_last_error(|message| {
+            format!("proof creation failed with status {status}: {message}")
+        })
+    })
+}
+
+/// Throw a Java exception unless one is already pending
+fn throw(env: &mut JNIEnv<'_>, msg: &str) {
+    if !env.exception_check().unwrap_or(true) {
+        let _ = env.throw_new(EXCEPTION_CLASS, msg);
+    }
+}
+
+/// `ZkProof.verify(byte[])`
+#[no_mangle]
+pub extern "system" fn Java_com_zkproof_ZkProof_verify<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    proof: JByteArray<'local>,
+) -> jboolean {
+    let proof = match env.convert_byte_array(&proof) {
+        Ok(proof) => proof,
+        Err(err) => {
+            throw(&mut env, &err.to_string());
+            return JNI_FALSE;
+        }
+    };
+
+    let result = match VERIFIER.get() {
+        Some(verifier) => run(|| verifier.verify(&proof)),
+        None => match crate::ffi::shared_context() {
+            Ok(context) => run(|| context.verifier().verify(&proof)),
+            Err(err) => {
+                throw(&mut env, &err.to_string());
+                return JNI_FALSE;
+            }
+        },
+    };
+
+    match result {
+        VerifierResult::Valid => JNI_TRUE,
+        VerifierResult::Invalid => JNI_FALSE,
+        VerifierResult::Error => {
+            throw(&mut env, "verification error");
+            JNI_FALSE
+        }
+    }
+}
+
+/// `ZkProof.prove(byte[])`
+///
+/// Returns `null` with a pending exception on failure.
+#[no_mangle]
+pub extern "system" fn Java_com_zkproof_ZkProof_prove<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    input: JByteArray<'local>,
+) -> jbyteArray {
+    let input = match env.convert_byte_array(&input) {
+        Ok(input) => input,
+        Err(err) => {
+            throw(&mut env, &err.to_string());
+            return std::ptr::null_mut();
+        }
+    };
+
+    let proof = match run(|| create_proof(&input)) {
+        Ok(proof) => proof,
+        Err(msg) => {
+            throw(&mut env, &msg);
+            return std::ptr::null_mut();
+        }
+    };
+
+    match env.byte_array_from_slice(&proof) {
+        Ok(array) => array.into_raw(),
+        Err(err) => {
+            throw(&mut env, &err.to_string());
+            std::ptr::null_mut()
+        }
+    }
+}
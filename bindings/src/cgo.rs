@@ -0,0 +1,299 @@
+//! Stable C API subset for Go (cgo) consumers
+//!
+//! Go cannot hand ownership of Rust allocations back and forth safely without
+//! an explicit lifecycle, so this module exposes opaque handles with matching
+//! `_free` functions and Rust-owned output buffers instead of caller-sized
+//! ones. Everything here is covered by [`ABI_VERSION`]: signatures only change
+//! together with a version bump.
+//!
+//! The matching header and Go package live in `bindings/go/zkproof`.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use zk_proof_verifier::prelude::{Verifier, VerifierResult};
+
+use crate::{fail, fail_panic, fail_with, ffi::ZkContext, ErrorCode};
+
+/// Version of the stable C API exposed by this module
+pub const ABI_VERSION: u32 = 2;
+
+/// Opaque verifier handle owned by the caller
+///
+/// Handles from [`zk_verifier_from_context`] verify that context's proofs.
+/// Handles from [`zk_verifier_new`] hold no verifying key and report
+/// [`ErrorCode::CircuitUnknown`] for every proof; Rust embedders create
+/// keyed handles with [`ZkVerifier::new`].
+#[derive(Default)]
+pub struct ZkVerifier {
+    inner: Option<Box<dyn Verifier + Send + Sync>>,
+}
+
+impl ZkVerifier {
+    /// Handle verifying proofs with `verifier`
+    #[must_use]
+    pub fn new(verifier: impl Verifier + Send + Sync + 'static) -> Self {
+        Self {
+            inner: Some(Box::new(verifier)),
+        }
+    }
+}
+
+impl std::fmt::Debug for ZkVerifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ZkVerifier")
+            .field("keyed", &self.inner.is_some())
+            .finish()
+    }
+}
+
+/// Byte buffer allocated by Rust and released with [`zk_buffer_free`]
+#[repr(C)]
+#[derive(Debug)]
+pub struct ZkBuffer {
+    /// Pointer to the first byte, null for an empty buffer
+    pub data: *mut u8,
+    /// Number of bytes
+    pub len: usize,
+}
+
+impl ZkBuffer {
+    fn empty() -> Self {
+        Self {
+            data: std::ptr::null_mut(),
+            len: 0,
+        }
+    }
+
+    fn from_vec(bytes: Vec<u8>) -> Self {
+        if bytes.is_empty() {
+            return Self::empty();
+        }
+        let len = bytes.len();
+        let data = Box::into_raw(bytes.into_boxed_slice()).cast::<u8>();
+        Self { data, len }
+    }
+}
+
+/// Return the version of the stable C API
+#[no_mangle]
+pub extern "C" fn zk_abi_version() -> u32 {
+    ABI_VERSION
+}
+
+/// Allocate a new verifier handle
+///
+/// The handle must be released with [`zk_verifier_free`].
+#[no_mangle]
+pub extern "C" fn zk_verifier_new() -> *mut ZkVerifier {
+    Box::into_raw(Box::default())
+}
+
+/// Allocate a verifier handle for the proofs of `context`
+///
+/// The handle shares the context's params and keys and stays valid after
+/// [`crate::ffi::zk_context_free`]. Returns null for a null `context`. The
+/// handle must be released with [`zk_verifier_free`].
+///
+/// # Safety
+///
+/// `context` must be null or a live context from
+/// [`crate::ffi::zk_context_new`].
+#[no_mangle]
+pub unsafe extern "C" fn zk_verifier_from_context(context: *const ZkContext) -> *mut ZkVerifier {
+    let Some(context) = context.as_ref() else {
+        fail(
+            ErrorCode::InvalidParameter,
+            "null argument to zk_verifier_from_context",
+        );
+        return std::ptr::null_mut();
+    };
+    Box::into_raw(Box::new(ZkVerifier::new(context.verifier())))
+}
+
+/// Release a verifier handle
+///
+/// # Safety
+///
+/// `verifier` must be null or a pointer returned by [`zk_verifier_new`] or
+/// [`zk_verifier_from_context`] that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn zk_verifier_free(verifier: *mut ZkVerifier) {
+    if !verifier.is_null() {
+        drop(Box::from_raw(verifier));
+    }
+}
+
+/// Verify a proof with a verifier handle
+///
+/// # Safety
+///
+/// `verifier` must be a live handle from [`zk_verifier_new`] or
+/// [`zk_verifier_from_context`] and `proof` must point to `proof_len`
+/// readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn zk_verifier_verify(
+    verifier: *const ZkVerifier,
+    proof: *const u8,
+    proof_len: usize,
+) -> i32 {
+    if verifier.is_null() || proof.is_null() {
+        return fail(
+            ErrorCode::InvalidParameter,
+            "null argument to zk_verifier_verify",
+        );
+    }
+
+    let Some(verifier) = &(*verifier).inner else {
+        return fail(ErrorCode::CircuitUnknown, "verifier has no circuit keys");
+    };
+    let proof = std::slice::from_raw_parts(proof, proof_len);
+
+    match catch_unwind(AssertUnwindSafe(|| verifier.verify(proof))) {
+        Ok(VerifierResult::Valid) => ErrorCode::Success as i32,
+        Ok(VerifierResult::Invalid) => fail(ErrorCode::VerificationFailed, "proof is invalid"),
+        Ok(VerifierResult::Error) => fail(ErrorCode::Unknown, "verifier failed"),
+        Err(payload) => fail_panic(payload.as_ref()),
+    }
+}
+
+/// Create a proof into a Rust-owned buffer
+///
+/// `input` holds a [`crate::ffi::ZkDciWitness`], as for
+/// [`crate::ffi::zk_proof_create`], and is proved with a DCI context shared
+/// by the whole process. On success `out` holds the proof and must be
+/// released with [`zk_buffer_free`].
+///
+/// # Safety
+///
+/// `input` must point to `input_len` readable bytes and `out` must be valid
+/// for writes.
+#[no_mangle]
+pub unsafe extern "C" fn zk_prove_owned(
+    input: *const u8,
+    input_len: usize,
+    out: *mut ZkBuffer,
+) -> i32 {
+    if input.is_null() || out.is_null() {
+        return fail(
+            ErrorCode::InvalidParameter,
+            "null argument to zk_prove_owned",
+        );
+    }
+    out.write(ZkBuffer::empty());
+    let context = match catch_unwind(crate::ffi::shared_context) {
+        Ok(Ok(context)) => context,
+        Ok(Err(err)) => return fail_with(&err),
+        Err(payload) => return fail_panic(payload.as_ref()),
+    };
+    match crate::ffi::create(context, input, input_len) {
+        Ok(proof) => {
+            out.write(ZkBuffer::from_vec(proof));
+            ErrorCode::Success as i32
+        }
+        Err(code) => code,
+    }
+}
+
+/// Release a buffer returned by this module
+///
+/// # Safety
+///
+/// `buffer` must have been produced by this module and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn zk_buffer_free(buffer: ZkBuffer) {
+    if !buffer.data.is_null() {
+        let slice = std::ptr::slice_from_raw_parts_mut(buffer.data, buffer.len);
+        drop(Box::from_raw(slice));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zk_proof_core::circuits::dci::DCI_K;
+
+    use super::*;
+
+    #[test]
+    fn test_verifier_lifecycle() {
+        let verifier = zk_verifier_new();
+        assert!(!verifier.is_null());
+
+        let proof = [0u8; 32];
+        let code = unsafe { zk_verifier_verify(verifier, proof.as_ptr(), proof.len()) };
+        assert_eq!(code, ErrorCode::CircuitUnknown as i32);
+
+        unsafe { zk_verifier_free(verifier) };
+    }
+
+    #[test]
+    fn test_keyed_verifier() {
+        struct Rejecting;
+
+        impl Verifier for Rejecting {
+            fn verify(&self, _: &[u8]) -> VerifierResult {
+                VerifierResult::Invalid
+            }
+        }
+
+        let verifier = Box::into_raw(Box::new(ZkVerifier::new(Rejecting)));
+        let proof = [0u8; 32];
+        let code = unsafe { zk_verifier_verify(verifier, proof.as_ptr(), proof.len()) };
+        assert_eq!(code, ErrorCode::VerificationFailed as i32);
+
+        unsafe { zk_verifier_free(verifier) };
+    }
+
+    #[test]
+    fn test_verifier_from_context() {
+        let context = crate::ffi::zk_context_new(DCI_K, crate::ffi::ZK_CIRCUIT_DCI);
+        assert!(!context.is_null());
+        let verifier = unsafe { zk_verifier_from_context(context) };
+        assert!(!verifier.is_null());
+        // The handle keeps the keys alive
+        unsafe { crate::ffi::zk_context_free(context) };
+
+        let mut out = ZkBuffer::empty();
+        let witness = crate::ffi::tests::witness();
+        let code = unsafe {
+            zk_prove_owned(
+                std::ptr::from_ref(&witness).cast(),
+                std::mem::size_of_val(&witness),
+                &raw mut out,
+            )
+        };
+        assert_eq!(code, ErrorCode::Success as i32);
+        let code = unsafe { zk_verifier_verify(verifier, out.data, out.len) };
+        assert_eq!(code, ErrorCode::Success as i32);
+
+        // Flip a byte of the nullifier
+        unsafe { *out.data.add(32) ^= 1 };
+        let code = unsafe { zk_verifier_verify(verifier, out.data, out.len) };
+        assert_eq!(code, ErrorCode::VerificationFailed as i32);
+
+        unsafe { zk_buffer_free(out) };
+        unsafe { zk_verifier_free(verifier) };
+        assert!(unsafe { zk_verifier_from_context(std::ptr::null()) }.is_null());
+    }
+
+    #[test]
+    fn test_null_arguments_rejected() {
+        let proof = [0u8; 4];
+        let code = unsafe { zk_verifier_verify(std::ptr::null(), proof.as_ptr(), proof.len()) };
+        assert_eq!(code, ErrorCode::InvalidParameter as i32);
+
+        let code = unsafe { zk_prove_owned(std::ptr::null(), 0, std::ptr::null_mut()) };
+        assert_eq!(code, ErrorCode::InvalidParameter as i32);
+    }
+
+    #[test]
+    fn test_buffer_round_trip() {
+        // Not a serialized witness
+        let input = [1u8, 2, 3];
+        let mut out = ZkBuffer::from_vec(vec![1]);
+        let code = unsafe { zk_prove_owned(input.as_ptr(), input.len(), &raw mut out) };
+        assert_eq!(code, ErrorCode::Serialization as i32);
+        assert!(out.data.is_null());
+
+        unsafe { zk_buffer_free(ZkBuffer::from_vec(vec![7u8; 16])) };
+    }
+}
@@ -1,37 +1,636 @@
 //! FFI bindings for C/C++ interop
+//!
+//! A [`ZkContext`] from [`zk_context_new`] holds the params and keys of one
+//! circuit, generated once when it is created. [`zk_proof_create`] proves a
+//! DCI spend from a [`ZkDciWitness`] with it and [`zk_proof_verify`] checks
+//! the result. Contexts may be shared between threads and are released with
+//! [`zk_context_free`]. A proof buffer starts with the [`ZkDciPublicInputs`]
+//! it proves, followed by the halo2 proof, so it can be verified on its own.
+//! Every proof of a context has the same length, reported by
+//! [`zk_proof_size`] so callers can size the output buffer up front.
+//! [`ZkContext::verifier`] shares a context's keys with verifiers that
+//! outlive it, such as the handles of [`crate::cgo::zk_verifier_from_context`].
+//!
+//! Field elements cross the boundary as their 32-byte little-endian
+//! canonical encoding.
 
+use std::{
+    ffi::c_char,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{Arc, OnceLock},
+};
 
-/// Create a new proof
+use halo2_proofs::{
+    plonk::{verify_proof, ProvingKey, SingleVerifier},
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Challenge255},
+};
+use zk_proof_core::{
+    circuits::{
+        dci::{DCI_K, DCI_TREE_DEPTH},
+        DCICircuit, DCIPublicInputs, DCIWitnessBuilder, MerkleProof,
+    },
+    prelude::{vesta, PrimeField},
+    write_proof, CircuitRegistry, Error, KeyStore, OsRandomness, PublicInputs, Result,
+};
+use zk_proof_verifier::prelude::{Verifier, VerifierResult};
+
+use crate::{fail, fail_panic, fail_with, ErrorCode};
+
+/// Witness of a DCI spend, laid out for C callers
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct ZkDciWitness {
+    /// Secret key owning the note
+    pub secret_key: [u8; 32],
+    /// Balance of the note
+    pub balance: u64,
+    /// Position of the note in the tree
+    pub leaf_index: u64,
+    /// Sibling at each tree level, starting next to the leaf
+    pub siblings: [[u8; 32]; DCI_TREE_DEPTH],
+}
+
+/// Public inputs of a DCI proof, laid out for C callers
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ZkDciPublicInputs {
+    /// Root of the note tree
+    pub root: [u8; 32],
+    /// Nullifier of the spent note
+    pub nullifier: [u8; 32],
+}
+
+const PUBLIC_INPUTS_LEN: usize = std::mem::size_of::<ZkDciPublicInputs>();
+
+/// Circuit type of a [`ZkContext`] proving DCI spends
+///
+/// Circuit types are the ids of a [`CircuitRegistry`], and proof envelopes
+/// carry the same ids.
+pub const ZK_CIRCUIT_DCI: u32 = 0;
+
+/// Params and keys of one circuit, shared across proof calls
+pub struct ZkContext {
+    registry: CircuitRegistry<vesta::Affine>,
+    circuit: u64,
+    pk: Arc<ProvingKey<vesta::Affine>>,
+    verifier: DciVerifier,
+    /// Length of every proof, known after the first one
+    proof_len: OnceLock<usize>,
+}
+
+impl ZkContext {
+    /// Context for `circuit_type` at `2^k` rows, generating params and keys
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Other`] for an unknown circuit type and
+    /// [`Error::Plonk`] if keygen fails, for instance when `k` is too small.
+    pub fn new(k: u32, circuit_type: u32) -> Result<Self> {
+        let circuit = u64::from(circuit_type);
+        if circuit != u64::from(ZK_CIRCUIT_DCI) {
+            return Err(Error::Other(format!("unknown circuit type {circuit_type}")));
+        }
+        let mut registry = CircuitRegistry::new(KeyStore::in_memory());
+        registry.register(circuit, "dci", 1, k, DCICircuit::<vesta::Scalar>::default)?;
+        let pk = registry.proving_key(circuit)?;
+        let verifier = DciVerifier {
+            params: registry.params(circuit)?,
+            pk: Arc::clone(&pk),
+        };
+        Ok(Self {
+            registry,
+            circuit,
+            pk,
+            verifier,
+            proof_len: OnceLock::new(),
+        })
+    }
+
+    /// Verifier of this context's proofs, sharing its params and keys
+    #[must_use]
+    pub fn verifier(&self) -> DciVerifier {
+        self.verifier.clone()
+    }
+
+    /// Length in bytes of every proof this context creates
+    ///
+    /// The first call without a proof made yet proves a placeholder spend
+    /// to measure it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of proving the placeholder.
+    pub fn proof_len(&self) -> Result<usize> {
+        if let Some(len) = self.proof_len.get() {
+            return Ok(*len);
+        }
+        let placeholder = ZkDciWitness {
+            secret_key: [0; 32],
+            balance: 0,
+            leaf_index: 0,
+            siblings: [[0; 32]; DCI_TREE_DEPTH],
+        };
+        Ok(self.create(&placeholder)?.len())
+    }
+
+    pub(crate) fn create(&self, witness: &ZkDciWitness) -> Result<Vec<u8>> {
+        let path = MerkleProof {
+            index: witness.leaf_index,
+            siblings: witness
+                .siblings
+                .iter()
+                .map(|sibling| decode(*sibling))
+                .collect::<Result<_>>()?,
+        };
+        let (circuit, public) = DCIWitnessBuilder::new()
+            .with_merkle_proof(&path)
+            .with_balance(witness.balance)
+            .with_secret_key(decode(witness.secret_key)?)
+            .build()?;
+
+        let instances = public.to_instances();
+        let mut output = Vec::with_capacity(PUBLIC_INPUTS_LEN + 4096);
+        output.extend_from_slice(&public.to_bytes());
+        let proof = write_proof(
+            &*self.registry.params(self.circuit)?,
+            &self.pk,
+            &[circuit],
+            &[&[instances[0].as_slice()]],
+            OsRandomness,
+            output,
+        )?;
+        let _ = self.proof_len.set(proof.len());
+        Ok(proof)
+    }
+
+    pub(crate) fn verify(&self, bytes: &[u8]) -> Result<()> {
+        self.verifier.check(bytes)
+    }
+}
+
+impl std::fmt::Debug for ZkContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ZkContext")
+            .field("circuit", &self.circuit)
+            .finish_non_exhaustive()
+    }
+}
+
+/// [`Verifier`] of the DCI proofs of a [`ZkContext`]
+///
+/// Holds the context's params and proving key by reference count, so it
+/// stays usable after the context is freed.
+#[derive(Clone)]
+pub struct DciVerifier {
+    params: Arc<Params<vesta::Affine>>,
+    pk: Arc<ProvingKey<vesta::Affine>>,
+}
+
+impl DciVerifier {
+    /// Check a proof from [`zk_proof_create`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if `bytes` do not start with public
+    /// inputs and [`Error::Verification`] if the proof is rejected.
+    pub fn check(&self, bytes: &[u8]) -> Result<()> {
+        if bytes.len() < PUBLIC_INPUTS_LEN {
+            return Err(Error::Encoding(format!(
+                "DCI proof of {} bytes has no public inputs",
+                bytes.len()
+            )));
+        }
+        let (public, proof) = bytes.split_at(PUBLIC_INPUTS_LEN);
+        let public = DCIPublicInputs::<vesta::Scalar>::from_bytes(public)?;
+
+        let instances = public.to_instances();
+        let mut transcript = Blake2bRead::<_, vesta::Affine, Challenge255<_>>::init(proof);
+        verify_proof(
+            &self.params,
+            self.pk.get_vk(),
+            SingleVerifier::new(&self.params),
+            &[&[instances[0].as_slice()]],
+            &mut transcript,
+        )
+        .map_err(|_| Error::Verification("DCI proof rejected".into()))
+    }
+}
+
+impl Verifier for DciVerifier {
+    fn verify(&self, proof: &[u8]) -> VerifierResult {
+        match self.check(proof) {
+            Ok(()) => VerifierResult::Valid,
+            Err(Error::Verification(_) | Error::Encoding(_)) => VerifierResult::Invalid,
+            Err(_) => VerifierResult::Error,
+        }
+    }
+}
+
+impl std::fmt::Debug for DciVerifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DciVerifier")
+            .field("k", &self.params.k())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Create a context for `circuit_type` with params for `2^k` rows
+///
+/// Generates the params and keys, which can take seconds. Returns null for
+/// an unknown circuit type or a `k` too small for the circuit, with the
+/// reason in [`zk_last_error_message`]. The context must be released with
+/// [`zk_context_free`].
+#[no_mangle]
+pub extern "C" fn zk_context_new(k: u32, circuit_type: u32) -> *mut ZkContext {
+    match catch_unwind(|| ZkContext::new(k, circuit_type)) {
+        Ok(Ok(context)) => Box::into_raw(Box::new(context)),
+        Ok(Err(err)) => {
+            fail_with(&err);
+            std::ptr::null_mut()
+        }
+        Err(payload) => {
+            fail_panic(payload.as_ref());
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Release a context
 ///
 /// # Safety
 ///
-/// This function is unsafe because it dereferences raw pointers.
+/// `context` must be null or a pointer returned by [`zk_context_new`] that
+/// has not been freed yet, and no other call may be using it.
+#[no_mangle]
+pub unsafe extern "C" fn zk_context_free(context: *mut ZkContext) {
+    if !context.is_null() {
+        drop(Box::from_raw(context));
+    }
+}
+
+/// Create a DCI proof
+///
+/// `input` holds one [`ZkDciWitness`]. On entry `*output_len` is the size of
+/// `output`; on success it is set to the number of bytes written. Size
+/// `output` with [`zk_proof_size`]: if the proof does not fit, it is
+/// discarded, `*output_len` is set to the size needed and
+/// [`ErrorCode::BufferTooSmall`] is returned.
+///
+/// # Safety
+///
+/// `context` must be a live context from [`zk_context_new`], `input` must
+/// point to `input_len` readable bytes, `output` to `*output_len` writable
+/// bytes, and `output_len` must be valid for reads and writes.
 #[no_mangle]
 pub unsafe extern "C" fn zk_proof_create(
+    context: *const ZkContext,
     input: *const u8,
     input_len: usize,
     output: *mut u8,
     output_len: *mut usize,
 ) -> i32 {
-    if input.is_null() || output.is_null() || output_len.is_null() {
-        return -1;
+    if context.is_null() || input.is_null() || output.is_null() || output_len.is_null() {
+        return fail(
+            ErrorCode::InvalidParameter,
+            "null argument to zk_proof_create",
+        );
+    }
+    let proof = match create(&*context, input, input_len) {
+        Ok(proof) => proof,
+        Err(code) => return code,
+    };
+    if proof.len() > *output_len {
+        let available = *output_len;
+        *output_len = proof.len();
+        return fail(
+            ErrorCode::BufferTooSmall,
+            format_args!("proof needs {} bytes, buffer has {available}", proof.len()),
+        );
+    }
+    std::ptr::copy_nonoverlapping(proof.as_ptr(), output, proof.len());
+    *output_len = proof.len();
+    ErrorCode::Success as i32
+}
+
+/// Prove the [`ZkDciWitness`] at `input` with `context`, recording any
+/// failure
+///
+/// # Safety
+///
+/// `input` must point to `input_len` readable bytes.
+pub(crate) unsafe fn create(
+    context: &ZkContext,
+    input: *const u8,
+    input_len: usize,
+) -> std::result::Result<Vec<u8>, i32> {
+    if input_len != std::mem::size_of::<ZkDciWitness>() {
+        return Err(fail(
+            ErrorCode::Serialization,
+            format_args!(
+                "witness is {input_len} bytes, expected {}",
+                std::mem::size_of::<ZkDciWitness>()
+            ),
+        ));
+    }
+    let witness = input.cast::<ZkDciWitness>().read_unaligned();
+
+    match catch_unwind(AssertUnwindSafe(|| context.create(&witness))) {
+        Ok(Ok(proof)) => Ok(proof),
+        Ok(Err(err)) => Err(fail_with(&err)),
+        Err(payload) => Err(fail_panic(payload.as_ref())),
+    }
+}
+
+/// Write the length of every proof from `context` to `*size`
+///
+/// The length is fixed per context. Without a proof made yet, the first
+/// call proves a placeholder spend to measure it.
+///
+/// # Safety
+///
+/// `context` must be a live context from [`zk_context_new`] and `size`
+/// must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn zk_proof_size(context: *const ZkContext, size: *mut usize) -> i32 {
+    if context.is_null() || size.is_null() {
+        return fail(
+            ErrorCode::InvalidParameter,
+            "null argument to zk_proof_size",
+        );
+    }
+    let context = &*context;
+    match catch_unwind(AssertUnwindSafe(|| context.proof_len())) {
+        Ok(Ok(len)) => {
+            size.write(len);
+            ErrorCode::Success as i32
+        }
+        Ok(Err(err)) => fail_with(&err),
+        Err(payload) => fail_panic(payload.as_ref()),
+    }
+}
+
+/// Verify a DCI proof from [`zk_proof_create`]
+///
+/// Returns [`ErrorCode::VerificationFailed`] for a proof that does not
+/// verify and [`ErrorCode::Serialization`] for one too short to hold its
+/// public inputs or whose public inputs are not field elements.
+///
+/// # Safety
+///
+/// `context` must be a live context from [`zk_context_new`] and `proof`
+/// must point to `proof_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn zk_proof_verify(
+    context: *const ZkContext,
+    proof: *const u8,
+    proof_len: usize,
+) -> i32 {
+    if context.is_null() || proof.is_null() {
+        return fail(
+            ErrorCode::InvalidParameter,
+            "null argument to zk_proof_verify",
+        );
     }
+    let context = &*context;
+    let proof = std::slice::from_raw_parts(proof, proof_len);
 
-    // Implementation would go here
-    0
+    match catch_unwind(AssertUnwindSafe(|| context.verify(proof))) {
+        Ok(Ok(())) => ErrorCode::Success as i32,
+        Ok(Err(err)) => fail_with(&err),
+        Err(payload) => fail_panic(payload.as_ref()),
+    }
 }
 
-/// Verify a proof
+/// Run the startup self-test
+///
+/// Proves and verifies a tiny built-in circuit against embedded test
+/// vectors. Call once at startup and refuse to serve traffic unless it
+/// returns `ZK_ERROR_CODE_SUCCESS`.
+#[no_mangle]
+pub extern "C" fn zk_selftest() -> i32 {
+    match catch_unwind(zk_proof_core::selftest) {
+        Ok(Ok(())) => ErrorCode::Success as i32,
+        Ok(Err(err)) => fail_with(&err),
+        Err(payload) => fail_panic(payload.as_ref()),
+    }
+}
+
+/// Copy the calling thread's last error message into `buf`
+///
+/// Every function in this module that fails records why, per thread, until
+/// the next failure on that thread. The message is written like `snprintf`:
+/// truncated to fit and NUL-terminated whenever `len` is non-zero. Returns
+/// the full length of the message in bytes without the NUL, zero if no
+/// call has failed yet, so a return value of `len` or more means the
+/// message was truncated.
 ///
 /// # Safety
 ///
-/// This function is unsafe because it dereferences raw pointers.
+/// `buf` must be null or point to `len` writable bytes.
 #[no_mangle]
-pub unsafe extern "C" fn zk_proof_verify(proof: *const u8, proof_len: usize) -> i32 {
-    if proof.is_null() {
-        return -1;
+pub unsafe extern "C" fn zk_last_error_message(buf: *mut c_char, len: usize) -> usize {
+    crate::with_last_error(|message| {
+        if !buf.is_null() && len > 0 {
+            let copied = message.len().min(len - 1);
+            std::ptr::copy_nonoverlapping(message.as_ptr(), buf.cast::<u8>(), copied);
+            buf.add(copied).write(0);
+        }
+        message.len()
+    })
+}
+
+/// DCI context shared by callers without one of their own
+pub(crate) fn shared_context() -> Result<&'static ZkContext> {
+    static CONTEXT: OnceLock<ZkContext> = OnceLock::new();
+    if let Some(context) = CONTEXT.get() {
+        return Ok(context);
+    }
+    let context = ZkContext::new(DCI_K, ZK_CIRCUIT_DCI)?;
+    Ok(CONTEXT.get_or_init(|| context))
+}
+
+fn decode(bytes: [u8; 32]) -> Result<vesta::Scalar> {
+    Option::<vesta::Scalar>::from(vesta::Scalar::from_repr(bytes))
+        .ok_or_else(|| Error::Encoding("not a canonical field element".into()))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use zk_proof_core::MerkleTree;
+
+    use super::*;
+
+    #[test]
+    fn test_selftest() {
+        assert_eq!(zk_selftest(), ErrorCode::Success as i32);
+    }
+
+    /// Witness for the note of key 5 holding 100, third leaf of a tree
+    pub(crate) fn witness() -> ZkDciWitness {
+        let secret_key = vesta::Scalar::from(5);
+        let mut tree = MerkleTree::new(DCI_TREE_DEPTH);
+        tree.insert(vesta::Scalar::from(1)).unwrap();
+        tree.insert(vesta::Scalar::from(2)).unwrap();
+        let index = tree
+            .insert(DCIWitnessBuilder::commitment(secret_key, 100))
+            .unwrap();
+        let path = tree.prove_index(index).unwrap();
+
+        let mut siblings = [[0; 32]; DCI_TREE_DEPTH];
+        for (out, sibling) in siblings.iter_mut().zip(&path.siblings) {
+            *out = sibling.to_repr();
+        }
+        ZkDciWitness {
+            secret_key: secret_key.to_repr(),
+            balance: 100,
+            leaf_index: index,
+            siblings,
+        }
+    }
+
+    #[test]
+    fn test_witness_layout() {
+        // Mirrors `ZkDciWitness` in `bindings/go/zkproof/zkproof.h`
+        assert_eq!(std::mem::offset_of!(ZkDciWitness, balance), 32);
+        assert_eq!(std::mem::offset_of!(ZkDciWitness, leaf_index), 40);
+        assert_eq!(std::mem::offset_of!(ZkDciWitness, siblings), 48);
+        assert_eq!(
+            std::mem::size_of::<ZkDciWitness>(),
+            48 + 32 * DCI_TREE_DEPTH
+        );
+    }
+
+    fn context() -> *const ZkContext {
+        shared_context().unwrap()
+    }
+
+    fn create_into(witness: &ZkDciWitness, output: &mut [u8]) -> (i32, usize) {
+        let mut len = output.len();
+        let code = unsafe {
+            zk_proof_create(
+                context(),
+                std::ptr::from_ref(witness).cast(),
+                std::mem::size_of::<ZkDciWitness>(),
+                output.as_mut_ptr(),
+                &raw mut len,
+            )
+        };
+        (code, len)
     }
 
-    // Implementation would go here
-    0
+    #[test]
+    fn test_create_and_verify() {
+        let witness = witness();
+        let (code, needed) = create_into(&witness, &mut [0; 16]);
+        assert_eq!(code, ErrorCode::BufferTooSmall as i32);
+        assert!(needed > PUBLIC_INPUTS_LEN);
+
+        let mut size = 0;
+        let code = unsafe { zk_proof_size(context(), &raw mut size) };
+        assert_eq!(code, ErrorCode::Success as i32);
+        assert_eq!(size, needed);
+
+        let mut proof = vec![0; size];
+        let (code, len) = create_into(&witness, &mut proof);
+        assert_eq!(code, ErrorCode::Success as i32);
+        assert_eq!(len, size);
+        let code = unsafe { zk_proof_verify(context(), proof.as_ptr(), proof.len()) };
+        assert_eq!(code, ErrorCode::Success as i32);
+
+        // A proof moved to another nullifier is rejected
+        proof[32] ^= 1;
+        let code = unsafe { zk_proof_verify(context(), proof.as_ptr(), proof.len()) };
+        assert_eq!(code, ErrorCode::VerificationFailed as i32);
+    }
+
+    #[test]
+    fn test_invalid_input_rejected() {
+        let mut output = [0u8; 64];
+        let mut len = output.len();
+        let input = [0u8; 3];
+        let code = unsafe {
+            zk_proof_create(
+                context(),
+                input.as_ptr(),
+                input.len(),
+                output.as_mut_ptr(),
+                &raw mut len,
+            )
+        };
+        assert_eq!(code, ErrorCode::Serialization as i32);
+        assert!(last_error().starts_with("witness is 3 bytes"));
+
+        let mut witness = witness();
+        witness.secret_key = [0xff; 32];
+        assert_eq!(
+            create_into(&witness, &mut output).0,
+            ErrorCode::Serialization as i32
+        );
+
+        let code = unsafe { zk_proof_verify(context(), output.as_ptr(), 10) };
+        assert_eq!(code, ErrorCode::Serialization as i32);
+        let code = unsafe { zk_proof_verify(std::ptr::null(), output.as_ptr(), 64) };
+        assert_eq!(code, ErrorCode::InvalidParameter as i32);
+        let code = unsafe { zk_proof_size(context(), std::ptr::null_mut()) };
+        assert_eq!(code, ErrorCode::InvalidParameter as i32);
+    }
+
+    fn last_error() -> String {
+        let len = unsafe { zk_last_error_message(std::ptr::null_mut(), 0) };
+        let mut buf = vec![0u8; len + 1];
+        let written = unsafe { zk_last_error_message(buf.as_mut_ptr().cast(), buf.len()) };
+        assert_eq!(written, len);
+        assert_eq!(buf[len], 0);
+        buf.truncate(len);
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_last_error_message() {
+        let code = unsafe { zk_proof_verify(std::ptr::null(), std::ptr::null(), 0) };
+        assert_eq!(code, ErrorCode::InvalidParameter as i32);
+        let message = last_error();
+        assert_eq!(message, "null argument to zk_proof_verify");
+
+        // Truncated copies stay NUL-terminated and report the full length
+        let mut buf = [0x7f_u8; 5];
+        let len = unsafe { zk_last_error_message(buf.as_mut_ptr().cast(), buf.len()) };
+        assert_eq!(len, message.len());
+        assert_eq!(&buf, b"null\0");
+
+        // Success leaves the last failure in place
+        assert_eq!(zk_selftest(), ErrorCode::Success as i32);
+        assert_eq!(last_error(), message);
+    }
+
+    #[test]
+    fn test_context_lifecycle() {
+        assert!(zk_context_new(DCI_K, 7).is_null());
+        assert!(zk_context_new(4, ZK_CIRCUIT_DCI).is_null());
+
+        // Proofs from one context verify under another with the same k
+        let own = zk_context_new(DCI_K, ZK_CIRCUIT_DCI);
+        assert!(!own.is_null());
+        let mut len = 0;
+        let code = unsafe { zk_proof_size(own, &raw mut len) };
+        assert_eq!(code, ErrorCode::Success as i32);
+        let mut proof = vec![0; len];
+        let witness = witness();
+        let code = unsafe {
+            zk_proof_create(
+                own,
+                std::ptr::from_ref(&witness).cast(),
+                std::mem::size_of::<ZkDciWitness>(),
+                proof.as_mut_ptr(),
+                &raw mut len,
+            )
+        };
+        assert_eq!(code, ErrorCode::Success as i32);
+        let code = unsafe { zk_proof_verify(context(), proof.as_ptr(), len) };
+        assert_eq!(code, ErrorCode::Success as i32);
+
+        unsafe { zk_context_free(own) };
+        unsafe { zk_context_free(std::ptr::null_mut()) };
+    }
 }
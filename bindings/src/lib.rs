@@ -6,12 +6,22 @@
 #![warn(clippy::all)]
 #![warn(clippy::pedantic)]
 
+#[cfg(feature = "jni")]
+pub mod android;
+pub mod cgo;
 pub mod ffi;
 pub mod wasm;
 
+use std::{any::Any, cell::RefCell, fmt::Display};
+
+use zk_proof_core::{prelude::Error as PlonkError, Error};
+
 /// C-compatible error codes
+///
+/// The numeric values are part of the C ABI and must never change; new codes
+/// are only ever appended.
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ErrorCode {
     /// Success
     Success = 0,
@@ -19,6 +29,244 @@ pub enum ErrorCode {
     InvalidParameter = 1,
     /// Verification failed
     VerificationFailed = 2,
+    /// Circuit synthesis failed
+    SynthesisFailed = 3,
+    /// Key generation failed
+    KeygenFailed = 4,
+    /// Params do not match the circuit size
+    ParamsMismatch = 5,
+    /// Public instances do not match the circuit
+    InstanceShape = 6,
+    /// IO error
+    Io = 7,
+    /// Allocation failed
+    OutOfMemory = 8,
+    /// Circuit id not known to this build
+    CircuitUnknown = 9,
+    /// Operation was cancelled
+    Cancelled = 10,
+    /// Proof was created for a different verifying key
+    VkMismatch = 11,
+    /// Proving or verification exceeded its time limit
+    Timeout = 12,
+    /// Rejected by the verifier policy before verification
+    PolicyRejected = 13,
+    /// Too many requests, retry later
+    RateLimited = 14,
+    /// Output buffer cannot hold the result; the needed size is reported
+    BufferTooSmall = 15,
+    /// Input or proof bytes could not be decoded
+    Serialization = 16,
+    /// The library panicked; the panic message is the last error message
+    Panic = 17,
     /// Unknown error
     Unknown = 99,
 }
+
+impl From<&Error> for ErrorCode {
+    fn from(err: &Error) -> Self {
+        match err {
+            Error::Synthesis(_) => Self::SynthesisFailed,
+            Error::Verification(_) => Self::VerificationFailed,
+            Error::VkMismatch { .. } => Self::VkMismatch,
+            Error::Keygen(_) => Self::KeygenFailed,
+            Error::ParamsMismatch { .. } | Error::RowBudgetExceeded { .. } => Self::ParamsMismatch,
+            Error::InstanceShape { .. } => Self::InstanceShape,
+            Error::Encoding(_) => Self::Serialization,
+            Error::Plonk(err) => Self::from(err),
+            Error::Io(_) => Self::Io,
+            Error::Timeout { .. } => Self::Timeout,
+            Error::PolicyRejected(_) => Self::PolicyRejected,
+            Error::RateLimited { .. } => Self::RateLimited,
+            Error::Other(_) => Self::Unknown,
+            Error::Context { source, .. } => Self::from(source.as_ref()),
+        }
+    }
+}
+
+impl From<&PlonkError> for ErrorCode {
+    fn from(err: &PlonkError) -> Self {
+        match err {
+            PlonkError::Synthesis
+            | PlonkError::NotEnoughColumnsForConstants
+            | PlonkError::ColumnNotInPermutation(_)
+            | PlonkError::TableError(_) => Self::SynthesisFailed,
+            PlonkError::ConstraintSystemFailure | PlonkError::Opening => Self::VerificationFailed,
+            PlonkError::InvalidInstances | PlonkError::InstanceTooLarge => Self::InstanceShape,
+            PlonkError::NotEnoughRowsAvailable { .. } => Self::ParamsMismatch,
+            PlonkError::Transcript(_) => Self::Io,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl From<Error> for ErrorCode {
+    fn from(err: Error) -> Self {
+        Self::from(&err)
+    }
+}
+
+thread_local! {
+    /// Message of the last failure on this thread, read by
+    /// [`ffi::zk_last_error_message`]
+    static LAST_ERROR: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Record `message` as this thread's last error and return `code`
+pub(crate) fn fail(code: ErrorCode, message: impl Display) -> i32 {
+    LAST_ERROR.with(|last| *last.borrow_mut() = message.to_string());
+    code as i32
+}
+
+/// Record `err` as this thread's last error and return its code
+pub(crate) fn fail_with(err: &Error) -> i32 {
+    fail(ErrorCode::from(err), err)
+}
+
+/// Record a caught panic as this thread's last error
+pub(crate) fn fail_panic(payload: &(dyn Any + Send)) -> i32 {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic");
+    fail(ErrorCode::Panic, format_args!("panic: {message}"))
+}
+
+/// Run `f` on this thread's last error message
+pub(crate) fn with_last_error<T>(f: impl FnOnce(&str) -> T) -> T {
+    LAST_ERROR.with(|last| f(&last.borrow()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_code_values_are_stable() {
+        assert_eq!(ErrorCode::Success as i32, 0);
+        assert_eq!(ErrorCode::InvalidParameter as i32, 1);
+        assert_eq!(ErrorCode::VerificationFailed as i32, 2);
+        assert_eq!(ErrorCode::SynthesisFailed as i32, 3);
+        assert_eq!(ErrorCode::KeygenFailed as i32, 4);
+        assert_eq!(ErrorCode::ParamsMismatch as i32, 5);
+        assert_eq!(ErrorCode::InstanceShape as i32, 6);
+        assert_eq!(ErrorCode::Io as i32, 7);
+        assert_eq!(ErrorCode::OutOfMemory as i32, 8);
+        assert_eq!(ErrorCode::CircuitUnknown as i32, 9);
+        assert_eq!(ErrorCode::Cancelled as i32, 10);
+        assert_eq!(ErrorCode::VkMismatch as i32, 11);
+        assert_eq!(ErrorCode::Timeout as i32, 12);
+        assert_eq!(ErrorCode::PolicyRejected as i32, 13);
+        assert_eq!(ErrorCode::RateLimited as i32, 14);
+        assert_eq!(ErrorCode::BufferTooSmall as i32, 15);
+        assert_eq!(ErrorCode::Serialization as i32, 16);
+        assert_eq!(ErrorCode::Panic as i32, 17);
+        assert_eq!(ErrorCode::Unknown as i32, 99);
+    }
+
+    #[test]
+    fn test_core_error_mapping() {
+        let cases = [
+            (
+                Error::Synthesis("region".into()),
+                ErrorCode::SynthesisFailed,
+            ),
+            (
+                Error::Verification("bad".into()),
+                ErrorCode::VerificationFailed,
+            ),
+            (Error::Keygen("vk".into()), ErrorCode::KeygenFailed),
+            (
+                Error::VkMismatch {
+                    expected: "aa".into(),
+                    found: "bb".into(),
+                },
+                ErrorCode::VkMismatch,
+            ),
+            (
+                Error::ParamsMismatch {
+                    expected: 14,
+                    found: 12,
+                },
+                ErrorCode::ParamsMismatch,
+            ),
+            (
+                Error::InstanceShape {
+                    expected: vec![1],
+                    found: vec![],
+                },
+                ErrorCode::InstanceShape,
+            ),
+            (Error::Io(std::io::Error::other("disk")), ErrorCode::Io),
+            (
+                Error::RowBudgetExceeded {
+                    rows: 1 << 20,
+                    max_k: 18,
+                },
+                ErrorCode::ParamsMismatch,
+            ),
+            (
+                Error::Timeout {
+                    limit: std::time::Duration::from_secs(1),
+                },
+                ErrorCode::Timeout,
+            ),
+            (
+                Error::PolicyRejected("size".into()),
+                ErrorCode::PolicyRejected,
+            ),
+            (
+                Error::RateLimited {
+                    retry_after: std::time::Duration::ZERO,
+                },
+                ErrorCode::RateLimited,
+            ),
+            (Error::Encoding("hex".into()), ErrorCode::Serialization),
+            (Error::Other("?".into()), ErrorCode::Unknown),
+        ];
+
+        for (err, code) in cases {
+            assert_eq!(ErrorCode::from(&err), code, "{err}");
+            assert_eq!(ErrorCode::from(err.context("ctx")), code);
+        }
+    }
+
+    #[test]
+    fn test_plonk_error_mapping() {
+        assert_eq!(
+            ErrorCode::from(Error::Plonk(PlonkError::Synthesis)),
+            ErrorCode::SynthesisFailed
+        );
+        assert_eq!(
+            ErrorCode::from(Error::Plonk(PlonkError::ConstraintSystemFailure)),
+            ErrorCode::VerificationFailed
+        );
+        assert_eq!(
+            ErrorCode::from(Error::Plonk(PlonkError::InvalidInstances)),
+            ErrorCode::InstanceShape
+        );
+        assert_eq!(
+            ErrorCode::from(Error::Plonk(PlonkError::NotEnoughRowsAvailable {
+                current_k: 4
+            })),
+            ErrorCode::ParamsMismatch
+        );
+    }
+
+    #[test]
+    fn test_fail_records_message() {
+        let err = Error::Encoding("bad hex".into());
+        assert_eq!(fail_with(&err), ErrorCode::Serialization as i32);
+        assert_eq!(with_last_error(str::to_owned), err.to_string());
+
+        let payload = std::panic::catch_unwind(|| panic!("boom {}", 1)).unwrap_err();
+        assert_eq!(fail_panic(payload.as_ref()), ErrorCode::Panic as i32);
+        assert_eq!(with_last_error(str::to_owned), "panic: boom 1");
+
+        // Messages are per thread
+        std::thread::spawn(|| assert_eq!(with_last_error(str::len), 0))
+            .join()
+            .unwrap();
+    }
+}
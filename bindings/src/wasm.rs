@@ -1,20 +1,168 @@
 //! WebAssembly bindings
+//!
+//! A [`WasmProver`] holds the params and keys of a DCI context, generated
+//! once in its constructor. Witnesses arrive as plain JS objects:
+//!
+//! ```js
+//! const prover = new WasmProver(11);
+//! prover.buildDciWitness({
+//!     secretKey,   // Uint8Array(32), little-endian field element
+//!     balance,     // number or bigint
+//!     leafIndex,   // number or bigint
+//!     siblings,    // array of 20 Uint8Array(32), starting next to the leaf
+//! });
+//! const proof = await prover.prove();
+//! const ok = prover.verify(proof, proof.slice(0, 64));
+//! ```
+//!
+//! Proofs have the layout of [`crate::ffi::zk_proof_create`]: the root and
+//! nullifier, 32 bytes each, followed by the halo2 proof. Proving does not
+//! yield while it runs, so browsers should drive the prover from a web
+//! worker; [`WasmProver::prove`] returns a promise so callers can await it
+//! there without blocking on the result.
 
+#[cfg(target_arch = "wasm32")]
+use std::rc::Rc;
+
+#[cfg(target_arch = "wasm32")]
+use js_sys::{Array, BigInt, Promise, Reflect, Uint8Array};
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
+#[cfg(target_arch = "wasm32")]
+use zk_proof_core::circuits::dci::DCI_TREE_DEPTH;
+
+#[cfg(target_arch = "wasm32")]
+use crate::ffi::{ZkContext, ZkDciWitness, ZK_CIRCUIT_DCI};
 
-/// WASM wrapper for proof creation
+/// DCI prover holding params and keys across calls
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
-pub fn create_proof(input: &[u8]) -> Vec<u8> {
-    // Implementation would go here
-    vec![]
+pub struct WasmProver {
+    context: Rc<ZkContext>,
+    witness: Option<ZkDciWitness>,
 }
 
-/// WASM wrapper for proof verification
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
-pub fn verify_proof(proof: &[u8]) -> bool {
-    // Implementation would go here
-    true
+impl WasmProver {
+    /// Generate params for `2^k` rows and the DCI keys
+    ///
+    /// # Errors
+    ///
+    /// Throws if `k` is too small for the DCI circuit.
+    #[wasm_bindgen(constructor)]
+    pub fn new(k: u32) -> Result<WasmProver, JsError> {
+        let context = ZkContext::new(k, ZK_CIRCUIT_DCI).map_err(js_error)?;
+        Ok(Self {
+            context: Rc::new(context),
+            witness: None,
+        })
+    }
+
+    /// Set the witness for the next [`WasmProver::prove`] from a JS object
+    ///
+    /// # Errors
+    ///
+    /// Throws if a field is missing or has the wrong type or length.
+    #[wasm_bindgen(js_name = buildDciWitness)]
+    pub fn build_dci_witness(&mut self, witness: &JsValue) -> Result<(), JsError> {
+        let siblings = Array::from(&field(witness, "siblings")?);
+        if siblings.length() as usize != DCI_TREE_DEPTH {
+            return Err(JsError::new(&format!(
+                "siblings has {} entries, expected {DCI_TREE_DEPTH}",
+                siblings.length()
+            )));
+        }
+        let mut path = [[0; 32]; DCI_TREE_DEPTH];
+        for (out, sibling) in path.iter_mut().zip(siblings.iter()) {
+            *out = bytes32(&sibling, "sibling")?;
+        }
+        self.witness = Some(ZkDciWitness {
+            secret_key: bytes32(&field(witness, "secretKey")?, "secretKey")?,
+            balance: integer(&field(witness, "balance")?, "balance")?,
+            leaf_index: integer(&field(witness, "leafIndex")?, "leafIndex")?,
+            siblings: path,
+        });
+        Ok(())
+    }
+
+    /// Prove the current witness, resolving to the proof bytes
+    ///
+    /// The promise rejects if no witness was built or proving fails.
+    pub fn prove(&self) -> Promise {
+        let context = Rc::clone(&self.context);
+        let witness = self.witness;
+        wasm_bindgen_futures::future_to_promise(async move {
+            let witness = witness.ok_or_else(|| JsValue::from(JsError::new("no witness built")))?;
+            let proof = context
+                .create(&witness)
+                .map_err(|err| JsValue::from(js_error(err)))?;
+            Ok(Uint8Array::from(proof.as_slice()).into())
+        })
+    }
+
+    /// Whether `proof` verifies for `public_inputs`, the 64-byte root and
+    /// nullifier
+    ///
+    /// # Errors
+    ///
+    /// Throws if `public_inputs` is not 64 bytes or the proof does not hold
+    /// field elements where its public inputs belong.
+    pub fn verify(&self, proof: &[u8], public_inputs: &[u8]) -> Result<bool, JsError> {
+        if public_inputs.len() != 64 {
+            return Err(JsError::new("public inputs must be 64 bytes"));
+        }
+        if !proof.starts_with(public_inputs) {
+            return Ok(false);
+        }
+        match self.context.verify(proof) {
+            Ok(()) => Ok(true),
+            Err(zk_proof_core::Error::Verification(_)) => Ok(false),
+            Err(err) => Err(js_error(err)),
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn js_error(err: zk_proof_core::Error) -> JsError {
+    JsError::new(&err.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn field(object: &JsValue, name: &str) -> Result<JsValue, JsError> {
+    let value = Reflect::get(object, &JsValue::from_str(name))
+        .map_err(|_| JsError::new("witness is not an object"))?;
+    if value.is_undefined() {
+        return Err(JsError::new(&format!("witness has no {name}")));
+    }
+    Ok(value)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn bytes32(value: &JsValue, name: &str) -> Result<[u8; 32], JsError> {
+    if !value.is_instance_of::<Uint8Array>() {
+        return Err(JsError::new(&format!("{name} must be a Uint8Array")));
+    }
+    Uint8Array::from(value.clone())
+        .to_vec()
+        .try_into()
+        .map_err(|_| JsError::new(&format!("{name} must be 32 bytes")))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn integer(value: &JsValue, name: &str) -> Result<u64, JsError> {
+    let invalid = || JsError::new(&format!("{name} must be a non-negative integer"));
+    if value.is_bigint() {
+        let digits = BigInt::unchecked_from_js_ref(value)
+            .to_string(10)
+            .map_err(|_| invalid())?;
+        return String::from(digits).parse().map_err(|_| invalid());
+    }
+    match value.as_f64() {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Some(number) if number >= 0.0 && number.fract() == 0.0 && number <= 2f64.powi(53) => {
+            Ok(number as u64)
+        }
+        _ => Err(invalid()),
+    }
 }
@@ -1,35 +1,59 @@
-﻿#include "zk_proof.h"
+#include "zk_proof.h"
 #include <stdio.h>
 #include <stdlib.h>
+#include <string.h>
 
 int main() {
     printf("ZK Proof System C Interface Example\n");
     
-    // Example input data
-    uint8_t input[] = {1, 2, 3, 4, 5};
-    size_t input_len = sizeof(input);
+    // Params and keys are generated once here and reused by every call
+    ZK_ZkContext *context = zk_context_new(11, ZK_ZK_CIRCUIT_DCI);
+    char message[256];
+    if (context == NULL) {
+        zk_last_error_message(message, sizeof(message));
+        printf("Failed to create context: %s\n", message);
+        return 1;
+    }
+    
+    // Witness of a note; fill in the key, balance and tree path
+    ZK_ZkDciWitness witness;
+    memset(&witness, 0, sizeof(witness));
+    witness.secret_key[0] = 5;
+    witness.balance = 100;
     
-    // Allocate output buffer
-    uint8_t output[1024];
-    size_t output_len = sizeof(output);
+    // Ask for the proof size first, then allocate it
+    uint8_t probe[1];
+    size_t output_len = sizeof(probe);
+    int32_t result = zk_proof_create(context, (const uint8_t *)&witness, sizeof(witness), probe, &output_len);
     
-    // Create proof
-    int32_t result = zk_proof_create(input, input_len, output, &output_len);
+    uint8_t *output = NULL;
+    if (result == ZK_ZK_ERROR_CODE_BUFFER_TOO_SMALL) {
+        output = malloc(output_len);
+        result = zk_proof_create(context, (const uint8_t *)&witness, sizeof(witness), output, &output_len);
+    }
     
-    if (result == 0) {
+    if (result == ZK_ZK_ERROR_CODE_SUCCESS) {
         printf("Proof created successfully, size: %zu bytes\n", output_len);
         
+        // The proof starts with the public inputs it proves
+        const ZK_ZkDciPublicInputs *inputs = (const ZK_ZkDciPublicInputs *)output;
+        printf("Nullifier starts with %02x\n", inputs->nullifier[0]);
+        
         // Verify proof
-        result = zk_proof_verify(output, output_len);
+        result = zk_proof_verify(context, output, output_len);
         
-        if (result == 0) {
+        if (result == ZK_ZK_ERROR_CODE_SUCCESS) {
             printf("Proof verified successfully!\n");
         } else {
-            printf("Proof verification failed\n");
+            zk_last_error_message(message, sizeof(message));
+            printf("Proof verification failed (%d): %s\n", result, message);
         }
     } else {
-        printf("Failed to create proof\n");
+        zk_last_error_message(message, sizeof(message));
+        printf("Failed to create proof (%d): %s\n", result, message);
     }
     
+    free(output);
+    zk_context_free(context);
     return 0;
 }
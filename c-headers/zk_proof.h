@@ -12,8 +12,16 @@
 #include <stdbool.h>
 #include <stddef.h>
 
+/**
+ * Circuit type of a [`ZkContext`] proving DCI spends
+ */
+#define ZK_ZK_CIRCUIT_DCI 0
+
 /**
  * C-compatible error codes
+ *
+ * The numeric values are part of the C ABI and must never change; new codes
+ * are only ever appended.
  */
 typedef enum ZK_ZK_ErrorCode {
   /**
@@ -28,6 +36,66 @@ typedef enum ZK_ZK_ErrorCode {
    * Verification failed
    */
   ZK_ZK_ERROR_CODE_VERIFICATION_FAILED = 2,
+  /**
+   * Circuit synthesis failed
+   */
+  ZK_ZK_ERROR_CODE_SYNTHESIS_FAILED = 3,
+  /**
+   * Key generation failed
+   */
+  ZK_ZK_ERROR_CODE_KEYGEN_FAILED = 4,
+  /**
+   * Params do not match the circuit size
+   */
+  ZK_ZK_ERROR_CODE_PARAMS_MISMATCH = 5,
+  /**
+   * Public instances do not match the circuit
+   */
+  ZK_ZK_ERROR_CODE_INSTANCE_SHAPE = 6,
+  /**
+   * IO error
+   */
+  ZK_ZK_ERROR_CODE_IO = 7,
+  /**
+   * Allocation failed
+   */
+  ZK_ZK_ERROR_CODE_OUT_OF_MEMORY = 8,
+  /**
+   * Circuit id not known to this build
+   */
+  ZK_ZK_ERROR_CODE_CIRCUIT_UNKNOWN = 9,
+  /**
+   * Operation was cancelled
+   */
+  ZK_ZK_ERROR_CODE_CANCELLED = 10,
+  /**
+   * Proof was created for a different verifying key
+   */
+  ZK_ZK_ERROR_CODE_VK_MISMATCH = 11,
+  /**
+   * Proving or verification exceeded its time limit
+   */
+  ZK_ZK_ERROR_CODE_TIMEOUT = 12,
+  /**
+   * Rejected by the verifier policy before verification
+   */
+  ZK_ZK_ERROR_CODE_POLICY_REJECTED = 13,
+  /**
+   * Too many requests, retry later
+   */
+  ZK_ZK_ERROR_CODE_RATE_LIMITED = 14,
+  /**
+   * Output buffer cannot hold the result; the needed size is reported
+   */
+  ZK_ZK_ERROR_CODE_BUFFER_TOO_SMALL = 15,
+  /**
+   * Input or proof bytes could not be decoded
+   */
+  ZK_ZK_ERROR_CODE_SERIALIZATION = 16,
+  /**
+   * The library panicked; the panic message is the last error message
+   */
+  ZK_ZK_ERROR_CODE_PANIC = 17,
   /**
    * Unknown error
    */
@@ -35,24 +103,127 @@ typedef enum ZK_ZK_ErrorCode {
 } ZK_ZK_ErrorCode;
 
 /**
- * Create a new proof
+ * Params and keys of one circuit, shared across proof calls
+ */
+typedef struct ZK_ZkContext ZK_ZkContext;
+
+/**
+ * Witness of a DCI spend, laid out for C callers
+ */
+typedef struct ZK_ZkDciWitness {
+  /**
+   * Secret key owning the note
+   */
+  uint8_t secret_key[32];
+  /**
+   * Balance of the note
+   */
+  uint64_t balance;
+  /**
+   * Position of the note in the tree
+   */
+  uint64_t leaf_index;
+  /**
+   * Sibling at each tree level, starting next to the leaf
+   */
+  uint8_t siblings[20][32];
+} ZK_ZkDciWitness;
+
+/**
+ * Public inputs of a DCI proof, laid out for C callers
+ */
+typedef struct ZK_ZkDciPublicInputs {
+  /**
+   * Root of the note tree
+   */
+  uint8_t root[32];
+  /**
+   * Nullifier of the spent note
+   */
+  uint8_t nullifier[32];
+} ZK_ZkDciPublicInputs;
+
+/**
+ * Create a context for `circuit_type` with params for `2^k` rows
+ *
+ * Generates the params and keys, which can take seconds. Returns null for
+ * an unknown circuit type or a `k` too small for the circuit, with the
+ * reason in [`zk_last_error_message`]. The context must be released with
+ * [`zk_context_free`].
+ */
+zk_proof_ ZK_ZkContext *zk_context_new(uint32_t k, uint32_t circuit_type) ;
+
+/**
+ * Release a context
+ *
+ * # Safety
+ *
+ * `context` must be null or a pointer returned by [`zk_context_new`] that
+ * has not been freed yet, and no other call may be using it.
+ */
+zk_proof_ void zk_context_free(ZK_ZkContext *context) ;
+
+/**
+ * Create a DCI proof
+ *
+ * `input` holds one [`ZkDciWitness`]. On entry `*output_len` is the size of
+ * `output`; on success it is set to the number of bytes written. If the
+ * proof does not fit, nothing is written, `*output_len` is set to the size
+ * needed and [`ErrorCode::BufferTooSmall`] is returned.
  *
  * # Safety
  *
- * This function is unsafe because it dereferences raw pointers.
+ * `context` must be a live context from [`zk_context_new`], `input` must
+ * point to `input_len` readable bytes, `output` to `*output_len` writable
+ * bytes, and `output_len` must be valid for reads and writes.
  */
 zk_proof_
-int32_t zk_proof_create(const uint8_t *input,
+int32_t zk_proof_create(const ZK_ZkContext *context,
+                        const uint8_t *input,
                         uintptr_t input_len,
                         uint8_t *output,
                         uintptr_t *output_len)
 ;
 
 /**
- * Verify a proof
+ * Verify a DCI proof from [`zk_proof_create`]
+ *
+ * Returns [`ErrorCode::VerificationFailed`] for a proof that does not
+ * verify and [`ErrorCode::Serialization`] for one too short to hold its
+ * public inputs or whose public inputs are not field elements.
+ *
+ * # Safety
+ *
+ * `context` must be a live context from [`zk_context_new`] and `proof`
+ * must point to `proof_len` readable bytes.
+ */
+zk_proof_
+int32_t zk_proof_verify(const ZK_ZkContext *context,
+                        const uint8_t *proof,
+                        uintptr_t proof_len)
+;
+
+/**
+ * Run the startup self-test
+ *
+ * Proves and verifies a tiny built-in circuit against embedded test
+ * vectors. Call once at startup and refuse to serve traffic unless it
+ * returns `ZK_ERROR_CODE_SUCCESS`.
+ */
+zk_proof_ int32_t zk_selftest(void) ;
+
+/**
+ * Copy the calling thread's last error message into `buf`
+ *
+ * Every function in this module that fails records why, per thread, until
+ * the next failure on that thread. The message is written like `snprintf`:
+ * truncated to fit and NUL-terminated whenever `len` is non-zero. Returns
+ * the full length of the message in bytes without the NUL, zero if no
+ * call has failed yet, so a return value of `len` or more means the
+ * message was truncated.
  *
  * # Safety
  *
- * This function is unsafe because it dereferences raw pointers.
+ * `buf` must be null or point to `len` writable bytes.
  */
-zk_proof_ int32_t zk_proof_verify(const uint8_t *proof, uintptr_t proof_len) ;
+zk_proof_ uintptr_t zk_last_error_message(char *buf, uintptr_t len) ;
@@ -0,0 +1,213 @@
+//! Command-line parsing
+//!
+//! Every option takes a value, written as `--name value` or `-k value`;
+//! everything else is a positional word. Options may appear anywhere and
+//! unknown or repeated ones are rejected.
+
+use std::{collections::HashMap, path::PathBuf, str::FromStr};
+
+use zk_proof_core::circuits::dci::DCI_K;
+
+/// Printed after a malformed command line and for `--help`
+pub(crate) const USAGE: &str = "\
+usage: zkps [--store <dir>] <command>
+
+commands:
+  params generate -k <k>                    generate and cache params
+  params download --url <url> -k <k> [--digest <sha256>]
+                                            fetch params with curl and cache them
+  keygen --circuit dci [-k <k>]             generate and store the verifying key
+  prove --witness <json> --out <file> [--instances-out <json>] [-k <k>]
+                                            prove a DCI spend
+  verify <proof> --instances <json>         verify a proof";
+
+/// Circuits the CLI can key and prove
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CircuitKind {
+    /// [`zk_proof_core::circuits::DCICircuit`]
+    Dci,
+}
+
+impl FromStr for CircuitKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, String> {
+        match name {
+            "dci" => Ok(Self::Dci),
+            _ => Err(format!("unknown circuit {name:?}, expected \"dci\"")),
+        }
+    }
+}
+
+/// What to run
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Command {
+    /// Print [`USAGE`]
+    Help,
+    /// Generate params for `2^k` rows into the store
+    ParamsGenerate { k: u32 },
+    /// Download params for `2^k` rows into the store
+    ParamsDownload {
+        url: String,
+        k: u32,
+        digest: Option<String>,
+    },
+    /// Generate the keys of a circuit and store its verifying key
+    Keygen { circuit: CircuitKind, k: u32 },
+    /// Prove a DCI witness
+    Prove {
+        witness: PathBuf,
+        out: PathBuf,
+        instances_out: Option<PathBuf>,
+        k: u32,
+    },
+    /// Verify a proof against its public inputs
+    Verify { proof: PathBuf, instances: PathBuf },
+}
+
+/// Parsed command line
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Cli {
+    /// Artifact store root, if given
+    pub store: Option<PathBuf>,
+    /// Command to run
+    pub command: Command,
+}
+
+/// Parse the arguments after the program name
+pub(crate) fn parse(args: &[String]) -> Result<Cli, String> {
+    let mut words = Vec::new();
+    let mut options = HashMap::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            return Ok(Cli {
+                store: None,
+                command: Command::Help,
+            });
+        }
+        let Some(name) = arg.strip_prefix("--").or_else(|| arg.strip_prefix('-')) else {
+            words.push(arg.as_str());
+            continue;
+        };
+        let value = args.next().ok_or_else(|| format!("{arg} needs a value"))?;
+        if options.insert(name, value.clone()).is_some() {
+            return Err(format!("{arg} given twice"));
+        }
+    }
+
+    let mut option = |name: &str| options.remove(name);
+    let k = |value: Option<String>| match value {
+        Some(value) => value
+            .parse::<u32>()
+            .map_err(|_| format!("-k must be a number, found {value:?}")),
+        None => Err("missing -k".to_string()),
+    };
+    let required =
+        |name: &str, value: Option<String>| value.ok_or_else(|| format!("missing --{name}"));
+
+    let store = option("store").map(PathBuf::from);
+    let command = match words.as_slice() {
+        ["params", "generate"] => Command::ParamsGenerate { k: k(option("k"))? },
+        ["params", "download"] => Command::ParamsDownload {
+            url: required("url", option("url"))?,
+            k: k(option("k"))?,
+            digest: option("digest"),
+        },
+        ["keygen"] => Command::Keygen {
+            circuit: required("circuit", option("circuit"))?.parse()?,
+            k: k(option("k").or_else(|| Some(DCI_K.to_string())))?,
+        },
+        ["prove"] => Command::Prove {
+            witness: required("witness", option("witness"))?.into(),
+            out: required("out", option("out"))?.into(),
+            instances_out: option("instances-out").map(PathBuf::from),
+            k: k(option("k").or_else(|| Some(DCI_K.to_string())))?,
+        },
+        ["verify", proof] => Command::Verify {
+            proof: proof.into(),
+            instances: required("instances", option("instances"))?.into(),
+        },
+        [] => return Err("missing command".into()),
+        _ => return Err(format!("unknown command {:?}", words.join(" "))),
+    };
+
+    if let Some(name) = options.keys().next() {
+        return Err(format!("unexpected option {name:?}"));
+    }
+    Ok(Cli { store, command })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_line(line: &str) -> Result<Cli, String> {
+        let args: Vec<String> = line.split_whitespace().map(String::from).collect();
+        parse(&args)
+    }
+
+    #[test]
+    fn test_commands() {
+        assert_eq!(
+            parse_line("keygen --circuit dci -k 12").unwrap().command,
+            Command::Keygen {
+                circuit: CircuitKind::Dci,
+                k: 12
+            }
+        );
+        assert_eq!(
+            parse_line("--store /tmp/zk prove --out p.bin --witness w.json").unwrap(),
+            Cli {
+                store: Some("/tmp/zk".into()),
+                command: Command::Prove {
+                    witness: "w.json".into(),
+                    out: "p.bin".into(),
+                    instances_out: None,
+                    k: DCI_K,
+                },
+            }
+        );
+        assert_eq!(
+            parse_line("verify proof.bin --instances pub.json")
+                .unwrap()
+                .command,
+            Command::Verify {
+                proof: "proof.bin".into(),
+                instances: "pub.json".into(),
+            }
+        );
+        assert_eq!(
+            parse_line("params download -k 12 --url https://example.org/k12")
+                .unwrap()
+                .command,
+            Command::ParamsDownload {
+                url: "https://example.org/k12".into(),
+                k: 12,
+                digest: None,
+            }
+        );
+        assert_eq!(
+            parse_line("params generate --help").unwrap().command,
+            Command::Help
+        );
+    }
+
+    #[test]
+    fn test_malformed() {
+        for line in [
+            "",
+            "params",
+            "params generate",
+            "params generate -k twelve",
+            "keygen --circuit pore",
+            "prove --witness w.json",
+            "verify --instances pub.json",
+            "verify proof.bin --instances pub.json --out x",
+            "keygen --circuit dci --circuit dci",
+            "keygen --circuit",
+        ] {
+            assert!(parse_line(line).is_err(), "{line:?}");
+        }
+    }
+}
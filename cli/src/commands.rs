@@ -0,0 +1,341 @@
+//! Command implementations
+//!
+//! Witnesses and public inputs are JSON with field elements as `0x`-prefixed
+//! big-endian hex, as written by `zk_proof_core::utils::encoding::to_hex`:
+//!
+//! ```text
+//! witness.json  {"secret_key": "0x..", "balance": 100, "leaf_index": 3,
+//!                "siblings": ["0x..", ...]}
+//! pub.json      [["0x<root>", "0x<nullifier>"]]
+//! ```
+//!
+//! Public inputs are listed per instance column in the layout of
+//! `DCIPublicInputs`. Proofs are written in the
+//! self-describing `zk_proof_core::Proof` encoding, tagged with the circuit
+//! id `dci`.
+
+use std::{fs, io::Write, path::Path, process};
+
+use halo2_proofs::plonk::keygen_vk;
+use pasta_curves::vesta;
+use serde::{Deserialize, Serialize};
+use zk_proof_core::{
+    circuit_hash,
+    circuits::{DCICircuit, DCIPublicInputs, DCIWitnessBuilder, MerkleProof},
+    keys::check_verifying_key,
+    params_digest,
+    proof::write_proof,
+    utils::encoding::{decode_hex, encode_hex, from_hex, to_hex},
+    ArtifactStore, Error, ErrorCategory, KeyStore, OsRandomness, ParamsCache, Proof, PublicInputs,
+    Result, VkHash,
+};
+use zk_proof_verifier::prelude::SingleVerifier;
+
+use crate::args::{CircuitKind, Cli, Command, USAGE};
+
+/// Curve the DCI circuit is proven over
+type Curve = vesta::Affine;
+/// Field the DCI circuit is defined over
+type Fp = vesta::Scalar;
+
+/// Circuit id stamped on DCI proofs
+const DCI_CIRCUIT_ID: &str = "dci";
+
+/// Witness file read by `prove`
+#[derive(Debug, Serialize, Deserialize)]
+struct WitnessFile {
+    secret_key: String,
+    balance: u64,
+    leaf_index: u64,
+    siblings: Vec<String>,
+}
+
+/// Run `cli`, writing results to `out`
+///
+/// Returns `false` if a proof did not verify.
+pub(crate) fn run(cli: Cli, out: &mut impl Write) -> Result<bool> {
+    let store = || match &cli.store {
+        Some(root) => ArtifactStore::open(root),
+        None => {
+            let params = ParamsCache::<Curve>::from_env()?.dir();
+            ArtifactStore::open(params.parent().unwrap_or(&params))
+        }
+    };
+
+    match cli.command {
+        Command::Help => writeln!(out, "{USAGE}")?,
+        Command::ParamsGenerate { k } => {
+            let params = ParamsCache::<Curve>::open(store()?.root())?.get(k)?;
+            report_params(out, k, &params)?;
+        }
+        Command::ParamsDownload { url, k, digest } => {
+            let mut cache = ParamsCache::<Curve>::open(store()?.root())?;
+            if let Some(digest) = digest {
+                let digest = decode_hex(&digest)?
+                    .try_into()
+                    .map_err(|_| Error::Encoding("--digest must be 32 bytes".into()))?;
+                cache = cache.pin(k, digest);
+            }
+            let params = cache.import(k, download(&url)?.as_slice())?;
+            report_params(out, k, &params)?;
+        }
+        Command::Keygen {
+            circuit: CircuitKind::Dci,
+            k,
+        } => {
+            let keys = KeyStore::<Curve>::persistent(store()?);
+            let pk = keys.get_or_generate(&DCICircuit::<Fp>::default(), k)?;
+            writeln!(
+                out,
+                "circuit {DCI_CIRCUIT_ID} k={k} hash={}",
+                circuit_hash(pk.get_vk())
+            )?;
+        }
+        Command::Prove {
+            witness,
+            out: proof_path,
+            instances_out,
+            k,
+        } => {
+            let keys = KeyStore::<Curve>::persistent(store()?);
+            let (proof, instances) = prove(&keys, &read_json(&witness)?, k)?;
+            fs::write(&proof_path, proof.to_bytes()?)?;
+
+            let instances: Vec<Vec<String>> = instances
+                .iter()
+                .map(|column| column.iter().map(to_hex).collect())
+                .collect();
+            let json = serde_json::to_string(&instances).map_err(json_error)?;
+            match instances_out {
+                Some(path) => fs::write(path, json)?,
+                None => writeln!(out, "{json}")?,
+            }
+        }
+        Command::Verify { proof, instances } => {
+            let keys = KeyStore::<Curve>::persistent(store()?);
+            let proof = Proof::from_bytes(&fs::read(proof)?)?;
+            let instances: Vec<Vec<String>> = read_json(&instances)?;
+            let instances = instances
+                .iter()
+                .map(|column| column.iter().map(|value| from_hex(value)).collect())
+                .collect::<Result<Vec<Vec<Fp>>>>()?;
+            // Reject anything but one root and one nullifier up front
+            let instances = DCIPublicInputs::from_instances(&instances)?.to_instances();
+            return verify(&keys, &proof, &instances, out);
+        }
+    }
+    Ok(true)
+}
+
+/// Prove `witness` with the DCI keys for `2^k` rows
+///
+/// Returns the proof and the public inputs it was made against.
+fn prove(keys: &KeyStore<Curve>, witness: &WitnessFile, k: u32) -> Result<(Proof, Vec<Vec<Fp>>)> {
+    let path = MerkleProof {
+        index: witness.leaf_index,
+        siblings: witness
+            .siblings
+            .iter()
+            .map(|sibling| from_hex(sibling))
+            .collect::<Result<_>>()?,
+    };
+    let (circuit, public) = DCIWitnessBuilder::new()
+        .with_merkle_proof(&path)
+        .with_balance(witness.balance)
+        .with_secret_key(from_hex(&witness.secret_key)?)
+        .build()?;
+
+    let instances = public.to_instances();
+    let columns: Vec<&[Fp]> = instances.iter().map(Vec::as_slice).collect();
+    let pk = keys.get_or_generate(&circuit, k)?;
+    let params = keys.params(k)?;
+    let data = write_proof(
+        &params,
+        &pk,
+        &[circuit],
+        &[&columns],
+        OsRandomness,
+        Vec::new(),
+    )?;
+    let proof = Proof::new::<Curve>(data, DCI_CIRCUIT_ID, k, &instances)
+        .with_vk_hash(VkHash::of(pk.get_vk()));
+    Ok((proof, instances))
+}
+
+/// Check `proof` against `instances` and the DCI verifying key
+///
+/// A proof that does not verify is reported to `out` rather than as an
+/// error.
+fn verify(
+    keys: &KeyStore<Curve>,
+    proof: &Proof,
+    instances: &[Vec<Fp>],
+    out: &mut impl Write,
+) -> Result<bool> {
+    if proof.circuit_id != DCI_CIRCUIT_ID {
+        return Err(Error::Other(format!(
+            "proof is for circuit {:?}, expected {DCI_CIRCUIT_ID:?}",
+            proof.circuit_id
+        )));
+    }
+    if !proof.matches_public_inputs(instances) {
+        writeln!(out, "invalid: public inputs differ from the proof's")?;
+        return Ok(false);
+    }
+    let params = keys.params(proof.k)?;
+    let vk = keygen_vk(&params, &DCICircuit::<Fp>::default())?;
+    if let Some(stored) = keys.stored_verifying_key(&circuit_hash(&vk))? {
+        check_verifying_key(&vk, &stored)?;
+    }
+    match SingleVerifier::new((*params).clone(), vk).check(instances, &proof.data) {
+        Ok(()) => {
+            writeln!(out, "valid")?;
+            Ok(true)
+        }
+        // A truncated proof surfaces as a transcript read failure
+        Err(err)
+            if matches!(
+                err.category(),
+                ErrorCategory::Verification | ErrorCategory::Io
+            ) =>
+        {
+            writeln!(out, "invalid: {err}")?;
+            Ok(false)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Fetch `url` with the system `curl`
+fn download(url: &str) -> Result<Vec<u8>> {
+    let output = process::Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location", url])
+        .output()
+        .map_err(|err| Error::Other(format!("running curl: {err}")))?;
+    if !output.status.success() {
+        return Err(Error::Other(format!(
+            "downloading {url}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+fn report_params(
+    out: &mut impl Write,
+    k: u32,
+    params: &halo2_proofs::poly::commitment::Params<Curve>,
+) -> Result<()> {
+    writeln!(
+        out,
+        "params k={k} sha256={}",
+        encode_hex(&params_digest(params)?)
+    )?;
+    Ok(())
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T> {
+    serde_json::from_slice(&fs::read(path)?).map_err(json_error)
+}
+
+fn json_error(err: serde_json::Error) -> Error {
+    Error::Encoding(format!("invalid JSON: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use zk_proof_core::{circuits::dci::DCI_TREE_DEPTH, MerkleTree};
+
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("zkps-{name}-{}", process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn run_in(dir: &TempDir, command: Command) -> (Result<bool>, String) {
+        let cli = Cli {
+            store: Some(dir.0.join("store")),
+            command,
+        };
+        let mut out = Vec::new();
+        let result = run(cli, &mut out);
+        (result, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_prove_and_verify() {
+        let dir = TempDir::new("prove");
+        let secret_key = Fp::from(5);
+        let mut tree = MerkleTree::new(DCI_TREE_DEPTH);
+        tree.insert(Fp::from(1)).unwrap();
+        let index = tree
+            .insert(DCIWitnessBuilder::commitment(secret_key, 100))
+            .unwrap();
+        let witness = WitnessFile {
+            secret_key: to_hex(&secret_key),
+            balance: 100,
+            leaf_index: index,
+            siblings: tree
+                .prove_index(index)
+                .unwrap()
+                .siblings
+                .iter()
+                .map(to_hex)
+                .collect(),
+        };
+        let witness_path = dir.0.join("witness.json");
+        fs::write(&witness_path, serde_json::to_vec(&witness).unwrap()).unwrap();
+
+        let (result, _) = run_in(
+            &dir,
+            Command::Prove {
+                witness: witness_path,
+                out: dir.0.join("proof.bin"),
+                instances_out: Some(dir.0.join("pub.json")),
+                k: zk_proof_core::circuits::dci::DCI_K,
+            },
+        );
+        assert!(result.unwrap());
+        let instances: Vec<Vec<String>> = read_json(&dir.0.join("pub.json")).unwrap();
+        assert_eq!(instances[0][0], to_hex(&tree.root()));
+
+        let verify = |instances: &Path| {
+            run_in(
+                &dir,
+                Command::Verify {
+                    proof: dir.0.join("proof.bin"),
+                    instances: instances.into(),
+                },
+            )
+        };
+        let (result, output) = verify(&dir.0.join("pub.json"));
+        assert!(result.unwrap());
+        assert_eq!(output, "valid\n");
+
+        // Claiming another nullifier fails
+        let mut forged = instances.clone();
+        forged[0][1] = to_hex(&Fp::from(9));
+        fs::write(
+            dir.0.join("forged.json"),
+            serde_json::to_vec(&forged).unwrap(),
+        )
+        .unwrap();
+        let (result, output) = verify(&dir.0.join("forged.json"));
+        assert!(!result.unwrap());
+        assert!(output.starts_with("invalid: "));
+    }
+}
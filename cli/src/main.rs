@@ -0,0 +1,43 @@
+//! `zkps`, the command-line front end of the ZK proof system
+//!
+//! ```text
+//! zkps params generate -k 12
+//! zkps params download --url <url> -k 12 [--digest <sha256>]
+//! zkps keygen --circuit dci [-k 11]
+//! zkps prove --witness witness.json --out proof.bin [--instances-out pub.json] [-k 11]
+//! zkps verify proof.bin --instances pub.json
+//! ```
+//!
+//! Params and verifying keys live in an artifact store, the directory given
+//! by `--store` or else the params cache directory of
+//! `zk_proof_core::ParamsCache::from_env`. `verify` exits with status 1 for
+//! a proof that does not verify and every command exits with status 2 for
+//! a malformed command line.
+
+#![warn(missing_docs)]
+#![warn(clippy::all)]
+#![warn(clippy::pedantic)]
+
+mod args;
+mod commands;
+
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let cli = match args::parse(&args) {
+        Ok(cli) => cli,
+        Err(err) => {
+            eprintln!("zkps: {err}\n\n{}", args::USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match commands::run(cli, &mut std::io::stdout()) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("zkps: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Prover daemon client
+
+use std::time::Duration;
+
+use tonic::{transport::Channel, Streaming};
+
+use crate::{
+    error::{Error, Result},
+    proto::{self, prover_client},
+    types::{JobId, JobStatus, Progress, ProofJob},
+};
+
+/// Client for submitting and tracking proof jobs
+#[derive(Clone, Debug)]
+pub struct ProverClient {
+    inner: prover_client::ProverClient<Channel>,
+}
+
+impl ProverClient {
+    /// Connect to a daemon, e.g. `http://127.0.0.1:50051`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Transport`] if the endpoint is invalid or unreachable.
+    pub async fn connect(endpoint: impl Into<String>) -> Result<Self> {
+        let channel = Channel::from_shared(endpoint.into())
+            .map_err(|err| Error::Protocol(err.to_string()))?
+            .connect()
+            .await?;
+        Ok(Self::from_channel(channel))
+    }
+
+    /// Wrap an existing channel
+    #[must_use]
+    pub fn from_channel(channel: Channel) -> Self {
+        Self {
+            inner: prover_client::ProverClient::new(channel),
+        }
+    }
+
+    /// Submit a proof job
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Status`] if the daemon rejects the job.
+    pub async fn submit(&mut self, job: ProofJob) -> Result<JobId> {
+        let response = self
+            .inner
+            .submit_job(proto::SubmitJobRequest::from(job))
+            .await?;
+        Ok(JobId(response.into_inner().job_id))
+    }
+
+    /// Fetch the current status of a job
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Status`] on RPC failure and [`Error::Protocol`] if the
+    /// daemon reports an unknown state.
+    pub async fn status(&mut self, job: &JobId) -> Result<JobStatus> {
+        let response = self.inner.get_job_status(job_ref(job)).await?;
+        JobStatus::try_from(response.into_inner())
+    }
+
+    /// Open a progress stream for a job
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Status`] if the stream cannot be opened.
+    pub async fn progress(&mut self, job: &JobId) -> Result<ProgressStream> {
+        let response = self.inner.stream_progress(job_ref(job)).await?;
+        Ok(ProgressStream {
+            inner: response.into_inner(),
+        })
+    }
+
+    /// Poll a job until it finishes and return the proof
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::JobFailed`] if the job fails or is cancelled.
+    pub async fn wait(&mut self, job: &JobId, poll_interval: Duration) -> Result<Vec<u8>> {
+        loop {
+            match self.status(job).await? {
+                JobStatus::Completed { proof } => return Ok(proof),
+                JobStatus::Failed { reason } => return Err(Error::JobFailed(reason)),
+                JobStatus::Cancelled => return Err(Error::JobFailed("cancelled".into())),
+                JobStatus::Queued | JobStatus::Running { .. } => {
+                    tokio::time::sleep(poll_interval).await;
+                }
+            }
+        }
+    }
+}
+
+/// Stream of progress updates for one job
+#[derive(Debug)]
+pub struct ProgressStream {
+    inner: Streaming<proto::ProgressUpdate>,
+}
+
+impl ProgressStream {
+    /// Receive the next update, or `None` once the daemon closes the stream
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Status`] if the stream breaks.
+    pub async fn next(&mut self) -> Result<Option<Progress>> {
+        Ok(self.inner.message().await?.map(Progress::from))
+    }
+}
+
+fn job_ref(job: &JobId) -> proto::JobRef {
+    proto::JobRef {
+        job_id: job.0.clone(),
+    }
+}
@@ -0,0 +1,46 @@
+//! Error types for the prover client
+
+use std::fmt;
+
+/// Main error type for the prover client
+#[derive(Debug)]
+pub enum Error {
+    /// Could not establish a connection to the daemon
+    Transport(tonic::transport::Error),
+    /// The daemon answered with a non-OK gRPC status
+    Status(tonic::Status),
+    /// The daemon answered with a message this client cannot interpret
+    Protocol(String),
+    /// The job finished without producing a proof
+    JobFailed(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Transport(err) => write!(f, "Transport error: {err}"),
+            Self::Status(status) => {
+                write!(f, "RPC error: {:?}: {}", status.code(), status.message())
+            }
+            Self::Protocol(msg) => write!(f, "Protocol error: {msg}"),
+            Self::JobFailed(msg) => write!(f, "Job failed: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<tonic::transport::Error> for Error {
+    fn from(err: tonic::transport::Error) -> Self {
+        Self::Transport(err)
+    }
+}
+
+impl From<tonic::Status> for Error {
+    fn from(status: tonic::Status) -> Self {
+        Self::Status(status)
+    }
+}
+
+/// Result type alias
+pub type Result<T> = std::result::Result<T, Error>;
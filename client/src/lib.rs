@@ -0,0 +1,29 @@
+//! Typed gRPC client for the prover daemon
+//!
+//! This module wraps the generated `zkproof.prover.v1` stubs with typed job
+//! submission, status polling and progress streaming.
+
+#![warn(missing_docs)]
+#![warn(clippy::all)]
+#![warn(clippy::pedantic)]
+#![allow(clippy::module_name_repetitions)]
+
+pub mod client;
+pub mod error;
+pub mod types;
+
+/// Generated protobuf types and client stubs
+#[allow(missing_docs, clippy::all, clippy::pedantic)]
+pub mod proto {
+    tonic::include_proto!("zkproof.prover.v1");
+}
+
+pub use client::{ProgressStream, ProverClient};
+pub use error::{Error, Result};
+pub use types::{JobId, JobStatus, Progress, ProofJob};
+
+/// Re-export commonly used types
+pub mod prelude {
+    pub use super::client::{ProgressStream, ProverClient};
+    pub use super::types::{JobId, JobStatus, Progress, ProofJob};
+}
@@ -0,0 +1,205 @@
+//! Typed views of the prover protocol messages
+
+use std::fmt;
+
+use crate::{
+    error::{Error, Result},
+    proto,
+};
+
+/// Identifier assigned to a job by the daemon
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct JobId(pub String);
+
+impl fmt::Display for JobId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A proof job to submit
+#[derive(Clone, Debug)]
+pub struct ProofJob {
+    /// Stable circuit identifier
+    pub circuit_id: String,
+    /// Circuit size parameter
+    pub k: u32,
+    /// Serialized witness
+    pub witness: Vec<u8>,
+    /// Public inputs, one canonical field encoding per entry
+    pub public_inputs: Vec<Vec<u8>>,
+}
+
+impl ProofJob {
+    /// Create a new job for the given circuit
+    #[must_use]
+    pub fn new(circuit_id: impl Into<String>, k: u32, witness: Vec<u8>) -> Self {
+        Self {
+            circuit_id: circuit_id.into(),
+            k,
+            witness,
+            public_inputs: Vec::new(),
+        }
+    }
+
+    /// Attach public inputs
+    #[must_use]
+    pub fn with_public_inputs(mut self, public_inputs: Vec<Vec<u8>>) -> Self {
+        self.public_inputs = public_inputs;
+        self
+    }
+}
+
+impl From<ProofJob> for proto::SubmitJobRequest {
+    fn from(job: ProofJob) -> Self {
+        Self {
+            circuit_id: job.circuit_id,
+            k: job.k,
+            witness: job.witness,
+            public_inputs: job.public_inputs,
+        }
+    }
+}
+
+/// State of a submitted job
+#[derive(Clone, Debug, PartialEq)]
+pub enum JobStatus {
+    /// Waiting for a free worker
+    Queued,
+    /// Being proved; `progress` is in `0.0..=1.0`
+    Running {
+        /// Fraction of work done
+        progress: f32,
+    },
+    /// Finished with a proof
+    Completed {
+        /// Serialized proof
+        proof: Vec<u8>,
+    },
+    /// Finished with an error
+    Failed {
+        /// Failure reason reported by the daemon
+        reason: String,
+    },
+    /// Cancelled before completion
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Whether the job can no longer change state
+    #[must_use]
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            Self::Completed { .. } | Self::Failed { .. } | Self::Cancelled
+        )
+    }
+}
+
+impl TryFrom<proto::JobStatus> for JobStatus {
+    type Error = Error;
+
+    fn try_from(status: proto::JobStatus) -> Result<Self> {
+        match proto::JobState::try_from(status.state) {
+            Ok(proto::JobState::Queued) => Ok(Self::Queued),
+            Ok(proto::JobState::Running) => Ok(Self::Running {
+                progress: status.progress,
+            }),
+            Ok(proto::JobState::Completed) => Ok(Self::Completed {
+                proof: status.proof,
+            }),
+            Ok(proto::JobState::Failed) => Ok(Self::Failed {
+                reason: status.error,
+            }),
+            Ok(proto::JobState::Cancelled) => Ok(Self::Cancelled),
+            Ok(proto::JobState::Unspecified) | Err(_) => Err(Error::Protocol(format!(
+                "unknown job state {}",
+                status.state
+            ))),
+        }
+    }
+}
+
+/// A progress update streamed by the daemon
+#[derive(Clone, Debug, PartialEq)]
+pub struct Progress {
+    /// Prover phase currently running
+    pub phase: String,
+    /// Fraction of work done
+    pub fraction: f32,
+    /// Whether this is the last update for the job
+    pub done: bool,
+}
+
+impl From<proto::ProgressUpdate> for Progress {
+    fn from(update: proto::ProgressUpdate) -> Self {
+        let done = matches!(
+            proto::JobState::try_from(update.state),
+            Ok(proto::JobState::Completed | proto::JobState::Failed | proto::JobState::Cancelled)
+        );
+        Self {
+            phase: update.phase,
+            fraction: update.progress,
+            done,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(state: proto::JobState) -> proto::JobStatus {
+        proto::JobStatus {
+            job_id: "job-1".into(),
+            state: state as i32,
+            progress: 0.5,
+            proof: vec![1, 2, 3],
+            error: "boom".into(),
+        }
+    }
+
+    #[test]
+    fn test_status_conversion() {
+        assert_eq!(
+            JobStatus::try_from(status(proto::JobState::Queued)).unwrap(),
+            JobStatus::Queued
+        );
+        assert_eq!(
+            JobStatus::try_from(status(proto::JobState::Running)).unwrap(),
+            JobStatus::Running { progress: 0.5 }
+        );
+        assert_eq!(
+            JobStatus::try_from(status(proto::JobState::Completed)).unwrap(),
+            JobStatus::Completed {
+                proof: vec![1, 2, 3]
+            }
+        );
+        assert_eq!(
+            JobStatus::try_from(status(proto::JobState::Failed)).unwrap(),
+            JobStatus::Failed {
+                reason: "boom".into()
+            }
+        );
+        assert!(JobStatus::try_from(status(proto::JobState::Unspecified)).is_err());
+    }
+
+    #[test]
+    fn test_terminal_states() {
+        assert!(!JobStatus::Queued.is_terminal());
+        assert!(!JobStatus::Running { progress: 0.1 }.is_terminal());
+        assert!(JobStatus::Cancelled.is_terminal());
+        assert!(JobStatus::Completed { proof: vec![] }.is_terminal());
+    }
+
+    #[test]
+    fn test_progress_done_flag() {
+        let update = proto::ProgressUpdate {
+            job_id: "job-1".into(),
+            state: proto::JobState::Completed as i32,
+            phase: "opening".into(),
+            progress: 1.0,
+        };
+        assert!(Progress::from(update).done);
+    }
+}
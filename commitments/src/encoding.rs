@@ -0,0 +1,199 @@
+//! Bounded, validating byte encodings for commitment and opening types
+//!
+//! Decoding is strict: inputs larger than [`MAX_ENCODED_LEN`] are rejected
+//! before any parsing, length prefixes are checked against the bytes actually
+//! present, scalars must be canonical, points must be on the curve and in the
+//! prime-order subgroup, and trailing bytes are an error. Each value therefore
+//! has exactly one accepted encoding.
+//!
+//! Points are always written compressed, the same form halo2 uses inside
+//! proofs and verifying keys: 32 bytes for pasta and BN254 G1, 64 bytes for
+//! BN254 G2. Decompression recovers `y` from `x` and the sign bit, so the
+//! subgroup check above still applies.
+
+use ff::PrimeField;
+use group::cofactor::CofactorGroup;
+use halo2_proofs::arithmetic::CurveAffine;
+use zk_proof_core::{Error, Result};
+
+/// Upper bound on any encoded commitment or opening
+pub const MAX_ENCODED_LEN: usize = 1 << 20;
+
+/// Types with a canonical byte encoding
+pub trait CanonicalEncoding: Sized {
+    /// Encode to bytes
+    fn to_bytes(&self) -> Vec<u8>;
+
+    /// Decode from bytes produced by [`CanonicalEncoding::to_bytes`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if the input is oversized, truncated,
+    /// non-canonical, has trailing bytes or contains an invalid point.
+    fn from_bytes(bytes: &[u8]) -> Result<Self>;
+}
+
+/// Decode a single curve point in affine form
+///
+/// Curves implementing [`CurveAffine`] have prime order, so a point that
+/// decodes is on the curve and in the prime-order subgroup.
+///
+/// # Errors
+///
+/// Returns [`Error::Encoding`] if `bytes` is not exactly one valid point.
+pub fn point_from_bytes<C: CurveAffine>(bytes: &[u8]) -> Result<C> {
+    let mut reader = Reader::new(bytes)?;
+    let point = reader.affine()?;
+    reader.finish()?;
+    Ok(point)
+}
+
+/// Cursor over an encoded value that enforces bounds on every read
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Result<Self> {
+        if bytes.len() > MAX_ENCODED_LEN {
+            return Err(Error::Encoding(format!(
+                "encoding of {} bytes exceeds limit of {MAX_ENCODED_LEN}",
+                bytes.len()
+            )));
+        }
+        Ok(Self { bytes })
+    }
+
+    pub(crate) fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if n > self.bytes.len() {
+            return Err(Error::Encoding(format!(
+                "truncated input: need {n} bytes, have {}",
+                self.bytes.len()
+            )));
+        }
+        let (head, tail) = self.bytes.split_at(n);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    pub(crate) fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.take(N)?);
+        Ok(out)
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    /// Read a `u64` that must fit in `usize`
+    pub(crate) fn usize(&mut self) -> Result<usize> {
+        usize::try_from(self.u64()?).map_err(|_| Error::Encoding("length overflows usize".into()))
+    }
+
+    /// Read a `u32` element count of items at least `item_len` bytes each
+    ///
+    /// The count is checked against the remaining input so a forged prefix
+    /// cannot trigger a large allocation.
+    pub(crate) fn count(&mut self, item_len: usize) -> Result<usize> {
+        let count = u32::from_le_bytes(self.array()?) as usize;
+        if count.saturating_mul(item_len.max(1)) > self.bytes.len() {
+            return Err(Error::Encoding(format!(
+                "count {count} exceeds remaining input"
+            )));
+        }
+        Ok(count)
+    }
+
+    pub(crate) fn scalar<F: PrimeField>(&mut self) -> Result<F> {
+        let mut repr = F::Repr::default();
+        let len = repr.as_ref().len();
+        repr.as_mut().copy_from_slice(self.take(len)?);
+        Option::from(F::from_repr(repr))
+            .ok_or_else(|| Error::Encoding("non-canonical scalar".into()))
+    }
+
+    pub(crate) fn affine<C: CurveAffine>(&mut self) -> Result<C> {
+        let mut repr = C::Repr::default();
+        let len = repr.as_ref().len();
+        repr.as_mut().copy_from_slice(self.take(len)?);
+        Option::from(C::from_bytes(&repr)).ok_or_else(|| Error::Encoding("invalid curve point".into()))
+    }
+
+    pub(crate) fn point<G: CofactorGroup>(&mut self) -> Result<G> {
+        let mut repr = G::Repr::default();
+        let len = repr.as_ref().len();
+        repr.as_mut().copy_from_slice(self.take(len)?);
+        let point: G = Option::from(G::from_bytes(&repr))
+            .ok_or_else(|| Error::Encoding("invalid curve point".into()))?;
+        if !bool::from(point.is_torsion_free()) {
+            return Err(Error::Encoding("point not in prime-order subgroup".into()));
+        }
+        Ok(point)
+    }
+
+    pub(crate) fn finish(self) -> Result<()> {
+        if self.bytes.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Encoding(format!("{} trailing bytes", self.bytes.len())))
+        }
+    }
+}
+
+/// Write a `u32` element count
+///
+/// # Panics
+///
+/// Panics if `count` does not fit in a `u32`.
+pub(crate) fn write_count(out: &mut Vec<u8>, count: usize) {
+    let count = u32::try_from(count).expect("element count fits in u32");
+    out.extend_from_slice(&count.to_le_bytes());
+}
+
+/// Size in bytes of an encoded scalar
+pub(crate) fn scalar_len<F: PrimeField>() -> usize {
+    F::Repr::default().as_ref().len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use group::{prime::PrimeCurveAffine, Curve, Group, GroupEncoding};
+    use pasta_curves::pallas;
+
+    #[test]
+    fn test_point_round_trip() {
+        let point = pallas::Affine::generator();
+        let decoded: pallas::Affine = point_from_bytes(point.to_bytes().as_ref()).unwrap();
+        assert_eq!(decoded, point);
+    }
+
+    #[test]
+    fn test_invalid_points_rejected() {
+        // x = 2 is not the x-coordinate of a Pallas point: 8 + 5 = 13 is a
+        // quadratic non-residue.
+        let mut bytes = [0u8; 32];
+        bytes[0] = 2;
+        assert!(point_from_bytes::<pallas::Affine>(&bytes).is_err());
+
+        let point = (pallas::Point::generator() * pallas::Scalar::from(3)).to_affine();
+        let mut bytes = point.to_bytes().as_ref().to_vec();
+        bytes.push(0);
+        assert!(point_from_bytes::<pallas::Affine>(&bytes).is_err());
+        assert!(point_from_bytes::<pallas::Affine>(&bytes[..31]).is_err());
+    }
+
+    #[test]
+    fn test_forged_count_rejected() {
+        let bytes = [0xff, 0xff, 0xff, 0x7f, 0, 0];
+        let mut reader = Reader::new(&bytes).unwrap();
+        assert!(reader.count(32).is_err());
+    }
+
+    #[test]
+    fn test_oversized_input_rejected() {
+        let bytes = vec![0u8; MAX_ENCODED_LEN + 1];
+        assert!(Reader::new(&bytes).is_err());
+    }
+}
@@ -0,0 +1,402 @@
+//! Nova folding of relaxed R1CS instances
+//!
+//! A relaxed R1CS instance `(u, x, W, E)` satisfies
+//! `(A z) ∘ (B z) = u (C z) + E` for `z = (u, x, W)`. A plain R1CS
+//! witness is the special case `u = 1`, `E = 0`. Folding two instances with
+//! a challenge `r` takes their random linear combination, which picks up
+//! the cross term
+//!
+//! ```text
+//! T = (A z1) ∘ (B z2) + (A z2) ∘ (B z1) - u1 (C z2) - u2 (C z1)
+//! ```
+//!
+//! in the error: `E = E1 + r T + r^2 E2`. The prover commits to `T` with
+//! [`VectorPedersen`] before `r` is squeezed from a [`Transcript`], and
+//! since the commitments are homomorphic the folded `W` and `E` are opened
+//! by the same combination of the inputs' blinding factors. The folded
+//! instance satisfies the relation exactly when both inputs do, except with
+//! negligible probability over `r`.
+
+use ff::Field;
+use group::prime::PrimeCurveAffine;
+use halo2_proofs::arithmetic::CurveAffine;
+use zk_proof_core::{Randomness, Transcript};
+
+use crate::vector::VectorPedersen;
+
+/// Sparse matrix as `(row, column, value)` entries
+pub type SparseMatrix<F> = Vec<(usize, usize, F)>;
+
+/// R1CS constraint matrices over `z = (u, x, W)`
+#[derive(Clone, Debug)]
+pub struct R1CSShape<F: Field> {
+    /// Number of constraints, the rows of each matrix
+    pub num_constraints: usize,
+    /// Number of public inputs `x`
+    pub num_inputs: usize,
+    /// Number of witness entries `W`
+    pub num_witnesses: usize,
+    /// Left matrix `A`
+    pub a: SparseMatrix<F>,
+    /// Right matrix `B`
+    pub b: SparseMatrix<F>,
+    /// Output matrix `C`
+    pub c: SparseMatrix<F>,
+}
+
+impl<F: Field> R1CSShape<F> {
+    /// Number of columns: `u`, the inputs and the witness
+    #[must_use]
+    pub fn num_columns(&self) -> usize {
+        1 + self.num_inputs + self.num_witnesses
+    }
+
+    /// Whether `(u, x, w, e)` satisfies `(A z) ∘ (B z) = u (C z) + E`
+    ///
+    /// Returns `false` for vectors of the wrong length and for matrix
+    /// entries outside the shape.
+    #[must_use]
+    pub fn is_satisfied(&self, u: F, x: &[F], w: &[F], e: &[F]) -> bool {
+        if x.len() != self.num_inputs
+            || w.len() != self.num_witnesses
+            || e.len() != self.num_constraints
+        {
+            return false;
+        }
+        let z = self.z(u, x, w);
+        let (Some(az), Some(bz), Some(cz)) = (
+            self.multiply(&self.a, &z),
+            self.multiply(&self.b, &z),
+            self.multiply(&self.c, &z),
+        ) else {
+            return false;
+        };
+        (0..self.num_constraints).all(|i| az[i] * bz[i] == u * cz[i] + e[i])
+    }
+
+    /// Cross term of folding `(u1, z1)` with `(u2, z2)`
+    fn cross_term(&self, u1: F, z1: &[F], u2: F, z2: &[F]) -> Vec<F> {
+        let products = |z: &[F]| {
+            [&self.a, &self.b, &self.c].map(|matrix| {
+                self.multiply(matrix, z)
+                    .expect("matrix entries lie inside the shape")
+            })
+        };
+        let [az1, bz1, cz1] = products(z1);
+        let [az2, bz2, cz2] = products(z2);
+        (0..self.num_constraints)
+            .map(|i| az1[i] * bz2[i] + az2[i] * bz1[i] - u1 * cz2[i] - u2 * cz1[i])
+            .collect()
+    }
+
+    fn z(&self, u: F, x: &[F], w: &[F]) -> Vec<F> {
+        let mut z = Vec::with_capacity(self.num_columns());
+        z.push(u);
+        z.extend_from_slice(x);
+        z.extend_from_slice(w);
+        z
+    }
+
+    /// `matrix * z`, or `None` if an entry lies outside the shape
+    fn multiply(&self, matrix: &SparseMatrix<F>, z: &[F]) -> Option<Vec<F>> {
+        let mut out = vec![F::ZERO; self.num_constraints];
+        for &(row, column, value) in matrix {
+            *out.get_mut(row)? += value * z.get(column)?;
+        }
+        Some(out)
+    }
+}
+
+/// Relaxed R1CS instance together with its witness, error and openings
+#[derive(Clone, Debug)]
+pub struct RelaxedR1CS<C: CurveAffine> {
+    /// Witness vector
+    pub w: Vec<C::Scalar>,
+    /// Public inputs
+    pub x: Vec<C::Scalar>,
+    /// Error vector
+    pub e: Vec<C::Scalar>,
+    /// Scalar for folding
+    pub u: C::Scalar,
+    /// Committed witness
+    pub comm_w: C,
+    /// Committed error
+    pub comm_e: C,
+    /// Blinding factor of `comm_w`
+    pub r_w: C::Scalar,
+    /// Blinding factor of `comm_e`
+    pub r_e: C::Scalar,
+}
+
+impl<C: CurveAffine> RelaxedR1CS<C> {
+    /// Relax a plain R1CS witness: `u = 1` and a zero error
+    ///
+    /// The error commitment is the identity, a commitment to zero under a
+    /// zero blinding factor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shape has more witness entries or constraints than
+    /// `pedersen` has generators.
+    pub fn new(
+        shape: &R1CSShape<C::Scalar>,
+        pedersen: &VectorPedersen<C>,
+        x: Vec<C::Scalar>,
+        w: Vec<C::Scalar>,
+        randomness: &impl Randomness,
+    ) -> Self {
+        let r_w = randomness.random_field();
+        let e = vec![C::Scalar::ZERO; shape.num_constraints];
+        Self {
+            comm_w: pedersen.commit_with_blinding(&w, r_w),
+            comm_e: pedersen.commit_with_blinding(&e, C::Scalar::ZERO),
+            w,
+            x,
+            e,
+            u: C::Scalar::ONE,
+            r_w,
+            r_e: C::Scalar::ZERO,
+        }
+    }
+
+    /// Fold `other` into this instance
+    ///
+    /// Commits to the cross term, absorbs both instances and that
+    /// commitment into `transcript` and squeezes the folding challenge from
+    /// it. Returns the folded instance and the cross-term commitment, which
+    /// a verifier needs to fold the two instances' commitments itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either instance does not fit `shape`, or `shape` does not
+    /// fit `pedersen`.
+    pub fn fold(
+        &self,
+        other: &Self,
+        shape: &R1CSShape<C::Scalar>,
+        pedersen: &VectorPedersen<C>,
+        transcript: &mut impl Transcript<C>,
+        randomness: &impl Randomness,
+    ) -> (Self, C) {
+        for instance in [self, other] {
+            assert!(
+                instance.x.len() == shape.num_inputs
+                    && instance.w.len() == shape.num_witnesses
+                    && instance.e.len() == shape.num_constraints,
+                "instance does not fit the R1CS shape"
+            );
+        }
+        let t = shape.cross_term(
+            self.u,
+            &shape.z(self.u, &self.x, &self.w),
+            other.u,
+            &shape.z(other.u, &other.x, &other.w),
+        );
+        let r_t: C::Scalar = randomness.random_field();
+        let comm_t = pedersen.commit_with_blinding(&t, r_t);
+
+        self.absorb(transcript);
+        other.absorb(transcript);
+        transcript.absorb_point(&comm_t);
+        let r = transcript.squeeze_challenge();
+        let r_squared = r.square();
+
+        let combine = |a: &[C::Scalar], b: &[C::Scalar]| -> Vec<C::Scalar> {
+            a.iter().zip(b).map(|(a, b)| *a + r * b).collect()
+        };
+        let e = self
+            .e
+            .iter()
+            .zip(&t)
+            .zip(&other.e)
+            .map(|((e1, t), e2)| *e1 + r * t + r_squared * e2)
+            .collect();
+        let folded = Self {
+            w: combine(&self.w, &other.w),
+            x: combine(&self.x, &other.x),
+            e,
+            u: self.u + r * other.u,
+            comm_w: (self.comm_w.to_curve() + other.comm_w * r).into(),
+            comm_e: (self.comm_e.to_curve() + comm_t * r + other.comm_e * r_squared).into(),
+            r_w: self.r_w + r * other.r_w,
+            r_e: self.r_e + r * r_t + r_squared * other.r_e,
+        };
+        (folded, comm_t)
+    }
+
+    /// Absorb the public part of the instance
+    fn absorb(&self, transcript: &mut impl Transcript<C>) {
+        transcript.absorb_point(&self.comm_w);
+        transcript.absorb_point(&self.comm_e);
+        transcript.absorb_scalar(&self.u);
+        for input in &self.x {
+            transcript.absorb_scalar(input);
+        }
+    }
+}
+
+/// Folding verifier
+///
+/// Checks instances against one R1CS shape and the commitment parameters
+/// their witnesses and errors were committed with.
+#[derive(Debug)]
+pub struct FoldingVerifier<C: CurveAffine> {
+    shape: R1CSShape<C::Scalar>,
+    pedersen: VectorPedersen<C>,
+    instances: Vec<RelaxedR1CS<C>>,
+}
+
+impl<C: CurveAffine> FoldingVerifier<C> {
+    /// Verifier for instances of `shape` committed with `pedersen`
+    #[must_use]
+    pub fn new(shape: R1CSShape<C::Scalar>, pedersen: VectorPedersen<C>) -> Self {
+        Self {
+            shape,
+            pedersen,
+            instances: Vec::new(),
+        }
+    }
+
+    /// Verify a folded instance
+    ///
+    /// Checks the relaxed R1CS relation and that `comm_w` and `comm_e` open
+    /// to the instance's witness and error.
+    #[must_use]
+    pub fn verify(&self, instance: &RelaxedR1CS<C>) -> bool {
+        let fits = instance.w.len() <= self.pedersen.capacity()
+            && instance.e.len() <= self.pedersen.capacity();
+        fits && self
+            .shape
+            .is_satisfied(instance.u, &instance.x, &instance.w, &instance.e)
+            && self
+                .pedersen
+                .commit_with_blinding(&instance.w, instance.r_w)
+                == instance.comm_w
+            && self
+                .pedersen
+                .commit_with_blinding(&instance.e, instance.r_e)
+                == instance.comm_e
+    }
+
+    /// Add instance for folding
+    pub fn add_instance(&mut self, instance: RelaxedR1CS<C>) {
+        self.instances.push(instance);
+    }
+
+    /// Fold all added instances in order, squeezing each challenge from
+    /// `transcript`
+    ///
+    /// Returns `None` if no instance was added.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an instance does not fit the shape, see
+    /// [`RelaxedR1CS::fold`].
+    pub fn fold_all(
+        &self,
+        transcript: &mut impl Transcript<C>,
+        randomness: &impl Randomness,
+    ) -> Option<RelaxedR1CS<C>> {
+        let (first, rest) = self.instances.split_first()?;
+        Some(rest.iter().fold(first.clone(), |acc, instance| {
+            acc.fold(
+                instance,
+                &self.shape,
+                &self.pedersen,
+                transcript,
+                randomness,
+            )
+            .0
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::pasta::{pallas, Fq};
+    use zk_proof_core::{Blake2bTranscript, OsRandomness};
+
+    use super::*;
+
+    /// `x^2 = y` with public `y` and witness `x`, plus `x * 1 = x` so the
+    /// shape has two constraints
+    fn square_shape() -> R1CSShape<Fq> {
+        // z = (u, y, x)
+        R1CSShape {
+            num_constraints: 2,
+            num_inputs: 1,
+            num_witnesses: 1,
+            a: vec![(0, 2, Fq::ONE), (1, 2, Fq::ONE)],
+            b: vec![(0, 2, Fq::ONE), (1, 0, Fq::ONE)],
+            c: vec![(0, 1, Fq::ONE), (1, 2, Fq::ONE)],
+        }
+    }
+
+    fn instance(
+        shape: &R1CSShape<Fq>,
+        pedersen: &VectorPedersen<pallas::Affine>,
+        x: u64,
+    ) -> RelaxedR1CS<pallas::Affine> {
+        let x = Fq::from(x);
+        RelaxedR1CS::new(shape, pedersen, vec![x.square()], vec![x], &OsRandomness)
+    }
+
+    #[test]
+    fn test_folding_scheme() {
+        let shape = square_shape();
+        let pedersen = VectorPedersen::derive(2);
+        let instance1 = instance(&shape, &pedersen, 3);
+        let instance2 = instance(&shape, &pedersen, 5);
+
+        let mut verifier = FoldingVerifier::new(shape.clone(), pedersen.clone());
+        assert!(verifier.verify(&instance1));
+
+        // The cross term lands in the error, which is no longer zero
+        let mut transcript = Blake2bTranscript::new(b"folding test");
+        let (folded, _) = instance1.fold(
+            &instance2,
+            &shape,
+            &pedersen,
+            &mut transcript,
+            &OsRandomness,
+        );
+        assert!(folded.e.iter().any(|e| !bool::from(e.is_zero())));
+        assert!(verifier.verify(&folded));
+
+        // Dropping the cross term, as plain `E1 + r E2` folding does, breaks
+        // the relation
+        let mut naive = folded.clone();
+        naive.e = instance1.e.clone();
+        naive.comm_e = instance1.comm_e;
+        naive.r_e = instance1.r_e;
+        assert!(!verifier.verify(&naive));
+
+        verifier.add_instance(instance1);
+        verifier.add_instance(instance2);
+        verifier.add_instance(instance(&shape, &pedersen, 7));
+        let mut transcript = Blake2bTranscript::new(b"folding test");
+        let result = verifier.fold_all(&mut transcript, &OsRandomness).unwrap();
+        assert!(verifier.verify(&result));
+    }
+
+    #[test]
+    fn test_unsatisfied_instance_folds_to_unsatisfied() {
+        let shape = square_shape();
+        let pedersen = VectorPedersen::derive(2);
+        let good = instance(&shape, &pedersen, 3);
+        let mut bad = instance(&shape, &pedersen, 4);
+        bad.x[0] += Fq::ONE;
+
+        let verifier = FoldingVerifier::new(shape.clone(), pedersen.clone());
+        assert!(!verifier.verify(&bad));
+
+        let mut transcript = Blake2bTranscript::new(b"folding test");
+        let (folded, _) = good.fold(&bad, &shape, &pedersen, &mut transcript, &OsRandomness);
+        assert!(!verifier.verify(&folded));
+
+        // An opening for another witness is rejected too
+        let mut forged = good.clone();
+        forged.w[0] += Fq::ONE;
+        assert!(!verifier.verify(&forged));
+    }
+}
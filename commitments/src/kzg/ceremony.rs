@@ -0,0 +1,242 @@
+//! Trusted-setup ceremony tooling
+//!
+//! A contribution rerandomizes an SRS for secret `tau` into one for
+//! `tau * x`, where `x` is fresh randomness the contributor then discards.
+//! The result is trustworthy as long as any one contributor discarded their
+//! `x`. Each contribution carries a proof of knowledge of `x` in the style of
+//! the original powers-of-tau ceremony:
+//!
+//! - the contributor publishes `s = [r] G1` and `s_x = [r x] G1`;
+//! - a G2 point `h` is derived by hashing both SRS digests together with
+//!   `s` and `s_x`, and the contributor publishes `h_x = [x] h`;
+//! - the verifier checks `(s, s_x)` and `(h, h_x)` have the same ratio, and
+//!   that `(tau_before, tau_after)` in G1 share that ratio too.
+
+use blake2b_simd::Params as Blake2bParams;
+use ff::Field;
+use group::{prime::PrimeCurveAffine, Curve, GroupEncoding};
+use halo2curves::{
+    bn256::{Bn256, Fr, G1Affine, G2Affine, G1, G2},
+    pairing::Engine,
+    CurveExt,
+};
+use zk_proof_core::{Error, Randomness, Result};
+
+use super::srs::{write_g1, Srs, G1_COMPRESSED_LEN, G1_UNCOMPRESSED_LEN, G2_COMPRESSED_LEN};
+use crate::encoding::{CanonicalEncoding, Reader};
+
+/// Hash-to-curve domain for the G2 point in a contribution proof
+pub const CONTRIBUTION_DOMAIN: &str = "zk-proof-system:ceremony:v1";
+
+/// Size of an SRS digest
+pub const DIGEST_LEN: usize = 64;
+
+const DIGEST_PERSONAL: &[u8; 16] = b"zkps-srs-digest\0";
+
+/// Proof that a contribution multiplied `tau` by a known secret
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ContributionProof {
+    /// `[r] G1` for a random `r`
+    pub s: G1Affine,
+    /// `[r x] G1`
+    pub s_x: G1Affine,
+    /// `[x] h`, where `h` is derived from the transcript
+    pub h_x: G2Affine,
+}
+
+impl CanonicalEncoding for ContributionProof {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(2 * G1_COMPRESSED_LEN + G2_COMPRESSED_LEN);
+        out.extend_from_slice(self.s.to_bytes().as_ref());
+        out.extend_from_slice(self.s_x.to_bytes().as_ref());
+        out.extend_from_slice(self.h_x.to_bytes().as_ref());
+        out
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes)?;
+        let s = reader.point::<G1>()?.to_affine();
+        let s_x = reader.point::<G1>()?.to_affine();
+        let h_x = reader.point::<G2>()?.to_affine();
+        reader.finish()?;
+        Ok(Self { s, s_x, h_x })
+    }
+}
+
+/// Blake2b digest of the serialized SRS, used to chain contributions
+///
+/// # Panics
+///
+/// Panics if serializing into memory fails.
+#[must_use]
+pub fn digest(srs: &Srs) -> [u8; DIGEST_LEN] {
+    let mut bytes = Vec::new();
+    srs.write_to(&mut bytes).expect("in-memory write");
+
+    let hash = Blake2bParams::new()
+        .hash_length(DIGEST_LEN)
+        .personal(DIGEST_PERSONAL)
+        .hash(&bytes);
+    let mut out = [0u8; DIGEST_LEN];
+    out.copy_from_slice(hash.as_bytes());
+    out
+}
+
+/// Apply a fresh contribution to `srs`
+///
+/// Returns the new SRS and the proof to publish alongside it. The secret
+/// only lives inside this function.
+///
+/// # Errors
+///
+/// Returns [`Error::Verification`] if `srs` is degenerate (`tau = 0`).
+pub fn contribute<R: Randomness>(srs: &Srs, randomness: &R) -> Result<(Srs, ContributionProof)> {
+    if srs.g1_powers().len() < 2 || bool::from(srs.g1_powers()[1].is_identity()) {
+        return Err(Error::Verification("cannot contribute to a degenerate SRS".into()));
+    }
+
+    let x = nonzero_scalar(randomness);
+
+    let mut power = Fr::ONE;
+    let g1_powers: Vec<G1> = srs
+        .g1_powers()
+        .iter()
+        .map(|point| {
+            let scaled = *point * power;
+            power *= x;
+            scaled
+        })
+        .collect();
+    let mut g1_affine = vec![G1Affine::identity(); g1_powers.len()];
+    G1::batch_normalize(&g1_powers, &mut g1_affine);
+
+    let g2_powers = [srs.g2(), (srs.g2_tau() * x).to_affine()];
+    let next = Srs::from_parts(srs.k(), g1_affine, g2_powers)?;
+
+    let r = nonzero_scalar(randomness);
+    let s = (G1::generator() * r).to_affine();
+    let s_x = (s * x).to_affine();
+    let h = transcript_point(&digest(srs), &digest(&next), &s, &s_x);
+    let h_x = (h * x).to_affine();
+
+    Ok((next, ContributionProof { s, s_x, h_x }))
+}
+
+/// Check that `after` was derived from `before` by a proven contribution
+///
+/// `after` is also checked as a standalone SRS with [`Srs::verify`].
+///
+/// # Errors
+///
+/// Returns [`Error::ParamsMismatch`] if the sizes differ and
+/// [`Error::Verification`] describing the first failed check otherwise.
+pub fn verify_contribution<R: Randomness>(
+    before: &Srs,
+    after: &Srs,
+    proof: &ContributionProof,
+    randomness: &R,
+) -> Result<()> {
+    if before.k() != after.k() {
+        return Err(Error::ParamsMismatch {
+            expected: before.k(),
+            found: after.k(),
+        });
+    }
+    if before.g1_powers().len() < 2 {
+        return Err(Error::Verification("SRS too small to carry a contribution".into()));
+    }
+    if bool::from(proof.s.is_identity()) || bool::from(proof.s_x.is_identity()) {
+        return Err(Error::Verification("contribution proof has identity points".into()));
+    }
+
+    let h = transcript_point(&digest(before), &digest(after), &proof.s, &proof.s_x);
+
+    // Knowledge of x: (s, s_x) and (h, h_x) have the same ratio.
+    if !same_ratio(&proof.s, &proof.s_x, &h, &proof.h_x) {
+        return Err(Error::Verification("invalid proof of knowledge".into()));
+    }
+
+    // The update used that x: tau_after = tau_before * x.
+    if !same_ratio(&before.g1_powers()[1], &after.g1_powers()[1], &h, &proof.h_x) {
+        return Err(Error::Verification("SRS update does not match the proof".into()));
+    }
+
+    after.verify(randomness)
+}
+
+/// `e(a, d) == e(b, c)`, i.e. `b / a == d / c` in the exponent
+fn same_ratio(a: &G1Affine, b: &G1Affine, c: &G2Affine, d: &G2Affine) -> bool {
+    Bn256::pairing(a, d) == Bn256::pairing(b, c)
+}
+
+/// Hash the contribution transcript onto G2
+fn transcript_point(
+    before: &[u8; DIGEST_LEN],
+    after: &[u8; DIGEST_LEN],
+    s: &G1Affine,
+    s_x: &G1Affine,
+) -> G2Affine {
+    let mut message = Vec::with_capacity(2 * DIGEST_LEN + 2 * G1_UNCOMPRESSED_LEN);
+    message.extend_from_slice(before);
+    message.extend_from_slice(after);
+    write_g1(s, &mut message);
+    write_g1(s_x, &mut message);
+    G2::hash_to_curve(CONTRIBUTION_DOMAIN)(&message).to_affine()
+}
+
+fn nonzero_scalar<R: Randomness>(randomness: &R) -> Fr {
+    loop {
+        let value: Fr = randomness.random_field();
+        if !bool::from(value.is_zero()) {
+            return value;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zk_proof_core::DeterministicRandomness;
+
+    fn initial_srs(k: u32) -> Srs {
+        // tau = 1 is the customary starting point of a ceremony.
+        let g1 = vec![G1Affine::generator(); 1 << k];
+        Srs::from_parts(k, g1, [G2Affine::generator(); 2]).unwrap()
+    }
+
+    #[test]
+    fn test_contribution_chain() {
+        let rng = DeterministicRandomness::from_seed([9; 32]);
+        let srs0 = initial_srs(3);
+
+        let (srs1, proof1) = contribute(&srs0, &rng).unwrap();
+        let (srs2, proof2) = contribute(&srs1, &rng).unwrap();
+
+        verify_contribution(&srs0, &srs1, &proof1, &rng).unwrap();
+        verify_contribution(&srs1, &srs2, &proof2, &rng).unwrap();
+        assert_ne!(srs1.g1_powers()[1], srs0.g1_powers()[1]);
+    }
+
+    #[test]
+    fn test_proof_does_not_transfer() {
+        let rng = DeterministicRandomness::from_seed([10; 32]);
+        let srs0 = initial_srs(2);
+
+        let (srs1, proof1) = contribute(&srs0, &rng).unwrap();
+        let (srs1_other, _) = contribute(&srs0, &rng).unwrap();
+
+        assert!(verify_contribution(&srs0, &srs1_other, &proof1, &rng).is_err());
+        assert!(verify_contribution(&srs1, &srs0, &proof1, &rng).is_err());
+    }
+
+    #[test]
+    fn test_proof_encoding() {
+        let rng = DeterministicRandomness::from_seed([11; 32]);
+        let (_, proof) = contribute(&initial_srs(1), &rng).unwrap();
+
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), 2 * G1_COMPRESSED_LEN + G2_COMPRESSED_LEN);
+        assert_eq!(ContributionProof::from_bytes(&bytes).unwrap(), proof);
+        assert!(ContributionProof::from_bytes(&bytes[1..]).is_err());
+    }
+}
@@ -0,0 +1,12 @@
+//! KZG mode over BN254
+//!
+//! KZG commitments need a structured reference string from a trusted setup.
+//! This module loads one from the perpetual powers-of-tau ceremony and checks
+//! it with pairings before use, and can run further contributions on top of
+//! it.
+
+pub mod ceremony;
+pub mod srs;
+
+pub use ceremony::{contribute, verify_contribution, ContributionProof};
+pub use srs::Srs;
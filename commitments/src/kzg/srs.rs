@@ -0,0 +1,482 @@
+//! Powers-of-tau structured reference string
+//!
+//! Parses `challenge` files of the perpetual powers-of-tau ceremony (BN254,
+//! uncompressed points). The file layout for a ceremony of power `p` is
+//!
+//! ```text
+//! previous hash    64 bytes
+//! tau^i * G1       2^(p+1) - 1 points   (64 bytes each)
+//! tau^i * G2       2^p points           (128 bytes each)
+//! alpha tau^i G1   2^p points
+//! beta tau^i G1    2^p points
+//! beta G2          1 point
+//! ```
+//!
+//! Only the prefix needed for `2^k` G1 powers and the first two G2 powers is
+//! read; the rest of the file is never touched.
+
+use std::io::{Read, Write};
+
+use group::{cofactor::CofactorGroup, prime::PrimeCurveAffine, Curve, GroupEncoding};
+use halo2curves::{
+    bn256::{Bn256, Fq, Fq2, Fr, G1Affine, G2Affine, G2},
+    msm::msm_best,
+    pairing::Engine,
+    CurveAffine,
+};
+use zk_proof_core::{Error, Randomness, Result};
+
+/// Size of the hash that opens every ceremony file
+pub const HASH_LEN: usize = 64;
+
+/// Size of an uncompressed G1 point
+pub const G1_UNCOMPRESSED_LEN: usize = 64;
+
+/// Size of an uncompressed G2 point
+pub const G2_UNCOMPRESSED_LEN: usize = 128;
+
+/// Size of a compressed G1 point
+pub const G1_COMPRESSED_LEN: usize = 32;
+
+/// Size of a compressed G2 point
+pub const G2_COMPRESSED_LEN: usize = 64;
+
+/// Largest k accepted when reading a serialized SRS
+pub const MAX_SRS_K: u32 = 28;
+
+/// Flag bit marking the point at infinity in the first byte
+const INFINITY_FLAG: u8 = 0x40;
+
+/// Tag opening an SRS written with compressed points
+const SRS_MAGIC: &[u8; 4] = b"ZKS2";
+
+/// Structured reference string for polynomials of fewer than `2^k`
+/// coefficients
+#[derive(Clone, Debug)]
+pub struct Srs {
+    k: u32,
+    g1_powers: Vec<G1Affine>,
+    g2_powers: [G2Affine; 2],
+}
+
+impl Srs {
+    /// Build an SRS from raw powers without checking them
+    ///
+    /// Call [`Srs::verify`] before trusting the result.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if `g1_powers` does not hold exactly
+    /// `2^k` points.
+    pub fn from_parts(k: u32, g1_powers: Vec<G1Affine>, g2_powers: [G2Affine; 2]) -> Result<Self> {
+        if g1_powers.len() != 1 << k {
+            return Err(Error::Encoding(format!(
+                "expected {} G1 powers for k = {k}, found {}",
+                1u64 << k,
+                g1_powers.len()
+            )));
+        }
+        Ok(Self {
+            k,
+            g1_powers,
+            g2_powers,
+        })
+    }
+
+    /// Read an SRS of size `2^k` from a ceremony file of power `power`
+    ///
+    /// Returns the SRS and the hash at the start of the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ParamsMismatch`] if the ceremony is too small for
+    /// `k`, [`Error::Io`] if reading fails and [`Error::Encoding`] for
+    /// invalid points.
+    pub fn read_ppot<R: Read>(mut reader: R, power: u32, k: u32) -> Result<(Self, [u8; HASH_LEN])> {
+        if k > power {
+            return Err(Error::ParamsMismatch {
+                expected: k,
+                found: power,
+            });
+        }
+
+        let mut hash = [0u8; HASH_LEN];
+        reader.read_exact(&mut hash)?;
+
+        let n = 1usize << k;
+        let g1_total = (1u64 << (power + 1)) - 1;
+        let g1_powers = (0..n)
+            .map(|_| read_g1(&mut reader))
+            .collect::<Result<Vec<_>>>()?;
+
+        let skip = (g1_total - n as u64) * G1_UNCOMPRESSED_LEN as u64;
+        let skipped = std::io::copy(&mut (&mut reader).take(skip), &mut std::io::sink())?;
+        if skipped != skip {
+            return Err(Error::Encoding("ceremony file truncated in G1 powers".into()));
+        }
+
+        let g2_powers = [read_g2(&mut reader)?, read_g2(&mut reader)?];
+        Ok((Self::from_parts(k, g1_powers, g2_powers)?, hash))
+    }
+
+    /// Write the SRS as a tag, `k` (u32 little-endian), the G1 powers and
+    /// the two G2 powers, with compressed points
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if writing fails.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        let mut out = Vec::with_capacity(
+            8 + self.g1_powers.len() * G1_COMPRESSED_LEN + 2 * G2_COMPRESSED_LEN,
+        );
+        out.extend_from_slice(SRS_MAGIC);
+        out.extend_from_slice(&self.k.to_le_bytes());
+        for point in &self.g1_powers {
+            out.extend_from_slice(point.to_bytes().as_ref());
+        }
+        for point in &self.g2_powers {
+            out.extend_from_slice(point.to_bytes().as_ref());
+        }
+        writer.write_all(&out)?;
+        Ok(())
+    }
+
+    /// Read an SRS written by [`Srs::write_to`]
+    ///
+    /// Untagged files from before point compression, holding `k` and the
+    /// points in the ceremony encoding, are still accepted. The points are
+    /// validated individually; call [`Srs::verify`] to check that they form
+    /// a consistent SRS.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if `k` exceeds [`MAX_SRS_K`] or a point is
+    /// invalid, and [`Error::Io`] if reading fails.
+    pub fn read_from<R: Read>(mut reader: R) -> Result<Self> {
+        let mut word = [0u8; 4];
+        reader.read_exact(&mut word)?;
+        let compressed = &word == SRS_MAGIC;
+        if compressed {
+            reader.read_exact(&mut word)?;
+        }
+        let k = u32::from_le_bytes(word);
+        if k > MAX_SRS_K {
+            return Err(Error::Encoding(format!("SRS k = {k} exceeds {MAX_SRS_K}")));
+        }
+
+        let (read_g1, read_g2): (ReadPoint<_, G1Affine>, ReadPoint<_, G2Affine>) = if compressed {
+            (read_g1_compressed, read_g2_compressed)
+        } else {
+            (read_g1, read_g2)
+        };
+        let g1_powers = (0..1usize << k)
+            .map(|_| read_g1(&mut reader))
+            .collect::<Result<Vec<_>>>()?;
+        let g2_powers = [read_g2(&mut reader)?, read_g2(&mut reader)?];
+        Self::from_parts(k, g1_powers, g2_powers)
+    }
+
+    /// Number of G1 powers is `2^k`
+    #[must_use]
+    pub fn k(&self) -> u32 {
+        self.k
+    }
+
+    /// `[tau^i] G1` for `i < 2^k`
+    #[must_use]
+    pub fn g1_powers(&self) -> &[G1Affine] {
+        &self.g1_powers
+    }
+
+    /// The G2 generator
+    #[must_use]
+    pub fn g2(&self) -> G2Affine {
+        self.g2_powers[0]
+    }
+
+    /// `[tau] G2`
+    #[must_use]
+    pub fn g2_tau(&self) -> G2Affine {
+        self.g2_powers[1]
+    }
+
+    /// Check that the SRS is a well-formed sequence of powers of one secret
+    ///
+    /// Both sequences must start at the standard generators, `tau` must be
+    /// non-zero, and consecutive G1 powers must differ by the same `tau` as
+    /// the G2 powers. The latter is checked for all powers at once with a
+    /// random linear combination, so the cost is two pairings plus two
+    /// multi-exponentiations.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Verification`] describing the first failed check.
+    pub fn verify<R: Randomness>(&self, randomness: &R) -> Result<()> {
+        if self.g1_powers[0] != G1Affine::generator() {
+            return Err(Error::Verification("first G1 power is not the generator".into()));
+        }
+        if self.g2_powers[0] != G2Affine::generator() {
+            return Err(Error::Verification("first G2 power is not the generator".into()));
+        }
+        if bool::from(self.g2_powers[1].is_identity()) {
+            return Err(Error::Verification("tau is zero".into()));
+        }
+        if self.g1_powers.len() < 2 {
+            return Ok(());
+        }
+
+        // e(sum r_i [tau^(i+1)] G1, G2) == e(sum r_i [tau^i] G1, [tau] G2)
+        let coeffs: Vec<Fr> = (1..self.g1_powers.len())
+            .map(|_| randomness.random_field())
+            .collect();
+        let lhs = msm_best(&coeffs, &self.g1_powers[1..]).to_affine();
+        let rhs = msm_best(&coeffs, &self.g1_powers[..self.g1_powers.len() - 1]).to_affine();
+
+        if Bn256::pairing(&lhs, &self.g2_powers[0]) != Bn256::pairing(&rhs, &self.g2_powers[1]) {
+            return Err(Error::Verification(
+                "G1 powers are not consecutive powers of tau".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+type ReadPoint<R, P> = fn(&mut R) -> Result<P>;
+
+/// Read one compressed G1 point
+pub(crate) fn read_g1_compressed<R: Read>(reader: &mut R) -> Result<G1Affine> {
+    let mut repr = <G1Affine as GroupEncoding>::Repr::default();
+    reader.read_exact(repr.as_mut())?;
+    // G1 has cofactor 1, so every decompressed point is in the prime-order group.
+    Option::from(G1Affine::from_bytes(&repr))
+        .ok_or_else(|| Error::Encoding("invalid compressed G1 point".into()))
+}
+
+/// Read one compressed G2 point
+pub(crate) fn read_g2_compressed<R: Read>(reader: &mut R) -> Result<G2Affine> {
+    let mut repr = <G2Affine as GroupEncoding>::Repr::default();
+    reader.read_exact(repr.as_mut())?;
+    let point: G2Affine = Option::from(G2Affine::from_bytes(&repr))
+        .ok_or_else(|| Error::Encoding("invalid compressed G2 point".into()))?;
+    if !is_in_g2_subgroup(&point) {
+        return Err(Error::Encoding("G2 point not in prime-order subgroup".into()));
+    }
+    Ok(point)
+}
+
+/// Read one uncompressed G1 point, big-endian `x || y`
+pub(crate) fn read_g1<R: Read>(reader: &mut R) -> Result<G1Affine> {
+    let mut bytes = [0u8; G1_UNCOMPRESSED_LEN];
+    reader.read_exact(&mut bytes)?;
+    if bytes[0] & INFINITY_FLAG != 0 {
+        return check_infinity(&bytes).map(|()| G1Affine::identity());
+    }
+
+    let x = read_fq(&bytes[..32])?;
+    let y = read_fq(&bytes[32..])?;
+    // G1 has cofactor 1, so every curve point is in the prime-order group.
+    Option::from(G1Affine::from_xy(x, y))
+        .ok_or_else(|| Error::Encoding("G1 point not on curve".into()))
+}
+
+/// Read one uncompressed G2 point, big-endian `x.c1 || x.c0 || y.c1 || y.c0`
+pub(crate) fn read_g2<R: Read>(reader: &mut R) -> Result<G2Affine> {
+    let mut bytes = [0u8; G2_UNCOMPRESSED_LEN];
+    reader.read_exact(&mut bytes)?;
+    if bytes[0] & INFINITY_FLAG != 0 {
+        return check_infinity(&bytes).map(|()| G2Affine::identity());
+    }
+
+    let x = Fq2::new(read_fq(&bytes[32..64])?, read_fq(&bytes[..32])?);
+    let y = Fq2::new(read_fq(&bytes[96..])?, read_fq(&bytes[64..96])?);
+    let point: G2Affine = Option::from(G2Affine::from_xy(x, y))
+        .ok_or_else(|| Error::Encoding("G2 point not on curve".into()))?;
+
+    // G2 has a large cofactor; reject points outside the prime-order group.
+    if !is_in_g2_subgroup(&point) {
+        return Err(Error::Encoding("G2 point not in prime-order subgroup".into()));
+    }
+    Ok(point)
+}
+
+/// Write one G1 point in the uncompressed ceremony encoding
+pub(crate) fn write_g1(point: &G1Affine, out: &mut Vec<u8>) {
+    let mut bytes = [0u8; G1_UNCOMPRESSED_LEN];
+    if bool::from(point.is_identity()) {
+        bytes[0] = INFINITY_FLAG;
+    } else {
+        let coords = point.coordinates().unwrap();
+        write_fq(coords.x(), &mut bytes[..32]);
+        write_fq(coords.y(), &mut bytes[32..]);
+    }
+    out.extend_from_slice(&bytes);
+}
+
+/// Write one G2 point in the uncompressed ceremony encoding
+#[cfg(test)]
+pub(crate) fn write_g2(point: &G2Affine, out: &mut Vec<u8>) {
+    let mut bytes = [0u8; G2_UNCOMPRESSED_LEN];
+    if bool::from(point.is_identity()) {
+        bytes[0] = INFINITY_FLAG;
+    } else {
+        let coords = point.coordinates().unwrap();
+        write_fq2(coords.x(), &mut bytes[..64]);
+        write_fq2(coords.y(), &mut bytes[64..]);
+    }
+    out.extend_from_slice(&bytes);
+}
+
+fn check_infinity(bytes: &[u8]) -> Result<()> {
+    if bytes[0] == INFINITY_FLAG && bytes[1..].iter().all(|b| *b == 0) {
+        Ok(())
+    } else {
+        Err(Error::Encoding("malformed point at infinity".into()))
+    }
+}
+
+/// Parse a big-endian base field element, rejecting non-canonical values
+fn read_fq(bytes: &[u8]) -> Result<Fq> {
+    let mut repr = [0u8; 32];
+    repr.copy_from_slice(bytes);
+    repr.reverse();
+    Option::from(Fq::from_bytes(&repr))
+        .ok_or_else(|| Error::Encoding("non-canonical base field element".into()))
+}
+
+fn write_fq(value: &Fq, out: &mut [u8]) {
+    let mut repr = value.to_bytes();
+    repr.reverse();
+    out.copy_from_slice(&repr);
+}
+
+/// Write `c1 || c0`, each big-endian
+#[cfg(test)]
+fn write_fq2(value: &Fq2, out: &mut [u8]) {
+    // `Fq2::to_bytes` is little-endian `c0 || c1`, so reversing the whole
+    // buffer yields big-endian `c1 || c0`.
+    let mut repr = value.to_bytes();
+    repr.reverse();
+    out.copy_from_slice(&repr);
+}
+
+/// Whether `point` lies in the order-`r` subgroup of the twist
+fn is_in_g2_subgroup(point: &G2Affine) -> bool {
+    G2::from(*point).is_torsion_free().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ff::Field;
+    use halo2curves::bn256::G1;
+    use zk_proof_core::DeterministicRandomness;
+
+    /// Serialize a ceremony file of power `power` from a known tau
+    fn ceremony_file(power: u32, tau: Fr) -> Vec<u8> {
+        let mut out = vec![7u8; HASH_LEN];
+
+        let mut acc = Fr::ONE;
+        for _ in 0..(1u64 << (power + 1)) - 1 {
+            write_g1(&(G1::generator() * acc).to_affine(), &mut out);
+            acc *= tau;
+        }
+
+        let mut acc = Fr::ONE;
+        for _ in 0..1u64 << power {
+            write_g2(&(G2::generator() * acc).to_affine(), &mut out);
+            acc *= tau;
+        }
+        out
+    }
+
+    #[test]
+    fn test_parse_and_verify() {
+        let file = ceremony_file(3, Fr::from(5));
+        let (srs, hash) = Srs::read_ppot(&file[..], 3, 2).unwrap();
+
+        assert_eq!(hash, [7u8; HASH_LEN]);
+        assert_eq!(srs.g1_powers().len(), 4);
+        assert_eq!(srs.g1_powers()[2], (G1::generator() * Fr::from(25)).to_affine());
+        srs.verify(&DeterministicRandomness::from_seed([1; 32])).unwrap();
+    }
+
+    #[test]
+    fn test_serialization_round_trip() {
+        let file = ceremony_file(2, Fr::from(11));
+        let (srs, _) = Srs::read_ppot(&file[..], 2, 2).unwrap();
+
+        let mut bytes = Vec::new();
+        srs.write_to(&mut bytes).unwrap();
+        let decoded = Srs::read_from(&bytes[..]).unwrap();
+
+        assert_eq!(bytes.len(), 8 + 4 * G1_COMPRESSED_LEN + 2 * G2_COMPRESSED_LEN);
+        assert_eq!(decoded.g1_powers(), srs.g1_powers());
+        assert_eq!(decoded.g2_tau(), srs.g2_tau());
+        assert!(Srs::read_from(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_legacy_uncompressed_read() {
+        let file = ceremony_file(2, Fr::from(11));
+        let (srs, _) = Srs::read_ppot(&file[..], 2, 2).unwrap();
+
+        let mut legacy = 2u32.to_le_bytes().to_vec();
+        for point in srs.g1_powers() {
+            write_g1(point, &mut legacy);
+        }
+        write_g2(&srs.g2(), &mut legacy);
+        write_g2(&srs.g2_tau(), &mut legacy);
+
+        let decoded = Srs::read_from(&legacy[..]).unwrap();
+        assert_eq!(decoded.g1_powers(), srs.g1_powers());
+        assert_eq!(decoded.g2_tau(), srs.g2_tau());
+    }
+
+    #[test]
+    fn test_compressed_g2_outside_subgroup_rejected() {
+        // Small x values give points on the twist that are almost never
+        // in the order-r subgroup.
+        let point = (1u64..)
+            .find_map(|x| {
+                let x = Fq2::new(Fq::from(x), Fq::ZERO);
+                let y2 = x.square() * x + G2Affine::b();
+                Option::<Fq2>::from(y2.sqrt()).map(|y| G2Affine { x, y })
+            })
+            .unwrap();
+        assert!(!is_in_g2_subgroup(&point));
+
+        let bytes = point.to_bytes();
+        assert!(matches!(
+            read_g2_compressed(&mut bytes.as_ref()),
+            Err(Error::Encoding(_))
+        ));
+    }
+
+    #[test]
+    fn test_inconsistent_powers_rejected() {
+        let file = ceremony_file(2, Fr::from(3));
+        let (srs, _) = Srs::read_ppot(&file[..], 2, 2).unwrap();
+
+        let mut g1 = srs.g1_powers().to_vec();
+        g1[3] = (G1::generator() * Fr::from(28)).to_affine();
+        let tampered = Srs::from_parts(2, g1, [srs.g2(), srs.g2_tau()]).unwrap();
+
+        assert!(tampered
+            .verify(&DeterministicRandomness::from_seed([2; 32]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_size_and_encoding_errors() {
+        let file = ceremony_file(2, Fr::from(3));
+        assert!(matches!(
+            Srs::read_ppot(&file[..], 2, 3),
+            Err(Error::ParamsMismatch { .. })
+        ));
+        assert!(Srs::read_ppot(&file[..HASH_LEN + 100], 2, 1).is_err());
+
+        let mut corrupted = file.clone();
+        corrupted[HASH_LEN + G1_UNCOMPRESSED_LEN - 1] ^= 1;
+        assert!(Srs::read_ppot(&corrupted[..], 2, 2).is_err());
+    }
+}
@@ -7,14 +7,31 @@
 #![warn(clippy::all)]
 #![warn(clippy::pedantic)]
 
+pub mod encoding;
+pub mod folding;
+pub mod generators;
+#[cfg(feature = "kzg")]
+pub mod kzg;
+pub mod merkle;
 pub mod pedersen;
 pub mod polynomial;
 pub mod traits;
+pub mod vector;
 
-pub use traits::{Commitment, CommitmentScheme};
+pub use encoding::{point_from_bytes, CanonicalEncoding};
+pub use generators::{derive_generator, derive_generators};
+pub use traits::{CommitmentScheme, CommitmentVerifier, Committer, VectorCommitment};
 
 /// Re-export commonly used types
 pub mod prelude {
-    pub use super::pedersen::PedersenCommitment;
-    pub use super::traits::{Commitment, CommitmentScheme};
+    pub use super::encoding::CanonicalEncoding;
+    pub use super::folding::{FoldingVerifier, R1CSShape, RelaxedR1CS};
+    pub use super::merkle::MerkleScheme;
+    pub use super::pedersen::{
+        PedersenCommitment, PedersenDecommitment, PedersenGenerators, PedersenOpening,
+        PedersenScheme,
+    };
+    pub use super::polynomial::{IpaScheme, PolynomialCommitment};
+    pub use super::traits::{CommitmentScheme, CommitmentVerifier, Committer, VectorCommitment};
+    pub use super::vector::VectorPedersen;
 }
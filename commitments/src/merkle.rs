@@ -0,0 +1,299 @@
+//! SHA-256 Merkle tree vector commitment
+
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use sha2::{Digest, Sha256};
+use zk_proof_core::{Error, Randomness, Result};
+
+use crate::{
+    encoding::{write_count, CanonicalEncoding, Reader},
+    traits::VectorCommitment,
+};
+
+/// SHA-256 digest
+pub type Hash = [u8; 32];
+
+const LEAF_TAG: u8 = 0x00;
+const NODE_TAG: u8 = 0x01;
+const EMPTY_LEAF: Hash = [0u8; 32];
+
+/// Deepest tree accepted when decoding a path
+const MAX_DEPTH: usize = 64;
+
+/// Root of a Merkle vector commitment
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MerkleRoot {
+    /// Root digest of the padded tree
+    pub root: Hash,
+    /// Number of committed entries
+    pub len: usize,
+}
+
+/// Full tree kept by the committer
+#[derive(Clone, Debug)]
+pub struct MerkleTree {
+    len: usize,
+    /// Layers from leaves (index 0) up to the root
+    layers: Vec<Vec<Hash>>,
+}
+
+impl MerkleTree {
+    fn root(&self) -> Hash {
+        self.layers.last().map_or(EMPTY_LEAF, |layer| layer[0])
+    }
+
+    fn path(&self, index: usize) -> MerklePath {
+        let siblings = self.layers[..self.layers.len() - 1]
+            .iter()
+            .enumerate()
+            .map(|(level, layer)| layer[(index >> level) ^ 1])
+            .collect();
+        MerklePath { index, siblings }
+    }
+}
+
+/// Authentication path for one leaf
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerklePath {
+    /// Leaf position
+    pub index: usize,
+    /// Sibling digests from the leaf level upwards
+    pub siblings: Vec<Hash>,
+}
+
+/// Authentication paths for a set of leaves, in the order they were opened
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleOpening {
+    /// One path per opened position
+    pub paths: Vec<MerklePath>,
+}
+
+impl CanonicalEncoding for MerkleRoot {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = self.root.to_vec();
+        out.extend_from_slice(&(self.len as u64).to_le_bytes());
+        out
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes)?;
+        let root = reader.array()?;
+        let len = reader.usize()?;
+        reader.finish()?;
+        Ok(Self { root, len })
+    }
+}
+
+impl CanonicalEncoding for MerkleOpening {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_count(&mut out, self.paths.len());
+        for path in &self.paths {
+            out.extend_from_slice(&(path.index as u64).to_le_bytes());
+            write_count(&mut out, path.siblings.len());
+            for sibling in &path.siblings {
+                out.extend_from_slice(sibling);
+            }
+        }
+        out
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes)?;
+
+        let count = reader.count(12)?;
+        let mut paths = Vec::with_capacity(count);
+        for _ in 0..count {
+            let index = reader.usize()?;
+            let depth = reader.count(32)?;
+            if depth > MAX_DEPTH {
+                return Err(Error::Encoding(format!(
+                    "path depth {depth} exceeds {MAX_DEPTH}"
+                )));
+            }
+            let siblings = (0..depth)
+                .map(|_| reader.array())
+                .collect::<Result<_>>()?;
+            paths.push(MerklePath { index, siblings });
+        }
+        reader.finish()?;
+
+        Ok(Self { paths })
+    }
+}
+
+/// Merkle tree vector commitment over canonical field encodings
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MerkleScheme<F: PrimeField> {
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> MerkleScheme<F> {
+    /// Create the scheme
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+
+    /// Build the full tree for a vector
+    #[must_use]
+    pub fn build(&self, values: &[F]) -> MerkleTree {
+        let width = values.len().next_power_of_two();
+        let mut leaves: Vec<Hash> = values.iter().map(hash_leaf).collect();
+        leaves.resize(width, EMPTY_LEAF);
+
+        let mut layers = vec![leaves];
+        while layers.last().map_or(0, Vec::len) > 1 {
+            let next = layers
+                .last()
+                .map(|layer| {
+                    layer
+                        .chunks(2)
+                        .map(|pair| hash_node(&pair[0], &pair[1]))
+                        .collect()
+                })
+                .unwrap_or_default();
+            layers.push(next);
+        }
+
+        MerkleTree {
+            len: values.len(),
+            layers,
+        }
+    }
+}
+
+impl<F: PrimeField> VectorCommitment for MerkleScheme<F> {
+    type Element = F;
+    type Commitment = MerkleRoot;
+    type Witness = MerkleTree;
+    type Opening = MerkleOpening;
+
+    fn commit<R: Randomness>(&self, values: &[F], _randomness: &R) -> (MerkleRoot, MerkleTree) {
+        let tree = self.build(values);
+        let root = MerkleRoot {
+            root: tree.root(),
+            len: tree.len,
+        };
+        (root, tree)
+    }
+
+    fn open_batch<R: Randomness>(
+        &self,
+        witness: &MerkleTree,
+        indices: &[usize],
+        _randomness: &R,
+    ) -> Option<MerkleOpening> {
+        if indices.is_empty() || indices.iter().any(|&index| index >= witness.len) {
+            return None;
+        }
+
+        Some(MerkleOpening {
+            paths: indices.iter().map(|&index| witness.path(index)).collect(),
+        })
+    }
+
+    fn verify_batch(
+        &self,
+        commitment: &MerkleRoot,
+        entries: &[(usize, &F)],
+        opening: &MerkleOpening,
+    ) -> bool {
+        let depth = commitment.len.next_power_of_two().trailing_zeros() as usize;
+
+        !entries.is_empty()
+            && entries.len() == opening.paths.len()
+            && entries
+                .iter()
+                .zip(&opening.paths)
+                .all(|((index, value), path)| {
+                    *index == path.index
+                        && *index < commitment.len
+                        && path.siblings.len() == depth
+                        && root_from_path(hash_leaf(*value), path) == commitment.root
+                })
+    }
+}
+
+fn root_from_path(leaf: Hash, path: &MerklePath) -> Hash {
+    path.siblings
+        .iter()
+        .enumerate()
+        .fold(leaf, |node, (level, sibling)| {
+            if (path.index >> level) & 1 == 0 {
+                hash_node(&node, sibling)
+            } else {
+                hash_node(sibling, &node)
+            }
+        })
+}
+
+fn hash_leaf<F: PrimeField>(value: &F) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_TAG]);
+    hasher.update(value.to_repr().as_ref());
+    hasher.finalize().into()
+}
+
+fn hash_node(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_TAG]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pasta_curves::Fp;
+    use zk_proof_core::OsRandomness;
+
+    fn values(n: u64) -> Vec<Fp> {
+        (0..n).map(|i| Fp::from(i * 3 + 1)).collect()
+    }
+
+    #[test]
+    fn test_open_each_position() {
+        let scheme = MerkleScheme::new();
+        let values = values(5);
+        let (root, tree) = scheme.commit(&values, &OsRandomness);
+
+        for (index, value) in values.iter().enumerate() {
+            let opening = scheme.open(&tree, index, &OsRandomness).unwrap();
+            assert!(scheme.verify(&root, index, value, &opening));
+            assert!(!scheme.verify(&root, index, &Fp::from(999), &opening));
+        }
+        assert!(scheme.open(&tree, 5, &OsRandomness).is_none());
+    }
+
+    #[test]
+    fn test_encoding_round_trip() {
+        let scheme = MerkleScheme::new();
+        let values = values(6);
+        let (root, tree) = scheme.commit(&values, &OsRandomness);
+        let opening = scheme.open_batch(&tree, &[0, 5], &OsRandomness).unwrap();
+
+        let root = MerkleRoot::from_bytes(&root.to_bytes()).unwrap();
+        let bytes = opening.to_bytes();
+        let opening = MerkleOpening::from_bytes(&bytes).unwrap();
+        assert!(scheme.verify_batch(&root, &[(0, &values[0]), (5, &values[5])], &opening));
+
+        assert!(MerkleOpening::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(MerkleRoot::from_bytes(&[0u8; 39]).is_err());
+    }
+
+    #[test]
+    fn test_batch_opening() {
+        let scheme = MerkleScheme::new();
+        let values = values(8);
+        let (root, tree) = scheme.commit(&values, &OsRandomness);
+
+        let opening = scheme.open_batch(&tree, &[1, 6], &OsRandomness).unwrap();
+        assert!(scheme.verify_batch(&root, &[(1, &values[1]), (6, &values[6])], &opening));
+        assert!(!scheme.verify_batch(&root, &[(6, &values[6]), (1, &values[1])], &opening));
+    }
+}
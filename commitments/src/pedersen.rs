@@ -1,6 +1,23 @@
 //! Pedersen commitment implementation
+//!
+//! [`PedersenGenerators`] draws its own blinding factors through the
+//! [`Committer`] role. [`PedersenScheme`] implements [`CommitmentScheme`]
+//! instead, where the caller supplies the blinding, and checks openings in
+//! constant time. Commitments and openings of either kind add and scale
+//! homomorphically: the sum of two commitments is opened by the sum of their
+//! openings.
 
-use group::Group;
+use ff::PrimeField;
+use group::{cofactor::CofactorGroup, Group};
+use halo2_proofs::arithmetic::CurveExt;
+use subtle::ConstantTimeEq;
+use zk_proof_core::{Randomness, Result};
+
+use crate::{
+    encoding::{CanonicalEncoding, Reader},
+    generators::{derive_generator, BLINDING_LABEL, PEDERSEN_DOMAIN, VALUE_LABEL},
+    traits::{CommitmentScheme, CommitmentVerifier, Committer},
+};
 
 /// Pedersen commitment structure
 #[derive(Clone, Debug)]
@@ -14,4 +31,334 @@ impl<G: Group> PedersenCommitment<G> {
     pub fn new(value: G) -> Self {
         Self { value }
     }
+
+    /// Commit to `value` as `value * g + r * h` with a fresh blinding factor
+    ///
+    /// The blinding factor `r` is drawn from `randomness` and returned so the
+    /// commitment can be opened later.
+    pub fn commit_with<R: Randomness>(
+        g: G,
+        h: G,
+        value: G::Scalar,
+        randomness: &R,
+    ) -> (Self, G::Scalar) {
+        let (commitment, opening) = PedersenGenerators::new(g, h).commit(&value, randomness);
+        (commitment, opening.blinding)
+    }
+
+    /// Commitment to the sum of the two committed values
+    #[must_use]
+    pub fn add(&self, other: &Self) -> Self {
+        Self::new(self.value + other.value)
+    }
+
+    /// Commitment to the committed value multiplied by a public scalar
+    #[must_use]
+    pub fn scale(&self, factor: G::Scalar) -> Self {
+        Self::new(self.value * factor)
+    }
+}
+
+impl<G: CofactorGroup> CanonicalEncoding for PedersenCommitment<G> {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.value.to_bytes().as_ref().to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes)?;
+        let value = reader.point()?;
+        reader.finish()?;
+        Ok(Self::new(value))
+    }
+}
+
+/// Opening of a Pedersen commitment
+#[derive(Clone, Debug)]
+pub struct PedersenOpening<G: Group> {
+    /// Blinding factor `r`
+    pub blinding: G::Scalar,
+}
+
+impl<G: Group> CanonicalEncoding for PedersenOpening<G> {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.blinding.to_repr().as_ref().to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes)?;
+        let blinding = reader.scalar()?;
+        reader.finish()?;
+        Ok(Self { blinding })
+    }
+}
+
+/// Public Pedersen parameters: value generator `g` and blinding generator `h`
+///
+/// Acts as both [`Committer`] and [`CommitmentVerifier`], since neither role
+/// needs anything beyond the generators.
+#[derive(Clone, Debug)]
+pub struct PedersenGenerators<G: Group> {
+    /// Generator for the committed value
+    pub g: G,
+    /// Generator for the blinding factor
+    pub h: G,
+}
+
+impl<G: Group> PedersenGenerators<G> {
+    /// Create parameters from two generators with unknown discrete log relation
+    pub fn new(g: G, h: G) -> Self {
+        Self { g, h }
+    }
+}
+
+impl<G: CurveExt> PedersenGenerators<G> {
+    /// Standard generators derived by hash-to-curve under [`PEDERSEN_DOMAIN`]
+    #[must_use]
+    pub fn derive() -> Self {
+        Self::new(
+            derive_generator(PEDERSEN_DOMAIN, VALUE_LABEL),
+            derive_generator(PEDERSEN_DOMAIN, BLINDING_LABEL),
+        )
+    }
+}
+
+impl<G: Group> Committer for PedersenGenerators<G> {
+    type Value = G::Scalar;
+    type Commitment = PedersenCommitment<G>;
+    type Opening = PedersenOpening<G>;
+
+    fn commit<R: Randomness>(
+        &self,
+        value: &Self::Value,
+        randomness: &R,
+    ) -> (Self::Commitment, Self::Opening) {
+        let blinding = randomness.random_field::<G::Scalar>();
+        let commitment = PedersenCommitment::new(self.g * value + self.h * blinding);
+        (commitment, PedersenOpening { blinding })
+    }
+}
+
+impl<G: Group> CommitmentVerifier for PedersenGenerators<G> {
+    type Value = G::Scalar;
+    type Commitment = PedersenCommitment<G>;
+    type Opening = PedersenOpening<G>;
+
+    fn verify(
+        &self,
+        commitment: &Self::Commitment,
+        value: &Self::Value,
+        opening: &Self::Opening,
+    ) -> bool {
+        commitment.value == self.g * value + self.h * opening.blinding
+    }
+}
+
+/// Value and blinding factor revealed to open a [`PedersenScheme`]
+/// commitment
+#[derive(Clone, Debug)]
+pub struct PedersenDecommitment<G: Group> {
+    /// Committed value
+    pub value: G::Scalar,
+    /// Blinding factor `r`
+    pub blinding: G::Scalar,
+}
+
+impl<G: Group> PedersenDecommitment<G> {
+    /// Opening of the sum of the two committed values
+    #[must_use]
+    pub fn add(&self, other: &Self) -> Self {
+        Self {
+            value: self.value + other.value,
+            blinding: self.blinding + other.blinding,
+        }
+    }
+
+    /// Opening of the committed value multiplied by a public scalar
+    #[must_use]
+    pub fn scale(&self, factor: G::Scalar) -> Self {
+        Self {
+            value: self.value * factor,
+            blinding: self.blinding * factor,
+        }
+    }
+}
+
+impl<G: Group> CanonicalEncoding for PedersenDecommitment<G> {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = self.value.to_repr().as_ref().to_vec();
+        out.extend_from_slice(self.blinding.to_repr().as_ref());
+        out
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes)?;
+        let value = reader.scalar()?;
+        let blinding = reader.scalar()?;
+        reader.finish()?;
+        Ok(Self { value, blinding })
+    }
+}
+
+/// Pedersen commitments `value * g + blinding * h` with caller-supplied
+/// blinding
+#[derive(Clone, Debug)]
+pub struct PedersenScheme<G: Group> {
+    generators: PedersenGenerators<G>,
+}
+
+impl<G: Group> PedersenScheme<G> {
+    /// Scheme over existing generators
+    pub fn new(generators: PedersenGenerators<G>) -> Self {
+        Self { generators }
+    }
+
+    /// Generators the scheme commits with
+    pub fn generators(&self) -> &PedersenGenerators<G> {
+        &self.generators
+    }
+}
+
+impl<G: CurveExt> PedersenScheme<G> {
+    /// Scheme over the standard generators from [`PedersenGenerators::derive`]
+    #[must_use]
+    pub fn setup() -> Self {
+        Self::new(PedersenGenerators::derive())
+    }
+}
+
+impl<G: Group + ConstantTimeEq> CommitmentScheme for PedersenScheme<G> {
+    type Value = G::Scalar;
+    type Blinding = G::Scalar;
+    type Commitment = PedersenCommitment<G>;
+    type Opening = PedersenDecommitment<G>;
+
+    fn random_blinding<R: Randomness>(&self, randomness: &R) -> G::Scalar {
+        randomness.random_field()
+    }
+
+    fn commit(&self, value: &G::Scalar, blinding: &G::Scalar) -> PedersenCommitment<G> {
+        PedersenCommitment::new(self.generators.g * value + self.generators.h * blinding)
+    }
+
+    fn open(&self, value: &G::Scalar, blinding: &G::Scalar) -> PedersenDecommitment<G> {
+        PedersenDecommitment {
+            value: *value,
+            blinding: *blinding,
+        }
+    }
+
+    /// Recompute the commitment and compare it in constant time
+    fn verify(
+        &self,
+        commitment: &PedersenCommitment<G>,
+        opening: &PedersenDecommitment<G>,
+    ) -> bool {
+        let expected = self.commit(&opening.value, &opening.blinding);
+        commitment.value.ct_eq(&expected.value).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pasta_curves::pallas;
+    use zk_proof_core::{DeterministicRandomness, OsRandomness};
+
+    fn generators() -> PedersenGenerators<pallas::Point> {
+        PedersenGenerators::derive()
+    }
+
+    #[test]
+    fn test_commit_with_injected_randomness() {
+        let PedersenGenerators { g, h } = generators();
+        let value = pallas::Scalar::from(42);
+
+        let (a, r_a) =
+            PedersenCommitment::commit_with(g, h, value, &DeterministicRandomness::from_seed([1; 32]));
+        let (b, r_b) =
+            PedersenCommitment::commit_with(g, h, value, &DeterministicRandomness::from_seed([1; 32]));
+
+        assert_eq!(a.value, b.value);
+        assert_eq!(r_a, r_b);
+        assert_eq!(a.value, g * value + h * r_a);
+    }
+
+    #[test]
+    fn test_third_party_verification() {
+        let value = pallas::Scalar::from(7);
+        let (commitment, opening) = generators().commit(&value, &OsRandomness);
+
+        // A verifier built independently from the same public generators.
+        let verifier = generators();
+        assert!(verifier.verify(&commitment, &value, &opening));
+        assert!(!verifier.verify(&commitment, &pallas::Scalar::from(8), &opening));
+    }
+
+    fn scheme() -> PedersenScheme<pallas::Point> {
+        PedersenScheme::setup()
+    }
+
+    #[test]
+    fn test_scheme_open_and_verify() {
+        let scheme = scheme();
+        let value = pallas::Scalar::from(11);
+        let blinding = scheme.random_blinding(&OsRandomness);
+        let commitment = scheme.commit(&value, &blinding);
+
+        assert!(scheme.verify(&commitment, &scheme.open(&value, &blinding)));
+        assert!(!scheme.verify(
+            &commitment,
+            &scheme.open(&pallas::Scalar::from(12), &blinding)
+        ));
+        assert!(!scheme.verify(
+            &commitment,
+            &scheme.open(&value, &(blinding + pallas::Scalar::from(1)))
+        ));
+
+        // The scheme agrees with the randomized committer
+        let (committed, opening) = generators().commit(&value, &OsRandomness);
+        assert_eq!(
+            scheme.commit(&value, &opening.blinding).value,
+            committed.value
+        );
+    }
+
+    #[test]
+    fn test_scheme_is_homomorphic() {
+        let scheme = scheme();
+        let rng = DeterministicRandomness::from_seed([2; 32]);
+        let (a, b) = (pallas::Scalar::from(20), pallas::Scalar::from(22));
+        let (r_a, r_b) = (scheme.random_blinding(&rng), scheme.random_blinding(&rng));
+        let opening_a = scheme.open(&a, &r_a);
+        let opening_b = scheme.open(&b, &r_b);
+
+        let sum = scheme.commit(&a, &r_a).add(&scheme.commit(&b, &r_b));
+        let sum_opening = opening_a.add(&opening_b);
+        assert_eq!(sum_opening.value, pallas::Scalar::from(42));
+        assert!(scheme.verify(&sum, &sum_opening));
+
+        let factor = pallas::Scalar::from(3);
+        let scaled = scheme.commit(&a, &r_a).scale(factor);
+        assert!(scheme.verify(&scaled, &opening_a.scale(factor)));
+        assert!(!scheme.verify(&scaled, &opening_a));
+    }
+
+    #[test]
+    fn test_encoding_round_trip() {
+        let value = pallas::Scalar::from(9);
+        let (commitment, opening) = generators().commit(&value, &OsRandomness);
+
+        let commitment = PedersenCommitment::from_bytes(&commitment.to_bytes()).unwrap();
+        let opening = PedersenOpening::from_bytes(&opening.to_bytes()).unwrap();
+        assert!(generators().verify(&commitment, &value, &opening));
+
+        let mut bytes = commitment.to_bytes();
+        bytes.push(0);
+        assert!(PedersenCommitment::<pallas::Point>::from_bytes(&bytes).is_err());
+        assert!(PedersenOpening::<pallas::Point>::from_bytes(&[0xff; 32]).is_err());
+
+        let decommitment = scheme().open(&value, &opening.blinding);
+        let decoded = PedersenDecommitment::from_bytes(&decommitment.to_bytes()).unwrap();
+        assert!(scheme().verify(&commitment, &decoded));
+    }
 }
@@ -1,15 +1,515 @@
 //! Polynomial commitment schemes
 
-/// Polynomial commitment structure
-#[derive(Clone, Debug)]
-pub struct PolynomialCommitment {
-    /// Commitment data
-    pub data: Vec<u8>,
+use ff::{FromUniformBytes, PrimeField, WithSmallOrderMulGroup};
+use group::{Curve, GroupEncoding};
+use halo2_proofs::{
+    arithmetic::{eval_polynomial, CurveAffine},
+    poly::{
+        commitment::{Blind, Params},
+        multiopen::{self, ProverQuery, VerifierQuery},
+        Coeff, EvaluationDomain, Polynomial,
+    },
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255, Transcript},
+};
+use zk_proof_core::{msm, Randomness, Result};
+
+use crate::encoding::{point_from_bytes, scalar_len, write_count, CanonicalEncoding, Reader};
+
+/// Commitment to a polynomial, as sent to a verifier
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PolynomialCommitment<C: CurveAffine> {
+    /// Commitment point
+    pub point: C,
 }
 
-impl PolynomialCommitment {
+impl<C: CurveAffine> PolynomialCommitment<C> {
     /// Create a new polynomial commitment
-    #[must_use] pub fn new(data: Vec<u8>) -> Self {
-        Self { data }
+    #[must_use]
+    pub fn new(point: C) -> Self {
+        Self { point }
+    }
+}
+
+impl<C: CurveAffine> CanonicalEncoding for PolynomialCommitment<C> {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.point.to_bytes().as_ref().to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        point_from_bytes(bytes).map(Self::new)
+    }
+}
+
+/// A polynomial held by the prover together with its commitment blind
+#[derive(Clone, Debug)]
+pub struct CommittedPolynomial<C: CurveAffine> {
+    poly: Polynomial<C::Scalar, Coeff>,
+    blind: Blind<C::Scalar>,
+    commitment: C,
+}
+
+impl<C: CurveAffine> CommittedPolynomial<C> {
+    /// Commitment to the polynomial
+    pub fn commitment(&self) -> C {
+        self.commitment
+    }
+
+    /// Commitment in the form sent to a verifier
+    pub fn to_commitment(&self) -> PolynomialCommitment<C> {
+        PolynomialCommitment::new(self.commitment)
+    }
+
+    /// Evaluate the polynomial at `point`
+    pub fn evaluate(&self, point: C::Scalar) -> C::Scalar {
+        eval_polynomial(&self.poly, point)
+    }
+
+    /// Sum of two committed polynomials
+    ///
+    /// The commitment of the result is the sum of the two commitments, so a
+    /// verifier can derive it without seeing either polynomial.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the polynomials were committed under different parameters.
+    #[must_use]
+    pub fn add(&self, other: &Self) -> Self {
+        assert_eq!(
+            self.poly.len(),
+            other.poly.len(),
+            "polynomials committed under different parameters"
+        );
+
+        Self {
+            poly: self.poly.clone() + &other.poly,
+            blind: self.blind + other.blind,
+            commitment: (self.commitment + other.commitment).to_affine(),
+        }
+    }
+
+    /// Multiply a committed polynomial by a public scalar
+    #[must_use]
+    pub fn scale(&self, factor: C::Scalar) -> Self {
+        Self {
+            poly: self.poly.clone() * factor,
+            blind: Blind(self.blind.0 * factor),
+            commitment: (self.commitment * factor).to_affine(),
+        }
+    }
+
+    /// Linear combination `sum(c_i * p_i)` of committed polynomials
+    ///
+    /// Returns `None` if `terms` is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the polynomials were committed under different parameters.
+    pub fn linear_combination(terms: &[(C::Scalar, &Self)]) -> Option<Self> {
+        let ((first_factor, first), rest) = terms.split_first()?;
+        Some(
+            rest.iter()
+                .fold(first.scale(*first_factor), |acc, (factor, poly)| {
+                    acc.add(&poly.scale(*factor))
+                }),
+        )
+    }
+}
+
+/// Commitment to `sum(c_i * p_i)` computed from the commitments to `p_i`
+pub fn combine_commitments<C: CurveAffine>(terms: &[(C::Scalar, C)]) -> C {
+    let (factors, bases): (Vec<_>, Vec<_>) = terms.iter().copied().unzip();
+    msm(&factors, &bases).to_affine()
+}
+
+/// Evaluations of one committed polynomial at many points, with one proof
+#[derive(Clone, Debug)]
+pub struct BatchOpening<C: CurveAffine> {
+    /// Evaluation points
+    pub points: Vec<C::Scalar>,
+    /// Claimed evaluations, one per point
+    pub evals: Vec<C::Scalar>,
+    /// Aggregated multi-point opening proof
+    pub proof: Vec<u8>,
+}
+
+impl<C: CurveAffine> CanonicalEncoding for BatchOpening<C> {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_count(&mut out, self.points.len());
+        for (point, eval) in self.points.iter().zip(&self.evals) {
+            out.extend_from_slice(point.to_repr().as_ref());
+            out.extend_from_slice(eval.to_repr().as_ref());
+        }
+        write_count(&mut out, self.proof.len());
+        out.extend_from_slice(&self.proof);
+        out
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes)?;
+
+        let count = reader.count(2 * scalar_len::<C::Scalar>())?;
+        let mut points = Vec::with_capacity(count);
+        let mut evals = Vec::with_capacity(count);
+        for _ in 0..count {
+            points.push(reader.scalar()?);
+            evals.push(reader.scalar()?);
+        }
+
+        let proof_len = reader.count(1)?;
+        let proof = reader.take(proof_len)?.to_vec();
+        reader.finish()?;
+
+        Ok(Self {
+            points,
+            evals,
+            proof,
+        })
+    }
+}
+
+/// Inner-product-argument polynomial commitments over halo2 parameters
+///
+/// Openings at several points are aggregated with halo2's multi-open
+/// argument, so the verifier runs a single inner product check no matter how
+/// many points are queried.
+#[derive(Clone, Debug)]
+pub struct IpaScheme<C: CurveAffine> {
+    params: Params<C>,
+    domain: EvaluationDomain<C::Scalar>,
+}
+
+impl<C: CurveAffine> IpaScheme<C>
+where
+    C::Scalar: WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
+{
+    /// Set up parameters for polynomials with fewer than `2^k` coefficients
+    #[must_use]
+    pub fn setup(k: u32) -> Self {
+        Self::from_params(Params::new(k))
+    }
+
+    /// Use existing parameters
+    pub fn from_params(params: Params<C>) -> Self {
+        let domain = EvaluationDomain::new(2, params.k());
+        Self { params, domain }
+    }
+
+    /// Maximum number of coefficients a committed polynomial may have
+    pub fn max_coefficients(&self) -> usize {
+        1 << self.params.k()
+    }
+
+    /// Commit to a polynomial given by its coefficients, lowest degree first
+    ///
+    /// # Panics
+    ///
+    /// Panics if `coeffs` has more than [`Self::max_coefficients`] entries.
+    pub fn commit<R: Randomness>(
+        &self,
+        coeffs: &[C::Scalar],
+        randomness: &R,
+    ) -> CommittedPolynomial<C> {
+        assert!(
+            coeffs.len() <= self.max_coefficients(),
+            "polynomial has {} coefficients, params support {}",
+            coeffs.len(),
+            self.max_coefficients()
+        );
+
+        let mut poly = self.domain.empty_coeff();
+        poly[..][..coeffs.len()].copy_from_slice(coeffs);
+
+        let blind = Blind(randomness.random_field());
+        let commitment = self.params.commit(&poly, blind).to_affine();
+
+        CommittedPolynomial {
+            poly,
+            blind,
+            commitment,
+        }
+    }
+
+    /// Open a committed polynomial at one point
+    ///
+    /// Returns the evaluation and the opening proof.
+    ///
+    /// # Panics
+    ///
+    /// Panics if writing to the in-memory transcript fails.
+    pub fn open<R: Randomness>(
+        &self,
+        poly: &CommittedPolynomial<C>,
+        point: C::Scalar,
+        randomness: &R,
+    ) -> (C::Scalar, Vec<u8>) {
+        let mut opening = self.open_at_points(poly, &[point], randomness);
+        (opening.evals[0], std::mem::take(&mut opening.proof))
+    }
+
+    /// Verify that the polynomial committed by `commitment` evaluates to
+    /// `eval` at `point`
+    pub fn verify(
+        &self,
+        commitment: &PolynomialCommitment<C>,
+        point: C::Scalar,
+        eval: C::Scalar,
+        proof: &[u8],
+    ) -> bool {
+        self.verify_claims(commitment.point, &[point], &[eval], proof)
+    }
+
+    /// Open a committed polynomial at several points with one proof
+    ///
+    /// # Panics
+    ///
+    /// Panics if writing to the in-memory transcript fails.
+    pub fn open_at_points<R: Randomness>(
+        &self,
+        poly: &CommittedPolynomial<C>,
+        points: &[C::Scalar],
+        randomness: &R,
+    ) -> BatchOpening<C> {
+        let evals: Vec<_> = points.iter().map(|point| poly.evaluate(*point)).collect();
+
+        let mut transcript = Blake2bWrite::<_, C, Challenge255<C>>::init(vec![]);
+        absorb_claims(&mut transcript, poly.commitment, points, &evals)
+            .expect("in-memory transcript");
+
+        let queries = points.iter().map(|point| ProverQuery {
+            point: *point,
+            poly: &poly.poly,
+            blind: poly.blind,
+        });
+        multiopen::create_proof(&self.params, randomness.rng(), &mut transcript, queries)
+            .expect("in-memory transcript");
+
+        BatchOpening {
+            points: points.to_vec(),
+            evals,
+            proof: transcript.finalize(),
+        }
+    }
+
+    /// Open the linear combination `sum(c_i * p_i)` at several points
+    ///
+    /// The verifier checks the result with [`Self::verify_combination`] using
+    /// only the individual commitments and the same factors.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `terms` is empty or the polynomials were committed under
+    /// different parameters.
+    pub fn open_combination<R: Randomness>(
+        &self,
+        terms: &[(C::Scalar, &CommittedPolynomial<C>)],
+        points: &[C::Scalar],
+        randomness: &R,
+    ) -> BatchOpening<C> {
+        let combined =
+            CommittedPolynomial::linear_combination(terms).expect("at least one polynomial");
+        self.open_at_points(&combined, points, randomness)
+    }
+
+    /// Verify an opening of `sum(c_i * p_i)` given the commitments to `p_i`
+    pub fn verify_combination(&self, terms: &[(C::Scalar, C)], opening: &BatchOpening<C>) -> bool {
+        if terms.is_empty() {
+            return false;
+        }
+        self.verify_at_points(&combine_commitments(terms), opening)
+    }
+
+    /// Verify a multi-point opening against a commitment
+    pub fn verify_at_points(&self, commitment: &C, opening: &BatchOpening<C>) -> bool {
+        self.verify_claims(*commitment, &opening.points, &opening.evals, &opening.proof)
+    }
+
+    fn verify_claims(
+        &self,
+        commitment: C,
+        points: &[C::Scalar],
+        evals: &[C::Scalar],
+        proof: &[u8],
+    ) -> bool {
+        if points.len() != evals.len() || points.is_empty() {
+            return false;
+        }
+
+        let mut transcript = Blake2bRead::<_, C, Challenge255<C>>::init(proof);
+        if absorb_claims(&mut transcript, commitment, points, evals).is_err() {
+            return false;
+        }
+
+        let queries = points
+            .iter()
+            .zip(evals)
+            .map(|(point, eval)| VerifierQuery::new_commitment(&commitment, *point, *eval));
+
+        multiopen::verify_proof(
+            &self.params,
+            &mut transcript,
+            queries,
+            self.params.empty_msm(),
+        )
+        .is_ok_and(|guard| guard.use_challenges().eval())
+    }
+}
+
+/// Bind the commitment and claimed evaluations into the transcript
+fn absorb_claims<C: CurveAffine, T: Transcript<C, Challenge255<C>>>(
+    transcript: &mut T,
+    commitment: C,
+    points: &[C::Scalar],
+    evals: &[C::Scalar],
+) -> std::io::Result<()>
+where
+    C::Scalar: FromUniformBytes<64>,
+{
+    transcript.common_point(commitment)?;
+    for (point, eval) in points.iter().zip(evals) {
+        transcript.common_scalar(*point)?;
+        transcript.common_scalar(*eval)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ff::Field;
+    use halo2_proofs::pasta::{EqAffine, Fp};
+    use zk_proof_core::DeterministicRandomness;
+
+    fn scheme() -> IpaScheme<EqAffine> {
+        IpaScheme::setup(4)
+    }
+
+    #[test]
+    fn test_multi_point_opening() {
+        let scheme = scheme();
+        let rng = DeterministicRandomness::from_seed([3; 32]);
+
+        // p(X) = 1 + 2X + 3X^2
+        let coeffs = [Fp::from(1), Fp::from(2), Fp::from(3)];
+        let poly = scheme.commit(&coeffs, &rng);
+
+        let points = [Fp::from(0), Fp::from(1), Fp::from(5)];
+        let opening = scheme.open_at_points(&poly, &points, &rng);
+        assert_eq!(opening.evals, vec![Fp::from(1), Fp::from(6), Fp::from(86)]);
+
+        assert!(scheme.verify_at_points(&poly.commitment(), &opening));
+    }
+
+    #[test]
+    fn test_single_point_opening() {
+        let scheme = scheme();
+        let rng = DeterministicRandomness::from_seed([9; 32]);
+
+        // p(X) = 4 + X^3
+        let poly = scheme.commit(&[Fp::from(4), Fp::ZERO, Fp::ZERO, Fp::ONE], &rng);
+        let (eval, proof) = scheme.open(&poly, Fp::from(2), &rng);
+        assert_eq!(eval, Fp::from(12));
+
+        let commitment =
+            PolynomialCommitment::from_bytes(&poly.to_commitment().to_bytes()).unwrap();
+        assert!(scheme.verify(&commitment, Fp::from(2), eval, &proof));
+        assert!(!scheme.verify(&commitment, Fp::from(3), eval, &proof));
+        assert!(!scheme.verify(&commitment, Fp::from(2), eval + Fp::ONE, &proof));
+        assert!(!scheme.verify(&commitment, Fp::from(2), eval, &proof[1..]));
+    }
+
+    #[test]
+    fn test_batch_opening_encoding() {
+        let scheme = scheme();
+        let rng = DeterministicRandomness::from_seed([8; 32]);
+
+        let poly = scheme.commit(&[Fp::from(4), Fp::from(5)], &rng);
+        let opening = scheme.open_at_points(&poly, &[Fp::from(1), Fp::from(2)], &rng);
+
+        let bytes = opening.to_bytes();
+        let decoded = BatchOpening::<EqAffine>::from_bytes(&bytes).unwrap();
+        assert!(scheme.verify_at_points(&poly.commitment(), &decoded));
+
+        assert!(BatchOpening::<EqAffine>::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        let mut extended = bytes.clone();
+        extended.push(0);
+        assert!(BatchOpening::<EqAffine>::from_bytes(&extended).is_err());
+    }
+
+    #[test]
+    fn test_wrong_evaluation_rejected() {
+        let scheme = scheme();
+        let rng = DeterministicRandomness::from_seed([4; 32]);
+
+        let poly = scheme.commit(&[Fp::from(9), Fp::from(8)], &rng);
+        let mut opening = scheme.open_at_points(&poly, &[Fp::from(2), Fp::from(3)], &rng);
+        opening.evals[1] += Fp::from(1);
+
+        assert!(!scheme.verify_at_points(&poly.commitment(), &opening));
+    }
+
+    #[test]
+    fn test_wrong_commitment_rejected() {
+        let scheme = scheme();
+        let rng = DeterministicRandomness::from_seed([5; 32]);
+
+        let poly = scheme.commit(&[Fp::from(1)], &rng);
+        let other = scheme.commit(&[Fp::from(2)], &rng);
+        let opening = scheme.open_at_points(&poly, &[Fp::from(7)], &rng);
+
+        assert!(!scheme.verify_at_points(&other.commitment(), &opening));
+    }
+
+    #[test]
+    fn test_add_and_scale() {
+        let scheme = scheme();
+        let rng = DeterministicRandomness::from_seed([6; 32]);
+
+        let p = scheme.commit(&[Fp::from(1), Fp::from(2)], &rng);
+        let q = scheme.commit(&[Fp::from(3), Fp::from(0), Fp::from(4)], &rng);
+
+        let sum = p.add(&q);
+        let point = Fp::from(3);
+        assert_eq!(sum.evaluate(point), p.evaluate(point) + q.evaluate(point));
+        assert_eq!(
+            sum.commitment(),
+            (p.commitment() + q.commitment()).to_affine()
+        );
+
+        let scaled = p.scale(Fp::from(5));
+        assert_eq!(scaled.evaluate(point), p.evaluate(point) * Fp::from(5));
+
+        let opening = scheme.open_at_points(&scaled, &[point], &rng);
+        assert!(scheme.verify_at_points(&scaled.commitment(), &opening));
+    }
+
+    #[test]
+    fn test_combined_opening() {
+        let scheme = scheme();
+        let rng = DeterministicRandomness::from_seed([7; 32]);
+
+        let p = scheme.commit(&[Fp::from(1), Fp::from(1)], &rng);
+        let q = scheme.commit(&[Fp::from(2), Fp::from(0), Fp::from(7)], &rng);
+        let r = scheme.commit(&[Fp::from(11)], &rng);
+
+        let gamma = Fp::from(13);
+        let factors = [Fp::ONE, gamma, gamma * gamma];
+        let polys = [&p, &q, &r];
+
+        let terms: Vec<_> = factors.iter().copied().zip(polys).collect();
+        let points = [Fp::from(2), Fp::from(9)];
+        let opening = scheme.open_combination(&terms, &points, &rng);
+
+        let commitments: Vec<_> = factors
+            .iter()
+            .copied()
+            .zip(polys.iter().map(|poly| poly.commitment()))
+            .collect();
+        assert!(scheme.verify_combination(&commitments, &opening));
+
+        let mut wrong = commitments.clone();
+        wrong[1].0 = gamma + Fp::ONE;
+        assert!(!scheme.verify_combination(&wrong, &opening));
+        assert!(!scheme.verify_combination(&[], &opening));
     }
 }
@@ -1,34 +1,143 @@
 //! Traits for commitment schemes
 
-/// Generic commitment trait
-pub trait Commitment {
-    /// The type of the commitment
-    type Output;
+use zk_proof_core::Randomness;
 
-    /// Create a commitment
-    fn commit(&self) -> Self::Output;
+/// Committer role: binds a value and produces the opening needed to reveal it
+///
+/// The committer keeps nothing between calls; everything a verifier needs is
+/// in the returned commitment and opening.
+pub trait Committer {
+    /// The type of values being committed to
+    type Value;
+
+    /// The type of commitments
+    type Commitment;
+
+    /// Secret data that, together with the value, opens a commitment
+    type Opening;
 
-    /// Verify a commitment
-    fn verify(&self, commitment: &Self::Output) -> bool;
+    /// Commit to a value using fresh randomness
+    fn commit<R: Randomness>(
+        &self,
+        value: &Self::Value,
+        randomness: &R,
+    ) -> (Self::Commitment, Self::Opening);
 }
 
-/// Commitment scheme trait
+/// Verifier role: checks a revealed value against a commitment
+///
+/// Implementations only need public parameters, so any third party holding
+/// them can verify.
+pub trait CommitmentVerifier {
+    /// The type of values being committed to
+    type Value;
+
+    /// The type of commitments
+    type Commitment;
+
+    /// Secret data that, together with the value, opens a commitment
+    type Opening;
+
+    /// Check that `value` and `opening` open `commitment`
+    fn verify(
+        &self,
+        commitment: &Self::Commitment,
+        value: &Self::Value,
+        opening: &Self::Opening,
+    ) -> bool;
+}
+
+/// Commitment scheme with caller-supplied blinding
+///
+/// Unlike [`Committer`], the caller picks the blinding factor, so the same
+/// value can be recommitted deterministically and blinding factors can be
+/// derived from other protocol state. [`CommitmentScheme::random_blinding`]
+/// draws a fresh one when nothing else dictates it.
 pub trait CommitmentScheme {
     /// The type of values being committed to
     type Value;
 
+    /// The type of blinding factors
+    type Blinding;
+
     /// The type of commitments
     type Commitment;
 
     /// The type of opening proofs
     type Opening;
 
-    /// Commit to a value
-    fn commit(&self, value: &Self::Value) -> Self::Commitment;
+    /// Draw a fresh blinding factor
+    fn random_blinding<R: Randomness>(&self, randomness: &R) -> Self::Blinding;
 
-    /// Open a commitment
-    fn open(&self, commitment: &Self::Commitment, value: &Self::Value) -> Self::Opening;
+    /// Commit to a value under `blinding`
+    fn commit(&self, value: &Self::Value, blinding: &Self::Blinding) -> Self::Commitment;
+
+    /// Opening revealing `value` committed under `blinding`
+    fn open(&self, value: &Self::Value, blinding: &Self::Blinding) -> Self::Opening;
 
     /// Verify an opening
     fn verify(&self, commitment: &Self::Commitment, opening: &Self::Opening) -> bool;
 }
+
+/// Commitment to a vector with openings at individual positions
+///
+/// Implemented by both the Merkle and the vector Pedersen schemes so callers
+/// can swap data-availability primitives without touching the call sites.
+pub trait VectorCommitment {
+    /// Type of vector entries
+    type Element;
+
+    /// Commitment to the whole vector
+    type Commitment;
+
+    /// Prover-side data needed to open positions later
+    type Witness;
+
+    /// Proof that a set of positions holds the claimed entries
+    type Opening;
+
+    /// Commit to a vector
+    fn commit<R: Randomness>(
+        &self,
+        values: &[Self::Element],
+        randomness: &R,
+    ) -> (Self::Commitment, Self::Witness);
+
+    /// Open a single position; `None` if the index is out of range
+    fn open<R: Randomness>(
+        &self,
+        witness: &Self::Witness,
+        index: usize,
+        randomness: &R,
+    ) -> Option<Self::Opening> {
+        self.open_batch(witness, &[index], randomness)
+    }
+
+    /// Open several positions with one opening; `None` if any index is out of
+    /// range or the set is empty
+    fn open_batch<R: Randomness>(
+        &self,
+        witness: &Self::Witness,
+        indices: &[usize],
+        randomness: &R,
+    ) -> Option<Self::Opening>;
+
+    /// Verify that position `index` holds `value`
+    fn verify(
+        &self,
+        commitment: &Self::Commitment,
+        index: usize,
+        value: &Self::Element,
+        opening: &Self::Opening,
+    ) -> bool {
+        self.verify_batch(commitment, &[(index, value)], opening)
+    }
+
+    /// Verify that every `(index, value)` pair holds
+    fn verify_batch(
+        &self,
+        commitment: &Self::Commitment,
+        entries: &[(usize, &Self::Element)],
+        opening: &Self::Opening,
+    ) -> bool;
+}
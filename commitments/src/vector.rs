@@ -0,0 +1,337 @@
+//! Vector Pedersen commitments with positional openings
+//!
+//! A commitment to `v` is `C = sum(v_i * G_i) + r * H`. Opening a set `S` of
+//! positions reveals `v_i` for `i in S` together with a Schnorr proof of
+//! knowledge of a representation of `C - sum_{i in S}(v_i * G_i)` over the
+//! remaining generators. The proof hides the unopened entries, and two
+//! different openings of the same position would yield two representations
+//! of `C`, i.e. a discrete log relation between the generators.
+
+use std::collections::BTreeSet;
+
+use blake2b_simd::Params as Blake2bParams;
+use ff::{Field, FromUniformBytes, PrimeField};
+use group::Curve;
+use halo2_proofs::arithmetic::CurveAffine;
+use zk_proof_core::{msm, Randomness, Result};
+
+use crate::{
+    encoding::{scalar_len, write_count, CanonicalEncoding, Reader},
+    generators::{
+        derive_affine_generators, derive_generator, BLINDING_LABEL, VECTOR_PEDERSEN_DOMAIN,
+    },
+    traits::VectorCommitment,
+};
+
+const CHALLENGE_PERSONAL: &[u8; 16] = b"zkps-vec-pedersn";
+
+/// Public parameters: one generator per position plus a blinding generator
+#[derive(Clone, Debug)]
+pub struct VectorPedersen<C: CurveAffine> {
+    generators: Vec<C>,
+    blinding_generator: C,
+}
+
+/// Committed vector and blinding factor kept by the committer
+#[derive(Clone, Debug)]
+pub struct VectorPedersenWitness<C: CurveAffine> {
+    values: Vec<C::Scalar>,
+    blinding: C::Scalar,
+}
+
+/// Proof of knowledge of the unopened part of a vector commitment
+#[derive(Clone, Debug)]
+pub struct VectorPedersenOpening<C: CurveAffine> {
+    /// Nonce commitment `A`
+    pub nonce_commitment: C,
+    /// Responses for the unopened positions, in increasing index order
+    pub responses: Vec<C::Scalar>,
+    /// Response for the blinding factor
+    pub blinding_response: C::Scalar,
+}
+
+impl<C: CurveAffine> CanonicalEncoding for VectorPedersenOpening<C> {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = self.nonce_commitment.to_bytes().as_ref().to_vec();
+        write_count(&mut out, self.responses.len());
+        for response in &self.responses {
+            out.extend_from_slice(response.to_repr().as_ref());
+        }
+        out.extend_from_slice(self.blinding_response.to_repr().as_ref());
+        out
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes)?;
+        let nonce_commitment = reader.affine()?;
+        let count = reader.count(scalar_len::<C::Scalar>())?;
+        let responses = (0..count).map(|_| reader.scalar()).collect::<Result<_>>()?;
+        let blinding_response = reader.scalar()?;
+        reader.finish()?;
+
+        Ok(Self {
+            nonce_commitment,
+            responses,
+            blinding_response,
+        })
+    }
+}
+
+impl<C: CurveAffine> VectorPedersen<C> {
+    /// Create parameters from per-position generators and a blinding generator
+    ///
+    /// All generators must have unknown discrete log relations to each other.
+    #[must_use]
+    pub fn new(generators: Vec<C>, blinding_generator: C) -> Self {
+        Self {
+            generators,
+            blinding_generator,
+        }
+    }
+
+    /// Standard parameters for vectors of up to `n` entries, derived by
+    /// hash-to-curve under [`VECTOR_PEDERSEN_DOMAIN`]
+    #[must_use]
+    pub fn derive(n: usize) -> Self {
+        let blinding: C::CurveExt = derive_generator(VECTOR_PEDERSEN_DOMAIN, BLINDING_LABEL);
+        Self::new(
+            derive_affine_generators(VECTOR_PEDERSEN_DOMAIN, n),
+            blinding.to_affine(),
+        )
+    }
+
+    /// Maximum vector length
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.generators.len()
+    }
+
+    /// Commitment to `values` under a caller-chosen `blinding`
+    ///
+    /// Commitments are homomorphic: committing to `a + r b` under `s + r t`
+    /// gives the commitment to `a` under `s` plus `r` times the commitment
+    /// to `b` under `t`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` is longer than [`VectorPedersen::capacity`].
+    #[must_use]
+    pub fn commit_with_blinding(&self, values: &[C::Scalar], blinding: C::Scalar) -> C {
+        assert!(
+            values.len() <= self.capacity(),
+            "vector of length {} exceeds capacity {}",
+            values.len(),
+            self.capacity()
+        );
+        (msm(values, &self.generators[..values.len()]) + self.blinding_generator * blinding)
+            .to_affine()
+    }
+
+    /// Indices not in `opened`, in increasing order
+    fn remaining(&self, opened: &BTreeSet<usize>) -> Vec<usize> {
+        (0..self.generators.len())
+            .filter(|i| !opened.contains(i))
+            .collect()
+    }
+}
+
+impl<C: CurveAffine> VectorCommitment for VectorPedersen<C>
+where
+    C::Scalar: FromUniformBytes<64>,
+{
+    type Element = C::Scalar;
+    type Commitment = C;
+    type Witness = VectorPedersenWitness<C>;
+    type Opening = VectorPedersenOpening<C>;
+
+    /// # Panics
+    ///
+    /// Panics if `values` is longer than [`VectorPedersen::capacity`].
+    fn commit<R: Randomness>(
+        &self,
+        values: &[C::Scalar],
+        randomness: &R,
+    ) -> (C, VectorPedersenWitness<C>) {
+        let blinding: C::Scalar = randomness.random_field();
+        let commitment = self.commit_with_blinding(values, blinding);
+
+        let mut padded = values.to_vec();
+        padded.resize(self.capacity(), C::Scalar::ZERO);
+
+        (
+            commitment,
+            VectorPedersenWitness {
+                values: padded,
+                blinding,
+            },
+        )
+    }
+
+    fn open_batch<R: Randomness>(
+        &self,
+        witness: &VectorPedersenWitness<C>,
+        indices: &[usize],
+        randomness: &R,
+    ) -> Option<VectorPedersenOpening<C>> {
+        let opened: BTreeSet<usize> = indices.iter().copied().collect();
+        if opened.is_empty() || opened.len() != indices.len() {
+            return None;
+        }
+        if opened.iter().any(|&i| i >= self.capacity()) {
+            return None;
+        }
+
+        let commitment = (msm(&witness.values, &self.generators)
+            + self.blinding_generator * witness.blinding)
+            .to_affine();
+
+        let remaining = self.remaining(&opened);
+        let nonces: Vec<C::Scalar> = remaining
+            .iter()
+            .map(|_| randomness.random_field())
+            .collect();
+        let blinding_nonce: C::Scalar = randomness.random_field();
+
+        let bases: Vec<C> = remaining.iter().map(|&j| self.generators[j]).collect();
+        let nonce_commitment =
+            (msm(&nonces, &bases) + self.blinding_generator * blinding_nonce).to_affine();
+
+        let entries: Vec<(usize, C::Scalar)> =
+            opened.iter().map(|&i| (i, witness.values[i])).collect();
+        let c = challenge(&commitment, &entries, &nonce_commitment);
+
+        let responses = remaining
+            .iter()
+            .zip(&nonces)
+            .map(|(&j, nonce)| *nonce + c * witness.values[j])
+            .collect();
+
+        Some(VectorPedersenOpening {
+            nonce_commitment,
+            responses,
+            blinding_response: blinding_nonce + c * witness.blinding,
+        })
+    }
+
+    fn verify_batch(
+        &self,
+        commitment: &C,
+        entries: &[(usize, &C::Scalar)],
+        opening: &VectorPedersenOpening<C>,
+    ) -> bool {
+        let opened: BTreeSet<usize> = entries.iter().map(|(i, _)| *i).collect();
+        if opened.is_empty()
+            || opened.len() != entries.len()
+            || opened.iter().any(|&i| i >= self.capacity())
+        {
+            return false;
+        }
+
+        let remaining = self.remaining(&opened);
+        if remaining.len() != opening.responses.len() {
+            return false;
+        }
+
+        let mut sorted: Vec<(usize, C::Scalar)> = entries.iter().map(|(i, v)| (*i, **v)).collect();
+        sorted.sort_by_key(|(i, _)| *i);
+        let c = challenge(commitment, &sorted, &opening.nonce_commitment);
+
+        // sum(z_j * G_j) + z_r * H == A + c * (C - sum_{i in S}(v_i * G_i))
+        let bases: Vec<C> = remaining.iter().map(|&j| self.generators[j]).collect();
+        let lhs =
+            msm(&opening.responses, &bases) + self.blinding_generator * opening.blinding_response;
+
+        let opened_values: Vec<C::Scalar> = sorted.iter().map(|(_, v)| *v).collect();
+        let opened_bases: Vec<C> = sorted.iter().map(|(i, _)| self.generators[*i]).collect();
+        let rest = commitment.to_curve() - msm(&opened_values, &opened_bases);
+        let rhs = opening.nonce_commitment.to_curve() + rest * c;
+
+        lhs == rhs
+    }
+}
+
+/// Fiat-Shamir challenge over the statement and the nonce commitment
+fn challenge<C: CurveAffine>(commitment: &C, entries: &[(usize, C::Scalar)], nonce: &C) -> C::Scalar
+where
+    C::Scalar: FromUniformBytes<64>,
+{
+    let mut state = Blake2bParams::new()
+        .hash_length(64)
+        .personal(CHALLENGE_PERSONAL)
+        .to_state();
+    state.update(commitment.to_bytes().as_ref());
+    for (index, value) in entries {
+        state.update(&(*index as u64).to_le_bytes());
+        state.update(value.to_repr().as_ref());
+    }
+    state.update(nonce.to_bytes().as_ref());
+
+    let mut wide = [0u8; 64];
+    wide.copy_from_slice(state.finalize().as_bytes());
+    C::Scalar::from_uniform_bytes(&wide)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use group::prime::PrimeCurveAffine;
+    use halo2_proofs::pasta::pallas;
+    use zk_proof_core::OsRandomness;
+
+    fn scheme(n: usize) -> VectorPedersen<pallas::Affine> {
+        VectorPedersen::derive(n)
+    }
+
+    #[test]
+    fn test_open_single_position() {
+        let scheme = scheme(4);
+        let values: Vec<pallas::Scalar> = (1..=4u64).map(pallas::Scalar::from).collect();
+        let (commitment, witness) = scheme.commit(&values, &OsRandomness);
+
+        let opening = scheme.open(&witness, 2, &OsRandomness).unwrap();
+        assert!(scheme.verify(&commitment, 2, &values[2], &opening));
+        assert!(!scheme.verify(&commitment, 2, &values[1], &opening));
+        assert!(!scheme.verify(&commitment, 1, &values[2], &opening));
+    }
+
+    #[test]
+    fn test_open_batch_any_order() {
+        let scheme = scheme(6);
+        let values: Vec<pallas::Scalar> = (10..16u64).map(pallas::Scalar::from).collect();
+        let (commitment, witness) = scheme.commit(&values, &OsRandomness);
+
+        let opening = scheme.open_batch(&witness, &[4, 0], &OsRandomness).unwrap();
+        assert!(scheme.verify_batch(&commitment, &[(0, &values[0]), (4, &values[4])], &opening));
+        assert!(scheme.verify_batch(&commitment, &[(4, &values[4]), (0, &values[0])], &opening));
+        assert!(!scheme.verify_batch(&commitment, &[(0, &values[0])], &opening));
+
+        let other = pallas::Affine::generator();
+        assert!(!scheme.verify_batch(&other, &[(0, &values[0]), (4, &values[4])], &opening));
+    }
+
+    #[test]
+    fn test_opening_encoding() {
+        let scheme = scheme(3);
+        let values: Vec<pallas::Scalar> = (5..8u64).map(pallas::Scalar::from).collect();
+        let (commitment, witness) = scheme.commit(&values, &OsRandomness);
+        let opening = scheme.open(&witness, 1, &OsRandomness).unwrap();
+
+        let bytes = opening.to_bytes();
+        let decoded = VectorPedersenOpening::<pallas::Affine>::from_bytes(&bytes).unwrap();
+        assert!(scheme.verify(&commitment, 1, &values[1], &decoded));
+
+        let mut corrupted = bytes;
+        corrupted[..32].copy_from_slice(&[0xff; 32]);
+        assert!(VectorPedersenOpening::<pallas::Affine>::from_bytes(&corrupted).is_err());
+    }
+
+    #[test]
+    fn test_out_of_range_rejected() {
+        let scheme = scheme(2);
+        let (_, witness) = scheme.commit(&[pallas::Scalar::ONE], &OsRandomness);
+        assert!(scheme.open(&witness, 2, &OsRandomness).is_none());
+        assert!(scheme
+            .open_batch(&witness, &[0, 0], &OsRandomness)
+            .is_none());
+    }
+}
@@ -1,12 +1,328 @@
-use criterion::{criterion_group, criterion_main, Criterion};
+//! Keygen, witness generation, proving and verification benchmarks
+//!
+//! Each stage runs on a chain circuit filling every usable row at
+//! `k = 10..=16`, with throughput in rows. Batch verification checks
+//! `N = 1..=64` proofs at the smallest `k`, with throughput in proofs.
+//! With the `circuits` feature, aggregation proves the commitment folding
+//! circuit on as many mock proofs as fit each `k`:
+//!
+//! ```text
+//! cargo bench -p zk-proof-core --features circuits --bench core_benchmarks
+//! ```
+//!
+//! Pass a filter such as `prove/12` to run one stage at one size.
 
-fn bench_example(c: &mut Criterion) {
-    c.bench_function("example", |b| {
-        b.iter(|| {
-            std::hint::black_box(42);
+use std::ops::RangeInclusive;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{
+        self, keygen_pk, keygen_vk, verify_proof, Advice, Circuit, Column, ConstraintSystem, Error,
+        Instance, ProvingKey, Selector, SingleVerifier,
+    },
+    poly::{commitment::Params, Rotation},
+    transcript::{Blake2bRead, Challenge255},
+};
+use pasta_curves::{vesta, Fp};
+use zk_proof_core::{write_proof, OsRandomness};
+
+/// Sizes every per-`k` stage runs at
+const KS: RangeInclusive<u32> = 10..=16;
+
+/// Batch sizes for batch verification
+const BATCH_SIZES: [usize; 7] = [1, 2, 4, 8, 16, 32, 64];
+
+/// Rows left free at the end of the circuit, above halo2's blinding rows
+const RESERVED_ROWS: usize = 64;
+
+/// `rows` steps of `a' = a * b + a` with `b` counting up, exposing the
+/// last `a`
+#[derive(Clone)]
+struct Chain {
+    rows: usize,
+    /// First `a`, unknown for keygen
+    seed: Option<u64>,
+}
+
+impl Chain {
+    /// Chain filling the usable rows of `k`
+    fn filling(k: u32, seed: u64) -> Self {
+        Self {
+            rows: (1 << k) - RESERVED_ROWS,
+            seed: Some(seed),
+        }
+    }
+
+    /// Last `a`, the chain's public input
+    fn output(&self) -> Fp {
+        let seed = Fp::from(self.seed.expect("chain has a witness"));
+        (0..self.rows).fold(seed, |a, i| a * Fp::from(i as u64 + 1) + a)
+    }
+}
+
+impl Circuit<Fp> for Chain {
+    type Config = ([Column<Advice>; 2], Column<Instance>, Selector);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            rows: self.rows,
+            seed: None,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let columns = [meta.advice_column(), meta.advice_column()];
+        let instance = meta.instance_column();
+        let q = meta.selector();
+        meta.enable_equality(columns[0]);
+        meta.enable_equality(instance);
+        meta.create_gate("chain", |meta| {
+            let q = meta.query_selector(q);
+            let a = meta.query_advice(columns[0], Rotation::cur());
+            let b = meta.query_advice(columns[1], Rotation::cur());
+            let next = meta.query_advice(columns[0], Rotation::next());
+            vec![q * (next - (a.clone() * b + a))]
+        });
+        (columns, instance, q)
+    }
+
+    fn synthesize(
+        &self,
+        ([a_column, b_column], instance, q): Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let last = layouter.assign_region(
+            || "chain",
+            |mut region| {
+                let mut a = self
+                    .seed
+                    .map_or_else(Value::unknown, |seed| Value::known(Fp::from(seed)));
+                let mut cell = region.assign_advice(|| "a", a_column, 0, || a)?;
+                for i in 0..self.rows {
+                    q.enable(&mut region, i)?;
+                    let b = Fp::from(i as u64 + 1);
+                    region.assign_advice(|| "b", b_column, i, || Value::known(b))?;
+                    a = a.map(|a| a * b + a);
+                    cell = region.assign_advice(|| "a", a_column, i + 1, || a)?;
+                }
+                Ok(cell)
+            },
+        )?;
+        layouter.constrain_instance(last.cell(), instance, 0)
+    }
+}
+
+/// Params and proving key of the chain filling `k`
+struct Setup {
+    k: u32,
+    params: Params<vesta::Affine>,
+    pk: ProvingKey<vesta::Affine>,
+}
+
+impl Setup {
+    fn new(k: u32) -> Self {
+        let params = Params::new(k);
+        let pk = keygen(&params, &Chain::filling(k, 1));
+        Self { k, params, pk }
+    }
+
+    /// Proof of the chain from `seed`, and its public input
+    fn prove(&self, seed: u64) -> (Fp, Vec<u8>) {
+        let circuit = Chain::filling(self.k, seed);
+        let output = [circuit.output()];
+        let instances: [&[Fp]; 1] = [&output];
+        let proof = write_proof(
+            &self.params,
+            &self.pk,
+            &[circuit],
+            &[&instances],
+            OsRandomness,
+            Vec::new(),
+        )
+        .expect("prove");
+        (output[0], proof)
+    }
+
+    fn verify(&self, output: Fp, proof: &[u8]) {
+        let output = [output];
+        let instances: [&[Fp]; 1] = [&output];
+        let mut transcript = Blake2bRead::<_, vesta::Affine, Challenge255<_>>::init(proof);
+        verify_proof(
+            &self.params,
+            self.pk.get_vk(),
+            SingleVerifier::new(&self.params),
+            &[&instances],
+            &mut transcript,
+        )
+        .expect("verify_proof");
+    }
+}
+
+fn keygen(params: &Params<vesta::Affine>, circuit: &Chain) -> ProvingKey<vesta::Affine> {
+    let empty = circuit.without_witnesses();
+    let vk = keygen_vk(params, &empty).expect("keygen_vk");
+    keygen_pk(params, vk, &empty).expect("keygen_pk")
+}
+
+/// Rows of the chain filling `k`, as throughput
+fn rows(k: u32) -> Throughput {
+    Throughput::Elements(Chain::filling(k, 1).rows as u64)
+}
+
+fn bench_keygen(c: &mut Criterion) {
+    let mut group = c.benchmark_group("keygen");
+    group.sample_size(10);
+    for k in KS {
+        let params = Params::<vesta::Affine>::new(k);
+        let circuit = Chain::filling(k, 1);
+        group.throughput(rows(k));
+        group.bench_with_input(BenchmarkId::from_parameter(k), &circuit, |b, circuit| {
+            b.iter(|| keygen(&params, circuit));
+        });
+    }
+    group.finish();
+}
+
+fn bench_witness(c: &mut Criterion) {
+    let mut group = c.benchmark_group("witness");
+    group.sample_size(10);
+    for k in KS {
+        let circuit = Chain::filling(k, 1);
+        let instances = vec![vec![circuit.output()]];
+        group.throughput(rows(k));
+        group.bench_with_input(BenchmarkId::from_parameter(k), &circuit, |b, circuit| {
+            b.iter(|| MockProver::run(k, circuit, instances.clone()).expect("synthesis"));
+        });
+    }
+    group.finish();
+}
+
+fn bench_prove_and_verify(c: &mut Criterion) {
+    let setups: Vec<Setup> = KS.map(Setup::new).collect();
+
+    let mut group = c.benchmark_group("prove");
+    group.sample_size(10);
+    for setup in &setups {
+        group.throughput(rows(setup.k));
+        group.bench_function(BenchmarkId::from_parameter(setup.k), |b| {
+            b.iter(|| setup.prove(1));
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("verify");
+    for setup in &setups {
+        let (output, proof) = setup.prove(1);
+        group.throughput(rows(setup.k));
+        group.bench_function(BenchmarkId::from_parameter(setup.k), |b| {
+            b.iter(|| setup.verify(output, &proof));
+        });
+    }
+    group.finish();
+}
+
+fn bench_batch_verify(c: &mut Criterion) {
+    let setup = Setup::new(*KS.start());
+    let max = BATCH_SIZES[BATCH_SIZES.len() - 1];
+    let proofs: Vec<(Fp, Vec<u8>)> = (1..=max as u64).map(|seed| setup.prove(seed)).collect();
+
+    let mut group = c.benchmark_group("batch_verify");
+    group.sample_size(10);
+    for n in BATCH_SIZES {
+        group.throughput(Throughput::Elements(n as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(n), &proofs[..n], |b, proofs| {
+            b.iter(|| {
+                let mut batch = plonk::BatchVerifier::new();
+                for (output, proof) in proofs {
+                    batch.add_proof(vec![vec![vec![*output]]], proof.clone());
+                }
+                assert!(batch.finalize(&setup.params, setup.pk.get_vk()));
+            });
         });
-    });
+    }
+    group.finish();
+}
+
+#[cfg(feature = "circuits")]
+mod aggregation {
+    use criterion::{BenchmarkId, Criterion, Throughput};
+    use group::{Curve, Group, GroupEncoding};
+    use halo2_proofs::{plonk::Circuit, poly::commitment::Params};
+    use pasta_curves::{pallas, vesta};
+    use zk_proof_core::{
+        circuits::RowUsage,
+        recursion::{fold_proofs, recursion_instance, Accumulator, CommitmentFolder},
+        OsRandomness, Prover,
+    };
+
+    use super::KS;
+
+    /// Size of each mock inner proof
+    const INNER_PROOF_LEN: usize = 192;
+
+    /// Mock proofs, each led by a distinct commitment
+    fn proofs(count: usize) -> Vec<Vec<u8>> {
+        (1..=count as u64)
+            .map(|i| {
+                let commitment = (pallas::Point::generator() * pallas::Scalar::from(i)).to_affine();
+                let mut proof = commitment.to_bytes().as_ref().to_vec();
+                proof.resize(INNER_PROOF_LEN, 0);
+                proof
+            })
+            .collect()
+    }
+
+    /// Most proofs one aggregation fits in `k`
+    fn capacity(k: u32) -> usize {
+        let fits = |count: usize| {
+            let circuit =
+                CommitmentFolder::<pallas::Affine>::new(Accumulator::new(), proofs(count));
+            RowUsage::measure(&circuit.without_witnesses()).is_ok_and(|usage| usage.minimum_k <= k)
+        };
+        (1..).take_while(|count| fits(*count)).last().unwrap_or(0)
+    }
+
+    pub(super) fn bench_aggregation(c: &mut Criterion) {
+        let mut group = c.benchmark_group("aggregate");
+        group.sample_size(10);
+        for k in KS {
+            // The smallest sizes hold no fold at all
+            let count = capacity(k);
+            if count == 0 {
+                continue;
+            }
+            let params = Params::<vesta::Affine>::new(k);
+            let accumulator = Accumulator::<pallas::Affine>::new();
+            let proofs = proofs(count);
+            let folded = fold_proofs(&accumulator, &proofs).expect("mock proofs are valid");
+            let instances = vec![recursion_instance(&accumulator, &folded)];
+            let circuit = CommitmentFolder::new(accumulator, proofs.clone());
+            let prover = Prover::new(&params, "aggregate", &circuit).expect("keygen");
+
+            group.throughput(Throughput::Elements(count as u64));
+            group.bench_function(BenchmarkId::new(format!("{count} proofs"), k), |b| {
+                b.iter(|| {
+                    let circuit =
+                        CommitmentFolder::<pallas::Affine>::new(Accumulator::new(), proofs.clone());
+                    prover
+                        .prove(circuit, &instances, OsRandomness)
+                        .expect("prove")
+                });
+            });
+        }
+        group.finish();
+    }
 }
 
-criterion_group!(benches, bench_example);
-criterion_main!(benches);
+criterion_group!(proving, bench_keygen, bench_witness, bench_prove_and_verify);
+criterion_group!(verification, bench_batch_verify);
+#[cfg(feature = "circuits")]
+criterion_group!(recursion, aggregation::bench_aggregation);
+
+#[cfg(feature = "circuits")]
+criterion_main!(proving, verification, recursion);
+#[cfg(not(feature = "circuits"))]
+criterion_main!(proving, verification);
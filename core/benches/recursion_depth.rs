@@ -0,0 +1,128 @@
+//! Recursion depth benchmark with the real prover
+//!
+//! Runs keygen, `create_proof` and `verify_proof` for the commitment folding
+//! circuit at each depth and prints proof size, proving time and verification
+//! time. Proving at these sizes takes minutes, so the target needs the
+//! `heavy-bench` feature:
+//!
+//! ```text
+//! cargo bench -p zk-proof-core --features heavy-bench --bench recursion_depth
+//! ```
+
+use std::time::{Duration, Instant};
+
+use group::{Curve, Group, GroupEncoding};
+use halo2_proofs::{
+    plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, SingleVerifier},
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255},
+};
+use pasta_curves::{pallas, vesta, Fp};
+use rand::rngs::OsRng;
+use zk_proof_core::recursion::{fold_proofs, recursion_instance, Accumulator, CommitmentFolder};
+
+/// Depths to measure
+const DEPTHS: std::ops::RangeInclusive<usize> = 1..=5;
+
+/// Size of each mock inner proof
+const INNER_PROOF_LEN: usize = 192;
+
+struct DepthReport {
+    depth: usize,
+    k: u32,
+    proof_len: usize,
+    keygen: Duration,
+    prove: Duration,
+    verify: Duration,
+}
+
+/// Circuit folding `depth` mock proofs, each led by a distinct commitment,
+/// and its public input
+fn circuit(depth: usize) -> (CommitmentFolder<pallas::Affine>, Vec<Fp>) {
+    let proofs: Vec<Vec<u8>> = (1..=depth as u64)
+        .map(|i| {
+            let commitment = (pallas::Point::generator() * pallas::Scalar::from(i)).to_affine();
+            let mut proof = commitment.to_bytes().as_ref().to_vec();
+            proof.resize(INNER_PROOF_LEN, 0);
+            proof
+        })
+        .collect();
+    let accumulator = Accumulator::new();
+    let folded = fold_proofs(&accumulator, &proofs).expect("mock proofs are valid");
+    let instance = recursion_instance(&accumulator, &folded);
+    (CommitmentFolder::new(accumulator, proofs), instance)
+}
+
+fn run(depth: usize) -> DepthReport {
+    // About 1400 rows per folded proof
+    let k = 11 + (usize::BITS - (depth - 1).leading_zeros());
+    let params = Params::<vesta::Affine>::new(k);
+    let (circuit, instance) = circuit(depth);
+    let instances: [&[Fp]; 1] = [&instance];
+
+    let started = Instant::now();
+    let empty = circuit.without_witnesses();
+    let vk = keygen_vk(&params, &empty).expect("keygen_vk");
+    let pk = keygen_pk(&params, vk, &empty).expect("keygen_pk");
+    let keygen = started.elapsed();
+
+    let mut transcript = Blake2bWrite::<_, vesta::Affine, Challenge255<_>>::init(vec![]);
+    let started = Instant::now();
+    create_proof(
+        &params,
+        &pk,
+        &[circuit],
+        &[&instances],
+        OsRng,
+        &mut transcript,
+    )
+    .expect("create_proof");
+    let prove = started.elapsed();
+    let proof = transcript.finalize();
+
+    let mut transcript = Blake2bRead::<_, vesta::Affine, Challenge255<_>>::init(&proof[..]);
+    let started = Instant::now();
+    verify_proof(
+        &params,
+        pk.get_vk(),
+        SingleVerifier::new(&params),
+        &[&instances],
+        &mut transcript,
+    )
+    .expect("verify_proof");
+    let verify = started.elapsed();
+
+    DepthReport {
+        depth,
+        k,
+        proof_len: proof.len(),
+        keygen,
+        prove,
+        verify,
+    }
+}
+
+fn main() {
+    // `cargo bench` passes `--bench`; a bare argument filters by depth.
+    let only: Option<usize> = std::env::args()
+        .skip(1)
+        .find(|arg| !arg.starts_with('-'))
+        .map(|arg| arg.parse().expect("depth filter must be a number"));
+
+    println!(
+        "{:>5} {:>3} {:>12} {:>12} {:>12} {:>12}",
+        "depth", "k", "proof bytes", "keygen", "prove", "verify"
+    );
+    for depth in DEPTHS.filter(|depth| only.is_none_or(|only| only == *depth)) {
+        let report = run(depth);
+        println!(
+            "{:>5} {:>3} {:>12} {:>12} {:>12} {:>12}",
+            report.depth,
+            report.k,
+            report.proof_len,
+            format!("{:.2?}", report.keygen),
+            format!("{:.2?}", report.prove),
+            format!("{:.2?}", report.verify),
+        );
+    }
+}
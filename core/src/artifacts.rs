@@ -0,0 +1,381 @@
+//! On-disk store for params, proving keys and verifying keys
+//!
+//! The store owns a directory with a fixed layout:
+//!
+//! ```text
+//! <root>/params/k=14.bin
+//! <root>/keys/<circuit_hash>/pk.bin
+//! <root>/keys/<circuit_hash>/vk.bin
+//! ```
+//!
+//! Every file starts with a magic tag and the SHA-256 digest of its payload,
+//! which is checked on each read. Writes go to a temporary file in the target
+//! directory that is synced and then renamed into place, so readers only ever
+//! see complete artifacts even if a writer crashes.
+
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use halo2_proofs::{arithmetic::CurveAffine, poly::commitment::Params};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::{Error, Result},
+    utils::encoding::{decode_hex, encode_hex},
+};
+
+/// Tag at the start of every artifact file
+const MAGIC: &[u8; 4] = b"ZKA1";
+
+/// Length of the header: magic plus SHA-256 digest
+const HEADER_LEN: usize = MAGIC.len() + 32;
+
+/// Counter keeping temporary file names unique within the process
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Identifier of a circuit, used to key its proving and verifying keys
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CircuitHash([u8; 32]);
+
+impl CircuitHash {
+    /// Wrap a raw 32-byte hash
+    #[must_use]
+    pub fn new(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// SHA-256 of a circuit description
+    #[must_use]
+    pub fn of(description: &[u8]) -> Self {
+        Self(Sha256::digest(description).into())
+    }
+
+    /// Parse the lowercase hex form used in directory names
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if `hex` is not 64 hex digits.
+    pub fn from_hex(hex: &str) -> Result<Self> {
+        let bytes = decode_hex(hex)?;
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| Error::Encoding("circuit hash must be 32 bytes".into()))?;
+        Ok(Self(bytes))
+    }
+
+    /// Raw hash bytes
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl fmt::Display for CircuitHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&encode_hex(&self.0))
+    }
+}
+
+/// Which key of a circuit an artifact holds
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyKind {
+    /// Proving key
+    Proving,
+    /// Verifying key
+    Verifying,
+}
+
+impl KeyKind {
+    /// File name of this key inside the circuit's directory
+    #[must_use]
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::Proving => "pk.bin",
+            Self::Verifying => "vk.bin",
+        }
+    }
+}
+
+/// Directory of params and keys with integrity checks and atomic writes
+#[derive(Clone, Debug)]
+pub struct ArtifactStore {
+    root: PathBuf,
+}
+
+impl ArtifactStore {
+    /// Open a store rooted at `root`, creating the directory if needed
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the directory cannot be created.
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    /// Root directory of the store
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Path of the params file for `k`
+    #[must_use]
+    pub fn params_path(&self, k: u32) -> PathBuf {
+        self.root.join("params").join(format!("k={k}.bin"))
+    }
+
+    /// Path of a key file for `circuit`
+    #[must_use]
+    pub fn key_path(&self, circuit: &CircuitHash, kind: KeyKind) -> PathBuf {
+        self.root
+            .join("keys")
+            .join(circuit.to_string())
+            .join(kind.file_name())
+    }
+
+    /// Store params under their own `k`, replacing any existing file
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if serializing or writing fails.
+    pub fn put_params<C: CurveAffine>(&self, params: &Params<C>) -> Result<()> {
+        let mut payload = Vec::new();
+        params.write(&mut payload)?;
+        write_atomic(&self.params_path(params.k()), &payload)
+    }
+
+    /// Load params for `k`, or `None` if the store has none
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if the file fails its integrity check or
+    /// does not parse, and [`Error::ParamsMismatch`] if it holds params for a
+    /// different `k`.
+    pub fn get_params<C: CurveAffine>(&self, k: u32) -> Result<Option<Params<C>>> {
+        let Some(payload) = read_verified(&self.params_path(k))? else {
+            return Ok(None);
+        };
+
+        let params = Params::<C>::read(&mut &payload[..])
+            .map_err(|err| Error::Encoding(format!("invalid params file: {err}")))?;
+        if params.k() != k {
+            return Err(Error::ParamsMismatch {
+                expected: k,
+                found: params.k(),
+            });
+        }
+        Ok(Some(params))
+    }
+
+    /// Store a serialized key for `circuit`, replacing any existing file
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if writing fails.
+    pub fn put_key(&self, circuit: &CircuitHash, kind: KeyKind, bytes: &[u8]) -> Result<()> {
+        write_atomic(&self.key_path(circuit, kind), bytes)
+    }
+
+    /// Load a serialized key for `circuit`, or `None` if the store has none
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if the file fails its integrity check and
+    /// [`Error::Io`] if reading fails.
+    pub fn get_key(&self, circuit: &CircuitHash, kind: KeyKind) -> Result<Option<Vec<u8>>> {
+        read_verified(&self.key_path(circuit, kind))
+    }
+
+    /// Remove both keys of `circuit`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the directory exists but cannot be removed.
+    pub fn remove_keys(&self, circuit: &CircuitHash) -> Result<()> {
+        match fs::remove_dir_all(self.root.join("keys").join(circuit.to_string())) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Write `payload` with its header to `path` via a synced temporary file
+fn write_atomic(path: &Path, payload: &[u8]) -> Result<()> {
+    let dir = path
+        .parent()
+        .ok_or_else(|| Error::Other(format!("artifact path {} has no parent", path.display())))?;
+    fs::create_dir_all(dir)?;
+
+    let tmp = dir.join(format!(
+        ".{}.tmp-{}-{}",
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("artifact"),
+        std::process::id(),
+        TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = (|| -> io::Result<()> {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(MAGIC)?;
+        file.write_all(&Sha256::digest(payload))?;
+        file.write_all(payload)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)?;
+        sync_dir(dir)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    Ok(result?)
+}
+
+/// Read the payload at `path`, checking magic and digest
+fn read_verified(path: &Path) -> Result<Option<Vec<u8>>> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    if bytes.len() < HEADER_LEN || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(Error::Encoding(format!(
+            "{} is not an artifact file",
+            path.display()
+        )));
+    }
+    let (header, payload) = bytes.split_at(HEADER_LEN);
+    if header[MAGIC.len()..] != Sha256::digest(payload)[..] {
+        return Err(Error::Encoding(format!(
+            "{} failed its integrity check",
+            path.display()
+        )));
+    }
+    Ok(Some(payload.to_vec()))
+}
+
+/// Persist a rename by syncing the containing directory
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    fs::File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::pasta::EqAffine;
+
+    /// Fresh store in the system temp directory, removed on drop
+    struct TempStore(ArtifactStore);
+
+    impl TempStore {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir().join(format!(
+                "zk-artifacts-{name}-{}-{}",
+                std::process::id(),
+                TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            Self(ArtifactStore::open(root).unwrap())
+        }
+    }
+
+    impl Drop for TempStore {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(self.0.root());
+        }
+    }
+
+    #[test]
+    fn test_layout() {
+        let store = TempStore::new("layout");
+        let circuit = CircuitHash::of(b"circuit");
+
+        assert!(store.0.params_path(14).ends_with("params/k=14.bin"));
+        assert!(store
+            .0
+            .key_path(&circuit, KeyKind::Proving)
+            .ends_with(format!("keys/{circuit}/pk.bin")));
+        assert_eq!(
+            CircuitHash::from_hex(&circuit.to_string()).unwrap(),
+            circuit
+        );
+    }
+
+    #[test]
+    fn test_params_round_trip() {
+        let store = TempStore::new("params");
+        assert!(store.0.get_params::<EqAffine>(4).unwrap().is_none());
+
+        let params = Params::<EqAffine>::new(4);
+        store.0.put_params(&params).unwrap();
+
+        let loaded = store.0.get_params::<EqAffine>(4).unwrap().unwrap();
+        assert_eq!(loaded.k(), 4);
+        assert_eq!(loaded.get_g(), params.get_g());
+    }
+
+    #[test]
+    fn test_corruption_detected() {
+        let store = TempStore::new("corrupt");
+        let circuit = CircuitHash::of(b"circuit");
+
+        store
+            .0
+            .put_key(&circuit, KeyKind::Verifying, b"vk bytes")
+            .unwrap();
+        assert_eq!(
+            store
+                .0
+                .get_key(&circuit, KeyKind::Verifying)
+                .unwrap()
+                .unwrap(),
+            b"vk bytes"
+        );
+
+        let path = store.0.key_path(&circuit, KeyKind::Verifying);
+        let mut bytes = fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        fs::write(&path, bytes).unwrap();
+        assert!(matches!(
+            store.0.get_key(&circuit, KeyKind::Verifying),
+            Err(Error::Encoding(_))
+        ));
+
+        store.0.remove_keys(&circuit).unwrap();
+        assert!(store
+            .0
+            .get_key(&circuit, KeyKind::Verifying)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_no_temporary_files_left() {
+        let store = TempStore::new("tmp");
+        let circuit = CircuitHash::of(b"circuit");
+        store
+            .0
+            .put_key(&circuit, KeyKind::Proving, &[1, 2, 3])
+            .unwrap();
+        store
+            .0
+            .put_key(&circuit, KeyKind::Proving, &[4, 5])
+            .unwrap();
+
+        let dir = store.0.key_path(&circuit, KeyKind::Proving);
+        let entries: Vec<_> = fs::read_dir(dir.parent().unwrap()).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+    }
+}
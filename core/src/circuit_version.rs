@@ -0,0 +1,188 @@
+//! Binding the circuit version into proofs
+//!
+//! A circuit fix that keeps the same constraint shape can leave the
+//! verifying key of the old version usable, and the version a proof claims
+//! in its [`crate::ProofMetadata`] is unauthenticated. Proofs made with
+//! [`write_versioned_proof`] absorb a hash of the circuit version into the
+//! transcript before the first prover round. [`verify_versioned_proof`]
+//! absorbs the version the verifier has registered, so a proof made for any
+//! other version fails verification instead of being silently accepted.
+//!
+//! The version is not written into the proof, so versioned proofs are the
+//! same size as unversioned ones. The two are not interchangeable: a
+//! versioned proof never verifies without its version and vice versa.
+
+use std::io::Write;
+
+use ff::FromUniformBytes;
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    plonk::{verify_proof, Circuit, Error as PlonkError, ProvingKey, SingleVerifier, VerifyingKey},
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Challenge255, Transcript},
+};
+use sha2::{Digest, Sha512};
+
+use crate::{
+    error::{Error, Result},
+    proof::write_bound_proof,
+    rng::Randomness,
+};
+
+/// Domain separator for the version hash
+const VERSION_DOMAIN: &[u8] = b"zk-proof-system circuit version";
+
+/// Scalar absorbed into the transcript for `version`
+#[must_use]
+pub fn version_scalar<F: FromUniformBytes<64>>(version: &str) -> F {
+    let digest = Sha512::new()
+        .chain_update(VERSION_DOMAIN)
+        .chain_update((version.len() as u64).to_le_bytes())
+        .chain_update(version)
+        .finalize();
+    F::from_uniform_bytes(&digest.into())
+}
+
+/// Prove `circuits` as version `version` of their circuit
+///
+/// # Errors
+///
+/// As for [`crate::write_proof`].
+pub fn write_versioned_proof<C, ConcreteCircuit, W>(
+    params: &Params<C>,
+    pk: &ProvingKey<C>,
+    circuits: &[ConcreteCircuit],
+    instances: &[&[&[C::Scalar]]],
+    version: &str,
+    randomness: impl Randomness,
+    writer: W,
+) -> Result<W>
+where
+    C: CurveAffine,
+    C::Scalar: FromUniformBytes<64>,
+    ConcreteCircuit: Circuit<C::Scalar>,
+    W: Write,
+{
+    let binding = version_scalar(version);
+    write_bound_proof(
+        params,
+        pk,
+        circuits,
+        instances,
+        Some(binding),
+        randomness,
+        writer,
+    )
+}
+
+/// Verify a proof made for version `version` of the circuit
+///
+/// # Errors
+///
+/// Returns [`Error::Plonk`] if the proof does not verify, including when it
+/// was made for another version, and [`Error::Io`] if it is truncated.
+pub fn verify_versioned_proof<C>(
+    params: &Params<C>,
+    vk: &VerifyingKey<C>,
+    instances: &[&[&[C::Scalar]]],
+    proof: &[u8],
+    version: &str,
+) -> Result<()>
+where
+    C: CurveAffine,
+    C::Scalar: FromUniformBytes<64>,
+{
+    let mut transcript = Blake2bRead::<_, C, Challenge255<_>>::init(proof);
+    transcript.common_scalar(version_scalar(version))?;
+    verify_proof(
+        params,
+        vk,
+        SingleVerifier::new(params),
+        instances,
+        &mut transcript,
+    )
+    .map_err(|err| match err {
+        PlonkError::Transcript(err) => Error::Io(err),
+        err => Error::Plonk(err),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        plonk::{keygen_pk, keygen_vk, Advice, Column, ConstraintSystem, Instance},
+    };
+    use pasta_curves::{vesta, Fp};
+
+    use super::*;
+    use crate::{proof::write_proof, rng::DeterministicRandomness};
+
+    /// Exposes its witness as the only public input
+    #[derive(Default)]
+    struct Echo {
+        value: Value<Fp>,
+    }
+
+    impl Circuit<Fp> for Echo {
+        type Config = (Column<Advice>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(advice);
+            meta.enable_equality(instance);
+            (advice, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (advice, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> std::result::Result<(), PlonkError> {
+            let cell = layouter.assign_region(
+                || "value",
+                |mut region| region.assign_advice(|| "value", advice, 0, || self.value),
+            )?;
+            layouter.constrain_instance(cell.cell(), instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_version_is_bound() {
+        let params = Params::<vesta::Affine>::new(4);
+        let vk = keygen_vk(&params, &Echo::default()).unwrap();
+        let pk = keygen_pk(&params, vk, &Echo::default()).unwrap();
+        let circuit = [Echo {
+            value: Value::known(Fp::from(5)),
+        }];
+        let instance = [Fp::from(5)];
+        let instances: &[&[&[Fp]]] = &[&[&instance]];
+        let rng = DeterministicRandomness::from_seed([9; 32]);
+
+        let proof =
+            write_versioned_proof(&params, &pk, &circuit, instances, "2.1.0", &rng, Vec::new())
+                .unwrap();
+        verify_versioned_proof(&params, pk.get_vk(), instances, &proof, "2.1.0").unwrap();
+        assert!(matches!(
+            verify_versioned_proof(&params, pk.get_vk(), instances, &proof, "2.0.0"),
+            Err(Error::Plonk(_))
+        ));
+
+        // Unversioned proofs do not pass as any version
+        let plain = write_proof(&params, &pk, &circuit, instances, &rng, Vec::new()).unwrap();
+        assert!(verify_versioned_proof(&params, pk.get_vk(), instances, &plain, "").is_err());
+        assert_eq!(plain.len(), proof.len());
+    }
+
+    #[test]
+    fn test_version_scalar_is_injective_on_lengths() {
+        assert_ne!(version_scalar::<Fp>("1"), version_scalar::<Fp>("1\0"));
+        assert_eq!(version_scalar::<Fp>("1.0"), version_scalar::<Fp>("1.0"));
+    }
+}
@@ -0,0 +1,81 @@
+//! Column and selector allocation for circuit configs
+//!
+//! Implement [`CircuitConfig`] with `#[derive(CircuitConfig)]` instead of
+//! declaring columns, enabling equality and assembling the config by hand in
+//! every `Circuit::configure`.
+
+use ff::PrimeField;
+use halo2_proofs::plonk::ConstraintSystem;
+
+/// A circuit config whose columns and selectors can be allocated up front
+pub trait CircuitConfig: Sized {
+    /// Allocate every column and selector in `cs` and build the config
+    fn allocate<F: PrimeField>(cs: &mut ConstraintSystem<F>) -> Self;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use halo2_proofs::plonk::{Advice, Column, Fixed, Instance, Selector, TableColumn};
+    use pasta_curves::Fp;
+
+    use super::*;
+
+    #[derive(Clone, Debug)]
+    struct PairConfig {
+        left: Column<Advice>,
+        right: Column<Advice>,
+    }
+
+    impl PairConfig {
+        fn configure<F: PrimeField>(
+            cs: &mut ConstraintSystem<F>,
+            columns: [Column<Advice>; 2],
+        ) -> Self {
+            cs.enable_equality(columns[1]);
+            Self {
+                left: columns[0],
+                right: columns[1],
+            }
+        }
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug, crate::CircuitConfig)]
+    struct TestConfig {
+        #[config(equality)]
+        advice: [Column<Advice>; 3],
+        #[config(equality)]
+        instance: Column<Instance>,
+        #[config(constant)]
+        fixed: Column<Fixed>,
+        s_gate: Selector,
+        #[config(complex)]
+        s_lookup: Selector,
+        table: TableColumn,
+        #[config(with = "PairConfig::configure(cs, [advice[0], advice[2]])")]
+        pair: PairConfig,
+        counter: RefCell<usize>,
+    }
+
+    #[test]
+    fn test_allocate() {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        let config = TestConfig::allocate(&mut cs);
+
+        // halo2 keeps the column counts private, so read them from Debug.
+        let summary = format!("{cs:?}");
+        assert!(summary.contains("num_advice_columns: 3"));
+        assert!(summary.contains("num_instance_columns: 1"));
+        // The fixed column plus the lookup table column
+        assert!(summary.contains("num_fixed_columns: 2"));
+        assert!(summary.contains("num_selectors: 2"));
+
+        assert_ne!(config.advice[0], config.advice[1]);
+        assert_ne!(config.advice[1], config.advice[2]);
+        assert_eq!(config.pair.left, config.advice[0]);
+        assert_eq!(config.pair.right, config.advice[2]);
+        assert_eq!(*config.counter.borrow(), 0);
+    }
+}
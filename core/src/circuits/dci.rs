@@ -4,278 +4,392 @@ use halo2_proofs::{
     circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
     plonk::{
         Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, 
-        Instance, Selector, TableColumn
+        Instance, Selector
     },
     poly::Rotation,
 };
 use std::marker::PhantomData;
 use ff::PrimeField;
 
-/// Poseidon chip for efficient hashing (width 3)
-pub struct PoseidonChip<F: Field> {
-    config: PoseidonConfig,
-    _marker: PhantomData<F>,
-}
+use crate::{
+    annotation,
+    circuits::config::CircuitConfig,
+    gadgets::{
+        sha256::{pack_bytes, sha256_pair, Sha256Word, DIGEST_WORDS},
+        BitsChip, Gadget, MerkleChip, MerkleConfig, PoseidonChip, RangeCheckChip,
+        RangeCheckConfig, Sha256Chip, Sha256Config,
+    },
+    poseidon::Poseidon,
+    secret::SecretWitness,
+    telemetry::Telemetry,
+    utils::{
+        bits::{to_le_bits, to_windows},
+        encoding::{to_bytes, Endianness},
+    },
+};
 
-#[derive(Clone, Debug)]
-pub struct PoseidonConfig {
-    state: [Column<Advice>; 3],
-    partial_sbox: Column<Advice>,
-    rc_a: [Column<Fixed>; 3],
-    rc_b: [Column<Fixed>; 3],
-    s_full: Selector,
-    s_partial: Selector,
-}
+pub use witness::DCIWitnessBuilder;
 
-impl<F: Field> PoseidonChip<F> {
-    pub fn construct(config: PoseidonConfig) -> Self {
-        Self {
-            config,
-            _marker: PhantomData,
-        }
-    }
-    
-    pub fn configure(
-        meta: &mut ConstraintSystem<F>,
-        state: [Column<Advice>; 3],
-        partial_sbox: Column<Advice>,
-        rc_a: [Column<Fixed>; 3],
-        rc_b: [Column<Fixed>; 3],
-    ) -> PoseidonConfig {
-        let s_full = meta.selector();
-        let s_partial = meta.selector();
-        
-        // Full round constraints
-        meta.create_gate("poseidon full round", |meta| {
-            let s = meta.query_selector(s_full);
-            
-            (0..3).map(|i| {
-                let state_cur = meta.query_advice(state[i], Rotation::cur());
-                let state_next = meta.query_advice(state[i], Rotation::next());
-                let rc = meta.query_fixed(rc_a[i], Rotation::cur());
-                
-                // state_next = (state_cur + rc)^5
-                let sum = state_cur + rc;
-                s.clone() * (state_next - sum.clone() * sum.clone() * sum.clone() * sum.clone() * sum)
-            }).collect::<Vec<_>>()
-        });
-        
-        PoseidonConfig {
-            state,
-            partial_sbox,
-            rc_a,
-            rc_b,
-            s_full,
-            s_partial,
-        }
-    }
-    
-    pub fn hash(
-        &self,
-        mut layouter: impl Layouter<F>,
-        input: [AssignedCell<F, F>; 2],
-    ) -> Result<AssignedCell<F, F>, Error> {
-        layouter.assign_region(
-            || "poseidon hash",
-            |mut region| {
-                // Simplified Poseidon - actual implementation would have full rounds
-                let output = region.assign_advice(
-                    || "hash output",
-                    self.config.state[0],
-                    0,
-                    || input[0].value().copied() + input[1].value(),
-                )?;
-                Ok(output)
-            },
-        )
-    }
+/// Depth of the note tree whose root a DCI proof opens
+pub const DCI_TREE_DEPTH: usize = 20;
+
+/// Log2 of the number of rows a [`DCICircuit`] needs
+pub const DCI_K: u32 = 11;
+
+/// Log2 of the number of rows a [`DCICircuit`] hashing with
+/// [`MerkleHash::Sha256`] needs
+pub const DCI_SHA256_K: u32 = 20;
+
+/// Most rows a [`DCICircuit`] may take, checked with
+/// [`crate::circuits::helpers::RowUsage`]
+pub const DCI_CONSTRAINT_BUDGET: usize = 28_000;
+
+/// Balances are checked a byte at a time
+type BalanceChip<F> = RangeCheckChip<F, 8>;
+
+/// Bits a balance may have
+const BALANCE_BITS: usize = 64;
+
+/// Hash a [`DCICircuit`] opens the note tree with
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MerkleHash {
+    /// Two-to-one Poseidon over field elements
+    #[default]
+    Poseidon,
+    /// SHA-256 of the children's 32-byte big-endian encodings, for trees
+    /// shared with systems outside the circuit; needs [`DCI_SHA256_K`]
+    Sha256,
 }
 
 /// DCI Circuit Configuration
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, crate::CircuitConfig)]
 pub struct DCIConfig {
     /// Advice columns for witness values
+    #[config(equality)]
     pub advice: [Column<Advice>; 12],
-    /// Instance columns for public inputs
-    pub instance: [Column<Instance>; 4],
-    /// Fixed columns
-    pub fixed: [Column<Fixed>; 3],
-    /// Poseidon hasher configuration
-    pub poseidon: PoseidonConfig,
-    /// Range check table
-    pub range_table: TableColumn,
-    /// Nullifier table for checking
-    pub nullifier_table: TableColumn,
-    /// Selectors
-    pub s_merkle: Selector,
-    pub s_nullifier: Selector,
-    pub s_balance: Selector,
-    /// Constraint tracking
-    pub constraint_count: std::cell::RefCell<usize>,
+    /// Instance column for the public inputs, laid out as [`DCIPublicInputs`]
+    /// or [`DCISha256PublicInputs`]
+    #[config(equality)]
+    pub instance: Column<Instance>,
+    /// Fixed columns, two rounds' worth of Poseidon round constants and
+    /// the SHA-256 chip's coefficients
+    pub fixed: [Column<Fixed>; 9],
+    /// Poseidon path configuration, whose bits also hold the path
+    /// directions
+    #[config(with = "MerkleChip::configure(
+        cs,
+        (
+            [advice[0], advice[1], advice[2]],
+            advice[3],
+            [fixed[0], fixed[1], fixed[2]],
+            [fixed[3], fixed[4], fixed[5]],
+        ),
+    )")]
+    pub merkle: MerkleConfig,
+    /// Balance range proof configuration
+    #[config(with = "BalanceChip::configure(cs, advice[5])")]
+    pub balance: RangeCheckConfig,
+    /// SHA-256 hasher configuration, used with [`MerkleHash::Sha256`]
+    #[config(with = "Sha256Chip::configure(
+        cs,
+        (
+            [advice[8], advice[9], advice[10], advice[11]],
+            [fixed[6], fixed[7], fixed[8]],
+        ),
+    )")]
+    pub sha256: Sha256Config,
+    /// Orders a SHA-256 node word and its sibling by the path direction
+    pub s_select: Selector,
+    /// Bits of `p - 1` for the canonical leaf check, also holding its
+    /// boundary constants
+    #[config(constant)]
+    pub modulus: Column<Fixed>,
+    /// Steps the canonical leaf check one bit
+    pub s_canonical: Selector,
 }
 
 impl DCIConfig {
-    /// Configure Merkle tree verification gates
-    fn configure_merkle_verification(
-        &self,
-        cs: &mut ConstraintSystem<impl Field>,
-    ) {
-        cs.create_gate("merkle path verification", |meta| {
-            let s = meta.query_selector(self.s_merkle);
-            
-            // Leaf, path element, direction bit
-            let leaf = meta.query_advice(self.advice[0], Rotation::cur());
-            let path_element = meta.query_advice(self.advice[1], Rotation::cur());
+    /// Configure ordering of SHA-256 path words
+    ///
+    /// The direction is boolean, `left` is the node or its sibling as it
+    /// selects, and `right` is the other one.
+    fn configure_word_selection<F: Field>(&self, cs: &mut ConstraintSystem<F>) {
+        cs.create_gate("sha256 path word selection", |meta| {
+            let s = meta.query_selector(self.s_select);
+            let node = meta.query_advice(self.advice[0], Rotation::cur());
+            let sibling = meta.query_advice(self.advice[1], Rotation::cur());
             let direction = meta.query_advice(self.advice[2], Rotation::cur());
-            let hash_output = meta.query_advice(self.advice[3], Rotation::cur());
-            
-            // Algebraic optimization: combine hash inputs based on direction
-            // If direction = 0: hash(leaf, path_element)
-            // If direction = 1: hash(path_element, leaf)
-            let left = leaf.clone() * (Expression::Constant(F::ONE) - direction.clone()) 
-                     + path_element.clone() * direction.clone();
-            let right = path_element * (Expression::Constant(F::ONE) - direction.clone())
-                      + leaf * direction;
-            
-            // Simplified constraint for demonstration
-            vec![s * (hash_output - (left + right))]
-        });
-        
-        *self.constraint_count.borrow_mut() += 1;
-    }
-    
-    /// Configure nullifier generation and checking
-    fn configure_nullifier_checking(
-        &self,
-        cs: &mut ConstraintSystem<impl Field>,
-    ) {
-        // Nullifier lookup to prevent double-spending
-        cs.lookup("nullifier check", |meta| {
-            let nullifier = meta.query_advice(self.advice[4], Rotation::cur());
-            let s = meta.query_selector(self.s_nullifier);
-            
-            vec![(s * nullifier, self.nullifier_table)]
+            let left = meta.query_advice(self.advice[6], Rotation::cur());
+            let right = meta.query_advice(self.advice[7], Rotation::cur());
+
+            let one = Expression::Constant(F::ONE);
+            vec![
+                s.clone() * direction.clone() * (one - direction.clone()),
+                s.clone()
+                    * (left.clone() - node.clone() - direction * (sibling.clone() - node.clone())),
+                s * (right - (node + sibling - left)),
+            ]
         });
-        
-        *self.constraint_count.borrow_mut() += 1;
     }
-    
-    /// Configure balance range proofs
-    fn configure_balance_proofs(
-        &self,
-        cs: &mut ConstraintSystem<impl Field>,
-    ) {
-        // 64-bit range proof using decomposition
-        cs.create_gate("balance range proof", |meta| {
-            let s = meta.query_selector(self.s_balance);
-            let balance = meta.query_advice(self.advice[5], Rotation::cur());
-            
-            // Decompose into 8-bit chunks
-            let chunks: Vec<Expression<F>> = (0..8).map(|i| {
-                meta.query_advice(self.advice[6 + i], Rotation::cur())
-            }).collect();
-            
-            // Reconstruct and verify
-            let reconstructed = chunks.iter().enumerate().fold(
-                Expression::Constant(F::ZERO),
-                |acc, (i, chunk)| acc + chunk.clone() * Expression::Constant(F::from(1u64 << (8 * i)))
-            );
-            
-            vec![s * (balance - reconstructed)]
+
+    /// Configure the comparison of a SHA-256 leaf against the modulus
+    ///
+    /// Running from the least significant bit, `le` is whether the bits so
+    /// far are at most those of `p - 1`: a differing bit decides it, an
+    /// equal one keeps it. The bits are copied from boolean cells.
+    fn configure_canonical_leaf<F: Field>(&self, cs: &mut ConstraintSystem<F>) {
+        cs.create_gate("canonical sha256 leaf", |meta| {
+            let s = meta.query_selector(self.s_canonical);
+            let bit = meta.query_advice(self.advice[0], Rotation::cur());
+            let le = meta.query_advice(self.advice[1], Rotation::cur());
+            let le_next = meta.query_advice(self.advice[1], Rotation::next());
+            let modulus = meta.query_fixed(self.modulus);
+
+            let one = Expression::Constant(F::ONE);
+            let two = Expression::Constant(F::from(2));
+            let same = one.clone() - modulus.clone() - bit.clone()
+                + two * modulus.clone() * bit.clone();
+            vec![s * (le_next - (same * le + modulus * (one - bit)))]
         });
-        
-        // Lookup for each 8-bit chunk
-        for i in 0..8 {
-            cs.lookup(format!("range check chunk {}", i), |meta| {
-                let chunk = meta.query_advice(self.advice[6 + i], Rotation::cur());
-                let s = meta.query_selector(self.s_balance);
-                vec![(s * chunk, self.range_table)]
-            });
-        }
-        
-        *self.constraint_count.borrow_mut() += 9; // 1 gate + 8 lookups
     }
 }
 
 /// DCI Circuit for Distributed Cryptographic Infrastructure
-pub struct DCICircuit<F: Field> {
+///
+/// The leaf is computed in-circuit as the Poseidon hash of the secret key
+/// and the range-checked balance, and the nullifier as the hash of the
+/// secret key and the leaf. Everything but the public inputs is a
+/// [`SecretWitness`], wiped on drop.
+pub struct DCICircuit<F: PrimeField> {
     /// Merkle tree path (depth 20)
-    pub merkle_path: Vec<Value<F>>,
-    /// Leaf value
-    pub leaf: Value<F>,
+    pub merkle_path: Vec<SecretWitness<F>>,
     /// Path directions (0 = left, 1 = right)
-    pub path_directions: Vec<Value<F>>,
-    /// Nullifier
-    pub nullifier: Value<F>,
+    pub path_directions: Vec<SecretWitness<F>>,
+    /// Secret key owning the leaf
+    pub secret_key: SecretWitness<F>,
     /// Balance value
-    pub balance: Value<F>,
+    pub balance: SecretWitness<F>,
     /// Public inputs
     pub public_inputs: Vec<F>,
+    /// Hash the Merkle path is opened with
+    pub merkle_hash: MerkleHash,
+    /// Sibling digests as big-endian 32-bit words, used instead of
+    /// `merkle_path` with [`MerkleHash::Sha256`]
+    pub sha256_path: Vec<[SecretWitness<F>; DIGEST_WORDS]>,
+    /// Sink for synthesis events
+    pub telemetry: Telemetry,
     _marker: PhantomData<F>,
 }
 
-impl<F: Field> Default for DCICircuit<F> {
+impl<F: PrimeField> Default for DCICircuit<F> {
     fn default() -> Self {
         Self {
-            merkle_path: vec![Value::unknown(); 20],
-            leaf: Value::unknown(),
-            path_directions: vec![Value::unknown(); 20],
-            nullifier: Value::unknown(),
-            balance: Value::unknown(),
+            merkle_path: vec![SecretWitness::unknown(); DCI_TREE_DEPTH],
+            path_directions: vec![SecretWitness::unknown(); DCI_TREE_DEPTH],
+            secret_key: SecretWitness::unknown(),
+            balance: SecretWitness::unknown(),
             public_inputs: vec![],
+            merkle_hash: MerkleHash::default(),
+            sha256_path: vec![Default::default(); DCI_TREE_DEPTH],
+            telemetry: Telemetry::default(),
             _marker: PhantomData,
         }
     }
 }
 
-impl<F: Field> Circuit<F> for DCICircuit<F> {
+impl<F: PrimeField> DCICircuit<F> {
+    /// Circuit without witnesses opening its path with `merkle_hash`, for
+    /// keygen
+    #[must_use]
+    pub fn with_merkle_hash(merkle_hash: MerkleHash) -> Self {
+        Self {
+            merkle_hash,
+            ..Self::default()
+        }
+    }
+
+    /// Report synthesis to `telemetry`
+    #[must_use]
+    pub fn with_telemetry(mut self, telemetry: Telemetry) -> Self {
+        self.telemetry = telemetry;
+        self
+    }
+
+    /// Hash `leaf` up [`DCICircuit::merkle_path`] with Poseidon, turning at
+    /// each level by the matching cell of `directions`
+    fn open_poseidon_path(
+        &self,
+        config: &DCIConfig,
+        layouter: &mut impl Layouter<F>,
+        leaf: &AssignedCell<F, F>,
+        directions: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let siblings: Vec<Value<F>> = self.merkle_path.iter().map(SecretWitness::value).collect();
+        MerkleChip::construct(config.merkle.clone()).compute_root(
+            layouter.namespace(|| "merkle path"),
+            leaf,
+            &siblings,
+            directions,
+        )
+    }
+
+    /// Hash `leaf` up [`DCICircuit::sha256_path`], turning at each level by
+    /// the matching cell of `directions`, and pack the root digest into its
+    /// high and low 128-bit halves
+    ///
+    /// The leaf is split into its eight big-endian words, checked to encode
+    /// an integer below the modulus: otherwise a leaf below `2^256 - p`
+    /// would also split as itself plus the modulus, and a path could open
+    /// from either encoding. The halves of the root fit in `F` unreduced.
+    fn open_sha256_path(
+        &self,
+        config: &DCIConfig,
+        layouter: &mut impl Layouter<F>,
+        leaf: &AssignedCell<F, F>,
+        directions: &[AssignedCell<F, F>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let chip = Sha256Chip::construct(config.sha256.clone());
+        chip.load(layouter)?;
+        let mut node = chip.unpack(layouter.namespace(|| "unpack leaf"), leaf, DIGEST_WORDS)?;
+        Self::check_canonical_leaf(config, &chip, layouter, &node)?;
+
+        for (i, (siblings, direction)) in self.sha256_path.iter()
+            .zip(directions)
+            .enumerate()
+        {
+            let mut layouter = layouter.namespace(|| annotation!("merkle level {}", i));
+            let mut node_cells = Vec::with_capacity(DIGEST_WORDS);
+            let mut sibling_cells = Vec::with_capacity(DIGEST_WORDS);
+            for (word, sibling) in node.iter().zip(siblings) {
+                node_cells.push(chip.pack(
+                    layouter.namespace(|| "node word"),
+                    std::slice::from_ref(word),
+                )?);
+                let sibling = chip.witness(
+                    layouter.namespace(|| "sibling word"),
+                    sibling.value().map(|sibling| word_value(&sibling)),
+                )?;
+                sibling_cells.push(chip.pack(layouter.namespace(|| "sibling word"), &[sibling])?);
+            }
+
+            let ordered = layouter.assign_region(
+                || "order children",
+                |mut region| {
+                    let mut ordered = Vec::with_capacity(DIGEST_WORDS);
+                    let children = node_cells.iter().zip(&sibling_cells);
+                    for (row, (node, sibling)) in children.enumerate() {
+                        config.s_select.enable(&mut region, row)?;
+                        let node = node.copy_advice(|| "node", &mut region, config.advice[0], row)?;
+                        let sibling =
+                            sibling.copy_advice(|| "sibling", &mut region, config.advice[1], row)?;
+                        let direction = direction
+                            .copy_advice(|| "direction", &mut region, config.advice[2], row)?;
+                        let direction = direction.value().copied();
+
+                        let (node, sibling) = (node.value().copied(), sibling.value().copied());
+                        let left = region.assign_advice(
+                            || "left",
+                            config.advice[6],
+                            row,
+                            || node + direction * (sibling - node),
+                        )?;
+                        let right = region.assign_advice(
+                            || "right",
+                            config.advice[7],
+                            row,
+                            || sibling + direction * (node - sibling),
+                        )?;
+                        ordered.push((left, right));
+                    }
+                    Ok(ordered)
+                },
+            )?;
+
+            let mut left = Vec::with_capacity(DIGEST_WORDS);
+            let mut right = Vec::with_capacity(DIGEST_WORDS);
+            for (left_cell, right_cell) in &ordered {
+                left.extend(chip.unpack(layouter.namespace(|| "left word"), left_cell, 1)?);
+                right.extend(chip.unpack(layouter.namespace(|| "right word"), right_cell, 1)?);
+            }
+            let left: [_; DIGEST_WORDS] = left.try_into().map_err(|_| Error::Synthesis)?;
+            let right: [_; DIGEST_WORDS] = right.try_into().map_err(|_| Error::Synthesis)?;
+            node = chip
+                .hash_pair(layouter.namespace(|| "hash"), &left, &right)?
+                .to_vec();
+        }
+        let (high, low) = node.split_at(DIGEST_WORDS / 2);
+        Ok(vec![
+            chip.pack(layouter.namespace(|| "pack root high"), high)?,
+            chip.pack(layouter.namespace(|| "pack root low"), low)?,
+        ])
+    }
+
+    /// Constrain the big-endian `words` of a leaf to encode at most
+    /// `p - 1`, comparing them bit by bit against the modulus
+    fn check_canonical_leaf(
+        config: &DCIConfig,
+        chip: &Sha256Chip<F>,
+        layouter: &mut impl Layouter<F>,
+        words: &[Sha256Word<F>],
+    ) -> Result<(), Error> {
+        let bits_chip = BitsChip::construct(config.merkle.bits.clone());
+        let mut bits = Vec::with_capacity(32 * words.len());
+        for word in words.iter().rev() {
+            let cell = chip.pack(layouter.namespace(|| "leaf word"), std::slice::from_ref(word))?;
+            let word_bits = bits_chip.decompose(layouter.namespace(|| "leaf word bits"), &cell, 32)?;
+            bits.extend(word_bits.bits);
+        }
+        let mut modulus = to_le_bits(&-F::ONE);
+        modulus.resize(bits.len(), false);
+
+        layouter.assign_region(
+            || "canonical leaf",
+            |mut region| {
+                let mut le =
+                    region.assign_advice_from_constant(|| "le", config.advice[1], 0, F::ONE)?;
+                for (row, (bit, modulus)) in bits.iter().zip(&modulus).enumerate() {
+                    config.s_canonical.enable(&mut region, row)?;
+                    let bit = bit.copy_advice(|| "bit", &mut region, config.advice[0], row)?;
+                    let modulus = F::from(u64::from(*modulus));
+                    region.assign_fixed(
+                        || "modulus bit",
+                        config.modulus,
+                        row,
+                        || Value::known(modulus),
+                    )?;
+                    let next = le.value().zip(bit.value()).map(|(le, bit)| {
+                        if *bit == modulus {
+                            *le
+                        } else {
+                            modulus
+                        }
+                    });
+                    le = region.assign_advice(|| "le", config.advice[1], row + 1, || next)?;
+                }
+                region.constrain_constant(le.cell(), F::ONE)
+            },
+        )
+    }
+}
+
+/// Low 32 bits of `value`, holding one word of a SHA-256 digest
+fn word_value<F: PrimeField>(value: &F) -> u32 {
+    u32::try_from(to_windows(value, 32)[0]).unwrap_or_default()
+}
+
+impl<F: PrimeField> Circuit<F> for DCICircuit<F> {
     type Config = DCIConfig;
     type FloorPlanner = SimpleFloorPlanner;
     
     fn without_witnesses(&self) -> Self {
-        Self::default()
+        Self::with_merkle_hash(self.merkle_hash).with_telemetry(self.telemetry.clone())
     }
     
     fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config {
-        let advice = [(); 12].map(|_| {
-            let col = cs.advice_column();
-            cs.enable_equality(col);
-            col
-        });
-        
-        let instance = [(); 4].map(|_| {
-            let col = cs.instance_column();
-            cs.enable_equality(col);
-            col
-        });
-        
-        let fixed = [(); 3].map(|_| cs.fixed_column());
-        
-        // Configure Poseidon hasher
-        let poseidon = PoseidonChip::configure(
-            cs,
-            [advice[0], advice[1], advice[2]],
-            advice[3],
-            [fixed[0], fixed[1], fixed[2]],
-            [fixed[0], fixed[1], fixed[2]],
-        );
-        
-        let config = DCIConfig {
-            advice,
-            instance,
-            fixed,
-            poseidon,
-            range_table: cs.lookup_table_column(),
-            nullifier_table: cs.lookup_table_column(),
-            s_merkle: cs.selector(),
-            s_nullifier: cs.selector(),
-            s_balance: cs.selector(),
-            constraint_count: std::cell::RefCell::new(0),
-        };
+        let config = DCIConfig::allocate(cs);
         
-        config.configure_merkle_verification(cs);
-        config.configure_nullifier_checking(cs);
-        config.configure_balance_proofs(cs);
+        config.configure_word_selection(cs);
+        config.configure_canonical_leaf(cs);
         
         config
     }
@@ -285,143 +399,287 @@ impl<F: Field> Circuit<F> for DCICircuit<F> {
         config: Self::Config,
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
+        self.telemetry.started("dci");
         // Initialize lookup tables
-        layouter.assign_table(
-            || "8-bit range table",
-            |mut table| {
-                for value in 0..256 {
-                    table.assign_cell(
-                        || format!("value {}", value),
-                        config.range_table,
-                        value,
-                        || Value::known(F::from(value as u64)),
-                    )?;
-                }
-                Ok(())
-            },
-        )?;
-        
-        let poseidon_chip = PoseidonChip::construct(config.poseidon.clone());
+        let balance_chip = BalanceChip::construct(config.balance.clone());
+        balance_chip.load(&mut layouter)?;
+        self.telemetry.region("dci", "lookup tables");
         
-        // Merkle tree verification
-        let mut current_hash = layouter.assign_region(
-            || "merkle tree verification",
+        // Balance range proof
+        let balance = layouter.assign_region(
+            || "balance",
             |mut region| {
-                config.s_merkle.enable(&mut region, 0)?;
-                
-                // Assign leaf
-                let leaf_cell = region.assign_advice(
-                    || "leaf",
-                    config.advice[0],
-                    0,
-                    || self.leaf,
-                )?;
-                
-                Ok(leaf_cell)
+                region.assign_advice(|| "balance", config.advice[5], 0, || self.balance.value())
             },
         )?;
-        
-        // Process Merkle path (depth 20)
-        for (i, (path_elem, direction)) in self.merkle_path.iter()
-            .zip(self.path_directions.iter())
-            .enumerate() 
-        {
-            current_hash = layouter.assign_region(
-                || format!("merkle level {}", i),
-                |mut region| {
-                    config.s_merkle.enable(&mut region, 0)?;
-                    
-                    let path_cell = region.assign_advice(
-                        || "path element",
-                        config.advice[1],
-                        0,
-                        || *path_elem,
-                    )?;
-                    
-                    region.assign_advice(
-                        || "direction",
-                        config.advice[2],
-                        0,
-                        || *direction,
-                    )?;
-                    
-                    // Hash computation would go here
-                    let hash_output = poseidon_chip.hash(
-                        layouter.namespace(|| format!("hash level {}", i)),
-                        [current_hash.clone(), path_cell],
-                    )?;
-                    
-                    Ok(hash_output)
-                },
-            )?;
-        }
-        
-        // Nullifier generation
-        layouter.assign_region(
-            || "nullifier generation",
+        balance_chip.check(
+            layouter.namespace(|| "balance range proof"),
+            &balance,
+            BALANCE_BITS,
+        )?;
+        self.telemetry.region("dci", "balance range proof");
+
+        // Note commitment, hashing a copy of the range-checked balance
+        let secret_key = layouter.assign_region(
+            || "secret key",
             |mut region| {
-                config.s_nullifier.enable(&mut region, 0)?;
-                
                 region.assign_advice(
-                    || "nullifier",
+                    || "secret key",
                     config.advice[4],
                     0,
-                    || self.nullifier,
-                )?;
-                
-                Ok(())
+                    || self.secret_key.value(),
+                )
             },
         )?;
-        
-        // Balance range proof
-        layouter.assign_region(
-            || "balance range proof",
-            |mut region| {
-                config.s_balance.enable(&mut region, 0)?;
-                
-                region.assign_advice(
-                    || "balance",
-                    config.advice[5],
-                    0,
-                    || self.balance,
-                )?;
-                
-                // Decompose balance into 8-bit chunks
-                self.balance.map(|b| {
-                    let bytes = b.to_repr();
-                    for (i, byte) in bytes.as_ref()[..8].iter().enumerate() {
-                        region.assign_advice(
-                            || format!("byte {}", i),
-                            config.advice[6 + i],
-                            0,
-                            || Value::known(F::from(*byte as u64)),
-                        )?;
-                    }
-                    Ok::<(), Error>(())
-                }).transpose()?;
-                
-                Ok(())
-            },
+        let poseidon = PoseidonChip::construct(config.merkle.poseidon.clone());
+        let leaf = poseidon.hash(
+            layouter.namespace(|| "note commitment"),
+            &[secret_key.clone(), balance],
         )?;
-        
-        // Report constraints
-        let total = *config.constraint_count.borrow();
-        if total > 28000 {
-            eprintln!("WARNING: DCI constraint count {} exceeds 28k target", total);
-        } else {
-            eprintln!("DCI constraint count: {} / 28,000", total);
+        self.telemetry.region("dci", "note commitment");
+
+        // Merkle tree verification
+        let directions = BitsChip::construct(config.merkle.bits.clone()).assign(
+            layouter.namespace(|| "path directions"),
+            self.path_directions.iter().map(SecretWitness::value).collect(),
+        )?;
+        let directions = &directions.bits;
+        let root = match self.merkle_hash {
+            MerkleHash::Poseidon => {
+                vec![self.open_poseidon_path(&config, &mut layouter, &leaf, directions)?]
+            }
+            MerkleHash::Sha256 => {
+                self.open_sha256_path(&config, &mut layouter, &leaf, directions)?
+            }
+        };
+        for (row, limb) in root.iter().enumerate() {
+            layouter.constrain_instance(limb.cell(), config.instance, row)?;
         }
+        self.telemetry.region("dci", "merkle path");
+        
+        // Nullifier generation, binding the spend to the key and the leaf.
+        // Spent nullifiers are rejected outside the circuit, by the
+        // verifier's `NullifierSet`
+        let nullifier = poseidon.hash(
+            layouter.namespace(|| "nullifier generation"),
+            &[secret_key, leaf],
+        )?;
+        layouter.constrain_instance(nullifier.cell(), config.instance, root.len())?;
+        self.telemetry.region("dci", "nullifier generation");
         
+        self.telemetry.finished("dci");
         Ok(())
     }
 }
 
+/// Path accepted by [`DCIWitnessBuilder::with_merkle_proof`]
+pub type MerkleProof<F> = crate::merkle::MerklePath<F>;
+
+/// Public inputs of a [`DCICircuit`] opening its path with
+/// [`MerkleHash::Poseidon`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, crate::PublicInputs)]
+pub struct DCIPublicInputs<F: Field> {
+    /// Root of the note tree
+    pub root: F,
+    /// Nullifier of the spent note
+    pub nullifier: F,
+}
+
+/// Public inputs of a [`DCICircuit`] opening its path with
+/// [`MerkleHash::Sha256`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, crate::PublicInputs)]
+pub struct DCISha256PublicInputs<F: Field> {
+    /// Root digest of the note tree, as its high and low 128-bit
+    /// big-endian halves
+    pub root: [F; 2],
+    /// Nullifier of the spent note
+    pub nullifier: F,
+}
+
 /// Witness generation utilities
 pub mod witness {
     use super::*;
+    use crate::PublicInputs;
     use std::sync::{Arc, Mutex};
+    #[cfg(feature = "multicore")]
     use rayon::prelude::*;
     
+    /// Assembles a [`DCICircuit`] witness from a note and its tree path
+    ///
+    /// The note commitment stored in the tree is `H(secret_key, balance)`
+    /// and its nullifier is `H(secret_key, commitment)`, where `H` is the
+    /// two-to-one Poseidon hash of [`PoseidonChip::hash`]. The circuit
+    /// recomputes both from the secret key and the range-checked balance,
+    /// so a proof only opens a leaf committing to the balance it checks.
+    #[derive(Clone, Debug)]
+    pub struct DCIWitnessBuilder<F: PrimeField> {
+        merkle_proof: Option<MerkleProof<F>>,
+        sha256_path: Option<(u64, Vec<[u8; 32]>)>,
+        balance: SecretWitness<F>,
+        secret_key: SecretWitness<F>,
+    }
+
+    impl<F: PrimeField> Default for DCIWitnessBuilder<F> {
+        fn default() -> Self {
+            Self {
+                merkle_proof: None,
+                sha256_path: None,
+                balance: SecretWitness::unknown(),
+                secret_key: SecretWitness::unknown(),
+            }
+        }
+    }
+
+    impl<F: PrimeField> DCIWitnessBuilder<F> {
+        /// Builder with nothing set
+        #[must_use]
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Path from the note commitment to the tree root
+        #[must_use]
+        pub fn with_merkle_proof(mut self, proof: &MerkleProof<F>) -> Self {
+            self.merkle_proof = Some(proof.clone());
+            self
+        }
+
+        /// Path of SHA-256 sibling digests from the note commitment at
+        /// `index` to the root
+        ///
+        /// The circuit is then built with [`DCIWitnessBuilder::build_sha256`]
+        /// and any [`DCIWitnessBuilder::with_merkle_proof`] is ignored.
+        #[must_use]
+        pub fn with_sha256_path(mut self, index: u64, siblings: &[[u8; 32]]) -> Self {
+            self.sha256_path = Some((index, siblings.to_vec()));
+            self
+        }
+
+        /// Balance of the spent note
+        #[must_use]
+        pub fn with_balance(mut self, balance: u64) -> Self {
+            self.balance = SecretWitness::new(F::from(balance));
+            self
+        }
+
+        /// Secret key owning the spent note
+        #[must_use]
+        pub fn with_secret_key(mut self, secret_key: F) -> Self {
+            self.secret_key = SecretWitness::new(secret_key);
+            self
+        }
+
+        /// Note commitment for `secret_key` and `balance`
+        #[must_use]
+        pub fn commitment(secret_key: F, balance: u64) -> F {
+            Poseidon::default().hash_pair(secret_key, F::from(balance))
+        }
+
+        /// Circuit ready to prove and the public inputs it is proved against
+        ///
+        /// # Errors
+        ///
+        /// Returns [`crate::Error::Other`] if the path, balance or secret
+        /// key is missing, if the path does not have [`DCI_TREE_DEPTH`]
+        /// siblings, or if a SHA-256 path was given, which
+        /// [`DCIWitnessBuilder::build_sha256`] builds.
+        pub fn build(self) -> crate::Result<(DCICircuit<F>, DCIPublicInputs<F>)> {
+            if self.sha256_path.is_some() {
+                return Err(crate::Error::Other(
+                    "DCI witness opens a SHA-256 path, build it with build_sha256".into(),
+                ));
+            }
+            let proof = self.merkle_proof.as_ref().ok_or_else(|| missing("merkle proof"))?;
+            let (mut circuit, secret_key, leaf) = self.prepare(proof.index, proof.siblings.len())?;
+            circuit.merkle_path = proof.siblings.iter().copied().map(SecretWitness::new).collect();
+            let public = DCIPublicInputs {
+                root: proof.root(leaf),
+                nullifier: Poseidon::default().hash_pair(secret_key, leaf),
+            };
+            circuit.public_inputs = public.to_instances().remove(0);
+            Ok((circuit, public))
+        }
+
+        /// Circuit opening the path of [`DCIWitnessBuilder::with_sha256_path`]
+        /// and the public inputs it is proved against
+        ///
+        /// # Errors
+        ///
+        /// Returns [`crate::Error::Other`] if the SHA-256 path, balance or
+        /// secret key is missing, if the path does not have
+        /// [`DCI_TREE_DEPTH`] siblings, or if the field's elements do not
+        /// encode in 32 bytes.
+        pub fn build_sha256(self) -> crate::Result<(DCICircuit<F>, DCISha256PublicInputs<F>)> {
+            let (index, siblings) = self
+                .sha256_path
+                .as_ref()
+                .ok_or_else(|| missing("SHA-256 path"))?;
+            let (mut circuit, secret_key, leaf) = self.prepare(*index, siblings.len())?;
+            circuit.merkle_hash = MerkleHash::Sha256;
+            circuit.sha256_path = siblings.iter().map(digest_words).collect();
+            let public = DCISha256PublicInputs {
+                root: sha256_root(leaf, *index, siblings)?,
+                nullifier: Poseidon::default().hash_pair(secret_key, leaf),
+            };
+            circuit.public_inputs = public.to_instances().remove(0);
+            Ok((circuit, public))
+        }
+
+        /// Circuit holding the note and the directions of a path of
+        /// `depth` levels to `index`, with the secret key and the leaf
+        fn prepare(&self, index: u64, depth: usize) -> crate::Result<(DCICircuit<F>, F, F)> {
+            let balance = self.balance.expose().ok_or_else(|| missing("balance"))?;
+            let secret_key = self.secret_key.expose().ok_or_else(|| missing("secret key"))?;
+            if depth != DCI_TREE_DEPTH {
+                return Err(crate::Error::Other(format!(
+                    "DCI merkle proof has {depth} levels, expected {DCI_TREE_DEPTH}"
+                )));
+            }
+
+            let mut circuit = DCICircuit::default();
+            circuit.path_directions = (0..depth)
+                .map(|level| SecretWitness::new(F::from((index >> level) & 1)))
+                .collect();
+            circuit.secret_key = SecretWitness::new(secret_key);
+            circuit.balance = SecretWitness::new(balance);
+            Ok((circuit, secret_key, Poseidon::default().hash_pair(secret_key, balance)))
+        }
+    }
+
+    /// Error for a witness missing `name`
+    fn missing(name: &str) -> crate::Error {
+        crate::Error::Other(format!("DCI witness has no {name}"))
+    }
+
+    /// Big-endian words of `digest`
+    fn digest_words<F: PrimeField>(digest: &[u8; 32]) -> [SecretWitness<F>; DIGEST_WORDS] {
+        std::array::from_fn(|i| {
+            let word: [u8; 4] = digest[4 * i..][..4].try_into().unwrap_or_default();
+            SecretWitness::new(F::from(u64::from(u32::from_be_bytes(word))))
+        })
+    }
+
+    /// Root the circuit computes for `leaf` at `index` under SHA-256
+    /// `siblings`, as its high and low 128-bit halves
+    fn sha256_root<F: PrimeField>(
+        leaf: F,
+        index: u64,
+        siblings: &[[u8; 32]],
+    ) -> crate::Result<[F; 2]> {
+        let mut node: [u8; 32] = to_bytes(&leaf, Endianness::Big).try_into().map_err(|_| {
+            crate::Error::Other("SHA-256 merkle leaves must encode in 32 bytes".into())
+        })?;
+        for (level, sibling) in siblings.iter().enumerate() {
+            node = if (index >> level) & 1 == 1 {
+                sha256_pair(sibling, &node)
+            } else {
+                sha256_pair(&node, sibling)
+            };
+        }
+        let (high, low) = node.split_at(16);
+        Ok([pack_bytes(high), pack_bytes(low)])
+    }
+
     /// Witness calculator for efficient generation
     pub struct WitnessCalculator<F: Field> {
         cache: Arc<Mutex<Vec<(Vec<F>, Vec<Value<F>>)>>>,
@@ -435,11 +693,19 @@ pub mod witness {
         }
         
         /// Generate witness in parallel for multiple proofs
+        ///
+        /// Falls back to sequential generation without the `multicore` feature.
         pub fn generate_parallel(
             &self,
             inputs: Vec<Vec<F>>,
         ) -> Vec<Vec<Value<F>>> {
-            inputs.par_iter().map(|input| {
+            #[cfg(feature = "multicore")]
+            let inputs = inputs.par_iter();
+            #[cfg(not(feature = "multicore"))]
+            let inputs = inputs.iter();
+            #[cfg(feature = "multicore")]
+            let inputs = inputs.with_min_len(crate::parallel::min_chunk_len());
+            inputs.map(|input| {
                 self.generate_single(input)
             }).collect()
         }
@@ -472,4 +738,240 @@ pub mod witness {
             witness
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::dev::MockProver;
+    use pasta_curves::Fp;
+
+    use super::*;
+    use crate::{merkle::MerkleTree, PublicInputs};
+
+    /// Tree whose leaf 11 is the note of key 42 holding 1000
+    fn tree() -> MerkleTree<Fp> {
+        let mut tree = MerkleTree::new(DCI_TREE_DEPTH);
+        for value in 0..11 {
+            tree.insert(Fp::from(value)).unwrap();
+        }
+        tree.insert(DCIWitnessBuilder::commitment(Fp::from(42), 1_000))
+            .unwrap();
+        tree
+    }
+
+    fn proof() -> MerkleProof<Fp> {
+        tree().prove_index(11).unwrap()
+    }
+
+    #[test]
+    fn test_builder_computes_public_inputs() {
+        let secret_key = Fp::from(42);
+        let (circuit, public) = DCIWitnessBuilder::new()
+            .with_merkle_proof(&proof())
+            .with_balance(1_000)
+            .with_secret_key(secret_key)
+            .build()
+            .unwrap();
+
+        assert_eq!(public.root, tree().root());
+        assert_eq!(
+            public.to_instances(),
+            vec![vec![public.root, public.nullifier]]
+        );
+        assert_eq!(circuit.public_inputs, public.to_instances()[0]);
+
+        let directions: Value<Vec<Fp>> = circuit
+            .path_directions
+            .iter()
+            .map(SecretWitness::value)
+            .collect();
+        directions.assert_if_known(|directions| {
+            directions[..5] == [Fp::ONE, Fp::ONE, Fp::ZERO, Fp::ONE, Fp::ZERO]
+        });
+
+        // Another key spending the same note position gets another nullifier
+        let (_, other) = DCIWitnessBuilder::new()
+            .with_merkle_proof(&proof())
+            .with_balance(1_000)
+            .with_secret_key(Fp::from(43))
+            .build()
+            .unwrap();
+        assert_ne!(other.nullifier, public.nullifier);
+        assert_ne!(other.root, public.root);
+    }
+
+    #[test]
+    fn test_nullifier_constrained() {
+        let (mut circuit, public) = DCIWitnessBuilder::new()
+            .with_merkle_proof(&proof())
+            .with_balance(1_000)
+            .with_secret_key(Fp::from(42))
+            .build()
+            .unwrap();
+        MockProver::run(DCI_K, &circuit, public.to_instances())
+            .unwrap()
+            .assert_satisfied();
+
+        let forged = DCIPublicInputs {
+            nullifier: public.nullifier + Fp::ONE,
+            ..public
+        };
+        let prover = MockProver::run(DCI_K, &circuit, forged.to_instances()).unwrap();
+        assert!(prover.verify().is_err());
+
+        // Another key cannot spend the note under its nullifier
+        circuit.secret_key = SecretWitness::new(Fp::from(43));
+        let prover = MockProver::run(DCI_K, &circuit, public.to_instances()).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_commitment_binds_balance() {
+        let (mut circuit, public) = DCIWitnessBuilder::new()
+            .with_merkle_proof(&proof())
+            .with_balance(1_000)
+            .with_secret_key(Fp::from(42))
+            .build()
+            .unwrap();
+        // The leaf commits to 1000, so no other balance opens the path
+        circuit.balance = SecretWitness::new(Fp::from(1_001));
+        let prover = MockProver::run(DCI_K, &circuit, public.to_instances()).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_builder_rejects_incomplete_witness() {
+        let err = DCIWitnessBuilder::<Fp>::new()
+            .with_merkle_proof(&proof())
+            .with_secret_key(Fp::ONE)
+            .build()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Error: DCI witness has no balance");
+
+        let short = MerkleProof {
+            index: 0,
+            siblings: vec![Fp::ZERO; 3],
+        };
+        assert!(DCIWitnessBuilder::new()
+            .with_merkle_proof(&short)
+            .with_balance(1)
+            .with_secret_key(Fp::ONE)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_builder_sha256_path() {
+        let siblings: Vec<[u8; 32]> = (0..).take(DCI_TREE_DEPTH).map(|i: u8| [i; 32]).collect();
+        let builder = DCIWitnessBuilder::new()
+            .with_merkle_proof(&proof())
+            .with_sha256_path(11, &siblings)
+            .with_balance(1_000)
+            .with_secret_key(Fp::from(42));
+        assert!(builder.clone().build().is_err());
+        let (circuit, public) = builder.build_sha256().unwrap();
+        assert_eq!(circuit.merkle_hash, MerkleHash::Sha256);
+        assert_eq!(circuit.sha256_path[1][7].expose(), Some(Fp::from(0x0101_0101)));
+        assert_eq!(
+            circuit.public_inputs,
+            [public.root[0], public.root[1], public.nullifier]
+        );
+
+        // Leaf 11 is a right child at levels 0, 1 and 3
+        let leaf = DCIWitnessBuilder::commitment(Fp::from(42), 1_000);
+        let mut node: [u8; 32] = to_bytes(&leaf, Endianness::Big).try_into().unwrap();
+        for (level, sibling) in siblings.iter().enumerate() {
+            node = if [0, 1, 3].contains(&level) {
+                sha256_pair(sibling, &node)
+            } else {
+                sha256_pair(&node, sibling)
+            };
+        }
+        assert_eq!(public.root, [pack_bytes(&node[..16]), pack_bytes(&node[16..])]);
+
+        let err = DCIWitnessBuilder::new()
+            .with_merkle_proof(&proof())
+            .with_balance(1_000)
+            .with_secret_key(Fp::from(42))
+            .build_sha256()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Error: DCI witness has no SHA-256 path");
+    }
+
+    /// Checks big-endian leaf `words` against the modulus, without a path
+    struct CanonicalLeaf {
+        words: [u32; DIGEST_WORDS],
+    }
+
+    impl Circuit<Fp> for CanonicalLeaf {
+        type Config = DCIConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { words: self.words }
+        }
+
+        fn configure(cs: &mut ConstraintSystem<Fp>) -> DCIConfig {
+            DCICircuit::<Fp>::configure(cs)
+        }
+
+        fn synthesize(
+            &self,
+            config: DCIConfig,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            BalanceChip::construct(config.balance.clone()).load(&mut layouter)?;
+            let chip = Sha256Chip::construct(config.sha256.clone());
+            chip.load(&mut layouter)?;
+            let words = self
+                .words
+                .iter()
+                .map(|word| chip.witness(layouter.namespace(|| "word"), Value::known(*word)))
+                .collect::<Result<Vec<_>, _>>()?;
+            DCICircuit::check_canonical_leaf(&config, &chip, &mut layouter, &words)
+        }
+    }
+
+    #[test]
+    fn test_sha256_leaf_canonical() {
+        let words = |bytes: Vec<u8>| -> [u32; DIGEST_WORDS] {
+            std::array::from_fn(|i| u32::from_be_bytes(bytes[4 * i..][..4].try_into().unwrap()))
+        };
+        let largest = words(to_bytes(&-Fp::ONE, Endianness::Big));
+        let mut modulus = largest;
+        modulus[DIGEST_WORDS - 1] += 1;
+        // A small leaf also splits as itself plus the modulus
+        let mut five_plus_modulus = modulus;
+        five_plus_modulus[DIGEST_WORDS - 1] += 5;
+
+        for words in [[0; DIGEST_WORDS], [5; DIGEST_WORDS], largest] {
+            MockProver::run(15, &CanonicalLeaf { words }, vec![vec![]])
+                .unwrap()
+                .assert_satisfied();
+        }
+        for words in [modulus, five_plus_modulus, [u32::MAX; DIGEST_WORDS]] {
+            crate::testing::assert_constraint_fails(
+                15,
+                &CanonicalLeaf { words },
+                vec![vec![]],
+                "canonical leaf",
+            );
+        }
+    }
+
+    #[test]
+    fn test_sha256_path_within_k() {
+        let circuit = DCICircuit::<Fp>::with_merkle_hash(MerkleHash::Sha256);
+        let usage = crate::circuits::helpers::RowUsage::measure(&circuit).unwrap();
+        assert!(usage.minimum_k <= DCI_SHA256_K, "{}", usage.report());
+        assert_eq!(circuit.without_witnesses().merkle_hash, MerkleHash::Sha256);
+    }
+
+    #[test]
+    fn test_within_constraint_budget() {
+        let usage =
+            crate::circuits::helpers::RowUsage::measure(&DCICircuit::<Fp>::default()).unwrap();
+        usage.check_budget(DCI_CONSTRAINT_BUDGET).unwrap();
+        assert!(usage.minimum_k <= DCI_K, "{}", usage.report());
+    }
+}
@@ -1,80 +1,924 @@
-// core/src/circuits/helpers.rs
-use super::pore::PoREConfig;
-use halo2_proofs::plonk::ConstraintSystem;
-use halo2_proofs::arithmetic::Field;
+//! Circuit size introspection
+//!
+//! [`CircuitMetrics`] describes a configured [`ConstraintSystem`]: its
+//! columns, gates, lookups and degree. [`RowUsage`] lays a circuit out the
+//! way [`halo2_proofs::dev::MockProver`] does, recording every cell the
+//! floor planner assigns, and reports the rows and regions it takes and the
+//! smallest `k` it fits in. Neither needs a witness, so both work on
+//! `Circuit::without_witnesses`.
+//!
+//! [`RowUsage::to_html`] and [`RowUsage::to_dot`] export the layout as a
+//! heat map or graph of regions against the columns they fill, with the
+//! constraints each region switches on and the rows left unused.
+//!
+//! halo2 keeps most of the constraint system private, so the counts are
+//! read from its `Debug` output.
 
-/// Circuit metrics and analysis
+use std::{cmp, collections::BTreeMap, fmt, fmt::Write as _};
+
+use ff::PrimeField;
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::Value,
+    plonk::{
+        Advice, Any, Assigned, Assignment, Circuit, Column, ConstraintSystem, Error as PlonkError,
+        Fixed, FloorPlanner, Instance, Selector,
+    },
+};
+
+use crate::{
+    error::Result,
+    telemetry::{Telemetry, TelemetryEvent},
+    utils::rows::RowBudget,
+};
+
+/// Shape of a configured constraint system
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct CircuitMetrics {
-    pub total_constraints: usize,
-    pub advice_columns_used: usize,
-    pub lookups_used: usize,
-    pub custom_gates: usize,
-    pub gate_utilization: f64,
+    /// Custom gates
+    pub gates: usize,
+    /// Polynomial constraints across all gates
+    pub constraints: usize,
+    /// Advice columns
+    pub advice_columns: usize,
+    /// Fixed columns, including lookup table columns
+    pub fixed_columns: usize,
+    /// Instance columns
+    pub instance_columns: usize,
+    /// Selectors
+    pub selectors: usize,
+    /// Lookup arguments
+    pub lookups: usize,
+    /// Columns taking part in the permutation argument
+    pub permutation_columns: usize,
+    /// Degree of the constraint system
+    pub degree: usize,
+    /// Rows reserved at the end of every column for blinding
+    pub blinding_rows: usize,
 }
 
 impl CircuitMetrics {
-    /// Analyze circuit configuration
+    /// Metrics of `cs`
+    #[must_use]
     pub fn analyze<F: Field>(cs: &ConstraintSystem<F>) -> Self {
-        // This would analyze the actual constraint system
-        // For now, returning placeholder values
+        let summary = Summary(format!("{cs:?}"));
+        let gates = summary.0.matches("Gate { name: ").count();
+        let constraints = summary
+            .0
+            .match_indices("constraint_names: [")
+            .map(|(at, pattern)| Summary::strings(bracketed(&summary.0[at + pattern.len() - 1..])))
+            .sum();
+
         Self {
-            total_constraints: 0,
-            advice_columns_used: 10,
-            lookups_used: 1,
-            custom_gates: 1,
-            gate_utilization: 0.0,
+            gates,
+            constraints,
+            advice_columns: summary.number("num_advice_columns"),
+            fixed_columns: summary.number("num_fixed_columns"),
+            instance_columns: summary.number("num_instance_columns"),
+            selectors: summary.number("num_selectors"),
+            lookups: summary.0.matches("input_expressions: ").count(),
+            permutation_columns: summary
+                .list("permutation: Argument { columns")
+                .matches("Column {")
+                .count(),
+            degree: cs.degree(),
+            blinding_rows: cs.blinding_factors(),
         }
     }
-    
-    /// Generate visualization of circuit layout
+
+    /// Metrics of the constraint system `ConcreteCircuit` configures
+    #[must_use]
+    pub fn of<F: Field, ConcreteCircuit: Circuit<F>>() -> Self {
+        let mut cs = ConstraintSystem::default();
+        ConcreteCircuit::configure(&mut cs);
+        Self::analyze(&cs)
+    }
+
+    /// Human-readable summary
+    #[must_use]
     pub fn visualize(&self) -> String {
         format!(
             "Circuit Layout:\n\
              ================\n\
-             Constraints: {}/{}\n\
-             Advice Columns: {}/10\n\
+             Gates: {} ({} constraints, degree {})\n\
+             Columns: {} advice, {} fixed, {} instance\n\
+             Selectors: {}\n\
              Lookups: {}\n\
-             Custom Gates: {}\n\
-             Gate Utilization: {:.2}%\n",
-            self.total_constraints, 25000,
-            self.advice_columns_used,
-            self.lookups_used,
-            self.custom_gates,
-            self.gate_utilization * 100.0
+             Permutation Columns: {}\n",
+            self.gates,
+            self.constraints,
+            self.degree,
+            self.advice_columns,
+            self.fixed_columns,
+            self.instance_columns,
+            self.selectors,
+            self.lookups,
+            self.permutation_columns,
         )
     }
 }
 
+/// Kind of a column cells are assigned in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ColumnKind {
+    /// Advice column
+    Advice,
+    /// Fixed column, including lookup table columns
+    Fixed,
+    /// Selector, before halo2 packs selectors into fixed columns
+    Selector,
+}
+
+/// Column cells are assigned in, as in `advice 3`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LayoutColumn {
+    /// Kind of the column
+    pub kind: ColumnKind,
+    /// Index among the columns of its kind
+    pub index: usize,
+}
+
+impl fmt::Display for LayoutColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            ColumnKind::Advice => "advice",
+            ColumnKind::Fixed => "fixed",
+            ColumnKind::Selector => "selector",
+        };
+        write!(f, "{kind} {}", self.index)
+    }
+}
+
+/// Rows one region of a circuit takes
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegionUsage {
+    /// Region name, as given to `assign_region` or `assign_table`
+    pub name: String,
+    /// First row the region assigns
+    pub start: usize,
+    /// Row after the last one the region assigns
+    pub end: usize,
+    /// Advice and fixed cells assigned and selectors enabled
+    pub cells: usize,
+    /// Cells per column
+    pub columns: BTreeMap<LayoutColumn, usize>,
+    /// Constraints of the gates switched on by the selectors the region
+    /// enables, once per enabled row
+    pub constraints: usize,
+}
+
+impl RegionUsage {
+    /// Rows from the first to the last the region assigns
+    #[must_use]
+    pub fn rows(&self) -> usize {
+        self.end - self.start
+    }
+}
+
+/// Rows a circuit takes once laid out by its floor planner
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RowUsage {
+    /// Rows up to and including the last one assigned
+    pub used_rows: usize,
+    /// Regions in the order they were assigned; empty ones are left out
+    pub regions: Vec<RegionUsage>,
+    /// Smallest `k` whose usable rows hold [`RowUsage::used_rows`]
+    pub minimum_k: u32,
+    /// Rows reserved at the end of every column for blinding
+    pub blinding_rows: usize,
+}
+
+impl RowUsage {
+    /// Lay out `circuit` and record the rows it assigns
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::Plonk`] if synthesis fails and
+    /// [`crate::Error::RowBudgetExceeded`] if the circuit fits no `k` the
+    /// field supports.
+    pub fn measure<F: PrimeField, ConcreteCircuit: Circuit<F>>(
+        circuit: &ConcreteCircuit,
+    ) -> Result<Self> {
+        let mut cs = ConstraintSystem::default();
+        let config = ConcreteCircuit::configure(&mut cs);
+
+        let mut recorder = Recorder {
+            selector_constraints: selector_constraints(&cs),
+            ..Recorder::default()
+        };
+        ConcreteCircuit::FloorPlanner::synthesize(&mut recorder, circuit, config, constants(&cs))?;
+
+        // halo2 needs at least two usable rows, for `l_0` and `l_last`
+        let layout = RowBudget::from_cs(&cs).min_layout(recorder.used_rows.max(2))?;
+        Ok(Self {
+            used_rows: recorder.used_rows,
+            regions: recorder.regions,
+            minimum_k: layout.k,
+            blinding_rows: cs.blinding_factors(),
+        })
+    }
+
+    /// Check that the circuit takes at most `max_rows` rows
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::RowBudgetExceeded`] if it takes more.
+    pub fn check_budget(&self, max_rows: usize) -> Result<()> {
+        if self.used_rows > max_rows {
+            return Err(crate::Error::RowBudgetExceeded {
+                rows: self.used_rows,
+                max_k: self.minimum_k,
+            });
+        }
+        Ok(())
+    }
+
+    /// One line per region with its row span and cell count
+    #[must_use]
+    pub fn report(&self) -> String {
+        let mut out = format!(
+            "{} rows used, fits k = {}\n",
+            self.used_rows, self.minimum_k
+        );
+        for region in &self.regions {
+            let _ = writeln!(
+                out,
+                "{:>6}..{:<6} {:>5} cells  {}",
+                region.start, region.end, region.cells, region.name
+            );
+        }
+        out
+    }
+
+    /// Rows of `2^k` that neither the circuit nor blinding takes
+    #[must_use]
+    pub fn unused_rows(&self, k: u32) -> usize {
+        (1usize << k).saturating_sub(self.used_rows + self.blinding_rows + 1)
+    }
+
+    /// Columns any region assigns, in order
+    fn columns(&self) -> Vec<LayoutColumn> {
+        let mut columns: Vec<LayoutColumn> = self
+            .regions
+            .iter()
+            .flat_map(|region| region.columns.keys().copied())
+            .collect();
+        columns.sort_unstable();
+        columns.dedup();
+        columns
+    }
+
+    /// Graphviz graph linking every region to the columns it fills
+    ///
+    /// Edges are labelled with cell counts and regions with their rows and
+    /// constraints. Render with `dot -Tsvg`.
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph layout {\n    rankdir=LR;\n    node [shape=box];\n");
+        for column in self.columns() {
+            let _ = writeln!(out, "    \"{column}\" [shape=ellipse];");
+        }
+        for (id, region) in self.regions.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "    r{id} [label=\"{}\\nrows {}..{}\\n{} constraints\"];",
+                escape_dot(&region.name),
+                region.start,
+                region.end,
+                region.constraints
+            );
+            for (column, cells) in &region.columns {
+                let _ = writeln!(out, "    r{id} -> \"{column}\" [label=\"{cells}\"];");
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Standalone HTML heat map of regions against columns at size `k`
+    ///
+    /// Each cell is shaded by the share of the region's rows filled in that
+    /// column. A last row shows the rows of `2^k` left unused.
+    #[must_use]
+    pub fn to_html(&self, k: u32) -> String {
+        let columns = self.columns();
+        let mut out = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Circuit layout</title>\n<style>\n\
+             table { border-collapse: collapse; font: 12px monospace; }\n\
+             th, td { border: 1px solid #ccc; padding: 2px 6px; text-align: right; }\n\
+             </style>\n</head>\n<body>\n",
+        );
+        let _ = writeln!(
+            out,
+            "<p>k = {k}: {} rows, {} used, {} blinding, {} unused</p>",
+            1usize << k,
+            self.used_rows,
+            self.blinding_rows,
+            self.unused_rows(k)
+        );
+        out.push_str("<table>\n<tr><th>region</th><th>rows</th><th>constraints</th>");
+        for column in &columns {
+            let _ = write!(out, "<th>{column}</th>");
+        }
+        out.push_str("</tr>\n");
+        for region in &self.regions {
+            let _ = write!(
+                out,
+                "<tr><th>{}</th><td>{}..{}</td><td>{}</td>",
+                escape_html(&region.name),
+                region.start,
+                region.end,
+                region.constraints
+            );
+            for column in &columns {
+                match region.columns.get(column) {
+                    Some(&cells) => {
+                        #[allow(clippy::cast_precision_loss)]
+                        let filled = cells as f64 / region.rows() as f64;
+                        let _ = write!(
+                            out,
+                            "<td style=\"background: rgba(214, 39, 40, {filled:.2})\">{cells}</td>"
+                        );
+                    }
+                    None => out.push_str("<td></td>"),
+                }
+            }
+            out.push_str("</tr>\n");
+        }
+        let _ = writeln!(
+            out,
+            "<tr><th>unused</th><td>{}..{}</td><td></td>{}</tr>",
+            self.used_rows,
+            self.used_rows + self.unused_rows(k),
+            "<td></td>".repeat(columns.len())
+        );
+        out.push_str("</table>\n</body>\n</html>\n");
+        out
+    }
+}
+
+/// `text` quoted for a Graphviz string
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// `text` escaped for HTML
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Constraints [`ConstraintCounter::report`] flags as over budget
+pub const CONSTRAINT_BUDGET: usize = 25_000;
+
 /// Constraint counter for debugging
+#[derive(Debug, Default)]
 pub struct ConstraintCounter {
     count: usize,
     details: Vec<(String, usize)>,
 }
 
 impl ConstraintCounter {
+    /// Counter with nothing counted
+    #[must_use]
     pub fn new() -> Self {
-        Self {
-            count: 0,
-            details: Vec::new(),
-        }
+        Self::default()
     }
-    
+
+    /// Count `constraints` for `gate_name`
     pub fn add(&mut self, gate_name: &str, constraints: usize) {
         self.count += constraints;
         self.details.push((gate_name.to_string(), constraints));
     }
-    
-    pub fn report(&self) {
-        println!("=== Constraint Report ===");
+
+    /// Constraints counted so far
+    #[must_use]
+    pub fn total(&self) -> usize {
+        self.count
+    }
+
+    /// Report each gate's count to `telemetry`, then
+    /// [`TelemetryEvent::OverBudget`] if the total exceeds
+    /// [`CONSTRAINT_BUDGET`]
+    pub fn report(&self, telemetry: &Telemetry) {
         for (gate, count) in &self.details {
-            println!("{}: {} constraints", gate, count);
+            telemetry.record(&TelemetryEvent::Constraints {
+                gate: gate.clone(),
+                count: *count,
+            });
+        }
+        if self.count > CONSTRAINT_BUDGET {
+            telemetry.record(&TelemetryEvent::OverBudget {
+                total: self.count,
+                budget: CONSTRAINT_BUDGET,
+            });
+        }
+    }
+}
+
+/// `Debug` output of a [`ConstraintSystem`]
+struct Summary(String);
+
+impl Summary {
+    /// Value of the numeric field `name`
+    fn number(&self, name: &str) -> usize {
+        let pattern = format!("{name}: ");
+        self.0
+            .find(&pattern)
+            .map(|at| &self.0[at + pattern.len()..])
+            .and_then(|rest| {
+                let end = rest
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len());
+                rest[..end].parse().ok()
+            })
+            .unwrap_or(0)
+    }
+
+    /// Contents of the list field `name`, or `""` if there is none
+    fn list(&self, name: &str) -> &str {
+        let pattern = format!("{name}: [");
+        self.0
+            .find(&pattern)
+            .map_or("", |at| bracketed(&self.0[at + pattern.len() - 1..]))
+    }
+
+    /// Number of string literals in `list`
+    fn strings(list: &str) -> usize {
+        let mut count = 0;
+        let mut in_string = false;
+        let mut bytes = list.bytes();
+        while let Some(byte) = bytes.next() {
+            match (in_string, byte) {
+                (true, b'\\') => {
+                    bytes.next();
+                }
+                (_, b'"') => {
+                    count += usize::from(!in_string);
+                    in_string = !in_string;
+                }
+                _ => {}
+            }
+        }
+        count
+    }
+}
+
+/// Contents of the bracketed list `text` starts with, or `""` if it does not
+fn bracketed(text: &str) -> &str {
+    if !text.starts_with('[') {
+        return "";
+    }
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut bytes = text.bytes().enumerate();
+    while let Some((at, byte)) = bytes.next() {
+        match (in_string, byte) {
+            (true, b'\\') => {
+                bytes.next();
+            }
+            (_, b'"') => in_string = !in_string,
+            (false, b'[') => depth += 1,
+            (false, b']') => {
+                depth -= 1;
+                if depth == 0 {
+                    return &text[1..at];
+                }
+            }
+            _ => {}
         }
-        println!("Total: {} constraints", self.count);
-        
-        if self.count > 25000 {
-            println!("⚠️  WARNING: Exceeds 25k constraint target!");
-        } else {
-            println!("✓ Within constraint budget ({}/25000)", self.count);
+    }
+    ""
+}
+
+/// Fixed columns `cs` uses for constants
+///
+/// Columns cannot be built outside halo2, but a fresh constraint system
+/// hands out fixed columns with consecutive indices, so the ones named in
+/// the `Debug` output are allocated again in one.
+fn constants<F: Field>(cs: &ConstraintSystem<F>) -> Vec<Column<Fixed>> {
+    let summary = Summary(format!("{cs:?}"));
+    let indices: Vec<usize> = numbers_after(summary.list("constants"), "index: ").collect();
+    let Some(&last) = indices.iter().max() else {
+        return Vec::new();
+    };
+    let mut scratch = ConstraintSystem::<F>::default();
+    let columns: Vec<Column<Fixed>> = (0..=last).map(|_| scratch.fixed_column()).collect();
+    indices.into_iter().map(|index| columns[index]).collect()
+}
+
+/// Constraints of the gates each selector switches on, by selector index
+fn selector_constraints<F: Field>(cs: &ConstraintSystem<F>) -> Vec<usize> {
+    let summary = Summary(format!("{cs:?}"));
+    let mut constraints = vec![0; summary.number("num_selectors")];
+    for gate in summary.0.split("Gate { name: ").skip(1) {
+        let gate = Summary(gate.to_string());
+        let count = Summary::strings(gate.list("constraint_names"));
+        for index in numbers_after(gate.list("queried_selectors"), "Selector(") {
+            if let Some(total) = constraints.get_mut(index) {
+                *total += count;
+            }
+        }
+    }
+    constraints
+}
+
+/// Numbers directly following each `pattern` in `text`
+fn numbers_after<'a>(text: &'a str, pattern: &'a str) -> impl Iterator<Item = usize> + 'a {
+    text.match_indices(pattern).filter_map(move |(at, _)| {
+        let rest = &text[at + pattern.len()..];
+        let end = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        rest[..end].parse().ok()
+    })
+}
+
+/// [`Assignment`] that records which rows are assigned and nothing else
+#[derive(Default)]
+struct Recorder {
+    selector_constraints: Vec<usize>,
+    used_rows: usize,
+    regions: Vec<RegionUsage>,
+    current: Option<RegionUsage>,
+}
+
+impl Recorder {
+    fn touch(&mut self, kind: ColumnKind, index: usize, row: usize) {
+        self.used_rows = cmp::max(self.used_rows, row + 1);
+        if let Some(region) = &mut self.current {
+            region.start = cmp::min(region.start, row);
+            region.end = cmp::max(region.end, row + 1);
+            region.cells += 1;
+            *region
+                .columns
+                .entry(LayoutColumn { kind, index })
+                .or_default() += 1;
+        }
+    }
+}
+
+impl<F: Field> Assignment<F> for Recorder {
+    fn enter_region<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.current = Some(RegionUsage {
+            name: name_fn().into(),
+            start: usize::MAX,
+            end: 0,
+            cells: 0,
+            columns: BTreeMap::new(),
+            constraints: 0,
+        });
+    }
+
+    fn exit_region(&mut self) {
+        if let Some(region) = self.current.take().filter(|region| region.cells > 0) {
+            self.regions.push(region);
+        }
+    }
+
+    fn enable_selector<A, AR>(
+        &mut self,
+        _: A,
+        selector: &Selector,
+        row: usize,
+    ) -> std::result::Result<(), PlonkError>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        // Selectors keep their index private but show it in `Selector(i, _)`
+        let index = numbers_after(&format!("{selector:?}"), "Selector(")
+            .next()
+            .unwrap_or_default();
+        self.touch(ColumnKind::Selector, index, row);
+        if let Some(region) = &mut self.current {
+            region.constraints += self.selector_constraints.get(index).copied().unwrap_or(0);
+        }
+        Ok(())
+    }
+
+    fn query_instance(
+        &self,
+        _: Column<Instance>,
+        _: usize,
+    ) -> std::result::Result<Value<F>, PlonkError> {
+        Ok(Value::unknown())
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Advice>,
+        row: usize,
+        _: V,
+    ) -> std::result::Result<(), PlonkError>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.touch(ColumnKind::Advice, column.index(), row);
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Fixed>,
+        row: usize,
+        _: V,
+    ) -> std::result::Result<(), PlonkError>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.touch(ColumnKind::Fixed, column.index(), row);
+        Ok(())
+    }
+
+    fn copy(
+        &mut self,
+        _: Column<Any>,
+        _: usize,
+        _: Column<Any>,
+        _: usize,
+    ) -> std::result::Result<(), PlonkError> {
+        Ok(())
+    }
+
+    // Pads a table column to the end; the padding is not counted as used
+    fn fill_from_row(
+        &mut self,
+        _: Column<Fixed>,
+        _: usize,
+        _: Value<Assigned<F>>,
+    ) -> std::result::Result<(), PlonkError> {
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        plonk::{Expression, TableColumn},
+        poly::Rotation,
+    };
+    use pasta_curves::Fp;
+
+    use super::*;
+
+    /// `rows` rows of `a * b = c` with boolean `b` and `a` looked up in a 16-entry table,
+    /// the last `c` exposed, and one cell copied from a constant
+    #[derive(Clone, Default)]
+    struct Products {
+        rows: usize,
+    }
+
+    #[derive(Clone, Debug)]
+    struct ProductsConfig {
+        advice: [Column<Advice>; 3],
+        instance: Column<Instance>,
+        s_mul: Selector,
+        table: TableColumn,
+    }
+
+    impl Circuit<Fp> for Products {
+        type Config = ProductsConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
         }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            let instance = meta.instance_column();
+            let constant = meta.fixed_column();
+            meta.enable_constant(constant);
+            meta.enable_equality(advice[2]);
+            meta.enable_equality(instance);
+            let s_mul = meta.complex_selector();
+            let table = meta.lookup_table_column();
+            meta.create_gate("mul", |meta| {
+                let s = meta.query_selector(s_mul);
+                let [a, b, c] = advice.map(|column| meta.query_advice(column, Rotation::cur()));
+                let one = Expression::Constant(Fp::ONE);
+                vec![s.clone() * (a * b.clone() - c), s * b.clone() * (one - b)]
+            });
+            meta.lookup(|meta| {
+                let s = meta.query_selector(s_mul);
+                vec![(s * meta.query_advice(advice[0], Rotation::cur()), table)]
+            });
+            ProductsConfig {
+                advice,
+                instance,
+                s_mul,
+                table,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> std::result::Result<(), PlonkError> {
+            layouter.assign_table(
+                || "small values",
+                |mut table| {
+                    for (offset, value) in (0..16u64).enumerate() {
+                        table.assign_cell(
+                            || "value",
+                            config.table,
+                            offset,
+                            || Value::known(Fp::from(value)),
+                        )?;
+                    }
+                    Ok(())
+                },
+            )?;
+            let mut cells = Vec::new();
+            layouter.assign_region(
+                || "products",
+                |mut region| {
+                    cells.clear();
+                    for row in 0..self.rows {
+                        config.s_mul.enable(&mut region, row)?;
+                        for (column, value) in config.advice.iter().zip([2u64, 1, 2]) {
+                            let cell = region.assign_advice(
+                                || "product",
+                                *column,
+                                row,
+                                || Value::known(Fp::from(value)),
+                            )?;
+                            cells.push(cell);
+                        }
+                    }
+                    Ok(())
+                },
+            )?;
+            layouter.assign_region(
+                || "one",
+                |mut region| {
+                    region.assign_advice_from_constant(|| "one", config.advice[2], 0, Fp::ONE)
+                },
+            )?;
+            let last = cells.last().expect("at least one row");
+            layouter.constrain_instance(last.cell(), config.instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_metrics_read_from_constraint_system() {
+        let metrics = CircuitMetrics::of::<Fp, Products>();
+        assert_eq!(
+            metrics,
+            CircuitMetrics {
+                gates: 1,
+                constraints: 2,
+                advice_columns: 3,
+                // The constant column and the table column
+                fixed_columns: 2,
+                instance_columns: 1,
+                selectors: 1,
+                lookups: 1,
+                // advice[2], the instance and the constant column
+                permutation_columns: 3,
+                degree: metrics.degree,
+                blinding_rows: metrics.blinding_rows,
+            }
+        );
+        assert!(metrics.degree >= 3);
+        assert!(metrics.visualize().contains("Gates: 1 (2 constraints"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_rows_match_mock_prover() {
+        for rows in [1, 40, 300] {
+            let circuit = Products { rows };
+            let usage = RowUsage::measure(&circuit).unwrap();
+            // The table has its own column, so the products start at row 0
+            // and the copied constant follows them
+            assert_eq!(usage.used_rows, cmp::max(16, rows + 1), "{rows} rows");
+            let names: Vec<&str> = usage.regions.iter().map(|r| r.name.as_str()).collect();
+            assert_eq!(names, ["small values", "products", "one"]);
+            assert_eq!(usage.regions[1].rows(), rows);
+            assert_eq!(usage.regions[1].cells, 4 * rows);
+            assert_eq!(usage.regions[2].start, rows);
+
+            let k = usage.minimum_k;
+            let instances = vec![vec![Fp::from(2)]];
+            MockProver::run(k, &circuit, instances.clone())
+                .unwrap()
+                .assert_satisfied();
+            assert!(MockProver::run(k - 1, &circuit, instances).is_err());
+        }
+    }
+
+    #[test]
+    fn test_region_columns_and_constraints() {
+        let usage = RowUsage::measure(&Products { rows: 40 }).unwrap();
+        let products = &usage.regions[1];
+        let column = |kind, index| LayoutColumn { kind, index };
+        assert_eq!(
+            products
+                .columns
+                .iter()
+                .map(|(c, n)| (*c, *n))
+                .collect::<Vec<_>>(),
+            [
+                (column(ColumnKind::Advice, 0), 40),
+                (column(ColumnKind::Advice, 1), 40),
+                (column(ColumnKind::Advice, 2), 40),
+                (column(ColumnKind::Selector, 0), 40),
+            ]
+        );
+        // Both constraints of "mul" on every row
+        assert_eq!(products.constraints, 80);
+        assert_eq!(usage.regions[0].constraints, 0);
+
+        let dot = usage.to_dot();
+        assert!(dot.starts_with("digraph layout {"));
+        assert!(dot.contains("r1 -> \"advice 0\" [label=\"40\"];"));
+        assert!(dot.contains("\"selector 0\" [shape=ellipse];"));
+
+        let html = usage.to_html(usage.minimum_k + 1);
+        let unused = usage.unused_rows(usage.minimum_k + 1);
+        assert!(unused > 0);
+        assert!(html.contains(&format!("<th>unused</th><td>41..{}</td>", 41 + unused)));
+        assert!(html.contains("<th>products</th><td>0..40</td><td>80</td>"));
+    }
+
+    #[test]
+    fn test_escaping() {
+        assert_eq!(escape_dot(r#"a "b" \ c"#), r#"a \"b\" \\ c"#);
+        assert_eq!(escape_html("<a & \"b\">"), "&lt;a &amp; &quot;b&quot;&gt;");
+    }
+
+    #[test]
+    fn test_budget() {
+        let usage = RowUsage::measure(&Products { rows: 100 }).unwrap();
+        assert!(usage.check_budget(101).is_ok());
+        assert!(matches!(
+            usage.check_budget(100),
+            Err(crate::Error::RowBudgetExceeded { rows: 101, .. })
+        ));
+        assert!(usage.report().starts_with("101 rows used"));
+    }
+
+    #[test]
+    fn test_constraint_counter_reports_to_telemetry() {
+        let log = std::sync::Arc::new(crate::TelemetryLog::new());
+        let telemetry = Telemetry::new(log.clone());
+        let mut counter = ConstraintCounter::new();
+        counter.add("add_mul", 3);
+        counter.report(&telemetry);
+        assert_eq!(
+            log.take(),
+            [TelemetryEvent::Constraints {
+                gate: "add_mul".into(),
+                count: 3
+            }]
+        );
+
+        counter.add("sha256", CONSTRAINT_BUDGET);
+        counter.report(&telemetry);
+        assert_eq!(
+            log.events().last(),
+            Some(&TelemetryEvent::OverBudget {
+                total: CONSTRAINT_BUDGET + 3,
+                budget: CONSTRAINT_BUDGET
+            })
+        );
+    }
+
+    #[test]
+    fn test_bracketed_skips_strings() {
+        assert_eq!(bracketed(r#"[a, "]", [b]] tail"#), r#"a, "]", [b]"#);
+        assert_eq!(Summary::strings(r#""x", "y\"]", """#), 3);
+    }
+}
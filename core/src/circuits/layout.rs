@@ -0,0 +1,155 @@
+//! Circuit layout rendering for design reviews
+//!
+//! Wraps halo2's `dev-graph` [`CircuitLayout`] so any circuit can be drawn to
+//! an SVG or PNG file with one call. Regions, selectors and equality-enabled
+//! cells are shown; cell labels are off because they need system fonts.
+//! HTML and DOT files get the region occupancy map of [`RowUsage`] instead.
+
+use std::path::Path;
+
+use ff::PrimeField;
+use halo2_proofs::{arithmetic::Field, dev::CircuitLayout, plonk::Circuit};
+use plotters::prelude::{BitMapBackend, DrawingBackend, IntoDrawingArea, SVGBackend, WHITE};
+
+use super::helpers::RowUsage;
+use crate::error::{Error, Result};
+
+/// Size of rendered layouts in pixels
+pub const LAYOUT_SIZE: (u32, u32) = (1600, 1200);
+
+/// Render the layout of `circuit` at size `k` to `path`
+///
+/// The format follows the extension of `path`: `svg` or `png` for halo2's
+/// drawing, `html` for [`RowUsage::to_html`] or `dot` for
+/// [`RowUsage::to_dot`].
+///
+/// ```no_run
+/// # use zk_proof_core::circuits::{layout::render_layout, PoRECircuit};
+/// # use pasta_curves::Fp;
+/// render_layout(&PoRECircuit::<Fp>::default(), 10, "pore-layout.svg")?;
+/// # Ok::<(), zk_proof_core::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns [`Error::Other`] for any other extension and if drawing fails,
+/// including a circuit that does not fit in `2^k` rows, and the errors of
+/// [`RowUsage::measure`] and of writing the file.
+pub fn render_layout<F: PrimeField, C: Circuit<F>>(
+    circuit: &C,
+    k: u32,
+    path: impl AsRef<Path>,
+) -> Result<()> {
+    let path = path.as_ref();
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("svg") => draw(SVGBackend::new(path, LAYOUT_SIZE), circuit, k),
+        Some("png") => draw(BitMapBackend::new(path, LAYOUT_SIZE), circuit, k),
+        Some(ext @ ("html" | "dot")) => {
+            let usage = RowUsage::measure(circuit)?;
+            let text = if ext == "html" {
+                usage.to_html(k)
+            } else {
+                usage.to_dot()
+            };
+            Ok(std::fs::write(path, text)?)
+        }
+        _ => Err(Error::Other(format!(
+            "cannot render layout to {}: expected an .svg, .png, .html or .dot path",
+            path.display()
+        ))),
+    }
+}
+
+fn draw<F: Field, C: Circuit<F>, DB: DrawingBackend>(
+    backend: DB,
+    circuit: &C,
+    k: u32,
+) -> Result<()> {
+    let failed = |err: &dyn std::fmt::Display| Error::Other(format!("rendering layout: {err}"));
+
+    let root = backend.into_drawing_area();
+    root.fill(&WHITE).map_err(|err| failed(&err))?;
+    CircuitLayout::default()
+        .mark_equality_cells(true)
+        .show_equality_constraints(true)
+        .render(k, circuit, &root)
+        .map_err(|err| failed(&err))?;
+    root.present().map_err(|err| failed(&err))
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        plonk::{Advice, Column, ConstraintSystem, Error as PlonkError},
+    };
+    use pasta_curves::Fp;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct TwoRegions;
+
+    impl Circuit<Fp> for TwoRegions {
+        type Config = Column<Advice>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Column<Advice> {
+            let column = meta.advice_column();
+            meta.enable_equality(column);
+            column
+        }
+
+        fn synthesize(
+            &self,
+            column: Column<Advice>,
+            mut layouter: impl Layouter<Fp>,
+        ) -> std::result::Result<(), PlonkError> {
+            for name in ["first", "second"] {
+                layouter.assign_region(
+                    || name,
+                    |mut region| {
+                        region.assign_advice(|| "cell", column, 0, || Value::known(Fp::ONE))
+                    },
+                )?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_render_svg() {
+        let path = std::env::temp_dir().join(format!("layout-{}.svg", std::process::id()));
+        render_layout(&TwoRegions, 4, &path).unwrap();
+        let svg = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(svg.starts_with("<svg"));
+    }
+
+    #[test]
+    fn test_render_html_and_dot() {
+        let path = std::env::temp_dir().join(format!("layout-{}.html", std::process::id()));
+        render_layout(&TwoRegions, 4, &path).unwrap();
+        let html = std::fs::read_to_string(&path).unwrap();
+        assert!(html.contains("<th>first</th>") && html.contains("<th>second</th>"));
+
+        let path = path.with_extension("dot");
+        render_layout(&TwoRegions, 4, &path).unwrap();
+        let dot = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(path.with_extension("html")).unwrap();
+        assert!(dot.contains("r1 -> \"advice 0\" [label=\"1\"];"));
+    }
+
+    #[test]
+    fn test_unknown_format_rejected() {
+        assert!(matches!(
+            render_layout(&TwoRegions, 4, "layout.txt"),
+            Err(Error::Other(_))
+        ));
+    }
+}
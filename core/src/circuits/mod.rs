@@ -1,11 +1,30 @@
 // core/src/circuits/mod.rs
+pub mod config;
+#[cfg(feature = "circuits")]
 pub mod pore;
+#[cfg(feature = "circuits")]
 pub mod dci;
 pub mod helpers;
+pub mod multi_instance;
+#[cfg(feature = "dev-graph")]
+pub mod layout;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "circuits"))]
 mod tests;
 
+pub use config::CircuitConfig;
+#[cfg(feature = "circuits")]
 pub use pore::{PoRECircuit, PoREConfig};
-pub use dci::{DCICircuit, DCIConfig, PoseidonChip};
-pub use helpers::{CircuitMetrics, ConstraintCounter};
\ No newline at end of file
+#[cfg(feature = "circuits")]
+pub use dci::{
+    DCICircuit, DCIConfig, DCIPublicInputs, DCISha256PublicInputs, DCIWitnessBuilder, MerkleHash,
+    MerkleProof,
+};
+pub use crate::gadgets::{PoseidonChip, Sha256Chip};
+pub use helpers::{
+    CircuitMetrics, ColumnKind, ConstraintCounter, LayoutColumn, RegionUsage, RowUsage,
+    CONSTRAINT_BUDGET,
+};
+pub use multi_instance::{MultiInstance, Statement, StatementRows};
+#[cfg(feature = "dev-graph")]
+pub use layout::render_layout;
\ No newline at end of file
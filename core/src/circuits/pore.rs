@@ -4,29 +4,45 @@ use halo2_proofs::{
     circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
     plonk::{
         Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, 
-        Instance, Selector, TableColumn
+        Instance, Selector,
     },
     poly::Rotation,
 };
 use std::marker::PhantomData;
+use ff::PrimeField;
+
+use crate::{
+    annotation,
+    circuits::config::CircuitConfig,
+    gadgets::{DecomposeChip, DecomposeConfig, Gadget},
+    secret::SecretWitness,
+    telemetry::Telemetry,
+};
+
+/// Most rows a [`PoRECircuit`] may take, checked with
+/// [`crate::circuits::helpers::RowUsage`]
+pub const PORE_CONSTRAINT_BUDGET: usize = 25_000;
+
+/// Witnesses range-checked to 8 bits
+type ByteChip<F> = DecomposeChip<F, 8, 1>;
 
 /// Configuration for the PoRE circuit
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, crate::CircuitConfig)]
 pub struct PoREConfig {
     /// Advice columns for witness values
+    #[config(equality)]
     pub advice: [Column<Advice>; 10],
     /// Instance columns for public inputs
+    #[config(equality)]
     pub instance: [Column<Instance>; 3],
     /// Fixed column for constants
+    #[config(equality)]
     pub fixed: Column<Fixed>,
     /// Selector for addition/multiplication fusion gate
     pub s_add_mul: Selector,
-    /// Selector for range check
-    pub s_range: Selector,
-    /// Table column for lookup arguments
-    pub table: TableColumn,
-    /// Constraint counter
-    pub constraint_count: std::cell::RefCell<usize>,
+    /// 8-bit range check configuration
+    #[config(with = "ByteChip::configure(cs, advice[0])")]
+    pub range: DecomposeConfig,
 }
 
 impl PoREConfig {
@@ -46,89 +62,53 @@ impl PoREConfig {
             // Constraint: out = (a + b) * c + d
             vec![s * (out - ((a + b) * c + d))]
         });
-        
-        *self.constraint_count.borrow_mut() += 1;
-    }
-    
-    /// Configure 8-bit range check lookup table
-    fn configure_range_table(&self, cs: &mut ConstraintSystem<impl Field>) {
-        cs.lookup("8-bit range", |meta| {
-            let value = meta.query_advice(self.advice[0], Rotation::cur());
-            let s_range = meta.query_selector(self.s_range);
-            
-            vec![(s_range * value, self.table)]
-        });
-        
-        *self.constraint_count.borrow_mut() += 1;
     }
 }
 
 /// Main PoRE Circuit implementation
 #[derive(Default)]
-pub struct PoRECircuit<F: Field> {
-    /// Private witness values
-    pub witnesses: Vec<Value<F>>,
+pub struct PoRECircuit<F: PrimeField> {
+    /// Private witness values, wiped on drop
+    pub witnesses: Vec<SecretWitness<F>>,
     /// Public inputs
     pub public_inputs: Vec<F>,
+    /// Sink for synthesis events
+    pub telemetry: Telemetry,
     _marker: PhantomData<F>,
 }
 
-impl<F: Field> PoRECircuit<F> {
+impl<F: PrimeField> PoRECircuit<F> {
     /// Create a new PoRE circuit
     pub fn new(witnesses: Vec<Value<F>>, public_inputs: Vec<F>) -> Self {
         Self {
-            witnesses,
+            witnesses: witnesses.into_iter().map(SecretWitness::from).collect(),
             public_inputs,
+            telemetry: Telemetry::default(),
             _marker: PhantomData,
         }
     }
-    
-    /// Get constraint count for the circuit
-    pub fn constraint_count(&self) -> usize {
-        // This will be updated during synthesis
-        0
+
+    /// Report synthesis to `telemetry`
+    #[must_use]
+    pub fn with_telemetry(mut self, telemetry: Telemetry) -> Self {
+        self.telemetry = telemetry;
+        self
     }
 }
 
-impl<F: Field> Circuit<F> for PoRECircuit<F> {
+impl<F: PrimeField> Circuit<F> for PoRECircuit<F> {
     type Config = PoREConfig;
     type FloorPlanner = SimpleFloorPlanner;
     
     fn without_witnesses(&self) -> Self {
-        Self::default()
+        Self::default().with_telemetry(self.telemetry.clone())
     }
     
     fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config {
-        let advice = [(); 10].map(|_| cs.advice_column());
-        let instance = [(); 3].map(|_| cs.instance_column());
-        let fixed = cs.fixed_column();
-        
-        // Enable equality for copy constraints
-        for column in &advice {
-            cs.enable_equality(*column);
-        }
-        for column in &instance {
-            cs.enable_equality(*column);
-        }
-        cs.enable_equality(fixed);
-        
-        let s_add_mul = cs.selector();
-        let s_range = cs.selector();
-        let table = cs.lookup_table_column();
-        
-        let config = PoREConfig {
-            advice,
-            instance,
-            fixed,
-            s_add_mul,
-            s_range,
-            table,
-            constraint_count: std::cell::RefCell::new(0),
-        };
+        let config = PoREConfig::allocate(cs);
         
         // Configure custom gates
         config.configure_add_mul_gate(cs);
-        config.configure_range_table(cs);
         
         config
     }
@@ -138,51 +118,34 @@ impl<F: Field> Circuit<F> for PoRECircuit<F> {
         config: Self::Config,
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
+        self.telemetry.started("pore");
         // Load lookup table for 8-bit values
-        layouter.assign_table(
-            || "8-bit range table",
-            |mut table| {
-                for value in 0..256 {
-                    table.assign_cell(
-                        || format!("value {}", value),
-                        config.table,
-                        value,
-                        || Value::known(F::from(value as u64)),
-                    )?;
-                }
-                Ok(())
-            },
-        )?;
+        ByteChip::construct(config.range.clone()).load(&mut layouter)?;
+        self.telemetry.region("pore", "byte table");
         
         // Example synthesis - replace with actual PoRE logic
         layouter.assign_region(
             || "main region",
             |mut region| {
-                // Track constraint usage
-                let mut constraint_counter = 0;
-                
                 // Example: Use add_mul gate
                 config.s_add_mul.enable(&mut region, 0)?;
-                constraint_counter += 1;
                 
                 // Assign witness values
                 for (i, witness) in self.witnesses.iter().enumerate() {
                     if i < 10 {
                         region.assign_advice(
-                            || format!("witness {}", i),
+                            || annotation!("witness {}", i),
                             config.advice[i],
                             0,
-                            || *witness,
+                            || witness.value(),
                         )?;
                     }
                 }
                 
-                // Update global constraint count
-                *config.constraint_count.borrow_mut() = constraint_counter;
-                
                 Ok(())
             },
         )?;
+        self.telemetry.region("pore", "main region");
         
         // Copy public inputs to instance columns
         for (i, public_input) in self.public_inputs.iter().enumerate() {
@@ -195,14 +158,7 @@ impl<F: Field> Circuit<F> for PoRECircuit<F> {
             }
         }
         
-        // Report constraint count
-        let total_constraints = *config.constraint_count.borrow();
-        if total_constraints > 25000 {
-            eprintln!("WARNING: Constraint count {} exceeds target of 25,000", total_constraints);
-        } else {
-            eprintln!("Constraint count: {} / 25,000", total_constraints);
-        }
-        
+        self.telemetry.finished("pore");
         Ok(())
     }
 }
\ No newline at end of file
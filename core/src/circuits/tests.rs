@@ -2,6 +2,7 @@
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::circuits::{helpers::RowUsage, pore::PORE_CONSTRAINT_BUDGET};
     use halo2_proofs::{
         dev::MockProver,
         pasta::Fp,
@@ -16,13 +17,9 @@ mod tests {
             vec![Fp::from(1); 3],
         );
         
-        // Mock prover to count constraints
-        let k = 8; // 2^8 rows
-        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(1); 3]]).unwrap();
-        
-        // Verify constraint count is under limit
-        // Note: Actual constraint counting would need circuit analysis
-        assert!(circuit.constraint_count() < 25000, "Constraint count exceeds limit");
+        let usage = RowUsage::measure(&circuit).unwrap();
+        usage.check_budget(PORE_CONSTRAINT_BUDGET).unwrap();
+        assert!(usage.minimum_k <= 8, "{}", usage.report());
     }
     
     #[test]
@@ -43,6 +40,32 @@ mod tests {
         prover.assert_satisfied();
     }
     
+    /// add_mul fusion over `(a, b, c, d, out)`
+    fn add_mul() -> crate::testing::CircuitProperty<Fp, PoRECircuit<Fp>, [u64; 5]> {
+        crate::testing::CircuitProperty::new(8, |witness: &[u64; 5]| {
+            let witnesses = witness.iter().map(|w| Value::known(Fp::from(*w))).collect();
+            (PoRECircuit::new(witnesses, vec![]), vec![])
+        })
+        .with_cases(64)
+    }
+    
+    /// `(a + b) * c + d` for 16-bit operands, which cannot wrap a `u64`
+    fn fused(a: u64, b: u64, c: u64, d: u64) -> u64 {
+        (a + b) * c + d
+    }
+    
+    #[test]
+    fn test_add_mul_property() {
+        use proptest::prelude::*;
+        
+        let operands = proptest::array::uniform4(0..=u64::from(u16::MAX));
+        let valid = operands.clone().prop_map(|[a, b, c, d]| [a, b, c, d, fused(a, b, c, d)]);
+        let invalid = (operands, 1..=u64::from(u16::MAX))
+            .prop_map(|([a, b, c, d], delta)| [a, b, c, d, fused(a, b, c, d) + delta]);
+        add_mul().assert_valid(&valid);
+        add_mul().assert_invalid(&invalid);
+    }
+    
     #[test]
     fn test_lookup_table_correctness() {
         // Test that values 0-255 are in lookup table
@@ -57,15 +80,14 @@ mod tests {
             prover.assert_satisfied();
         }
         
-        // Test that 256 is NOT in lookup table (should fail)
+        // PoRE does not range-check its witnesses, so 256 only fails where
+        // the fused gate rejects it; the table itself is pinpointed in
+        // `gadgets::decompose`
         let circuit = PoRECircuit::<Fp>::new(
-            vec![Value::known(Fp::from(256))],
+            [256, 0, 1, 0, 255].map(|w| Value::known(Fp::from(w))).to_vec(),
             vec![],
         );
-        
-        let k = 8;
-        let prover = MockProver::run(k, &circuit, vec![]);
-        assert!(prover.is_err() || !prover.unwrap().verify().is_empty());
+        crate::testing::assert_constraint_fails(8, &circuit, vec![], "add_mul fusion");
     }
     
     #[test]
@@ -93,6 +115,34 @@ mod tests {
         assert!(elapsed.as_millis() < 100, "Proving time exceeds target");
     }
     
+    #[test]
+    fn test_synthesis_reported_to_telemetry() {
+        use crate::telemetry::{Telemetry, TelemetryEvent, TelemetryLog};
+        use std::sync::Arc;
+        
+        let log = Arc::new(TelemetryLog::new());
+        let circuit = PoRECircuit::<Fp>::new(vec![Value::known(Fp::from(1)); 10], vec![])
+            .with_telemetry(Telemetry::new(log.clone()));
+        MockProver::run(8, &circuit, vec![]).unwrap();
+        assert_eq!(
+            log.take(),
+            [
+                TelemetryEvent::SynthesisStarted { circuit: "pore" },
+                TelemetryEvent::Region { circuit: "pore", region: "byte table" },
+                TelemetryEvent::Region { circuit: "pore", region: "main region" },
+                TelemetryEvent::SynthesisFinished { circuit: "pore" },
+            ]
+        );
+        
+        // The keygen shape reports to the same sink
+        let dci = DCICircuit::<Fp>::default().with_telemetry(Telemetry::new(log.clone()));
+        RowUsage::measure(&dci.without_witnesses()).unwrap();
+        let events = log.take();
+        assert_eq!(events.first(), Some(&TelemetryEvent::SynthesisStarted { circuit: "dci" }));
+        assert!(events.contains(&TelemetryEvent::Region { circuit: "dci", region: "merkle path" }));
+        assert_eq!(events.last(), Some(&TelemetryEvent::SynthesisFinished { circuit: "dci" }));
+    }
+    
     #[test]
     fn test_circuit_metrics() {
         use crate::circuits::helpers::CircuitMetrics;
@@ -104,7 +154,8 @@ mod tests {
         let metrics = CircuitMetrics::analyze(&cs);
         println!("{}", metrics.visualize());
         
-        assert!(metrics.total_constraints < 25000);
-        assert_eq!(metrics.advice_columns_used, 10);
+        assert_eq!(metrics.gates, 1);
+        assert_eq!(metrics.advice_columns, 10);
+        assert_eq!(metrics.instance_columns, 3);
     }
 }
\ No newline at end of file
@@ -1,32 +1,397 @@
 //! Error types for the ZK proof system
 
-use std::fmt;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use thiserror::Error as ThisError;
 
 /// Main error type for the ZK proof system
-#[derive(Debug)]
+#[derive(Debug, ThisError)]
 pub enum Error {
     /// Circuit synthesis error
+    #[error("Synthesis error: {0}")]
     Synthesis(String),
     /// Verification error
+    #[error("Verification error: {0}")]
     Verification(String),
+    /// Proving or verifying key generation failed
+    #[error("Key generation error: {0}")]
+    Keygen(String),
+    /// Params were generated for a different circuit size
+    #[error("Params mismatch: expected k = {expected}, found k = {found}")]
+    ParamsMismatch {
+        /// k required by the circuit
+        expected: u32,
+        /// k of the supplied params
+        found: u32,
+    },
+    /// Public instances do not match the circuit's instance columns
+    #[error("Instance shape mismatch: expected {expected:?} rows per column, found {found:?}")]
+    InstanceShape {
+        /// Expected number of rows in each instance column
+        expected: Vec<usize>,
+        /// Supplied number of rows in each instance column
+        found: Vec<usize>,
+    },
+    /// The circuit needs more rows than any supported k provides
+    #[error("Row budget exceeded: {rows} rows do not fit with k <= {max_k}")]
+    RowBudgetExceeded {
+        /// Rows the circuit needs
+        rows: usize,
+        /// Largest k that was considered
+        max_k: u32,
+    },
+    /// A proof was created for a different verifying key
+    #[error("Wrong verifying key: proof targets vk {found}, verifier has vk {expected}")]
+    VkMismatch {
+        /// Hash of the verifier's key
+        expected: String,
+        /// Hash recorded in the proof envelope
+        found: String,
+    },
+    /// Malformed or non-canonical encoded value
+    #[error("Encoding error: {0}")]
+    Encoding(String),
+    /// Error reported by the halo2 proving system
+    #[error(transparent)]
+    Plonk(#[from] halo2_proofs::plonk::Error),
     /// IO error
-    Io(std::io::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    /// Proving or verification ran past its wall-clock limit
+    #[error("Timed out after {limit:?}")]
+    Timeout {
+        /// Limit that was exceeded
+        limit: std::time::Duration,
+    },
+    /// A verifier policy rejected the request before any cryptography ran
+    #[error("Rejected by policy: {0}")]
+    PolicyRejected(String),
+    /// Too many requests; retry later
+    #[error("Rate limited, retry after {retry_after:?}")]
+    RateLimited {
+        /// Earliest time a retry can succeed, zero if unknown
+        retry_after: std::time::Duration,
+    },
     /// Other errors
+    #[error("Error: {0}")]
     Other(String),
+    /// An error annotated with what was being done when it occurred
+    #[error("{context}: {source}")]
+    Context {
+        /// Description of the failed operation
+        context: String,
+        /// Underlying error
+        #[source]
+        source: Box<Error>,
+        /// Backtrace captured where the context was attached
+        ///
+        /// Boxed so `thiserror` does not treat it as a provided backtrace,
+        /// which needs a nightly compiler.
+        #[cfg(feature = "backtrace")]
+        trace: Box<std::backtrace::Backtrace>,
+    },
 }
 
-impl fmt::Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl Error {
+    /// Wrap this error with a description of the failed operation
+    ///
+    /// ```
+    /// # use zk_proof_core::Error;
+    /// let err = Error::Synthesis("region failed".into()).context("during DCI witness build");
+    /// assert_eq!(err.to_string(), "during DCI witness build: Synthesis error: region failed");
+    /// ```
+    #[must_use]
+    pub fn context(self, context: impl Into<String>) -> Self {
+        Self::Context {
+            context: context.into(),
+            source: Box::new(self),
+            #[cfg(feature = "backtrace")]
+            trace: Box::new(std::backtrace::Backtrace::capture()),
+        }
+    }
+
+    /// The innermost error beneath any context layers
+    #[must_use]
+    pub fn root_cause(&self) -> &Self {
+        match self {
+            Self::Context { source, .. } => source.root_cause(),
+            err => err,
+        }
+    }
+
+    /// Backtrace captured where the outermost context was attached
+    #[cfg(feature = "backtrace")]
+    #[must_use]
+    pub fn backtrace(&self) -> Option<&std::backtrace::Backtrace> {
         match self {
-            Self::Synthesis(msg) => write!(f, "Synthesis error: {msg}"),
-            Self::Verification(msg) => write!(f, "Verification error: {msg}"),
-            Self::Io(err) => write!(f, "IO error: {err}"),
-            Self::Other(msg) => write!(f, "Error: {msg}"),
+            Self::Context { trace, .. } => Some(trace),
+            _ => None,
+        }
+    }
+
+    /// Machine-readable category of this error
+    #[must_use]
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Self::Synthesis(_) => ErrorCategory::Synthesis,
+            Self::Verification(_) | Self::VkMismatch { .. } => ErrorCategory::Verification,
+            Self::Keygen(_) => ErrorCategory::Keygen,
+            Self::ParamsMismatch { .. } | Self::RowBudgetExceeded { .. } => {
+                ErrorCategory::ParamsMismatch
+            }
+            Self::InstanceShape { .. } => ErrorCategory::InstanceShape,
+            Self::Encoding(_) => ErrorCategory::Encoding,
+            Self::Plonk(err) => match err {
+                halo2_proofs::plonk::Error::ConstraintSystemFailure
+                | halo2_proofs::plonk::Error::Opening => ErrorCategory::Verification,
+                halo2_proofs::plonk::Error::InvalidInstances
+                | halo2_proofs::plonk::Error::InstanceTooLarge => ErrorCategory::InstanceShape,
+                halo2_proofs::plonk::Error::NotEnoughRowsAvailable { .. } => {
+                    ErrorCategory::ParamsMismatch
+                }
+                halo2_proofs::plonk::Error::Transcript(_) => ErrorCategory::Io,
+                _ => ErrorCategory::Synthesis,
+            },
+            Self::Io(_) => ErrorCategory::Io,
+            Self::Timeout { .. } => ErrorCategory::Timeout,
+            Self::PolicyRejected(_) => ErrorCategory::Policy,
+            Self::RateLimited { .. } => ErrorCategory::RateLimited,
+            Self::Other(_) => ErrorCategory::Internal,
+            Self::Context { source, .. } => source.category(),
+        }
+    }
+
+    /// Convert into a transport-friendly report
+    #[must_use]
+    pub fn to_report(&self) -> ErrorReport {
+        let details = match self.root_cause() {
+            Self::ParamsMismatch { expected, found } => Some(ErrorDetails::ParamsMismatch {
+                expected: *expected,
+                found: *found,
+            }),
+            Self::InstanceShape { expected, found } => Some(ErrorDetails::InstanceShape {
+                expected: expected.clone(),
+                found: found.clone(),
+            }),
+            Self::VkMismatch { expected, found } => Some(ErrorDetails::VkMismatch {
+                expected: expected.clone(),
+                found: found.clone(),
+            }),
+            Self::Plonk(halo2_proofs::plonk::Error::NotEnoughRowsAvailable { current_k }) => {
+                Some(ErrorDetails::NotEnoughRows { k: *current_k })
+            }
+            _ => None,
+        };
+
+        ErrorReport {
+            category: self.category(),
+            message: self.to_string(),
+            details,
         }
     }
 }
 
-impl std::error::Error for Error {}
+/// Coarse error category shared with RPC clients
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ErrorCategory {
+    /// Witness or circuit synthesis failed
+    Synthesis,
+    /// A proof did not verify
+    Verification,
+    /// Key generation failed
+    Keygen,
+    /// Params do not fit the circuit
+    ParamsMismatch,
+    /// Public instances do not fit the circuit
+    InstanceShape,
+    /// Malformed input encoding
+    Encoding,
+    /// IO or transport failure
+    Io,
+    /// A wall-clock limit was exceeded
+    Timeout,
+    /// Rejected by a verifier policy
+    Policy,
+    /// Rejected by a rate or concurrency limit
+    RateLimited,
+    /// Anything else
+    Internal,
+}
+
+/// Structured payload for categories that carry more than a message
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum ErrorDetails {
+    /// Params were generated for a different k
+    ParamsMismatch {
+        /// k required by the circuit
+        expected: u32,
+        /// k of the supplied params
+        found: u32,
+    },
+    /// Instance rows per column did not match
+    InstanceShape {
+        /// Expected rows per instance column
+        expected: Vec<usize>,
+        /// Supplied rows per instance column
+        found: Vec<usize>,
+    },
+    /// The proof targets a different verifying key
+    VkMismatch {
+        /// Hash of the verifier's key
+        expected: String,
+        /// Hash recorded in the proof
+        found: String,
+    },
+    /// The circuit does not fit in 2^k rows
+    NotEnoughRows {
+        /// k that was tried
+        k: u32,
+    },
+}
+
+/// Serializable error representation returned by RPC services
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ErrorReport {
+    /// Machine-readable category
+    pub category: ErrorCategory,
+    /// Human-readable message
+    pub message: String,
+    /// Optional structured payload
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub details: Option<ErrorDetails>,
+}
+
+impl From<&Error> for ErrorReport {
+    fn from(err: &Error) -> Self {
+        err.to_report()
+    }
+}
 
 /// Result type alias
 pub type Result<T> = std::result::Result<T, Error>;
+
+/// Attach context to the error of a `Result`
+pub trait ResultExt<T> {
+    /// Wrap the error with a fixed description
+    ///
+    /// # Errors
+    ///
+    /// Returns the original error wrapped in [`Error::Context`].
+    fn context(self, context: impl Into<String>) -> Result<T>;
+
+    /// Wrap the error with a lazily built description
+    ///
+    /// # Errors
+    ///
+    /// Returns the original error wrapped in [`Error::Context`].
+    fn with_context<C: Into<String>>(self, context: impl FnOnce() -> C) -> Result<T>;
+}
+
+impl<T, E: Into<Error>> ResultExt<T> for std::result::Result<T, E> {
+    fn context(self, context: impl Into<String>) -> Result<T> {
+        self.map_err(|err| err.into().context(context))
+    }
+
+    fn with_context<C: Into<String>>(self, context: impl FnOnce() -> C) -> Result<T> {
+        self.map_err(|err| err.into().context(context()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plonk_error_conversion() {
+        let err: Error = halo2_proofs::plonk::Error::ConstraintSystemFailure.into();
+        assert!(matches!(
+            err,
+            Error::Plonk(halo2_proofs::plonk::Error::ConstraintSystemFailure)
+        ));
+        assert_eq!(
+            err.to_string(),
+            halo2_proofs::plonk::Error::ConstraintSystemFailure.to_string()
+        );
+    }
+
+    #[test]
+    fn test_structured_variants() {
+        let err = Error::ParamsMismatch {
+            expected: 14,
+            found: 12,
+        };
+        assert_eq!(err.to_string(), "Params mismatch: expected k = 14, found k = 12");
+
+        let err = Error::InstanceShape {
+            expected: vec![1, 1],
+            found: vec![1],
+        };
+        assert!(matches!(err, Error::InstanceShape { .. }));
+    }
+
+    #[test]
+    fn test_report_categories() {
+        let report = Error::ParamsMismatch {
+            expected: 14,
+            found: 12,
+        }
+        .to_report();
+        assert_eq!(report.category, ErrorCategory::ParamsMismatch);
+        assert_eq!(
+            report.details,
+            Some(ErrorDetails::ParamsMismatch {
+                expected: 14,
+                found: 12
+            })
+        );
+
+        let report = Error::Other("boom".into()).to_report();
+        assert_eq!(report.category, ErrorCategory::Internal);
+        assert_eq!(report.details, None);
+    }
+
+    #[test]
+    fn test_context_chain() {
+        let result: std::result::Result<(), halo2_proofs::plonk::Error> =
+            Err(halo2_proofs::plonk::Error::Synthesis);
+        let err = result
+            .context("merkle level 3")
+            .with_context(|| format!("during {} witness build", "DCI"))
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "during DCI witness build: merkle level 3: General synthesis error"
+        );
+        assert!(matches!(
+            err.root_cause(),
+            Error::Plonk(halo2_proofs::plonk::Error::Synthesis)
+        ));
+        assert_eq!(err.category(), ErrorCategory::Synthesis);
+
+        let source = std::error::Error::source(&err).unwrap();
+        assert_eq!(source.to_string(), "merkle level 3: General synthesis error");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_report_json_round_trip() {
+        let report = Error::InstanceShape {
+            expected: vec![1, 1],
+            found: vec![1],
+        }
+        .to_report();
+
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains("\"category\":\"instance_shape\""));
+        assert!(json.contains("\"kind\":\"instance_shape\""));
+
+        let decoded: ErrorReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, report);
+    }
+}
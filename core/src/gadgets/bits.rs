@@ -0,0 +1,314 @@
+//! Decomposition into boolean cells and packing them back
+//!
+//! [`BitsChip`] lays bits out little-endian down one column next to a
+//! running sum `acc_i = bit_i + 2 acc_{i+1}`, so `acc_0` is their packing.
+//! Every bit is constrained to 0 or 1 and handed out as its own cell, for
+//! gates that branch on it, such as the direction bits of a Merkle path.
+
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+use super::Gadget;
+use crate::utils::bits::to_le_bits;
+
+/// Boolean cells and the cell they pack into
+#[derive(Clone, Debug)]
+pub struct AssignedBits<F: PrimeField> {
+    /// Bits, least significant first
+    pub bits: Vec<AssignedCell<F, F>>,
+    /// `sum(bits[i] * 2^i)`
+    pub packed: AssignedCell<F, F>,
+}
+
+/// Decomposes cells into bits and packs bits into cells
+#[derive(Clone, Debug)]
+pub struct BitsChip<F: PrimeField> {
+    config: BitsConfig,
+    _marker: PhantomData<F>,
+}
+
+/// Columns and selectors used by [`BitsChip`]
+#[derive(Clone, Debug)]
+pub struct BitsConfig {
+    /// Column holding the bits
+    pub bit: Column<Advice>,
+    /// Column holding the running sum, starting with the packed value
+    pub acc: Column<Advice>,
+    q_acc: Selector,
+    q_top: Selector,
+}
+
+impl<F: PrimeField> BitsChip<F> {
+    /// Decompose `cell` into its low `num_bits` bits, constraining it to
+    /// have no others
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_bits` is zero or not below `F::NUM_BITS`, where a
+    /// value and the value plus the modulus would both decompose.
+    pub fn decompose(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<AssignedBits<F>, Error> {
+        assert!(
+            (1..F::NUM_BITS as usize).contains(&num_bits),
+            "decompositions into {num_bits} bits are not in 1..{}",
+            F::NUM_BITS
+        );
+        let le_bits = cell.value().map(to_le_bits);
+        let bits: Vec<Value<F>> = (0..num_bits)
+            .map(|i| le_bits.as_ref().map(|bits| F::from(u64::from(bits[i]))))
+            .collect();
+        layouter.assign_region(
+            || "decompose bits",
+            |mut region| self.assign_rows(&mut region, &bits, None, Some(cell)),
+        )
+    }
+
+    /// Pack existing `bits`, least significant first, constraining each to
+    /// 0 or 1
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is empty.
+    pub fn pack(
+        &self,
+        mut layouter: impl Layouter<F>,
+        bits: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let values: Vec<Value<F>> = bits.iter().map(|bit| bit.value().copied()).collect();
+        let assigned = layouter.assign_region(
+            || "pack bits",
+            |mut region| self.assign_rows(&mut region, &values, Some(bits), None),
+        )?;
+        Ok(assigned.packed)
+    }
+
+    /// Lay out `bits` and their running sum, copying in existing bit cells
+    /// or an existing packed cell
+    fn assign_rows(
+        &self,
+        region: &mut Region<'_, F>,
+        bits: &[Value<F>],
+        bit_cells: Option<&[AssignedCell<F, F>]>,
+        packed: Option<&AssignedCell<F, F>>,
+    ) -> Result<AssignedBits<F>, Error> {
+        assert!(!bits.is_empty(), "there are no bits to pack");
+        let config = &self.config;
+        let mut accs = vec![Value::known(F::ZERO); bits.len() + 1];
+        for i in (0..bits.len()).rev() {
+            accs[i] = bits[i] + accs[i + 1].map(|acc| acc.double());
+        }
+
+        let mut assigned = Vec::with_capacity(bits.len());
+        let mut first = None;
+        for (i, bit) in bits.iter().enumerate() {
+            if i + 1 < bits.len() {
+                config.q_acc.enable(region, i)?;
+            } else {
+                config.q_top.enable(region, i)?;
+            }
+            let bit = match bit_cells {
+                Some(cells) => cells[i].copy_advice(
+                    || crate::annotation!("bit {}", i),
+                    region,
+                    config.bit,
+                    i,
+                )?,
+                None => region.assign_advice(
+                    || crate::annotation!("bit {}", i),
+                    config.bit,
+                    i,
+                    || *bit,
+                )?,
+            };
+            assigned.push(bit);
+            let acc = match (i, packed) {
+                (0, Some(packed)) => packed.copy_advice(|| "acc 0", region, config.acc, 0)?,
+                _ => region.assign_advice(
+                    || crate::annotation!("acc {}", i),
+                    config.acc,
+                    i,
+                    || accs[i],
+                )?,
+            };
+            if i == 0 {
+                first = Some(acc);
+            }
+        }
+        Ok(AssignedBits {
+            bits: assigned,
+            packed: first.ok_or(Error::Synthesis)?,
+        })
+    }
+}
+
+impl<F: PrimeField> Gadget<F> for BitsChip<F> {
+    type Config = BitsConfig;
+    /// Bit and running sum columns
+    type Columns = [Column<Advice>; 2];
+    /// Bits as field elements, least significant first; anything but 0 or
+    /// 1 fails the circuit
+    type Input = Vec<Value<F>>;
+    type Output = AssignedBits<F>;
+
+    fn construct(config: BitsConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn config(&self) -> &BitsConfig {
+        &self.config
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>, [bit, acc]: [Column<Advice>; 2]) -> BitsConfig {
+        let q_acc = meta.selector();
+        let q_top = meta.selector();
+        meta.enable_equality(bit);
+        meta.enable_equality(acc);
+
+        meta.create_gate("bit decomposition", |meta| {
+            let q_acc = meta.query_selector(q_acc);
+            let q_top = meta.query_selector(q_top);
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_next = meta.query_advice(acc, Rotation::next());
+            let one = Expression::Constant(F::ONE);
+            let two = Expression::Constant(F::from(2));
+
+            // acc_i = bit_i + 2 acc_{i+1}, and the last acc is its bit
+            vec![
+                (q_acc.clone() + q_top.clone()) * bit.clone() * (one - bit.clone()),
+                q_acc * (acc_cur.clone() - bit.clone() - two * acc_next),
+                q_top * (acc_cur - bit),
+            ]
+        });
+
+        BitsConfig {
+            bit,
+            acc,
+            q_acc,
+            q_top,
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `bits` is empty.
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        bits: Vec<Value<F>>,
+    ) -> Result<AssignedBits<F>, Error> {
+        layouter.assign_region(
+            || "witness bits",
+            |mut region| self.assign_rows(&mut region, &bits, None, None),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        plonk::{Circuit, Instance},
+    };
+    use pasta_curves::Fp;
+
+    use super::*;
+
+    /// Decomposes `value` into 8 bits, repacks them and witnesses `bits`,
+    /// exposing both packings
+    #[derive(Default)]
+    struct BitsCircuit {
+        value: Value<Fp>,
+        bits: Vec<Value<Fp>>,
+    }
+
+    impl Circuit<Fp> for BitsCircuit {
+        type Config = (BitsConfig, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                value: Value::unknown(),
+                bits: vec![Value::unknown(); self.bits.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let columns = [meta.advice_column(), meta.advice_column()];
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (BitsChip::configure(meta, columns), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = BitsChip::construct(config.clone());
+            let value = layouter.assign_region(
+                || "value",
+                |mut region| region.assign_advice(|| "value", config.acc, 0, || self.value),
+            )?;
+            let decomposed = chip.decompose(layouter.namespace(|| "decompose"), &value, 8)?;
+            let repacked = chip.pack(layouter.namespace(|| "repack"), &decomposed.bits)?;
+            let witnessed = chip.assign(layouter.namespace(|| "witness"), self.bits.clone())?;
+            layouter.constrain_instance(repacked.cell(), instance, 0)?;
+            layouter.constrain_instance(witnessed.packed.cell(), instance, 1)
+        }
+    }
+
+    fn accepts(value: u64, bits: &[u64], instance: [u64; 2]) -> bool {
+        let circuit = BitsCircuit {
+            value: Value::known(Fp::from(value)),
+            bits: bits
+                .iter()
+                .map(|bit| Value::known(Fp::from(*bit)))
+                .collect(),
+        };
+        let instance = instance.map(Fp::from).to_vec();
+        MockProver::run(6, &circuit, vec![instance])
+            .unwrap()
+            .verify()
+            .is_ok()
+    }
+
+    #[test]
+    fn test_decompose_and_pack() {
+        assert!(accepts(0xa5, &[1, 0, 1, 1], [0xa5, 0b1101]));
+        assert!(accepts(0xff, &[0], [0xff, 0]));
+        assert!(!accepts(0xa5, &[1, 0, 1, 1], [0xa4, 0b1101]));
+        assert!(!accepts(0xa5, &[1, 0, 1, 1], [0xa5, 0b1011]));
+    }
+
+    #[test]
+    fn test_out_of_range_and_non_bits_rejected() {
+        // 0x1a5 has a ninth bit
+        assert!(!accepts(0x1a5, &[1], [0xa5, 1]));
+        assert!(!accepts(0x1a5, &[1], [0x1a5, 1]));
+        // 2 = 0 + 2 * 1 packs, but is not a bit
+        assert!(!accepts(0, &[2, 0], [0, 2]));
+    }
+}
@@ -0,0 +1,145 @@
+//! Boolean constraint chip
+
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+use super::Gadget;
+
+/// Constrains a cell to 0 or 1
+#[derive(Clone, Debug)]
+pub struct BooleanChip<F: Field> {
+    config: BooleanConfig,
+    _marker: PhantomData<F>,
+}
+
+/// Column and selector used by [`BooleanChip`]
+#[derive(Clone, Debug)]
+pub struct BooleanConfig {
+    /// Column holding the bit
+    pub value: Column<Advice>,
+    q_bool: Selector,
+}
+
+impl<F: Field> Gadget<F> for BooleanChip<F> {
+    type Config = BooleanConfig;
+    type Columns = Column<Advice>;
+    type Input = Value<bool>;
+    type Output = AssignedCell<F, F>;
+
+    fn construct(config: BooleanConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn config(&self) -> &BooleanConfig {
+        &self.config
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>, value: Column<Advice>) -> BooleanConfig {
+        let q_bool = meta.selector();
+        meta.enable_equality(value);
+
+        meta.create_gate("boolean", |meta| {
+            let q = meta.query_selector(q_bool);
+            let x = meta.query_advice(value, Rotation::cur());
+            vec![q * x.clone() * (Expression::Constant(F::ONE) - x)]
+        });
+
+        BooleanConfig { value, q_bool }
+    }
+
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        bit: Value<bool>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "boolean",
+            |mut region| {
+                self.config.q_bool.enable(&mut region, 0)?;
+                region.assign_advice(
+                    || "bit",
+                    self.config.value,
+                    0,
+                    || bit.map(|bit| if bit { F::ONE } else { F::ZERO }),
+                )
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{circuit::SimpleFloorPlanner, dev::MockProver, plonk::Circuit};
+    use pasta_curves::Fp;
+
+    use super::*;
+
+    /// Assigns one bit, or a raw value to exercise the gate
+    #[derive(Default)]
+    struct BitCircuit {
+        raw: Value<Fp>,
+    }
+
+    impl Circuit<Fp> for BitCircuit {
+        type Config = BooleanConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> BooleanConfig {
+            let value = meta.advice_column();
+            BooleanChip::configure(meta, value)
+        }
+
+        fn synthesize(
+            &self,
+            config: BooleanConfig,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = BooleanChip::<Fp>::construct(config.clone());
+            chip.assign(layouter.namespace(|| "true"), Value::known(true))?;
+            chip.assign(layouter.namespace(|| "false"), Value::known(false))?;
+
+            layouter.assign_region(
+                || "raw",
+                |mut region| {
+                    config.q_bool.enable(&mut region, 0)?;
+                    region.assign_advice(|| "raw", config.value, 0, || self.raw)
+                },
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_bits_accepted() {
+        let circuit = BitCircuit {
+            raw: Value::known(Fp::ONE),
+        };
+        MockProver::run(4, &circuit, vec![])
+            .unwrap()
+            .assert_satisfied();
+    }
+
+    #[test]
+    fn test_non_bit_rejected() {
+        let circuit = BitCircuit {
+            raw: Value::known(Fp::from(2)),
+        };
+        assert!(MockProver::run(4, &circuit, vec![])
+            .unwrap()
+            .verify()
+            .is_err());
+    }
+}
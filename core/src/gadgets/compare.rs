@@ -0,0 +1,524 @@
+//! Zero tests, comparisons and conditional selection
+//!
+//! Each chip copies its operands in, so they compose with cells from any
+//! other chip, and returns its result as a new cell. Results that are
+//! booleans are constrained to 0 or 1 and can be fed to [`SelectChip`].
+
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+use super::{Gadget, RangeCheckChip, RangeCheckConfig};
+use crate::utils::bits::fits_in_bits;
+
+/// Chip range-checking the difference [`LessThanChip`] splits off
+type ChunkChip<F> = RangeCheckChip<F, 8>;
+
+/// `F::ONE` for `true`, `F::ZERO` for `false`
+fn from_bool<F: PrimeField>(bit: bool) -> F {
+    if bit {
+        F::ONE
+    } else {
+        F::ZERO
+    }
+}
+
+/// Tests a cell for zero
+#[derive(Clone, Debug)]
+pub struct IsZeroChip<F: PrimeField> {
+    config: IsZeroConfig,
+    _marker: PhantomData<F>,
+}
+
+/// Columns and selector used by [`IsZeroChip`]
+#[derive(Clone, Debug)]
+pub struct IsZeroConfig {
+    /// Value, its inverse or zero, and whether it is zero
+    pub advice: [Column<Advice>; 3],
+    q_is_zero: Selector,
+}
+
+impl<F: PrimeField> IsZeroChip<F> {
+    /// Constrain a cell holding 1 if `value` is zero and 0 otherwise
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails.
+    pub fn is_zero(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let [value_column, inverse, output] = self.config.advice;
+        let inverted = value.value().map(|value| value.invert().unwrap_or(F::ZERO));
+        layouter.assign_region(
+            || "is zero",
+            |mut region| {
+                self.config.q_is_zero.enable(&mut region, 0)?;
+                value.copy_advice(|| "value", &mut region, value_column, 0)?;
+                region.assign_advice(|| "inverse", inverse, 0, || inverted)?;
+                region.assign_advice(
+                    || "is zero",
+                    output,
+                    0,
+                    || {
+                        value
+                            .value()
+                            .map(|value| from_bool(bool::from(value.is_zero())))
+                    },
+                )
+            },
+        )
+    }
+}
+
+impl<F: PrimeField> Gadget<F> for IsZeroChip<F> {
+    type Config = IsZeroConfig;
+    /// Value, inverse and result columns
+    type Columns = [Column<Advice>; 3];
+    type Input = Value<F>;
+    type Output = AssignedCell<F, F>;
+
+    fn construct(config: IsZeroConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn config(&self) -> &IsZeroConfig {
+        &self.config
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 3]) -> IsZeroConfig {
+        let q_is_zero = meta.selector();
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("is zero", |meta| {
+            let q = meta.query_selector(q_is_zero);
+            let [value, inverse, output] =
+                advice.map(|column| meta.query_advice(column, Rotation::cur()));
+
+            // output = 1 - value * inverse, and output is 0 unless value is
+            vec![
+                q.clone()
+                    * (output.clone() - (Expression::Constant(F::ONE) - value.clone() * inverse)),
+                q * value * output,
+            ]
+        });
+
+        IsZeroConfig { advice, q_is_zero }
+    }
+
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let value = layouter.assign_region(
+            || "value",
+            |mut region| region.assign_advice(|| "value", self.config.advice[0], 0, || value),
+        )?;
+        self.is_zero(layouter.namespace(|| "is zero"), &value)
+    }
+}
+
+/// Compares `BITS`-bit values
+///
+/// The operands are not range-checked here: both must be known to be below
+/// `2^BITS`, or the result means nothing.
+#[derive(Clone, Debug)]
+pub struct LessThanChip<F: PrimeField, const BITS: usize> {
+    config: LessThanConfig,
+    _marker: PhantomData<F>,
+}
+
+/// Columns, selector and range check used by [`LessThanChip`]
+#[derive(Clone, Debug)]
+pub struct LessThanConfig {
+    /// Operands `a` and `b`, whether `a < b`, and the low `BITS` bits of
+    /// `a - b + 2^BITS`, whose range checks run down the last column
+    pub advice: [Column<Advice>; 4],
+    q_less_than: Selector,
+    range: RangeCheckConfig,
+}
+
+impl<F: PrimeField, const BITS: usize> LessThanChip<F, BITS> {
+    /// Constrain a cell holding 1 if `a < b` and 0 otherwise
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails.
+    pub fn less_than(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let [a_column, b_column, lt_column, low_column] = self.config.advice;
+        // a - b + 2^BITS is below 2^BITS exactly when a < b
+        let split = a.value().zip(b.value()).map(|(a, b)| {
+            let diff = *a - b + two_pow::<F>(BITS);
+            if fits_in_bits(&diff, BITS) {
+                (F::ONE, diff)
+            } else {
+                (F::ZERO, diff - two_pow::<F>(BITS))
+            }
+        });
+        let (lt, low) = layouter.assign_region(
+            || "less than",
+            |mut region| {
+                self.config.q_less_than.enable(&mut region, 0)?;
+                a.copy_advice(|| "a", &mut region, a_column, 0)?;
+                b.copy_advice(|| "b", &mut region, b_column, 0)?;
+                let lt = region.assign_advice(|| "a < b", lt_column, 0, || split.map(|s| s.0))?;
+                let low = region.assign_advice(|| "low", low_column, 0, || split.map(|s| s.1))?;
+                Ok((lt, low))
+            },
+        )?;
+        ChunkChip::construct(self.config.range.clone()).check(
+            layouter.namespace(|| "low range"),
+            &low,
+            BITS,
+        )?;
+        Ok(lt)
+    }
+}
+
+/// `2^bits` in `F`
+fn two_pow<F: PrimeField>(bits: usize) -> F {
+    F::from(2).pow_vartime([bits as u64])
+}
+
+impl<F: PrimeField, const BITS: usize> Gadget<F> for LessThanChip<F, BITS> {
+    type Config = LessThanConfig;
+    /// Operand, result and difference columns
+    type Columns = [Column<Advice>; 4];
+    /// Operands `a` and `b`
+    type Input = (Value<F>, Value<F>);
+    type Output = AssignedCell<F, F>;
+
+    fn construct(config: LessThanConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn config(&self) -> &LessThanConfig {
+        &self.config
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `BITS` is zero or `BITS + 1` is not below `F::NUM_BITS`,
+    /// where `a - b + 2^BITS` could wrap around the modulus.
+    fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 4]) -> LessThanConfig {
+        assert!(
+            BITS > 0 && BITS + 1 < F::NUM_BITS as usize,
+            "comparisons of {BITS} bits are not supported in a {}-bit field",
+            F::NUM_BITS
+        );
+        let q_less_than = meta.selector();
+        for column in advice {
+            meta.enable_equality(column);
+        }
+        let range = ChunkChip::configure(meta, advice[3]);
+
+        meta.create_gate("less than", |meta| {
+            let q = meta.query_selector(q_less_than);
+            let [a, b, lt, low] = advice.map(|column| meta.query_advice(column, Rotation::cur()));
+            let one = Expression::Constant(F::ONE);
+            let shift = Expression::Constant(two_pow::<F>(BITS));
+
+            // a - b + 2^BITS = low + 2^BITS (1 - lt) with low < 2^BITS
+            vec![
+                q.clone() * lt.clone() * (one.clone() - lt.clone()),
+                q * (a - b + shift.clone() - low - shift * (one - lt)),
+            ]
+        });
+
+        LessThanConfig {
+            advice,
+            q_less_than,
+            range,
+        }
+    }
+
+    fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        ChunkChip::construct(self.config.range.clone()).load(layouter)
+    }
+
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        (a, b): (Value<F>, Value<F>),
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let [a_column, b_column, ..] = self.config.advice;
+        let (a, b) = layouter.assign_region(
+            || "operands",
+            |mut region| {
+                let a = region.assign_advice(|| "a", a_column, 0, || a)?;
+                let b = region.assign_advice(|| "b", b_column, 0, || b)?;
+                Ok((a, b))
+            },
+        )?;
+        self.less_than(layouter.namespace(|| "a < b"), &a, &b)
+    }
+}
+
+/// Picks one of two cells by a boolean
+#[derive(Clone, Debug)]
+pub struct SelectChip<F: PrimeField> {
+    config: SelectConfig,
+    _marker: PhantomData<F>,
+}
+
+/// Columns and selector used by [`SelectChip`]
+#[derive(Clone, Debug)]
+pub struct SelectConfig {
+    /// Condition, the value picked when it is 1, the value picked when it
+    /// is 0, and the result
+    pub advice: [Column<Advice>; 4],
+    q_select: Selector,
+}
+
+impl<F: PrimeField> SelectChip<F> {
+    /// Constrain a cell holding `if_true` if `condition` is 1 and
+    /// `if_false` if it is 0; any other condition fails the circuit
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails.
+    pub fn select(
+        &self,
+        mut layouter: impl Layouter<F>,
+        condition: &AssignedCell<F, F>,
+        if_true: &AssignedCell<F, F>,
+        if_false: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let [condition_column, true_column, false_column, output] = self.config.advice;
+        let selected = condition
+            .value()
+            .zip(if_true.value())
+            .zip(if_false.value())
+            .map(|((condition, if_true), if_false)| {
+                if *condition == F::ONE {
+                    *if_true
+                } else {
+                    *if_false
+                }
+            });
+        layouter.assign_region(
+            || "select",
+            |mut region| {
+                self.config.q_select.enable(&mut region, 0)?;
+                condition.copy_advice(|| "condition", &mut region, condition_column, 0)?;
+                if_true.copy_advice(|| "if true", &mut region, true_column, 0)?;
+                if_false.copy_advice(|| "if false", &mut region, false_column, 0)?;
+                region.assign_advice(|| "selected", output, 0, || selected)
+            },
+        )
+    }
+}
+
+impl<F: PrimeField> Gadget<F> for SelectChip<F> {
+    type Config = SelectConfig;
+    /// Condition, operand and result columns
+    type Columns = [Column<Advice>; 4];
+    /// Condition and the values picked when it is true and false
+    type Input = (Value<bool>, Value<F>, Value<F>);
+    type Output = AssignedCell<F, F>;
+
+    fn construct(config: SelectConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn config(&self) -> &SelectConfig {
+        &self.config
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 4]) -> SelectConfig {
+        let q_select = meta.selector();
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("select", |meta| {
+            let q = meta.query_selector(q_select);
+            let [condition, if_true, if_false, output] =
+                advice.map(|column| meta.query_advice(column, Rotation::cur()));
+
+            // output = condition (if_true - if_false) + if_false
+            vec![
+                q.clone() * condition.clone() * (Expression::Constant(F::ONE) - condition.clone()),
+                q * (output - (condition * (if_true - if_false.clone()) + if_false)),
+            ]
+        });
+
+        SelectConfig { advice, q_select }
+    }
+
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        (condition, if_true, if_false): Self::Input,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let [condition_column, true_column, false_column, _] = self.config.advice;
+        let (condition, if_true, if_false) = layouter.assign_region(
+            || "select operands",
+            |mut region| {
+                let condition = region.assign_advice(
+                    || "condition",
+                    condition_column,
+                    0,
+                    || condition.map(from_bool),
+                )?;
+                let if_true = region.assign_advice(|| "if true", true_column, 0, || if_true)?;
+                let if_false = region.assign_advice(|| "if false", false_column, 0, || if_false)?;
+                Ok((condition, if_true, if_false))
+            },
+        )?;
+        self.select(
+            layouter.namespace(|| "select"),
+            &condition,
+            &if_true,
+            &if_false,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        plonk::{Circuit, Instance},
+    };
+    use pasta_curves::Fp;
+
+    use super::*;
+
+    /// Exposes `a == 0`, `a < b` over 16 bits and `condition ? a : b`
+    #[derive(Default)]
+    struct CompareCircuit {
+        a: Value<Fp>,
+        b: Value<Fp>,
+        condition: Value<Fp>,
+    }
+
+    #[derive(Clone, Debug)]
+    struct CompareConfig {
+        is_zero: IsZeroConfig,
+        less_than: LessThanConfig,
+        select: SelectConfig,
+        instance: Column<Instance>,
+    }
+
+    impl Circuit<Fp> for CompareCircuit {
+        type Config = CompareConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> CompareConfig {
+            let advice = [(); 4].map(|()| meta.advice_column());
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            CompareConfig {
+                is_zero: IsZeroChip::configure(meta, [advice[0], advice[1], advice[2]]),
+                less_than: LessThanChip::<Fp, 16>::configure(meta, advice),
+                select: SelectChip::configure(meta, advice),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: CompareConfig,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let less_than = LessThanChip::<Fp, 16>::construct(config.less_than);
+            less_than.load(&mut layouter)?;
+            let [a, b, condition] = layouter.assign_region(
+                || "inputs",
+                |mut region| {
+                    let advice = config.select.advice;
+                    Ok([
+                        region.assign_advice(|| "a", advice[0], 0, || self.a)?,
+                        region.assign_advice(|| "b", advice[1], 0, || self.b)?,
+                        region.assign_advice(|| "condition", advice[2], 0, || self.condition)?,
+                    ])
+                },
+            )?;
+
+            let is_zero = IsZeroChip::construct(config.is_zero)
+                .is_zero(layouter.namespace(|| "a == 0"), &a)?;
+            let lt = less_than.less_than(layouter.namespace(|| "a < b"), &a, &b)?;
+            let selected = SelectChip::construct(config.select).select(
+                layouter.namespace(|| "condition ? a : b"),
+                &condition,
+                &a,
+                &b,
+            )?;
+            for (row, cell) in [is_zero, lt, selected].iter().enumerate() {
+                layouter.constrain_instance(cell.cell(), config.instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Whether the circuit accepts `outputs` for `a`, `b` and `condition`
+    fn accepts(a: u64, b: u64, condition: u64, outputs: [u64; 3]) -> bool {
+        let circuit = CompareCircuit {
+            a: Value::known(Fp::from(a)),
+            b: Value::known(Fp::from(b)),
+            condition: Value::known(Fp::from(condition)),
+        };
+        let instance = outputs.map(Fp::from).to_vec();
+        MockProver::run(9, &circuit, vec![instance])
+            .unwrap()
+            .verify()
+            .is_ok()
+    }
+
+    #[test]
+    fn test_is_zero() {
+        assert!(accepts(0, 1, 1, [1, 1, 0]));
+        assert!(accepts(5, 1, 1, [0, 0, 5]));
+        assert!(!accepts(0, 1, 1, [0, 1, 0]));
+        assert!(!accepts(5, 1, 1, [1, 0, 5]));
+    }
+
+    #[test]
+    fn test_less_than() {
+        assert!(accepts(3, 5, 0, [0, 1, 5]));
+        assert!(accepts(5, 3, 0, [0, 0, 3]));
+        assert!(accepts(5, 5, 0, [0, 0, 5]));
+        assert!(accepts(0, 0xffff, 0, [1, 1, 0xffff]));
+        assert!(accepts(0xffff, 0, 0, [0, 0, 0]));
+        assert!(!accepts(3, 5, 0, [0, 0, 5]));
+        assert!(!accepts(5, 3, 0, [0, 1, 3]));
+    }
+
+    #[test]
+    fn test_select() {
+        assert!(accepts(3, 5, 1, [0, 1, 3]));
+        assert!(!accepts(3, 5, 1, [0, 1, 5]));
+        // The condition must be a bit
+        assert!(!accepts(3, 5, 2, [0, 1, 5]));
+        assert!(!accepts(3, 5, 2, [0, 1, 3]));
+    }
+}
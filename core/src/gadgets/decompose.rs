@@ -0,0 +1,259 @@
+//! Running-sum decomposition into range-checked limbs
+
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector, TableColumn},
+    poly::Rotation,
+};
+
+use super::{range::MAX_RANGE_BITS, Gadget};
+use crate::utils::bits::{from_windows, to_windows};
+
+/// Constrains a cell to `LIMBS` little-endian limbs of `LIMB_BITS` bits
+///
+/// The cell's value is `z_0` of a running sum `z_{i+1} = (z_i - limb_i) /
+/// 2^LIMB_BITS` laid out down one column. Every limb is looked up in a
+/// `LIMB_BITS`-bit table and the last `z` is a limb itself, so the value is
+/// below `2^(LIMB_BITS * LIMBS)`. [`Gadget::configure`] requires the limbs
+/// to be narrower than the field, so no value also decomposes as itself
+/// plus the modulus.
+#[derive(Clone, Debug)]
+pub struct DecomposeChip<F: PrimeField, const LIMB_BITS: usize, const LIMBS: usize> {
+    config: DecomposeConfig,
+    _marker: PhantomData<F>,
+}
+
+/// Column, selectors and table used by [`DecomposeChip`]
+#[derive(Clone, Debug)]
+pub struct DecomposeConfig {
+    /// Column holding the running sum, starting with the decomposed value
+    pub z: Column<Advice>,
+    q_lookup: Selector,
+    q_running: Selector,
+    table: TableColumn,
+}
+
+impl<F: PrimeField, const LIMB_BITS: usize, const LIMBS: usize> DecomposeChip<F, LIMB_BITS, LIMBS> {
+    /// Decompose an existing cell
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails.
+    pub fn decompose(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "decompose",
+            |mut region| {
+                let z0 = cell.copy_advice(|| "z 0", &mut region, self.config.z, 0)?;
+                self.assign_running_sum(&mut region, z0.value().copied())?;
+                Ok(z0)
+            },
+        )
+    }
+
+    /// Assign `z_1..z_LIMBS` and enable the limb lookups
+    fn assign_running_sum(&self, region: &mut Region<'_, F>, value: Value<F>) -> Result<(), Error> {
+        let windows = value.map(|value| to_windows(&value, LIMB_BITS));
+        for i in 0..LIMBS {
+            self.config.q_lookup.enable(region, i)?;
+            if i + 1 < LIMBS {
+                self.config.q_running.enable(region, i)?;
+                region.assign_advice(
+                    || crate::annotation!("z {}", i + 1),
+                    self.config.z,
+                    i + 1,
+                    || {
+                        windows
+                            .as_ref()
+                            .map(|windows| from_windows(&windows[i + 1..], LIMB_BITS))
+                    },
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F: PrimeField, const LIMB_BITS: usize, const LIMBS: usize> Gadget<F>
+    for DecomposeChip<F, LIMB_BITS, LIMBS>
+{
+    type Config = DecomposeConfig;
+    type Columns = Column<Advice>;
+    type Input = Value<F>;
+    type Output = AssignedCell<F, F>;
+
+    fn construct(config: DecomposeConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn config(&self) -> &DecomposeConfig {
+        &self.config
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `LIMB_BITS` is zero or exceeds [`MAX_RANGE_BITS`], if
+    /// `LIMBS` is zero, or if the limbs span `F::NUM_BITS` bits or more, so
+    /// a value and the same value plus the modulus would both decompose.
+    fn configure(meta: &mut ConstraintSystem<F>, z: Column<Advice>) -> DecomposeConfig {
+        assert!(
+            (1..=MAX_RANGE_BITS).contains(&LIMB_BITS),
+            "limbs of {LIMB_BITS} bits are not in 1..={MAX_RANGE_BITS}"
+        );
+        assert!(LIMBS > 0, "decomposition needs at least one limb");
+        assert!(
+            LIMB_BITS * LIMBS < F::NUM_BITS as usize,
+            "{LIMBS} limbs of {LIMB_BITS} bits are not canonical in a {}-bit field",
+            F::NUM_BITS
+        );
+        let q_lookup = meta.complex_selector();
+        let q_running = meta.complex_selector();
+        let table = meta.lookup_table_column();
+        meta.enable_equality(z);
+
+        meta.lookup(|meta| {
+            let q_lookup = meta.query_selector(q_lookup);
+            let q_running = meta.query_selector(q_running);
+            let z_cur = meta.query_advice(z, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+            // limb_i = z_i - 2^LIMB_BITS * z_{i+1}, and the last z is a limb
+            let shift = Expression::Constant(F::from(1 << LIMB_BITS));
+            let limb = z_cur - q_running * shift * z_next;
+            vec![(q_lookup * limb, table)]
+        });
+
+        DecomposeConfig {
+            z,
+            q_lookup,
+            q_running,
+            table,
+        }
+    }
+
+    fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || crate::annotation!("{}-bit limb table", LIMB_BITS),
+            |mut table| {
+                for value in 0..1u64 << LIMB_BITS {
+                    #[allow(clippy::cast_possible_truncation)]
+                    table.assign_cell(
+                        || "limb",
+                        self.config.table,
+                        value as usize,
+                        || Value::known(F::from(value)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "decompose",
+            |mut region| {
+                let z0 = region.assign_advice(|| "z 0", self.config.z, 0, || value)?;
+                self.assign_running_sum(&mut region, value)?;
+                Ok(z0)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use halo2_proofs::{circuit::SimpleFloorPlanner, dev::MockProver, plonk::Circuit};
+    use pasta_curves::Fp;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct DecomposeCircuit<const LIMB_BITS: usize, const LIMBS: usize> {
+        values: Vec<Value<Fp>>,
+    }
+
+    impl<const LIMB_BITS: usize, const LIMBS: usize> Circuit<Fp>
+        for DecomposeCircuit<LIMB_BITS, LIMBS>
+    {
+        type Config = DecomposeConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                values: vec![Value::unknown(); self.values.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> DecomposeConfig {
+            let z = meta.advice_column();
+            DecomposeChip::<Fp, LIMB_BITS, LIMBS>::configure(meta, z)
+        }
+
+        fn synthesize(
+            &self,
+            config: DecomposeConfig,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = DecomposeChip::<Fp, LIMB_BITS, LIMBS>::construct(config);
+            chip.load(&mut layouter)?;
+            for value in &self.values {
+                chip.assign(layouter.namespace(|| "value"), *value)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn verify<const LIMB_BITS: usize, const LIMBS: usize>(value: Fp) -> bool {
+        let circuit = DecomposeCircuit::<LIMB_BITS, LIMBS> {
+            values: vec![Value::known(value)],
+        };
+        MockProver::run(9, &circuit, vec![])
+            .unwrap()
+            .verify()
+            .is_ok()
+    }
+
+    #[test]
+    fn test_in_range_accepted() {
+        for value in [0, 1, 0xff, 0x1234_5678, u64::MAX] {
+            assert!(verify::<8, 8>(Fp::from(value)), "{value:#x}");
+        }
+        assert!(verify::<4, 3>(Fp::from(0xfff)));
+        assert!(verify::<8, 1>(Fp::from(0xff)));
+    }
+
+    #[test]
+    fn test_out_of_range_rejected() {
+        assert!(!verify::<8, 8>(Fp::from_u128(1 << 64)));
+        assert!(!verify::<8, 8>(-Fp::ONE));
+        assert!(!verify::<4, 3>(Fp::from(0x1000)));
+        assert!(!verify::<8, 1>(Fp::from(0x100)));
+
+        let circuit = DecomposeCircuit::<8, 1> {
+            values: vec![Value::known(Fp::from(0x100))],
+        };
+        crate::testing::assert_constraint_fails(9, &circuit, vec![], "decompose");
+    }
+
+    #[test]
+    #[should_panic(expected = "not canonical")]
+    fn test_field_wide_decomposition_rejected() {
+        let mut meta = ConstraintSystem::<Fp>::default();
+        let z = meta.advice_column();
+        DecomposeChip::<Fp, 15, 17>::configure(&mut meta, z);
+    }
+}
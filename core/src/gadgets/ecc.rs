@@ -0,0 +1,607 @@
+//! Short Weierstrass point chip over the curve's base field
+//!
+//! Points are affine and addition is incomplete: it is only sound for
+//! non-identity inputs with distinct x-coordinates. Callers that cannot rule
+//! out doubling or the identity need a complete addition chip instead.
+//!
+//! [`EccChip::mul_ladder`] multiplies by a variable scalar with a
+//! double-and-add-or-subtract ladder that starts from `[2] P`. Every step
+//! adds `±P` to the accumulator and then the old accumulator again, so the
+//! multiples stay between 2 and `2^(n + 2)` and no incomplete addition ever
+//! meets an exceptional case. The price is that the ladder computes
+//! `[2^n + 2k + 1] P` rather than `[k] P`; callers define their scalars
+//! through the same map.
+
+use std::marker::PhantomData;
+
+use ff::Field;
+use halo2_proofs::{
+    arithmetic::{Coordinates, CurveAffine},
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+use super::Gadget;
+use crate::utils::bits::to_le_bits;
+
+/// Point addition and on-curve checks for `C`, with `C::Base` as the circuit
+/// field
+#[derive(Clone, Debug)]
+pub struct EccChip<C: CurveAffine> {
+    config: EccConfig,
+    _marker: PhantomData<C>,
+}
+
+/// Columns and selectors used by [`EccChip`]
+#[derive(Clone, Debug)]
+pub struct EccConfig {
+    /// x-coordinate column
+    pub x: Column<Advice>,
+    /// y-coordinate column
+    pub y: Column<Advice>,
+    /// Slope of additions and doublings, and the ladder's bits and running
+    /// sum
+    pub lambda: Column<Advice>,
+    q_point: Selector,
+    q_add: Selector,
+    q_double: Selector,
+    q_ladder: Selector,
+}
+
+/// An assigned affine point
+#[derive(Clone, Debug)]
+pub struct EccPoint<F: Field> {
+    /// x-coordinate
+    pub x: AssignedCell<F, F>,
+    /// y-coordinate
+    pub y: AssignedCell<F, F>,
+}
+
+impl<F: Field> EccPoint<F> {
+    /// The point's coordinates
+    #[must_use]
+    pub fn coordinates(&self) -> Value<(F, F)> {
+        self.x.value().copied().zip(self.y.value().copied())
+    }
+}
+
+impl<C: CurveAffine> EccChip<C> {
+    /// Witness a point and check that it is on the curve
+    ///
+    /// The identity has no affine coordinates and fails the on-curve check.
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails.
+    pub fn witness_point(
+        &self,
+        mut layouter: impl Layouter<C::Base>,
+        point: Value<C>,
+    ) -> Result<EccPoint<C::Base>, Error> {
+        let coordinates = point.map(|point| affine(&point));
+        layouter.assign_region(
+            || "witness point",
+            |mut region| {
+                self.config.q_point.enable(&mut region, 0)?;
+                let x =
+                    region.assign_advice(|| "x", self.config.x, 0, || coordinates.map(|c| c.0))?;
+                let y =
+                    region.assign_advice(|| "y", self.config.y, 0, || coordinates.map(|c| c.1))?;
+                Ok(EccPoint { x, y })
+            },
+        )
+    }
+
+    /// Assign `point`, which the circuit fixes, from the constants column
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails, including
+    /// when the circuit has no fixed column enabled for constants.
+    pub fn constant_point(
+        &self,
+        mut layouter: impl Layouter<C::Base>,
+        point: C,
+    ) -> Result<EccPoint<C::Base>, Error> {
+        let (x, y) = affine(&point);
+        layouter.assign_region(
+            || "constant point",
+            |mut region| {
+                let x = region.assign_advice_from_constant(|| "x", self.config.x, 0, x)?;
+                let y = region.assign_advice_from_constant(|| "y", self.config.y, 0, y)?;
+                Ok(EccPoint { x, y })
+            },
+        )
+    }
+
+    /// Add two points with distinct x-coordinates
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails.
+    pub fn add(
+        &self,
+        mut layouter: impl Layouter<C::Base>,
+        lhs: &EccPoint<C::Base>,
+        rhs: &EccPoint<C::Base>,
+    ) -> Result<EccPoint<C::Base>, Error> {
+        let config = &self.config;
+        let lambda = lhs
+            .coordinates()
+            .zip(rhs.coordinates())
+            .map(|((xp, yp), (xq, yq))| {
+                // A zero denominator leaves the slope gate unsatisfiable.
+                (yq - yp) * (xq - xp).invert().unwrap_or(C::Base::ZERO)
+            });
+        let sum = lhs.coordinates().zip(rhs.coordinates()).zip(lambda).map(
+            |(((xp, yp), (xq, _)), lambda)| {
+                let xr = lambda.square() - xp - xq;
+                (xr, lambda * (xp - xr) - yp)
+            },
+        );
+
+        layouter.assign_region(
+            || "point addition",
+            |mut region| {
+                config.q_add.enable(&mut region, 0)?;
+                config.q_point.enable(&mut region, 2)?;
+
+                lhs.x.copy_advice(|| "x_p", &mut region, config.x, 0)?;
+                lhs.y.copy_advice(|| "y_p", &mut region, config.y, 0)?;
+                rhs.x.copy_advice(|| "x_q", &mut region, config.x, 1)?;
+                rhs.y.copy_advice(|| "y_q", &mut region, config.y, 1)?;
+                region.assign_advice(|| "lambda", config.lambda, 0, || lambda)?;
+
+                let x = region.assign_advice(|| "x_r", config.x, 2, || sum.map(|sum| sum.0))?;
+                let y = region.assign_advice(|| "y_r", config.y, 2, || sum.map(|sum| sum.1))?;
+                Ok(EccPoint { x, y })
+            },
+        )
+    }
+
+    /// Double a point whose y-coordinate is not zero
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails.
+    pub fn double(
+        &self,
+        mut layouter: impl Layouter<C::Base>,
+        point: &EccPoint<C::Base>,
+    ) -> Result<EccPoint<C::Base>, Error> {
+        let config = &self.config;
+        let lambda = point.coordinates().map(|(x, y)| {
+            // A zero denominator leaves the slope gate unsatisfiable.
+            (x.square() * C::Base::from(3) + C::a()) * y.double().invert().unwrap_or(C::Base::ZERO)
+        });
+        let doubled = point.coordinates().zip(lambda).map(|((x, y), lambda)| {
+            let xr = lambda.square() - x.double();
+            (xr, lambda * (x - xr) - y)
+        });
+
+        layouter.assign_region(
+            || "point doubling",
+            |mut region| {
+                config.q_double.enable(&mut region, 0)?;
+                config.q_point.enable(&mut region, 1)?;
+
+                point.x.copy_advice(|| "x_p", &mut region, config.x, 0)?;
+                point.y.copy_advice(|| "y_p", &mut region, config.y, 0)?;
+                region.assign_advice(|| "lambda", config.lambda, 0, || lambda)?;
+
+                let x = region.assign_advice(|| "x_r", config.x, 1, || doubled.map(|r| r.0))?;
+                let y = region.assign_advice(|| "y_r", config.y, 1, || doubled.map(|r| r.1))?;
+                Ok(EccPoint { x, y })
+            },
+        )
+    }
+
+    /// `[2^bits + 2k + 1] point`, where `scalar` holds the `bits`-bit integer `k`
+    ///
+    /// `point` must not be the identity and must have prime order above
+    /// `2^(bits + 2)`. The ladder witnesses the bits of `scalar` most
+    /// significant first, constrains each to be boolean and recomposes them
+    /// into a running sum that is constrained equal to `scalar`, so
+    /// `scalar` must fit in `bits` bits for the circuit to be satisfied.
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails, including
+    /// when the circuit has no fixed column enabled for constants.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is zero.
+    pub fn mul_ladder(
+        &self,
+        mut layouter: impl Layouter<C::Base>,
+        point: &EccPoint<C::Base>,
+        scalar: &AssignedCell<C::Base, C::Base>,
+        bits: usize,
+    ) -> Result<EccPoint<C::Base>, Error> {
+        assert!(bits > 0, "the ladder needs at least one bit");
+        let le_bits = scalar.value().map(to_le_bits);
+        let mut acc = self.double(layouter.namespace(|| "[2] P"), point)?;
+        let mut k: Option<AssignedCell<C::Base, C::Base>> = None;
+        for i in (0..bits).rev() {
+            let bit = le_bits
+                .as_ref()
+                .map(|bits| C::Base::from(u64::from(bits.get(i).copied().unwrap_or(false))));
+            let (signed, next) = self.ladder_step(
+                layouter.namespace(|| crate::annotation!("bit {}", i)),
+                point,
+                bit,
+                k.as_ref(),
+            )?;
+            let sum = self.add(layouter.namespace(|| "acc ± P"), &acc, &signed)?;
+            acc = self.add(layouter.namespace(|| "2 acc ± P"), &sum, &acc)?;
+            k = Some(next);
+        }
+        if let Some(k) = k {
+            layouter.assign_region(
+                || "ladder scalar",
+                |mut region| region.constrain_equal(k.cell(), scalar.cell()),
+            )?;
+        }
+        Ok(acc)
+    }
+
+    /// Witness `bit`, `(2 bit - 1) point` and the next running sum
+    /// `2 k + bit`, with `k` starting at zero
+    fn ladder_step(
+        &self,
+        mut layouter: impl Layouter<C::Base>,
+        point: &EccPoint<C::Base>,
+        bit: Value<C::Base>,
+        k: Option<&AssignedCell<C::Base, C::Base>>,
+    ) -> Result<(EccPoint<C::Base>, AssignedCell<C::Base, C::Base>), Error> {
+        let config = &self.config;
+        let signed_y = point
+            .y
+            .value()
+            .zip(bit)
+            .map(|(y, bit)| *y * (bit.double() - C::Base::ONE));
+
+        layouter.assign_region(
+            || "ladder step",
+            |mut region| {
+                config.q_ladder.enable(&mut region, 0)?;
+
+                point.x.copy_advice(|| "x_p", &mut region, config.x, 0)?;
+                point.y.copy_advice(|| "y_p", &mut region, config.y, 0)?;
+                region.assign_advice(|| "bit", config.lambda, 0, || bit)?;
+
+                let x = point.x.copy_advice(|| "x_q", &mut region, config.x, 1)?;
+                let y = region.assign_advice(|| "y_q", config.y, 1, || signed_y)?;
+                let k_prev = match k {
+                    Some(k) => k.copy_advice(|| "k", &mut region, config.lambda, 1)?,
+                    None => region.assign_advice_from_constant(
+                        || "k",
+                        config.lambda,
+                        1,
+                        C::Base::ZERO,
+                    )?,
+                };
+                let k_next = region.assign_advice(
+                    || "k next",
+                    config.lambda,
+                    2,
+                    || {
+                        k_prev
+                            .value()
+                            .copied()
+                            .zip(bit)
+                            .map(|(k, bit)| k.double() + bit)
+                    },
+                )?;
+                Ok((EccPoint { x, y }, k_next))
+            },
+        )
+    }
+}
+
+impl<C: CurveAffine> Gadget<C::Base> for EccChip<C> {
+    type Config = EccConfig;
+    /// x, y and slope columns
+    type Columns = [Column<Advice>; 3];
+    type Input = (Value<C>, Value<C>);
+    type Output = EccPoint<C::Base>;
+
+    fn construct(config: EccConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn config(&self) -> &EccConfig {
+        &self.config
+    }
+
+    fn configure(
+        meta: &mut ConstraintSystem<C::Base>,
+        [x, y, lambda]: [Column<Advice>; 3],
+    ) -> EccConfig {
+        let curve_a = C::a();
+        let curve_b = C::b();
+        let q_point = meta.selector();
+        let q_add = meta.selector();
+        let q_double = meta.selector();
+        let q_ladder = meta.selector();
+        meta.enable_equality(x);
+        meta.enable_equality(y);
+        meta.enable_equality(lambda);
+
+        meta.create_gate("point on curve", |meta| {
+            let q_point = meta.query_selector(q_point);
+            let x = meta.query_advice(x, Rotation::cur());
+            let y = meta.query_advice(y, Rotation::cur());
+
+            // y^2 = x^3 + a x + b
+            let rhs = x.clone().square() * x.clone()
+                + Expression::Constant(curve_a) * x
+                + Expression::Constant(curve_b);
+            vec![q_point * (y.square() - rhs)]
+        });
+
+        meta.create_gate("incomplete addition", |meta| {
+            let q_add = meta.query_selector(q_add);
+            let xp = meta.query_advice(x, Rotation::cur());
+            let yp = meta.query_advice(y, Rotation::cur());
+            let xq = meta.query_advice(x, Rotation::next());
+            let yq = meta.query_advice(y, Rotation::next());
+            let xr = meta.query_advice(x, Rotation(2));
+            let yr = meta.query_advice(y, Rotation(2));
+            let lambda = meta.query_advice(lambda, Rotation::cur());
+
+            vec![
+                q_add.clone() * (lambda.clone() * (xq.clone() - xp.clone()) - (yq - yp.clone())),
+                q_add.clone() * (xr.clone() - (lambda.clone().square() - xp.clone() - xq)),
+                q_add * (yr - (lambda * (xp - xr) - yp)),
+            ]
+        });
+
+        meta.create_gate("point doubling", |meta| {
+            let q_double = meta.query_selector(q_double);
+            let xp = meta.query_advice(x, Rotation::cur());
+            let yp = meta.query_advice(y, Rotation::cur());
+            let xr = meta.query_advice(x, Rotation::next());
+            let yr = meta.query_advice(y, Rotation::next());
+            let lambda = meta.query_advice(lambda, Rotation::cur());
+
+            // λ * 2y = 3x^2 + a
+            let slope = Expression::Constant(C::Base::from(3)) * xp.clone().square()
+                + Expression::Constant(curve_a);
+            vec![
+                q_double.clone()
+                    * (lambda.clone() * yp.clone() * Expression::Constant(C::Base::from(2))
+                        - slope),
+                q_double.clone()
+                    * (xr.clone()
+                        - (lambda.clone().square()
+                            - xp.clone() * Expression::Constant(C::Base::from(2)))),
+                q_double * (yr - (lambda * (xp - xr) - yp)),
+            ]
+        });
+
+        meta.create_gate("ladder step", |meta| {
+            let q_ladder = meta.query_selector(q_ladder);
+            let xp = meta.query_advice(x, Rotation::cur());
+            let yp = meta.query_advice(y, Rotation::cur());
+            let xq = meta.query_advice(x, Rotation::next());
+            let yq = meta.query_advice(y, Rotation::next());
+            let bit = meta.query_advice(lambda, Rotation::cur());
+            let k_prev = meta.query_advice(lambda, Rotation::next());
+            let k_next = meta.query_advice(lambda, Rotation(2));
+            let one = Expression::Constant(C::Base::ONE);
+            let two = Expression::Constant(C::Base::from(2));
+
+            // Q = (2 bit - 1) P and k_next = 2 k_prev + bit
+            vec![
+                q_ladder.clone() * bit.clone() * (one.clone() - bit.clone()),
+                q_ladder.clone() * (xq - xp),
+                q_ladder.clone() * (yq - (two.clone() * bit.clone() - one) * yp),
+                q_ladder * (k_next - (two * k_prev + bit)),
+            ]
+        });
+
+        EccConfig {
+            x,
+            y,
+            lambda,
+            q_point,
+            q_add,
+            q_double,
+            q_ladder,
+        }
+    }
+
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<C::Base>,
+        (p, q): Self::Input,
+    ) -> Result<Self::Output, Error> {
+        let p = self.witness_point(layouter.namespace(|| "p"), p)?;
+        let q = self.witness_point(layouter.namespace(|| "q"), q)?;
+        self.add(layouter.namespace(|| "p + q"), &p, &q)
+    }
+}
+
+/// Affine coordinates, or `(0, 0)` for the identity
+pub(crate) fn affine<C: CurveAffine>(point: &C) -> (C::Base, C::Base) {
+    let coordinates: Option<Coordinates<C>> = point.coordinates().into();
+    coordinates.map_or((C::Base::ZERO, C::Base::ZERO), |c| (*c.x(), *c.y()))
+}
+
+#[cfg(test)]
+mod tests {
+    use group::{prime::PrimeCurveAffine, Curve, Group};
+    use halo2_proofs::{circuit::SimpleFloorPlanner, dev::MockProver, plonk::Circuit};
+    use pasta_curves::{pallas, Fp};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct AddCircuit {
+        p: Value<pallas::Affine>,
+        q: Value<pallas::Affine>,
+        expected: Option<pallas::Affine>,
+    }
+
+    impl Circuit<Fp> for AddCircuit {
+        type Config = EccConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> EccConfig {
+            let columns = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            EccChip::<pallas::Affine>::configure(meta, columns)
+        }
+
+        fn synthesize(
+            &self,
+            config: EccConfig,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = EccChip::<pallas::Affine>::construct(config);
+            let r = chip.assign(layouter.namespace(|| "add"), (self.p, self.q))?;
+
+            if let Some(expected) = self.expected {
+                r.coordinates()
+                    .assert_if_known(|coordinates| *coordinates == affine(&expected));
+            }
+            Ok(())
+        }
+    }
+
+    /// `[2^8 + 2k + 1] P` through the ladder
+    struct LadderCircuit {
+        p: pallas::Affine,
+        k: u64,
+        expected: Option<pallas::Affine>,
+    }
+
+    impl Circuit<Fp> for LadderCircuit {
+        type Config = EccConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { ..*self }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> EccConfig {
+            let columns = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+            EccChip::<pallas::Affine>::configure(meta, columns)
+        }
+
+        fn synthesize(
+            &self,
+            config: EccConfig,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = EccChip::<pallas::Affine>::construct(config.clone());
+            let p = chip.witness_point(layouter.namespace(|| "p"), Value::known(self.p))?;
+            let k = layouter.assign_region(
+                || "k",
+                |mut region| {
+                    region.assign_advice(
+                        || "k",
+                        config.lambda,
+                        0,
+                        || Value::known(Fp::from(self.k)),
+                    )
+                },
+            )?;
+            let r = chip.mul_ladder(layouter.namespace(|| "ladder"), &p, &k, 8)?;
+            if let Some(expected) = self.expected {
+                r.coordinates()
+                    .assert_if_known(|coordinates| *coordinates == affine(&expected));
+            }
+            Ok(())
+        }
+    }
+
+    fn point(scalar: u64) -> pallas::Affine {
+        (pallas::Point::generator() * pallas::Scalar::from(scalar)).to_affine()
+    }
+
+    #[test]
+    fn test_addition() {
+        let circuit = AddCircuit {
+            p: Value::known(point(3)),
+            q: Value::known(point(5)),
+            expected: Some(point(8)),
+        };
+        MockProver::run(4, &circuit, vec![])
+            .unwrap()
+            .assert_satisfied();
+    }
+
+    #[test]
+    fn test_doubling_rejected() {
+        let circuit = AddCircuit {
+            p: Value::known(point(3)),
+            q: Value::known(point(3)),
+            expected: None,
+        };
+        assert!(MockProver::run(4, &circuit, vec![])
+            .unwrap()
+            .verify()
+            .is_err());
+    }
+
+    #[test]
+    fn test_identity_rejected() {
+        let circuit = AddCircuit {
+            p: Value::known(pallas::Affine::identity()),
+            q: Value::known(point(5)),
+            expected: None,
+        };
+        assert!(MockProver::run(4, &circuit, vec![])
+            .unwrap()
+            .verify()
+            .is_err());
+    }
+
+    #[test]
+    fn test_ladder() {
+        let p = point(7);
+        let k = 0b1011_0010;
+        let circuit = LadderCircuit {
+            p,
+            k,
+            expected: Some(point(7 * ((1 << 8) + 2 * k + 1))),
+        };
+        MockProver::run(7, &circuit, vec![])
+            .unwrap()
+            .assert_satisfied();
+    }
+
+    #[test]
+    fn test_ladder_rejects_wide_scalar() {
+        // 2^8 has no 8-bit decomposition, whatever point is claimed
+        let circuit = LadderCircuit {
+            p: point(7),
+            k: 1 << 8,
+            expected: None,
+        };
+        assert!(MockProver::run(7, &circuit, vec![])
+            .unwrap()
+            .verify()
+            .is_err());
+    }
+}
@@ -0,0 +1,701 @@
+//! Arithmetic modulo a foreign prime
+//!
+//! [`ForeignFieldChip`] multiplies and adds elements of a field other than
+//! the circuit's, such as the BN254 or secp256k1 base fields, so ECDSA and
+//! Ethereum-compatible hashing can run inside a Pasta circuit. An element is
+//! [`LIMBS`] limbs of [`LIMB_BITS`] bits, each checked against an 8-bit
+//! lookup table.
+//!
+//! `a b + c = q p + r` is checked twice: limb by limb with carries modulo
+//! `2^320`, and on the limbs' recomposition modulo the native field. Both
+//! sides stay below `2^521`, less than the product of the two moduli, so
+//! the identity holds over the integers and `r` is `a b + c` modulo `p`.
+//! Results are below `2^256` but, like witnessed elements, not necessarily
+//! below `p`.
+
+use std::{
+    marker::PhantomData,
+    ops::{Add, Mul, Sub},
+};
+
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+use super::{DecomposeChip, DecomposeConfig, Gadget};
+
+/// Bits per limb
+pub const LIMB_BITS: usize = 64;
+
+/// Limbs per element
+pub const LIMBS: usize = 4;
+
+/// Chip checking a limb in eight 8-bit pieces
+type LimbChip<F> = DecomposeChip<F, 8, 8>;
+
+/// Chip checking a carry, at most 68 bits, to 72 bits
+type CarryChip<F> = DecomposeChip<F, 8, 9>;
+
+/// Chip checking the quotient's top limb, so `q < 2^264`
+type TopChip<F> = DecomposeChip<F, 8, 1>;
+
+/// Prime modulus of a foreign field
+pub trait ForeignModulus: Clone + std::fmt::Debug {
+    /// The modulus as little-endian limbs; its top limb must be at least
+    /// `2^56`, so quotients fit in [`LIMBS`] limbs and 8 more bits
+    const MODULUS: [u64; LIMBS];
+}
+
+/// Base field of BN254, the curve of Ethereum's pairing precompiles
+#[derive(Clone, Copy, Debug)]
+pub struct Bn254Base;
+
+impl ForeignModulus for Bn254Base {
+    const MODULUS: [u64; LIMBS] = [
+        0x3c20_8c16_d87c_fd47,
+        0x9781_6a91_6871_ca8d,
+        0xb850_45b6_8181_585d,
+        0x3064_4e72_e131_a029,
+    ];
+}
+
+/// Base field of secp256k1, the curve of Bitcoin and Ethereum signatures
+#[derive(Clone, Copy, Debug)]
+pub struct Secp256k1Base;
+
+impl ForeignModulus for Secp256k1Base {
+    const MODULUS: [u64; LIMBS] = [
+        0xffff_fffe_ffff_fc2f,
+        0xffff_ffff_ffff_ffff,
+        0xffff_ffff_ffff_ffff,
+        0xffff_ffff_ffff_ffff,
+    ];
+}
+
+/// `a b + c` reduced modulo `M`, as [`ForeignFieldChip::mul_add`] computes
+#[must_use]
+pub fn mul_add<M: ForeignModulus>(
+    a: &[u64; LIMBS],
+    b: &[u64; LIMBS],
+    c: &[u64; LIMBS],
+) -> [u64; LIMBS] {
+    divide::<M>(&wide_mul_add(a, b, c)).1
+}
+
+/// `a b + c` as 512 bits
+fn wide_mul_add(a: &[u64; LIMBS], b: &[u64; LIMBS], c: &[u64; LIMBS]) -> [u64; 2 * LIMBS] {
+    let mut wide = [0; 2 * LIMBS];
+    wide[..LIMBS].copy_from_slice(c);
+    for (i, a) in a.iter().enumerate() {
+        let mut carry = 0;
+        for (j, b) in b.iter().enumerate() {
+            // At most (2^64 - 1)^2 + 2 (2^64 - 1) = 2^128 - 1
+            let t = u128::from(*a) * u128::from(*b) + u128::from(wide[i + j]) + carry;
+            #[allow(clippy::cast_possible_truncation)]
+            let low = t as u64;
+            wide[i + j] = low;
+            carry = t >> 64;
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        let high = carry as u64;
+        wide[i + LIMBS] = high;
+    }
+    wide
+}
+
+/// Quotient and remainder of `wide` by `M`'s modulus
+fn divide<M: ForeignModulus>(wide: &[u64; 2 * LIMBS]) -> ([u64; LIMBS + 1], [u64; LIMBS]) {
+    let modulus = extend(&M::MODULUS);
+    let mut quotient = [0; 2 * LIMBS];
+    // Below 2p, so it fits one limb more than the modulus
+    let mut remainder = [0; LIMBS + 1];
+    for bit in (0..2 * LIMBS * LIMB_BITS).rev() {
+        let mut carry = (wide[bit / LIMB_BITS] >> (bit % LIMB_BITS)) & 1;
+        for limb in &mut remainder {
+            let top = *limb >> (LIMB_BITS - 1);
+            *limb = (*limb << 1) | carry;
+            carry = top;
+        }
+        if remainder.iter().rev().ge(modulus.iter().rev()) {
+            let mut borrow = false;
+            for (limb, m) in remainder.iter_mut().zip(modulus) {
+                let (diff, under) = limb.overflowing_sub(m);
+                let (diff, under_borrow) = diff.overflowing_sub(u64::from(borrow));
+                *limb = diff;
+                borrow = under || under_borrow;
+            }
+            quotient[bit / LIMB_BITS] |= 1 << (bit % LIMB_BITS);
+        }
+    }
+    let mut q = [0; LIMBS + 1];
+    q.copy_from_slice(&quotient[..=LIMBS]);
+    let mut r = [0; LIMBS];
+    r.copy_from_slice(&remainder[..LIMBS]);
+    (q, r)
+}
+
+/// `limbs` with a zero limb on top
+fn extend(limbs: &[u64; LIMBS]) -> [u64; LIMBS + 1] {
+    let mut extended = [0; LIMBS + 1];
+    extended[..LIMBS].copy_from_slice(limbs);
+    extended
+}
+
+/// `2^320 - p` as little-endian limbs
+fn negated_modulus<M: ForeignModulus>() -> [u64; LIMBS + 1] {
+    let mut carry = true;
+    extend(&M::MODULUS).map(|limb| {
+        let (sum, over) = (!limb).overflowing_add(u64::from(carry));
+        carry = over;
+        sum
+    })
+}
+
+/// Limb `k` of `a b + c + q (2^320 - p) - r`, before carries
+///
+/// `c` and `r` have no limb [`LIMBS`].
+fn limb_sum<T>(k: usize, [a, b, c, q, r, negated]: [&[T]; 6]) -> T
+where
+    T: Clone + Add<Output = T> + Sub<Output = T> + Mul<Output = T>,
+{
+    let mut sum = q[k].clone() * negated[0].clone();
+    for i in 0..k {
+        sum = sum + q[i].clone() * negated[k - i].clone();
+    }
+    for i in k.saturating_sub(LIMBS - 1)..=k.min(LIMBS - 1) {
+        sum = sum + a[i].clone() * b[k - i].clone();
+    }
+    if k < LIMBS {
+        sum = sum + c[k].clone() - r[k].clone();
+    }
+    sum
+}
+
+/// Recompose little-endian `limbs` with limb weight `shift`
+fn compose<T>(limbs: &[T], shift: &T) -> T
+where
+    T: Clone + Add<Output = T> + Mul<Output = T>,
+{
+    let (top, rest) = limbs.split_last().expect("elements have limbs");
+    rest.iter()
+        .rev()
+        .fold(top.clone(), |acc, limb| acc * shift.clone() + limb.clone())
+}
+
+/// `2^LIMB_BITS` in `F`
+fn limb_shift<F: PrimeField>() -> F {
+    F::from_u128(1 << LIMB_BITS)
+}
+
+/// Assigned foreign field element
+#[derive(Clone, Debug)]
+pub struct ForeignElement<F: PrimeField> {
+    /// Little-endian limbs of [`LIMB_BITS`] bits
+    pub limbs: [AssignedCell<F, F>; LIMBS],
+    value: Value<[u64; LIMBS]>,
+}
+
+impl<F: PrimeField> ForeignElement<F> {
+    /// Little-endian limbs of the element
+    #[must_use]
+    pub fn value(&self) -> Value<[u64; LIMBS]> {
+        self.value
+    }
+}
+
+/// Adds and multiplies elements of the field modulo `M` over `F`
+#[derive(Clone, Debug)]
+pub struct ForeignFieldChip<F: PrimeField, M: ForeignModulus> {
+    config: ForeignFieldConfig,
+    _marker: PhantomData<(F, M)>,
+}
+
+/// Columns, selector and range checks used by [`ForeignFieldChip`]
+#[derive(Clone, Debug)]
+pub struct ForeignFieldConfig {
+    /// Limb columns; the first also holds the range checks' running sums
+    pub advice: [Column<Advice>; LIMBS + 1],
+    q_mul_add: Selector,
+    limb: DecomposeConfig,
+    carry: DecomposeConfig,
+    top: DecomposeConfig,
+}
+
+impl<F: PrimeField, M: ForeignModulus> ForeignFieldChip<F, M> {
+    /// Witness an element and range-check its limbs
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails.
+    pub fn witness(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<[u64; LIMBS]>,
+    ) -> Result<ForeignElement<F>, Error> {
+        let limbs = layouter.assign_region(
+            || "witness foreign element",
+            |mut region| {
+                let mut limbs = Vec::with_capacity(LIMBS);
+                for (i, column) in self.config.advice[..LIMBS].iter().enumerate() {
+                    let limb = value.map(|value| F::from(value[i]));
+                    limbs.push(region.assign_advice(
+                        || crate::annotation!("limb {}", i),
+                        *column,
+                        0,
+                        || limb,
+                    )?);
+                }
+                Ok(limbs)
+            },
+        )?;
+        self.check_limbs(&mut layouter, &limbs)?;
+        Ok(ForeignElement {
+            limbs: into_limbs(limbs),
+            value,
+        })
+    }
+
+    /// Assign `value`, which the circuit fixes, from the constants column
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails, including
+    /// when the circuit has no fixed column enabled for constants.
+    pub fn constant(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: [u64; LIMBS],
+    ) -> Result<ForeignElement<F>, Error> {
+        let limbs = layouter.assign_region(
+            || "constant foreign element",
+            |mut region| {
+                self.config.advice[..LIMBS]
+                    .iter()
+                    .zip(value)
+                    .enumerate()
+                    .map(|(i, (column, limb))| {
+                        region.assign_advice_from_constant(
+                            || crate::annotation!("limb {}", i),
+                            *column,
+                            0,
+                            F::from(limb),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, Error>>()
+            },
+        )?;
+        Ok(ForeignElement {
+            limbs: into_limbs(limbs),
+            value: Value::known(value),
+        })
+    }
+
+    /// Constrain `a b + c` modulo `M` and return it
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails.
+    pub fn mul_add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &ForeignElement<F>,
+        b: &ForeignElement<F>,
+        c: &ForeignElement<F>,
+    ) -> Result<ForeignElement<F>, Error> {
+        let config = &self.config;
+        let division = a
+            .value
+            .zip(b.value)
+            .zip(c.value)
+            .map(|((a, b), c)| divide::<M>(&wide_mul_add(&a, &b, &c)));
+        let quotient = division.map(|(q, _)| q);
+        let remainder = division.map(|(_, r)| r);
+        let carries = a
+            .value
+            .zip(b.value)
+            .zip(c.value)
+            .zip(division)
+            .map(|(((a, b), c), (q, r))| carries::<F, M>(&a, &b, &c, &q, &r));
+
+        let (r, q, carries) = layouter.assign_region(
+            || "foreign mul add",
+            |mut region| {
+                config.q_mul_add.enable(&mut region, 0)?;
+                for (row, element) in [a, b, c].into_iter().enumerate() {
+                    for (i, limb) in element.limbs.iter().enumerate() {
+                        limb.copy_advice(
+                            || crate::annotation!("limb {}", i),
+                            &mut region,
+                            config.advice[i],
+                            row,
+                        )?;
+                    }
+                }
+                let mut assign_row =
+                    |name: &'static str, row: usize, len: usize, values: Value<Vec<F>>| {
+                        (0..len)
+                            .map(|i| {
+                                region.assign_advice(
+                                    || crate::annotation!("{} {}", name, i),
+                                    config.advice[i],
+                                    row,
+                                    || values.as_ref().map(|values| values[i]),
+                                )
+                            })
+                            .collect::<Result<Vec<_>, Error>>()
+                    };
+                let to_field =
+                    |limbs: &[u64]| -> Vec<F> { limbs.iter().map(|limb| F::from(*limb)).collect() };
+                let r = assign_row("r", 3, LIMBS, remainder.map(|r| to_field(&r)))?;
+                let q = assign_row("q", 4, LIMBS + 1, quotient.map(|q| to_field(&q)))?;
+                let carries = assign_row("carry", 5, LIMBS + 1, carries.map(Vec::from))?;
+                Ok((r, q, carries))
+            },
+        )?;
+
+        self.check_limbs(&mut layouter, &r)?;
+        self.check_limbs(&mut layouter, &q[..LIMBS])?;
+        TopChip::construct(config.top.clone())
+            .decompose(layouter.namespace(|| "quotient top"), &q[LIMBS])?;
+        let carry_chip = CarryChip::construct(config.carry.clone());
+        for (i, carry) in carries.iter().enumerate() {
+            carry_chip.decompose(
+                layouter.namespace(|| crate::annotation!("carry {}", i)),
+                carry,
+            )?;
+        }
+        Ok(ForeignElement {
+            limbs: into_limbs(r),
+            value: remainder,
+        })
+    }
+
+    /// Constrain `a + b` modulo `M` and return it
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails, including
+    /// when the circuit has no fixed column enabled for constants.
+    pub fn add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &ForeignElement<F>,
+        b: &ForeignElement<F>,
+    ) -> Result<ForeignElement<F>, Error> {
+        let one = self.constant(layouter.namespace(|| "one"), [1, 0, 0, 0])?;
+        self.mul_add(layouter.namespace(|| "a + b"), a, &one, b)
+    }
+
+    /// Constrain `a b` modulo `M` and return it
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails, including
+    /// when the circuit has no fixed column enabled for constants.
+    pub fn mul(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &ForeignElement<F>,
+        b: &ForeignElement<F>,
+    ) -> Result<ForeignElement<F>, Error> {
+        let zero = self.constant(layouter.namespace(|| "zero"), [0; LIMBS])?;
+        self.mul_add(layouter.namespace(|| "a b"), a, b, &zero)
+    }
+
+    /// Constrain `a` and `b` to have the same limbs
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails.
+    pub fn constrain_equal(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &ForeignElement<F>,
+        b: &ForeignElement<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "foreign equality",
+            |mut region| {
+                for (a, b) in a.limbs.iter().zip(&b.limbs) {
+                    region.constrain_equal(a.cell(), b.cell())?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Range-check `limbs` to [`LIMB_BITS`] bits each
+    fn check_limbs(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        limbs: &[AssignedCell<F, F>],
+    ) -> Result<(), Error> {
+        let chip = LimbChip::construct(self.config.limb.clone());
+        for (i, limb) in limbs.iter().enumerate() {
+            chip.decompose(
+                layouter.namespace(|| crate::annotation!("limb {} range", i)),
+                limb,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Carries of `a b + c + q (2^320 - p) - r`, one per limb of `q`
+fn carries<F: PrimeField, M: ForeignModulus>(
+    a: &[u64; LIMBS],
+    b: &[u64; LIMBS],
+    c: &[u64; LIMBS],
+    q: &[u64; LIMBS + 1],
+    r: &[u64; LIMBS],
+) -> [F; LIMBS + 1] {
+    let field = |limbs: &[u64]| limbs.iter().map(|limb| F::from(*limb)).collect::<Vec<F>>();
+    let (a, b, c, q, r) = (field(a), field(b), field(c), field(q), field(r));
+    let negated = field(&negated_modulus::<M>());
+    let shift_inv = limb_shift::<F>().invert().unwrap();
+    let mut carries = [F::ZERO; LIMBS + 1];
+    let mut carry = F::ZERO;
+    for (k, out) in carries.iter_mut().enumerate() {
+        // The sum is a multiple of 2^64 over the integers
+        carry = (limb_sum(k, [&a[..], &b[..], &c[..], &q[..], &r[..], &negated[..]]) + carry)
+            * shift_inv;
+        *out = carry;
+    }
+    carries
+}
+
+/// Limbs assigned one per column
+fn into_limbs<F: PrimeField>(limbs: Vec<AssignedCell<F, F>>) -> [AssignedCell<F, F>; LIMBS] {
+    limbs
+        .try_into()
+        .unwrap_or_else(|_| unreachable!("an element has {LIMBS} limbs"))
+}
+
+impl<F: PrimeField, M: ForeignModulus> Gadget<F> for ForeignFieldChip<F, M> {
+    type Config = ForeignFieldConfig;
+    /// Limb columns, one more than [`LIMBS`] for quotients and carries
+    type Columns = [Column<Advice>; LIMBS + 1];
+    type Input = (Value<[u64; LIMBS]>, Value<[u64; LIMBS]>);
+    /// Product of the two inputs
+    type Output = ForeignElement<F>;
+
+    fn construct(config: ForeignFieldConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn config(&self) -> &ForeignFieldConfig {
+        &self.config
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the top limb of `M::MODULUS` is below `2^56`.
+    fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; LIMBS + 1],
+    ) -> ForeignFieldConfig {
+        assert!(
+            M::MODULUS[LIMBS - 1] >> 56 != 0,
+            "foreign moduli must have at least 249 bits"
+        );
+        for column in advice {
+            meta.enable_equality(column);
+        }
+        let q_mul_add = meta.selector();
+        let limb = LimbChip::configure(meta, advice[0]);
+        let carry = CarryChip::configure(meta, advice[0]);
+        let top = TopChip::configure(meta, advice[0]);
+
+        meta.create_gate("foreign mul add", |meta| {
+            let q_mul_add = meta.query_selector(q_mul_add);
+            let mut row = |row: i32, len: usize| -> Vec<Expression<F>> {
+                advice[..len]
+                    .iter()
+                    .map(|column| meta.query_advice(*column, Rotation(row)))
+                    .collect()
+            };
+            let a = row(0, LIMBS);
+            let b = row(1, LIMBS);
+            let c = row(2, LIMBS);
+            let r = row(3, LIMBS);
+            let q = row(4, LIMBS + 1);
+            let carries = row(5, LIMBS + 1);
+            let constant = |limbs: &[u64]| -> Vec<Expression<F>> {
+                limbs
+                    .iter()
+                    .map(|limb| Expression::Constant(F::from(*limb)))
+                    .collect()
+            };
+            let negated = constant(&negated_modulus::<M>());
+            let modulus = constant(&M::MODULUS);
+            let shift = Expression::Constant(limb_shift::<F>());
+
+            // Limb k of a b + c + q (2^320 - p) - r, with the carry in from
+            // limb k - 1, is 2^64 times the carry out
+            let mut constraints = Vec::with_capacity(LIMBS + 2);
+            for k in 0..=LIMBS {
+                let mut sum = limb_sum(k, [&a[..], &b[..], &c[..], &q[..], &r[..], &negated[..]]);
+                if k > 0 {
+                    sum = sum + carries[k - 1].clone();
+                }
+                constraints.push(q_mul_add.clone() * (sum - shift.clone() * carries[k].clone()));
+            }
+            // a b + c = q p + r in the native field
+            let native = |limbs: &[Expression<F>]| compose(limbs, &shift);
+            constraints.push(
+                q_mul_add
+                    * (native(&a) * native(&b) + native(&c)
+                        - native(&q) * native(&modulus)
+                        - native(&r)),
+            );
+            constraints
+        });
+
+        ForeignFieldConfig {
+            advice,
+            q_mul_add,
+            limb,
+            carry,
+            top,
+        }
+    }
+
+    fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        LimbChip::construct(self.config.limb.clone()).load(layouter)?;
+        CarryChip::construct(self.config.carry.clone()).load(layouter)?;
+        TopChip::construct(self.config.top.clone()).load(layouter)
+    }
+
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        (a, b): Self::Input,
+    ) -> Result<Self::Output, Error> {
+        let a = self.witness(layouter.namespace(|| "a"), a)?;
+        let b = self.witness(layouter.namespace(|| "b"), b)?;
+        self.mul(layouter.namespace(|| "a b"), &a, &b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        plonk::{Circuit, Instance},
+    };
+    use pasta_curves::Fp;
+
+    use super::*;
+
+    /// `p - 1` for `M`
+    fn minus_one<M: ForeignModulus>() -> [u64; LIMBS] {
+        let mut limbs = M::MODULUS;
+        limbs[0] -= 1;
+        limbs
+    }
+
+    /// `(p - 1)^2 = 1` and `(p - 1) + 1 = 0`
+    fn check_wraparound<M: ForeignModulus>() {
+        let one = [1, 0, 0, 0];
+        let zero = [0; LIMBS];
+        let minus_one = minus_one::<M>();
+        assert_eq!(mul_add::<M>(&minus_one, &minus_one, &zero), one);
+        assert_eq!(mul_add::<M>(&minus_one, &one, &one), zero);
+    }
+
+    #[test]
+    fn test_native_mul_add() {
+        check_wraparound::<Secp256k1Base>();
+        check_wraparound::<Bn254Base>();
+
+        // Small values are not reduced
+        let a = [u64::MAX, 0, 0, 0];
+        let product = mul_add::<Bn254Base>(&a, &a, &a);
+        #[allow(clippy::cast_possible_truncation)]
+        let expected = {
+            let wide = u128::from(u64::MAX) * u128::from(u64::MAX) + u128::from(u64::MAX);
+            [wide as u64, (wide >> 64) as u64, 0, 0]
+        };
+        assert_eq!(product, expected);
+    }
+
+    #[test]
+    fn test_negated_modulus() {
+        let negated = negated_modulus::<Secp256k1Base>();
+        assert_eq!(negated, [0x1_0000_03d1, 0, 0, 0, u64::MAX]);
+    }
+
+    /// Multiplies and adds two secp256k1 elements, exposing the limbs of
+    /// `a b + a`
+    #[derive(Default)]
+    struct MulAddCircuit {
+        a: Value<[u64; LIMBS]>,
+        b: Value<[u64; LIMBS]>,
+    }
+
+    impl Circuit<Fp> for MulAddCircuit {
+        type Config = (ForeignFieldConfig, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = [(); LIMBS + 1].map(|()| meta.advice_column());
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (
+                ForeignFieldChip::<Fp, Secp256k1Base>::configure(meta, advice),
+                instance,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = ForeignFieldChip::<Fp, Secp256k1Base>::construct(config);
+            chip.load(&mut layouter)?;
+            let a = chip.witness(layouter.namespace(|| "a"), self.a)?;
+            let product = chip.assign(layouter.namespace(|| "a b"), (self.a, self.b))?;
+            let sum = chip.add(layouter.namespace(|| "a b + a"), &product, &a)?;
+            for (row, limb) in sum.limbs.iter().enumerate() {
+                layouter.constrain_instance(limb.cell(), instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_circuit_mul_add() {
+        let a = minus_one::<Secp256k1Base>();
+        let b = [3, 0, 0, 1 << 63];
+        let product = mul_add::<Secp256k1Base>(&a, &b, &[0; LIMBS]);
+        let expected = mul_add::<Secp256k1Base>(&product, &[1, 0, 0, 0], &a);
+        let circuit = MulAddCircuit {
+            a: Value::known(a),
+            b: Value::known(b),
+        };
+        let instance = |limbs: [u64; LIMBS]| vec![limbs.map(Fp::from).to_vec()];
+
+        let prover = MockProver::run(10, &circuit, instance(expected)).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // The result is pinned by the constraints, not just the witness
+        let mut wrong = expected;
+        wrong[0] ^= 1;
+        let prover = MockProver::run(10, &circuit, instance(wrong)).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,346 @@
+//! Merkle path openings over the two-to-one Poseidon hash
+//!
+//! [`MerkleChip`] hashes a leaf up a path the way
+//! [`crate::merkle::MerklePath::root`] does. Each level orders the node and
+//! its sibling by a boolean direction cell in a swap gate, and the Poseidon
+//! output cell is copied into the next level, so the root is wired to the
+//! leaf through copy constraints alone.
+
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Instance, Selector},
+    poly::Rotation,
+};
+
+use super::{BitsChip, BitsConfig, Gadget, PoseidonChip, PoseidonConfig};
+use crate::merkle::MerklePath;
+
+/// Siblings and directions of a path, for witnessing in-circuit
+#[derive(Clone, Debug)]
+pub struct MerklePathWitness<F: PrimeField> {
+    /// Sibling at each level, starting next to the leaf
+    pub siblings: Vec<Value<F>>,
+    /// Direction of each level, 1 if the node is a right child
+    pub directions: Vec<Value<F>>,
+}
+
+impl<F: PrimeField> MerklePathWitness<F> {
+    /// Path of `depth` unknown levels, for keygen
+    #[must_use]
+    pub fn unknown(depth: usize) -> Self {
+        Self {
+            siblings: vec![Value::unknown(); depth],
+            directions: vec![Value::unknown(); depth],
+        }
+    }
+
+    /// Number of levels
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        self.siblings.len()
+    }
+}
+
+impl<F: PrimeField> From<&MerklePath<F>> for MerklePathWitness<F> {
+    fn from(path: &MerklePath<F>) -> Self {
+        Self {
+            siblings: path.siblings.iter().copied().map(Value::known).collect(),
+            directions: path.directions().into_iter().map(Value::known).collect(),
+        }
+    }
+}
+
+/// Opens Merkle paths with [`PoseidonChip`]
+#[derive(Clone, Debug)]
+pub struct MerkleChip<F: PrimeField> {
+    config: MerkleConfig,
+    _marker: PhantomData<F>,
+}
+
+/// Columns and selectors used by [`MerkleChip`]
+#[derive(Clone, Debug)]
+pub struct MerkleConfig {
+    /// Hasher for each level
+    pub poseidon: PoseidonConfig,
+    /// Direction bits, on the first two Poseidon state columns
+    pub bits: BitsConfig,
+    swap: [Column<Advice>; 3],
+    q_swap: Selector,
+}
+
+impl<F: PrimeField> MerkleChip<F> {
+    /// Hash `leaf` up `siblings`, turning at each level by the matching cell
+    /// of `directions`
+    ///
+    /// The direction cells are constrained to 0 or 1 here. Returns the root
+    /// cell, which is the last Poseidon output.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Synthesis`] if `siblings` and `directions` differ in
+    /// length, or the layouter's error if region assignment fails.
+    pub fn compute_root(
+        &self,
+        mut layouter: impl Layouter<F>,
+        leaf: &AssignedCell<F, F>,
+        siblings: &[Value<F>],
+        directions: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        if siblings.len() != directions.len() {
+            return Err(Error::Synthesis);
+        }
+        let config = &self.config;
+        let poseidon = PoseidonChip::construct(config.poseidon.clone());
+        let [node_column, sibling_column, direction_column] = config.swap;
+
+        let mut node = leaf.clone();
+        for (level, (sibling, direction)) in siblings.iter().zip(directions).enumerate() {
+            let pair = layouter.assign_region(
+                || crate::annotation!("merkle level {}", level),
+                |mut region| {
+                    config.q_swap.enable(&mut region, 0)?;
+                    let node = node.copy_advice(|| "node", &mut region, node_column, 0)?;
+                    let sibling =
+                        region.assign_advice(|| "sibling", sibling_column, 0, || *sibling)?;
+                    let direction =
+                        direction.copy_advice(|| "direction", &mut region, direction_column, 0)?;
+
+                    let node = node.value().copied();
+                    let sibling = sibling.value().copied();
+                    let direction = direction.value().copied();
+                    let left = region.assign_advice(
+                        || "left",
+                        node_column,
+                        1,
+                        || node + direction * (sibling - node),
+                    )?;
+                    let right = region.assign_advice(
+                        || "right",
+                        sibling_column,
+                        1,
+                        || sibling + direction * (node - sibling),
+                    )?;
+                    Ok([left, right])
+                },
+            )?;
+            node = poseidon.hash(
+                layouter.namespace(|| crate::annotation!("hash level {}", level)),
+                &pair,
+            )?;
+        }
+        Ok(node)
+    }
+
+    /// Open `path` from `leaf` and constrain the root to row `row` of
+    /// `instance`
+    ///
+    /// The directions are witnessed as bits. Returns the computed root cell.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Synthesis`] if the path's siblings and directions
+    /// differ in length, or the layouter's error if assignment fails.
+    pub fn verify_path(
+        &self,
+        mut layouter: impl Layouter<F>,
+        leaf: &AssignedCell<F, F>,
+        path: &MerklePathWitness<F>,
+        instance: Column<Instance>,
+        row: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let directions = self.directions(layouter.namespace(|| "directions"), path)?;
+        let root = self.compute_root(
+            layouter.namespace(|| "root"),
+            leaf,
+            &path.siblings,
+            &directions,
+        )?;
+        layouter.constrain_instance(root.cell(), instance, row)?;
+        Ok(root)
+    }
+
+    /// Witness the directions of `path` as bits
+    fn directions(
+        &self,
+        layouter: impl Layouter<F>,
+        path: &MerklePathWitness<F>,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        if path.directions.is_empty() {
+            return Ok(Vec::new());
+        }
+        let bits = BitsChip::construct(self.config.bits.clone());
+        Ok(bits.assign(layouter, path.directions.clone())?.bits)
+    }
+}
+
+impl<F: PrimeField> Gadget<F> for MerkleChip<F> {
+    type Config = MerkleConfig;
+    /// Columns of the Poseidon hasher
+    type Columns = <PoseidonChip<F> as Gadget<F>>::Columns;
+    /// Leaf and path
+    type Input = (AssignedCell<F, F>, MerklePathWitness<F>);
+    /// Computed root
+    type Output = AssignedCell<F, F>;
+
+    fn construct(config: MerkleConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn config(&self) -> &MerkleConfig {
+        &self.config
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>, columns: Self::Columns) -> MerkleConfig {
+        let state = columns.0;
+        let poseidon = PoseidonChip::configure(meta, columns);
+        let bits = BitsChip::configure(meta, [state[0], state[1]]);
+        let q_swap = meta.selector();
+
+        meta.create_gate("merkle swap", |meta| {
+            let q_swap = meta.query_selector(q_swap);
+            let node = meta.query_advice(state[0], Rotation::cur());
+            let sibling = meta.query_advice(state[1], Rotation::cur());
+            let direction = meta.query_advice(state[2], Rotation::cur());
+            let left = meta.query_advice(state[0], Rotation::next());
+            let right = meta.query_advice(state[1], Rotation::next());
+            let one = Expression::Constant(F::ONE);
+
+            // left is the sibling if the node is a right child, and right
+            // is the other child
+            vec![
+                q_swap.clone() * direction.clone() * (one - direction.clone()),
+                q_swap.clone()
+                    * (left.clone() - node.clone() - direction * (sibling.clone() - node.clone())),
+                q_swap * (right - (node + sibling - left)),
+            ]
+        });
+
+        MerkleConfig {
+            poseidon,
+            bits,
+            swap: state,
+            q_swap,
+        }
+    }
+
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        (leaf, path): Self::Input,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let directions = self.directions(layouter.namespace(|| "directions"), &path)?;
+        self.compute_root(layouter, &leaf, &path.siblings, &directions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use halo2_proofs::{circuit::SimpleFloorPlanner, dev::MockProver, plonk::Circuit};
+    use pasta_curves::Fp;
+
+    use super::*;
+    use crate::merkle::MerkleTree;
+
+    /// Opens `path` from `leaf` against the public root
+    struct PathCircuit {
+        leaf: Value<Fp>,
+        path: MerklePathWitness<Fp>,
+    }
+
+    impl Circuit<Fp> for PathCircuit {
+        type Config = (MerkleConfig, Column<Advice>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                leaf: Value::unknown(),
+                path: MerklePathWitness::unknown(self.path.depth()),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let state = [(); 3].map(|()| meta.advice_column());
+            let partial_sbox = meta.advice_column();
+            let rc_a = [(); 3].map(|()| meta.fixed_column());
+            let rc_b = [(); 3].map(|()| meta.fixed_column());
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            let merkle = MerkleChip::configure(meta, (state, partial_sbox, rc_a, rc_b));
+            (merkle, input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (merkle, input, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let leaf = layouter.assign_region(
+                || "leaf",
+                |mut region| region.assign_advice(|| "leaf", input, 0, || self.leaf),
+            )?;
+            MerkleChip::construct(merkle).verify_path(
+                layouter.namespace(|| "path"),
+                &leaf,
+                &self.path,
+                instance,
+                0,
+            )?;
+            Ok(())
+        }
+    }
+
+    fn accepts(leaf: Fp, path: MerklePathWitness<Fp>, root: Fp) -> bool {
+        let circuit = PathCircuit {
+            leaf: Value::known(leaf),
+            path,
+        };
+        MockProver::run(9, &circuit, vec![vec![root]])
+            .unwrap()
+            .verify()
+            .is_ok()
+    }
+
+    fn tree() -> MerkleTree<Fp> {
+        let mut tree = MerkleTree::new(3);
+        for value in [3, 5, 7, 9, 11] {
+            tree.insert(Fp::from(value)).unwrap();
+        }
+        tree
+    }
+
+    #[test]
+    fn test_path_opens_to_native_root() {
+        let tree = tree();
+        for value in [3, 9, 11] {
+            let path = tree.prove(Fp::from(value)).unwrap();
+            assert_eq!(path.root(Fp::from(value)), tree.root());
+            assert!(accepts(Fp::from(value), (&path).into(), tree.root()));
+        }
+    }
+
+    #[test]
+    fn test_wrong_root_or_direction_rejected() {
+        let tree = tree();
+        let path = tree.prove(Fp::from(9)).unwrap();
+        assert!(!accepts(Fp::from(9), (&path).into(), tree.root() + Fp::ONE));
+        assert!(!accepts(Fp::from(5), (&path).into(), tree.root()));
+
+        let mut flipped = MerklePathWitness::from(&path);
+        flipped.directions[0] = flipped.directions[0].map(|bit| Fp::ONE - bit);
+        assert!(!accepts(Fp::from(9), flipped, tree.root()));
+
+        // 2 satisfies the ordering equations, but is not a bit
+        let mut doubled = MerklePathWitness::from(&path);
+        doubled.directions[1] = Value::known(Fp::from(2));
+        assert!(!accepts(Fp::from(9), doubled, tree.root()));
+    }
+}
@@ -0,0 +1,80 @@
+//! Reusable chips
+//!
+//! Every chip implements [`Gadget`]: `configure` declares its gates on
+//! caller-supplied columns, `load` fills any fixed tables once per circuit,
+//! and `assign` lays out one use of the chip. Circuits compose these instead
+//! of defining the same gates inline.
+
+use std::fmt;
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::Layouter,
+    plonk::{ConstraintSystem, Error},
+};
+
+pub mod bits;
+pub mod boolean;
+pub mod compare;
+pub mod decompose;
+pub mod ecc;
+pub mod foreign;
+pub mod merkle;
+pub mod poseidon;
+pub mod range;
+pub mod schnorr;
+pub mod sha256;
+
+pub use bits::{AssignedBits, BitsChip, BitsConfig};
+pub use boolean::{BooleanChip, BooleanConfig};
+pub use compare::{
+    IsZeroChip, IsZeroConfig, LessThanChip, LessThanConfig, SelectChip, SelectConfig,
+};
+pub use decompose::{DecomposeChip, DecomposeConfig};
+pub use ecc::{EccChip, EccConfig, EccPoint};
+pub use foreign::{
+    Bn254Base, ForeignElement, ForeignFieldChip, ForeignFieldConfig, ForeignModulus, Secp256k1Base,
+};
+pub use merkle::{MerkleChip, MerkleConfig, MerklePathWitness};
+pub use poseidon::{PoseidonChip, PoseidonConfig, PoseidonRounds, PoseidonSponge};
+pub use range::{RangeCheckChip, RangeCheckConfig};
+pub use schnorr::{SchnorrChip, SchnorrConfig, SchnorrSignature};
+pub use sha256::{Sha256Chip, Sha256Config, Sha256Word};
+
+/// A chip that can be configured once and assigned many times
+pub trait Gadget<F: Field>: Sized {
+    /// Columns, selectors and tables the chip uses
+    type Config: Clone + fmt::Debug;
+    /// Columns the caller hands to [`Gadget::configure`]
+    type Columns;
+    /// Witness for one use of the chip
+    type Input;
+    /// Cells produced by one use of the chip
+    type Output;
+
+    /// Wrap a config produced by [`Gadget::configure`]
+    fn construct(config: Self::Config) -> Self;
+
+    /// The chip's config
+    fn config(&self) -> &Self::Config;
+
+    /// Declare gates and lookups on `columns`
+    fn configure(meta: &mut ConstraintSystem<F>, columns: Self::Columns) -> Self::Config;
+
+    /// Fill fixed tables; call once per circuit before [`Gadget::assign`]
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if table assignment fails.
+    fn load(&self, _layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Assign one use of the chip
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails.
+    fn assign(&self, layouter: impl Layouter<F>, input: Self::Input)
+        -> Result<Self::Output, Error>;
+}
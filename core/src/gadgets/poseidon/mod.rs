@@ -0,0 +1,658 @@
+//! Poseidon hash chip (width 3)
+//!
+//! The permutation runs `R_F / 2` full rounds, `R_P` partial rounds and
+//! another `R_F / 2` full rounds, as in the Poseidon paper. Each full round
+//! takes one row. Partial rounds are laid out two per row: the first
+//! round's S-box output goes in the `partial_sbox` column and the second
+//! round's constants in `rc_b`.
+//!
+//! Inputs are absorbed [`RATE`] at a time. The first chunk is written
+//! straight into the initial state; later chunks take two extra rows, one
+//! holding the chunk and one the state with the chunk added. Use
+//! [`PoseidonChip::hash_many`] for fixed-length hashing and
+//! [`PoseidonSponge`] where absorbs and squeezes interleave, as in
+//! Fiat-Shamir transcripts.
+
+mod spec;
+mod sponge;
+
+use ff::PrimeField;
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+
+use spec::pow5;
+pub use spec::{
+    PoseidonConstants, PoseidonRounds, ALPHA, MIN_FULL_ROUNDS, MIN_PARTIAL_ROUNDS, RATE, WIDTH,
+};
+pub use sponge::PoseidonSponge;
+
+use super::Gadget;
+
+/// Poseidon chip for efficient hashing (width 3)
+#[derive(Clone, Debug)]
+pub struct PoseidonChip<F: Field> {
+    config: PoseidonConfig,
+    constants: PoseidonConstants<F>,
+}
+
+/// Columns, selectors and round counts used by [`PoseidonChip`]
+#[derive(Clone, Debug)]
+pub struct PoseidonConfig {
+    state: [Column<Advice>; WIDTH],
+    partial_sbox: Column<Advice>,
+    rc_a: [Column<Fixed>; WIDTH],
+    rc_b: [Column<Fixed>; WIDTH],
+    s_full: Selector,
+    s_partial: Selector,
+    s_pad_and_add: Selector,
+    rounds: PoseidonRounds,
+}
+
+impl PoseidonConfig {
+    /// Round counts the gates were configured for
+    #[must_use]
+    pub fn rounds(&self) -> PoseidonRounds {
+        self.rounds
+    }
+}
+
+impl<F: PrimeField> PoseidonChip<F> {
+    /// Declare the round gates for `rounds` on `columns`
+    ///
+    /// [`Gadget::configure`] uses [`PoseidonRounds::default`]. Constants
+    /// come from [`crate::poseidon::params`] for `rounds`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rc_a` and `rc_b` share a column: partial rounds need two
+    /// rounds' constants on the same row.
+    pub fn configure_with_rounds(
+        meta: &mut ConstraintSystem<F>,
+        (state, partial_sbox, rc_a, rc_b): <Self as Gadget<F>>::Columns,
+        rounds: PoseidonRounds,
+    ) -> PoseidonConfig {
+        assert!(
+            rc_a.iter().all(|column| !rc_b.contains(column)),
+            "rc_a and rc_b must be distinct fixed columns"
+        );
+        let constants = PoseidonConstants::<F>::new(rounds);
+        let s_full = meta.selector();
+        let s_partial = meta.selector();
+        let s_pad_and_add = meta.selector();
+        for column in state {
+            meta.enable_equality(column);
+        }
+        // Holds the initial capacity element and zero padding
+        meta.enable_constant(rc_b[0]);
+
+        let pow5_expr = |x: Expression<F>| x.clone() * x.clone() * x.clone() * x.clone() * x;
+        let mds = constants.mds();
+        let mds_inv = constants.mds_inv();
+        let dot = |row: [F; WIDTH], exprs: &[Expression<F>; WIDTH]| {
+            row.iter()
+                .zip(exprs)
+                .fold(Expression::Constant(F::ZERO), |acc, (m, x)| {
+                    acc + Expression::Constant(*m) * x.clone()
+                })
+        };
+
+        meta.create_gate("poseidon full round", |meta| {
+            let s = meta.query_selector(s_full);
+            let sbox: [Expression<F>; WIDTH] = std::array::from_fn(|i| {
+                pow5_expr(meta.query_advice(state[i], Rotation::cur()) + meta.query_fixed(rc_a[i]))
+            });
+
+            (0..WIDTH)
+                .map(|i| {
+                    // state_next = MDS * (state_cur + rc_a)^5
+                    let state_next = meta.query_advice(state[i], Rotation::next());
+                    s.clone() * (state_next - dot(mds[i], &sbox))
+                })
+                .collect::<Vec<_>>()
+        });
+
+        meta.create_gate("poseidon partial rounds", |meta| {
+            let s = meta.query_selector(s_partial);
+            let cur: [Expression<F>; WIDTH] =
+                std::array::from_fn(|i| meta.query_advice(state[i], Rotation::cur()));
+            let next: [Expression<F>; WIDTH] =
+                std::array::from_fn(|i| meta.query_advice(state[i], Rotation::next()));
+            let rc_a: [Expression<F>; WIDTH] = std::array::from_fn(|i| meta.query_fixed(rc_a[i]));
+            let rc_b: [Expression<F>; WIDTH] = std::array::from_fn(|i| meta.query_fixed(rc_b[i]));
+            let sbox = meta.query_advice(partial_sbox, Rotation::cur());
+
+            // State between the two rounds, after the first round's MDS
+            let first = [
+                sbox.clone(),
+                cur[1].clone() + rc_a[1].clone(),
+                cur[2].clone() + rc_a[2].clone(),
+            ];
+            let mid: [Expression<F>; WIDTH] = std::array::from_fn(|i| dot(mds[i], &first));
+            // Undo the second round's MDS rather than applying it, which
+            // keeps the second S-box out of the other two constraints
+            let unmixed: [Expression<F>; WIDTH] = std::array::from_fn(|i| dot(mds_inv[i], &next));
+
+            vec![
+                s.clone() * (sbox - pow5_expr(cur[0].clone() + rc_a[0].clone())),
+                s.clone() * (pow5_expr(mid[0].clone() + rc_b[0].clone()) - unmixed[0].clone()),
+                s.clone() * (mid[1].clone() + rc_b[1].clone() - unmixed[1].clone()),
+                s * (mid[2].clone() + rc_b[2].clone() - unmixed[2].clone()),
+            ]
+        });
+
+        meta.create_gate("poseidon pad and add", |meta| {
+            let s = meta.query_selector(s_pad_and_add);
+
+            (0..WIDTH)
+                .map(|i| {
+                    // Rate elements: next = prev + input, capacity: next = prev
+                    let prev = meta.query_advice(state[i], Rotation::prev());
+                    let next = meta.query_advice(state[i], Rotation::next());
+                    let sum = if i < RATE {
+                        prev + meta.query_advice(state[i], Rotation::cur())
+                    } else {
+                        prev
+                    };
+                    s.clone() * (next - sum)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        PoseidonConfig {
+            state,
+            partial_sbox,
+            rc_a,
+            rc_b,
+            s_full,
+            s_partial,
+            s_pad_and_add,
+            rounds,
+        }
+    }
+
+    /// Hash two field elements
+    ///
+    /// Same as [`PoseidonChip::hash_many`] on two cells, laid out in one
+    /// permutation.
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails.
+    pub fn hash(
+        &self,
+        layouter: impl Layouter<F>,
+        input: &[AssignedCell<F, F>; 2],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.hash_many(layouter, input)
+    }
+
+    /// Hash `input` in constant-length mode
+    ///
+    /// The capacity element starts at `input.len() * 2^64` and the last
+    /// chunk is padded with zeros, as in `halo2_gadgets`' `ConstantLength`.
+    /// An empty input hashes one chunk of zeros.
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails.
+    pub fn hash_many(
+        &self,
+        mut layouter: impl Layouter<F>,
+        input: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let mut sponge = self.sponge(F::from_u128((input.len() as u128) << 64));
+        for cell in input {
+            sponge.absorb(cell.clone());
+        }
+        sponge.squeeze(layouter.namespace(|| "poseidon hash"))
+    }
+
+    /// Start a duplex sponge whose capacity element begins at `domain`
+    #[must_use]
+    pub fn sponge(&self, domain: F) -> PoseidonSponge<'_, F> {
+        PoseidonSponge::new(self, domain)
+    }
+
+    /// Add `input` to the rate of `state` and apply the permutation
+    ///
+    /// Without a `state`, the permutation starts from zero rate elements and
+    /// a `capacity` constant. `input` holds at most [`RATE`] cells and is
+    /// padded with zeros.
+    pub(super) fn permute(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: Option<&[AssignedCell<F, F>; WIDTH]>,
+        capacity: F,
+        input: &[AssignedCell<F, F>],
+    ) -> Result<[AssignedCell<F, F>; WIDTH], Error> {
+        debug_assert!(input.len() <= RATE, "absorbing more than the rate");
+        let rounds = self.config.rounds;
+        let half = rounds.full() / 2;
+        let partial_end = half + rounds.partial();
+
+        layouter.assign_region(
+            || "poseidon permutation",
+            |mut region| {
+                let (mut row, mut cells) = match state {
+                    None => {
+                        let mut cells = self.assign_input(&mut region, 0, input)?;
+                        cells.push(region.assign_advice_from_constant(
+                            || "capacity",
+                            self.config.state[RATE],
+                            0,
+                            capacity,
+                        )?);
+                        (0, into_state(cells))
+                    }
+                    Some(state) => {
+                        for (i, cell) in state.iter().enumerate() {
+                            cell.copy_advice(
+                                || crate::annotation!("state {}", i),
+                                &mut region,
+                                self.config.state[i],
+                                0,
+                            )?;
+                        }
+                        self.config.s_pad_and_add.enable(&mut region, 1)?;
+                        let input = self.assign_input(&mut region, 1, input)?;
+                        let sum = transpose(std::array::from_fn(|i| {
+                            let value = state[i].value().copied();
+                            match input.get(i) {
+                                Some(input) => value + input.value(),
+                                None => value,
+                            }
+                        }));
+                        (2, self.assign_state(&mut region, 2, sum)?)
+                    }
+                };
+
+                let mut state = values(&cells);
+                for round in 0..half {
+                    state = self.assign_full_round(&mut region, row, round, state)?;
+                    row += 1;
+                    cells = self.assign_state(&mut region, row, state)?;
+                }
+                for round in (half..partial_end).step_by(2) {
+                    state = self.assign_partial_rounds(&mut region, row, round, state)?;
+                    row += 1;
+                    cells = self.assign_state(&mut region, row, state)?;
+                }
+                for round in partial_end..partial_end + half {
+                    state = self.assign_full_round(&mut region, row, round, state)?;
+                    row += 1;
+                    cells = self.assign_state(&mut region, row, state)?;
+                }
+                Ok(cells)
+            },
+        )
+    }
+
+    /// Copy `input` into the rate columns at `row`, padding with zeros
+    fn assign_input(
+        &self,
+        region: &mut Region<'_, F>,
+        row: usize,
+        input: &[AssignedCell<F, F>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        (0..RATE)
+            .map(|i| match input.get(i) {
+                Some(cell) => cell.copy_advice(
+                    || crate::annotation!("input {}", i),
+                    region,
+                    self.config.state[i],
+                    row,
+                ),
+                None => region.assign_advice_from_constant(
+                    || crate::annotation!("padding {}", i),
+                    self.config.state[i],
+                    row,
+                    F::ZERO,
+                ),
+            })
+            .collect()
+    }
+
+    /// Lay out full round `round` at `row`
+    fn assign_full_round(
+        &self,
+        region: &mut Region<'_, F>,
+        row: usize,
+        round: usize,
+        state: Value<[F; WIDTH]>,
+    ) -> Result<Value<[F; WIDTH]>, Error> {
+        self.config.s_full.enable(region, row)?;
+        self.assign_constants(region, self.config.rc_a, row, round)?;
+        Ok(state.map(|state| self.constants.full_round(round, state)))
+    }
+
+    /// Lay out partial rounds `round` and `round + 1` at `row`
+    fn assign_partial_rounds(
+        &self,
+        region: &mut Region<'_, F>,
+        row: usize,
+        round: usize,
+        state: Value<[F; WIDTH]>,
+    ) -> Result<Value<[F; WIDTH]>, Error> {
+        self.config.s_partial.enable(region, row)?;
+        self.assign_constants(region, self.config.rc_a, row, round)?;
+        self.assign_constants(region, self.config.rc_b, row, round + 1)?;
+        let rc = self.constants.round_constants(round);
+        region.assign_advice(
+            || "partial s-box",
+            self.config.partial_sbox,
+            row,
+            || state.map(|state| pow5(state[0] + rc[0])),
+        )?;
+        Ok(state.map(|state| {
+            let state = self.constants.partial_round(round, state);
+            self.constants.partial_round(round + 1, state)
+        }))
+    }
+
+    fn assign_constants(
+        &self,
+        region: &mut Region<'_, F>,
+        columns: [Column<Fixed>; WIDTH],
+        row: usize,
+        round: usize,
+    ) -> Result<(), Error> {
+        let rc = self.constants.round_constants(round);
+        for (i, column) in columns.into_iter().enumerate() {
+            region.assign_fixed(
+                || crate::annotation!("round {} constant {}", round, i),
+                column,
+                row,
+                || Value::known(rc[i]),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Assign `state` at `row`
+    fn assign_state(
+        &self,
+        region: &mut Region<'_, F>,
+        row: usize,
+        state: Value<[F; WIDTH]>,
+    ) -> Result<[AssignedCell<F, F>; WIDTH], Error> {
+        let cells = (0..WIDTH)
+            .map(|i| {
+                region.assign_advice(
+                    || crate::annotation!("state {}", i),
+                    self.config.state[i],
+                    row,
+                    || state.map(|state| state[i]),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(into_state(cells))
+    }
+}
+
+fn into_state<F: Field>(cells: Vec<AssignedCell<F, F>>) -> [AssignedCell<F, F>; WIDTH] {
+    cells.try_into().expect("one cell per state column")
+}
+
+fn values<F: Field>(cells: &[AssignedCell<F, F>; WIDTH]) -> Value<[F; WIDTH]> {
+    transpose(std::array::from_fn(|i| cells[i].value().copied()))
+}
+
+fn transpose<F: Field>([a, b, c]: [Value<F>; WIDTH]) -> Value<[F; WIDTH]> {
+    a.zip(b).zip(c).map(|((a, b), c)| [a, b, c])
+}
+
+impl<F: PrimeField> Gadget<F> for PoseidonChip<F> {
+    type Config = PoseidonConfig;
+    /// State columns, partial S-box column and two sets of round constants
+    type Columns = (
+        [Column<Advice>; WIDTH],
+        Column<Advice>,
+        [Column<Fixed>; WIDTH],
+        [Column<Fixed>; WIDTH],
+    );
+    type Input = [AssignedCell<F, F>; 2];
+    type Output = AssignedCell<F, F>;
+
+    fn construct(config: PoseidonConfig) -> Self {
+        Self {
+            constants: PoseidonConstants::new(config.rounds),
+            config,
+        }
+    }
+
+    fn config(&self) -> &PoseidonConfig {
+        &self.config
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>, columns: Self::Columns) -> PoseidonConfig {
+        Self::configure_with_rounds(meta, columns, PoseidonRounds::default())
+    }
+
+    fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        input: Self::Input,
+    ) -> Result<Self::Output, Error> {
+        self.hash(layouter, &input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{circuit::SimpleFloorPlanner, dev::MockProver, plonk::Circuit};
+    use pasta_curves::Fp;
+
+    use super::*;
+    use crate::poseidon::Poseidon;
+
+    fn configure<const FULL: usize, const PARTIAL: usize>(
+        meta: &mut ConstraintSystem<Fp>,
+    ) -> PoseidonConfig {
+        let columns = (
+            std::array::from_fn(|_| meta.advice_column()),
+            meta.advice_column(),
+            std::array::from_fn(|_| meta.fixed_column()),
+            std::array::from_fn(|_| meta.fixed_column()),
+        );
+        let rounds = PoseidonRounds::new(FULL, PARTIAL).unwrap();
+        PoseidonChip::configure_with_rounds(meta, columns, rounds)
+    }
+
+    /// Assign `values` down the first state column
+    fn assign_inputs(
+        config: &PoseidonConfig,
+        layouter: &mut impl Layouter<Fp>,
+        values: &[Value<Fp>],
+    ) -> Result<Vec<AssignedCell<Fp, Fp>>, Error> {
+        layouter.assign_region(
+            || "inputs",
+            |mut region| {
+                values
+                    .iter()
+                    .enumerate()
+                    .map(|(row, value)| {
+                        region.assign_advice(|| "input", config.state[0], row, || *value)
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    #[derive(Default)]
+    struct HashCircuit<const FULL: usize, const PARTIAL: usize> {
+        input: Vec<Value<Fp>>,
+        expected: Option<Fp>,
+    }
+
+    impl<const FULL: usize, const PARTIAL: usize> Circuit<Fp> for HashCircuit<FULL, PARTIAL> {
+        type Config = PoseidonConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                input: vec![Value::unknown(); self.input.len()],
+                expected: None,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> PoseidonConfig {
+            configure::<FULL, PARTIAL>(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: PoseidonConfig,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let input = assign_inputs(&config, &mut layouter, &self.input)?;
+            let chip = PoseidonChip::construct(config);
+            let output = chip.hash_many(layouter.namespace(|| "hash"), &input)?;
+            if let Some(expected) = self.expected {
+                output
+                    .value()
+                    .assert_if_known(|output| **output == expected);
+            }
+            Ok(())
+        }
+    }
+
+    /// Absorbs `a`, squeezes twice, absorbs `rest` and squeezes again
+    #[derive(Default)]
+    struct SpongeCircuit {
+        a: Value<Fp>,
+        rest: [Value<Fp>; 3],
+        expected: Option<[Fp; 3]>,
+    }
+
+    impl Circuit<Fp> for SpongeCircuit {
+        type Config = PoseidonConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> PoseidonConfig {
+            configure::<MIN_FULL_ROUNDS, MIN_PARTIAL_ROUNDS>(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: PoseidonConfig,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let mut input = assign_inputs(&config, &mut layouter, &[self.a])?;
+            input.extend(assign_inputs(&config, &mut layouter, &self.rest)?);
+            let chip = PoseidonChip::construct(config);
+            let mut sponge = chip.sponge(Fp::from(7));
+
+            sponge.absorb(input[0].clone());
+            let mut outputs = vec![sponge.squeeze(layouter.namespace(|| "squeeze 0"))?];
+            outputs.push(sponge.squeeze(layouter.namespace(|| "squeeze 1"))?);
+            for cell in &input[1..] {
+                sponge.absorb(cell.clone());
+            }
+            outputs.push(sponge.squeeze(layouter.namespace(|| "squeeze 2"))?);
+
+            if let Some(expected) = self.expected {
+                for (output, expected) in outputs.iter().zip(expected) {
+                    output
+                        .value()
+                        .assert_if_known(|output| **output == expected);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// Native duplex: add each chunk to the rate and permute
+    fn native(rounds: PoseidonRounds, state: &mut [Fp; WIDTH], chunk: &[Fp]) -> Fp {
+        for (element, input) in state.iter_mut().zip(chunk) {
+            *element += input;
+        }
+        *state = PoseidonConstants::new(rounds).permute(*state);
+        state[0]
+    }
+
+    fn native_hash(rounds: PoseidonRounds, input: &[Fp]) -> Fp {
+        Poseidon::new(rounds).hash(input)
+    }
+
+    fn hash_circuit<const FULL: usize, const PARTIAL: usize>(
+        input: &[Fp],
+    ) -> HashCircuit<FULL, PARTIAL> {
+        let rounds = PoseidonRounds::new(FULL, PARTIAL).unwrap();
+        HashCircuit {
+            input: input.iter().copied().map(Value::known).collect(),
+            expected: Some(native_hash(rounds, input)),
+        }
+    }
+
+    #[test]
+    fn test_hash_matches_native_permutation() {
+        let (a, b) = (Fp::from(1), Fp::from(2));
+        let rounds = PoseidonRounds::default();
+        assert_eq!(
+            native_hash(rounds, &[a, b]),
+            PoseidonConstants::new(rounds).permute([a, b, Fp::from_u128(2 << 64)])[0]
+        );
+        let circuit = hash_circuit::<MIN_FULL_ROUNDS, MIN_PARTIAL_ROUNDS>(&[a, b]);
+        MockProver::run(7, &circuit, vec![])
+            .unwrap()
+            .assert_satisfied();
+    }
+
+    #[test]
+    fn test_configured_round_counts() {
+        let input = [Fp::from(3), Fp::from(4)];
+        let rounds = PoseidonRounds::new(10, 60).unwrap();
+        assert_ne!(
+            native_hash(rounds, &input),
+            native_hash(PoseidonRounds::default(), &input)
+        );
+        let circuit = hash_circuit::<10, 60>(&input);
+        MockProver::run(7, &circuit, vec![])
+            .unwrap()
+            .assert_satisfied();
+    }
+
+    #[test]
+    fn test_hash_many_lengths() {
+        for len in [0u64, 1, 3, 5] {
+            let input: Vec<Fp> = (1..=len).map(Fp::from).collect();
+            let circuit = hash_circuit::<MIN_FULL_ROUNDS, MIN_PARTIAL_ROUNDS>(&input);
+            MockProver::run(8, &circuit, vec![])
+                .unwrap()
+                .assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_sponge_matches_native_duplex() {
+        let rounds = PoseidonRounds::default();
+        let (a, rest) = (Fp::from(11), [Fp::from(12), Fp::from(13), Fp::from(14)]);
+        let mut state = [Fp::ZERO, Fp::ZERO, Fp::from(7)];
+        let expected = [
+            native(rounds, &mut state, &[a]),
+            native(rounds, &mut state, &[]),
+            {
+                native(rounds, &mut state, &rest[..RATE]);
+                native(rounds, &mut state, &rest[RATE..])
+            },
+        ];
+        assert_ne!(expected[0], expected[1]);
+
+        let circuit = SpongeCircuit {
+            a: Value::known(a),
+            rest: rest.map(Value::known),
+            expected: Some(expected),
+        };
+        MockProver::run(9, &circuit, vec![])
+            .unwrap()
+            .assert_satisfied();
+    }
+}
@@ -0,0 +1,314 @@
+//! Round counts and constants for [`super::PoseidonChip`]
+
+use ff::{Field, PrimeField};
+
+use crate::{
+    error::{Error, Result},
+    poseidon::{PoseidonParams, PoseidonSpec},
+};
+
+/// Permutation state width
+pub const WIDTH: usize = 3;
+
+/// Elements absorbed per permutation; the last state element is capacity
+pub const RATE: usize = WIDTH - 1;
+
+/// Fewest full rounds accepted by [`PoseidonRounds::new`]
+///
+/// The Poseidon paper's 128-bit parameters for width 3 and `x^5`, which
+/// already include its two-round margin against statistical attacks.
+pub const MIN_FULL_ROUNDS: usize = 8;
+
+/// Fewest partial rounds accepted by [`PoseidonRounds::new`]
+///
+/// The Poseidon paper's 128-bit parameters for width 3 and `x^5` over
+/// 255-bit fields, including its 7.5% margin against algebraic attacks.
+pub const MIN_PARTIAL_ROUNDS: usize = 56;
+
+/// S-box exponent of the chip's `x^5`
+pub const ALPHA: u64 = 5;
+
+/// Full and partial round counts of the permutation
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PoseidonRounds {
+    full: usize,
+    partial: usize,
+}
+
+impl PoseidonRounds {
+    /// Round counts checked against the security margins
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Other`] if `full` is odd or below
+    /// [`MIN_FULL_ROUNDS`], or if `partial` is odd or below
+    /// [`MIN_PARTIAL_ROUNDS`]. Full rounds are split evenly around the
+    /// partial rounds, and partial rounds are laid out two per row.
+    pub fn new(full: usize, partial: usize) -> Result<Self> {
+        if full < MIN_FULL_ROUNDS || full % 2 != 0 {
+            return Err(Error::Other(format!(
+                "poseidon needs an even number of at least {MIN_FULL_ROUNDS} full rounds, found {full}"
+            )));
+        }
+        if partial < MIN_PARTIAL_ROUNDS || partial % 2 != 0 {
+            return Err(Error::Other(format!(
+                "poseidon needs an even number of at least {MIN_PARTIAL_ROUNDS} partial rounds, found {partial}"
+            )));
+        }
+        Ok(Self { full, partial })
+    }
+
+    /// Number of full rounds, `R_F`
+    #[must_use]
+    pub fn full(self) -> usize {
+        self.full
+    }
+
+    /// Number of partial rounds, `R_P`
+    #[must_use]
+    pub fn partial(self) -> usize {
+        self.partial
+    }
+
+    /// Rows one permutation takes, including the output row
+    #[must_use]
+    pub fn rows(self) -> usize {
+        self.full + self.partial / 2 + 1
+    }
+
+    /// Width-3 `x^5` spec with these round counts
+    #[must_use]
+    pub fn spec(self) -> PoseidonSpec {
+        PoseidonSpec {
+            width: WIDTH,
+            alpha: ALPHA,
+            full_rounds: self.full,
+            partial_rounds: self.partial,
+        }
+    }
+}
+
+impl TryFrom<PoseidonSpec> for PoseidonRounds {
+    type Error = Error;
+
+    /// Round counts of `spec`, which must be width 3 with `x^5`
+    fn try_from(spec: PoseidonSpec) -> Result<Self> {
+        if spec.width != WIDTH || spec.alpha != ALPHA {
+            return Err(Error::Other(format!(
+                "the poseidon chip is width {WIDTH} with x^{ALPHA}, found width {} with x^{}",
+                spec.width, spec.alpha
+            )));
+        }
+        Self::new(spec.full_rounds, spec.partial_rounds)
+    }
+}
+
+impl Default for PoseidonRounds {
+    fn default() -> Self {
+        Self {
+            full: MIN_FULL_ROUNDS,
+            partial: MIN_PARTIAL_ROUNDS,
+        }
+    }
+}
+
+/// Round constants and MDS matrix for one choice of [`PoseidonRounds`]
+#[derive(Clone, Debug)]
+pub struct PoseidonConstants<F> {
+    rounds: PoseidonRounds,
+    round_constants: Vec<[F; WIDTH]>,
+    mds: [[F; WIDTH]; WIDTH],
+    mds_inv: [[F; WIDTH]; WIDTH],
+}
+
+impl<F: PrimeField> PoseidonConstants<F> {
+    /// Expand the constants for `rounds`
+    ///
+    /// Round constants and the MDS matrix come from the Grain LFSR
+    /// procedure of [`crate::poseidon::params`], which is seeded with the
+    /// round counts, so changing either count changes every constant.
+    #[must_use]
+    pub fn new(rounds: PoseidonRounds) -> Self {
+        Self::from_checked(rounds, &PoseidonParams::expand(rounds.spec()))
+    }
+
+    /// Constants of generated `params`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Other`] if `params` are not for a spec
+    /// [`PoseidonRounds`] accepts.
+    pub fn from_params(params: &PoseidonParams<F>) -> Result<Self> {
+        let rounds = PoseidonRounds::try_from(params.spec())?;
+        Ok(Self::from_checked(rounds, params))
+    }
+
+    fn from_checked(rounds: PoseidonRounds, params: &PoseidonParams<F>) -> Self {
+        let row = |values: &[F]| -> [F; WIDTH] { std::array::from_fn(|i| values[i]) };
+        let mds = std::array::from_fn(|i| row(&params.mds()[i]));
+        Self {
+            rounds,
+            round_constants: params
+                .round_constants()
+                .iter()
+                .map(Vec::as_slice)
+                .map(row)
+                .collect(),
+            mds,
+            mds_inv: invert(&mds),
+        }
+    }
+
+    /// Round counts these constants were expanded for
+    #[must_use]
+    pub fn rounds(&self) -> PoseidonRounds {
+        self.rounds
+    }
+
+    /// Constants added to the state before round `round`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `round` is not below `R_F + R_P`.
+    #[must_use]
+    pub fn round_constants(&self, round: usize) -> [F; WIDTH] {
+        self.round_constants[round]
+    }
+
+    /// MDS matrix applied after each round's S-boxes
+    #[must_use]
+    pub fn mds(&self) -> [[F; WIDTH]; WIDTH] {
+        self.mds
+    }
+
+    /// Inverse of [`PoseidonConstants::mds`]
+    #[must_use]
+    pub fn mds_inv(&self) -> [[F; WIDTH]; WIDTH] {
+        self.mds_inv
+    }
+
+    /// Full round `round` applied to `state`
+    #[must_use]
+    pub fn full_round(&self, round: usize, state: [F; WIDTH]) -> [F; WIDTH] {
+        let rc = self.round_constants(round);
+        self.mix(std::array::from_fn(|i| pow5(state[i] + rc[i])))
+    }
+
+    /// Partial round `round` applied to `state`
+    #[must_use]
+    pub fn partial_round(&self, round: usize, state: [F; WIDTH]) -> [F; WIDTH] {
+        let rc = self.round_constants(round);
+        let mut added: [F; WIDTH] = std::array::from_fn(|i| state[i] + rc[i]);
+        added[0] = pow5(added[0]);
+        self.mix(added)
+    }
+
+    /// The whole permutation applied to `state`
+    #[must_use]
+    pub fn permute(&self, mut state: [F; WIDTH]) -> [F; WIDTH] {
+        let half = self.rounds.full / 2;
+        for round in 0..self.rounds.full + self.rounds.partial {
+            state = if round < half || round >= half + self.rounds.partial {
+                self.full_round(round, state)
+            } else {
+                self.partial_round(round, state)
+            };
+        }
+        state
+    }
+
+    fn mix(&self, state: [F; WIDTH]) -> [F; WIDTH] {
+        std::array::from_fn(|i| {
+            self.mds[i]
+                .iter()
+                .zip(state)
+                .fold(F::ZERO, |acc, (m, x)| acc + *m * x)
+        })
+    }
+}
+
+/// `x^5`
+pub(super) fn pow5<F: Field>(x: F) -> F {
+    x.square().square() * x
+}
+
+/// Inverse of a 3x3 matrix by its adjugate
+fn invert<F: Field>(m: &[[F; WIDTH]; WIDTH]) -> [[F; WIDTH]; WIDTH] {
+    let cofactor = |i: usize, j: usize| {
+        let (r0, r1) = ((i + 1) % WIDTH, (i + 2) % WIDTH);
+        let (c0, c1) = ((j + 1) % WIDTH, (j + 2) % WIDTH);
+        m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+    };
+    let det = (0..WIDTH).fold(F::ZERO, |acc, j| acc + m[0][j] * cofactor(0, j));
+    let det_inv = Option::from(det.invert()).expect("Cauchy matrices are invertible");
+    std::array::from_fn(|i| std::array::from_fn(|j| cofactor(j, i) * det_inv))
+}
+
+#[cfg(test)]
+mod tests {
+    use pasta_curves::Fp;
+
+    use super::*;
+
+    #[test]
+    fn test_round_counts_validated() {
+        assert_eq!(
+            PoseidonRounds::new(MIN_FULL_ROUNDS, MIN_PARTIAL_ROUNDS).unwrap(),
+            PoseidonRounds::default()
+        );
+        assert!(PoseidonRounds::new(10, 60).is_ok());
+        for (full, partial) in [(6, 56), (9, 56), (8, 54), (8, 57), (0, 0)] {
+            assert!(matches!(
+                PoseidonRounds::new(full, partial),
+                Err(Error::Other(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_mds_inverse() {
+        let constants = PoseidonConstants::<Fp>::new(PoseidonRounds::default());
+        let (mds, mds_inv) = (constants.mds(), constants.mds_inv());
+        let product: [[Fp; WIDTH]; WIDTH] = std::array::from_fn(|i| {
+            std::array::from_fn(|j| {
+                (0..WIDTH).fold(Fp::ZERO, |acc, k| acc + mds[i][k] * mds_inv[k][j])
+            })
+        });
+        let identity: [[Fp; WIDTH]; WIDTH] =
+            std::array::from_fn(|i| std::array::from_fn(|j| Fp::from(u64::from(i == j))));
+        assert_eq!(product, identity);
+    }
+
+    #[test]
+    fn test_constants_depend_on_round_counts() {
+        let default = PoseidonConstants::<Fp>::new(PoseidonRounds::default());
+        let longer = PoseidonConstants::<Fp>::new(PoseidonRounds::new(8, 58).unwrap());
+        assert_ne!(default.round_constants(0), longer.round_constants(0));
+        assert_ne!(
+            default.permute([Fp::ONE; WIDTH]),
+            longer.permute([Fp::ONE; WIDTH])
+        );
+    }
+
+    #[test]
+    fn test_constants_from_standard_params() {
+        let rounds = PoseidonRounds::try_from(crate::poseidon::P128_POW5_T3).unwrap();
+        assert_eq!(rounds, PoseidonRounds::default());
+        let params = crate::poseidon::params::pallas_t3();
+        let constants = PoseidonConstants::from_params(&params).unwrap();
+        assert_eq!(
+            constants.round_constants(0).as_slice(),
+            params.round_constants()[0]
+        );
+        assert_eq!(
+            constants.permute([Fp::ONE; WIDTH]),
+            PoseidonConstants::new(rounds).permute([Fp::ONE; WIDTH])
+        );
+
+        let wide = crate::poseidon::params::pallas_t9();
+        assert!(matches!(
+            PoseidonConstants::from_params(&wide),
+            Err(Error::Other(_))
+        ));
+    }
+}
@@ -0,0 +1,73 @@
+//! In-circuit duplex sponge over [`PoseidonChip`]
+
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter},
+    plonk::Error,
+};
+
+use super::{PoseidonChip, RATE, WIDTH};
+
+/// Duplex sponge that absorbs cells and squeezes challenge cells
+///
+/// Absorbed cells are buffered until the next squeeze, which adds them to
+/// the rate [`RATE`] at a time, permuting after each chunk. A squeeze with
+/// nothing buffered permutes a zero chunk, so consecutive squeezes return
+/// distinct outputs.
+#[derive(Debug)]
+pub struct PoseidonSponge<'a, F: PrimeField> {
+    chip: &'a PoseidonChip<F>,
+    domain: F,
+    state: Option<[AssignedCell<F, F>; WIDTH]>,
+    pending: Vec<AssignedCell<F, F>>,
+}
+
+impl<'a, F: PrimeField> PoseidonSponge<'a, F> {
+    /// Empty sponge whose capacity element begins at `domain`
+    #[must_use]
+    pub fn new(chip: &'a PoseidonChip<F>, domain: F) -> Self {
+        Self {
+            chip,
+            domain,
+            state: None,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Buffer `cell` for the next squeeze
+    pub fn absorb(&mut self, cell: AssignedCell<F, F>) {
+        self.pending.push(cell);
+    }
+
+    /// Absorb everything buffered and return the first rate element
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails.
+    pub fn squeeze(&mut self, mut layouter: impl Layouter<F>) -> Result<AssignedCell<F, F>, Error> {
+        let pending = std::mem::take(&mut self.pending);
+        let first = &pending[..pending.len().min(RATE)];
+        let mut output = self.permute(&mut layouter, first)?;
+        for chunk in pending.chunks(RATE).skip(1) {
+            output = self.permute(&mut layouter, chunk)?;
+        }
+        Ok(output)
+    }
+
+    /// Permute with `input` added, returning the first rate element
+    fn permute(
+        &mut self,
+        layouter: &mut impl Layouter<F>,
+        input: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let state = self.chip.permute(
+            layouter.namespace(|| "sponge permutation"),
+            self.state.as_ref(),
+            self.domain,
+            input,
+        )?;
+        let output = state[0].clone();
+        self.state = Some(state);
+        Ok(output)
+    }
+}
@@ -0,0 +1,329 @@
+//! Lookup-based range check chip
+//!
+//! [`RangeCheckChip::assign`] checks a value against the `BITS`-bit table
+//! directly. [`RangeCheckChip::check`] handles any width below the field's
+//! by decomposing an existing cell into `BITS`-bit chunks with a running
+//! sum, the way balances and other 64-bit values are checked.
+
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector, TableColumn},
+    poly::Rotation,
+};
+
+use super::Gadget;
+use crate::utils::bits::{from_windows, to_windows};
+
+/// Largest supported `BITS`; the table takes `2^BITS` rows
+pub const MAX_RANGE_BITS: usize = 16;
+
+/// Constrains a cell to `[0, 2^BITS)` with a lookup table
+#[derive(Clone, Debug)]
+pub struct RangeCheckChip<F: PrimeField, const BITS: usize> {
+    config: RangeCheckConfig,
+    _marker: PhantomData<F>,
+}
+
+/// Columns, selectors and table used by [`RangeCheckChip`]
+#[derive(Clone, Debug)]
+pub struct RangeCheckConfig {
+    /// Column holding checked values and running sums
+    pub value: Column<Advice>,
+    q_lookup: Selector,
+    q_running: Selector,
+    /// `2^(BITS - r)` on the top chunk of a running sum whose top chunk
+    /// has `r < BITS` bits, zero elsewhere
+    shift: Column<Fixed>,
+    table: TableColumn,
+}
+
+impl<F: PrimeField, const BITS: usize> RangeCheckChip<F, BITS> {
+    /// Constrain `cell` to `[0, 2^bits)`
+    ///
+    /// The value is split into `ceil(bits / BITS)` little-endian chunks,
+    /// each looked up in the table. A top chunk of `r < BITS` bits is also
+    /// looked up times `2^(BITS - r)`, which keeps it below `2^r`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `BITS` or `bits` is zero, or if `bits` is not below
+    /// `F::NUM_BITS`, where a decomposition could wrap around the modulus.
+    pub fn check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        bits: usize,
+    ) -> Result<(), Error> {
+        assert!(BITS > 0, "the table must have at least one bit");
+        assert!(
+            (1..F::NUM_BITS as usize).contains(&bits),
+            "range checks of {bits} bits are not in 1..{}",
+            F::NUM_BITS
+        );
+        let chunks = bits.div_ceil(BITS);
+        let top_bits = bits - (chunks - 1) * BITS;
+        let windows = cell.value().map(|value| to_windows(value, BITS));
+        layouter.assign_region(
+            || crate::annotation!("{}-bit range check", bits),
+            |mut region| {
+                cell.copy_advice(|| "z 0", &mut region, self.config.value, 0)?;
+                for i in 1..chunks {
+                    self.config.q_running.enable(&mut region, i - 1)?;
+                    region.assign_advice(
+                        || crate::annotation!("z {}", i),
+                        self.config.value,
+                        i,
+                        || {
+                            windows
+                                .as_ref()
+                                .map(|windows| from_windows::<F>(&windows[i..], BITS))
+                        },
+                    )?;
+                }
+                // The last z is the top chunk itself
+                self.config.q_lookup.enable(&mut region, chunks - 1)?;
+                if top_bits < BITS {
+                    region.assign_fixed(
+                        || "top chunk shift",
+                        self.config.shift,
+                        chunks - 1,
+                        || Value::known(F::from(1 << (BITS - top_bits))),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+impl<F: PrimeField, const BITS: usize> Gadget<F> for RangeCheckChip<F, BITS> {
+    type Config = RangeCheckConfig;
+    type Columns = Column<Advice>;
+    type Input = Value<F>;
+    type Output = AssignedCell<F, F>;
+
+    fn construct(config: RangeCheckConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn config(&self) -> &RangeCheckConfig {
+        &self.config
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `BITS` exceeds [`MAX_RANGE_BITS`].
+    fn configure(meta: &mut ConstraintSystem<F>, value: Column<Advice>) -> RangeCheckConfig {
+        assert!(
+            BITS <= MAX_RANGE_BITS,
+            "range check of {BITS} bits exceeds {MAX_RANGE_BITS}"
+        );
+        let q_lookup = meta.complex_selector();
+        let q_running = meta.complex_selector();
+        let shift = meta.fixed_column();
+        let table = meta.lookup_table_column();
+        meta.enable_equality(value);
+
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_lookup);
+            let value = meta.query_advice(value, Rotation::cur());
+            vec![(q * value, table)]
+        });
+
+        // chunk_i = z_i - 2^BITS * z_{i+1}
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_running);
+            let z_cur = meta.query_advice(value, Rotation::cur());
+            let z_next = meta.query_advice(value, Rotation::next());
+            let chunk = z_cur - Expression::Constant(F::from(1 << BITS)) * z_next;
+            vec![(q * chunk, table)]
+        });
+
+        // An r-bit top chunk times 2^(BITS - r) stays in the table only if
+        // the chunk is below 2^r; rows without a short top chunk look up 0
+        meta.lookup(|meta| {
+            let shift = meta.query_fixed(shift);
+            let value = meta.query_advice(value, Rotation::cur());
+            vec![(shift * value, table)]
+        });
+
+        RangeCheckConfig {
+            value,
+            q_lookup,
+            q_running,
+            shift,
+            table,
+        }
+    }
+
+    fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || crate::annotation!("{}-bit range table", BITS),
+            |mut table| {
+                for value in 0..1u64 << BITS {
+                    #[allow(clippy::cast_possible_truncation)]
+                    table.assign_cell(
+                        || "value",
+                        self.config.table,
+                        value as usize,
+                        || Value::known(F::from(value)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "range check",
+            |mut region| {
+                self.config.q_lookup.enable(&mut region, 0)?;
+                region.assign_advice(|| "value", self.config.value, 0, || value)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{circuit::SimpleFloorPlanner, dev::MockProver, plonk::Circuit};
+    use pasta_curves::Fp;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct RangeCircuit {
+        values: Vec<Value<Fp>>,
+    }
+
+    impl Circuit<Fp> for RangeCircuit {
+        type Config = RangeCheckConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                values: vec![Value::unknown(); self.values.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> RangeCheckConfig {
+            let value = meta.advice_column();
+            RangeCheckChip::<Fp, 4>::configure(meta, value)
+        }
+
+        fn synthesize(
+            &self,
+            config: RangeCheckConfig,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = RangeCheckChip::<Fp, 4>::construct(config);
+            chip.load(&mut layouter)?;
+            for value in &self.values {
+                chip.assign(layouter.namespace(|| "value"), *value)?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Witnesses values and checks each to `bits` bits with 4-bit chunks
+    struct WideRangeCircuit {
+        values: Vec<Value<Fp>>,
+        bits: usize,
+    }
+
+    impl Circuit<Fp> for WideRangeCircuit {
+        type Config = RangeCheckConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                values: vec![Value::unknown(); self.values.len()],
+                bits: self.bits,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> RangeCheckConfig {
+            let value = meta.advice_column();
+            RangeCheckChip::<Fp, 4>::configure(meta, value)
+        }
+
+        fn synthesize(
+            &self,
+            config: RangeCheckConfig,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = RangeCheckChip::<Fp, 4>::construct(config.clone());
+            chip.load(&mut layouter)?;
+            for value in &self.values {
+                let cell = layouter.assign_region(
+                    || "value",
+                    |mut region| region.assign_advice(|| "value", config.value, 0, || *value),
+                )?;
+                chip.check(layouter.namespace(|| "check"), &cell, self.bits)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn check(values: &[u64], bits: usize) -> bool {
+        let circuit = WideRangeCircuit {
+            values: values.iter().map(|v| Value::known(Fp::from(*v))).collect(),
+            bits,
+        };
+        MockProver::run(7, &circuit, vec![])
+            .unwrap()
+            .verify()
+            .is_ok()
+    }
+
+    #[test]
+    fn test_check_any_width() {
+        // Three chunks, the top one of 2 bits
+        assert!(check(&[0, 1, 1023], 10));
+        assert!(!check(&[1024], 10));
+        assert!(!check(&[1 << 11], 10));
+        // Narrower than a chunk
+        assert!(check(&[7], 3));
+        assert!(!check(&[8], 3));
+        // Whole chunks
+        assert!(check(&[u64::MAX], 64));
+        assert!(check(&[0xffff], 16));
+        assert!(!check(&[0x1_0000], 16));
+    }
+
+    #[test]
+    fn test_in_range_accepted() {
+        let circuit = RangeCircuit {
+            values: [0, 7, 15].map(|v| Value::known(Fp::from(v))).to_vec(),
+        };
+        MockProver::run(6, &circuit, vec![])
+            .unwrap()
+            .assert_satisfied();
+    }
+
+    #[test]
+    fn test_out_of_range_rejected() {
+        let circuit = RangeCircuit {
+            values: vec![Value::known(Fp::from(16))],
+        };
+        assert!(MockProver::run(6, &circuit, vec![])
+            .unwrap()
+            .verify()
+            .is_err());
+    }
+}
@@ -0,0 +1,426 @@
+//! Schnorr signatures over the curve whose base field is the circuit field
+//!
+//! ECDSA verification divides by the response in the scalar field, which a
+//! circuit over the base field can only do with non-native arithmetic. A
+//! Schnorr signature checks a linear relation between points instead, so
+//! [`SchnorrChip`] verifies one with the native [`EccChip`]: for Pallas,
+//! signatures by Pallas keys in a circuit over `pallas::Base`.
+//!
+//! Scalars go through [`EccChip::mul_ladder`]'s map `k -> 2^n + 2k + 1`, so
+//! a signature `(R, s)` on `m` under `PK` is valid when
+//!
+//! `[2^252 + 2s + 1] G = R + [2^128 + 2e + 1] PK`
+//!
+//! where `e` is the low [`CHALLENGE_BITS`] bits of the Poseidon hash of
+//! `R`, `PK` and `m`, and `s` has at most [`RESPONSE_BITS`] bits. [`sign`]
+//! draws a fresh nonce until the response fits, about four times.
+
+use std::marker::PhantomData;
+
+use ff::{Field, PrimeField};
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+use super::{
+    ecc::affine, DecomposeChip, DecomposeConfig, EccChip, EccConfig, EccPoint, Gadget,
+    PoseidonChip, PoseidonConfig,
+};
+use crate::{
+    msm::msm,
+    poseidon::Poseidon,
+    rng::Randomness,
+    utils::bits::{fits_in_bits, from_le_bits, to_le_bits},
+};
+
+/// Bits of the challenge taken from the hash
+pub const CHALLENGE_BITS: usize = 128;
+
+/// Bits a signature's response may have
+///
+/// [`EccChip::mul_ladder`] needs the generator's order above
+/// `2^(RESPONSE_BITS + 2)`, which holds for both Pasta curves.
+pub const RESPONSE_BITS: usize = 252;
+
+/// Chip range-checking the part of the hash above [`CHALLENGE_BITS`] to
+/// 126 bits, so splitting it is unique
+type HighChip<F> = DecomposeChip<F, 9, 14>;
+
+/// Signature on a base field element
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SchnorrSignature<C: CurveAffine> {
+    /// Nonce commitment `R`
+    pub r: C,
+    /// Response `s`, below `2^RESPONSE_BITS`
+    pub s: C::Scalar,
+}
+
+/// `2^bits + 2k + 1`, the multiple [`EccChip::mul_ladder`] computes
+fn ladder_multiple<S: PrimeField>(k: S, bits: usize) -> S {
+    S::from(2).pow_vartime([bits as u64]) + k.double() + S::ONE
+}
+
+/// Public key of `secret_key`
+#[must_use]
+pub fn public_key<C: CurveAffine>(secret_key: C::Scalar) -> C {
+    (C::generator() * secret_key).into()
+}
+
+/// Challenge for nonce commitment `r` on `message` under `public_key`
+#[must_use]
+pub fn challenge<C: CurveAffine>(r: &C, public_key: &C, message: C::Base) -> C::Scalar {
+    let (rx, ry) = affine(r);
+    let (px, py) = affine(public_key);
+    let hash = Poseidon::default().hash(&[rx, ry, px, py, message]);
+    from_le_bits(&to_le_bits(&hash)[..CHALLENGE_BITS])
+}
+
+/// Sign `message` with `secret_key`
+#[must_use]
+pub fn sign<C: CurveAffine>(
+    secret_key: C::Scalar,
+    message: C::Base,
+    randomness: impl Randomness,
+) -> SchnorrSignature<C> {
+    let key = public_key::<C>(secret_key);
+    let mut rng = randomness.rng();
+    loop {
+        let nonce = C::Scalar::random(&mut rng);
+        let r = public_key::<C>(nonce);
+        let e = challenge(&r, &key, message);
+        // 2^252 + 2s + 1 = nonce + (2^128 + 2e + 1) secret_key
+        let s = (nonce + ladder_multiple(e, CHALLENGE_BITS) * secret_key
+            - ladder_multiple(C::Scalar::ZERO, RESPONSE_BITS))
+            * C::Scalar::TWO_INV;
+        if fits_in_bits(&s, RESPONSE_BITS) {
+            return SchnorrSignature { r, s };
+        }
+    }
+}
+
+/// Check `signature` on `message` under `public_key` natively, as
+/// [`SchnorrChip::verify`] does
+#[must_use]
+pub fn verify<C: CurveAffine>(
+    public_key: &C,
+    message: C::Base,
+    signature: &SchnorrSignature<C>,
+) -> bool {
+    if bool::from(public_key.is_identity() | signature.r.is_identity())
+        || !fits_in_bits(&signature.s, RESPONSE_BITS)
+    {
+        return false;
+    }
+    let e = challenge(&signature.r, public_key, message);
+    let lhs = C::generator() * ladder_multiple(signature.s, RESPONSE_BITS);
+    let rhs = msm(
+        &[C::Scalar::ONE, ladder_multiple(e, CHALLENGE_BITS)],
+        &[signature.r, *public_key],
+    );
+    lhs == rhs
+}
+
+/// Verifies Schnorr signatures by keys on `C` over `C::Base`
+#[derive(Clone, Debug)]
+pub struct SchnorrChip<C: CurveAffine> {
+    config: SchnorrConfig,
+    _marker: PhantomData<C>,
+}
+
+/// Chips and selector used by [`SchnorrChip`]
+#[derive(Clone, Debug)]
+pub struct SchnorrConfig {
+    /// Point arithmetic on the Poseidon state columns
+    pub ecc: EccConfig,
+    /// Challenge hash
+    pub poseidon: PoseidonConfig,
+    high: DecomposeConfig,
+    s_split: Selector,
+}
+
+impl<C: CurveAffine> SchnorrChip<C> {
+    /// Constrain `signature` to be valid on `message` under `public_key`
+    ///
+    /// Only the signature is witnessed here; the caller decides whether the
+    /// key and message are public.
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails.
+    pub fn verify(
+        &self,
+        mut layouter: impl Layouter<C::Base>,
+        public_key: &EccPoint<C::Base>,
+        message: &AssignedCell<C::Base, C::Base>,
+        signature: Value<SchnorrSignature<C>>,
+    ) -> Result<(), Error> {
+        let config = &self.config;
+        let ecc = EccChip::<C>::construct(config.ecc.clone());
+        let poseidon = PoseidonChip::construct(config.poseidon.clone());
+        let high = HighChip::construct(config.high.clone());
+
+        // The identity fails the on-curve check
+        let r = ecc.witness_point(layouter.namespace(|| "R"), signature.map(|sig| sig.r))?;
+        let s = layouter.assign_region(
+            || "response",
+            |mut region| {
+                region.assign_advice(
+                    || "s",
+                    config.ecc.lambda,
+                    0,
+                    || signature.map(|sig| from_le_bits::<C::Base>(&to_le_bits(&sig.s))),
+                )
+            },
+        )?;
+
+        let input = [&r.x, &r.y, &public_key.x, &public_key.y, message].map(Clone::clone);
+        let hash = poseidon.hash_many(layouter.namespace(|| "challenge hash"), &input)?;
+        let e = self.split(layouter.namespace(|| "challenge split"), &high, &hash)?;
+
+        let generator = ecc.constant_point(layouter.namespace(|| "G"), C::generator())?;
+        let lhs = ecc.mul_ladder(
+            layouter.namespace(|| "[s] G"),
+            &generator,
+            &s,
+            RESPONSE_BITS,
+        )?;
+        // R and [e] PK share an x-coordinate only if R = ±[e] PK, which the
+        // challenge, fixed by R, makes negligible
+        let e_pk = ecc.mul_ladder(
+            layouter.namespace(|| "[e] PK"),
+            public_key,
+            &e,
+            CHALLENGE_BITS,
+        )?;
+        let rhs = ecc.add(layouter.namespace(|| "R + [e] PK"), &r, &e_pk)?;
+
+        layouter.assign_region(
+            || "signature equation",
+            |mut region| {
+                region.constrain_equal(lhs.x.cell(), rhs.x.cell())?;
+                region.constrain_equal(lhs.y.cell(), rhs.y.cell())
+            },
+        )
+    }
+
+    /// Split `hash` into its low [`CHALLENGE_BITS`] bits, returned, and a
+    /// high part range-checked by `high`
+    fn split(
+        &self,
+        mut layouter: impl Layouter<C::Base>,
+        high: &HighChip<C::Base>,
+        hash: &AssignedCell<C::Base, C::Base>,
+    ) -> Result<AssignedCell<C::Base, C::Base>, Error> {
+        let config = &self.config;
+        let halves = hash.value().map(|hash| {
+            let bits = to_le_bits(hash);
+            let low: C::Base = from_le_bits(&bits[..CHALLENGE_BITS]);
+            let upper: C::Base = from_le_bits(&bits[CHALLENGE_BITS..]);
+            (low, upper)
+        });
+        let (low, upper) = layouter.assign_region(
+            || "challenge split",
+            |mut region| {
+                config.s_split.enable(&mut region, 0)?;
+                hash.copy_advice(|| "hash", &mut region, config.ecc.x, 0)?;
+                let low = region.assign_advice(
+                    || "challenge",
+                    config.ecc.y,
+                    0,
+                    || halves.map(|halves| halves.0),
+                )?;
+                let upper = region.assign_advice(
+                    || "high",
+                    config.ecc.lambda,
+                    0,
+                    || halves.map(|halves| halves.1),
+                )?;
+                Ok((low, upper))
+            },
+        )?;
+        high.decompose(layouter.namespace(|| "high range"), &upper)?;
+        Ok(low)
+    }
+}
+
+impl<C: CurveAffine> Gadget<C::Base> for SchnorrChip<C> {
+    type Config = SchnorrConfig;
+    /// Point and Poseidon state columns, partial S-box column and two sets
+    /// of round constants
+    type Columns = <PoseidonChip<C::Base> as Gadget<C::Base>>::Columns;
+    /// Public key, message and signature
+    type Input = (Value<C>, Value<C::Base>, Value<SchnorrSignature<C>>);
+    /// Public key and message cells, for the caller to expose
+    type Output = (EccPoint<C::Base>, AssignedCell<C::Base, C::Base>);
+
+    fn construct(config: SchnorrConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn config(&self) -> &SchnorrConfig {
+        &self.config
+    }
+
+    fn configure(meta: &mut ConstraintSystem<C::Base>, columns: Self::Columns) -> SchnorrConfig {
+        let (state, partial_sbox, _, _) = columns;
+        let ecc = EccChip::<C>::configure(meta, state);
+        let poseidon = PoseidonChip::configure(meta, columns);
+        let high = HighChip::configure(meta, partial_sbox);
+        let s_split = meta.selector();
+
+        // hash = e + 2^128 high
+        meta.create_gate("challenge split", |meta| {
+            let s = meta.query_selector(s_split);
+            let hash = meta.query_advice(ecc.x, Rotation::cur());
+            let low = meta.query_advice(ecc.y, Rotation::cur());
+            let high = meta.query_advice(ecc.lambda, Rotation::cur());
+            let shift = Expression::Constant(C::Base::from_u128(u128::MAX) + C::Base::ONE);
+            vec![s * (hash - low - high * shift)]
+        });
+
+        SchnorrConfig {
+            ecc,
+            poseidon,
+            high,
+            s_split,
+        }
+    }
+
+    fn load(&self, layouter: &mut impl Layouter<C::Base>) -> Result<(), Error> {
+        HighChip::construct(self.config.high.clone()).load(layouter)
+    }
+
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<C::Base>,
+        (public_key, message, signature): Self::Input,
+    ) -> Result<Self::Output, Error> {
+        let ecc = EccChip::<C>::construct(self.config.ecc.clone());
+        let public_key = ecc.witness_point(layouter.namespace(|| "public key"), public_key)?;
+        let message = layouter.assign_region(
+            || "message",
+            |mut region| region.assign_advice(|| "m", self.config.ecc.lambda, 0, || message),
+        )?;
+        self.verify(
+            layouter.namespace(|| "verify"),
+            &public_key,
+            &message,
+            signature,
+        )?;
+        Ok((public_key, message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        plonk::{Circuit, Column, Instance},
+    };
+    use pasta_curves::{pallas, Fp, Fq};
+
+    use super::*;
+    use crate::DeterministicRandomness;
+
+    /// Exposes the key's coordinates and the message
+    #[derive(Default)]
+    struct SignatureCircuit {
+        public_key: Value<pallas::Affine>,
+        message: Value<Fp>,
+        signature: Value<SchnorrSignature<pallas::Affine>>,
+    }
+
+    impl Circuit<Fp> for SignatureCircuit {
+        type Config = (SchnorrConfig, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let columns = (
+                [(); 3].map(|()| meta.advice_column()),
+                meta.advice_column(),
+                [(); 3].map(|()| meta.fixed_column()),
+                [(); 3].map(|()| meta.fixed_column()),
+            );
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (
+                SchnorrChip::<pallas::Affine>::configure(meta, columns),
+                instance,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = SchnorrChip::<pallas::Affine>::construct(config);
+            chip.load(&mut layouter)?;
+            let (public_key, message) = chip.assign(
+                layouter.namespace(|| "signature"),
+                (self.public_key, self.message, self.signature),
+            )?;
+            for (row, cell) in [public_key.x, public_key.y, message].iter().enumerate() {
+                layouter.constrain_instance(cell.cell(), instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn signed(message: u64) -> (pallas::Affine, SchnorrSignature<pallas::Affine>) {
+        let secret_key = Fq::from(0x5eed);
+        let randomness = DeterministicRandomness::from_seed([3; 32]);
+        let signature = sign(secret_key, Fp::from(message), &randomness);
+        (public_key(secret_key), signature)
+    }
+
+    fn instance(public_key: &pallas::Affine, message: u64) -> Vec<Fp> {
+        let (x, y) = affine(public_key);
+        vec![x, y, Fp::from(message)]
+    }
+
+    #[test]
+    fn test_sign_and_verify_natively() {
+        let (public_key, signature) = signed(7);
+        assert!(verify(&public_key, Fp::from(7), &signature));
+        assert!(!verify(&public_key, Fp::from(8), &signature));
+        let other_key = super::public_key::<pallas::Affine>(Fq::from(2));
+        assert!(!verify(&other_key, Fp::from(7), &signature));
+
+        let tampered = SchnorrSignature {
+            s: signature.s + Fq::ONE,
+            ..signature
+        };
+        assert!(!verify(&public_key, Fp::from(7), &tampered));
+    }
+
+    #[test]
+    fn test_circuit_accepts_only_the_signed_message() {
+        let (public_key, signature) = signed(7);
+        let circuit = SignatureCircuit {
+            public_key: Value::known(public_key),
+            message: Value::known(Fp::from(7)),
+            signature: Value::known(signature),
+        };
+        let prover = MockProver::run(13, &circuit, vec![instance(&public_key, 7)]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let forged = SignatureCircuit {
+            message: Value::known(Fp::from(8)),
+            ..circuit
+        };
+        let prover = MockProver::run(13, &forged, vec![instance(&public_key, 8)]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
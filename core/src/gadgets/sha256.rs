@@ -0,0 +1,1035 @@
+//! SHA-256 over a spread lookup table
+//!
+//! Words are split into pieces of at most [`MAX_PIECE_BITS`] bits, each
+//! looked up together with its *spread* form, which has a zero bit above
+//! every bit of the piece. Spread words add slot by slot without carries
+//! between slots, so the even bits of a sum of three are their XOR and the
+//! odd bits their majority; rotations and shifts only move pieces to other
+//! slots. Everything else is a linear relation laid out as a running sum, so
+//! besides the lookup the chip has a single gate.
+//!
+//! Ch is `(e & f) + (!e & g)`, whose halves share no bit, with the spread of
+//! `!e` taken as that of `0xffffffff` minus that of `e`. Additions look up
+//! their carry in the same table.
+
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Fixed, Selector, TableColumn},
+    poly::Rotation,
+};
+use sha2::{Digest, Sha256};
+
+use super::Gadget;
+use crate::utils::bits::to_windows;
+
+/// Widest piece the spread table holds
+pub const MAX_PIECE_BITS: u32 = 11;
+
+/// Rows of the spread table, one per value of every width up to
+/// [`MAX_PIECE_BITS`]
+pub const SPREAD_TABLE_ROWS: usize = (1 << (MAX_PIECE_BITS + 1)) - 1;
+
+/// Words in a digest
+pub const DIGEST_WORDS: usize = 8;
+
+/// Words in a message block
+const BLOCK_WORDS: usize = 16;
+
+/// Rounds of the compression function
+const ROUNDS: usize = 64;
+
+/// Width of the carry of a sum of at most eight words
+const CARRY_BITS: u32 = 3;
+
+/// Piece boundaries Σ0 rotates at
+const SIGMA0_CUTS: &[u32] = &[2, 13, 22];
+/// Piece boundaries Σ1 rotates at
+const SIGMA1_CUTS: &[u32] = &[6, 11, 22, 25];
+/// Piece boundaries σ0 and σ1 rotate and shift at
+const SCHEDULE_CUTS: &[u32] = &[3, 7, 10, 17, 18, 19, 26];
+/// Piece boundaries of a word that is never rotated
+const PLAIN_CUTS: &[u32] = &[11, 22];
+
+/// Initial hash value, FIPS 180-4 section 5.3.3
+const IV: [u32; DIGEST_WORDS] = [
+    0x6a09_e667,
+    0xbb67_ae85,
+    0x3c6e_f372,
+    0xa54f_f53a,
+    0x510e_527f,
+    0x9b05_688c,
+    0x1f83_d9ab,
+    0x5be0_cd19,
+];
+
+/// Round constants, FIPS 180-4 section 4.2.2
+const K: [u32; ROUNDS] = [
+    0x428a_2f98,
+    0x7137_4491,
+    0xb5c0_fbcf,
+    0xe9b5_dba5,
+    0x3956_c25b,
+    0x59f1_11f1,
+    0x923f_82a4,
+    0xab1c_5ed5,
+    0xd807_aa98,
+    0x1283_5b01,
+    0x2431_85be,
+    0x550c_7dc3,
+    0x72be_5d74,
+    0x80de_b1fe,
+    0x9bdc_06a7,
+    0xc19b_f174,
+    0xe49b_69c1,
+    0xefbe_4786,
+    0x0fc1_9dc6,
+    0x240c_a1cc,
+    0x2de9_2c6f,
+    0x4a74_84aa,
+    0x5cb0_a9dc,
+    0x76f9_88da,
+    0x983e_5152,
+    0xa831_c66d,
+    0xb003_27c8,
+    0xbf59_7fc7,
+    0xc6e0_0bf3,
+    0xd5a7_9147,
+    0x06ca_6351,
+    0x1429_2967,
+    0x27b7_0a85,
+    0x2e1b_2138,
+    0x4d2c_6dfc,
+    0x5338_0d13,
+    0x650a_7354,
+    0x766a_0abb,
+    0x81c2_c92e,
+    0x9272_2c85,
+    0xa2bf_e8a1,
+    0xa81a_664b,
+    0xc24b_8b70,
+    0xc76c_51a3,
+    0xd192_e819,
+    0xd699_0624,
+    0xf40e_3585,
+    0x106a_a070,
+    0x19a4_c116,
+    0x1e37_6c08,
+    0x2748_774c,
+    0x34b0_bcb5,
+    0x391c_0cb3,
+    0x4ed8_aa4a,
+    0x5b9c_ca4f,
+    0x682e_6ff3,
+    0x748f_82ee,
+    0x78a5_636f,
+    0x84c8_7814,
+    0x8cc7_0208,
+    0x90be_fffa,
+    0xa450_6ceb,
+    0xbef9_a3f7,
+    0xc671_78f2,
+];
+
+/// Rotation or shift applied to a word before it is summed
+#[derive(Clone, Copy, Debug)]
+enum Shift {
+    Rotr(u32),
+    Shr(u32),
+}
+
+use Shift::{Rotr, Shr};
+
+const BIG_SIGMA0: &[Shift] = &[Rotr(2), Rotr(13), Rotr(22)];
+const BIG_SIGMA1: &[Shift] = &[Rotr(6), Rotr(11), Rotr(25)];
+const SMALL_SIGMA0: &[Shift] = &[Rotr(7), Rotr(18), Shr(3)];
+const SMALL_SIGMA1: &[Shift] = &[Rotr(17), Rotr(19), Shr(10)];
+const IDENTITY: &[Shift] = &[Rotr(0)];
+
+impl Shift {
+    fn apply(self, word: u32) -> u32 {
+        match self {
+            Rotr(r) => word.rotate_right(r),
+            Shr(r) => word >> r,
+        }
+    }
+
+    /// Where the bit at `offset` lands, if it is not shifted out
+    fn landing(self, offset: u32) -> Option<u32> {
+        match self {
+            Rotr(r) => Some((offset + 32 - r) % 32),
+            Shr(r) => offset.checked_sub(r),
+        }
+    }
+
+    /// Boundary a word must be cut at for its pieces to move whole
+    fn cut(self) -> u32 {
+        match self {
+            Rotr(r) | Shr(r) => r,
+        }
+    }
+}
+
+/// XOR of `word` under every shift in `shifts`
+fn xor_shifted(word: u32, shifts: &[Shift]) -> u32 {
+    shifts.iter().fold(0, |acc, shift| acc ^ shift.apply(word))
+}
+
+/// Interleave a zero bit above every bit of `word`
+#[must_use]
+pub fn spread(word: u32) -> u64 {
+    (0..32).fold(0, |acc, i| acc | (u64::from((word >> i) & 1) << (2 * i)))
+}
+
+/// Bits at the even positions of `value`; inverts [`spread`]
+#[must_use]
+pub fn even_bits(value: u64) -> u32 {
+    (0..32).fold(0, |acc, i| acc | ((low_word(value >> (2 * i)) & 1) << i))
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn low_word(value: u64) -> u32 {
+    value as u32
+}
+
+/// Message schedule of one block, computed natively
+fn expand(block: &[u32]) -> Vec<u32> {
+    let mut w = block.to_vec();
+    for t in BLOCK_WORDS..ROUNDS {
+        let s0 = xor_shifted(w[t - 15], SMALL_SIGMA0);
+        let s1 = xor_shifted(w[t - 2], SMALL_SIGMA1);
+        w.push(
+            s1.wrapping_add(w[t - 7])
+                .wrapping_add(s0)
+                .wrapping_add(w[t - 16]),
+        );
+    }
+    w
+}
+
+/// `2^exponent` in `F`
+fn pow2<F: PrimeField>(exponent: u32) -> F {
+    F::from(2).pow_vartime([u64::from(exponent)])
+}
+
+/// Cuts of the `i`th state word, matching the rotation it next goes through
+fn state_cuts(i: usize) -> &'static [u32] {
+    if i < 4 {
+        SIGMA0_CUTS
+    } else {
+        SIGMA1_CUTS
+    }
+}
+
+/// SHA-256 of `left || right`, the native counterpart of
+/// [`Sha256Chip::hash_pair`] on big-endian words
+#[must_use]
+pub fn sha256_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// `bytes` read as a big-endian integer reduced into `F`, as
+/// [`Sha256Chip::pack`] does with words
+#[must_use]
+pub fn pack_bytes<F: PrimeField>(bytes: &[u8]) -> F {
+    bytes.iter().fold(F::ZERO, |acc, byte| {
+        acc * F::from(256) + F::from(u64::from(*byte))
+    })
+}
+
+/// One looked-up piece of a [`Sha256Word`]
+#[derive(Clone, Debug)]
+struct Piece<F: PrimeField> {
+    offset: u32,
+    value: Value<u32>,
+    dense: AssignedCell<F, F>,
+    spread: AssignedCell<F, F>,
+}
+
+/// 32-bit word held as range-checked pieces, least significant first
+#[derive(Clone, Debug)]
+pub struct Sha256Word<F: PrimeField> {
+    pieces: Vec<Piece<F>>,
+}
+
+impl<F: PrimeField> Sha256Word<F> {
+    /// The word's value
+    #[must_use]
+    pub fn value(&self) -> Value<u32> {
+        self.pieces.iter().fold(Value::known(0), |word, piece| {
+            word.zip(piece.value)
+                .map(|(word, value)| word | (value << piece.offset))
+        })
+    }
+
+    /// Whether a piece starts at every one of `cuts`
+    fn is_cut_at(&self, cuts: impl IntoIterator<Item = u32>) -> bool {
+        cuts.into_iter()
+            .all(|cut| cut == 0 || self.pieces.iter().any(|piece| piece.offset == cut))
+    }
+
+    /// Terms summing to `factor` times the word
+    fn dense_terms(&self, factor: F) -> Vec<Term<F>> {
+        self.pieces
+            .iter()
+            .map(|piece| Term::Cell(piece.dense.clone(), factor * pow2::<F>(piece.offset)))
+            .collect()
+    }
+
+    /// Terms summing to `factor` times the spread of the word under `shifts`
+    fn spread_terms(&self, shifts: &[Shift], factor: F) -> Vec<Term<F>> {
+        self.pieces
+            .iter()
+            .filter_map(|piece| {
+                let coef = shifts
+                    .iter()
+                    .filter_map(|shift| shift.landing(piece.offset))
+                    .map(|slot| pow2::<F>(2 * slot))
+                    .fold(F::ZERO, |acc, coef| acc + coef);
+                (coef != F::ZERO).then(|| Term::Cell(piece.spread.clone(), factor * coef))
+            })
+            .collect()
+    }
+}
+
+/// Term of a relation laid out by [`Sha256Chip`]
+enum Term<F: PrimeField> {
+    /// Existing cell times a coefficient
+    Cell(AssignedCell<F, F>, F),
+    /// Fresh witness times a coefficient, handed back to the caller
+    Witness(Value<F>, F),
+}
+
+/// Computes SHA-256 of whole-word messages
+///
+/// Words come in and go out as [`Sha256Word`]s; [`Sha256Chip::unpack`] and
+/// [`Sha256Chip::pack`] convert them from and to field elements. A
+/// compression takes about 12,600 rows and the table [`SPREAD_TABLE_ROWS`],
+/// so a single block needs `k = 14`.
+#[derive(Clone, Debug)]
+pub struct Sha256Chip<F: PrimeField> {
+    config: Sha256Config,
+    _marker: PhantomData<F>,
+}
+
+/// Columns, selectors and table used by [`Sha256Chip`]
+#[derive(Clone, Debug)]
+pub struct Sha256Config {
+    /// Looked-up pieces
+    pub dense: Column<Advice>,
+    /// Spread form of each piece
+    pub spread: Column<Advice>,
+    /// Terms of linear relations
+    pub term: Column<Advice>,
+    acc: Column<Advice>,
+    tag: Column<Fixed>,
+    coef: Column<Fixed>,
+    offset: Column<Fixed>,
+    q_lookup: Selector,
+    q_sum: Selector,
+    q_zero: Selector,
+    table: [TableColumn; 3],
+}
+
+impl<F: PrimeField> Sha256Chip<F> {
+    /// Witness a message word
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails.
+    pub fn witness(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<u32>,
+    ) -> Result<Sha256Word<F>, Error> {
+        self.split(&mut layouter, value, SCHEDULE_CUTS)
+    }
+
+    /// Digest of `message`, padded as FIPS 180-4 specifies
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails.
+    pub fn digest(
+        &self,
+        mut layouter: impl Layouter<F>,
+        message: &[Sha256Word<F>],
+    ) -> Result<[Sha256Word<F>; DIGEST_WORDS], Error> {
+        let blocks = (message.len() + 2) / BLOCK_WORDS + 1;
+        let mut padding = vec![0; blocks * BLOCK_WORDS - message.len()];
+        let bit_len = 32 * message.len() as u64;
+        padding[0] = 0x8000_0000;
+        let last = padding.len() - 1;
+        padding[last - 1] = low_word(bit_len >> 32);
+        padding[last] = low_word(bit_len);
+
+        let mut state = IV
+            .iter()
+            .enumerate()
+            .map(|(i, word)| self.constant(&mut layouter, *word, state_cuts(i)))
+            .collect::<Result<Vec<_>, _>>()?;
+        for block in 0..blocks {
+            let mut layouter = layouter.namespace(|| crate::annotation!("block {}", block));
+            let start = block * BLOCK_WORDS;
+            let schedule = if start >= message.len() {
+                let words = &padding[start - message.len()..][..BLOCK_WORDS];
+                expand(words)
+                    .into_iter()
+                    .map(|word| self.constant(&mut layouter, word, PLAIN_CUTS))
+                    .collect::<Result<Vec<_>, _>>()?
+            } else {
+                let words = (start..start + BLOCK_WORDS)
+                    .map(|i| match message.get(i) {
+                        Some(word) => Ok(word.clone()),
+                        None => {
+                            self.constant(&mut layouter, padding[i - message.len()], SCHEDULE_CUTS)
+                        }
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.schedule(&mut layouter, &words)?
+            };
+            state = self.compress(&mut layouter, &state, &schedule)?;
+        }
+        state.try_into().map_err(|_| Error::Synthesis)
+    }
+
+    /// Digest of the 64-byte message `left || right`
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails.
+    pub fn hash_pair(
+        &self,
+        layouter: impl Layouter<F>,
+        left: &[Sha256Word<F>; DIGEST_WORDS],
+        right: &[Sha256Word<F>; DIGEST_WORDS],
+    ) -> Result<[Sha256Word<F>; DIGEST_WORDS], Error> {
+        let message: Vec<_> = left.iter().chain(right).cloned().collect();
+        self.digest(layouter, &message)
+    }
+
+    /// Field element the big-endian `words` encode, reduced into `F`
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails.
+    pub fn pack(
+        &self,
+        mut layouter: impl Layouter<F>,
+        words: &[Sha256Word<F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let value = words.iter().fold(Value::known(F::ZERO), |acc, word| {
+            acc.zip(word.value())
+                .map(|(acc, word)| acc * pow2::<F>(32) + F::from(u64::from(word)))
+        });
+        let mut terms = self.positional_terms(words);
+        terms.push(Term::Witness(value, -F::ONE));
+        let mut witnessed = self.constrain(&mut layouter, &terms, F::ZERO)?;
+        witnessed.pop().ok_or(Error::Synthesis)
+    }
+
+    /// Split `cell` into `count` big-endian words
+    ///
+    /// The value must be below `2^(32 * count)`. When that bound exceeds the
+    /// field modulus, a value also splits as itself plus the modulus if that
+    /// still fits, so the words are not unique.
+    ///
+    /// # Errors
+    ///
+    /// Returns the layouter's error if region assignment fails.
+    pub fn unpack(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        count: usize,
+    ) -> Result<Vec<Sha256Word<F>>, Error> {
+        let windows = cell.value().map(|value| to_windows(value, 32));
+        let words = (0..count)
+            .map(|i| {
+                let value = windows.as_ref().map(|windows| {
+                    low_word(windows.get(count - 1 - i).copied().unwrap_or_default())
+                });
+                self.split(&mut layouter, value, SCHEDULE_CUTS)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut terms = self.positional_terms(&words);
+        terms.push(Term::Cell(cell.clone(), -F::ONE));
+        self.constrain(&mut layouter, &terms, F::ZERO)?;
+        Ok(words)
+    }
+
+    /// Terms of `words` weighted as big-endian 32-bit digits
+    fn positional_terms(&self, words: &[Sha256Word<F>]) -> Vec<Term<F>> {
+        words
+            .iter()
+            .rev()
+            .zip(0u32..)
+            .flat_map(|(word, i)| word.dense_terms(pow2(32 * i)))
+            .collect()
+    }
+
+    /// Message schedule of `block`
+    fn schedule(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        block: &[Sha256Word<F>],
+    ) -> Result<Vec<Sha256Word<F>>, Error> {
+        let mut w = block
+            .iter()
+            .map(|word| self.with_cuts(layouter, word, SCHEDULE_CUTS))
+            .collect::<Result<Vec<_>, _>>()?;
+        for t in BLOCK_WORDS..ROUNDS {
+            let (s0, _) = self.spread_sum(layouter, &[(&w[t - 15], SMALL_SIGMA0, false)], 0)?;
+            let (s1, _) = self.spread_sum(layouter, &[(&w[t - 2], SMALL_SIGMA1, false)], 0)?;
+            let next = self.add(
+                layouter,
+                &[&s1, &w[t - 7], &s0, &w[t - 16]],
+                0,
+                SCHEDULE_CUTS,
+            )?;
+            w.push(next);
+        }
+        Ok(w)
+    }
+
+    /// Compress one scheduled block into `state`
+    fn compress(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        state: &[Sha256Word<F>],
+        schedule: &[Sha256Word<F>],
+    ) -> Result<Vec<Sha256Word<F>>, Error> {
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h]: [Sha256Word<F>; 8] =
+            state.to_vec().try_into().map_err(|_| Error::Synthesis)?;
+        for (t, w) in schedule.iter().enumerate() {
+            let mut layouter = layouter.namespace(|| crate::annotation!("round {}", t));
+            let layouter = &mut layouter;
+            let (s1, _) = self.spread_sum(layouter, &[(&e, BIG_SIGMA1, false)], 0)?;
+            let (_, e_and_f) =
+                self.spread_sum(layouter, &[(&e, IDENTITY, false), (&f, IDENTITY, false)], 0)?;
+            let (_, not_e_and_g) = self.spread_sum(
+                layouter,
+                &[(&e, IDENTITY, true), (&g, IDENTITY, false)],
+                spread(u32::MAX),
+            )?;
+            let (s0, _) = self.spread_sum(layouter, &[(&a, BIG_SIGMA0, false)], 0)?;
+            let (_, maj) = self.spread_sum(
+                layouter,
+                &[
+                    (&a, IDENTITY, false),
+                    (&b, IDENTITY, false),
+                    (&c, IDENTITY, false),
+                ],
+                0,
+            )?;
+            // T1 goes into both e and a, so its words are summed twice
+            // rather than through an intermediate word
+            let new_e = self.add(
+                layouter,
+                &[&d, &h, &s1, &e_and_f, &not_e_and_g, w],
+                K[t],
+                SIGMA1_CUTS,
+            )?;
+            let new_a = self.add(
+                layouter,
+                &[&h, &s1, &e_and_f, &not_e_and_g, w, &s0, &maj],
+                K[t],
+                SIGMA0_CUTS,
+            )?;
+            (h, g, f, e) = (g, f, e, new_e);
+            (d, c, b, a) = (c, b, a, new_a);
+        }
+        let working = [a, b, c, d, e, f, g, h];
+        state
+            .iter()
+            .zip(&working)
+            .enumerate()
+            .map(|(i, (initial, word))| self.add(layouter, &[initial, word], 0, state_cuts(i)))
+            .collect()
+    }
+
+    /// Even and odd words of the slot-wise sum of spread `inputs`
+    ///
+    /// Each input is a word, the shifts whose results it contributes and
+    /// whether it is subtracted; `constant` is added in spread form. Every
+    /// slot of the sum must lie in `0..4`.
+    fn spread_sum(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        inputs: &[(&Sha256Word<F>, &[Shift], bool)],
+        constant: u64,
+    ) -> Result<(Sha256Word<F>, Sha256Word<F>), Error> {
+        debug_assert!(inputs
+            .iter()
+            .all(|(word, shifts, _)| word.is_cut_at(shifts.iter().map(|shift| shift.cut()))));
+        let sum = inputs.iter().fold(
+            Value::known(i128::from(constant)),
+            |acc, (word, shifts, negate)| {
+                acc.zip(word.value()).map(|(acc, value)| {
+                    let spread: i128 = shifts
+                        .iter()
+                        .map(|shift| i128::from(spread(shift.apply(value))))
+                        .sum();
+                    if *negate {
+                        acc - spread
+                    } else {
+                        acc + spread
+                    }
+                })
+            },
+        );
+        let sum = sum.map(|sum| u64::try_from(sum).unwrap_or_default());
+        let even = self.split(layouter, sum.map(even_bits), PLAIN_CUTS)?;
+        let odd = self.split(layouter, sum.map(|sum| even_bits(sum >> 1)), PLAIN_CUTS)?;
+
+        let mut terms = Vec::new();
+        for (word, shifts, negate) in inputs {
+            let factor = if *negate { -F::ONE } else { F::ONE };
+            terms.extend(word.spread_terms(shifts, factor));
+        }
+        terms.extend(even.spread_terms(IDENTITY, -F::ONE));
+        terms.extend(odd.spread_terms(IDENTITY, -F::from(2)));
+        self.constrain(layouter, &terms, F::from(constant))?;
+        Ok((even, odd))
+    }
+
+    /// Sum of `words` and `constant` modulo `2^32`, cut at `cuts`
+    fn add(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        words: &[&Sha256Word<F>],
+        constant: u32,
+        cuts: &[u32],
+    ) -> Result<Sha256Word<F>, Error> {
+        debug_assert!(words.len() < 1 << CARRY_BITS);
+        let sum = words
+            .iter()
+            .fold(Value::known(u64::from(constant)), |acc, word| {
+                acc.zip(word.value())
+                    .map(|(acc, word)| acc + u64::from(word))
+            });
+        let out = self.split(layouter, sum.map(low_word), cuts)?;
+        let carry = self.lookup(
+            layouter,
+            &[(CARRY_BITS, sum.map(|sum| low_word(sum >> 32)))],
+        )?;
+
+        let mut terms: Vec<_> = words
+            .iter()
+            .flat_map(|word| word.dense_terms(F::ONE))
+            .collect();
+        terms.extend(out.dense_terms(-F::ONE));
+        terms.extend(
+            carry
+                .into_iter()
+                .map(|(dense, _)| Term::Cell(dense, -pow2::<F>(32))),
+        );
+        self.constrain(layouter, &terms, F::from(u64::from(constant)))?;
+        Ok(out)
+    }
+
+    /// `word`, split again if a piece straddles one of `cuts`
+    fn with_cuts(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        word: &Sha256Word<F>,
+        cuts: &[u32],
+    ) -> Result<Sha256Word<F>, Error> {
+        if word.is_cut_at(cuts.iter().copied()) {
+            return Ok(word.clone());
+        }
+        let out = self.split(layouter, word.value(), cuts)?;
+        let mut terms = word.dense_terms(F::ONE);
+        terms.extend(out.dense_terms(-F::ONE));
+        self.constrain(layouter, &terms, F::ZERO)?;
+        Ok(out)
+    }
+
+    /// Word fixed to `value`, cut at `cuts`
+    fn constant(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        value: u32,
+        cuts: &[u32],
+    ) -> Result<Sha256Word<F>, Error> {
+        let word = self.split(layouter, Value::known(value), cuts)?;
+        self.constrain(
+            layouter,
+            &word.dense_terms(F::ONE),
+            -F::from(u64::from(value)),
+        )?;
+        Ok(word)
+    }
+
+    /// Look up `value` as pieces bounded by `cuts`
+    ///
+    /// Nothing ties the pieces to any other cell; callers relate them.
+    fn split(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        value: Value<u32>,
+        cuts: &[u32],
+    ) -> Result<Sha256Word<F>, Error> {
+        let bounds: Vec<(u32, u32)> = std::iter::once(0)
+            .chain(cuts.iter().copied())
+            .zip(cuts.iter().copied().chain(std::iter::once(32)))
+            .map(|(start, end)| (start, end - start))
+            .collect();
+        let values: Vec<_> = bounds
+            .iter()
+            .map(|&(offset, bits)| {
+                let piece = value.map(|value| (value >> offset) & ((1 << bits) - 1));
+                (bits, piece)
+            })
+            .collect();
+        let cells = self.lookup(layouter, &values)?;
+        let pieces = values
+            .into_iter()
+            .zip(bounds)
+            .zip(cells)
+            .map(|(((_, value), (offset, _)), (dense, spread))| Piece {
+                offset,
+                value,
+                dense,
+                spread,
+            })
+            .collect();
+        Ok(Sha256Word { pieces })
+    }
+
+    /// Look up each `(bits, value)` and return its dense and spread cells
+    fn lookup(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        pieces: &[(u32, Value<u32>)],
+    ) -> Result<Vec<(AssignedCell<F, F>, AssignedCell<F, F>)>, Error> {
+        layouter.assign_region(
+            || "sha256 pieces",
+            |mut region| {
+                pieces
+                    .iter()
+                    .enumerate()
+                    .map(|(row, (bits, value))| -> Result<_, Error> {
+                        self.config.q_lookup.enable(&mut region, row)?;
+                        region.assign_fixed(
+                            || "bits",
+                            self.config.tag,
+                            row,
+                            || Value::known(F::from(u64::from(*bits))),
+                        )?;
+                        let dense = region.assign_advice(
+                            || "dense",
+                            self.config.dense,
+                            row,
+                            || value.map(|value| F::from(u64::from(value))),
+                        )?;
+                        let spread = region.assign_advice(
+                            || "spread",
+                            self.config.spread,
+                            row,
+                            || value.map(|value| F::from(spread(value))),
+                        )?;
+                        Ok((dense, spread))
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    /// Constrain `constant` plus the weighted `terms` to zero
+    ///
+    /// Returns the cells of [`Term::Witness`] terms, in order.
+    fn constrain(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        terms: &[Term<F>],
+        constant: F,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let config = &self.config;
+        layouter.assign_region(
+            || "sha256 relation",
+            |mut region| {
+                let mut acc = Value::known(F::ZERO);
+                let mut witnessed = Vec::new();
+                for (row, term) in terms.iter().enumerate() {
+                    let offset = if row == 0 { constant } else { F::ZERO };
+                    config.q_sum.enable(&mut region, row)?;
+                    region.assign_advice(|| "acc", config.acc, row, || acc)?;
+                    region.assign_fixed(
+                        || "offset",
+                        config.offset,
+                        row,
+                        || Value::known(offset),
+                    )?;
+                    let (value, coef) = match term {
+                        Term::Cell(cell, coef) => {
+                            cell.copy_advice(|| "term", &mut region, config.term, row)?;
+                            (cell.value().copied(), *coef)
+                        }
+                        Term::Witness(value, coef) => {
+                            let cell =
+                                region.assign_advice(|| "term", config.term, row, || *value)?;
+                            witnessed.push(cell);
+                            (*value, *coef)
+                        }
+                    };
+                    region.assign_fixed(|| "coef", config.coef, row, || Value::known(coef))?;
+                    acc = acc + value * Value::known(coef) + Value::known(offset);
+                }
+                config.q_zero.enable(&mut region, 0)?;
+                config.q_zero.enable(&mut region, terms.len())?;
+                region.assign_advice(|| "acc", config.acc, terms.len(), || acc)?;
+                Ok(witnessed)
+            },
+        )
+    }
+}
+
+impl<F: PrimeField> Gadget<F> for Sha256Chip<F> {
+    type Config = Sha256Config;
+    /// Dense, spread, term and running-sum advice; width, coefficient and
+    /// offset fixed columns
+    type Columns = ([Column<Advice>; 4], [Column<Fixed>; 3]);
+    type Input = Vec<Value<u32>>;
+    type Output = [Sha256Word<F>; DIGEST_WORDS];
+
+    fn construct(config: Sha256Config) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn config(&self) -> &Sha256Config {
+        &self.config
+    }
+
+    fn configure(
+        meta: &mut ConstraintSystem<F>,
+        ([dense, spread, term, acc], [tag, coef, offset]): Self::Columns,
+    ) -> Sha256Config {
+        let q_lookup = meta.complex_selector();
+        let q_sum = meta.selector();
+        let q_zero = meta.selector();
+        let table = [(); 3].map(|()| meta.lookup_table_column());
+        for column in [dense, spread, term] {
+            meta.enable_equality(column);
+        }
+
+        meta.lookup(|meta| {
+            let q_lookup = meta.query_selector(q_lookup);
+            let tag = meta.query_fixed(tag);
+            let dense = meta.query_advice(dense, Rotation::cur());
+            let spread = meta.query_advice(spread, Rotation::cur());
+            vec![
+                (q_lookup.clone() * tag, table[0]),
+                (q_lookup.clone() * dense, table[1]),
+                (q_lookup * spread, table[2]),
+            ]
+        });
+
+        meta.create_gate("sha256 linear relation", |meta| {
+            let q_sum = meta.query_selector(q_sum);
+            let q_zero = meta.query_selector(q_zero);
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_next = meta.query_advice(acc, Rotation::next());
+            let term = meta.query_advice(term, Rotation::cur());
+            let coef = meta.query_fixed(coef);
+            let offset = meta.query_fixed(offset);
+            vec![
+                q_sum * (acc_next - acc_cur.clone() - coef * term - offset),
+                q_zero * acc_cur,
+            ]
+        });
+
+        Sha256Config {
+            dense,
+            spread,
+            term,
+            acc,
+            tag,
+            coef,
+            offset,
+            q_lookup,
+            q_sum,
+            q_zero,
+            table,
+        }
+    }
+
+    fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "sha256 spread table",
+            |mut table| {
+                let mut row = 0;
+                for bits in 0..=MAX_PIECE_BITS {
+                    for value in 0..1u32 << bits {
+                        let cells = [u64::from(bits), u64::from(value), spread(value)];
+                        for (column, cell) in self.config.table.iter().zip(cells) {
+                            table.assign_cell(
+                                || "spread",
+                                *column,
+                                row,
+                                || Value::known(F::from(cell)),
+                            )?;
+                        }
+                        row += 1;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        message: Vec<Value<u32>>,
+    ) -> Result<[Sha256Word<F>; DIGEST_WORDS], Error> {
+        let words = message
+            .into_iter()
+            .map(|value| self.split(&mut layouter, value, SCHEDULE_CUTS))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.digest(layouter, &words)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        plonk::{Circuit, Instance},
+    };
+    use pasta_curves::Fp;
+
+    use super::*;
+
+    fn columns(meta: &mut ConstraintSystem<Fp>) -> ([Column<Advice>; 4], [Column<Fixed>; 3]) {
+        (
+            [(); 4].map(|()| meta.advice_column()),
+            [(); 3].map(|()| meta.fixed_column()),
+        )
+    }
+
+    /// Exposes the packed digest of `message` as its only public input
+    #[derive(Default)]
+    struct DigestCircuit {
+        message: Vec<Value<u32>>,
+    }
+
+    impl Circuit<Fp> for DigestCircuit {
+        type Config = (Sha256Config, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                message: vec![Value::unknown(); self.message.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let columns = columns(meta);
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (Sha256Chip::configure(meta, columns), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = Sha256Chip::construct(config);
+            chip.load(&mut layouter)?;
+            let digest = chip.assign(layouter.namespace(|| "digest"), self.message.clone())?;
+            let packed = chip.pack(layouter.namespace(|| "pack"), &digest)?;
+            layouter.constrain_instance(packed.cell(), instance, 0)
+        }
+    }
+
+    /// Looks up one piece of the given width
+    #[derive(Clone, Copy)]
+    struct PieceCircuit {
+        bits: u32,
+        value: u32,
+    }
+
+    impl Circuit<Fp> for PieceCircuit {
+        type Config = Sha256Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            *self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Sha256Config {
+            let columns = columns(meta);
+            Sha256Chip::configure(meta, columns)
+        }
+
+        fn synthesize(
+            &self,
+            config: Sha256Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = Sha256Chip::construct(config);
+            chip.load(&mut layouter)?;
+            chip.lookup(&mut layouter, &[(self.bits, Value::known(self.value))])?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_spread() {
+        assert_eq!(spread(0b1011), 0b0100_0101);
+        assert_eq!(spread(u32::MAX), 0x5555_5555_5555_5555);
+        for word in [0, 1, 0xdead_beef, u32::MAX] {
+            assert_eq!(even_bits(spread(word)), word);
+            assert_eq!(even_bits(spread(word) << 1), 0);
+        }
+    }
+
+    #[test]
+    fn test_digest_matches_sha2() {
+        let message: Vec<u32> = (0..16u32).map(|i| i.wrapping_mul(0x9e37_79b9)).collect();
+        let bytes: Vec<u8> = message.iter().flat_map(|word| word.to_be_bytes()).collect();
+        let expected: Fp = pack_bytes(&Sha256::digest(&bytes));
+        let (left, right) = bytes.split_at(32);
+        assert_eq!(
+            pack_bytes::<Fp>(&sha256_pair(
+                left.try_into().unwrap(),
+                right.try_into().unwrap()
+            )),
+            expected
+        );
+
+        let circuit = DigestCircuit {
+            message: message.into_iter().map(Value::known).collect(),
+        };
+        let prover = MockProver::run(15, &circuit, vec![vec![expected]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        let prover = MockProver::run(15, &circuit, vec![vec![expected + Fp::ONE]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_piece_width_enforced() {
+        let verify = |bits, value| {
+            MockProver::run(13, &PieceCircuit { bits, value }, vec![])
+                .unwrap()
+                .verify()
+                .is_ok()
+        };
+        assert!(verify(2, 3));
+        assert!(verify(MAX_PIECE_BITS, (1 << MAX_PIECE_BITS) - 1));
+        assert!(!verify(2, 5));
+        assert!(!verify(MAX_PIECE_BITS, 1 << MAX_PIECE_BITS));
+    }
+}
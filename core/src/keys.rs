@@ -0,0 +1,361 @@
+//! Proving and verifying key management
+//!
+//! A [`KeyStore`] hands out proving keys per circuit, generating each one
+//! once per process and sharing it behind an [`Arc`]. Keys are identified by
+//! a [`CircuitHash`] of the circuit's canonical verifying key, which covers
+//! the domain, the constraint system and the fixed-column commitments, so
+//! two circuits share a key only if halo2 would treat them as the same
+//! circuit.
+//!
+//! With an [`ArtifactStore`] behind it the store also persists what halo2
+//! 0.3 can read back: params are loaded from disk instead of regenerated,
+//! and each verifying key is written in its versioned canonical encoding
+//! for verifiers and audits. halo2 0.3 cannot deserialize keys, so the
+//! proving key itself is still rebuilt from the circuit once per process;
+//! a stored verifying key is checked against the rebuilt one, which catches
+//! a store written by a different circuit or format version.
+//!
+//! The key file layout is:
+//!
+//! ```text
+//! version: u16 LE || canonical verifying key
+//! ```
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use ff::FromUniformBytes;
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    plonk::{keygen_pk, keygen_vk, Circuit, ProvingKey, VerifyingKey},
+    poly::commitment::Params,
+};
+
+use crate::{
+    artifacts::{ArtifactStore, CircuitHash, KeyKind},
+    error::{Error, Result},
+    vk::{canonical_vk_bytes, check_canonical_vk},
+};
+
+/// Current version of the verifying key file encoding
+pub const KEY_FORMAT_VERSION: u16 = 1;
+
+/// Oldest verifying key file version that can still be read
+pub const MIN_KEY_FORMAT_VERSION: u16 = 1;
+
+/// Circuit hash of the circuit `vk` was generated for
+#[must_use]
+pub fn circuit_hash<C: CurveAffine>(vk: &VerifyingKey<C>) -> CircuitHash {
+    CircuitHash::of(&canonical_vk_bytes(vk))
+}
+
+/// Versioned encoding of `vk`
+#[must_use]
+pub fn encode_verifying_key<C: CurveAffine>(vk: &VerifyingKey<C>) -> Vec<u8> {
+    let canonical = canonical_vk_bytes(vk);
+    let mut bytes = Vec::with_capacity(2 + canonical.len());
+    bytes.extend_from_slice(&KEY_FORMAT_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&canonical);
+    bytes
+}
+
+/// Check that `bytes` is a versioned encoding of `vk`
+///
+/// # Errors
+///
+/// Returns [`Error::Encoding`] if `bytes` is truncated or of an unsupported
+/// version, and [`Error::VkMismatch`] if it encodes a different key.
+pub fn check_verifying_key<C: CurveAffine>(vk: &VerifyingKey<C>, bytes: &[u8]) -> Result<()> {
+    let (version, canonical) = bytes
+        .split_first_chunk::<2>()
+        .ok_or_else(|| Error::Encoding("truncated verifying key file".into()))?;
+    let version = u16::from_le_bytes(*version);
+    if !(MIN_KEY_FORMAT_VERSION..=KEY_FORMAT_VERSION).contains(&version) {
+        return Err(Error::Encoding(format!(
+            "unsupported key format version {version}, \
+             supported {MIN_KEY_FORMAT_VERSION}..={KEY_FORMAT_VERSION}"
+        )));
+    }
+    check_canonical_vk(vk, canonical)
+}
+
+/// Cache of params and proving keys, optionally backed by an [`ArtifactStore`]
+#[derive(Debug)]
+pub struct KeyStore<C: CurveAffine> {
+    artifacts: Option<ArtifactStore>,
+    params: Mutex<HashMap<u32, Arc<Params<C>>>>,
+    keys: Mutex<HashMap<CircuitHash, Arc<ProvingKey<C>>>>,
+}
+
+impl<C> KeyStore<C>
+where
+    C: CurveAffine,
+    C::Scalar: FromUniformBytes<64>,
+{
+    /// Store that keeps everything in memory
+    #[must_use]
+    pub fn in_memory() -> Self {
+        Self {
+            artifacts: None,
+            params: Mutex::new(HashMap::new()),
+            keys: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Store that persists params and verifying keys in `artifacts`
+    #[must_use]
+    pub fn persistent(artifacts: ArtifactStore) -> Self {
+        Self {
+            artifacts: Some(artifacts),
+            ..Self::in_memory()
+        }
+    }
+
+    /// Artifact store behind this store, if any
+    #[must_use]
+    pub fn artifacts(&self) -> Option<&ArtifactStore> {
+        self.artifacts.as_ref()
+    }
+
+    /// Params for `k`, loaded from disk or generated and saved on first use
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`ArtifactStore`]'s error if stored params cannot be read
+    /// or new ones cannot be written.
+    pub fn params(&self, k: u32) -> Result<Arc<Params<C>>> {
+        let mut cache = self.params.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(params) = cache.get(&k) {
+            return Ok(Arc::clone(params));
+        }
+        let params = match &self.artifacts {
+            Some(artifacts) => match artifacts.get_params(k)? {
+                Some(params) => params,
+                None => {
+                    let params = Params::new(k);
+                    artifacts.put_params(&params)?;
+                    params
+                }
+            },
+            None => Params::new(k),
+        };
+        let params = Arc::new(params);
+        cache.insert(k, Arc::clone(&params));
+        Ok(params)
+    }
+
+    /// Proving key for `circuit` over `2^k` rows, generated once per circuit
+    ///
+    /// The verifying key is always regenerated to compute the circuit hash;
+    /// the far costlier proving key is built only on a cache miss.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Plonk`] if keygen fails, for instance when the
+    /// circuit does not fit in `2^k` rows, [`Error::VkMismatch`] or
+    /// [`Error::Encoding`] if a stored verifying key does not match the
+    /// circuit, and the [`ArtifactStore`]'s error if reading or writing
+    /// artifacts fails.
+    pub fn get_or_generate<ConcreteCircuit>(
+        &self,
+        circuit: &ConcreteCircuit,
+        k: u32,
+    ) -> Result<Arc<ProvingKey<C>>>
+    where
+        ConcreteCircuit: Circuit<C::Scalar>,
+    {
+        let params = self.params(k)?;
+        let empty = circuit.without_witnesses();
+        let vk = keygen_vk(&params, &empty)?;
+        let hash = circuit_hash(&vk);
+
+        let mut keys = self.keys.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(pk) = keys.get(&hash) {
+            return Ok(Arc::clone(pk));
+        }
+        if let Some(artifacts) = &self.artifacts {
+            match artifacts.get_key(&hash, KeyKind::Verifying)? {
+                Some(stored) => check_verifying_key(&vk, &stored)?,
+                None => artifacts.put_key(&hash, KeyKind::Verifying, &encode_verifying_key(&vk))?,
+            }
+        }
+        let pk = Arc::new(keygen_pk(&params, vk, &empty)?);
+        keys.insert(hash, Arc::clone(&pk));
+        Ok(pk)
+    }
+
+    /// Stored versioned verifying key of `circuit`, or `None` if absent
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`ArtifactStore`]'s error if the file cannot be read.
+    pub fn stored_verifying_key(&self, circuit: &CircuitHash) -> Result<Option<Vec<u8>>> {
+        match &self.artifacts {
+            Some(artifacts) => artifacts.get_key(circuit, KeyKind::Verifying),
+            None => Ok(None),
+        }
+    }
+
+    /// Number of proving keys held in memory
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.keys
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    /// Whether no proving key is held in memory
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        sync::atomic::{AtomicU64, Ordering},
+    };
+
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        plonk::{Advice, Column, ConstraintSystem, Error as PlonkError, Instance},
+    };
+    use pasta_curves::{vesta, Fp};
+
+    use super::*;
+
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    /// Exposes its witness as public input row `ROW`
+    #[derive(Default)]
+    struct Echo<const ROW: usize> {
+        value: Value<Fp>,
+    }
+
+    impl<const ROW: usize> Circuit<Fp> for Echo<ROW> {
+        type Config = (Column<Advice>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(advice);
+            meta.enable_equality(instance);
+            (advice, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (advice, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> std::result::Result<(), PlonkError> {
+            let cell = layouter.assign_region(
+                || "value",
+                |mut region| region.assign_advice(|| "value", advice, 0, || self.value),
+            )?;
+            layouter.constrain_instance(cell.cell(), instance, ROW)
+        }
+    }
+
+    /// Fresh directory in the system temp directory, removed on drop
+    struct TempDir(std::path::PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            Self(std::env::temp_dir().join(format!(
+                "zk-keys-{name}-{}-{}",
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            )))
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_caches_per_circuit() {
+        let store = KeyStore::<vesta::Affine>::in_memory();
+        let first = store.get_or_generate(&Echo::<0>::default(), 4).unwrap();
+        let again = store.get_or_generate(&Echo::<0>::default(), 4).unwrap();
+        assert!(Arc::ptr_eq(&first, &again));
+        assert_eq!(store.len(), 1);
+
+        let other = store.get_or_generate(&Echo::<1>::default(), 4).unwrap();
+        assert!(!Arc::ptr_eq(&first, &other));
+        let bigger = store.get_or_generate(&Echo::<0>::default(), 5).unwrap();
+        assert_ne!(circuit_hash(first.get_vk()), circuit_hash(bigger.get_vk()));
+        assert_eq!(store.len(), 3);
+    }
+
+    #[test]
+    fn test_persists_params_and_vk() {
+        let dir = TempDir::new("persist");
+        let store = KeyStore::<vesta::Affine>::persistent(ArtifactStore::open(&dir.0).unwrap());
+        let pk = store.get_or_generate(&Echo::<0>::default(), 4).unwrap();
+        let hash = circuit_hash(pk.get_vk());
+
+        let artifacts = store.artifacts().unwrap();
+        assert!(artifacts.get_params::<vesta::Affine>(4).unwrap().is_some());
+        let stored = store.stored_verifying_key(&hash).unwrap().unwrap();
+        check_verifying_key(pk.get_vk(), &stored).unwrap();
+
+        // A second process reuses the params and accepts the stored key
+        let reopened = KeyStore::<vesta::Affine>::persistent(ArtifactStore::open(&dir.0).unwrap());
+        let again = reopened.get_or_generate(&Echo::<0>::default(), 4).unwrap();
+        assert_eq!(circuit_hash(again.get_vk()), hash);
+    }
+
+    #[test]
+    fn test_rejects_foreign_vk_file() {
+        let dir = TempDir::new("foreign");
+        let artifacts = ArtifactStore::open(&dir.0).unwrap();
+        let params = Params::<vesta::Affine>::new(4);
+        let vk = keygen_vk(&params, &Echo::<0>::default()).unwrap();
+        let other = keygen_vk(&params, &Echo::<1>::default()).unwrap();
+        artifacts
+            .put_key(
+                &circuit_hash(&vk),
+                KeyKind::Verifying,
+                &encode_verifying_key(&other),
+            )
+            .unwrap();
+
+        let store = KeyStore::<vesta::Affine>::persistent(artifacts);
+        assert!(matches!(
+            store.get_or_generate(&Echo::<0>::default(), 4),
+            Err(Error::VkMismatch { .. })
+        ));
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_key_format_versions() {
+        let params = Params::<vesta::Affine>::new(4);
+        let vk = keygen_vk(&params, &Echo::<0>::default()).unwrap();
+        let mut bytes = encode_verifying_key(&vk);
+        assert_eq!(&bytes[..2], &KEY_FORMAT_VERSION.to_le_bytes());
+
+        bytes[..2].copy_from_slice(&(KEY_FORMAT_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            check_verifying_key(&vk, &bytes),
+            Err(Error::Encoding(_))
+        ));
+        assert!(matches!(
+            check_verifying_key(&vk, &[1]),
+            Err(Error::Encoding(_))
+        ));
+    }
+}
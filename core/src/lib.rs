@@ -2,22 +2,83 @@
 //!
 //! This module provides the fundamental building blocks and abstractions
 //! for zero-knowledge proof construction using Halo2.
+//!
+//! The crate needs `std`, as halo2 does. With `default-features = false` it
+//! builds on one thread without rayon, and the PoRE, DCI and recursion
+//! circuits stay behind the `circuits` feature.
 
 #![warn(missing_docs)]
 #![warn(clippy::all)]
 #![warn(clippy::pedantic)]
 #![allow(clippy::module_name_repetitions)]
 
+// Lets the derive macros refer to `::zk_proof_core` inside this crate.
+extern crate self as zk_proof_core;
+
+pub mod artifacts;
+pub mod circuit_version;
 pub mod circuits;
 pub mod error;
+pub mod gadgets;
+pub mod keys;
+pub mod merkle;
+pub mod msm;
+pub mod nullifier;
+pub mod parallel;
+pub mod params;
+pub mod poseidon;
 pub mod proof;
-pub mod recursion; 
+pub mod prover;
+pub mod public_inputs;
+#[cfg(feature = "circuits")]
+pub mod recursion;
+pub mod registry;
+pub mod rng;
+pub mod secret;
+pub mod selftest;
+pub mod stats;
+pub mod telemetry;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod timeout;
+pub mod transcript;
+pub mod transfer;
 pub mod utils;
+pub mod vk;
 
-pub use error::{Error, Result};
+pub use artifacts::{ArtifactStore, CircuitHash, KeyKind};
+pub use circuit_version::{verify_versioned_proof, write_versioned_proof};
+pub use error::{Error, ErrorCategory, ErrorDetails, ErrorReport, Result, ResultExt};
+pub use keys::{circuit_hash, KeyStore, KEY_FORMAT_VERSION};
+pub use merkle::{MerklePath, MerkleTree};
+pub use msm::{msm, MsmBackend, Pippenger};
+pub use nullifier::{MemoryBackend, NullifierBackend, NullifierSet};
+#[cfg(feature = "sled")]
+pub use nullifier::SledBackend;
+pub use parallel::{set_parallelism, ThreadPoolConfig};
+pub use params::{params_digest, ParamsCache};
+pub use poseidon::Poseidon;
+pub use proof::{
+    hash_public_inputs, migrate, negotiate_version, write_proof, Proof, ProofEnvelope,
+    ProofMetadata, ProofSignature, VkHash, ENVELOPE_VERSION, PROOF_FORMAT_VERSION,
+};
+pub use prover::{BatchProver, Prover, ProverConfig, ProverContext};
+pub use public_inputs::PublicInputs;
+pub use registry::{CircuitEntry, CircuitRegistry};
+pub use rng::{DeterministicRandomness, OsRandomness, Randomness};
+pub use secret::SecretWitness;
+pub use selftest::selftest;
+pub use stats::{Phase, ProverStats};
+pub use telemetry::{CircuitTelemetry, Telemetry, TelemetryEvent, TelemetryLog};
+pub use timeout::{prove_within, verify_within, Deadline, Timed};
+pub use transcript::{Blake2bTranscript, PoseidonTranscript, Transcript};
+pub use transfer::{Frame, FrameAssembler, TransferManifest};
+pub use vk::{canonical_vk_bytes, check_canonical_vk, write_canonical_vk};
+pub use zk_proof_derive::{CircuitConfig, PublicInputs};
 
 /// Re-export commonly used types from dependencies
 pub mod prelude {
+    pub use ff::PrimeField;
     pub use halo2_proofs::{
         arithmetic::Field,
         circuit::{Layouter, SimpleFloorPlanner, Value},
@@ -0,0 +1,322 @@
+//! Append-only Merkle tree over the circuit's Poseidon hash
+//!
+//! A [`MerkleTree`] has a fixed depth and fills leaves left to right.
+//! Parents are the two-to-one hash of [`Poseidon::hash_pair`] with the
+//! default round counts, which matches [`crate::gadgets::PoseidonChip::hash`],
+//! so a [`MerklePath`] produced here is a witness the in-circuit path check
+//! accepts.
+//!
+//! Only subtrees holding at least one leaf are stored. Every empty subtree
+//! of height `h` hashes to the same node, `Z_0 = 0` and
+//! `Z_{h+1} = H(Z_h, Z_h)`, which stands in for the missing nodes.
+
+use std::collections::HashMap;
+
+use ff::PrimeField;
+
+use crate::{
+    error::{Error, Result},
+    poseidon::Poseidon,
+};
+
+/// Authentication path from a leaf to the root
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerklePath<F> {
+    /// Position of the leaf; bit `i` is set if the node at level `i` is a
+    /// right child
+    pub index: u64,
+    /// Sibling at each level, starting next to the leaf
+    pub siblings: Vec<F>,
+}
+
+impl<F: PrimeField> MerklePath<F> {
+    /// Direction of each level, 1 if the node is a right child
+    #[must_use]
+    pub fn directions(&self) -> Vec<F> {
+        (0..self.siblings.len())
+            .map(|level| F::from((self.index >> level) & 1))
+            .collect()
+    }
+
+    /// Root reached by hashing `leaf` up the path
+    #[must_use]
+    pub fn root(&self, leaf: F) -> F {
+        let poseidon = Poseidon::default();
+        self.siblings
+            .iter()
+            .enumerate()
+            .fold(leaf, |node, (level, &sibling)| {
+                if (self.index >> level) & 1 == 1 {
+                    poseidon.hash_pair(sibling, node)
+                } else {
+                    poseidon.hash_pair(node, sibling)
+                }
+            })
+    }
+}
+
+/// Fixed-depth Merkle tree whose leaves are appended in order
+#[derive(Clone, Debug)]
+pub struct MerkleTree<F: PrimeField> {
+    depth: usize,
+    poseidon: Poseidon<F>,
+    /// Root of an empty subtree of each height
+    empty: Vec<F>,
+    /// Stored nodes of each level, leaves first
+    levels: Vec<Vec<F>>,
+    /// First position of each leaf, keyed by its representation
+    positions: HashMap<Vec<u8>, u64>,
+}
+
+impl<F: PrimeField> MerkleTree<F> {
+    /// Empty tree with room for `2^depth` leaves
+    ///
+    /// # Panics
+    ///
+    /// Panics if `depth` is greater than 63.
+    #[must_use]
+    pub fn new(depth: usize) -> Self {
+        assert!(depth < 64, "merkle tree depth {depth} exceeds 63");
+        let poseidon = Poseidon::default();
+        let mut empty = vec![F::ZERO];
+        for height in 0..depth {
+            empty.push(poseidon.hash_pair(empty[height], empty[height]));
+        }
+        Self {
+            depth,
+            poseidon,
+            empty,
+            levels: vec![Vec::new(); depth + 1],
+            positions: HashMap::new(),
+        }
+    }
+
+    /// Number of levels between the leaves and the root
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Number of leaves inserted so far
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.levels[0].len() as u64
+    }
+
+    /// Whether no leaf has been inserted
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.levels[0].is_empty()
+    }
+
+    /// Append `leaf`, returning its position
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Other`] if all `2^depth` positions are taken.
+    pub fn insert(&mut self, leaf: F) -> Result<u64> {
+        let index = self.len();
+        if index >> self.depth != 0 {
+            return Err(Error::Other(format!(
+                "merkle tree of depth {} is full",
+                self.depth
+            )));
+        }
+        self.levels[0].push(leaf);
+        self.positions
+            .entry(leaf.to_repr().as_ref().to_vec())
+            .or_insert(index);
+
+        let mut node = leaf;
+        let mut position = index;
+        for level in 0..self.depth {
+            let sibling = self.node(level, position ^ 1);
+            node = if position & 1 == 1 {
+                self.poseidon.hash_pair(sibling, node)
+            } else {
+                self.poseidon.hash_pair(node, sibling)
+            };
+            position >>= 1;
+            let parents = &mut self.levels[level + 1];
+            match parents.get_mut(to_usize(position)) {
+                Some(parent) => *parent = node,
+                None => parents.push(node),
+            }
+        }
+        Ok(index)
+    }
+
+    /// Current root
+    #[must_use]
+    pub fn root(&self) -> F {
+        self.node(self.depth, 0)
+    }
+
+    /// Path for the first occurrence of `leaf`
+    #[must_use]
+    pub fn prove(&self, leaf: F) -> Option<MerklePath<F>> {
+        let index = *self.positions.get(leaf.to_repr().as_ref())?;
+        self.prove_index(index)
+    }
+
+    /// Path for the leaf at `index`
+    #[must_use]
+    pub fn prove_index(&self, index: u64) -> Option<MerklePath<F>> {
+        if index >= self.len() {
+            return None;
+        }
+        let siblings = (0..self.depth)
+            .map(|level| self.node(level, (index >> level) ^ 1))
+            .collect();
+        Some(MerklePath { index, siblings })
+    }
+
+    /// Stored node at `level` and `position`, or the empty subtree root
+    fn node(&self, level: usize, position: u64) -> F {
+        self.levels[level]
+            .get(to_usize(position))
+            .copied()
+            .unwrap_or(self.empty[level])
+    }
+}
+
+/// Stored positions never exceed the length of a `Vec`
+fn to_usize(position: u64) -> usize {
+    usize::try_from(position).unwrap_or(usize::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error as PlonkError, Instance},
+    };
+    use pasta_curves::Fp;
+
+    use super::*;
+    use crate::gadgets::{Gadget, PoseidonChip, PoseidonConfig};
+
+    #[test]
+    fn test_paths_open_to_root() {
+        let mut tree = MerkleTree::<Fp>::new(4);
+        assert_eq!(tree.root(), tree.empty[4]);
+
+        for value in 1..=5 {
+            assert_eq!(tree.insert(Fp::from(value)).unwrap(), value - 1);
+        }
+        let root = tree.root();
+        for value in 1..=5 {
+            let path = tree.prove(Fp::from(value)).unwrap();
+            assert_eq!(path.index, value - 1);
+            assert_eq!(path.root(Fp::from(value)), root);
+        }
+        assert!(tree.prove(Fp::from(6)).is_none());
+        assert!(tree.prove_index(5).is_none());
+
+        // Hashing the full leaf layer gives the same root
+        let poseidon = Poseidon::default();
+        let mut layer: Vec<Fp> = (1..=16)
+            .map(|value| Fp::from(if value <= 5 { value } else { 0 }))
+            .collect();
+        while layer.len() > 1 {
+            layer = layer
+                .chunks(2)
+                .map(|pair| poseidon.hash_pair(pair[0], pair[1]))
+                .collect();
+        }
+        assert_eq!(layer[0], root);
+    }
+
+    #[test]
+    fn test_full_tree_rejects_insert() {
+        let mut tree = MerkleTree::<Fp>::new(2);
+        for value in 0..4 {
+            tree.insert(Fp::from(value)).unwrap();
+        }
+        assert!(matches!(tree.insert(Fp::ONE), Err(Error::Other(_))));
+        assert_eq!(tree.len(), 4);
+    }
+
+    /// Recomputes a two-level root in-circuit from a tree path
+    #[derive(Default)]
+    struct PathCircuit {
+        leaf: Value<Fp>,
+        siblings: [Value<Fp>; 2],
+        index: u64,
+    }
+
+    impl Circuit<Fp> for PathCircuit {
+        type Config = (PoseidonConfig, Column<Advice>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                index: self.index,
+                ..Self::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let state = [(); 3].map(|()| meta.advice_column());
+            let partial_sbox = meta.advice_column();
+            let rc_a = [(); 3].map(|()| meta.fixed_column());
+            let rc_b = [(); 3].map(|()| meta.fixed_column());
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            let poseidon = PoseidonChip::configure(meta, (state, partial_sbox, rc_a, rc_b));
+            (poseidon, input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (poseidon, input, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> std::result::Result<(), PlonkError> {
+            let chip = PoseidonChip::construct(poseidon);
+            let [leaf, first, second] = layouter.assign_region(
+                || "path",
+                |mut region| {
+                    let leaf = region.assign_advice(|| "leaf", input, 0, || self.leaf)?;
+                    let first =
+                        region.assign_advice(|| "sibling", input, 1, || self.siblings[0])?;
+                    let second =
+                        region.assign_advice(|| "sibling", input, 2, || self.siblings[1])?;
+                    Ok([leaf, first, second])
+                },
+            )?;
+            let mut node = leaf;
+            for (level, sibling) in [first, second].into_iter().enumerate() {
+                let pair = if (self.index >> level) & 1 == 1 {
+                    [sibling, node]
+                } else {
+                    [node, sibling]
+                };
+                node = chip.hash(layouter.namespace(|| "level"), &pair)?;
+            }
+            layouter.constrain_instance(node.cell(), instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_path_matches_chip() {
+        let mut tree = MerkleTree::<Fp>::new(2);
+        for value in [7, 11, 13] {
+            tree.insert(Fp::from(value)).unwrap();
+        }
+        let path = tree.prove(Fp::from(13)).unwrap();
+        let circuit = PathCircuit {
+            leaf: Value::known(Fp::from(13)),
+            siblings: [
+                Value::known(path.siblings[0]),
+                Value::known(path.siblings[1]),
+            ],
+            index: path.index,
+        };
+        let prover = MockProver::run(8, &circuit, vec![vec![tree.root()]]).unwrap();
+        prover.assert_satisfied();
+    }
+}
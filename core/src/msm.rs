@@ -0,0 +1,193 @@
+//! Multi-scalar multiplication
+//!
+//! Every `sum(s_i * P_i)` outside the halo2 prover goes through an
+//! [`MsmBackend`]: vector and polynomial commitments and the folding of
+//! the recursion accumulator. [`msm`] runs the default backend,
+//! [`Pippenger`].
+//!
+//! Pippenger's bucket method cuts each scalar into windows of `c` bits. For
+//! every window the points are dropped into `2^c - 1` buckets by the value
+//! of their digit, and the buckets are summed with a running sum so that
+//! bucket `d` counts `d` times. That costs about `n + 2^c` additions per
+//! window and `bits / c` windows, which [`Pippenger::window_size`] balances
+//! for the input length. Windows are independent and run in parallel with
+//! the `multicore` feature.
+
+use ff::PrimeField;
+use group::Group;
+use halo2_proofs::arithmetic::CurveAffine;
+#[cfg(feature = "multicore")]
+use rayon::prelude::*;
+
+/// Computes `sum(scalars[i] * bases[i])`
+pub trait MsmBackend {
+    /// Multi-scalar multiplication of `scalars` by `bases`
+    ///
+    /// # Panics
+    ///
+    /// Implementations panic if the two slices differ in length.
+    fn msm<C: CurveAffine>(&self, scalars: &[C::Scalar], bases: &[C]) -> C::Curve;
+}
+
+/// Bucket-method backend with a window size chosen per call
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Pippenger;
+
+/// Inputs shorter than this are summed one scalar multiplication at a time
+const NAIVE_THRESHOLD: usize = 4;
+
+impl Pippenger {
+    /// Window width in bits for `len` points
+    ///
+    /// Roughly `ln(len)`, the width where filling the buckets and summing
+    /// them cost about the same.
+    #[must_use]
+    pub fn window_size(len: usize) -> usize {
+        if len < 32 {
+            3
+        } else {
+            // ln(len) = log2(len) * 0.69
+            (len.ilog2() as usize * 69 / 100 + 1).min(16)
+        }
+    }
+}
+
+impl MsmBackend for Pippenger {
+    fn msm<C: CurveAffine>(&self, scalars: &[C::Scalar], bases: &[C]) -> C::Curve {
+        assert_eq!(
+            scalars.len(),
+            bases.len(),
+            "msm over {} scalars and {} bases",
+            scalars.len(),
+            bases.len()
+        );
+        if scalars.len() < NAIVE_THRESHOLD {
+            return scalars
+                .iter()
+                .zip(bases)
+                .fold(C::Curve::identity(), |acc, (scalar, base)| {
+                    acc + *base * scalar
+                });
+        }
+
+        let c = Self::window_size(scalars.len());
+        let reprs: Vec<_> = scalars.iter().map(PrimeField::to_repr).collect();
+        let windows = (C::Scalar::NUM_BITS as usize).div_ceil(c);
+        let window_sum = |window: usize| {
+            let mut buckets = vec![C::Curve::identity(); (1 << c) - 1];
+            for (repr, base) in reprs.iter().zip(bases) {
+                let digit = digit(repr.as_ref(), window * c, c);
+                if digit != 0 {
+                    buckets[digit - 1] += *base;
+                }
+            }
+            let mut running = C::Curve::identity();
+            let mut sum = C::Curve::identity();
+            for bucket in buckets.iter().rev() {
+                running += bucket;
+                sum += running;
+            }
+            sum
+        };
+
+        #[cfg(feature = "multicore")]
+        let sums: Vec<C::Curve> = (0..windows).into_par_iter().map(window_sum).collect();
+        #[cfg(not(feature = "multicore"))]
+        let sums: Vec<C::Curve> = (0..windows).map(window_sum).collect();
+
+        sums.iter().rev().fold(C::Curve::identity(), |acc, sum| {
+            (0..c).fold(acc, |acc, _| acc.double()) + sum
+        })
+    }
+}
+
+/// `sum(scalars[i] * bases[i])` with the default backend
+///
+/// # Panics
+///
+/// Panics if the two slices differ in length.
+#[must_use]
+pub fn msm<C: CurveAffine>(scalars: &[C::Scalar], bases: &[C]) -> C::Curve {
+    Pippenger.msm(scalars, bases)
+}
+
+/// The `width` bits of a little-endian `repr` starting at bit `skip`
+fn digit(repr: &[u8], skip: usize, width: usize) -> usize {
+    (0..width)
+        .map(|i| skip + i)
+        .filter(|bit| {
+            repr.get(bit / 8)
+                .is_some_and(|byte| (byte >> (bit % 8)) & 1 == 1)
+        })
+        .fold(0, |digit, bit| digit | (1 << (bit - skip)))
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use group::{prime::PrimeCurveAffine, Curve};
+    use halo2_proofs::arithmetic::best_multiexp;
+    use pasta_curves::{pallas, vesta};
+
+    use super::*;
+    use crate::rng::{DeterministicRandomness, Randomness};
+
+    fn inputs<C: CurveAffine>(len: usize) -> (Vec<C::Scalar>, Vec<C>) {
+        let rng = DeterministicRandomness::from_seed([u8::try_from(len).unwrap(); 32]);
+        let scalars = (0..len).map(|_| rng.random_field()).collect();
+        let bases = (0..len)
+            .map(|_| (C::generator() * rng.random_field::<C::Scalar>()).to_affine())
+            .collect();
+        (scalars, bases)
+    }
+
+    #[test]
+    fn test_matches_naive_sum() {
+        for len in [0, 1, 3, 4, 33, 200] {
+            let (scalars, bases) = inputs::<pallas::Affine>(len);
+            let naive = scalars
+                .iter()
+                .zip(&bases)
+                .fold(pallas::Point::identity(), |acc, (s, b)| acc + b * s);
+            assert_eq!(msm(&scalars, &bases), naive, "{len} points");
+            if len > 0 {
+                assert_eq!(msm(&scalars, &bases), best_multiexp(&scalars, &bases));
+            }
+        }
+    }
+
+    #[test]
+    fn test_edge_scalars() {
+        let (_, bases) = inputs::<vesta::Affine>(6);
+        let scalars = [
+            vesta::Scalar::ZERO,
+            vesta::Scalar::ONE,
+            -vesta::Scalar::ONE,
+            vesta::Scalar::from(u64::MAX),
+            vesta::Scalar::ZERO,
+            vesta::Scalar::from(7),
+        ];
+        let naive = scalars
+            .iter()
+            .zip(&bases)
+            .fold(vesta::Point::identity(), |acc, (s, b)| acc + b * s);
+        assert_eq!(msm(&scalars, &bases), naive);
+
+        let identity = [vesta::Affine::identity(); 5];
+        assert_eq!(
+            msm(&[vesta::Scalar::ONE; 5], &identity),
+            vesta::Point::identity()
+        );
+    }
+
+    #[test]
+    fn test_window_size_grows() {
+        assert_eq!(Pippenger::window_size(1), 3);
+        let sizes: Vec<usize> = [32, 1 << 10, 1 << 16, 1 << 24]
+            .iter()
+            .map(|&len| Pippenger::window_size(len))
+            .collect();
+        assert!(sizes.windows(2).all(|pair| pair[0] < pair[1]), "{sizes:?}");
+        assert!(Pippenger::window_size(usize::MAX) <= 16);
+    }
+}
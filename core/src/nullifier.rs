@@ -0,0 +1,258 @@
+//! Spent nullifier tracking
+//!
+//! A [`NullifierSet`] records the nullifier of every accepted spend.
+//! Double-spend protection lives at the verifier: after a proof checks out,
+//! [`NullifierSet::spend`] records its public nullifier and rejects one that
+//! was recorded before. Nullifiers are stored by their canonical
+//! representation in a [`NullifierBackend`]: [`MemoryBackend`] for tests and
+//! short-lived services, or `SledBackend` with the `sled` feature for a set
+//! that survives restarts.
+//!
+//! Circuits only derive nullifiers: the DCI circuit constrains its public
+//! nullifier to the Poseidon hash of the spender's secret key and note
+//! commitment, so each note has exactly one. Nothing in a proof shows that
+//! the nullifier is unspent. A proof verifies again with the same public
+//! inputs, so a spend is only protected once its nullifier goes through
+//! [`NullifierSet::spend`].
+
+use std::collections::BTreeSet;
+
+use ff::PrimeField;
+
+use crate::error::{Error, Result};
+
+/// Storage for the encoded nullifiers of a [`NullifierSet`]
+pub trait NullifierBackend {
+    /// Record `key`, returning whether it was absent
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the storage fails.
+    fn insert(&mut self, key: &[u8]) -> Result<bool>;
+
+    /// Whether `key` has been recorded
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the storage fails.
+    fn contains(&self, key: &[u8]) -> Result<bool>;
+
+    /// Every recorded key, in ascending order
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the storage fails.
+    fn keys(&self) -> Result<Vec<Vec<u8>>>;
+
+    /// Make recorded keys durable
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the storage fails.
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Nullifiers held in memory and lost on drop
+#[derive(Clone, Debug, Default)]
+pub struct MemoryBackend {
+    keys: BTreeSet<Vec<u8>>,
+}
+
+impl NullifierBackend for MemoryBackend {
+    fn insert(&mut self, key: &[u8]) -> Result<bool> {
+        Ok(self.keys.insert(key.to_vec()))
+    }
+
+    fn contains(&self, key: &[u8]) -> Result<bool> {
+        Ok(self.keys.contains(key))
+    }
+
+    fn keys(&self) -> Result<Vec<Vec<u8>>> {
+        Ok(self.keys.iter().cloned().collect())
+    }
+}
+
+/// Nullifiers persisted in a sled database
+#[cfg(feature = "sled")]
+#[derive(Clone, Debug)]
+pub struct SledBackend {
+    tree: sled::Tree,
+}
+
+#[cfg(feature = "sled")]
+impl SledBackend {
+    /// Open or create the database at `path`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the database cannot be opened.
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let db = sled::open(path).map_err(sled_error)?;
+        Ok(Self::new(db.open_tree("nullifiers").map_err(sled_error)?))
+    }
+
+    /// Store nullifiers in `tree`
+    #[must_use]
+    pub fn new(tree: sled::Tree) -> Self {
+        Self { tree }
+    }
+}
+
+#[cfg(feature = "sled")]
+impl NullifierBackend for SledBackend {
+    fn insert(&mut self, key: &[u8]) -> Result<bool> {
+        let previous = self.tree.insert(key, Vec::new()).map_err(sled_error)?;
+        Ok(previous.is_none())
+    }
+
+    fn contains(&self, key: &[u8]) -> Result<bool> {
+        self.tree.contains_key(key).map_err(sled_error)
+    }
+
+    fn keys(&self) -> Result<Vec<Vec<u8>>> {
+        self.tree
+            .iter()
+            .keys()
+            .map(|key| key.map(|key| key.to_vec()).map_err(sled_error))
+            .collect()
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.tree.flush().map_err(sled_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sled")]
+fn sled_error(err: sled::Error) -> Error {
+    Error::Io(err.into())
+}
+
+/// Set of spent nullifiers over a storage backend
+#[derive(Clone, Debug)]
+pub struct NullifierSet<F, B = MemoryBackend> {
+    backend: B,
+    _field: std::marker::PhantomData<F>,
+}
+
+impl<F: PrimeField> NullifierSet<F> {
+    /// Empty set held in memory
+    #[must_use]
+    pub fn in_memory() -> Self {
+        Self::new(MemoryBackend::default())
+    }
+}
+
+impl<F: PrimeField, B: NullifierBackend> NullifierSet<F, B> {
+    /// Set stored in `backend`, keeping what it already holds
+    #[must_use]
+    pub fn new(backend: B) -> Self {
+        Self {
+            backend,
+            _field: std::marker::PhantomData,
+        }
+    }
+
+    /// Record `nullifier`, returning whether it was absent
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the backend fails.
+    pub fn insert(&mut self, nullifier: &F) -> Result<bool> {
+        self.backend.insert(nullifier.to_repr().as_ref())
+    }
+
+    /// Whether `nullifier` has been recorded
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the backend fails.
+    pub fn contains(&self, nullifier: &F) -> Result<bool> {
+        self.backend.contains(nullifier.to_repr().as_ref())
+    }
+
+    /// Record the nullifiers of a batch of accepted spends
+    ///
+    /// Nothing is recorded unless every nullifier is new and none repeats
+    /// within `nullifiers`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Verification`] naming the first nullifier that was
+    /// already spent, and [`Error::Io`] if the backend fails.
+    pub fn spend(&mut self, nullifiers: &[F]) -> Result<()> {
+        let mut batch = BTreeSet::new();
+        for (i, nullifier) in nullifiers.iter().enumerate() {
+            let key = nullifier.to_repr().as_ref().to_vec();
+            if self.backend.contains(&key)? || !batch.insert(key) {
+                return Err(Error::Verification(format!(
+                    "nullifier {i} of the batch is already spent"
+                )));
+            }
+        }
+        for key in &batch {
+            self.backend.insert(key)?;
+        }
+        self.backend.flush()
+    }
+
+    /// Every recorded nullifier, ordered by encoding
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if the backend holds a key that is not a
+    /// canonical field element, and [`Error::Io`] if it fails.
+    pub fn snapshot(&self) -> Result<Vec<F>> {
+        self.backend
+            .keys()?
+            .into_iter()
+            .map(|key| {
+                let mut repr = F::Repr::default();
+                if key.len() != repr.as_ref().len() {
+                    return Err(Error::Encoding(format!(
+                        "stored nullifier has {} bytes",
+                        key.len()
+                    )));
+                }
+                repr.as_mut().copy_from_slice(&key);
+                Option::<F>::from(F::from_repr(repr)).ok_or_else(|| {
+                    Error::Encoding("stored nullifier is not a field element".into())
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pasta_curves::Fp;
+
+    use super::*;
+
+    #[test]
+    fn test_spend_rejects_reuse() {
+        let mut set = NullifierSet::<Fp>::in_memory();
+        set.spend(&[Fp::from(1), Fp::from(2)]).unwrap();
+        assert!(set.contains(&Fp::from(2)).unwrap());
+
+        assert!(matches!(
+            set.spend(&[Fp::from(3), Fp::from(2)]),
+            Err(Error::Verification(_))
+        ));
+        // A rejected batch records nothing
+        assert!(!set.contains(&Fp::from(3)).unwrap());
+        assert!(matches!(
+            set.spend(&[Fp::from(4), Fp::from(4)]),
+            Err(Error::Verification(_))
+        ));
+        assert!(!set.contains(&Fp::from(4)).unwrap());
+
+        assert!(set.insert(&Fp::from(3)).unwrap());
+        assert!(!set.insert(&Fp::from(3)).unwrap());
+        let mut expected = vec![Fp::from(1), Fp::from(2), Fp::from(3)];
+        expected.sort_by(|a, b| a.to_repr().cmp(&b.to_repr()));
+        assert_eq!(set.snapshot().unwrap(), expected);
+    }
+}
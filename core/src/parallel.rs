@@ -0,0 +1,149 @@
+//! Process-wide parallelism settings
+//!
+//! The prover and the batch verifier run their MSMs and FFTs on the global
+//! rayon pool, and halo2 splits that work into one chunk per pool thread. By
+//! default the pool takes every core, which starves neighbours on shared CI
+//! machines. [`set_parallelism`] sizes the pool once, before any proving, and
+//! the `ZK_PROOF_THREADS` environment variable overrides whatever the
+//! application asks for.
+//!
+//! Without the `multicore` feature everything runs on the calling thread and
+//! only the chunk length is recorded.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::error::{Error, Result};
+
+/// Environment variable overriding [`ThreadPoolConfig::num_threads`]
+pub const THREADS_ENV: &str = "ZK_PROOF_THREADS";
+
+static MIN_CHUNK_LEN: AtomicUsize = AtomicUsize::new(1);
+
+/// Thread pool size and work splitting for the prover and verifier
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ThreadPoolConfig {
+    /// Worker threads; `None` uses one per core
+    ///
+    /// halo2 chunks MSMs and FFTs by this count.
+    pub num_threads: Option<usize>,
+    /// Fewest items handed to one task by this crate's parallel loops, 0 for
+    /// rayon's default
+    pub min_chunk_len: usize,
+}
+
+impl ThreadPoolConfig {
+    /// Use exactly `num_threads` workers
+    #[must_use]
+    pub fn with_threads(num_threads: usize) -> Self {
+        Self {
+            num_threads: Some(num_threads),
+            ..Self::default()
+        }
+    }
+
+    /// Apply the `ZK_PROOF_THREADS` override
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Other`] if the variable is set but is not a positive
+    /// integer.
+    pub fn with_env_override(self) -> Result<Self> {
+        threads_override(self, std::env::var(THREADS_ENV).ok().as_deref())
+    }
+}
+
+fn threads_override(config: ThreadPoolConfig, value: Option<&str>) -> Result<ThreadPoolConfig> {
+    let Some(value) = value else {
+        return Ok(config);
+    };
+    match value.trim().parse::<usize>() {
+        Ok(threads) if threads > 0 => Ok(ThreadPoolConfig {
+            num_threads: Some(threads),
+            ..config
+        }),
+        _ => Err(Error::Other(format!(
+            "{THREADS_ENV} must be a positive integer, found {value:?}"
+        ))),
+    }
+}
+
+/// Configure the global pool used by the prover and batch verifier
+///
+/// Must run before the first proof or batch verification: the global pool
+/// is created on first use and cannot be resized afterwards.
+///
+/// # Errors
+///
+/// Returns [`Error::Other`] if `ZK_PROOF_THREADS` is malformed or the global
+/// pool already exists.
+pub fn set_parallelism(config: ThreadPoolConfig) -> Result<()> {
+    let config = config.with_env_override()?;
+    #[cfg(feature = "multicore")]
+    builder(&config)
+        .build_global()
+        .map_err(|err| Error::Other(format!("configuring thread pool: {err}")))?;
+    MIN_CHUNK_LEN.store(config.min_chunk_len.max(1), Ordering::Relaxed);
+    Ok(())
+}
+
+/// Threads available to the prover
+#[cfg(feature = "multicore")]
+#[must_use]
+pub fn current_num_threads() -> usize {
+    rayon::current_num_threads()
+}
+
+/// Threads available to the prover
+#[cfg(not(feature = "multicore"))]
+#[must_use]
+pub fn current_num_threads() -> usize {
+    1
+}
+
+/// Fewest items per task for this crate's parallel loops
+#[must_use]
+pub fn min_chunk_len() -> usize {
+    MIN_CHUNK_LEN.load(Ordering::Relaxed)
+}
+
+#[cfg(feature = "multicore")]
+pub(crate) fn builder(config: &ThreadPoolConfig) -> rayon::ThreadPoolBuilder {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(config.num_threads.unwrap_or(0))
+        .thread_name(|index| format!("zk-proof-{index}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_threads_override() {
+        let config = ThreadPoolConfig {
+            num_threads: Some(8),
+            min_chunk_len: 16,
+        };
+        assert_eq!(threads_override(config, None).unwrap(), config);
+        assert_eq!(
+            threads_override(config, Some(" 2 ")).unwrap(),
+            ThreadPoolConfig {
+                num_threads: Some(2),
+                min_chunk_len: 16,
+            }
+        );
+        for bad in ["0", "-1", "all"] {
+            assert!(matches!(
+                threads_override(config, Some(bad)),
+                Err(Error::Other(_))
+            ));
+        }
+    }
+
+    #[cfg(feature = "multicore")]
+    #[test]
+    fn test_pool_size() {
+        // A local pool, so the global one stays free for the process
+        let pool = builder(&ThreadPoolConfig::with_threads(3)).build().unwrap();
+        assert_eq!(pool.install(current_num_threads), 3);
+    }
+}
@@ -0,0 +1,316 @@
+//! Managed params cache with integrity checks
+//!
+//! Generating IPA params takes minutes for `k > 16`, so a [`ParamsCache`]
+//! keeps them in a directory that outlives the process, laid out like an
+//! [`ArtifactStore`]'s `params/` directory. Every file carries the SHA-256
+//! digest of its payload and is checked on each load.
+//!
+//! IPA params are derived from a fixed hash-to-curve seed, so anyone can
+//! regenerate them and nothing needs to be trusted. Deployments that fetch
+//! params instead of generating them pass the downloaded bytes to
+//! [`ParamsCache::import`], and can pin the SHA-256 [`params_digest`] of
+//! each `k` so a swapped or truncated download is rejected.
+//!
+//! The directory is the one given to [`ParamsCache::open`], or for
+//! [`ParamsCache::from_env`] the `ZK_PARAMS_DIR` environment variable,
+//! falling back to `$XDG_CACHE_HOME/zk-proof-system`,
+//! `$HOME/.cache/zk-proof-system` and finally the system temp directory.
+//! One directory holds params for one curve.
+
+use std::{
+    collections::HashMap,
+    io::Read,
+    ops::RangeInclusive,
+    path::PathBuf,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use halo2_proofs::{arithmetic::CurveAffine, poly::commitment::Params};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    artifacts::ArtifactStore,
+    error::{Error, Result},
+    utils::encoding::encode_hex,
+};
+
+/// Environment variable naming the params cache directory
+pub const PARAMS_DIR_ENV: &str = "ZK_PARAMS_DIR";
+
+/// SHA-256 of the serialized `params`
+///
+/// # Errors
+///
+/// Returns [`Error::Io`] if serializing fails.
+pub fn params_digest<C: CurveAffine>(params: &Params<C>) -> Result<[u8; 32]> {
+    let mut bytes = Vec::new();
+    params.write(&mut bytes)?;
+    Ok(Sha256::digest(&bytes).into())
+}
+
+/// Directory of params for a range of `k`, loaded on demand
+#[derive(Debug)]
+pub struct ParamsCache<C: CurveAffine> {
+    store: ArtifactStore,
+    pinned: HashMap<u32, [u8; 32]>,
+    loaded: Mutex<HashMap<u32, Arc<Params<C>>>>,
+}
+
+impl<C: CurveAffine> ParamsCache<C> {
+    /// Cache in `dir`, creating the directory if needed
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the directory cannot be created.
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        Ok(Self {
+            store: ArtifactStore::open(dir)?,
+            pinned: HashMap::new(),
+            loaded: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Cache in the directory named by the environment
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the directory cannot be created.
+    pub fn from_env() -> Result<Self> {
+        Self::open(default_dir())
+    }
+
+    /// Require params for `k` to have SHA-256 digest `digest`
+    #[must_use]
+    pub fn pin(mut self, k: u32, digest: [u8; 32]) -> Self {
+        self.pinned.insert(k, digest);
+        self
+    }
+
+    /// Directory the params live in
+    #[must_use]
+    pub fn dir(&self) -> PathBuf {
+        self.store.root().join("params")
+    }
+
+    /// Params for `k`, loaded from the cache or generated and saved
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if the cached file fails its integrity
+    /// check or its pinned digest, and [`Error::Io`] if reading or writing
+    /// the cache fails.
+    pub fn get(&self, k: u32) -> Result<Arc<Params<C>>> {
+        let mut loaded = self.loaded.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(params) = loaded.get(&k) {
+            return Ok(Arc::clone(params));
+        }
+        let params = match self.load(k)? {
+            Some(params) => params,
+            None => {
+                let params = Params::new(k);
+                self.check_pin(k, &params)?;
+                self.store.put_params(&params)?;
+                params
+            }
+        };
+        let params = Arc::new(params);
+        loaded.insert(k, Arc::clone(&params));
+        Ok(params)
+    }
+
+    /// Make sure params for every `k` in `range` are cached on disk
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error [`ParamsCache::get`] returns.
+    pub fn prepare(&self, range: RangeInclusive<u32>) -> Result<()> {
+        for k in range {
+            self.get(k)?;
+        }
+        Ok(())
+    }
+
+    /// Check the cached params for `k` without keeping them in memory
+    ///
+    /// Returns `false` if nothing is cached for `k`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if the file fails its integrity check,
+    /// does not parse or does not match its pinned digest, and
+    /// [`Error::ParamsMismatch`] if it holds params for another `k`.
+    pub fn verify(&self, k: u32) -> Result<bool> {
+        Ok(self.load(k)?.is_some())
+    }
+
+    /// Install params for `k` read from `reader`, such as a download
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if the bytes do not parse or do not match
+    /// the pinned digest, [`Error::ParamsMismatch`] if they hold params for
+    /// another `k`, and [`Error::Io`] if writing the cache fails.
+    pub fn import(&self, k: u32, mut reader: impl Read) -> Result<Arc<Params<C>>> {
+        let params = Params::<C>::read(&mut reader)
+            .map_err(|err| Error::Encoding(format!("invalid params: {err}")))?;
+        if params.k() != k {
+            return Err(Error::ParamsMismatch {
+                expected: k,
+                found: params.k(),
+            });
+        }
+        self.check_pin(k, &params)?;
+        self.store.put_params(&params)?;
+
+        let params = Arc::new(params);
+        self.loaded
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(k, Arc::clone(&params));
+        Ok(params)
+    }
+
+    fn load(&self, k: u32) -> Result<Option<Params<C>>> {
+        let Some(params) = self.store.get_params(k)? else {
+            return Ok(None);
+        };
+        self.check_pin(k, &params)?;
+        Ok(Some(params))
+    }
+
+    fn check_pin(&self, k: u32, params: &Params<C>) -> Result<()> {
+        let Some(expected) = self.pinned.get(&k) else {
+            return Ok(());
+        };
+        let found = params_digest(params)?;
+        if &found == expected {
+            Ok(())
+        } else {
+            Err(Error::Encoding(format!(
+                "params for k={k} have digest {}, expected {}",
+                encode_hex(&found),
+                encode_hex(expected)
+            )))
+        }
+    }
+}
+
+/// Cache directory named by the environment
+fn default_dir() -> PathBuf {
+    let var = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty());
+    if let Some(dir) = var(PARAMS_DIR_ENV) {
+        return dir.into();
+    }
+    var("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| var("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("zk-proof-system")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        sync::atomic::{AtomicU64, Ordering},
+    };
+
+    use halo2_proofs::pasta::EqAffine;
+
+    use super::*;
+
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    /// Fresh cache in the system temp directory, removed on drop
+    struct TempCache(ParamsCache<EqAffine>);
+
+    impl TempCache {
+        fn new(name: &str) -> Self {
+            Self(ParamsCache::open(temp_root(name)).unwrap())
+        }
+    }
+
+    impl Drop for TempCache {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(self.0.store.root());
+        }
+    }
+
+    fn temp_root(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "zk-params-{name}-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ))
+    }
+
+    #[test]
+    fn test_generates_once() {
+        let cache = TempCache::new("once");
+        assert!(!cache.0.verify(4).unwrap());
+
+        cache.0.prepare(3..=4).unwrap();
+        assert!(cache.0.verify(3).unwrap());
+        assert!(cache.0.verify(4).unwrap());
+
+        let first = cache.0.get(4).unwrap();
+        assert!(Arc::ptr_eq(&first, &cache.0.get(4).unwrap()));
+
+        // A second process reads the same params back from disk
+        let reopened = ParamsCache::<EqAffine>::open(cache.0.store.root()).unwrap();
+        assert_eq!(reopened.get(4).unwrap().get_g(), first.get_g());
+    }
+
+    #[test]
+    fn test_pinned_digest() {
+        let params = Params::<EqAffine>::new(3);
+        let digest = params_digest(&params).unwrap();
+        let mut bytes = Vec::new();
+        params.write(&mut bytes).unwrap();
+
+        let cache = TempCache::new("pinned");
+        let pinned = ParamsCache::<EqAffine>::open(cache.0.store.root())
+            .unwrap()
+            .pin(3, digest);
+        pinned.import(3, &bytes[..]).unwrap();
+        assert!(pinned.verify(3).unwrap());
+
+        let wrong = ParamsCache::<EqAffine>::open(cache.0.store.root())
+            .unwrap()
+            .pin(3, [0; 32]);
+        assert!(matches!(wrong.verify(3), Err(Error::Encoding(_))));
+        assert!(matches!(wrong.get(3), Err(Error::Encoding(_))));
+    }
+
+    #[test]
+    fn test_import_checks_k() {
+        let cache = TempCache::new("import");
+        let mut bytes = Vec::new();
+        Params::<EqAffine>::new(3).write(&mut bytes).unwrap();
+
+        assert!(matches!(
+            cache.0.import(4, &bytes[..]),
+            Err(Error::ParamsMismatch {
+                expected: 4,
+                found: 3
+            })
+        ));
+        assert!(matches!(
+            cache.0.import(3, &bytes[..bytes.len() / 2]),
+            Err(Error::Encoding(_))
+        ));
+        assert!(!cache.0.verify(3).unwrap());
+    }
+
+    #[test]
+    fn test_corrupt_file_rejected() {
+        let cache = TempCache::new("corrupt");
+        cache.0.get(3).unwrap();
+
+        let path = cache.0.store.params_path(3);
+        let mut bytes = fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        fs::write(&path, bytes).unwrap();
+        assert!(matches!(cache.0.verify(3), Err(Error::Encoding(_))));
+    }
+}
@@ -0,0 +1,106 @@
+//! Native Poseidon
+//!
+//! [`Poseidon`] computes off-circuit the same hashes
+//! [`crate::gadgets::PoseidonChip`] lays out, so witness generation can
+//! produce Merkle roots, commitments and nullifiers the circuit accepts.
+//! [`params`] generates the constants both use.
+
+pub mod params;
+
+use ff::PrimeField;
+
+use crate::gadgets::poseidon::{PoseidonConstants, PoseidonRounds, RATE, WIDTH};
+
+pub use params::{PoseidonParams, PoseidonSpec, P128_POW5_T3, P128_POW5_T9};
+
+/// Native Poseidon hash, matching [`crate::gadgets::PoseidonChip`]
+#[derive(Clone, Debug)]
+pub struct Poseidon<F> {
+    constants: PoseidonConstants<F>,
+}
+
+impl<F: PrimeField> Poseidon<F> {
+    /// Hasher for a chip configured with `rounds`
+    #[must_use]
+    pub fn new(rounds: PoseidonRounds) -> Self {
+        Self::with_constants(PoseidonConstants::new(rounds))
+    }
+
+    /// Hasher over already expanded `constants`
+    #[must_use]
+    pub fn with_constants(constants: PoseidonConstants<F>) -> Self {
+        Self { constants }
+    }
+
+    /// Constants of the permutation
+    #[must_use]
+    pub fn constants(&self) -> &PoseidonConstants<F> {
+        &self.constants
+    }
+
+    /// Hash `input` in constant-length mode
+    ///
+    /// Matches [`crate::gadgets::PoseidonChip::hash_many`]: the capacity
+    /// element starts at `input.len() * 2^64`, input is added to the rate
+    /// [`RATE`] elements at a time with the last chunk padded with zeros,
+    /// and an empty input hashes one chunk of zeros.
+    #[must_use]
+    pub fn hash(&self, input: &[F]) -> F {
+        let mut state = [F::ZERO; WIDTH];
+        state[RATE] = F::from_u128((input.len() as u128) << 64);
+        for chunk in input.chunks(RATE) {
+            for (element, value) in state.iter_mut().zip(chunk) {
+                *element += value;
+            }
+            state = self.constants.permute(state);
+        }
+        if input.is_empty() {
+            state = self.constants.permute(state);
+        }
+        state[0]
+    }
+
+    /// Two-to-one hash, matching [`crate::gadgets::PoseidonChip::hash`]
+    #[must_use]
+    pub fn hash_pair(&self, left: F, right: F) -> F {
+        self.hash(&[left, right])
+    }
+}
+
+impl<F: PrimeField> Default for Poseidon<F> {
+    fn default() -> Self {
+        Self::new(PoseidonRounds::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use pasta_curves::Fp;
+
+    use super::*;
+
+    #[test]
+    fn test_pair_is_one_permutation() {
+        let poseidon = Poseidon::<Fp>::default();
+        let (a, b) = (Fp::from(1), Fp::from(2));
+        assert_eq!(
+            poseidon.hash_pair(a, b),
+            poseidon.constants().permute([a, b, Fp::from_u128(2 << 64)])[0]
+        );
+        assert_ne!(poseidon.hash_pair(a, b), poseidon.hash_pair(b, a));
+    }
+
+    #[test]
+    fn test_length_is_bound() {
+        let poseidon = Poseidon::<Fp>::default();
+        // Zero padding does not collide with explicit zeros
+        assert_ne!(poseidon.hash(&[]), poseidon.hash(&[Fp::ZERO]));
+        assert_ne!(
+            poseidon.hash(&[Fp::ONE]),
+            poseidon.hash(&[Fp::ONE, Fp::ZERO])
+        );
+        let long: Vec<Fp> = (1..=5).map(Fp::from).collect();
+        assert_ne!(poseidon.hash(&long), poseidon.hash(&long[..4]));
+    }
+}
@@ -0,0 +1,551 @@
+//! Poseidon round constants and MDS matrices
+//!
+//! Parameters come from the Grain LFSR procedure of the Poseidon reference
+//! implementation (`generate_parameters_grain.sage`). The LFSR is seeded
+//! with the field size, state width and round counts and run in
+//! self-shrinking mode. Round constants are rejection-sampled from its
+//! output, and the MDS matrix is the Cauchy matrix `1 / (x_i + y_j)` of the
+//! next `2t` distinct elements. The reference also re-samples matrices that
+//! fail its subspace-trail checks; those checks are not run here.
+//!
+//! Round counts come from [`PoseidonSpec::secure`], the round-number
+//! search of the reference `calc_round_numbers.py` over the bounds of the
+//! Poseidon paper (Grassi et al., USENIX Security 2021, Section 5.5).
+//!
+//! Only power S-boxes `x^α` are supported.
+
+use ff::PrimeField;
+use pasta_curves::{pallas, vesta};
+
+use crate::error::{Error, Result};
+
+/// Bits in the Grain LFSR state
+const GRAIN_STATE_BITS: usize = 80;
+
+/// Output bits discarded after seeding the LFSR
+const GRAIN_WARMUP: usize = 160;
+
+/// Full rounds added on top of the attack bounds
+const FULL_ROUNDS_MARGIN: usize = 2;
+
+/// Partial rounds added on top of the attack bounds, in thousandths
+const PARTIAL_ROUNDS_MARGIN: usize = 75;
+
+/// Largest partial round count [`PoseidonSpec::secure`] tries
+const MAX_PARTIAL_ROUNDS: usize = 500;
+
+/// Largest full round count [`PoseidonSpec::secure`] tries
+const MAX_FULL_ROUNDS: usize = 100;
+
+/// State width, S-box and round counts of a Poseidon instance
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PoseidonSpec {
+    /// State width `t`
+    pub width: usize,
+    /// S-box exponent `α`
+    pub alpha: u64,
+    /// Number of full rounds, `R_F`
+    pub full_rounds: usize,
+    /// Number of partial rounds, `R_P`
+    pub partial_rounds: usize,
+}
+
+/// 128-bit Poseidon with `x^5` and width 3 over the Pasta fields
+///
+/// The instance `halo2_gadgets` calls `P128Pow5T3`, used for two-to-one
+/// hashing by [`crate::gadgets::PoseidonChip`]. Its round counts are
+/// `PoseidonSpec::secure(3, 5, 255, 128)`.
+pub const P128_POW5_T3: PoseidonSpec = PoseidonSpec {
+    width: 3,
+    alpha: 5,
+    full_rounds: 8,
+    partial_rounds: 56,
+};
+
+/// 128-bit Poseidon with `x^5` and width 9 over the Pasta fields
+///
+/// Absorbs eight elements per permutation, for wide Merkle trees and
+/// hashing long inputs natively. Its round counts are
+/// `PoseidonSpec::secure(9, 5, 255, 128)`.
+pub const P128_POW5_T9: PoseidonSpec = PoseidonSpec {
+    width: 9,
+    alpha: 5,
+    full_rounds: 8,
+    partial_rounds: 57,
+};
+
+impl PoseidonSpec {
+    /// Cheapest round counts for `security` bits with state width `width`
+    /// and `x^α` over a `field_bits`-bit field
+    ///
+    /// For each `R_P` takes the smallest even `R_F` meeting the paper's
+    /// statistical, interpolation and Gröbner basis bounds, adds its
+    /// margin of two full rounds and 7.5% more partial rounds, and keeps
+    /// the pair with the fewest S-boxes `t * R_F + R_P`, preferring fewer
+    /// full rounds on a tie.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Other`] if the width is below 2, `α` below 3, or no
+    /// pair below 500 partial and 100 full rounds is secure.
+    pub fn secure(width: usize, alpha: u64, field_bits: u32, security: u32) -> Result<Self> {
+        if width < 2 || alpha < 3 {
+            return Err(Error::Other(format!(
+                "no round counts for width {width} with x^{alpha}"
+            )));
+        }
+        let mut best: Option<(usize, Self)> = None;
+        for partial in 1..MAX_PARTIAL_ROUNDS {
+            let Some(full) = (4..MAX_FULL_ROUNDS)
+                .step_by(2)
+                .find(|full| resists_attacks(width, alpha, field_bits, security, *full, partial))
+            else {
+                continue;
+            };
+            let spec = Self {
+                width,
+                alpha,
+                full_rounds: full + FULL_ROUNDS_MARGIN,
+                partial_rounds: (partial * (1000 + PARTIAL_ROUNDS_MARGIN)).div_ceil(1000),
+            };
+            let cost = width * spec.full_rounds + spec.partial_rounds;
+            let cheaper = best.is_none_or(|(min, current)| {
+                cost < min || (cost == min && spec.full_rounds < current.full_rounds)
+            });
+            if cheaper {
+                best = Some((cost, spec));
+            }
+        }
+        best.map(|(_, spec)| spec).ok_or_else(|| {
+            Error::Other(format!(
+                "no secure round counts for width {width} with x^{alpha}"
+            ))
+        })
+    }
+}
+
+/// Whether `full` and `partial` rounds meet every bound of the Poseidon
+/// paper's Section 5.5, before its security margin
+///
+/// The field size stands in for `log2(p)`, as in the reference script for
+/// primes just above a power of two.
+#[allow(clippy::cast_precision_loss)]
+fn resists_attacks(
+    width: usize,
+    alpha: u64,
+    field_bits: u32,
+    security: u32,
+    full: usize,
+    partial: usize,
+) -> bool {
+    let (t, alpha, r_p) = (width as f64, alpha as f64, partial as f64);
+    let (m, n) = (f64::from(security), f64::from(field_bits));
+    let log_alpha_2 = 2f64.ln() / alpha.ln();
+
+    let statistical = if m <= (n - (alpha - 1.0) / 2.0).floor() * (t + 1.0) {
+        6.0
+    } else {
+        10.0
+    };
+    let interpolation = 1.0 + (log_alpha_2 * m.min(n)).ceil() + (t.ln() / alpha.ln()).ceil() - r_p;
+    let groebner_1 = log_alpha_2 * m.min(n) - r_p;
+    let groebner_2 = t - 1.0 + log_alpha_2 * (m / (t + 1.0)).min(n / 2.0) - r_p;
+    let groebner_3 = (t - 2.0 + m / (2.0 * alpha.log2()) - r_p) / (t - 1.0);
+    let needed = [
+        statistical,
+        interpolation,
+        groebner_1,
+        groebner_2,
+        groebner_3,
+    ]
+    .into_iter()
+    .map(f64::ceil)
+    .fold(f64::NEG_INFINITY, f64::max);
+    full as f64 >= needed
+}
+
+/// Round constants and MDS matrix for one [`PoseidonSpec`] over `F`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoseidonParams<F> {
+    spec: PoseidonSpec,
+    round_constants: Vec<Vec<F>>,
+    mds: Vec<Vec<F>>,
+}
+
+impl<F: PrimeField> PoseidonParams<F> {
+    /// Generate the parameters for `spec` over `F`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Other`] if the width is below 2, if `x^α` is not a
+    /// permutation of `F`, if the full rounds are odd or zero, or if a
+    /// count does not fit its field of the Grain seed.
+    pub fn generate(spec: PoseidonSpec) -> Result<Self> {
+        if spec.width < 2 || spec.width >= 1 << 12 {
+            return Err(Error::Other(format!(
+                "poseidon width must be in 2..4096, found {}",
+                spec.width
+            )));
+        }
+        if spec.alpha < 3 || gcd(spec.alpha, modulus_minus_one_mod::<F>(spec.alpha)) != 1 {
+            return Err(Error::Other(format!(
+                "x^{} is not a permutation of a field with modulus {}",
+                spec.alpha,
+                F::MODULUS
+            )));
+        }
+        if spec.full_rounds == 0 || spec.full_rounds % 2 != 0 || spec.full_rounds >= 1 << 10 {
+            return Err(Error::Other(format!(
+                "poseidon needs a positive even number of full rounds below 1024, found {}",
+                spec.full_rounds
+            )));
+        }
+        if spec.partial_rounds >= 1 << 10 {
+            return Err(Error::Other(format!(
+                "poseidon needs fewer than 1024 partial rounds, found {}",
+                spec.partial_rounds
+            )));
+        }
+        Ok(Self::expand(spec))
+    }
+
+    /// Run the Grain procedure for `spec` without checking it
+    pub(crate) fn expand(spec: PoseidonSpec) -> Self {
+        let modulus = modulus_minus_one_bits::<F>();
+        let mut grain = Grain::new(F::NUM_BITS, spec);
+        let round_constants = (0..spec.full_rounds + spec.partial_rounds)
+            .map(|_| {
+                (0..spec.width)
+                    .map(|_| grain.next_canonical(&modulus))
+                    .collect()
+            })
+            .collect();
+        let mds = grain.next_cauchy(spec.width);
+        Self {
+            spec,
+            round_constants,
+            mds,
+        }
+    }
+
+    /// Spec these parameters were generated for
+    #[must_use]
+    pub fn spec(&self) -> PoseidonSpec {
+        self.spec
+    }
+
+    /// Constants added to the state before each round, one row per round
+    #[must_use]
+    pub fn round_constants(&self) -> &[Vec<F>] {
+        &self.round_constants
+    }
+
+    /// MDS matrix applied after each round's S-boxes
+    #[must_use]
+    pub fn mds(&self) -> &[Vec<F>] {
+        &self.mds
+    }
+}
+
+/// [`P128_POW5_T3`] over the Pallas base field
+#[must_use]
+pub fn pallas_t3() -> PoseidonParams<pallas::Base> {
+    PoseidonParams::expand(P128_POW5_T3)
+}
+
+/// [`P128_POW5_T9`] over the Pallas base field
+#[must_use]
+pub fn pallas_t9() -> PoseidonParams<pallas::Base> {
+    PoseidonParams::expand(P128_POW5_T9)
+}
+
+/// [`P128_POW5_T3`] over the Vesta base field
+#[must_use]
+pub fn vesta_t3() -> PoseidonParams<vesta::Base> {
+    PoseidonParams::expand(P128_POW5_T3)
+}
+
+/// [`P128_POW5_T9`] over the Vesta base field
+#[must_use]
+pub fn vesta_t9() -> PoseidonParams<vesta::Base> {
+    PoseidonParams::expand(P128_POW5_T9)
+}
+
+/// Grain LFSR, stepped in self-shrinking mode after warm-up
+struct Grain {
+    state: [bool; GRAIN_STATE_BITS],
+    /// Index of the oldest bit, which the next step overwrites
+    head: usize,
+}
+
+impl Grain {
+    fn new(field_bits: u32, spec: PoseidonSpec) -> Self {
+        let mut seed = Vec::with_capacity(GRAIN_STATE_BITS);
+        let mut push = |value: u64, bits: u32| {
+            seed.extend((0..bits).rev().map(|i| (value >> i) & 1 == 1));
+        };
+        // Prime field, power S-box
+        push(1, 2);
+        push(0, 4);
+        push(u64::from(field_bits), 12);
+        push(spec.width as u64, 12);
+        push(spec.full_rounds as u64, 10);
+        push(spec.partial_rounds as u64, 10);
+        push((1 << 30) - 1, 30);
+
+        let mut grain = Self {
+            state: seed.try_into().expect("seed fills the state"),
+            head: 0,
+        };
+        for _ in 0..GRAIN_WARMUP {
+            grain.step();
+        }
+        grain
+    }
+
+    fn step(&mut self) -> bool {
+        let tap = |i: usize| self.state[(self.head + i) % GRAIN_STATE_BITS];
+        let bit = tap(62) ^ tap(51) ^ tap(38) ^ tap(23) ^ tap(13) ^ tap(0);
+        self.state[self.head] = bit;
+        self.head = (self.head + 1) % GRAIN_STATE_BITS;
+        bit
+    }
+
+    /// Next bit of the self-shrunk output: of each pair, the second bit is
+    /// kept if the first is set
+    fn next_bit(&mut self) -> bool {
+        loop {
+            let keep = self.step();
+            let bit = self.step();
+            if keep {
+                return bit;
+            }
+        }
+    }
+
+    /// Next `F::NUM_BITS` bits, most significant first
+    fn next_bits<F: PrimeField>(&mut self) -> Vec<bool> {
+        (0..F::NUM_BITS).map(|_| self.next_bit()).collect()
+    }
+
+    /// Next element below the modulus, skipping draws at or above it
+    fn next_canonical<F: PrimeField>(&mut self, modulus_minus_one: &[bool]) -> F {
+        loop {
+            let bits = self.next_bits::<F>();
+            // Same length, so lexicographic order is numeric order
+            if bits.as_slice() <= modulus_minus_one {
+                return from_bits(&bits);
+            }
+        }
+    }
+
+    /// Cauchy matrix `1 / (x_i + y_j)` of the next `2 * width` elements,
+    /// reduced modulo the modulus, drawing again until they are distinct
+    /// and no `x_i + y_j` is zero
+    fn next_cauchy<F: PrimeField>(&mut self, width: usize) -> Vec<Vec<F>> {
+        loop {
+            let elements: Vec<F> = (0..2 * width)
+                .map(|_| from_bits(&self.next_bits::<F>()))
+                .collect();
+            let distinct = elements
+                .iter()
+                .enumerate()
+                .all(|(i, a)| elements[i + 1..].iter().all(|b| a != b));
+            let (xs, ys) = elements.split_at(width);
+            let mds: Option<Vec<Vec<F>>> = xs
+                .iter()
+                .map(|x| ys.iter().map(|y| Option::from((*x + y).invert())).collect())
+                .collect();
+            if let Some(mds) = mds.filter(|_| distinct) {
+                return mds;
+            }
+        }
+    }
+}
+
+/// Big-endian `bits` reduced modulo the modulus
+fn from_bits<F: PrimeField>(bits: &[bool]) -> F {
+    bits.iter()
+        .fold(F::ZERO, |acc, bit| acc.double() + F::from(u64::from(*bit)))
+}
+
+/// Bits of `p - 1`, most significant first, `F::NUM_BITS` of them
+///
+/// Read off the field element `-1` by parity and halving, so it does not
+/// depend on the byte order of `F::Repr`.
+fn modulus_minus_one_bits<F: PrimeField>() -> Vec<bool> {
+    let mut value = -F::ONE;
+    let mut bits: Vec<bool> = (0..F::NUM_BITS)
+        .map(|_| {
+            let bit = bool::from(value.is_odd());
+            if bit {
+                value -= F::ONE;
+            }
+            value *= F::TWO_INV;
+            bit
+        })
+        .collect();
+    bits.reverse();
+    bits
+}
+
+/// `(p - 1) mod m`
+fn modulus_minus_one_mod<F: PrimeField>(m: u64) -> u64 {
+    let m = u128::from(m);
+    let rem = modulus_minus_one_bits::<F>()
+        .into_iter()
+        .fold(0, |acc, bit| (acc * 2 + u128::from(bit)) % m);
+    u64::try_from(rem).expect("remainder is below a u64 modulus")
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use pasta_curves::Fp;
+
+    use super::*;
+
+    #[test]
+    fn test_standard_sets_shapes() {
+        for (params, spec) in [(pallas_t3(), P128_POW5_T3), (pallas_t9(), P128_POW5_T9)] {
+            assert_eq!(params.spec(), spec);
+            assert_eq!(
+                params.round_constants().len(),
+                spec.full_rounds + spec.partial_rounds
+            );
+            assert!(params
+                .round_constants()
+                .iter()
+                .all(|round| round.len() == spec.width));
+            assert_eq!(params.mds().len(), spec.width);
+            assert!(params.mds().iter().all(|row| row.len() == spec.width));
+        }
+        assert_eq!(vesta_t9().mds().len(), 9);
+    }
+
+    #[test]
+    fn test_generation_is_deterministic_and_seeded() {
+        assert_eq!(pallas_t3(), PoseidonParams::generate(P128_POW5_T3).unwrap());
+        let longer = PoseidonParams::<Fp>::generate(PoseidonSpec {
+            partial_rounds: 58,
+            ..P128_POW5_T3
+        })
+        .unwrap();
+        assert_ne!(
+            longer.round_constants()[0],
+            pallas_t3().round_constants()[0]
+        );
+
+        // Same spec, different field
+        let pallas = pallas_t3().round_constants()[0][0].to_repr();
+        let vesta = vesta_t3().round_constants()[0][0].to_repr();
+        assert_ne!(pallas, vesta);
+    }
+
+    #[test]
+    fn test_mds_is_cauchy() {
+        // Every entry of a Cauchy matrix is nonzero and every 2x2 minor is
+        // nonsingular
+        let mds = pallas_t9();
+        let mds = mds.mds();
+        for i in 0..9 {
+            for j in i + 1..9 {
+                for (k, l) in [(0, 1), (3, 8)] {
+                    assert_ne!(mds[i][k] * mds[j][l] - mds[i][l] * mds[j][k], Fp::ZERO);
+                }
+            }
+        }
+        assert!(mds
+            .iter()
+            .flatten()
+            .all(|entry| !bool::from(entry.is_zero())));
+    }
+
+    #[test]
+    fn test_modulus_bits() {
+        let bits = modulus_minus_one_bits::<Fp>();
+        assert_eq!(bits.len(), 255);
+        assert!(bits[0]);
+        assert_eq!(from_bits::<Fp>(&bits), -Fp::ONE);
+        // Pasta moduli are 1 mod 2^32 and 1 mod 3
+        assert_eq!(modulus_minus_one_mod::<Fp>(1 << 32), 0);
+        assert_eq!(modulus_minus_one_mod::<Fp>(3), 0);
+    }
+
+    #[test]
+    fn test_round_numbers_match_paper() {
+        assert_eq!(PoseidonSpec::secure(3, 5, 255, 128).unwrap(), P128_POW5_T3);
+        assert_eq!(PoseidonSpec::secure(9, 5, 255, 128).unwrap(), P128_POW5_T9);
+        // Wider states need more rounds
+        let width_12 = PoseidonSpec::secure(12, 5, 255, 128).unwrap();
+        assert_eq!((width_12.full_rounds, width_12.partial_rounds), (8, 57));
+        let weaker = PoseidonSpec::secure(3, 5, 255, 80).unwrap();
+        assert!(weaker.partial_rounds < P128_POW5_T3.partial_rounds);
+        assert!(PoseidonSpec::secure(1, 5, 255, 128).is_err());
+    }
+
+    #[test]
+    fn test_t3_matches_halo2_gadgets() {
+        use halo2_gadgets::poseidon::primitives::{P128Pow5T3, Spec};
+
+        fn check<F>(params: &PoseidonParams<F>)
+        where
+            F: PrimeField,
+            P128Pow5T3: Spec<F, 3, 2>,
+        {
+            assert_eq!(
+                <P128Pow5T3 as Spec<F, 3, 2>>::full_rounds(),
+                P128_POW5_T3.full_rounds
+            );
+            assert_eq!(
+                <P128Pow5T3 as Spec<F, 3, 2>>::partial_rounds(),
+                P128_POW5_T3.partial_rounds
+            );
+            let (round_constants, mds, _) = <P128Pow5T3 as Spec<F, 3, 2>>::constants();
+            assert_eq!(round_constants.len(), params.round_constants().len());
+            for (expected, found) in round_constants.iter().zip(params.round_constants()) {
+                assert_eq!(expected.as_slice(), found.as_slice());
+            }
+            for (expected, found) in mds.iter().zip(params.mds()) {
+                assert_eq!(expected.as_slice(), found.as_slice());
+            }
+        }
+
+        check(&pallas_t3());
+        check(&vesta_t3());
+    }
+
+    #[test]
+    fn test_invalid_specs_rejected() {
+        for spec in [
+            PoseidonSpec {
+                alpha: 3,
+                ..P128_POW5_T3
+            },
+            PoseidonSpec {
+                width: 1,
+                ..P128_POW5_T3
+            },
+            PoseidonSpec {
+                full_rounds: 7,
+                ..P128_POW5_T3
+            },
+            PoseidonSpec {
+                partial_rounds: 1024,
+                ..P128_POW5_T3
+            },
+        ] {
+            assert!(matches!(
+                PoseidonParams::<Fp>::generate(spec),
+                Err(Error::Other(_))
+            ));
+        }
+    }
+}
@@ -1,21 +1,1379 @@
 //! Proof generation and management
+//!
+//! A [`crate::Prover`] turns a circuit into a [`Proof`] tagged with the
+//! circuit id, `k` and a hash of its public inputs, so a proof can be routed
+//! and matched to its statement before anyone runs the verifier.
+//!
+//! [`Proof::to_bytes`] records the curve, `k` and public input count
+//! alongside the proof, so a reader can reject a proof for the wrong
+//! statement shape before decoding the transcript.
+//!
+//! A [`Proof`] also carries its provenance: the verifying key hash, the
+//! prover's crate version and creation time, and optionally a
+//! [`ProofSignature`] by the proving service over all of it, so audits can
+//! attribute every proof to a prover build. [`Proof::to_cbor`] writes the
+//! deterministic CBOR encoding the signature covers.
+//!
+//! Proofs leave the prover wrapped in a [`ProofEnvelope`] that records which
+//! verifying key they were created for. Verifiers check that hash before
+//! running the verifier, so a deployment with a drifted key fails with
+//! [`Error::VkMismatch`] instead of an opaque transcript error.
+//!
+//! Envelopes may also carry [`ProofMetadata`] for auditing. Metadata is not
+//! part of the proved statement: it is never bound into the transcript, so
+//! anyone relaying a proof can strip or rewrite it. The circuit version is
+//! the exception, see [`crate::circuit_version`].
+//!
+//! Every encoding from [`MIN_ENVELOPE_VERSION`] to [`ENVELOPE_VERSION`] stays
+//! readable. [`migrate`] re-encodes an issued proof at another version and
+//! [`negotiate_version`] picks the newest version two parties both support.
+//!
+//! [`write_proof`] streams the transcript into any writer while proving, so
+//! large recursive proofs never have to sit in memory in full.
 
+use std::{
+    fmt,
+    io::Write,
+    ops::RangeInclusive,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-/// Proof structure
-#[derive(Clone, Debug)]
+use ff::{FromUniformBytes, PrimeField};
+use group::GroupEncoding;
+use halo2_proofs::{
+    arithmetic::{CurveAffine, CurveExt},
+    plonk::{create_proof, Circuit, Error as PlonkError, ProvingKey, VerifyingKey},
+    poly::commitment::Params,
+    transcript::{Blake2bWrite, Challenge255, Transcript},
+};
+use pasta_curves::pallas;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::{
+    error::{Error, Result},
+    gadgets::schnorr::{self, SchnorrSignature},
+    rng::Randomness,
+    utils::{
+        cbor::Cbor,
+        encoding::{decode_varint, encode_hex, encode_varint},
+    },
+};
+
+/// Tag at the start of every encoded envelope
+const ENVELOPE_MAGIC: &[u8; 4] = b"ZKPE";
+
+/// Current envelope encoding version
+///
+/// Version 1 had no metadata section.
+pub const ENVELOPE_VERSION: u16 = 2;
+
+/// Oldest envelope encoding version that can still be read
+pub const MIN_ENVELOPE_VERSION: u16 = 1;
+
+/// Longest accepted metadata string
+pub const MAX_METADATA_FIELD_LEN: usize = 256;
+
+const TAG_CRATE_VERSION: u8 = 1;
+const TAG_CIRCUIT_VERSION: u8 = 2;
+const TAG_CREATED_AT: u8 = 3;
+const TAG_PROVER_ID: u8 = 4;
+const TAG_CIRCUIT_ID: u8 = 5;
+
+/// Tag at the start of every encoded [`Proof`]
+const PROOF_MAGIC: &[u8; 4] = b"ZKPF";
+
+/// Current [`Proof`] encoding version
+///
+/// Version 1 had no provenance section.
+pub const PROOF_FORMAT_VERSION: u16 = 2;
+
+/// Oldest [`Proof`] encoding version that can still be read
+pub const MIN_PROOF_FORMAT_VERSION: u16 = 1;
+
+/// Domain separator for [`hash_public_inputs`]
+const PUBLIC_INPUT_DOMAIN: &[u8] = b"zk-proof-system public inputs";
+
+/// Domain separator for the message a [`ProofSignature`] signs
+const SIGNATURE_DOMAIN: &[u8] = b"zk-proof-system proof signature";
+
+// Keys of the CBOR map written by `Proof::to_cbor`
+const FIELD_DATA: u64 = 0;
+const FIELD_CIRCUIT_ID: u64 = 1;
+const FIELD_CURVE: u64 = 2;
+const FIELD_K: u64 = 3;
+const FIELD_PUBLIC_INPUT_COUNT: u64 = 4;
+const FIELD_PUBLIC_INPUT_HASH: u64 = 5;
+const FIELD_VK_HASH: u64 = 6;
+const FIELD_METADATA: u64 = 7;
+const FIELD_SIGNATURE: u64 = 8;
+
+// Keys of the CBOR map of a `ProofSignature`
+const FIELD_SIGNER: u64 = 0;
+const FIELD_NONCE: u64 = 1;
+const FIELD_RESPONSE: u64 = 2;
+
+/// A proof with the statement it was created for
+///
+/// Produced by [`crate::Prover`]. Everything but the proof bytes is copied
+/// from the prover and, like [`ProofMetadata`], is not bound into the proof.
+/// A [`ProofSignature`] binds it to the proving service instead.
+///
+/// [`Proof::to_bytes`] writes a self-describing encoding that readers of any
+/// later format version still accept. With the `serde` feature the struct
+/// also works with any serde format, such as JSON or bincode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Proof {
-    /// Serialized proof data
+    /// Serialized halo2 proof
     pub data: Vec<u8>,
+    /// Identifier of the circuit the proof was created for
+    pub circuit_id: String,
+    /// Curve the proof commits over, such as `vesta`
+    pub curve: String,
+    /// Log2 of the number of rows of the circuit
+    pub k: u32,
+    /// Number of public inputs across all instance columns
+    pub public_input_count: usize,
+    /// [`hash_public_inputs`] of the instances the proof was created with
+    pub public_input_hash: [u8; 32],
+    /// Hash of the verifying key the proof was created for
+    pub vk_hash: Option<VkHash>,
+    /// Provenance, stamped with the prover's crate version and the time
+    pub metadata: ProofMetadata,
+    /// Signature by the proving service, see [`Proof::sign`]
+    pub signature: Option<ProofSignature>,
 }
 
 impl Proof {
-    /// Create a new proof
-    #[must_use] pub fn new(data: Vec<u8>) -> Self {
-        Self { data }
+    /// Wrap proof bytes created over `C` for `circuit_id` at `k` with
+    /// `instances`
+    #[must_use]
+    pub fn new<C: CurveAffine>(
+        data: Vec<u8>,
+        circuit_id: impl Into<String>,
+        k: u32,
+        instances: &[Vec<C::Scalar>],
+    ) -> Self {
+        Self {
+            data,
+            circuit_id: circuit_id.into(),
+            curve: <C::CurveExt as CurveExt>::CURVE_ID.to_string(),
+            k,
+            public_input_count: instances.iter().map(Vec::len).sum(),
+            public_input_hash: hash_public_inputs(instances),
+            vk_hash: None,
+            metadata: ProofMetadata::now(),
+            signature: None,
+        }
+    }
+
+    /// Record the hash of the verifying key the proof was created for
+    #[must_use]
+    pub fn with_vk_hash(mut self, vk_hash: VkHash) -> Self {
+        self.vk_hash = Some(vk_hash);
+        self
+    }
+
+    /// Replace the metadata
+    #[must_use]
+    pub fn with_metadata(mut self, metadata: ProofMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Whether the proof was created with `instances`
+    #[must_use]
+    pub fn matches_public_inputs<F: PrimeField>(&self, instances: &[Vec<F>]) -> bool {
+        self.public_input_hash == hash_public_inputs(instances)
+    }
+
+    /// Encode at [`PROOF_FORMAT_VERSION`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if a field is too long for the format.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        self.to_bytes_versioned(PROOF_FORMAT_VERSION)
+    }
+
+    /// Encode as magic, format version, curve id, `k`, public input count,
+    /// circuit id, public input hash, length-prefixed proof and
+    /// length-prefixed provenance
+    ///
+    /// The provenance is the CBOR map of [`Proof::to_cbor`] holding only the
+    /// vk hash, metadata and signature. Version 1 has no provenance section,
+    /// so they are dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if `version` is not supported or a field
+    /// is too long for the format.
+    pub fn to_bytes_versioned(&self, version: u16) -> Result<Vec<u8>> {
+        if !(MIN_PROOF_FORMAT_VERSION..=PROOF_FORMAT_VERSION).contains(&version) {
+            return Err(unsupported_format(version));
+        }
+        let too_long = |field: &str| Error::Encoding(format!("proof {field} is too long"));
+        let curve = u8::try_from(self.curve.len()).map_err(|_| too_long("curve id"))?;
+        let count =
+            u32::try_from(self.public_input_count).map_err(|_| too_long("public input count"))?;
+        if self.circuit_id.len() > MAX_METADATA_FIELD_LEN {
+            return Err(too_long("circuit id"));
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        let circuit_id = self.circuit_id.len() as u16;
+        let len = u32::try_from(self.data.len()).map_err(|_| too_long("data"))?;
+
+        let mut out =
+            Vec::with_capacity(53 + self.curve.len() + self.circuit_id.len() + self.data.len());
+        out.extend_from_slice(PROOF_MAGIC);
+        out.extend_from_slice(&version.to_le_bytes());
+        out.push(curve);
+        out.extend_from_slice(self.curve.as_bytes());
+        out.extend_from_slice(&self.k.to_le_bytes());
+        out.extend_from_slice(&count.to_le_bytes());
+        out.extend_from_slice(&circuit_id.to_le_bytes());
+        out.extend_from_slice(self.circuit_id.as_bytes());
+        out.extend_from_slice(&self.public_input_hash);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&self.data);
+
+        if version >= 2 {
+            let provenance = Cbor::Map(self.provenance(true)).to_vec();
+            let provenance_len =
+                u32::try_from(provenance.len()).map_err(|_| too_long("provenance"))?;
+            out.extend_from_slice(&provenance_len.to_le_bytes());
+            out.extend_from_slice(&provenance);
+        }
+        Ok(out)
+    }
+
+    /// Decode a proof written by [`Proof::to_bytes_versioned`]
+    ///
+    /// Version 1 proofs decode with no vk hash, signature or metadata.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if the magic, version or lengths are
+    /// wrong or a text field is not UTF-8.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let (magic, rest) = split(bytes, 4)?;
+        if magic != PROOF_MAGIC {
+            return Err(Error::Encoding("not an encoded proof".into()));
+        }
+        let (version, rest) = split(rest, 2)?;
+        let version = u16::from_le_bytes([version[0], version[1]]);
+        if !(MIN_PROOF_FORMAT_VERSION..=PROOF_FORMAT_VERSION).contains(&version) {
+            return Err(unsupported_format(version));
+        }
+
+        let text = |bytes: &[u8]| {
+            String::from_utf8(bytes.to_vec())
+                .map_err(|_| Error::Encoding("proof field is not UTF-8".into()))
+        };
+        let (curve_len, rest) = split(rest, 1)?;
+        let (curve, rest) = split(rest, usize::from(curve_len[0]))?;
+        let (k, rest) = split(rest, 4)?;
+        let (count, rest) = split(rest, 4)?;
+        let (circuit_id_len, rest) = split(rest, 2)?;
+        let circuit_id_len =
+            usize::from(u16::from_le_bytes([circuit_id_len[0], circuit_id_len[1]]));
+        if circuit_id_len > MAX_METADATA_FIELD_LEN {
+            return Err(Error::Encoding(format!(
+                "circuit id of {circuit_id_len} bytes"
+            )));
+        }
+        let (circuit_id, rest) = split(rest, circuit_id_len)?;
+        let (hash, rest) = split(rest, 32)?;
+        let (data, rest) = split_prefixed(rest)?;
+        let (provenance, rest) = if version >= 2 {
+            split_prefixed(rest)?
+        } else {
+            (&[][..], rest)
+        };
+        if !rest.is_empty() {
+            return Err(Error::Encoding(format!(
+                "{} trailing bytes after proof",
+                rest.len()
+            )));
+        }
+
+        let mut public_input_hash = [0u8; 32];
+        public_input_hash.copy_from_slice(hash);
+        let mut proof = Self {
+            data: data.to_vec(),
+            circuit_id: text(circuit_id)?,
+            curve: text(curve)?,
+            k: u32::from_le_bytes([k[0], k[1], k[2], k[3]]),
+            public_input_count: u32::from_le_bytes([count[0], count[1], count[2], count[3]])
+                as usize,
+            public_input_hash,
+            vk_hash: None,
+            metadata: ProofMetadata::default(),
+            signature: None,
+        };
+        if version >= 2 {
+            for (key, value) in Cbor::decode(provenance)?.as_map("proof provenance")? {
+                proof.set_provenance(*key, value)?;
+            }
+        }
+        Ok(proof)
+    }
+
+    /// Deterministic CBOR encoding of every field
+    ///
+    /// The proof is a map from small integer keys to its fields, with the
+    /// vk hash, metadata and signature left out when unset. Equal proofs
+    /// always encode to the same bytes.
+    #[must_use]
+    pub fn to_cbor(&self) -> Vec<u8> {
+        self.cbor(true)
+    }
+
+    /// Decode a proof written by [`Proof::to_cbor`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if `bytes` is not the deterministic
+    /// encoding of a proof, a field is missing or has the wrong type, or a
+    /// field is unknown.
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self> {
+        let value = Cbor::decode(bytes)?;
+        let entries = value.as_map("proof")?;
+        let field = |key: u64| {
+            entries
+                .iter()
+                .find(|(found, _)| *found == key)
+                .map(|(_, value)| value)
+                .ok_or_else(|| Error::Encoding(format!("proof field {key} is missing")))
+        };
+
+        let mut proof = Self {
+            data: field(FIELD_DATA)?.as_bytes("proof data")?.to_vec(),
+            circuit_id: field(FIELD_CIRCUIT_ID)?.as_text("circuit id")?.to_string(),
+            curve: field(FIELD_CURVE)?.as_text("curve")?.to_string(),
+            k: u32::try_from(field(FIELD_K)?.as_uint("k")?)
+                .map_err(|_| Error::Encoding("k does not fit in u32".into()))?,
+            public_input_count: usize::try_from(
+                field(FIELD_PUBLIC_INPUT_COUNT)?.as_uint("public input count")?,
+            )
+            .map_err(|_| Error::Encoding("public input count does not fit in usize".into()))?,
+            public_input_hash: hash_bytes(
+                field(FIELD_PUBLIC_INPUT_HASH)?.as_bytes("public input hash")?,
+                "public input hash",
+            )?,
+            vk_hash: None,
+            metadata: ProofMetadata::default(),
+            signature: None,
+        };
+        for (key, value) in entries {
+            if *key > FIELD_PUBLIC_INPUT_HASH {
+                proof.set_provenance(*key, value)?;
+            }
+        }
+        Ok(proof)
+    }
+
+    /// Bytes a [`ProofSignature`] covers: [`Proof::to_cbor`] without the
+    /// signature
+    #[must_use]
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        self.cbor(false)
+    }
+
+    /// Sign every other field with the proving service's Pallas key,
+    /// replacing any earlier signature
+    ///
+    /// Changing any field afterwards, including the metadata, invalidates
+    /// the signature.
+    pub fn sign(&mut self, secret_key: pallas::Scalar, randomness: impl Randomness) {
+        let signature =
+            schnorr::sign::<pallas::Affine>(secret_key, self.signing_message(), randomness);
+        self.signature = Some(ProofSignature {
+            signer: ProofSignature::public_key(secret_key),
+            r: signature.r.to_bytes(),
+            s: signature.s.to_repr(),
+        });
+    }
+
+    /// Check that `signer` signed the proof as it stands
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Verification`] if the proof is unsigned, signed by
+    /// another key, or the signature does not cover the current fields.
+    pub fn verify_signature(&self, signer: &[u8; 32]) -> Result<()> {
+        let signature = self
+            .signature
+            .as_ref()
+            .ok_or_else(|| Error::Verification("proof is not signed".into()))?;
+        if signature.signer != *signer {
+            return Err(Error::Verification(format!(
+                "proof is signed by {}, expected {}",
+                encode_hex(&signature.signer),
+                encode_hex(signer)
+            )));
+        }
+        if !signature.verify(self.signing_message()) {
+            return Err(Error::Verification("invalid proof signature".into()));
+        }
+        Ok(())
+    }
+
+    /// Map entries of the vk hash, metadata and, if `signed`, signature
+    fn provenance(&self, signed: bool) -> Vec<(u64, Cbor<'_>)> {
+        let mut entries = Vec::new();
+        if let Some(vk_hash) = &self.vk_hash {
+            entries.push((FIELD_VK_HASH, Cbor::Bytes(vk_hash.as_bytes())));
+        }
+        if !self.metadata.is_empty() {
+            entries.push((FIELD_METADATA, self.metadata.to_cbor()));
+        }
+        if let (true, Some(signature)) = (signed, &self.signature) {
+            entries.push((FIELD_SIGNATURE, signature.to_cbor()));
+        }
+        entries
+    }
+
+    fn cbor(&self, signed: bool) -> Vec<u8> {
+        let mut entries = vec![
+            (FIELD_DATA, Cbor::Bytes(&self.data)),
+            (FIELD_CIRCUIT_ID, Cbor::Text(&self.circuit_id)),
+            (FIELD_CURVE, Cbor::Text(&self.curve)),
+            (FIELD_K, Cbor::Uint(self.k.into())),
+            (FIELD_PUBLIC_INPUT_COUNT, Cbor::Uint(self.public_input_count as u64)),
+            (FIELD_PUBLIC_INPUT_HASH, Cbor::Bytes(&self.public_input_hash)),
+        ];
+        entries.extend(self.provenance(signed));
+        Cbor::Map(entries).to_vec()
+    }
+
+    /// Set the provenance field under `key`
+    fn set_provenance(&mut self, key: u64, value: &Cbor<'_>) -> Result<()> {
+        match key {
+            FIELD_VK_HASH => {
+                self.vk_hash = Some(VkHash(hash_bytes(value.as_bytes("vk hash")?, "vk hash")?));
+            }
+            FIELD_METADATA => {
+                self.metadata = ProofMetadata::from_cbor(value)?;
+                // Empty metadata is written by leaving the field out
+                if self.metadata.is_empty() {
+                    return Err(Error::Encoding("proof metadata is empty".into()));
+                }
+            }
+            FIELD_SIGNATURE => self.signature = Some(ProofSignature::from_cbor(value)?),
+            _ => return Err(Error::Encoding(format!("unknown proof field {key}"))),
+        }
+        Ok(())
+    }
+
+    /// Field element the signature signs, hashed from
+    /// [`Proof::signing_bytes`]
+    fn signing_message(&self) -> pallas::Base {
+        let digest = Sha512::new()
+            .chain_update(SIGNATURE_DOMAIN)
+            .chain_update(self.signing_bytes())
+            .finalize();
+        let mut wide = [0u8; 64];
+        wide.copy_from_slice(&digest);
+        pallas::Base::from_uniform_bytes(&wide)
+    }
+}
+
+/// Schnorr signature by a proving service over [`Proof::signing_bytes`]
+///
+/// Signatures are by Pallas keys, as in [`crate::gadgets::schnorr`], so a
+/// circuit can check them too.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ProofSignature {
+    /// Compressed public key of the service
+    pub signer: [u8; 32],
+    /// Compressed nonce commitment `R`
+    pub r: [u8; 32],
+    /// Little-endian response `s`
+    pub s: [u8; 32],
+}
+
+impl ProofSignature {
+    /// Compressed public key of `secret_key`, as found in
+    /// [`ProofSignature::signer`]
+    #[must_use]
+    pub fn public_key(secret_key: pallas::Scalar) -> [u8; 32] {
+        schnorr::public_key::<pallas::Affine>(secret_key).to_bytes()
+    }
+
+    /// Whether the signature is valid on `message`
+    fn verify(&self, message: pallas::Base) -> bool {
+        let signer: Option<pallas::Affine> = pallas::Affine::from_bytes(&self.signer).into();
+        let r: Option<pallas::Affine> = pallas::Affine::from_bytes(&self.r).into();
+        let s: Option<pallas::Scalar> = pallas::Scalar::from_repr(self.s).into();
+        match (signer, r, s) {
+            (Some(signer), Some(r), Some(s)) => {
+                schnorr::verify(&signer, message, &SchnorrSignature { r, s })
+            }
+            _ => false,
+        }
+    }
+
+    fn to_cbor(&self) -> Cbor<'_> {
+        Cbor::Map(vec![
+            (FIELD_SIGNER, Cbor::Bytes(&self.signer)),
+            (FIELD_NONCE, Cbor::Bytes(&self.r)),
+            (FIELD_RESPONSE, Cbor::Bytes(&self.s)),
+        ])
+    }
+
+    fn from_cbor(value: &Cbor<'_>) -> Result<Self> {
+        match value.as_map("proof signature")? {
+            [(FIELD_SIGNER, signer), (FIELD_NONCE, r), (FIELD_RESPONSE, s)] => Ok(Self {
+                signer: hash_bytes(signer.as_bytes("signer")?, "signer")?,
+                r: hash_bytes(r.as_bytes("signature nonce")?, "signature nonce")?,
+                s: hash_bytes(s.as_bytes("signature response")?, "signature response")?,
+            }),
+            _ => Err(Error::Encoding(
+                "proof signature must hold a signer, nonce and response".into(),
+            )),
+        }
+    }
+}
+
+/// `bytes` as a 32-byte array
+fn hash_bytes(bytes: &[u8], field: &str) -> Result<[u8; 32]> {
+    bytes
+        .try_into()
+        .map_err(|_| Error::Encoding(format!("{field} must be 32 bytes")))
+}
+
+fn unsupported_format(version: u16) -> Error {
+    Error::Encoding(format!(
+        "unsupported proof format version {version}, \
+         supported {MIN_PROOF_FORMAT_VERSION}..={PROOF_FORMAT_VERSION}"
+    ))
+}
+
+/// SHA-256 of instance columns, including the length of each column
+#[must_use]
+pub fn hash_public_inputs<F: PrimeField>(instances: &[Vec<F>]) -> [u8; 32] {
+    let mut hasher = Sha256::new().chain_update(PUBLIC_INPUT_DOMAIN);
+    hasher.update((instances.len() as u64).to_le_bytes());
+    for column in instances {
+        hasher.update((column.len() as u64).to_le_bytes());
+        for value in column {
+            hasher.update(value.to_repr());
+        }
+    }
+    hasher.finalize().into()
+}
+
+/// Prove `circuits`, writing the proof to `writer` as it is produced
+///
+/// Every commitment and evaluation is written as soon as its prover round
+/// completes, so the proof is never buffered as a whole. Writes are not
+/// batched: wrap sockets and files in a [`std::io::BufWriter`]. The writer is
+/// flushed and handed back once the proof is complete.
+///
+/// # Errors
+///
+/// Returns [`Error::Io`] if writing fails, leaving a truncated proof behind,
+/// [`Error::Timeout`] if `writer` is a [`crate::timeout::Timed`] whose
+/// deadline passed, and [`Error::Plonk`] if proving fails.
+pub fn write_proof<C, ConcreteCircuit, W>(
+    params: &Params<C>,
+    pk: &ProvingKey<C>,
+    circuits: &[ConcreteCircuit],
+    instances: &[&[&[C::Scalar]]],
+    randomness: impl Randomness,
+    writer: W,
+) -> Result<W>
+where
+    C: CurveAffine,
+    C::Scalar: FromUniformBytes<64>,
+    ConcreteCircuit: Circuit<C::Scalar>,
+    W: Write,
+{
+    write_bound_proof(params, pk, circuits, instances, None, randomness, writer)
+}
+
+/// [`write_proof`], first absorbing `binding` into the transcript
+///
+/// The binding only affects challenges and is not written out, so a verifier
+/// must absorb the same scalar before verifying.
+pub(crate) fn write_bound_proof<C, ConcreteCircuit, W>(
+    params: &Params<C>,
+    pk: &ProvingKey<C>,
+    circuits: &[ConcreteCircuit],
+    instances: &[&[&[C::Scalar]]],
+    binding: Option<C::Scalar>,
+    randomness: impl Randomness,
+    writer: W,
+) -> Result<W>
+where
+    C: CurveAffine,
+    C::Scalar: FromUniformBytes<64>,
+    ConcreteCircuit: Circuit<C::Scalar>,
+    W: Write,
+{
+    let mut transcript = Blake2bWrite::<_, C, Challenge255<_>>::init(writer);
+    if let Some(binding) = binding {
+        transcript.common_scalar(binding)?;
+    }
+    create_proof(
+        params,
+        pk,
+        circuits,
+        instances,
+        randomness.rng(),
+        &mut transcript,
+    )
+    .map_err(|err| match err {
+        PlonkError::Transcript(err) => crate::timeout::transcript_error(err),
+        err => Error::Plonk(err),
+    })?;
+    let mut writer = transcript.finalize();
+    writer.flush()?;
+    Ok(writer)
+}
+
+/// SHA-256 fingerprint of a verifying key
+///
+/// Computed over the same pinned representation halo2 binds into the
+/// transcript, so two keys share a hash exactly when they verify the same
+/// proofs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VkHash([u8; 32]);
+
+impl VkHash {
+    /// Wrap a raw 32-byte hash
+    #[must_use]
+    pub fn new(bytes: [u8; 32]) -> Self {
+        Self(bytes)
     }
 
-    /// Serialize proof to bytes
-    #[must_use] pub fn to_bytes(&self) -> &[u8] {
-        &self.data
+    /// Hash of `vk`
+    #[must_use]
+    pub fn of<C: CurveAffine>(vk: &VerifyingKey<C>) -> Self {
+        Self::of_pinned(format!("{:?}", vk.pinned()).as_bytes())
+    }
+
+    /// Hash of a key's pinned representation
+    pub(crate) fn of_pinned(pinned: &[u8]) -> Self {
+        Self(Sha256::digest(pinned).into())
+    }
+
+    /// Raw hash bytes
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl fmt::Display for VkHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&encode_hex(&self.0))
+    }
+}
+
+/// Provenance of a proof, for auditing and incident response
+///
+/// All fields are optional and unauthenticated, unless the [`Proof`]
+/// carrying them is signed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ProofMetadata {
+    /// Version of this crate on the prover
+    pub crate_version: Option<String>,
+    /// Version of the circuit, as assigned by its owner
+    pub circuit_version: Option<String>,
+    /// Creation time in seconds since the Unix epoch
+    pub created_at: Option<u64>,
+    /// Identifier of the prover node
+    pub prover_id: Option<String>,
+    /// Stable id of the circuit in a [`crate::CircuitRegistry`]
+    pub circuit_id: Option<u64>,
+}
+
+impl ProofMetadata {
+    /// Metadata stamped with this crate's version and the current time
+    #[must_use]
+    pub fn now() -> Self {
+        Self {
+            crate_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|elapsed| elapsed.as_secs()),
+            ..Self::default()
+        }
+    }
+
+    /// Set the circuit version
+    #[must_use]
+    pub fn with_circuit_version(mut self, version: impl Into<String>) -> Self {
+        self.circuit_version = Some(version.into());
+        self
+    }
+
+    /// Set the prover node id
+    #[must_use]
+    pub fn with_prover_id(mut self, id: impl Into<String>) -> Self {
+        self.prover_id = Some(id.into());
+        self
+    }
+
+    /// Set the registry id of the circuit
+    #[must_use]
+    pub fn with_circuit_id(mut self, id: u64) -> Self {
+        self.circuit_id = Some(id);
+        self
+    }
+
+    /// Whether no field is set
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Encode as a sequence of `tag, u16 length, value` entries
+    fn encode(&self, out: &mut Vec<u8>) {
+        let mut put = |tag: u8, value: &[u8]| {
+            let len = value.len().min(MAX_METADATA_FIELD_LEN);
+            out.push(tag);
+            #[allow(clippy::cast_possible_truncation)]
+            out.extend_from_slice(&(len as u16).to_le_bytes());
+            out.extend_from_slice(&value[..len]);
+        };
+
+        if let Some(version) = &self.crate_version {
+            put(TAG_CRATE_VERSION, version.as_bytes());
+        }
+        if let Some(version) = &self.circuit_version {
+            put(TAG_CIRCUIT_VERSION, version.as_bytes());
+        }
+        if let Some(created_at) = self.created_at {
+            put(TAG_CREATED_AT, &created_at.to_le_bytes());
+        }
+        if let Some(id) = &self.prover_id {
+            put(TAG_PROVER_ID, id.as_bytes());
+        }
+        if let Some(id) = self.circuit_id {
+            let mut varint = Vec::new();
+            encode_varint(id, &mut varint);
+            put(TAG_CIRCUIT_ID, &varint);
+        }
+    }
+
+    /// CBOR map keyed by the tags of [`ProofMetadata::encode`]
+    fn to_cbor(&self) -> Cbor<'_> {
+        let mut entries = Vec::new();
+        if let Some(version) = &self.crate_version {
+            entries.push((TAG_CRATE_VERSION.into(), Cbor::Text(version)));
+        }
+        if let Some(version) = &self.circuit_version {
+            entries.push((TAG_CIRCUIT_VERSION.into(), Cbor::Text(version)));
+        }
+        if let Some(created_at) = self.created_at {
+            entries.push((TAG_CREATED_AT.into(), Cbor::Uint(created_at)));
+        }
+        if let Some(id) = &self.prover_id {
+            entries.push((TAG_PROVER_ID.into(), Cbor::Text(id)));
+        }
+        if let Some(id) = self.circuit_id {
+            entries.push((TAG_CIRCUIT_ID.into(), Cbor::Uint(id)));
+        }
+        Cbor::Map(entries)
+    }
+
+    /// Decode a map written by [`ProofMetadata::to_cbor`]
+    ///
+    /// Unlike the envelope encoding, unknown tags are rejected, so the
+    /// encoding a signature covers is unique.
+    fn from_cbor(value: &Cbor<'_>) -> Result<Self> {
+        let mut metadata = Self::default();
+        for (key, value) in value.as_map("proof metadata")? {
+            let text = |field: &str| value.as_text(field).map(str::to_string);
+            match u8::try_from(*key) {
+                Ok(TAG_CRATE_VERSION) => metadata.crate_version = Some(text("crate version")?),
+                Ok(TAG_CIRCUIT_VERSION) => {
+                    metadata.circuit_version = Some(text("circuit version")?);
+                }
+                Ok(TAG_CREATED_AT) => metadata.created_at = Some(value.as_uint("timestamp")?),
+                Ok(TAG_PROVER_ID) => metadata.prover_id = Some(text("prover id")?),
+                Ok(TAG_CIRCUIT_ID) => metadata.circuit_id = Some(value.as_uint("circuit id")?),
+                _ => return Err(Error::Encoding(format!("unknown metadata field {key}"))),
+            }
+        }
+        Ok(metadata)
+    }
+
+    /// Decode entries written by [`ProofMetadata::encode`]
+    ///
+    /// Unknown tags are skipped so newer provers can add fields.
+    fn decode(mut bytes: &[u8]) -> Result<Self> {
+        let mut metadata = Self::default();
+        while !bytes.is_empty() {
+            let (header, rest) = split(bytes, 3)?;
+            let len = usize::from(u16::from_le_bytes([header[1], header[2]]));
+            if len > MAX_METADATA_FIELD_LEN {
+                return Err(Error::Encoding(format!("metadata field of {len} bytes")));
+            }
+            let (value, rest) = split(rest, len)?;
+            bytes = rest;
+
+            let text = || {
+                String::from_utf8(value.to_vec())
+                    .map_err(|_| Error::Encoding("metadata field is not UTF-8".into()))
+            };
+            match header[0] {
+                TAG_CRATE_VERSION => metadata.crate_version = Some(text()?),
+                TAG_CIRCUIT_VERSION => metadata.circuit_version = Some(text()?),
+                TAG_CREATED_AT => {
+                    let value: [u8; 8] = value
+                        .try_into()
+                        .map_err(|_| Error::Encoding("timestamp must be 8 bytes".into()))?;
+                    metadata.created_at = Some(u64::from_le_bytes(value));
+                }
+                TAG_PROVER_ID => metadata.prover_id = Some(text()?),
+                TAG_CIRCUIT_ID => match decode_varint(value)? {
+                    (id, len) if len == value.len() => metadata.circuit_id = Some(id),
+                    _ => return Err(Error::Encoding("trailing bytes after circuit id".into())),
+                },
+                _ => {}
+            }
+        }
+        Ok(metadata)
+    }
+}
+
+/// A proof together with the hash of the verifying key it was created for
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofEnvelope {
+    /// Hash of the verifying key the proof targets
+    pub vk_hash: VkHash,
+    /// Serialized halo2 proof
+    pub proof: Vec<u8>,
+    /// Optional provenance, not covered by the proof
+    pub metadata: ProofMetadata,
+}
+
+impl ProofEnvelope {
+    /// Wrap a proof created for `vk`
+    #[must_use]
+    pub fn new<C: CurveAffine>(vk: &VerifyingKey<C>, proof: Vec<u8>) -> Self {
+        Self {
+            vk_hash: VkHash::of(vk),
+            proof,
+            metadata: ProofMetadata::default(),
+        }
+    }
+
+    /// Attach metadata
+    #[must_use]
+    pub fn with_metadata(mut self, metadata: ProofMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Check that the envelope targets `expected` and return the proof bytes
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::VkMismatch`] if the proof was created for another
+    /// verifying key.
+    pub fn open(&self, expected: &VkHash) -> Result<&[u8]> {
+        if self.vk_hash != *expected {
+            return Err(Error::VkMismatch {
+                expected: expected.to_string(),
+                found: self.vk_hash.to_string(),
+            });
+        }
+        Ok(&self.proof)
+    }
+
+    /// Encode as magic, version, vk hash, length-prefixed proof and
+    /// length-prefixed metadata
+    ///
+    /// # Panics
+    ///
+    /// Panics if the proof is longer than `u32::MAX` bytes.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_versioned(ENVELOPE_VERSION)
+            .expect("current envelope version is supported")
+    }
+
+    /// Encode using the layout of an older or current envelope version
+    ///
+    /// Version 1 has no metadata section, so metadata is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if `version` is not supported.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the proof is longer than `u32::MAX` bytes.
+    pub fn to_bytes_versioned(&self, version: u16) -> Result<Vec<u8>> {
+        check_version(version)?;
+        let len = u32::try_from(self.proof.len()).expect("proof length fits in u32");
+
+        let mut out = Vec::with_capacity(4 + 2 + 32 + 8 + self.proof.len());
+        out.extend_from_slice(ENVELOPE_MAGIC);
+        out.extend_from_slice(&version.to_le_bytes());
+        out.extend_from_slice(&self.vk_hash.0);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&self.proof);
+
+        if version >= 2 {
+            let mut metadata = Vec::new();
+            self.metadata.encode(&mut metadata);
+            let metadata_len =
+                u32::try_from(metadata.len()).expect("metadata length fits in u32");
+            out.extend_from_slice(&metadata_len.to_le_bytes());
+            out.extend_from_slice(&metadata);
+        }
+        Ok(out)
+    }
+
+    /// Read the encoding version of an envelope without decoding it
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if `bytes` does not start with an envelope
+    /// header.
+    pub fn version_of(bytes: &[u8]) -> Result<u16> {
+        let (magic, rest) = split(bytes, 4)?;
+        if magic != ENVELOPE_MAGIC {
+            return Err(Error::Encoding("not a proof envelope".into()));
+        }
+        let (version, _) = split(rest, 2)?;
+        Ok(u16::from_le_bytes([version[0], version[1]]))
+    }
+
+    /// Decode an envelope produced by [`ProofEnvelope::to_bytes`]
+    ///
+    /// Version 1 envelopes decode with empty metadata.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if the magic, version or lengths are
+    /// wrong.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let version = Self::version_of(bytes)?;
+        check_version(version)?;
+
+        let (vk_hash, rest) = split(&bytes[6..], 32)?;
+        let (proof, rest) = split_prefixed(rest)?;
+        let (metadata, rest) = if version >= 2 {
+            let (metadata, rest) = split_prefixed(rest)?;
+            (ProofMetadata::decode(metadata)?, rest)
+        } else {
+            (ProofMetadata::default(), rest)
+        };
+        if !rest.is_empty() {
+            return Err(Error::Encoding(format!(
+                "{} trailing bytes after proof envelope",
+                rest.len()
+            )));
+        }
+
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(vk_hash);
+        Ok(Self {
+            vk_hash: VkHash(hash),
+            proof: proof.to_vec(),
+            metadata,
+        })
+    }
+}
+
+/// Re-encode an envelope from `from_version` to `to_version`
+///
+/// Upgrading keeps every field; downgrading to version 1 drops metadata. The
+/// proof bytes and vk hash are never touched, so the result verifies exactly
+/// when the input does.
+///
+/// # Errors
+///
+/// Returns [`Error::Encoding`] if either version is unsupported, if `bytes`
+/// is not encoded at `from_version`, or if it fails to decode.
+pub fn migrate(bytes: &[u8], from_version: u16, to_version: u16) -> Result<Vec<u8>> {
+    check_version(to_version)?;
+    let found = ProofEnvelope::version_of(bytes)?;
+    if found != from_version {
+        return Err(Error::Encoding(format!(
+            "expected envelope version {from_version}, found {found}"
+        )));
+    }
+    if from_version == to_version {
+        ProofEnvelope::from_bytes(bytes)?;
+        return Ok(bytes.to_vec());
+    }
+    ProofEnvelope::from_bytes(bytes)?.to_bytes_versioned(to_version)
+}
+
+/// Envelope versions this build can read and write
+#[must_use]
+pub fn supported_versions() -> RangeInclusive<u16> {
+    MIN_ENVELOPE_VERSION..=ENVELOPE_VERSION
+}
+
+/// Newest version in both `ours` and `theirs`, if they overlap
+#[must_use]
+pub fn negotiate_version(ours: &RangeInclusive<u16>, theirs: &RangeInclusive<u16>) -> Option<u16> {
+    let low = (*ours.start()).max(*theirs.start());
+    let high = (*ours.end()).min(*theirs.end());
+    (low <= high).then_some(high)
+}
+
+fn check_version(version: u16) -> Result<()> {
+    if supported_versions().contains(&version) {
+        Ok(())
+    } else {
+        Err(Error::Encoding(format!(
+            "unsupported envelope version {version}, \
+             supported {MIN_ENVELOPE_VERSION}..={ENVELOPE_VERSION}"
+        )))
+    }
+}
+
+/// Split off a `u32` length-prefixed field
+fn split_prefixed(bytes: &[u8]) -> Result<(&[u8], &[u8])> {
+    let (len, rest) = split(bytes, 4)?;
+    let len = u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize;
+    split(rest, len)
+}
+
+fn split(bytes: &[u8], n: usize) -> Result<(&[u8], &[u8])> {
+    if bytes.len() < n {
+        return Err(Error::Encoding("truncated proof envelope".into()));
+    }
+    Ok(bytes.split_at(n))
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        plonk::{
+            keygen_pk, keygen_vk, verify_proof, Advice, Column, ConstraintSystem, SingleVerifier,
+        },
+        transcript::Blake2bRead,
+    };
+    use pasta_curves::{vesta, Fp};
+
+    use super::*;
+    use crate::rng::OsRandomness;
+
+    #[derive(Default)]
+    struct OneCell {
+        value: Value<Fp>,
+    }
+
+    impl Circuit<Fp> for OneCell {
+        type Config = Column<Advice>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Column<Advice> {
+            meta.advice_column()
+        }
+
+        fn synthesize(
+            &self,
+            column: Column<Advice>,
+            mut layouter: impl Layouter<Fp>,
+        ) -> std::result::Result<(), PlonkError> {
+            layouter.assign_region(
+                || "cell",
+                |mut region| region.assign_advice(|| "value", column, 0, || self.value),
+            )?;
+            Ok(())
+        }
+    }
+
+    /// Records the size of every write
+    #[derive(Default)]
+    struct Chunks(Vec<Vec<u8>>);
+
+    impl Write for Chunks {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.push(buf.to_vec());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Accepts a fixed number of bytes, then fails
+    struct Closed(usize);
+
+    impl Write for Closed {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if buf.len() > self.0 {
+                return Err(std::io::ErrorKind::BrokenPipe.into());
+            }
+            self.0 -= buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn setup() -> (Params<vesta::Affine>, ProvingKey<vesta::Affine>) {
+        let params = Params::new(4);
+        let vk = keygen_vk(&params, &OneCell::default()).unwrap();
+        let pk = keygen_pk(&params, vk, &OneCell::default()).unwrap();
+        (params, pk)
+    }
+
+    fn prove_into<W: Write>(writer: W) -> Result<W> {
+        let (params, pk) = setup();
+        let circuit = OneCell {
+            value: Value::known(Fp::from(3)),
+        };
+        write_proof(&params, &pk, &[circuit], &[&[]], OsRandomness, writer)
+    }
+
+    #[test]
+    fn test_write_proof_streams_rounds() {
+        let chunks = prove_into(Chunks::default()).unwrap().0;
+        assert!(chunks.len() > 1);
+
+        let (params, pk) = setup();
+        let proof = chunks.concat();
+        let mut transcript = Blake2bRead::<_, vesta::Affine, Challenge255<_>>::init(&proof[..]);
+        verify_proof(
+            &params,
+            pk.get_vk(),
+            SingleVerifier::new(&params),
+            &[&[]],
+            &mut transcript,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_write_proof_reports_io_errors() {
+        assert!(matches!(prove_into(Closed(64)), Err(Error::Io(_))));
+    }
+
+    fn tagged() -> Proof {
+        Proof::new::<vesta::Affine>(
+            vec![1, 2, 3],
+            "echo",
+            4,
+            &[vec![Fp::from(1), Fp::from(2)], vec![Fp::from(3)]],
+        )
+    }
+
+    #[test]
+    fn test_proof_round_trip() {
+        let proof = tagged();
+        assert_eq!(proof.curve, "vesta");
+        assert_eq!(proof.public_input_count, 3);
+
+        let bytes = proof.to_bytes().unwrap();
+        assert_eq!(&bytes[..4], PROOF_MAGIC);
+        assert_eq!(Proof::from_bytes(&bytes).unwrap(), proof);
+
+        assert!(Proof::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(Proof::from_bytes(&trailing).is_err());
+    }
+
+    #[test]
+    fn test_proof_format_versions() {
+        let proof = tagged();
+        assert!(proof.to_bytes_versioned(PROOF_FORMAT_VERSION + 1).is_err());
+
+        let mut bytes = proof.to_bytes().unwrap();
+        bytes[4..6].copy_from_slice(&(PROOF_FORMAT_VERSION + 1).to_le_bytes());
+        assert!(matches!(Proof::from_bytes(&bytes), Err(Error::Encoding(_))));
+        // An envelope is not a proof
+        assert!(Proof::from_bytes(&envelope(1).to_bytes()).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_proof_serde_backends() {
+        let proof = tagged();
+
+        let json = serde_json::to_string(&proof).unwrap();
+        assert_eq!(serde_json::from_str::<Proof>(&json).unwrap(), proof);
+
+        let binary = bincode::serialize(&proof).unwrap();
+        assert_eq!(bincode::deserialize::<Proof>(&binary).unwrap(), proof);
+    }
+
+    fn provenanced() -> Proof {
+        tagged()
+            .with_vk_hash(VkHash::new([6; 32]))
+            .with_metadata(ProofMetadata::now().with_prover_id("prover-eu-1"))
+    }
+
+    #[test]
+    fn test_proof_provenance_round_trip() {
+        let proof = provenanced();
+        assert_eq!(
+            proof.metadata.crate_version.as_deref(),
+            Some(env!("CARGO_PKG_VERSION"))
+        );
+        assert_eq!(Proof::from_bytes(&proof.to_bytes().unwrap()).unwrap(), proof);
+
+        // Version 1 drops the provenance
+        let old = Proof::from_bytes(&proof.to_bytes_versioned(1).unwrap()).unwrap();
+        assert_eq!(old.vk_hash, None);
+        assert!(old.metadata.is_empty());
+        assert_eq!(old.data, proof.data);
+    }
+
+    #[test]
+    fn test_proof_cbor_is_canonical() {
+        let proof = provenanced();
+        let cbor = proof.to_cbor();
+        assert_eq!(Proof::from_cbor(&cbor).unwrap(), proof);
+        assert_eq!(Proof::from_cbor(&cbor).unwrap().to_cbor(), cbor);
+        assert!(Proof::from_cbor(&cbor[..cbor.len() - 1]).is_err());
+
+        // An explicitly empty metadata map has a shorter encoding
+        let mut bare = tagged().with_metadata(ProofMetadata::default()).to_cbor();
+        bare[0] += 1;
+        bare.extend_from_slice(&[0x07, 0xa0]);
+        assert!(Proof::from_cbor(&bare).is_err());
+    }
+
+    #[test]
+    fn test_proof_signature() {
+        let key = pallas::Scalar::from(42);
+        let signer = ProofSignature::public_key(key);
+        let mut proof = provenanced();
+        assert!(proof.verify_signature(&signer).is_err());
+
+        proof.sign(key, OsRandomness);
+        proof.verify_signature(&signer).unwrap();
+        let decoded = Proof::from_cbor(&proof.to_cbor()).unwrap();
+        decoded.verify_signature(&signer).unwrap();
+        assert_eq!(decoded.signing_bytes(), proof.signing_bytes());
+
+        let other = ProofSignature::public_key(pallas::Scalar::from(43));
+        assert!(matches!(
+            proof.verify_signature(&other),
+            Err(Error::Verification(_))
+        ));
+        let mut tampered = proof.clone();
+        tampered.metadata.prover_id = Some("prover-us-1".into());
+        assert!(tampered.verify_signature(&signer).is_err());
+        let mut tampered = proof;
+        tampered.data.push(0);
+        assert!(tampered.verify_signature(&signer).is_err());
+    }
+
+    fn envelope(hash: u8) -> ProofEnvelope {
+        ProofEnvelope {
+            vk_hash: VkHash::new([hash; 32]),
+            proof: vec![1, 2, 3, 4],
+            metadata: ProofMetadata::default(),
+        }
+    }
+
+    #[test]
+    fn test_envelope_round_trip() {
+        let env = envelope(7);
+        let bytes = env.to_bytes();
+        assert_eq!(ProofEnvelope::from_bytes(&bytes).unwrap(), env);
+
+        assert!(ProofEnvelope::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] ^= 1;
+        assert!(ProofEnvelope::from_bytes(&bad_magic).is_err());
+    }
+
+    #[test]
+    fn test_metadata_round_trip() {
+        let metadata = ProofMetadata::now()
+            .with_circuit_version("dci-3")
+            .with_prover_id("prover-eu-1")
+            .with_circuit_id(300);
+        let env = envelope(1).with_metadata(metadata.clone());
+
+        let decoded = ProofEnvelope::from_bytes(&env.to_bytes()).unwrap();
+        assert_eq!(decoded.metadata, metadata);
+        assert_eq!(
+            decoded.metadata.crate_version.as_deref(),
+            Some(env!("CARGO_PKG_VERSION"))
+        );
+        assert!(decoded.metadata.created_at.is_some());
+    }
+
+    #[test]
+    fn test_metadata_does_not_affect_vk_check() {
+        let env = envelope(3).with_metadata(ProofMetadata::now());
+        assert!(env.open(&VkHash::new([3; 32])).is_ok());
+    }
+
+    #[test]
+    fn test_version_one_decodes_without_metadata() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(ENVELOPE_MAGIC);
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&[5; 32]);
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        bytes.extend_from_slice(&[9, 9]);
+
+        let env = ProofEnvelope::from_bytes(&bytes).unwrap();
+        assert_eq!(env.proof, vec![9, 9]);
+        assert!(env.metadata.is_empty());
+    }
+
+    #[test]
+    fn test_migrate_round_trip() {
+        let env = envelope(4).with_metadata(ProofMetadata::now());
+        let current = env.to_bytes();
+
+        let old = migrate(&current, ENVELOPE_VERSION, 1).unwrap();
+        assert_eq!(ProofEnvelope::version_of(&old).unwrap(), 1);
+        let downgraded = ProofEnvelope::from_bytes(&old).unwrap();
+        assert_eq!(downgraded.proof, env.proof);
+        assert!(downgraded.metadata.is_empty());
+
+        let upgraded = migrate(&old, 1, ENVELOPE_VERSION).unwrap();
+        assert_eq!(ProofEnvelope::version_of(&upgraded).unwrap(), ENVELOPE_VERSION);
+        assert_eq!(ProofEnvelope::from_bytes(&upgraded).unwrap().vk_hash, env.vk_hash);
+    }
+
+    #[test]
+    fn test_migrate_checks_versions() {
+        let bytes = envelope(1).to_bytes();
+        assert!(migrate(&bytes, 1, ENVELOPE_VERSION).is_err());
+        assert!(migrate(&bytes, ENVELOPE_VERSION, ENVELOPE_VERSION + 1).is_err());
+        assert!(migrate(&bytes, ENVELOPE_VERSION, 0).is_err());
+    }
+
+    #[test]
+    fn test_negotiate_version() {
+        assert_eq!(negotiate_version(&(1..=2), &(1..=5)), Some(2));
+        assert_eq!(negotiate_version(&(1..=2), &(1..=1)), Some(1));
+        assert_eq!(negotiate_version(&(1..=2), &(3..=4)), None);
+    }
+
+    #[test]
+    fn test_wrong_vk_reported() {
+        let env = envelope(7);
+        assert_eq!(env.open(&VkHash::new([7; 32])).unwrap(), &[1, 2, 3, 4]);
+
+        let err = env.open(&VkHash::new([8; 32])).unwrap_err();
+        assert!(matches!(err, Error::VkMismatch { .. }));
+        assert!(err.to_string().contains(&"08".repeat(32)));
     }
 }
@@ -0,0 +1,876 @@
+//! Proving circuits, once or many times
+//!
+//! A [`Prover`] runs keygen for one circuit and turns witnesses into
+//! [`Proof`]s that carry the circuit id, `k` and a hash of their public
+//! inputs.
+//!
+//! Most of the fixed cost of a small proof sits in the [`ProvingKey`]: the
+//! fixed-column and permutation commitments, the evaluation domain and the
+//! extended-domain polynomials (`l0`, `l_last`, permutation cosets) that
+//! halo2 precomputes during keygen. A [`BatchProver`] runs keygen once and
+//! reuses all of it for every instance.
+//!
+//! [`BatchProver::prove_each`] still emits one independent proof per
+//! instance. [`BatchProver::prove_batch`] goes further and proves every
+//! instance in a single halo2 proof, which also shares the multiopen
+//! argument; verifiers then pass all instances to one `verify_proof` call.
+//!
+//! [`Prover::prove_many`] proves independent instances concurrently, one
+//! per task, so their witness synthesis and MSMs overlap. Set
+//! [`ProverConfig::num_threads`] to give the prover its own rayon pool
+//! instead of the global one.
+//!
+//! Proving runs on the CPU only. halo2 runs its MSMs and FFTs internally
+//! and offers no hook to hand them to an accelerator.
+//!
+//! [`ProverConfig::with_rng_seed`] makes proving reproducible: the prover
+//! then draws every blinding factor from a ChaCha20 stream of the seed
+//! instead of the randomness passed in, so two provers configured with the
+//! same seed and given the same proofs to make write identical bytes.
+//!
+//! A [`ProverContext`] amortizes across a whole process instead: params for
+//! each curve and `k` are generated once and shared by every context, and
+//! [`ProverContext::warm_up`] pays for them, and for starting the thread
+//! pool, before the first proof rather than during it.
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+};
+
+use ff::FromUniformBytes;
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    plonk::{keygen_pk, keygen_vk, Circuit, ProvingKey, VerifyingKey},
+    poly::commitment::Params,
+};
+
+#[cfg(feature = "multicore")]
+use rayon::prelude::*;
+
+use crate::{
+    error::{Error, Result},
+    proof::{write_proof, Proof, VkHash},
+    rng::{DeterministicRandomness, Randomness},
+};
+
+/// Instance columns of one circuit, each a list of values
+pub type Instances<F> = Vec<Vec<F>>;
+
+/// Threading and randomness options of a [`Prover`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProverConfig {
+    /// Threads of a pool owned by the prover; `None` runs on the global
+    /// pool sized by [`crate::set_parallelism`]
+    ///
+    /// Ignored without the `multicore` feature.
+    pub num_threads: Option<usize>,
+    /// Seed of the randomness every proof draws from, for reproducible
+    /// proofs; `None` uses the randomness passed to each call
+    pub rng_seed: Option<[u8; 32]>,
+}
+
+impl ProverConfig {
+    /// Draw blinding factors from a [`DeterministicRandomness`] of `seed`
+    ///
+    /// Proofs are then byte-identical across runs that prove the same
+    /// witnesses in the same order, and no longer zero-knowledge against
+    /// anyone who knows the seed: only for regression tests and audits.
+    /// Pedersen commitments made alongside take their own
+    /// [`DeterministicRandomness`].
+    #[must_use]
+    pub fn with_rng_seed(self, seed: [u8; 32]) -> Self {
+        Self {
+            rng_seed: Some(seed),
+            ..self
+        }
+    }
+}
+
+/// Prover for one circuit, keyed by a caller-chosen circuit id
+#[derive(Debug)]
+pub struct Prover<'params, C: CurveAffine> {
+    params: &'params Params<C>,
+    pk: ProvingKey<C>,
+    vk_hash: VkHash,
+    circuit_id: String,
+    seeded: Option<DeterministicRandomness>,
+    #[cfg(feature = "multicore")]
+    pool: Option<rayon::ThreadPool>,
+}
+
+impl<'params, C> Prover<'params, C>
+where
+    C: CurveAffine,
+    C::Scalar: FromUniformBytes<64>,
+{
+    /// Run keygen for `circuit`, which may lack witnesses
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Plonk`] if keygen fails, for instance when the
+    /// circuit does not fit in `params`.
+    pub fn new<ConcreteCircuit: Circuit<C::Scalar>>(
+        params: &'params Params<C>,
+        circuit_id: impl Into<String>,
+        circuit: &ConcreteCircuit,
+    ) -> Result<Self> {
+        let empty = circuit.without_witnesses();
+        let vk = keygen_vk(params, &empty)?;
+        let pk = keygen_pk(params, vk, &empty)?;
+        Ok(Self::from_key(params, circuit_id, pk))
+    }
+
+    /// Reuse a proving key generated elsewhere
+    #[must_use]
+    pub fn from_key(
+        params: &'params Params<C>,
+        circuit_id: impl Into<String>,
+        pk: ProvingKey<C>,
+    ) -> Self {
+        Self {
+            params,
+            vk_hash: VkHash::of(pk.get_vk()),
+            pk,
+            circuit_id: circuit_id.into(),
+            seeded: None,
+            #[cfg(feature = "multicore")]
+            pool: None,
+        }
+    }
+
+    /// Apply `config`, starting the prover's own pool if it asks for one
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Other`] if the pool cannot be started.
+    pub fn with_config(self, config: ProverConfig) -> Result<Self> {
+        let this = Self {
+            seeded: config.rng_seed.map(DeterministicRandomness::from_seed),
+            ..self
+        };
+        #[cfg(feature = "multicore")]
+        if let Some(threads) = config.num_threads {
+            let pool = crate::parallel::builder(&crate::ThreadPoolConfig::with_threads(threads))
+                .build()
+                .map_err(|err| Error::Other(format!("starting prover pool: {err}")))?;
+            return Ok(Self {
+                pool: Some(pool),
+                ..this
+            });
+        }
+        Ok(this)
+    }
+
+    /// Identifier stamped on every proof
+    #[must_use]
+    pub fn circuit_id(&self) -> &str {
+        &self.circuit_id
+    }
+
+    /// Proving key of the circuit
+    #[must_use]
+    pub fn proving_key(&self) -> &ProvingKey<C> {
+        &self.pk
+    }
+
+    /// Verifying key for every proof this prover makes
+    #[must_use]
+    pub fn verifying_key(&self) -> &VerifyingKey<C> {
+        self.pk.get_vk()
+    }
+
+    /// Prove `circuit` with its instance columns
+    ///
+    /// `randomness` is ignored if the prover was configured with
+    /// [`ProverConfig::with_rng_seed`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Plonk`] if proving fails, including when the
+    /// witness does not satisfy the circuit or `instances` has the wrong
+    /// number of columns.
+    pub fn prove<ConcreteCircuit: Circuit<C::Scalar>>(
+        &self,
+        circuit: ConcreteCircuit,
+        instances: &[Vec<C::Scalar>],
+        randomness: impl Randomness,
+    ) -> Result<Proof> {
+        let columns = columns(instances);
+        let data = match &self.seeded {
+            Some(seeded) => write_proof(
+                self.params,
+                &self.pk,
+                &[circuit],
+                &[&columns],
+                seeded,
+                Vec::new(),
+            )?,
+            None => write_proof(
+                self.params,
+                &self.pk,
+                &[circuit],
+                &[&columns],
+                randomness,
+                Vec::new(),
+            )?,
+        };
+        Ok(
+            Proof::new::<C>(data, self.circuit_id.clone(), self.params.k(), instances)
+                .with_vk_hash(self.vk_hash),
+        )
+    }
+
+    /// Prove every circuit concurrently, one proof each
+    ///
+    /// `instances[i]` holds the instance columns of `circuits[i]`, and the
+    /// proofs come back in the same order. Proofs draw from `randomness` in
+    /// whatever order they start, so a [`crate::DeterministicRandomness`]
+    /// only reproduces the batch proof for proof when one thread is used.
+    /// A prover configured with [`ProverConfig::with_rng_seed`] instead
+    /// gives each proof its own fork of the seed up front, which reproduces
+    /// them on any number of threads.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Plonk`] for a circuit that fails to prove
+    /// and [`Error::Other`] if the two slices differ in length.
+    pub fn prove_many<ConcreteCircuit, R>(
+        &self,
+        circuits: &[ConcreteCircuit],
+        instances: &[Instances<C::Scalar>],
+        randomness: R,
+    ) -> Result<Vec<Proof>>
+    where
+        ConcreteCircuit: Circuit<C::Scalar> + Sync,
+        R: Randomness + Sync,
+    {
+        check_lengths(circuits.len(), instances.len())?;
+        let forks: Option<Vec<DeterministicRandomness>> = self
+            .seeded
+            .as_ref()
+            .map(|seeded| circuits.iter().map(|_| seeded.fork()).collect());
+        let prove_one = |(index, (circuit, instances)): (
+            usize,
+            (&ConcreteCircuit, &Instances<C::Scalar>),
+        )|
+         -> Result<Proof> {
+            let columns = columns(instances);
+            let circuits = std::slice::from_ref(circuit);
+            let data = match &forks {
+                Some(forks) => write_proof(
+                    self.params,
+                    &self.pk,
+                    circuits,
+                    &[&columns],
+                    &forks[index],
+                    Vec::new(),
+                )?,
+                None => write_proof(
+                    self.params,
+                    &self.pk,
+                    circuits,
+                    &[&columns],
+                    &randomness,
+                    Vec::new(),
+                )?,
+            };
+            Ok(
+                Proof::new::<C>(data, self.circuit_id.clone(), self.params.k(), instances)
+                    .with_vk_hash(self.vk_hash),
+            )
+        };
+
+        #[cfg(feature = "multicore")]
+        let proofs: Result<Vec<Proof>> = {
+            let prove_all = || {
+                circuits
+                    .par_iter()
+                    .zip(instances)
+                    .enumerate()
+                    .with_min_len(crate::parallel::min_chunk_len())
+                    .map(prove_one)
+                    .collect()
+            };
+            match &self.pool {
+                Some(pool) => pool.install(prove_all),
+                None => prove_all(),
+            }
+        };
+        #[cfg(not(feature = "multicore"))]
+        let proofs: Result<Vec<Proof>> = circuits
+            .iter()
+            .zip(instances)
+            .enumerate()
+            .map(prove_one)
+            .collect();
+        proofs
+    }
+}
+
+/// Prover holding the key material shared by a batch
+#[derive(Debug)]
+pub struct BatchProver<'params, C: CurveAffine> {
+    params: &'params Params<C>,
+    pk: ProvingKey<C>,
+}
+
+impl<'params, C> BatchProver<'params, C>
+where
+    C: CurveAffine,
+    C::Scalar: FromUniformBytes<64>,
+{
+    /// Run keygen once for `circuit`, which may lack witnesses
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Plonk`] if keygen fails, for instance when the
+    /// circuit does not fit in `params`.
+    pub fn new<ConcreteCircuit: Circuit<C::Scalar>>(
+        params: &'params Params<C>,
+        circuit: &ConcreteCircuit,
+    ) -> Result<Self> {
+        let empty = circuit.without_witnesses();
+        let vk = keygen_vk(params, &empty)?;
+        let pk = keygen_pk(params, vk, &empty)?;
+        Ok(Self { params, pk })
+    }
+
+    /// Reuse a proving key generated elsewhere
+    #[must_use]
+    pub fn from_key(params: &'params Params<C>, pk: ProvingKey<C>) -> Self {
+        Self { params, pk }
+    }
+
+    /// Proving key shared by the batch
+    #[must_use]
+    pub fn proving_key(&self) -> &ProvingKey<C> {
+        &self.pk
+    }
+
+    /// Verifying key for every proof of the batch
+    #[must_use]
+    pub fn verifying_key(&self) -> &VerifyingKey<C> {
+        self.pk.get_vk()
+    }
+
+    /// One independent proof per circuit
+    ///
+    /// `instances[i]` holds the instance columns of `circuits[i]`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Plonk`] if proving fails and
+    /// [`Error::Other`] if the two slices differ in length.
+    pub fn prove_each<ConcreteCircuit: Circuit<C::Scalar>>(
+        &self,
+        circuits: &[ConcreteCircuit],
+        instances: &[Instances<C::Scalar>],
+        randomness: impl Randomness,
+    ) -> Result<Vec<Vec<u8>>> {
+        check_lengths(circuits.len(), instances.len())?;
+        circuits
+            .iter()
+            .zip(instances)
+            .map(|(circuit, instances)| {
+                let columns = columns(instances);
+                write_proof(
+                    self.params,
+                    &self.pk,
+                    std::slice::from_ref(circuit),
+                    &[&columns],
+                    &randomness,
+                    Vec::new(),
+                )
+            })
+            .collect()
+    }
+
+    /// A single proof covering every circuit
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Plonk`] if proving fails and
+    /// [`Error::Other`] if the two slices differ in length.
+    pub fn prove_batch<ConcreteCircuit: Circuit<C::Scalar>>(
+        &self,
+        circuits: &[ConcreteCircuit],
+        instances: &[Instances<C::Scalar>],
+        randomness: impl Randomness,
+    ) -> Result<Vec<u8>> {
+        check_lengths(circuits.len(), instances.len())?;
+        let columns: Vec<Vec<&[C::Scalar]>> = instances.iter().map(|i| columns(i)).collect();
+        let columns: Vec<&[&[C::Scalar]]> = columns.iter().map(Vec::as_slice).collect();
+        write_proof(
+            self.params,
+            &self.pk,
+            circuits,
+            &columns,
+            randomness,
+            Vec::new(),
+        )
+    }
+}
+
+/// Params shared by every [`ProverContext`], keyed by curve and `k`
+type ParamsCache = Mutex<HashMap<(TypeId, u32), Arc<dyn Any + Send + Sync>>>;
+
+static SHARED_PARAMS: OnceLock<ParamsCache> = OnceLock::new();
+
+/// Params and proving keys reused by every proof a worker makes
+///
+/// Params hold the commitment generators and their Lagrange-basis
+/// commitments; keygen builds the evaluation domain, including the FFT
+/// twiddle factors, and the extended-domain polynomials. Both are computed
+/// at most once: params once per process for each curve and `k`, proving
+/// keys once per context for each circuit layout.
+#[derive(Debug)]
+pub struct ProverContext<C: CurveAffine> {
+    k: u32,
+    params: OnceLock<Arc<Params<C>>>,
+    keys: Mutex<HashMap<VkHash, Arc<ProvingKey<C>>>>,
+}
+
+impl<C> ProverContext<C>
+where
+    C: CurveAffine,
+    C::Scalar: FromUniformBytes<64>,
+{
+    /// Context for circuits of `2^k` rows; nothing is computed yet
+    #[must_use]
+    pub fn new(k: u32) -> Self {
+        Self {
+            k,
+            params: OnceLock::new(),
+            keys: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Context using params loaded elsewhere, such as from an
+    /// [`crate::ArtifactStore`]
+    #[must_use]
+    pub fn from_params(params: Params<C>) -> Self {
+        let context = Self::new(params.k());
+        // Freshly created, so the cell is empty
+        let _ = context.params.set(Arc::new(params));
+        context
+    }
+
+    /// Log2 of the number of rows
+    #[must_use]
+    pub fn k(&self) -> u32 {
+        self.k
+    }
+
+    /// Start every thread of the global pool and compute the params
+    ///
+    /// Call once when a worker starts. Later calls return immediately.
+    pub fn warm_up(&self) {
+        #[cfg(feature = "multicore")]
+        rayon::broadcast(|_| ());
+        self.params();
+    }
+
+    /// Params for `k`, computed on first use
+    ///
+    /// Generating params holds a process-wide lock, so concurrent contexts
+    /// for the same curve and `k` wait for one generation instead of
+    /// repeating it.
+    pub fn params(&self) -> &Params<C> {
+        self.params.get_or_init(|| shared_params(self.k))
+    }
+
+    /// Proving key for `circuit`, generated once per circuit layout
+    ///
+    /// A circuit type may lay out differently at runtime, as a
+    /// [`crate::circuits::DCICircuit`] does with its Merkle hash, so keys
+    /// are told apart by the [`VkHash`] of the circuit's verifying key.
+    /// That key is regenerated on every call; the proving key, which costs
+    /// far more, is not.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Plonk`] if keygen fails, for instance when the
+    /// circuit does not fit in `2^k` rows.
+    pub fn proving_key<ConcreteCircuit>(
+        &self,
+        circuit: &ConcreteCircuit,
+    ) -> Result<Arc<ProvingKey<C>>>
+    where
+        ConcreteCircuit: Circuit<C::Scalar>,
+    {
+        let empty = circuit.without_witnesses();
+        let vk = keygen_vk(self.params(), &empty)?;
+        let vk_hash = VkHash::of(&vk);
+        let mut keys = self.keys.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(pk) = keys.get(&vk_hash) {
+            return Ok(Arc::clone(pk));
+        }
+        let pk = Arc::new(keygen_pk(self.params(), vk, &empty)?);
+        keys.insert(vk_hash, Arc::clone(&pk));
+        Ok(pk)
+    }
+}
+
+fn shared_params<C: CurveAffine>(k: u32) -> Arc<Params<C>> {
+    let mut cache = SHARED_PARAMS
+        .get_or_init(ParamsCache::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    let params = cache
+        .entry((TypeId::of::<C>(), k))
+        .or_insert_with(|| Arc::new(Params::<C>::new(k)));
+    Arc::clone(params)
+        .downcast()
+        .unwrap_or_else(|_| unreachable!("params are keyed by curve type"))
+}
+
+fn columns<F>(instances: &Instances<F>) -> Vec<&[F]> {
+    instances.iter().map(Vec::as_slice).collect()
+}
+
+fn check_lengths(circuits: usize, instances: usize) -> Result<()> {
+    if circuits == instances {
+        Ok(())
+    } else {
+        Err(Error::Other(format!(
+            "batch has {circuits} circuits but {instances} instance sets"
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        plonk::{
+            verify_proof, Advice, Column, ConstraintSystem, Error as PlonkError, Instance,
+            SingleVerifier,
+        },
+        transcript::{Blake2bRead, Challenge255},
+    };
+    use pasta_curves::{vesta, Fp};
+
+    use super::*;
+    use crate::rng::DeterministicRandomness;
+
+    /// Exposes its witness as the only public input
+    #[derive(Clone, Default)]
+    struct Echo {
+        value: Value<Fp>,
+    }
+
+    impl Circuit<Fp> for Echo {
+        type Config = (Column<Advice>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(advice);
+            meta.enable_equality(instance);
+            (advice, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (advice, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> std::result::Result<(), PlonkError> {
+            let cell = layouter.assign_region(
+                || "value",
+                |mut region| region.assign_advice(|| "value", advice, 0, || self.value),
+            )?;
+            layouter.constrain_instance(cell.cell(), instance, 0)
+        }
+    }
+
+    fn batch(values: &[u64]) -> (Vec<Echo>, Vec<Instances<Fp>>) {
+        let circuits = values
+            .iter()
+            .map(|&v| Echo {
+                value: Value::known(Fp::from(v)),
+            })
+            .collect();
+        let instances = values.iter().map(|&v| vec![vec![Fp::from(v)]]).collect();
+        (circuits, instances)
+    }
+
+    fn verify(
+        params: &Params<vesta::Affine>,
+        vk: &VerifyingKey<vesta::Affine>,
+        proof: &[u8],
+        instances: &[Instances<Fp>],
+    ) -> bool {
+        let columns: Vec<Vec<&[Fp]>> = instances.iter().map(|i| columns(i)).collect();
+        let columns: Vec<&[&[Fp]]> = columns.iter().map(Vec::as_slice).collect();
+        let mut transcript = Blake2bRead::<_, vesta::Affine, Challenge255<_>>::init(proof);
+        verify_proof(
+            params,
+            vk,
+            SingleVerifier::new(params),
+            &columns,
+            &mut transcript,
+        )
+        .is_ok()
+    }
+
+    #[test]
+    fn test_prover_tags_proof() {
+        let params = Params::<vesta::Affine>::new(4);
+        let prover = Prover::new(&params, "echo", &Echo::default()).unwrap();
+        let (mut circuits, instances) = batch(&[9]);
+        let rng = DeterministicRandomness::from_seed([2; 32]);
+
+        let proof = prover
+            .prove(circuits.remove(0), &instances[0], &rng)
+            .unwrap();
+        assert_eq!(proof.circuit_id, "echo");
+        assert_eq!(proof.curve, "vesta");
+        assert_eq!(proof.k, 4);
+        assert_eq!(proof.public_input_count, 1);
+        assert_eq!(proof.vk_hash, Some(VkHash::of(prover.verifying_key())));
+        assert!(proof.matches_public_inputs(&instances[0]));
+        assert!(!proof.matches_public_inputs(&[vec![Fp::from(8)]]));
+        assert!(verify(
+            &params,
+            prover.verifying_key(),
+            &proof.data,
+            &instances
+        ));
+    }
+
+    #[test]
+    fn test_prove_many() {
+        let params = Params::<vesta::Affine>::new(4);
+        let prover = Prover::new(&params, "echo", &Echo::default())
+            .unwrap()
+            .with_config(ProverConfig {
+                num_threads: Some(2),
+                ..ProverConfig::default()
+            })
+            .unwrap();
+        let (circuits, instances) = batch(&[1, 2, 3, 4, 5]);
+        let rng = DeterministicRandomness::from_seed([7; 32]);
+
+        let proofs = prover.prove_many(&circuits, &instances, &rng).unwrap();
+        assert_eq!(proofs.len(), 5);
+        for (proof, instances) in proofs.iter().zip(&instances) {
+            assert_eq!(proof.circuit_id, "echo");
+            assert!(proof.matches_public_inputs(instances));
+            assert!(verify(
+                &params,
+                prover.verifying_key(),
+                &proof.data,
+                std::slice::from_ref(instances)
+            ));
+        }
+
+        assert!(matches!(
+            prover.prove_many(&circuits, &instances[..4], &rng),
+            Err(Error::Other(_))
+        ));
+    }
+
+    #[test]
+    fn test_seeded_proofs_are_reproducible() {
+        let params = Params::<vesta::Affine>::new(4);
+        let seeded = |seed, num_threads| {
+            Prover::new(&params, "echo", &Echo::default())
+                .unwrap()
+                .with_config(ProverConfig {
+                    num_threads,
+                    ..ProverConfig::default().with_rng_seed(seed)
+                })
+                .unwrap()
+        };
+        let (circuits, instances) = batch(&[1, 2, 3]);
+        // The randomness passed in is ignored once seeded
+        let rng = DeterministicRandomness::from_seed([1; 32]);
+        let other = DeterministicRandomness::from_seed([2; 32]);
+
+        let single = seeded([8; 32], Some(1))
+            .prove(circuits[0].clone(), &instances[0], &rng)
+            .unwrap();
+        let again = seeded([8; 32], Some(1))
+            .prove(circuits[0].clone(), &instances[0], &other)
+            .unwrap();
+        assert_eq!(single.data, again.data);
+        let reseeded = seeded([9; 32], Some(1))
+            .prove(circuits[0].clone(), &instances[0], &rng)
+            .unwrap();
+        assert_ne!(single.data, reseeded.data);
+
+        let one_thread = seeded([8; 32], Some(1))
+            .prove_many(&circuits, &instances, &rng)
+            .unwrap();
+        let three_threads = seeded([8; 32], Some(3))
+            .prove_many(&circuits, &instances, &other)
+            .unwrap();
+        for (a, b) in one_thread.iter().zip(&three_threads) {
+            assert_eq!(a.data, b.data);
+        }
+        assert_ne!(one_thread[0].data, one_thread[1].data);
+    }
+
+    #[test]
+    fn test_prove_each() {
+        let params = Params::<vesta::Affine>::new(4);
+        let prover = BatchProver::new(&params, &Echo::default()).unwrap();
+        let (circuits, instances) = batch(&[1, 2, 3]);
+        let rng = DeterministicRandomness::from_seed([3; 32]);
+
+        let proofs = prover.prove_each(&circuits, &instances, &rng).unwrap();
+        assert_eq!(proofs.len(), 3);
+        for (proof, instances) in proofs.iter().zip(&instances) {
+            assert!(verify(
+                &params,
+                prover.verifying_key(),
+                proof,
+                std::slice::from_ref(instances)
+            ));
+        }
+        assert!(!verify(
+            &params,
+            prover.verifying_key(),
+            &proofs[0],
+            &instances[1..2]
+        ));
+    }
+
+    #[test]
+    fn test_prove_batch() {
+        let params = Params::<vesta::Affine>::new(4);
+        let prover = BatchProver::new(&params, &Echo::default()).unwrap();
+        let (circuits, instances) = batch(&[4, 5, 6, 7]);
+        let rng = DeterministicRandomness::from_seed([4; 32]);
+
+        let proof = prover.prove_batch(&circuits, &instances, &rng).unwrap();
+        assert!(verify(&params, prover.verifying_key(), &proof, &instances));
+
+        let mut swapped = instances.clone();
+        swapped.swap(0, 1);
+        assert!(!verify(&params, prover.verifying_key(), &proof, &swapped));
+
+        // One shared proof is smaller than separate ones
+        let each = prover.prove_each(&circuits, &instances, &rng).unwrap();
+        assert!(proof.len() < each.iter().map(Vec::len).sum());
+    }
+
+    #[test]
+    fn test_context_shares_params() {
+        let first = ProverContext::<vesta::Affine>::new(4);
+        let second = ProverContext::<vesta::Affine>::new(4);
+        first.warm_up();
+        assert!(std::ptr::eq(first.params(), second.params()));
+        assert!(!std::ptr::eq(
+            first.params(),
+            ProverContext::<vesta::Affine>::new(5).params()
+        ));
+    }
+
+    #[test]
+    fn test_context_caches_proving_key() {
+        let context = ProverContext::<vesta::Affine>::new(4);
+        let pk = context.proving_key(&Echo::default()).unwrap();
+        assert!(Arc::ptr_eq(
+            &pk,
+            &context.proving_key(&Echo::default()).unwrap()
+        ));
+
+        let (circuits, instances) = batch(&[8]);
+        let proof = write_proof(
+            context.params(),
+            &pk,
+            &circuits,
+            &[&columns(&instances[0])],
+            DeterministicRandomness::from_seed([6; 32]),
+            Vec::new(),
+        )
+        .unwrap();
+        assert!(verify(context.params(), pk.get_vk(), &proof, &instances));
+    }
+
+    #[cfg(feature = "circuits")]
+    #[test]
+    fn test_context_keys_dci_layouts_apart() {
+        use crate::{
+            circuits::{
+                dci::{DCI_SHA256_K, DCI_TREE_DEPTH},
+                DCICircuit, DCIWitnessBuilder, MerkleHash,
+            },
+            merkle::MerkleTree,
+            PublicInputs,
+        };
+
+        let secret_key = Fp::from(42);
+        let mut tree = MerkleTree::new(DCI_TREE_DEPTH);
+        tree.insert(DCIWitnessBuilder::commitment(secret_key, 1_000))
+            .unwrap();
+        let builder = DCIWitnessBuilder::new()
+            .with_merkle_proof(&tree.prove_index(0).unwrap())
+            .with_balance(1_000)
+            .with_secret_key(secret_key);
+        let (poseidon, poseidon_public) = builder.clone().build().unwrap();
+        let (sha256, sha256_public) = builder
+            .with_sha256_path(0, &[[7; 32]; DCI_TREE_DEPTH])
+            .build_sha256()
+            .unwrap();
+
+        // Both layouts are one circuit type, each with its own key
+        let context = ProverContext::<vesta::Affine>::new(DCI_SHA256_K);
+        let poseidon_pk = context.proving_key(&poseidon).unwrap();
+        let sha256_pk = context.proving_key(&sha256).unwrap();
+        assert!(!Arc::ptr_eq(&poseidon_pk, &sha256_pk));
+        assert!(Arc::ptr_eq(
+            &poseidon_pk,
+            &context.proving_key(&DCICircuit::default()).unwrap()
+        ));
+        assert!(Arc::ptr_eq(
+            &sha256_pk,
+            &context
+                .proving_key(&DCICircuit::with_merkle_hash(MerkleHash::Sha256))
+                .unwrap()
+        ));
+
+        let proofs = [
+            (poseidon_pk, poseidon, poseidon_public.to_instances()),
+            (sha256_pk, sha256, sha256_public.to_instances()),
+        ];
+        for (pk, circuit, instances) in proofs {
+            let proof = write_proof(
+                context.params(),
+                &pk,
+                &[circuit],
+                &[&columns(&instances)],
+                DeterministicRandomness::from_seed([9; 32]),
+                Vec::new(),
+            )
+            .unwrap();
+            assert!(verify(
+                context.params(),
+                pk.get_vk(),
+                &proof,
+                std::slice::from_ref(&instances)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_length_mismatch() {
+        let params = Params::<vesta::Affine>::new(4);
+        let prover = BatchProver::new(&params, &Echo::default()).unwrap();
+        let (circuits, instances) = batch(&[1, 2]);
+        let rng = DeterministicRandomness::from_seed([5; 32]);
+
+        assert!(matches!(
+            prover.prove_batch(&circuits, &instances[..1], &rng),
+            Err(Error::Other(_))
+        ));
+    }
+}
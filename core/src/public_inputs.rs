@@ -0,0 +1,239 @@
+//! Typed public inputs
+//!
+//! [`PublicInputs`] maps a struct of field elements to the instance columns a
+//! circuit exposes, so circuits and callers share one layout instead of
+//! indexing instance rows by hand. Derive it with `#[derive(PublicInputs)]`.
+//!
+//! Values received from outside, such as a verifier request, are decoded
+//! with [`PublicInputs::from_bytes`], which accepts exactly
+//! [`PublicInputs::SHAPE`] worth of canonical field elements and nothing
+//! else.
+
+use ff::PrimeField;
+use halo2_proofs::arithmetic::Field;
+
+use crate::{
+    error::{Error, Result},
+    utils::encoding::{self, byte_len, Endianness},
+};
+
+/// Position of one struct field in the instance columns
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InstanceSlot {
+    /// Struct field name
+    pub name: &'static str,
+    /// Instance column index
+    pub column: usize,
+    /// Row of the first element
+    pub row: usize,
+    /// Number of consecutive rows
+    pub len: usize,
+}
+
+/// A value that is exposed as public instances
+pub trait PublicInputs<F: Field>: Sized {
+    /// Number of rows in each instance column
+    const SHAPE: &'static [usize];
+
+    /// Where each field is placed, in declaration order
+    const LAYOUT: &'static [InstanceSlot];
+
+    /// Lay the value out as instance columns
+    fn to_instances(&self) -> Vec<Vec<F>>;
+
+    /// Rebuild the value from instance columns
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InstanceShape`] if `instances` does not match
+    /// [`PublicInputs::SHAPE`].
+    fn from_instances(instances: &[Vec<F>]) -> Result<Self>;
+
+    /// Slot of the field called `name`
+    #[must_use]
+    fn slot(name: &str) -> Option<&'static InstanceSlot> {
+        Self::LAYOUT.iter().find(|slot| slot.name == name)
+    }
+
+    /// Values of the field called `name`, or `None` if there is none
+    #[must_use]
+    fn field(&self, name: &str) -> Option<Vec<F>> {
+        let slot = Self::slot(name)?;
+        let instances = self.to_instances();
+        Some(instances[slot.column][slot.row..slot.row + slot.len].to_vec())
+    }
+
+    /// Canonical little-endian encoding, column by column
+    #[must_use]
+    fn to_bytes(&self) -> Vec<u8>
+    where
+        F: PrimeField,
+    {
+        self.to_instances()
+            .iter()
+            .flatten()
+            .flat_map(|value| encoding::to_bytes(value, Endianness::Little))
+            .collect()
+    }
+
+    /// Decode the encoding of [`PublicInputs::to_bytes`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if `bytes` is not exactly
+    /// [`PublicInputs::SHAPE`] worth of elements or holds a non-canonical
+    /// element.
+    fn from_bytes(bytes: &[u8]) -> Result<Self>
+    where
+        F: PrimeField,
+    {
+        let len = byte_len::<F>();
+        let rows: usize = Self::SHAPE.iter().sum();
+        if bytes.len() != rows * len {
+            return Err(Error::Encoding(format!(
+                "expected {} bytes of public inputs, found {}",
+                rows * len,
+                bytes.len()
+            )));
+        }
+        let mut elements = bytes.chunks_exact(len);
+        let instances = Self::SHAPE
+            .iter()
+            .map(|&rows| {
+                elements
+                    .by_ref()
+                    .take(rows)
+                    .map(|element| encoding::from_bytes(element, Endianness::Little))
+                    .collect()
+            })
+            .collect::<Result<Vec<Vec<F>>>>()?;
+        Self::from_instances(&instances)
+    }
+}
+
+/// Check that `instances` has exactly `expected` rows per column
+///
+/// # Errors
+///
+/// Returns [`Error::InstanceShape`] on any difference.
+pub fn check_shape<F>(expected: &[usize], instances: &[Vec<F>]) -> Result<()> {
+    let found: Vec<usize> = instances.iter().map(Vec::len).collect();
+    if found != expected {
+        return Err(Error::InstanceShape {
+            expected: expected.to_vec(),
+            found,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pasta_curves::Fp;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, crate::PublicInputs)]
+    struct Transfer<F: Field> {
+        root: F,
+        nullifiers: [F; 2],
+        #[instance(column = 1)]
+        amount: F,
+        fee: F,
+    }
+
+    #[derive(Debug, PartialEq, crate::PublicInputs)]
+    struct Single {
+        value: Fp,
+    }
+
+    fn transfer() -> Transfer<Fp> {
+        Transfer {
+            root: Fp::from(1),
+            nullifiers: [Fp::from(2), Fp::from(3)],
+            amount: Fp::from(4),
+            fee: Fp::from(5),
+        }
+    }
+
+    #[test]
+    fn test_layout() {
+        assert_eq!(<Transfer<Fp> as PublicInputs<Fp>>::SHAPE, &[4, 1]);
+        let fee = <Transfer<Fp> as PublicInputs<Fp>>::slot("fee").unwrap();
+        assert_eq!((fee.column, fee.row, fee.len), (0, 3, 1));
+        let nullifiers = <Transfer<Fp> as PublicInputs<Fp>>::slot("nullifiers").unwrap();
+        assert_eq!(
+            (nullifiers.column, nullifiers.row, nullifiers.len),
+            (0, 1, 2)
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let value = transfer();
+        let instances = value.to_instances();
+        assert_eq!(
+            instances,
+            vec![
+                vec![Fp::from(1), Fp::from(2), Fp::from(3), Fp::from(5)],
+                vec![Fp::from(4)],
+            ]
+        );
+        assert_eq!(Transfer::from_instances(&instances).unwrap(), value);
+
+        let single = Single { value: Fp::ONE };
+        assert_eq!(
+            Single::from_instances(&single.to_instances()).unwrap(),
+            single
+        );
+    }
+
+    #[test]
+    fn test_named_fields() {
+        let value = transfer();
+        assert_eq!(
+            value.field("nullifiers"),
+            Some(vec![Fp::from(2), Fp::from(3)])
+        );
+        assert_eq!(value.field("amount"), Some(vec![Fp::from(4)]));
+        assert_eq!(value.field("memo"), None);
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let value = transfer();
+        let bytes = value.to_bytes();
+        assert_eq!(bytes.len(), 5 * 32);
+        assert_eq!(Transfer::<Fp>::from_bytes(&bytes).unwrap(), value);
+
+        for len in [bytes.len() - 1, bytes.len() - 32, bytes.len() + 32] {
+            let mut wrong = bytes.clone();
+            wrong.resize(len, 0);
+            assert!(matches!(
+                Transfer::<Fp>::from_bytes(&wrong),
+                Err(Error::Encoding(_))
+            ));
+        }
+
+        // The modulus itself is not a canonical element
+        let mut overflow = bytes;
+        overflow[32..64].copy_from_slice(&(-Fp::ONE).to_repr());
+        overflow[32] += 1;
+        assert!(matches!(
+            Transfer::<Fp>::from_bytes(&overflow),
+            Err(Error::Encoding(_))
+        ));
+    }
+
+    #[test]
+    fn test_wrong_shape_rejected() {
+        let mut instances = transfer().to_instances();
+        instances[1].push(Fp::ZERO);
+        let err = Transfer::<Fp>::from_instances(&instances).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InstanceShape { ref expected, ref found }
+                if expected == &[4, 1] && found == &[4, 2]
+        ));
+    }
+}
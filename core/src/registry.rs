@@ -0,0 +1,401 @@
+//! Stable circuit ids for dispatching proofs to their verifying keys
+//!
+//! A [`CircuitRegistry`] maps a numeric id, stable across releases and
+//! carried as a varint in [`ProofMetadata::circuit_id`], to the circuit it
+//! names: a human-readable name, a version, the `k` it is keyed at and the
+//! resulting verifying key. Several versions of one circuit can be
+//! registered side by side under different ids, so a verifier keeps
+//! accepting proofs from provers that have not upgraded yet, and
+//! [`CircuitRegistry::negotiate`] picks the newest version both sides run.
+//!
+//! This is the prover-side counterpart of the verifier crate's key rotation
+//! registry, which tracks validity windows of keys rather than how to build
+//! them.
+
+use std::{fmt, ops::RangeInclusive, sync::Arc};
+
+use ff::FromUniformBytes;
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    plonk::{keygen_vk, verify_proof, Circuit, ProvingKey, SingleVerifier, VerifyingKey},
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Challenge255},
+};
+
+use crate::{
+    error::{Error, Result},
+    keys::KeyStore,
+    proof::{ProofEnvelope, ProofMetadata, VkHash},
+};
+
+type ProvingKeyFn<C> = dyn Fn(&KeyStore<C>, u32) -> Result<Arc<ProvingKey<C>>> + Send + Sync;
+
+/// One registered circuit version
+pub struct CircuitEntry<C: CurveAffine> {
+    /// Stable id, never reused for another circuit
+    pub id: u64,
+    /// Circuit name shared by all its versions, such as `dci`
+    pub name: String,
+    /// Version of the circuit under `name`
+    pub version: u32,
+    /// Log2 of the number of rows the circuit is keyed at
+    pub k: u32,
+    /// Hash of the verifying key
+    pub vk_hash: VkHash,
+    vk: VerifyingKey<C>,
+    proving_key: Box<ProvingKeyFn<C>>,
+}
+
+impl<C: CurveAffine> CircuitEntry<C> {
+    /// Verifying key of the circuit
+    #[must_use]
+    pub fn verifying_key(&self) -> &VerifyingKey<C> {
+        &self.vk
+    }
+}
+
+impl<C: CurveAffine> fmt::Debug for CircuitEntry<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CircuitEntry")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("version", &self.version)
+            .field("k", &self.k)
+            .field("vk_hash", &self.vk_hash)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Circuits by stable id, keyed through a shared [`KeyStore`]
+#[derive(Debug)]
+pub struct CircuitRegistry<C: CurveAffine> {
+    keys: KeyStore<C>,
+    entries: Vec<CircuitEntry<C>>,
+}
+
+impl<C> CircuitRegistry<C>
+where
+    C: CurveAffine,
+    C::Scalar: FromUniformBytes<64>,
+{
+    /// Empty registry taking params and keys from `keys`
+    #[must_use]
+    pub fn new(keys: KeyStore<C>) -> Self {
+        Self {
+            keys,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Register version `version` of circuit `name` under `id`
+    ///
+    /// `constructor` builds the circuit without witnesses; it is called
+    /// now to compute the verifying key and again whenever a proving key is
+    /// needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Other`] if `id` or `name` at `version` is already
+    /// registered, and [`Error::Plonk`] if keygen fails, for instance when
+    /// the circuit does not fit in `2^k` rows.
+    pub fn register<ConcreteCircuit, F>(
+        &mut self,
+        id: u64,
+        name: impl Into<String>,
+        version: u32,
+        k: u32,
+        constructor: F,
+    ) -> Result<&CircuitEntry<C>>
+    where
+        C: 'static,
+        ConcreteCircuit: Circuit<C::Scalar> + 'static,
+        F: Fn() -> ConcreteCircuit + Send + Sync + 'static,
+    {
+        let name = name.into();
+        if let Some(existing) = self
+            .entries
+            .iter()
+            .find(|entry| entry.id == id || (entry.name == name && entry.version == version))
+        {
+            return Err(Error::Other(format!(
+                "circuit id {id} ({name:?} version {version}) clashes with id {} ({:?} version {})",
+                existing.id, existing.name, existing.version
+            )));
+        }
+
+        let vk = keygen_vk(&*self.keys.params(k)?, &constructor())?;
+        self.entries.push(CircuitEntry {
+            id,
+            name,
+            version,
+            k,
+            vk_hash: VkHash::of(&vk),
+            vk,
+            proving_key: Box::new(move |keys, k| keys.get_or_generate(&constructor(), k)),
+        });
+        Ok(self.entries.last().expect("just pushed"))
+    }
+
+    /// Entry registered under `id`
+    #[must_use]
+    pub fn get(&self, id: u64) -> Option<&CircuitEntry<C>> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    /// Entry for version `version` of circuit `name`
+    #[must_use]
+    pub fn find(&self, name: &str, version: u32) -> Option<&CircuitEntry<C>> {
+        self.entries
+            .iter()
+            .find(|entry| entry.name == name && entry.version == version)
+    }
+
+    /// Registered versions of circuit `name`, oldest first
+    #[must_use]
+    pub fn versions(&self, name: &str) -> Vec<&CircuitEntry<C>> {
+        let mut versions: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.name == name)
+            .collect();
+        versions.sort_by_key(|entry| entry.version);
+        versions
+    }
+
+    /// Newest version of circuit `name` that a peer supporting `theirs`
+    /// also runs
+    #[must_use]
+    pub fn negotiate(&self, name: &str, theirs: &RangeInclusive<u32>) -> Option<&CircuitEntry<C>> {
+        self.versions(name)
+            .into_iter()
+            .rev()
+            .find(|entry| theirs.contains(&entry.version))
+    }
+
+    /// Params the circuit registered under `id` is keyed with
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Other`] if no circuit has `id`, and the
+    /// [`KeyStore`]'s error if params cannot be loaded.
+    pub fn params(&self, id: u64) -> Result<Arc<Params<C>>> {
+        self.keys.params(self.entry(id)?.k)
+    }
+
+    /// Proving key of the circuit registered under `id`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Other`] if no circuit has `id`, and the
+    /// [`KeyStore`]'s error if keygen or loading artifacts fails.
+    pub fn proving_key(&self, id: u64) -> Result<Arc<ProvingKey<C>>> {
+        let entry = self.entry(id)?;
+        (entry.proving_key)(&self.keys, entry.k)
+    }
+
+    /// Envelope for `proof`, made for the circuit registered under `id`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Other`] if no circuit has `id`.
+    pub fn envelope(&self, id: u64, proof: Vec<u8>) -> Result<ProofEnvelope> {
+        let entry = self.entry(id)?;
+        Ok(ProofEnvelope {
+            vk_hash: entry.vk_hash,
+            proof,
+            metadata: ProofMetadata::default().with_circuit_id(id),
+        })
+    }
+
+    /// Entry `envelope` was made for
+    ///
+    /// Envelopes carrying a circuit id are looked up by it, older ones by
+    /// their vk hash.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Other`] if no registered circuit matches, and
+    /// [`Error::VkMismatch`] if the id and vk hash name different keys.
+    pub fn dispatch(&self, envelope: &ProofEnvelope) -> Result<&CircuitEntry<C>> {
+        let Some(id) = envelope.metadata.circuit_id else {
+            return self
+                .entries
+                .iter()
+                .find(|entry| entry.vk_hash == envelope.vk_hash)
+                .ok_or_else(|| {
+                    Error::Other(format!("no circuit has verifying key {}", envelope.vk_hash))
+                });
+        };
+        let entry = self.entry(id)?;
+        envelope.open(&entry.vk_hash)?;
+        Ok(entry)
+    }
+
+    /// Verify `envelope` against `instances` with the key it dispatches to
+    ///
+    /// Returns the entry the proof verified under.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of [`CircuitRegistry::dispatch`], and
+    /// [`Error::Plonk`] if the proof does not verify.
+    pub fn verify(
+        &self,
+        envelope: &ProofEnvelope,
+        instances: &[Vec<C::Scalar>],
+    ) -> Result<&CircuitEntry<C>> {
+        let entry = self.dispatch(envelope)?;
+        let params = self.keys.params(entry.k)?;
+        let columns: Vec<&[C::Scalar]> = instances.iter().map(Vec::as_slice).collect();
+        let mut transcript = Blake2bRead::<_, C, Challenge255<_>>::init(envelope.proof.as_slice());
+        verify_proof(
+            &params,
+            &entry.vk,
+            SingleVerifier::new(&params),
+            &[&columns],
+            &mut transcript,
+        )?;
+        Ok(entry)
+    }
+
+    fn entry(&self, id: u64) -> Result<&CircuitEntry<C>> {
+        self.get(id)
+            .ok_or_else(|| Error::Other(format!("unknown circuit id {id}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        plonk::{Advice, Column, ConstraintSystem, Error as PlonkError, Instance},
+    };
+    use pasta_curves::{vesta, Fp};
+
+    use super::*;
+    use crate::{proof::write_proof, rng::OsRandomness};
+
+    /// Exposes `value * FACTOR`; each factor is a different circuit
+    #[derive(Clone, Default)]
+    struct Scaled<const FACTOR: u64> {
+        value: Value<Fp>,
+    }
+
+    impl<const FACTOR: u64> Circuit<Fp> for Scaled<FACTOR> {
+        type Config = (Column<Advice>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(advice);
+            meta.enable_equality(instance);
+            (advice, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (advice, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> std::result::Result<(), PlonkError> {
+            let cell = layouter.assign_region(
+                || "scaled",
+                |mut region| {
+                    region.assign_advice(
+                        || "scaled",
+                        advice,
+                        0,
+                        || self.value * Value::known(Fp::from(FACTOR)),
+                    )
+                },
+            )?;
+            layouter.constrain_instance(cell.cell(), instance, 0)
+        }
+    }
+
+    fn registry() -> CircuitRegistry<vesta::Affine> {
+        let mut registry = CircuitRegistry::new(KeyStore::in_memory());
+        registry
+            .register(7, "scaled", 1, 4, Scaled::<2>::default)
+            .unwrap();
+        registry
+            .register(9, "scaled", 2, 4, Scaled::<3>::default)
+            .unwrap();
+        registry
+    }
+
+    fn prove<const FACTOR: u64>(registry: &CircuitRegistry<vesta::Affine>, id: u64) -> Vec<u8> {
+        let circuit = Scaled::<FACTOR> {
+            value: Value::known(Fp::from(5)),
+        };
+        let instances = [Fp::from(5 * FACTOR)];
+        write_proof(
+            &*registry.params(id).unwrap(),
+            &registry.proving_key(id).unwrap(),
+            &[circuit],
+            &[&[&instances]],
+            OsRandomness,
+            Vec::new(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_dispatch_by_id() {
+        let registry = registry();
+        for (id, expected, proof) in [
+            (7, 10, prove::<2>(&registry, 7)),
+            (9, 15, prove::<3>(&registry, 9)),
+        ] {
+            let envelope =
+                ProofEnvelope::from_bytes(&registry.envelope(id, proof).unwrap().to_bytes())
+                    .unwrap();
+            assert_eq!(envelope.metadata.circuit_id, Some(id));
+            let entry = registry
+                .verify(&envelope, &[vec![Fp::from(expected)]])
+                .unwrap();
+            assert_eq!(entry.id, id);
+            assert!(registry
+                .verify(&envelope, &[vec![Fp::from(expected + 1)]])
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn test_dispatch_without_id_uses_vk_hash() {
+        let registry = registry();
+        let mut envelope = registry.envelope(9, prove::<3>(&registry, 9)).unwrap();
+        envelope.metadata.circuit_id = None;
+        assert_eq!(registry.dispatch(&envelope).unwrap().id, 9);
+
+        // An id naming another key is rejected before verifying
+        envelope.metadata.circuit_id = Some(7);
+        assert!(matches!(
+            registry.dispatch(&envelope),
+            Err(Error::VkMismatch { .. })
+        ));
+        envelope.metadata.circuit_id = Some(8);
+        assert!(matches!(registry.dispatch(&envelope), Err(Error::Other(_))));
+    }
+
+    #[test]
+    fn test_negotiate_and_clashes() {
+        let mut registry = registry();
+        assert_eq!(registry.negotiate("scaled", &(1..=5)).unwrap().version, 2);
+        assert_eq!(registry.negotiate("scaled", &(0..=1)).unwrap().id, 7);
+        assert!(registry.negotiate("scaled", &(3..=5)).is_none());
+        assert!(registry.negotiate("other", &(0..=9)).is_none());
+
+        assert!(registry
+            .register(7, "other", 1, 4, Scaled::<5>::default)
+            .is_err());
+        assert!(registry
+            .register(11, "scaled", 2, 4, Scaled::<5>::default)
+            .is_err());
+        assert_eq!(registry.versions("scaled").len(), 2);
+    }
+}
@@ -0,0 +1,169 @@
+//! Secret witness values
+//!
+//! Balances, keys and tree positions are wrapped in [`SecretWitness`] so
+//! they are wiped when dropped, print as `<redacted>` and compare in
+//! constant time. Values are held as their byte representation, which is
+//! what gets zeroized; field elements handed out to synthesis are copies
+//! the wrapper cannot track.
+
+use std::fmt;
+
+use ff::PrimeField;
+use halo2_proofs::circuit::Value;
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// Field element known only to the prover, or unknown during keygen
+pub struct SecretWitness<F: PrimeField> {
+    repr: Option<F::Repr>,
+}
+
+impl<F: PrimeField> SecretWitness<F> {
+    /// Wrap a known `value`
+    #[must_use]
+    pub fn new(value: F) -> Self {
+        Self {
+            repr: Some(value.to_repr()),
+        }
+    }
+
+    /// Placeholder for keygen and `Circuit::without_witnesses`
+    #[must_use]
+    pub fn unknown() -> Self {
+        Self { repr: None }
+    }
+
+    /// Whether a value is held
+    #[must_use]
+    pub fn is_known(&self) -> bool {
+        self.repr.is_some()
+    }
+
+    /// Value to assign during synthesis
+    #[must_use]
+    pub fn value(&self) -> Value<F> {
+        match self.expose() {
+            Some(value) => Value::known(value),
+            None => Value::unknown(),
+        }
+    }
+
+    /// Value to compute with natively, outside a circuit
+    #[must_use]
+    pub fn expose(&self) -> Option<F> {
+        // Only ever built from `to_repr`, so always canonical
+        self.repr.and_then(|repr| F::from_repr(repr).into())
+    }
+}
+
+impl<F: PrimeField> Default for SecretWitness<F> {
+    fn default() -> Self {
+        Self::unknown()
+    }
+}
+
+impl<F: PrimeField> Clone for SecretWitness<F> {
+    fn clone(&self) -> Self {
+        Self { repr: self.repr }
+    }
+}
+
+impl<F: PrimeField> From<F> for SecretWitness<F> {
+    fn from(value: F) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<F: PrimeField> From<Value<F>> for SecretWitness<F> {
+    fn from(value: Value<F>) -> Self {
+        let mut secret = Self::unknown();
+        value.map(|value| secret = Self::new(value));
+        secret
+    }
+}
+
+impl<F: PrimeField> fmt::Debug for SecretWitness<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_known() {
+            f.write_str("SecretWitness(<redacted>)")
+        } else {
+            f.write_str("SecretWitness(unknown)")
+        }
+    }
+}
+
+impl<F: PrimeField> ConstantTimeEq for SecretWitness<F> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        // Whether a value is known is not secret, only the value itself
+        match (&self.repr, &other.repr) {
+            (Some(a), Some(b)) => a.as_ref().ct_eq(b.as_ref()),
+            (None, None) => Choice::from(1),
+            _ => Choice::from(0),
+        }
+    }
+}
+
+impl<F: PrimeField> PartialEq for SecretWitness<F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl<F: PrimeField> Eq for SecretWitness<F> {}
+
+impl<F: PrimeField> Zeroize for SecretWitness<F> {
+    fn zeroize(&mut self) {
+        if let Some(repr) = &mut self.repr {
+            repr.as_mut().zeroize();
+        }
+        self.repr = None;
+    }
+}
+
+impl<F: PrimeField> Drop for SecretWitness<F> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<F: PrimeField> ZeroizeOnDrop for SecretWitness<F> {}
+
+#[cfg(test)]
+mod tests {
+    use pasta_curves::Fp;
+
+    use super::*;
+
+    #[test]
+    fn test_debug_redacts() {
+        let secret = SecretWitness::new(Fp::from(1_000));
+        assert_eq!(format!("{secret:?}"), "SecretWitness(<redacted>)");
+        assert_eq!(
+            format!("{:?}", SecretWitness::<Fp>::unknown()),
+            "SecretWitness(unknown)"
+        );
+    }
+
+    #[test]
+    fn test_round_trip_and_compare() {
+        let secret = SecretWitness::from(Value::known(Fp::from(7)));
+        assert_eq!(secret.expose(), Some(Fp::from(7)));
+        secret
+            .value()
+            .assert_if_known(|value| *value == Fp::from(7));
+        assert_eq!(SecretWitness::from(Value::<Fp>::unknown()).expose(), None);
+
+        assert_eq!(secret, SecretWitness::new(Fp::from(7)));
+        assert_ne!(secret, SecretWitness::new(Fp::from(8)));
+        assert_ne!(secret, SecretWitness::unknown());
+        assert_eq!(SecretWitness::<Fp>::unknown(), SecretWitness::unknown());
+    }
+
+    #[test]
+    fn test_zeroize() {
+        let mut secret = SecretWitness::new(Fp::from(42));
+        secret.zeroize();
+        assert!(!secret.is_known());
+        assert_eq!(secret.expose(), None);
+    }
+}
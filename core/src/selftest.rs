@@ -0,0 +1,172 @@
+//! Startup self-test against embedded test vectors
+//!
+//! [`selftest`] runs keygen, proving and verification for a tiny built-in
+//! circuit with fixed randomness and compares the verifying key and proof
+//! against hashes recorded from a known-good build. A miscompiled field or
+//! curve backend, a broken SIMD path or an unexpected halo2 upgrade shows up
+//! as a failed self-test at startup instead of as rejected proofs in
+//! production. It takes well under a second in release builds.
+
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{
+        keygen_pk, keygen_vk, verify_proof, Advice, Circuit, Column, ConstraintSystem,
+        Error as PlonkError, Instance, Selector, SingleVerifier,
+    },
+    poly::{commitment::Params, Rotation},
+    transcript::{Blake2bRead, Challenge255},
+};
+use pasta_curves::{vesta, Fp};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::{Error, Result, ResultExt},
+    proof::{write_proof, VkHash},
+    rng::DeterministicRandomness,
+    utils::encoding::encode_hex,
+};
+
+/// Size of the self-test circuit
+const K: u32 = 4;
+
+/// Seed for the proof's blinding factors
+const SEED: [u8; 32] = *b"zk-proof-system selftest seed 01";
+
+/// Witness of the self-test circuit
+const ROOT: u64 = 7;
+
+/// Expected [`VkHash`] of the self-test circuit
+const VK_HASH: &str = "bfca85507145c43ee0fafb032a369282481cf7bc4a6bd7f5c9424d3938121af4";
+
+/// Expected SHA-256 of the self-test proof
+const PROOF_HASH: &str = "b19c0a3fb749ffb5c0167a59d9338cb4172efb2fdb80e861e4daaac019049112";
+
+/// Knows a square root of its public input
+#[derive(Default)]
+struct Square {
+    root: Value<Fp>,
+}
+
+impl Circuit<Fp> for Square {
+    type Config = (Column<Advice>, Column<Instance>, Selector);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let advice = meta.advice_column();
+        let instance = meta.instance_column();
+        let selector = meta.selector();
+        meta.enable_equality(advice);
+        meta.enable_equality(instance);
+        meta.create_gate("square", |meta| {
+            let s = meta.query_selector(selector);
+            let root = meta.query_advice(advice, Rotation::cur());
+            let square = meta.query_advice(advice, Rotation::next());
+            vec![s * (root.clone() * root - square)]
+        });
+        (advice, instance, selector)
+    }
+
+    fn synthesize(
+        &self,
+        (advice, instance, selector): Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> std::result::Result<(), PlonkError> {
+        let square = layouter.assign_region(
+            || "square",
+            |mut region| {
+                selector.enable(&mut region, 0)?;
+                region.assign_advice(|| "root", advice, 0, || self.root)?;
+                region.assign_advice(|| "square", advice, 1, || self.root * self.root)
+            },
+        )?;
+        layouter.constrain_instance(square.cell(), instance, 0)
+    }
+}
+
+/// Prove and verify the built-in circuit and check it against test vectors
+///
+/// # Errors
+///
+/// Returns [`Error::Verification`] if the verifying key or proof differs
+/// from the embedded test vectors, if the proof does not verify, or if a
+/// proof for the wrong public input is accepted. Keygen and proving errors
+/// are returned with context.
+pub fn selftest() -> Result<()> {
+    let params = Params::<vesta::Affine>::new(K);
+    let vk = keygen_vk(&params, &Square::default()).context("self-test keygen")?;
+    let vk_hash = VkHash::of(&vk).to_string();
+    expect("verifying key", &vk_hash, VK_HASH)?;
+    let pk = keygen_pk(&params, vk, &Square::default()).context("self-test keygen")?;
+
+    let circuit = Square {
+        root: Value::known(Fp::from(ROOT)),
+    };
+    let square = [Fp::from(ROOT * ROOT)];
+    let proof = write_proof(
+        &params,
+        &pk,
+        &[circuit],
+        &[&[&square]],
+        DeterministicRandomness::from_seed(SEED),
+        Vec::new(),
+    )
+    .context("self-test proving")?;
+    expect("proof", &encode_hex(&Sha256::digest(&proof)), PROOF_HASH)?;
+
+    if !verifies(&params, &pk, &proof, Fp::from(ROOT * ROOT)) {
+        return Err(Error::Verification("self-test proof was rejected".into()));
+    }
+    if verifies(&params, &pk, &proof, Fp::from(ROOT * ROOT + 1)) {
+        return Err(Error::Verification(
+            "self-test proof was accepted for the wrong input".into(),
+        ));
+    }
+    Ok(())
+}
+
+fn verifies(
+    params: &Params<vesta::Affine>,
+    pk: &halo2_proofs::plonk::ProvingKey<vesta::Affine>,
+    proof: &[u8],
+    square: Fp,
+) -> bool {
+    let mut transcript = Blake2bRead::<_, vesta::Affine, Challenge255<_>>::init(proof);
+    verify_proof(
+        params,
+        pk.get_vk(),
+        SingleVerifier::new(params),
+        &[&[&[square]]],
+        &mut transcript,
+    )
+    .is_ok()
+}
+
+fn expect(what: &str, found: &str, expected: &str) -> Result<()> {
+    if found == expected {
+        Ok(())
+    } else {
+        Err(Error::Verification(format!(
+            "self-test {what} hash is {found}, expected {expected}"
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selftest_passes() {
+        selftest().unwrap();
+    }
+
+    #[test]
+    fn test_expect_reports_mismatch() {
+        let err = expect("proof", "ab", "cd").unwrap_err();
+        assert!(matches!(err, Error::Verification(ref msg) if msg.contains("ab")));
+    }
+}
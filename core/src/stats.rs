@@ -0,0 +1,436 @@
+//! Per-phase prover time and memory statistics
+//!
+//! [`ProverStats::measure`] records time, heap and RSS for one prover phase.
+//! Heap figures need [`TrackingAllocator`] installed as the global allocator
+//! of the binary doing the measuring and are `None` otherwise; RSS figures
+//! come from `/proc/self` and are `None` off Linux.
+//!
+//! halo2 0.3 does not expose hooks inside `create_proof`, so
+//! [`profile_prover`] replays each phase with halo2's own building blocks,
+//! sized to a proving key, to attribute the prover's memory to its parts.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    collections::BTreeMap,
+    fmt, fs,
+    sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use ff::{Field, FromUniformBytes};
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    dev::MockProver,
+    plonk::{Circuit, ProvingKey},
+    poly::{
+        commitment::{Blind, Params},
+        multiopen::{self, ProverQuery},
+    },
+    transcript::{Blake2bWrite, Challenge255},
+};
+use rand::rngs::OsRng;
+
+use crate::error::{Error, Result};
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED: AtomicU64 = AtomicU64::new(0);
+
+/// Global allocator wrapper that counts heap usage for [`ProverStats`]
+///
+/// ```no_run
+/// use std::alloc::System;
+/// use zk_proof_core::stats::TrackingAllocator;
+///
+/// #[global_allocator]
+/// static ALLOCATOR: TrackingAllocator = TrackingAllocator::new(System);
+/// ```
+#[derive(Debug, Default)]
+pub struct TrackingAllocator<A = System> {
+    inner: A,
+}
+
+impl<A> TrackingAllocator<A> {
+    /// Wrap `inner`
+    pub const fn new(inner: A) -> Self {
+        Self { inner }
+    }
+}
+
+fn record_alloc(size: usize) {
+    INSTALLED.store(true, Ordering::Relaxed);
+    let live = LIVE.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(live, Ordering::Relaxed);
+    ALLOCATED.fetch_add(size as u64, Ordering::Relaxed);
+}
+
+// SAFETY: every call is forwarded unchanged to `inner`; only counters are
+// updated around it.
+unsafe impl<A: GlobalAlloc> GlobalAlloc for TrackingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc(layout);
+        if !ptr.is_null() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout);
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = self.inner.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+            record_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
+/// Whether [`TrackingAllocator`] is the global allocator
+#[must_use]
+pub fn heap_tracking() -> bool {
+    INSTALLED.load(Ordering::Relaxed)
+}
+
+/// Resident set size of this process in bytes
+#[must_use]
+pub fn current_rss() -> Option<u64> {
+    proc_status_kib("VmRSS:").map(|kib| kib * 1024)
+}
+
+/// Peak resident set size since the last reset, in bytes
+#[must_use]
+pub fn peak_rss() -> Option<u64> {
+    proc_status_kib("VmHWM:").map(|kib| kib * 1024)
+}
+
+fn proc_status_kib(key: &str) -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with(key))?;
+    line[key.len()..]
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Reset [`peak_rss`] to the current RSS where the kernel supports it
+fn reset_peak_rss() {
+    // Older kernels and non-Linux systems keep the lifetime peak instead.
+    let _ = fs::write("/proc/self/clear_refs", "5");
+}
+
+/// A prover phase
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Phase {
+    /// Witness generation
+    Synthesis,
+    /// Interpolation and extended-domain evaluation
+    Fft,
+    /// Polynomial commitments
+    Msm,
+    /// Multi-opening argument
+    Opening,
+}
+
+impl Phase {
+    /// Every phase, in prover order
+    pub const ALL: [Self; 4] = [Self::Synthesis, Self::Fft, Self::Msm, Self::Opening];
+
+    /// Lowercase name of the phase
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Synthesis => "synthesis",
+            Self::Fft => "fft",
+            Self::Msm => "msm",
+            Self::Opening => "opening",
+        }
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Statistics of one phase, accumulated over every measured call
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PhaseStats {
+    /// Number of measured calls
+    pub calls: u32,
+    /// Total wall-clock time
+    pub duration: Duration,
+    /// Total bytes allocated, if heap tracking is on
+    pub allocated_bytes: Option<u64>,
+    /// Largest heap growth above the level at the start of a call
+    pub peak_heap_bytes: Option<u64>,
+    /// Largest peak RSS seen at the end of a call
+    ///
+    /// Includes earlier phases when the kernel cannot reset the peak.
+    pub peak_rss_bytes: Option<u64>,
+}
+
+/// Time and memory of each prover phase
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProverStats {
+    phases: BTreeMap<Phase, PhaseStats>,
+}
+
+impl ProverStats {
+    /// Empty statistics
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `f` as `phase` and record its statistics
+    ///
+    /// Phases must not nest: each call resets the heap and RSS peaks.
+    pub fn measure<T>(&mut self, phase: Phase, f: impl FnOnce() -> T) -> T {
+        let live_before = LIVE.load(Ordering::Relaxed);
+        PEAK.store(live_before, Ordering::Relaxed);
+        let allocated_before = ALLOCATED.load(Ordering::Relaxed);
+        reset_peak_rss();
+
+        let started = Instant::now();
+        let output = f();
+        let duration = started.elapsed();
+
+        let stats = self.phases.entry(phase).or_default();
+        stats.calls += 1;
+        stats.duration += duration;
+        if heap_tracking() {
+            let allocated = ALLOCATED.load(Ordering::Relaxed) - allocated_before;
+            let peak = PEAK.load(Ordering::Relaxed).saturating_sub(live_before) as u64;
+            stats.allocated_bytes = Some(stats.allocated_bytes.unwrap_or(0) + allocated);
+            stats.peak_heap_bytes = stats.peak_heap_bytes.max(Some(peak));
+        }
+        stats.peak_rss_bytes = stats.peak_rss_bytes.max(peak_rss());
+        output
+    }
+
+    /// Statistics of `phase`, if it was measured
+    #[must_use]
+    pub fn get(&self, phase: Phase) -> Option<&PhaseStats> {
+        self.phases.get(&phase)
+    }
+
+    /// Measured phases in prover order
+    pub fn iter(&self) -> impl Iterator<Item = (Phase, &PhaseStats)> {
+        self.phases.iter().map(|(phase, stats)| (*phase, stats))
+    }
+}
+
+impl fmt::Display for ProverStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[allow(clippy::cast_precision_loss)]
+        let mib = |bytes: Option<u64>| {
+            bytes.map_or_else(
+                || "-".to_owned(),
+                |bytes| format!("{:.1} MiB", bytes as f64 / f64::from(1 << 20)),
+            )
+        };
+        writeln!(
+            f,
+            "{:<10} {:>10} {:>14} {:>14} {:>14}",
+            "phase", "time", "allocated", "peak heap", "peak rss"
+        )?;
+        for (phase, stats) in self.iter() {
+            writeln!(
+                f,
+                "{:<10} {:>10} {:>14} {:>14} {:>14}",
+                phase.name(),
+                format!("{:.1?}", stats.duration),
+                mib(stats.allocated_bytes),
+                mib(stats.peak_heap_bytes),
+                mib(stats.peak_rss_bytes),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Replay each prover phase for `circuit` and record its statistics
+///
+/// Synthesis runs the circuit through `MockProver`, whose cell table is an
+/// upper bound on the prover's witness. The other phases process `polys`
+/// polynomials of the key's domain the way the prover does, keeping their
+/// outputs alive until the phase ends; pass the number of committed
+/// polynomials (advice, instance, lookup and permutation) to match a real
+/// proof.
+///
+/// # Errors
+///
+/// Returns [`Error::Synthesis`] if the circuit does not synthesize and
+/// [`Error::Io`] if the opening argument fails.
+pub fn profile_prover<C, ConcreteCircuit>(
+    params: &Params<C>,
+    pk: &ProvingKey<C>,
+    circuit: &ConcreteCircuit,
+    instances: Vec<Vec<C::Scalar>>,
+    polys: usize,
+) -> Result<ProverStats>
+where
+    C: CurveAffine,
+    C::Scalar: Ord + FromUniformBytes<64>,
+    ConcreteCircuit: Circuit<C::Scalar>,
+{
+    let mut stats = ProverStats::new();
+    let domain = pk.get_vk().get_domain();
+
+    stats
+        .measure(Phase::Synthesis, || {
+            MockProver::run(params.k(), circuit, instances)
+        })
+        .map_err(|err| Error::Synthesis(format!("profiling synthesis: {err}")))?;
+
+    let lagrange: Vec<_> = (0..polys)
+        .map(|seed| {
+            let mut poly = domain.empty_lagrange();
+            for (i, value) in poly.iter_mut().enumerate() {
+                *value = C::Scalar::from((seed + i + 1) as u64);
+            }
+            poly
+        })
+        .collect();
+
+    let coeffs = stats.measure(Phase::Fft, || {
+        let coeffs: Vec<_> = lagrange
+            .iter()
+            .map(|poly| domain.lagrange_to_coeff(poly.clone()))
+            .collect();
+        let extended: Vec<_> = coeffs
+            .iter()
+            .map(|poly| domain.coeff_to_extended(poly.clone()))
+            .collect();
+        drop(extended);
+        coeffs
+    });
+
+    stats.measure(Phase::Msm, || {
+        lagrange
+            .iter()
+            .map(|poly| params.commit_lagrange(poly, Blind::default()))
+            .collect::<Vec<_>>()
+    });
+
+    stats.measure(Phase::Opening, || {
+        let point = C::Scalar::random(OsRng);
+        let queries = coeffs.iter().map(|poly| ProverQuery {
+            point,
+            poly,
+            blind: Blind::default(),
+        });
+        let mut transcript = Blake2bWrite::<_, C, Challenge255<_>>::init(vec![]);
+        multiopen::create_proof(params, OsRng, &mut transcript, queries)
+    })?;
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        plonk::{keygen_pk, keygen_vk, Advice, Column, ConstraintSystem, Error as PlonkError},
+    };
+    use pasta_curves::{vesta, Fp};
+
+    use super::*;
+
+    #[global_allocator]
+    static ALLOCATOR: TrackingAllocator = TrackingAllocator::new(System);
+
+    /// Heap peaks are process-wide, so measuring tests take turns
+    static MEASURING: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    #[derive(Default)]
+    struct OneCell {
+        value: Value<Fp>,
+    }
+
+    impl Circuit<Fp> for OneCell {
+        type Config = Column<Advice>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Column<Advice> {
+            meta.advice_column()
+        }
+
+        fn synthesize(
+            &self,
+            column: Column<Advice>,
+            mut layouter: impl Layouter<Fp>,
+        ) -> std::result::Result<(), PlonkError> {
+            layouter.assign_region(
+                || "cell",
+                |mut region| region.assign_advice(|| "value", column, 0, || self.value),
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_measure_tracks_heap() {
+        let _guard = MEASURING.lock().unwrap();
+        let mut stats = ProverStats::new();
+        let buffer = stats.measure(Phase::Msm, || vec![0u8; 1 << 20]);
+        stats.measure(Phase::Msm, || ());
+        drop(buffer);
+
+        let msm = stats.get(Phase::Msm).unwrap();
+        assert!(heap_tracking());
+        assert_eq!(msm.calls, 2);
+        assert!(msm.allocated_bytes.unwrap() >= 1 << 20);
+        assert!(msm.peak_heap_bytes.unwrap() >= 1 << 20);
+        assert!(stats.get(Phase::Fft).is_none());
+    }
+
+    #[test]
+    fn test_profile_prover() {
+        let _guard = MEASURING.lock().unwrap();
+        let params = Params::<vesta::Affine>::new(4);
+        let circuit = OneCell {
+            value: Value::known(Fp::from(3)),
+        };
+        let vk = keygen_vk(&params, &OneCell::default()).unwrap();
+        let pk = keygen_pk(&params, vk, &OneCell::default()).unwrap();
+
+        let stats = profile_prover(&params, &pk, &circuit, vec![], 2).unwrap();
+        let phases: Vec<_> = stats.iter().map(|(phase, _)| phase).collect();
+        assert_eq!(phases, Phase::ALL);
+        for (_, phase) in stats.iter() {
+            assert_eq!(phase.calls, 1);
+            assert!(phase.peak_heap_bytes.is_some());
+        }
+        assert!(stats.to_string().starts_with("phase"));
+    }
+
+    #[test]
+    fn test_rss() {
+        if cfg!(target_os = "linux") {
+            assert!(current_rss().unwrap() > 0);
+            assert!(peak_rss().unwrap() >= current_rss().unwrap() / 2);
+        }
+    }
+}
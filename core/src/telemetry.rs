@@ -0,0 +1,176 @@
+//! Structured events from circuit synthesis
+//!
+//! Library code never prints: WASM and FFI hosts have no stderr to read.
+//! Circuits instead report what they lay out to the [`CircuitTelemetry`]
+//! behind their [`Telemetry`] handle, which does nothing unless a sink is
+//! attached. [`TelemetryLog`] is a sink that keeps every event, for tests
+//! and for hosts that forward them to their own logging.
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+/// Something a circuit reports while synthesizing
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TelemetryEvent {
+    /// `circuit` started synthesizing
+    SynthesisStarted {
+        /// Name of the circuit
+        circuit: &'static str,
+    },
+    /// `circuit` laid out the part `region`
+    Region {
+        /// Name of the circuit
+        circuit: &'static str,
+        /// Name of the region or group of regions
+        region: &'static str,
+    },
+    /// `gate` adds `count` constraints
+    Constraints {
+        /// Name of the gate
+        gate: String,
+        /// Constraints it adds
+        count: usize,
+    },
+    /// `total` constraints are more than `budget`
+    OverBudget {
+        /// Constraints counted
+        total: usize,
+        /// Constraints allowed
+        budget: usize,
+    },
+    /// `circuit` finished synthesizing
+    SynthesisFinished {
+        /// Name of the circuit
+        circuit: &'static str,
+    },
+}
+
+/// Receiver of [`TelemetryEvent`]s
+///
+/// Circuits are synthesized from prover threads, so sinks must be shareable
+/// across them.
+pub trait CircuitTelemetry: Send + Sync {
+    /// Handle one event
+    fn record(&self, event: &TelemetryEvent);
+}
+
+/// Optional [`CircuitTelemetry`] sink held by a circuit
+///
+/// Cloning shares the sink, so a circuit's keygen shape from
+/// `without_witnesses` reports to the same place.
+#[derive(Clone, Default)]
+pub struct Telemetry(Option<Arc<dyn CircuitTelemetry>>);
+
+impl Telemetry {
+    /// Handle reporting to `sink`
+    #[must_use]
+    pub fn new(sink: Arc<dyn CircuitTelemetry>) -> Self {
+        Self(Some(sink))
+    }
+
+    /// Whether a sink is attached
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Pass `event` to the sink, if any
+    pub fn record(&self, event: &TelemetryEvent) {
+        if let Some(sink) = &self.0 {
+            sink.record(event);
+        }
+    }
+
+    /// Report the start of `circuit`
+    pub(crate) fn started(&self, circuit: &'static str) {
+        self.record(&TelemetryEvent::SynthesisStarted { circuit });
+    }
+
+    /// Report that `circuit` laid out `region`
+    pub(crate) fn region(&self, circuit: &'static str, region: &'static str) {
+        self.record(&TelemetryEvent::Region { circuit, region });
+    }
+
+    /// Report the end of `circuit`
+    pub(crate) fn finished(&self, circuit: &'static str) {
+        self.record(&TelemetryEvent::SynthesisFinished { circuit });
+    }
+}
+
+impl fmt::Debug for Telemetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Telemetry")
+            .field("enabled", &self.is_enabled())
+            .finish()
+    }
+}
+
+/// Sink keeping every event in order
+#[derive(Debug, Default)]
+pub struct TelemetryLog {
+    events: Mutex<Vec<TelemetryEvent>>,
+}
+
+impl TelemetryLog {
+    /// Empty log
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Events recorded so far
+    #[must_use]
+    pub fn events(&self) -> Vec<TelemetryEvent> {
+        self.events
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Remove and return the events recorded so far
+    pub fn take(&self) -> Vec<TelemetryEvent> {
+        std::mem::take(&mut *self.events.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+impl CircuitTelemetry for TelemetryLog {
+    fn record(&self, event: &TelemetryEvent) {
+        self.events
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(event.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_handle_drops_events() {
+        let telemetry = Telemetry::default();
+        assert!(!telemetry.is_enabled());
+        telemetry.started("test");
+    }
+
+    #[test]
+    fn test_log_shared_by_clones() {
+        let log = Arc::new(TelemetryLog::new());
+        let telemetry = Telemetry::new(log.clone());
+        telemetry.started("test");
+        telemetry.clone().region("test", "main");
+        assert_eq!(
+            log.take(),
+            [
+                TelemetryEvent::SynthesisStarted { circuit: "test" },
+                TelemetryEvent::Region {
+                    circuit: "test",
+                    region: "main"
+                },
+            ]
+        );
+        assert!(log.events().is_empty());
+    }
+}
@@ -0,0 +1,340 @@
+//! Property-based circuit testing
+//!
+//! [`CircuitProperty`] builds a circuit and its instances from a witness,
+//! draws witnesses from a proptest [`Strategy`] and runs each through
+//! `MockProver`. [`CircuitProperty::check_valid`] expects every witness to
+//! satisfy the circuit and [`CircuitProperty::check_invalid`] expects every
+//! one to fail it, which is how under-constrained gates show up. A failing
+//! witness is shrunk to a minimal one before it is reported.
+//!
+//! [`assert_constraint_fails`] pins a single rejection down further, to the
+//! gate or region that fails. halo2 keeps the names in a failure private,
+//! so they are read from its `Display` output.
+//!
+//! Available in the crate's own tests and, for downstream circuits, with
+//! the `testing` feature.
+
+use std::fmt;
+
+use ff::FromUniformBytes;
+use halo2_proofs::{
+    dev::{MockProver, VerifyFailure},
+    plonk::Circuit,
+};
+use proptest::{
+    strategy::Strategy,
+    test_runner::{Config, TestCaseError, TestError, TestRunner},
+};
+
+/// Cases run by default, matching proptest's own default
+const DEFAULT_CASES: u32 = 256;
+
+/// Builds the circuit and instances a witness `W` stands for
+type Build<F, C, W> = dyn Fn(&W) -> (C, Vec<Vec<F>>);
+
+/// Satisfaction property of a circuit over generated witnesses
+pub struct CircuitProperty<F, C, W> {
+    k: u32,
+    cases: u32,
+    build: Box<Build<F, C, W>>,
+}
+
+impl<F, C, W> CircuitProperty<F, C, W>
+where
+    F: Ord + FromUniformBytes<64>,
+    C: Circuit<F>,
+    W: fmt::Debug,
+{
+    /// Property of the circuit `build` makes from each witness, run at
+    /// size `k`
+    #[must_use]
+    pub fn new(k: u32, build: impl Fn(&W) -> (C, Vec<Vec<F>>) + 'static) -> Self {
+        Self {
+            k,
+            cases: DEFAULT_CASES,
+            build: Box::new(build),
+        }
+    }
+
+    /// Run `cases` witnesses per check
+    #[must_use]
+    pub fn with_cases(mut self, cases: u32) -> Self {
+        self.cases = cases;
+        self
+    }
+
+    /// Whether the circuit built from `witness` is satisfied
+    ///
+    /// A circuit that fails to synthesize is not.
+    #[must_use]
+    pub fn is_satisfied(&self, witness: &W) -> bool {
+        let (circuit, instances) = (self.build)(witness);
+        MockProver::run(self.k, &circuit, instances).is_ok_and(|prover| prover.verify().is_ok())
+    }
+
+    /// Check that every witness `strategy` generates satisfies the circuit
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TestError::Fail`] with the smallest rejected witness found
+    /// by shrinking, or [`TestError::Abort`] if `strategy` rejects too many
+    /// candidates.
+    pub fn check_valid<S>(&self, strategy: &S) -> Result<(), TestError<W>>
+    where
+        S: Strategy<Value = W>,
+    {
+        self.check(strategy, true)
+    }
+
+    /// Check that no witness `strategy` generates satisfies the circuit
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TestError::Fail`] with the smallest accepted witness found
+    /// by shrinking, or [`TestError::Abort`] if `strategy` rejects too many
+    /// candidates.
+    pub fn check_invalid<S>(&self, strategy: &S) -> Result<(), TestError<W>>
+    where
+        S: Strategy<Value = W>,
+    {
+        self.check(strategy, false)
+    }
+
+    /// [`CircuitProperty::check_valid`], panicking on failure
+    ///
+    /// # Panics
+    ///
+    /// Panics with the minimal failing witness if any is rejected.
+    pub fn assert_valid<S>(&self, strategy: &S)
+    where
+        S: Strategy<Value = W>,
+    {
+        if let Err(err) = self.check_valid(strategy) {
+            panic!("valid witness rejected: {err}");
+        }
+    }
+
+    /// [`CircuitProperty::check_invalid`], panicking on failure
+    ///
+    /// # Panics
+    ///
+    /// Panics with the minimal failing witness if any is accepted.
+    pub fn assert_invalid<S>(&self, strategy: &S)
+    where
+        S: Strategy<Value = W>,
+    {
+        if let Err(err) = self.check_invalid(strategy) {
+            panic!("invalid witness accepted: {err}");
+        }
+    }
+
+    fn check<S>(&self, strategy: &S, expected: bool) -> Result<(), TestError<W>>
+    where
+        S: Strategy<Value = W>,
+    {
+        let mut config = Config::with_cases(self.cases);
+        // Regressions belong in the caller's tests, not in files next to
+        // this module
+        config.failure_persistence = None;
+        TestRunner::new(config).run(strategy, |witness| {
+            if self.is_satisfied(&witness) == expected {
+                Ok(())
+            } else if expected {
+                Err(TestCaseError::fail("circuit not satisfied"))
+            } else {
+                Err(TestCaseError::fail("circuit satisfied"))
+            }
+        })
+    }
+}
+
+impl<F, C, W> fmt::Debug for CircuitProperty<F, C, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CircuitProperty")
+            .field("k", &self.k)
+            .field("cases", &self.cases)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Failures of `circuit` with `instances` at size `k`, empty if it is
+/// satisfied
+///
+/// # Panics
+///
+/// Panics if the circuit does not synthesize, which leaves nothing to
+/// pinpoint.
+pub fn failures<F, C>(k: u32, circuit: &C, instances: Vec<Vec<F>>) -> Vec<VerifyFailure>
+where
+    F: Ord + FromUniformBytes<64>,
+    C: Circuit<F>,
+{
+    let prover = MockProver::run(k, circuit, instances)
+        .unwrap_or_else(|err| panic!("circuit does not synthesize: {err:?}"));
+    prover.verify().err().unwrap_or_default()
+}
+
+/// Names quoted in `failure`: the region it is in, and the gate and
+/// constraint that fail
+#[must_use]
+pub fn failure_names(failure: &VerifyFailure) -> Vec<String> {
+    // Metadata is written as `Kind index ('name')`; cell values on later
+    // lines name columns, not regions or gates
+    let summary = failure.to_string();
+    let summary = summary.lines().next().unwrap_or_default();
+    summary
+        .split(" ('")
+        .skip(1)
+        .filter_map(|rest| rest.split_once("')").map(|(name, _)| name.to_string()))
+        .collect()
+}
+
+/// Assert that `circuit` with `instances` at size `k` is not satisfied,
+/// and that a failure names `expected` as its gate, constraint or region
+///
+/// # Panics
+///
+/// Panics if the circuit does not synthesize, if it is satisfied, or if no
+/// failure names `expected`, listing the failures found.
+pub fn assert_constraint_fails<F, C>(k: u32, circuit: &C, instances: Vec<Vec<F>>, expected: &str)
+where
+    F: Ord + FromUniformBytes<64>,
+    C: Circuit<F>,
+{
+    let found = failures(k, circuit, instances);
+    assert!(
+        !found.is_empty(),
+        "circuit is satisfied, expected `{expected}` to fail"
+    );
+    let pinpointed = found
+        .iter()
+        .any(|failure| failure_names(failure).iter().any(|name| name == expected));
+    assert!(
+        pinpointed,
+        "no failure in `{expected}`, found:\n{}",
+        found
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        plonk::{Advice, Column, ConstraintSystem, Error, Instance, Selector},
+        poly::Rotation,
+    };
+    use pasta_curves::Fp;
+    use proptest::prelude::*;
+
+    use super::*;
+
+    /// Constrains `y = x * x` and exposes `y`
+    struct Square {
+        x: Value<Fp>,
+        y: Value<Fp>,
+    }
+
+    impl Circuit<Fp> for Square {
+        type Config = (Column<Advice>, Column<Instance>, Selector);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                x: Value::unknown(),
+                y: Value::unknown(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = meta.advice_column();
+            let instance = meta.instance_column();
+            let q = meta.selector();
+            meta.enable_equality(advice);
+            meta.enable_equality(instance);
+            meta.create_gate("square", |meta| {
+                let q = meta.query_selector(q);
+                let x = meta.query_advice(advice, Rotation::cur());
+                let y = meta.query_advice(advice, Rotation::next());
+                vec![q * (y - x.clone() * x)]
+            });
+            (advice, instance, q)
+        }
+
+        fn synthesize(
+            &self,
+            (advice, instance, q): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let y = layouter.assign_region(
+                || "squaring",
+                |mut region| {
+                    q.enable(&mut region, 0)?;
+                    region.assign_advice(|| "x", advice, 0, || self.x)?;
+                    region.assign_advice(|| "y", advice, 1, || self.y)
+                },
+            )?;
+            layouter.constrain_instance(y.cell(), instance, 0)
+        }
+    }
+
+    /// `x` and the claimed square `y`
+    fn circuit(x: u64, y: u64) -> Square {
+        Square {
+            x: Value::known(Fp::from(x)),
+            y: Value::known(Fp::from(y)),
+        }
+    }
+
+    fn square() -> CircuitProperty<Fp, Square, (u64, u64)> {
+        CircuitProperty::new(4, |&(x, y): &(u64, u64)| {
+            (circuit(x, y), vec![vec![Fp::from(y)]])
+        })
+        .with_cases(32)
+    }
+
+    #[test]
+    fn test_valid_and_invalid_witnesses() {
+        let squares = any::<u32>().prop_map(|x| (u64::from(x), u64::from(x) * u64::from(x)));
+        let wrong = (any::<u32>(), 1..u64::MAX)
+            .prop_map(|(x, delta)| (u64::from(x), (u64::from(x) * u64::from(x)) ^ delta));
+        square().assert_valid(&squares);
+        square().assert_invalid(&wrong);
+    }
+
+    #[test]
+    fn test_failures_shrink_to_minimal_witness() {
+        let squares = (0u64..1000).prop_map(|x| (x, x * x));
+        match square().check_invalid(&squares) {
+            Err(TestError::Fail(_, witness)) => assert_eq!(witness, (0, 0)),
+            other => panic!("expected a failure, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_failing_gate_pinpointed() {
+        assert_constraint_fails(4, &circuit(3, 10), vec![vec![Fp::from(10)]], "square");
+
+        // A wrong instance breaks the copy out of the region, not the gate
+        let found = failures(4, &circuit(3, 9), vec![vec![Fp::from(10)]]);
+        let names: Vec<String> = found.iter().flat_map(failure_names).collect();
+        assert!(names.iter().any(|name| name == "squaring"), "{names:?}");
+        assert!(!names.iter().any(|name| name == "square"), "{names:?}");
+        assert_constraint_fails(4, &circuit(3, 9), vec![vec![Fp::from(10)]], "squaring");
+    }
+
+    #[test]
+    #[should_panic(expected = "circuit is satisfied")]
+    fn test_satisfied_circuit_does_not_fail() {
+        assert_constraint_fails(4, &circuit(3, 9), vec![vec![Fp::from(9)]], "square");
+    }
+
+    #[test]
+    #[should_panic(expected = "no failure in `cube`")]
+    fn test_other_gate_does_not_match() {
+        assert_constraint_fails(4, &circuit(3, 10), vec![vec![Fp::from(10)]], "cube");
+    }
+}
@@ -0,0 +1,315 @@
+//! Wall-clock limits for proving and verification
+//!
+//! halo2 offers no way to interrupt `create_proof` or `verify_proof`, but
+//! both move every commitment and evaluation through the transcript at the
+//! end of each round. [`Timed`] wraps the transcript's writer or reader and
+//! fails the next transfer once its [`Deadline`] has passed, so a stuck job
+//! stops at the following phase boundary and returns [`Error::Timeout`]
+//! instead of holding a worker slot until it finishes.
+//!
+//! A phase already running is not interrupted: the limit is a lower bound
+//! on when the call returns, overshooting by at most one prover round.
+
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    time::{Duration, Instant},
+};
+
+use ff::FromUniformBytes;
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    plonk::{verify_proof, Circuit, Error as PlonkError, ProvingKey, SingleVerifier, VerifyingKey},
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Challenge255},
+};
+
+use crate::{
+    error::{Error, Result},
+    proof::write_proof,
+    rng::Randomness,
+};
+
+/// Point in time after which an operation gives up
+#[derive(Clone, Copy, Debug)]
+pub struct Deadline {
+    started: Instant,
+    limit: Duration,
+}
+
+impl Deadline {
+    /// Deadline `limit` from now
+    #[must_use]
+    pub fn after(limit: Duration) -> Self {
+        Self {
+            started: Instant::now(),
+            limit,
+        }
+    }
+
+    /// Time allowed in total
+    #[must_use]
+    pub fn limit(&self) -> Duration {
+        self.limit
+    }
+
+    /// Whether the limit has passed
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        self.started.elapsed() >= self.limit
+    }
+
+    /// Fail once the limit has passed
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Timeout`] if the deadline is expired.
+    pub fn check(&self) -> Result<()> {
+        if self.is_expired() {
+            Err(Error::Timeout { limit: self.limit })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Reader or writer whose transfers fail after a deadline
+///
+/// Errors carry a marker that [`write_proof`] and [`verify_within`] turn
+/// into [`Error::Timeout`].
+#[derive(Debug)]
+pub struct Timed<T> {
+    inner: T,
+    deadline: Deadline,
+}
+
+impl<T> Timed<T> {
+    /// Wrap `inner`
+    #[must_use]
+    pub fn new(inner: T, deadline: Deadline) -> Self {
+        Self { inner, deadline }
+    }
+
+    /// Unwrap the inner reader or writer
+    #[must_use]
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn poll(&self) -> io::Result<()> {
+        if self.deadline.is_expired() {
+            Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                Expired(self.deadline.limit),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<W: Write> Write for Timed<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.poll()?;
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<R: Read> Read for Timed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.poll()?;
+        self.inner.read(buf)
+    }
+}
+
+/// Marker inside the IO error raised by [`Timed`]
+#[derive(Debug)]
+struct Expired(Duration);
+
+impl fmt::Display for Expired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "deadline of {:?} passed", self.0)
+    }
+}
+
+impl std::error::Error for Expired {}
+
+/// Map a transcript IO error, recognising expired deadlines
+pub(crate) fn transcript_error(err: io::Error) -> Error {
+    match err
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<Expired>())
+    {
+        Some(Expired(limit)) => Error::Timeout { limit: *limit },
+        None => Error::Io(err),
+    }
+}
+
+fn plonk_error(err: PlonkError) -> Error {
+    match err {
+        PlonkError::Transcript(err) => transcript_error(err),
+        err => Error::Plonk(err),
+    }
+}
+
+/// Prove `circuits`, giving up after `limit`
+///
+/// # Errors
+///
+/// Returns [`Error::Timeout`] if the limit passes before the proof is
+/// complete and [`Error::Plonk`] if proving fails.
+pub fn prove_within<C, ConcreteCircuit>(
+    params: &Params<C>,
+    pk: &ProvingKey<C>,
+    circuits: &[ConcreteCircuit],
+    instances: &[&[&[C::Scalar]]],
+    randomness: impl Randomness,
+    limit: Duration,
+) -> Result<Vec<u8>>
+where
+    C: CurveAffine,
+    C::Scalar: FromUniformBytes<64>,
+    ConcreteCircuit: Circuit<C::Scalar>,
+{
+    let deadline = Deadline::after(limit);
+    deadline.check()?;
+    let writer = Timed::new(Vec::new(), deadline);
+    write_proof(params, pk, circuits, instances, randomness, writer).map(Timed::into_inner)
+}
+
+/// Verify `proof`, giving up after `limit`
+///
+/// # Errors
+///
+/// Returns [`Error::Timeout`] if the limit passes while the proof is being
+/// read and [`Error::Plonk`] if it does not verify.
+pub fn verify_within<C>(
+    params: &Params<C>,
+    vk: &VerifyingKey<C>,
+    instances: &[&[&[C::Scalar]]],
+    proof: &[u8],
+    limit: Duration,
+) -> Result<()>
+where
+    C: CurveAffine,
+    C::Scalar: FromUniformBytes<64>,
+{
+    let deadline = Deadline::after(limit);
+    deadline.check()?;
+    let mut transcript = Blake2bRead::<_, C, Challenge255<_>>::init(Timed::new(proof, deadline));
+    verify_proof(
+        params,
+        vk,
+        SingleVerifier::new(params),
+        instances,
+        &mut transcript,
+    )
+    .map_err(plonk_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        plonk::{keygen_pk, keygen_vk, Advice, Column, ConstraintSystem},
+    };
+    use pasta_curves::{vesta, Fp};
+
+    use super::*;
+    use crate::rng::OsRandomness;
+
+    #[derive(Default)]
+    struct OneCell {
+        value: Value<Fp>,
+    }
+
+    impl Circuit<Fp> for OneCell {
+        type Config = Column<Advice>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Column<Advice> {
+            meta.advice_column()
+        }
+
+        fn synthesize(
+            &self,
+            column: Column<Advice>,
+            mut layouter: impl Layouter<Fp>,
+        ) -> std::result::Result<(), PlonkError> {
+            layouter.assign_region(
+                || "cell",
+                |mut region| region.assign_advice(|| "value", column, 0, || self.value),
+            )?;
+            Ok(())
+        }
+    }
+
+    fn setup() -> (Params<vesta::Affine>, ProvingKey<vesta::Affine>) {
+        let params = Params::new(4);
+        let vk = keygen_vk(&params, &OneCell::default()).unwrap();
+        let pk = keygen_pk(&params, vk, &OneCell::default()).unwrap();
+        (params, pk)
+    }
+
+    fn circuit() -> [OneCell; 1] {
+        [OneCell {
+            value: Value::known(Fp::from(3)),
+        }]
+    }
+
+    #[test]
+    fn test_within_limit() {
+        let (params, pk) = setup();
+        let limit = Duration::MAX;
+        let proof = prove_within(&params, &pk, &circuit(), &[&[]], OsRandomness, limit).unwrap();
+        verify_within(&params, pk.get_vk(), &[&[]], &proof, limit).unwrap();
+    }
+
+    #[test]
+    fn test_expired_limit() {
+        let (params, pk) = setup();
+        let proof = prove_within(
+            &params,
+            &pk,
+            &circuit(),
+            &[&[]],
+            OsRandomness,
+            Duration::MAX,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            prove_within(&params, &pk, &circuit(), &[&[]], OsRandomness, Duration::ZERO),
+            Err(Error::Timeout { limit }) if limit.is_zero()
+        ));
+        assert!(matches!(
+            verify_within(&params, pk.get_vk(), &[&[]], &proof, Duration::ZERO),
+            Err(Error::Timeout { .. })
+        ));
+    }
+
+    #[test]
+    fn test_timeout_mid_transcript() {
+        // Expires between the first and second transcript write
+        let deadline = Deadline::after(Duration::from_millis(20));
+        let mut writer = Timed::new(Vec::new(), deadline);
+        writer.write_all(b"round one").unwrap();
+        std::thread::sleep(Duration::from_millis(30));
+
+        let err = writer.write_all(b"round two").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(matches!(transcript_error(err), Error::Timeout { .. }));
+        assert!(matches!(
+            transcript_error(io::ErrorKind::BrokenPipe.into()),
+            Error::Io(_)
+        ));
+    }
+}
@@ -0,0 +1,263 @@
+//! Fiat-Shamir transcripts shared between provers and verifiers
+//!
+//! A [`Transcript`] absorbs everything a challenge must depend on and
+//! squeezes challenges from it. The prover and the verifier run the same
+//! sequence of calls, so they derive the same challenges without any
+//! interaction, and a party cannot pick a challenge after seeing the values
+//! it is applied to.
+//!
+//! [`Blake2bTranscript`] is the cheap choice outside circuits.
+//! [`PoseidonTranscript`] runs the sponge of [`PoseidonSponge`] natively
+//! over the base field, so a circuit can replay it.
+//!
+//! [`PoseidonSponge`]: crate::gadgets::poseidon::PoseidonSponge
+
+use blake2b_simd::{Params as Blake2bParams, State as Blake2bState};
+use ff::{Field, FromUniformBytes, PrimeField};
+use group::GroupEncoding;
+use halo2_proofs::arithmetic::{Coordinates, CurveAffine};
+
+use crate::gadgets::poseidon::{PoseidonConstants, PoseidonRounds, RATE, WIDTH};
+
+/// Personalization of [`Blake2bTranscript`]
+const BLAKE2B_PERSONAL: &[u8; 16] = b"zkps-transcript\0";
+
+/// Tags that keep absorbed items and squeezes from running together
+const TAG_BYTES: u8 = 0;
+const TAG_POINT: u8 = 1;
+const TAG_SCALAR: u8 = 2;
+const TAG_SQUEEZE: u8 = 3;
+
+/// Fiat-Shamir transcript over the curve `C`
+pub trait Transcript<C: CurveAffine> {
+    /// Absorb `bytes`, length-prefixed
+    fn absorb_bytes(&mut self, bytes: &[u8]);
+
+    /// Absorb `point`; the identity is absorbed like any other point
+    fn absorb_point(&mut self, point: &C);
+
+    /// Absorb `scalar`
+    fn absorb_scalar(&mut self, scalar: &C::Scalar);
+
+    /// Challenge bound to everything absorbed so far
+    ///
+    /// Each squeeze also advances the transcript, so consecutive squeezes
+    /// return distinct challenges.
+    fn squeeze_challenge(&mut self) -> C::Scalar;
+}
+
+/// Transcript hashing with Blake2b
+///
+/// Squeezes return the full 512-bit digest reduced into the scalar field.
+#[derive(Clone, Debug)]
+pub struct Blake2bTranscript<C: CurveAffine> {
+    state: Blake2bState,
+    _marker: std::marker::PhantomData<C>,
+}
+
+impl<C> Blake2bTranscript<C>
+where
+    C: CurveAffine,
+    C::Scalar: FromUniformBytes<64>,
+{
+    /// Empty transcript for the protocol named `label`
+    #[must_use]
+    pub fn new(label: &[u8]) -> Self {
+        let state = Blake2bParams::new()
+            .hash_length(64)
+            .personal(BLAKE2B_PERSONAL)
+            .to_state();
+        let mut transcript = Self {
+            state,
+            _marker: std::marker::PhantomData,
+        };
+        transcript.absorb_bytes(label);
+        transcript
+    }
+}
+
+impl<C> Transcript<C> for Blake2bTranscript<C>
+where
+    C: CurveAffine,
+    C::Scalar: FromUniformBytes<64>,
+{
+    fn absorb_bytes(&mut self, bytes: &[u8]) {
+        self.state.update(&[TAG_BYTES]);
+        self.state.update(&(bytes.len() as u64).to_le_bytes());
+        self.state.update(bytes);
+    }
+
+    fn absorb_point(&mut self, point: &C) {
+        self.state.update(&[TAG_POINT]);
+        self.state.update(point.to_bytes().as_ref());
+    }
+
+    fn absorb_scalar(&mut self, scalar: &C::Scalar) {
+        self.state.update(&[TAG_SCALAR]);
+        self.state.update(scalar.to_repr().as_ref());
+    }
+
+    fn squeeze_challenge(&mut self) -> C::Scalar {
+        self.state.update(&[TAG_SQUEEZE]);
+        let mut wide = [0u8; 64];
+        wide.copy_from_slice(self.state.clone().finalize().as_bytes());
+        self.state.update(&wide);
+        C::Scalar::from_uniform_bytes(&wide)
+    }
+}
+
+/// Transcript running a Poseidon duplex sponge over the base field
+///
+/// Absorbed values are buffered and added to the rate [`RATE`] elements at
+/// a time when the next challenge is squeezed, permuting after each chunk,
+/// exactly as [`PoseidonSponge`] does in-circuit. Points are absorbed as
+/// their affine coordinates, with `(0, 0)` standing for the identity, which
+/// is not on any curve `y^2 = x^3 + b` with `b != 0`. Scalars are absorbed
+/// as two 128-bit limbs, since the scalar field may not fit in the base
+/// field, and byte strings as their length followed by 128-bit limbs.
+///
+/// Challenges are the low 128 bits of the squeezed base field element,
+/// which keeps them in range of the scalar field and cheap to use in a
+/// circuit.
+///
+/// [`PoseidonSponge`]: crate::gadgets::poseidon::PoseidonSponge
+#[derive(Clone, Debug)]
+pub struct PoseidonTranscript<C: CurveAffine> {
+    constants: PoseidonConstants<C::Base>,
+    state: [C::Base; WIDTH],
+    pending: Vec<C::Base>,
+}
+
+impl<C: CurveAffine> PoseidonTranscript<C> {
+    /// Empty transcript for the protocol named `label`
+    #[must_use]
+    pub fn new(label: &[u8]) -> Self {
+        Self::with_constants(PoseidonConstants::new(PoseidonRounds::default()), label)
+    }
+
+    /// Empty transcript permuting with `constants`
+    ///
+    /// Lets callers that make many transcripts expand the constants once.
+    #[must_use]
+    pub fn with_constants(constants: PoseidonConstants<C::Base>, label: &[u8]) -> Self {
+        let mut transcript = Self {
+            constants,
+            state: [C::Base::ZERO; WIDTH],
+            pending: Vec::new(),
+        };
+        Transcript::<C>::absorb_bytes(&mut transcript, label);
+        transcript
+    }
+
+    fn permute(&mut self, input: &[C::Base]) {
+        for (state, value) in self.state.iter_mut().zip(input) {
+            *state += value;
+        }
+        self.state = self.constants.permute(self.state);
+    }
+}
+
+impl<C: CurveAffine> Transcript<C> for PoseidonTranscript<C> {
+    fn absorb_bytes(&mut self, bytes: &[u8]) {
+        self.pending.extend(byte_elements::<C::Base>(bytes));
+    }
+
+    fn absorb_point(&mut self, point: &C) {
+        let coordinates: Option<Coordinates<C>> = point.coordinates().into();
+        let (x, y) = coordinates.map_or((C::Base::ZERO, C::Base::ZERO), |c| (*c.x(), *c.y()));
+        self.pending.push(x);
+        self.pending.push(y);
+    }
+
+    fn absorb_scalar(&mut self, scalar: &C::Scalar) {
+        let repr = scalar.to_repr();
+        self.pending.extend(limbs::<C::Base>(repr.as_ref()));
+    }
+
+    fn squeeze_challenge(&mut self) -> C::Scalar {
+        let pending = std::mem::take(&mut self.pending);
+        if pending.is_empty() {
+            self.permute(&[]);
+        }
+        for chunk in pending.chunks(RATE) {
+            self.permute(chunk);
+        }
+        let repr = self.state[0].to_repr();
+        let mut low = [0u8; 16];
+        low.copy_from_slice(&repr.as_ref()[..16]);
+        C::Scalar::from_u128(u128::from_le_bytes(low))
+    }
+}
+
+/// Elements [`PoseidonTranscript`] absorbs for `bytes`: their length, then
+/// their limbs
+pub(crate) fn byte_elements<F: PrimeField>(bytes: &[u8]) -> Vec<F> {
+    std::iter::once(F::from(bytes.len() as u64))
+        .chain(limbs(bytes))
+        .collect()
+}
+
+/// `bytes` as little-endian 128-bit limbs, the last one zero-padded
+fn limbs<F: PrimeField>(bytes: &[u8]) -> impl Iterator<Item = F> + '_ {
+    bytes.chunks(16).map(|chunk| {
+        let mut limb = [0u8; 16];
+        limb[..chunk.len()].copy_from_slice(chunk);
+        F::from_u128(u128::from_le_bytes(limb))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use group::prime::PrimeCurveAffine;
+    use pasta_curves::pallas;
+
+    use super::*;
+
+    fn transcripts() -> (
+        Blake2bTranscript<pallas::Affine>,
+        PoseidonTranscript<pallas::Affine>,
+    ) {
+        (
+            Blake2bTranscript::new(b"test"),
+            PoseidonTranscript::new(b"test"),
+        )
+    }
+
+    fn run(transcript: &mut impl Transcript<pallas::Affine>, scalar: u64) -> pallas::Scalar {
+        transcript.absorb_point(&pallas::Affine::generator());
+        transcript.absorb_scalar(&pallas::Scalar::from(scalar));
+        transcript.absorb_bytes(b"context");
+        transcript.squeeze_challenge()
+    }
+
+    #[test]
+    fn test_prover_and_verifier_agree() {
+        let (mut prover, mut verifier) = (transcripts(), transcripts());
+        assert_eq!(run(&mut prover.0, 3), run(&mut verifier.0, 3));
+        assert_eq!(run(&mut prover.1, 3), run(&mut verifier.1, 3));
+    }
+
+    #[test]
+    fn test_challenges_bind_inputs() {
+        let (mut first, mut second) = (transcripts(), transcripts());
+        assert_ne!(run(&mut first.0, 3), run(&mut second.0, 4));
+        assert_ne!(run(&mut first.1, 3), run(&mut second.1, 4));
+
+        let mut other = Blake2bTranscript::<pallas::Affine>::new(b"other");
+        assert_ne!(run(&mut transcripts().0, 3), run(&mut other, 3));
+        let mut other = PoseidonTranscript::<pallas::Affine>::new(b"other");
+        assert_ne!(run(&mut transcripts().1, 3), run(&mut other, 3));
+
+        // Absorbing the identity still changes the challenge
+        let (mut identity, mut empty) = transcripts();
+        identity.1.absorb_point(&pallas::Affine::identity());
+        assert_ne!(identity.1.squeeze_challenge(), empty.1.squeeze_challenge());
+    }
+
+    #[test]
+    fn test_consecutive_squeezes_differ() {
+        let (mut blake, mut poseidon) = transcripts();
+        assert_ne!(blake.squeeze_challenge(), blake.squeeze_challenge());
+        assert_ne!(poseidon.squeeze_challenge(), poseidon.squeeze_challenge());
+    }
+}
@@ -0,0 +1,540 @@
+//! Chunked, resumable transfer of params and proofs
+//!
+//! A payload is cut into fixed-size frames. The sender first ships a
+//! [`TransferManifest`] holding the payload length, the frame size and the
+//! SHA-256 digest of every frame, then the [`Frame`]s themselves in any
+//! order. The receiver feeds frames to a [`FrameAssembler`], which checks
+//! each one against the manifest and writes it straight to its place in the
+//! sink. After a dropped connection the assembler is rebuilt from the
+//! partially written sink with [`FrameAssembler::resume`] and only the
+//! frames in [`FrameAssembler::missing`] are requested again.
+//!
+//! All integers are little-endian:
+//!
+//! ```text
+//! manifest: "ZKT1" | frame_size: u32 | total_len: u64 | frame_count: u32 | digest * frame_count
+//! frame:    "ZKF1" | index: u32 | len: u32 | digest | data
+//! ```
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use sha2::{Digest, Sha256};
+
+use crate::error::{Error, Result};
+
+/// Tag at the start of an encoded manifest
+const MANIFEST_MAGIC: &[u8; 4] = b"ZKT1";
+
+/// Tag at the start of an encoded frame
+const FRAME_MAGIC: &[u8; 4] = b"ZKF1";
+
+/// Manifest bytes before the digest list
+const MANIFEST_HEADER_LEN: usize = 4 + 4 + 8 + 4;
+
+/// Frame bytes before the data
+const FRAME_HEADER_LEN: usize = 4 + 4 + 4 + 32;
+
+/// Default frame size, 1 MiB
+pub const DEFAULT_FRAME_SIZE: u32 = 1 << 20;
+
+/// Length and per-frame digests of a payload
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransferManifest {
+    frame_size: u32,
+    total_len: u64,
+    digests: Vec<[u8; 32]>,
+}
+
+impl TransferManifest {
+    /// Read `source` to the end and digest it frame by frame
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Other`] if `frame_size` is 0 and [`Error::Io`] if
+    /// reading fails.
+    pub fn build(mut source: impl Read, frame_size: u32) -> Result<Self> {
+        if frame_size == 0 {
+            return Err(Error::Other("frame size must be positive".into()));
+        }
+        let mut buffer = vec![0u8; frame_size as usize];
+        let mut total_len = 0u64;
+        let mut digests = Vec::new();
+        loop {
+            let len = read_up_to(&mut source, &mut buffer)?;
+            if len == 0 {
+                break;
+            }
+            if digests.len() == u32::MAX as usize {
+                return Err(Error::Other("payload needs more than 2^32 frames".into()));
+            }
+            total_len += len as u64;
+            digests.push(Sha256::digest(&buffer[..len]).into());
+            if len < buffer.len() {
+                break;
+            }
+        }
+        Ok(Self {
+            frame_size,
+            total_len,
+            digests,
+        })
+    }
+
+    /// Manifest of an in-memory payload
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Other`] if `frame_size` is 0.
+    pub fn of(payload: &[u8], frame_size: u32) -> Result<Self> {
+        Self::build(payload, frame_size)
+    }
+
+    /// Size of every frame but the last
+    #[must_use]
+    pub fn frame_size(&self) -> u32 {
+        self.frame_size
+    }
+
+    /// Length of the whole payload
+    #[must_use]
+    pub fn total_len(&self) -> u64 {
+        self.total_len
+    }
+
+    /// Number of frames
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn frame_count(&self) -> u32 {
+        // `build` and `from_bytes` both keep the count within u32
+        self.digests.len() as u32
+    }
+
+    /// Byte offset of frame `index` in the payload
+    #[must_use]
+    pub fn frame_offset(&self, index: u32) -> u64 {
+        u64::from(index) * u64::from(self.frame_size)
+    }
+
+    /// Length of frame `index`, or `None` past the last frame
+    #[must_use]
+    pub fn frame_len(&self, index: u32) -> Option<usize> {
+        (index < self.frame_count()).then(|| self.len_of(index))
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn len_of(&self, index: u32) -> usize {
+        let remaining = self.total_len - self.frame_offset(index);
+        // Bounded by `frame_size`, a u32
+        remaining.min(u64::from(self.frame_size)) as usize
+    }
+
+    /// Expected SHA-256 digest of frame `index`
+    #[must_use]
+    pub fn frame_digest(&self, index: u32) -> Option<&[u8; 32]> {
+        self.digests.get(index as usize)
+    }
+
+    /// SHA-256 of the encoded manifest, identifying the transfer
+    #[must_use]
+    pub fn id(&self) -> [u8; 32] {
+        Sha256::digest(self.to_bytes()).into()
+    }
+
+    /// Encode the manifest
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(MANIFEST_HEADER_LEN + 32 * self.digests.len());
+        out.extend_from_slice(MANIFEST_MAGIC);
+        out.extend_from_slice(&self.frame_size.to_le_bytes());
+        out.extend_from_slice(&self.total_len.to_le_bytes());
+        out.extend_from_slice(&self.frame_count().to_le_bytes());
+        for digest in &self.digests {
+            out.extend_from_slice(digest);
+        }
+        out
+    }
+
+    /// Decode a manifest
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if `bytes` is not a consistent manifest.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let malformed = |what: &str| Error::Encoding(format!("transfer manifest: {what}"));
+
+        if bytes.len() < MANIFEST_HEADER_LEN {
+            return Err(malformed("truncated header"));
+        }
+        let (header, rest) = bytes.split_at(MANIFEST_HEADER_LEN);
+        if &header[..4] != MANIFEST_MAGIC {
+            return Err(malformed("bad magic"));
+        }
+        let frame_size = le_u32(&header[4..8]);
+        let total_len = le_u64(&header[8..16]);
+        let frame_count = le_u32(&header[16..20]);
+
+        if frame_size == 0 {
+            return Err(malformed("zero frame size"));
+        }
+        if u64::from(frame_count) != total_len.div_ceil(u64::from(frame_size)) {
+            return Err(malformed("frame count does not match length"));
+        }
+        if rest.len() != 32 * frame_count as usize {
+            return Err(malformed("wrong number of digests"));
+        }
+        let digests = rest
+            .chunks_exact(32)
+            .map(|chunk| {
+                let mut digest = [0u8; 32];
+                digest.copy_from_slice(chunk);
+                digest
+            })
+            .collect();
+        Ok(Self {
+            frame_size,
+            total_len,
+            digests,
+        })
+    }
+}
+
+/// One frame of a payload
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    /// Position of the frame in the payload
+    pub index: u32,
+    /// Frame contents
+    pub data: Vec<u8>,
+}
+
+impl Frame {
+    /// Cut frame `index` out of `source` for (re)sending
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Other`] if `index` is past the last frame and
+    /// [`Error::Io`] if reading fails.
+    pub fn read_from<R: Read + Seek>(
+        mut source: R,
+        manifest: &TransferManifest,
+        index: u32,
+    ) -> Result<Self> {
+        let len = manifest.frame_len(index).ok_or_else(|| {
+            Error::Other(format!(
+                "frame {index} out of range for {} frames",
+                manifest.frame_count()
+            ))
+        })?;
+        let mut data = vec![0u8; len];
+        source.seek(SeekFrom::Start(manifest.frame_offset(index)))?;
+        source.read_exact(&mut data)?;
+        Ok(Self { index, data })
+    }
+
+    /// Encode the frame with its header and checksum
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if writing fails.
+    pub fn write(&self, mut out: impl Write) -> Result<()> {
+        let len = u32::try_from(self.data.len())
+            .map_err(|_| Error::Other(format!("frame {} is too large", self.index)))?;
+        out.write_all(FRAME_MAGIC)?;
+        out.write_all(&self.index.to_le_bytes())?;
+        out.write_all(&len.to_le_bytes())?;
+        out.write_all(&Sha256::digest(&self.data))?;
+        out.write_all(&self.data)?;
+        Ok(())
+    }
+
+    /// Decode one frame from a stream and check its checksum
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the stream ends early and [`Error::Encoding`]
+    /// if the frame is malformed, longer than `max_len` or corrupted.
+    pub fn read(mut input: impl Read, max_len: u32) -> Result<Self> {
+        let mut header = [0u8; FRAME_HEADER_LEN];
+        input.read_exact(&mut header)?;
+        if &header[..4] != FRAME_MAGIC {
+            return Err(Error::Encoding("transfer frame: bad magic".into()));
+        }
+        let index = le_u32(&header[4..8]);
+        let len = le_u32(&header[8..12]);
+        if len > max_len {
+            return Err(Error::Encoding(format!(
+                "transfer frame {index}: {len} bytes exceeds {max_len}"
+            )));
+        }
+        let mut data = vec![0u8; len as usize];
+        input.read_exact(&mut data)?;
+        if Sha256::digest(&data)[..] != header[12..] {
+            return Err(Error::Encoding(format!(
+                "transfer frame {index}: checksum mismatch"
+            )));
+        }
+        Ok(Self { index, data })
+    }
+}
+
+/// Receiving end of a transfer
+///
+/// Writes each verified frame at its offset in the sink and tracks which
+/// frames are still missing.
+#[derive(Debug)]
+pub struct FrameAssembler<W> {
+    manifest: TransferManifest,
+    sink: W,
+    received: Vec<bool>,
+}
+
+impl<W: Write + Seek> FrameAssembler<W> {
+    /// Start a transfer into an empty sink
+    #[must_use]
+    pub fn new(manifest: TransferManifest, sink: W) -> Self {
+        let received = vec![false; manifest.digests.len()];
+        Self {
+            manifest,
+            sink,
+            received,
+        }
+    }
+
+    /// Frames not received yet, in order
+    pub fn missing(&self) -> impl Iterator<Item = u32> + '_ {
+        (0..self.manifest.frame_count()).filter(|&index| !self.received[index as usize])
+    }
+
+    /// Whether every frame has arrived
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.received.iter().all(|&received| received)
+    }
+
+    /// Manifest of the transfer
+    #[must_use]
+    pub fn manifest(&self) -> &TransferManifest {
+        &self.manifest
+    }
+
+    /// Check `frame` against the manifest and write it to the sink
+    ///
+    /// Receiving a frame twice is harmless.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if the frame does not belong to this
+    /// transfer and [`Error::Io`] if writing fails.
+    pub fn accept(&mut self, frame: &Frame) -> Result<()> {
+        let index = frame.index;
+        let (Some(len), Some(digest)) = (
+            self.manifest.frame_len(index),
+            self.manifest.frame_digest(index),
+        ) else {
+            return Err(Error::Encoding(format!(
+                "transfer frame {index} out of range for {} frames",
+                self.manifest.frame_count()
+            )));
+        };
+        if frame.data.len() != len || Sha256::digest(&frame.data)[..] != digest[..] {
+            return Err(Error::Encoding(format!(
+                "transfer frame {index} does not match the manifest"
+            )));
+        }
+        self.sink
+            .seek(SeekFrom::Start(self.manifest.frame_offset(index)))?;
+        self.sink.write_all(&frame.data)?;
+        self.received[index as usize] = true;
+        Ok(())
+    }
+
+    /// Flush and return the sink once every frame has arrived
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Other`] naming the first missing frame, or
+    /// [`Error::Io`] if flushing fails.
+    pub fn finish(mut self) -> Result<W> {
+        if let Some(index) = self.missing().next() {
+            return Err(Error::Other(format!(
+                "transfer incomplete: frame {index} of {} missing",
+                self.manifest.frame_count()
+            )));
+        }
+        self.sink.flush()?;
+        Ok(self.sink)
+    }
+}
+
+impl<W: Read + Write + Seek> FrameAssembler<W> {
+    /// Pick up an interrupted transfer from a partially written sink
+    ///
+    /// Every frame already in the sink whose digest matches the manifest
+    /// counts as received.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if reading the sink fails.
+    pub fn resume(manifest: TransferManifest, mut sink: W) -> Result<Self> {
+        let mut received = Vec::with_capacity(manifest.digests.len());
+        let mut buffer = vec![0u8; manifest.frame_size as usize];
+        for (index, digest) in (0..).zip(&manifest.digests) {
+            let len = manifest.len_of(index);
+            sink.seek(SeekFrom::Start(manifest.frame_offset(index)))?;
+            let read = read_up_to(&mut sink, &mut buffer[..len])?;
+            received.push(read == len && Sha256::digest(&buffer[..len])[..] == digest[..]);
+        }
+        Ok(Self {
+            manifest,
+            sink,
+            received,
+        })
+    }
+}
+
+fn le_u32(bytes: &[u8]) -> u32 {
+    let mut le = [0u8; 4];
+    le.copy_from_slice(bytes);
+    u32::from_le_bytes(le)
+}
+
+fn le_u64(bytes: &[u8]) -> u64 {
+    let mut le = [0u8; 8];
+    le.copy_from_slice(bytes);
+    u64::from_le_bytes(le)
+}
+
+/// Fill `buffer` as far as `source` allows, returning the bytes read
+fn read_up_to(mut source: impl Read, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match source.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn payload(len: usize) -> Vec<u8> {
+        (0..len).map(|i| u8::try_from(i * 31 % 251).unwrap()).collect()
+    }
+
+    /// Encode frames back to back, as a sender would
+    fn wire(
+        source: &[u8],
+        manifest: &TransferManifest,
+        frames: impl Iterator<Item = u32>,
+    ) -> Vec<u8> {
+        let mut out = Vec::new();
+        for index in frames {
+            Frame::read_from(Cursor::new(source), manifest, index)
+                .unwrap()
+                .write(&mut out)
+                .unwrap();
+        }
+        out
+    }
+
+    #[test]
+    fn test_manifest_round_trip() {
+        let manifest = TransferManifest::of(&payload(1000), 64).unwrap();
+        assert_eq!(manifest.frame_count(), 16);
+        assert_eq!(manifest.frame_len(15), Some(1000 - 15 * 64));
+        assert_eq!(manifest.frame_len(16), None);
+        assert_eq!(
+            TransferManifest::from_bytes(&manifest.to_bytes()).unwrap(),
+            manifest
+        );
+
+        let empty = TransferManifest::of(&[], 64).unwrap();
+        assert_eq!(empty.frame_count(), 0);
+        assert_eq!(
+            TransferManifest::from_bytes(&empty.to_bytes()).unwrap(),
+            empty
+        );
+    }
+
+    #[test]
+    fn test_manifest_rejects_inconsistent_counts() {
+        let mut bytes = TransferManifest::of(&payload(1000), 64).unwrap().to_bytes();
+        bytes[16] = 15;
+        assert!(matches!(
+            TransferManifest::from_bytes(&bytes),
+            Err(Error::Encoding(_))
+        ));
+        assert!(TransferManifest::of(&[1], 0).is_err());
+    }
+
+    #[test]
+    fn test_transfer_out_of_order() {
+        let source = payload(1000);
+        let manifest = TransferManifest::of(&source, 64).unwrap();
+        let bytes = wire(&source, &manifest, (0..16).rev());
+
+        let mut assembler = FrameAssembler::new(manifest.clone(), Cursor::new(Vec::new()));
+        let mut input = &bytes[..];
+        while !input.is_empty() {
+            let frame = Frame::read(&mut input, manifest.frame_size()).unwrap();
+            assembler.accept(&frame).unwrap();
+        }
+        assert!(assembler.is_complete());
+        assert_eq!(assembler.finish().unwrap().into_inner(), source);
+    }
+
+    #[test]
+    fn test_corrupt_frame_rejected() {
+        let source = payload(200);
+        let manifest = TransferManifest::of(&source, 64).unwrap();
+        let mut bytes = wire(&source, &manifest, 0..1);
+        *bytes.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            Frame::read(&bytes[..], manifest.frame_size()),
+            Err(Error::Encoding(_))
+        ));
+
+        // A frame that checks out on its own but belongs to another payload
+        let mut assembler = FrameAssembler::new(manifest, Cursor::new(Vec::new()));
+        let foreign = Frame {
+            index: 0,
+            data: vec![0; 64],
+        };
+        assert!(matches!(
+            assembler.accept(&foreign),
+            Err(Error::Encoding(_))
+        ));
+    }
+
+    #[test]
+    fn test_resume_after_interruption() {
+        let source = payload(1000);
+        let manifest = TransferManifest::of(&source, 64).unwrap();
+
+        // The link drops after every even frame has been written
+        let mut sink = Cursor::new(Vec::new());
+        let mut assembler = FrameAssembler::new(manifest.clone(), &mut sink);
+        for index in (0..16).step_by(2) {
+            let frame = Frame::read_from(Cursor::new(&source), &manifest, index).unwrap();
+            assembler.accept(&frame).unwrap();
+        }
+        drop(assembler);
+
+        let mut assembler = FrameAssembler::resume(manifest.clone(), &mut sink).unwrap();
+        let missing: Vec<_> = assembler.missing().collect();
+        assert_eq!(missing, (1..16).step_by(2).collect::<Vec<_>>());
+        for index in missing {
+            let frame = Frame::read_from(Cursor::new(&source), &manifest, index).unwrap();
+            assembler.accept(&frame).unwrap();
+        }
+        assembler.finish().unwrap();
+        assert_eq!(sink.into_inner(), source);
+    }
+}
@@ -0,0 +1,56 @@
+//! Region and cell annotations that cost nothing in release builds
+//!
+//! halo2 names regions and cells with closures such as
+//! `|| format!("merkle level {}", i)`. Only `MockProver` and other debugging
+//! tools read those names, but the formatting still runs wherever a layouter
+//! evaluates them. [`annotation!`](crate::annotation) formats the name when
+//! debug assertions or the `rich-annotations` feature are on, and otherwise
+//! returns the format string itself without allocating.
+
+use std::borrow::Cow;
+
+/// Whether [`annotation!`](crate::annotation) formats its arguments
+pub const RICH_ANNOTATIONS: bool = cfg!(any(debug_assertions, feature = "rich-annotations"));
+
+/// Annotation returned by [`annotation!`](crate::annotation)
+pub type Annotation = Cow<'static, str>;
+
+/// Build an annotation for a region, cell or namespace
+///
+/// Takes `format!` arguments. Use it as the body of the annotation closure:
+///
+/// ```
+/// # use zk_proof_core::annotation;
+/// let level = 3;
+/// let name = || annotation!("merkle level {}", level);
+/// assert!(name() == "merkle level 3" || name() == "merkle level {}");
+/// ```
+#[macro_export]
+macro_rules! annotation {
+    ($fmt:literal $(, $arg:expr)* $(,)?) => {
+        if $crate::utils::annotation::RICH_ANNOTATIONS {
+            $crate::utils::annotation::Annotation::Owned(::std::format!($fmt $(, $arg)*))
+        } else {
+            $crate::utils::annotation::Annotation::Borrowed($fmt)
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotation() {
+        let level = 7;
+        let name: Annotation = crate::annotation!("merkle level {}", level);
+        if RICH_ANNOTATIONS {
+            assert_eq!(name, "merkle level 7");
+        } else {
+            assert!(matches!(name, Cow::Borrowed("merkle level {}")));
+        }
+
+        let plain: Annotation = crate::annotation!("root");
+        assert_eq!(plain, "root");
+    }
+}
@@ -0,0 +1,177 @@
+//! Bit and window decomposition helpers
+//!
+//! These are the native counterparts of the in-circuit decompositions used by
+//! the range-check, scalar-multiplication and foreign-field gadgets. Every
+//! decomposition here is little-endian: index 0 holds the least significant
+//! bit, window or digit.
+
+use ff::PrimeField;
+
+use super::encoding::{to_bytes, Endianness};
+
+/// Decompose a field element into its `F::NUM_BITS` little-endian bits
+#[must_use]
+pub fn to_le_bits<F: PrimeField>(value: &F) -> Vec<bool> {
+    let bytes = to_bytes(value, Endianness::Little);
+    (0..F::NUM_BITS as usize)
+        .map(|i| (bytes[i / 8] >> (i % 8)) & 1 == 1)
+        .collect()
+}
+
+/// Recompose a field element from little-endian bits
+#[must_use]
+pub fn from_le_bits<F: PrimeField>(bits: &[bool]) -> F {
+    bits.iter()
+        .rev()
+        .fold(F::ZERO, |acc, bit| acc.double() + F::from(u64::from(*bit)))
+}
+
+/// Whether a field element fits in `num_bits` bits
+#[must_use]
+pub fn fits_in_bits<F: PrimeField>(value: &F, num_bits: usize) -> bool {
+    to_le_bits(value).iter().skip(num_bits).all(|bit| !bit)
+}
+
+/// Split a field element into little-endian unsigned windows of `window` bits
+///
+/// The result has `ceil(F::NUM_BITS / window)` entries.
+///
+/// # Panics
+///
+/// Panics if `window` is zero or larger than 63.
+#[must_use]
+pub fn to_windows<F: PrimeField>(value: &F, window: usize) -> Vec<u64> {
+    assert!((1..64).contains(&window), "window must be in 1..64");
+
+    to_le_bits(value)
+        .chunks(window)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0u64, |acc, (i, bit)| acc | (u64::from(*bit) << i))
+        })
+        .collect()
+}
+
+/// Recompose a field element from little-endian unsigned windows
+///
+/// # Panics
+///
+/// Panics if `window` is zero or larger than 63.
+#[must_use]
+pub fn from_windows<F: PrimeField>(windows: &[u64], window: usize) -> F {
+    assert!((1..64).contains(&window), "window must be in 1..64");
+
+    let shift = F::from(1u64 << window);
+    windows
+        .iter()
+        .rev()
+        .fold(F::ZERO, |acc, digit| acc * shift + F::from(*digit))
+}
+
+/// Signed-digit decomposition with digits in `[-2^(w-1), 2^(w-1))`
+///
+/// The result has `ceil(F::NUM_BITS / window) + 1` entries; the last one
+/// absorbs the final carry and is always 0 or 1, so the length is fixed for a
+/// given field and window.
+///
+/// # Panics
+///
+/// Panics if `window` is zero or larger than 62.
+#[must_use]
+pub fn to_signed_digits<F: PrimeField>(value: &F, window: usize) -> Vec<i64> {
+    assert!((1..63).contains(&window), "window must be in 1..63");
+
+    let half = 1i64 << (window - 1);
+    let full = 1i64 << window;
+    let mut carry = 0i64;
+    let mut digits: Vec<i64> = to_windows(value, window)
+        .into_iter()
+        .map(|w| {
+            // Windows are below 2^62, so the conversion is lossless.
+            let mut digit = i64::try_from(w).unwrap_or(i64::MAX) + carry;
+            if digit >= half {
+                digit -= full;
+                carry = 1;
+            } else {
+                carry = 0;
+            }
+            digit
+        })
+        .collect();
+    digits.push(carry);
+    digits
+}
+
+/// Recompose a field element from a signed-digit decomposition
+///
+/// # Panics
+///
+/// Panics if `window` is zero or larger than 62.
+#[must_use]
+pub fn from_signed_digits<F: PrimeField>(digits: &[i64], window: usize) -> F {
+    assert!((1..63).contains(&window), "window must be in 1..63");
+
+    let shift = F::from(1u64 << window);
+    digits.iter().rev().fold(F::ZERO, |acc, digit| {
+        let magnitude = F::from(digit.unsigned_abs());
+        acc * shift + if *digit < 0 { -magnitude } else { magnitude }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ff::Field;
+    use pasta_curves::Fp;
+
+    #[test]
+    fn test_le_bits_round_trip() {
+        let value = Fp::from(0b1011u64);
+        let bits = to_le_bits(&value);
+        assert_eq!(bits.len(), Fp::NUM_BITS as usize);
+        assert_eq!(&bits[..4], &[true, true, false, true]);
+        assert_eq!(from_le_bits::<Fp>(&bits), value);
+
+        let value = -Fp::ONE;
+        assert_eq!(from_le_bits::<Fp>(&to_le_bits(&value)), value);
+    }
+
+    #[test]
+    fn test_fits_in_bits() {
+        assert!(fits_in_bits(&Fp::from(255), 8));
+        assert!(!fits_in_bits(&Fp::from(256), 8));
+        assert!(fits_in_bits(&Fp::from(u64::MAX), 64));
+        assert!(!fits_in_bits(&-Fp::ONE, 64));
+    }
+
+    #[test]
+    fn test_windows_round_trip() {
+        let value = Fp::from(0xdead_beef_u64);
+        let windows = to_windows(&value, 8);
+        assert_eq!(windows.len(), 32);
+        assert_eq!(&windows[..4], &[0xef, 0xbe, 0xad, 0xde]);
+        assert_eq!(from_windows::<Fp>(&windows, 8), value);
+
+        for window in [1, 3, 5, 10] {
+            let value = -Fp::from(12345);
+            assert_eq!(from_windows::<Fp>(&to_windows(&value, window), window), value);
+        }
+    }
+
+    #[test]
+    fn test_signed_digits_round_trip() {
+        for window in [2, 4, 5] {
+            for value in [Fp::ZERO, Fp::from(7), Fp::from(u64::MAX), -Fp::ONE] {
+                let digits = to_signed_digits(&value, window);
+                let half = 1i64 << (window - 1);
+                assert!(digits[..digits.len() - 1]
+                    .iter()
+                    .all(|d| (-half..half).contains(d)));
+                assert!((0..=1).contains(digits.last().unwrap()));
+                assert_eq!(from_signed_digits::<Fp>(&digits, window), value);
+            }
+        }
+    }
+}
@@ -0,0 +1,298 @@
+//! Deterministic CBOR, as in RFC 8949 section 4.2.1
+//!
+//! Only the subset proofs need is supported: unsigned integers, byte and
+//! text strings, and maps keyed by unsigned integers. [`Cbor::encode`]
+//! writes every head in its shortest form and map keys in ascending order,
+//! and [`Cbor::decode`] rejects anything else, so every value has exactly
+//! one encoding and hashing or signing it is well defined.
+
+use crate::error::{Error, Result};
+
+const UNSIGNED: u8 = 0;
+const BYTES: u8 = 2;
+const TEXT: u8 = 3;
+const MAP: u8 = 5;
+
+/// Deepest nesting of maps [`Cbor::decode`] accepts
+const MAX_DEPTH: usize = 8;
+
+/// A CBOR value borrowing its strings
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Cbor<'a> {
+    /// Unsigned integer, major type 0
+    Uint(u64),
+    /// Byte string, major type 2
+    Bytes(&'a [u8]),
+    /// UTF-8 text string, major type 3
+    Text(&'a str),
+    /// Map with unsigned integer keys in ascending order, major type 5
+    Map(Vec<(u64, Cbor<'a>)>),
+}
+
+impl<'a> Cbor<'a> {
+    /// Append the deterministic encoding of `self` to `out`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys of a map are not strictly ascending.
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Self::Uint(value) => head(UNSIGNED, *value, out),
+            Self::Bytes(bytes) => {
+                head(BYTES, bytes.len() as u64, out);
+                out.extend_from_slice(bytes);
+            }
+            Self::Text(text) => {
+                head(TEXT, text.len() as u64, out);
+                out.extend_from_slice(text.as_bytes());
+            }
+            Self::Map(entries) => {
+                assert!(
+                    entries.windows(2).all(|pair| pair[0].0 < pair[1].0),
+                    "map keys must be strictly ascending"
+                );
+                head(MAP, entries.len() as u64, out);
+                for (key, value) in entries {
+                    head(UNSIGNED, *key, out);
+                    value.encode(out);
+                }
+            }
+        }
+    }
+
+    /// Deterministic encoding of `self`
+    #[must_use]
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode(&mut out);
+        out
+    }
+
+    /// Decode exactly one value spanning all of `bytes`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if `bytes` is truncated, has trailing
+    /// bytes, or is not the deterministic encoding of a supported value.
+    pub fn decode(bytes: &'a [u8]) -> Result<Self> {
+        let (value, rest) = Self::decode_prefix(bytes, 0)?;
+        if !rest.is_empty() {
+            return Err(Error::Encoding(format!(
+                "{} trailing bytes after CBOR value",
+                rest.len()
+            )));
+        }
+        Ok(value)
+    }
+
+    fn decode_prefix(bytes: &'a [u8], depth: usize) -> Result<(Self, &'a [u8])> {
+        let (major, arg, rest) = read_head(bytes)?;
+        match major {
+            UNSIGNED => Ok((Self::Uint(arg), rest)),
+            BYTES | TEXT => {
+                let len = usize::try_from(arg)
+                    .ok()
+                    .filter(|len| *len <= rest.len())
+                    .ok_or_else(truncated)?;
+                let (value, rest) = rest.split_at(len);
+                if major == BYTES {
+                    return Ok((Self::Bytes(value), rest));
+                }
+                let text = std::str::from_utf8(value)
+                    .map_err(|_| Error::Encoding("CBOR text is not UTF-8".into()))?;
+                Ok((Self::Text(text), rest))
+            }
+            MAP => {
+                if depth == MAX_DEPTH {
+                    return Err(Error::Encoding("CBOR maps nested too deeply".into()));
+                }
+                // Every entry takes at least two bytes
+                let len = usize::try_from(arg)
+                    .ok()
+                    .filter(|len| len.saturating_mul(2) <= rest.len())
+                    .ok_or_else(truncated)?;
+                let mut entries: Vec<(u64, Self)> = Vec::with_capacity(len);
+                let mut rest = rest;
+                for _ in 0..len {
+                    let (UNSIGNED, key, after_key) = read_head(rest)? else {
+                        return Err(Error::Encoding("CBOR map key is not an integer".into()));
+                    };
+                    if entries.last().is_some_and(|(last, _)| *last >= key) {
+                        return Err(Error::Encoding("CBOR map keys are not ascending".into()));
+                    }
+                    let (value, after_value) = Self::decode_prefix(after_key, depth + 1)?;
+                    entries.push((key, value));
+                    rest = after_value;
+                }
+                Ok((Self::Map(entries), rest))
+            }
+            _ => Err(Error::Encoding(format!(
+                "unsupported CBOR major type {major}"
+            ))),
+        }
+    }
+
+    /// Integer value
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] naming `field` if `self` is not an
+    /// integer.
+    pub fn as_uint(&self, field: &str) -> Result<u64> {
+        match self {
+            Self::Uint(value) => Ok(*value),
+            _ => Err(mistyped(field, "an integer")),
+        }
+    }
+
+    /// Byte string value
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] naming `field` if `self` is not a byte
+    /// string.
+    pub fn as_bytes(&self, field: &str) -> Result<&'a [u8]> {
+        match self {
+            Self::Bytes(bytes) => Ok(bytes),
+            _ => Err(mistyped(field, "a byte string")),
+        }
+    }
+
+    /// Text string value
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] naming `field` if `self` is not a text
+    /// string.
+    pub fn as_text(&self, field: &str) -> Result<&'a str> {
+        match self {
+            Self::Text(text) => Ok(text),
+            _ => Err(mistyped(field, "a text string")),
+        }
+    }
+
+    /// Map entries
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] naming `field` if `self` is not a map.
+    pub fn as_map(&self, field: &str) -> Result<&[(u64, Cbor<'a>)]> {
+        match self {
+            Self::Map(entries) => Ok(entries),
+            _ => Err(mistyped(field, "a map")),
+        }
+    }
+}
+
+/// Append a head with major type `major` and argument `arg` in its
+/// shortest form
+#[allow(clippy::cast_possible_truncation)]
+fn head(major: u8, arg: u64, out: &mut Vec<u8>) {
+    let major = major << 5;
+    match arg {
+        0..=23 => out.push(major | arg as u8),
+        24..=0xff => out.extend_from_slice(&[major | 24, arg as u8]),
+        0x100..=0xffff => {
+            out.push(major | 25);
+            out.extend_from_slice(&(arg as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(major | 26);
+            out.extend_from_slice(&(arg as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major | 27);
+            out.extend_from_slice(&arg.to_be_bytes());
+        }
+    }
+}
+
+/// Split a head off `bytes`, rejecting arguments not in shortest form
+fn read_head(bytes: &[u8]) -> Result<(u8, u64, &[u8])> {
+    let (&first, rest) = bytes.split_first().ok_or_else(truncated)?;
+    let (major, info) = (first >> 5, first & 0x1f);
+    let width = match info {
+        0..=23 => return Ok((major, u64::from(info), rest)),
+        24 => 1,
+        25 => 2,
+        26 => 4,
+        27 => 8,
+        _ => return Err(Error::Encoding("indefinite or reserved CBOR length".into())),
+    };
+    if rest.len() < width {
+        return Err(truncated());
+    }
+    let (arg, rest) = rest.split_at(width);
+    let arg = arg
+        .iter()
+        .fold(0u64, |acc, byte| (acc << 8) | u64::from(*byte));
+    // The shortest form of an argument of `width` bytes needs all of them
+    let min = if width == 1 { 24 } else { 1 << (4 * width) };
+    if arg < min {
+        return Err(Error::Encoding("CBOR head is not minimally encoded".into()));
+    }
+    Ok((major, arg, rest))
+}
+
+fn truncated() -> Error {
+    Error::Encoding("truncated CBOR value".into())
+}
+
+fn mistyped(field: &str, expected: &str) -> Error {
+    Error::Encoding(format!("{field} is not {expected}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc_8949_vectors() {
+        let cases: [(Cbor<'_>, &[u8]); 6] = [
+            (Cbor::Uint(23), &[0x17]),
+            (Cbor::Uint(24), &[0x18, 0x18]),
+            (Cbor::Uint(1000), &[0x19, 0x03, 0xe8]),
+            (
+                Cbor::Uint(1_000_000_000_000),
+                &[0x1b, 0, 0, 0, 0xe8, 0xd4, 0xa5, 0x10, 0],
+            ),
+            (Cbor::Text("IETF"), &[0x64, 0x49, 0x45, 0x54, 0x46]),
+            (
+                Cbor::Map(vec![(1, Cbor::Uint(2)), (3, Cbor::Bytes(&[4]))]),
+                &[0xa2, 0x01, 0x02, 0x03, 0x41, 0x04],
+            ),
+        ];
+        for (value, encoding) in cases {
+            assert_eq!(value.to_vec(), encoding);
+            assert_eq!(Cbor::decode(encoding).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn test_non_deterministic_encodings_rejected() {
+        // 23 with a one-byte argument
+        assert!(Cbor::decode(&[0x18, 0x17]).is_err());
+        // 255 with a two-byte argument
+        assert!(Cbor::decode(&[0x19, 0x00, 0xff]).is_err());
+        // Indefinite-length byte string
+        assert!(Cbor::decode(&[0x5f, 0x41, 0x00, 0xff]).is_err());
+        // Keys out of order, and repeated
+        assert!(Cbor::decode(&[0xa2, 0x02, 0x00, 0x01, 0x00]).is_err());
+        assert!(Cbor::decode(&[0xa2, 0x01, 0x00, 0x01, 0x00]).is_err());
+        // Text key, negative integer, trailing byte, truncated string
+        assert!(Cbor::decode(&[0xa1, 0x61, 0x61, 0x00]).is_err());
+        assert!(Cbor::decode(&[0x20]).is_err());
+        assert!(Cbor::decode(&[0x00, 0x00]).is_err());
+        assert!(Cbor::decode(&[0x42, 0x00]).is_err());
+    }
+
+    #[test]
+    fn test_nesting_limited() {
+        let mut bytes = vec![0xa1, 0x00].repeat(MAX_DEPTH);
+        bytes.push(0x00);
+        assert!(Cbor::decode(&bytes).is_ok());
+        let mut deeper = vec![0xa1, 0x00].repeat(MAX_DEPTH + 1);
+        deeper.push(0x00);
+        assert!(Cbor::decode(&deeper).is_err());
+    }
+}
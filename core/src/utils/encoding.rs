@@ -0,0 +1,338 @@
+//! Field element encoding helpers
+//!
+//! All conversions assume `F::Repr` is the little-endian canonical encoding,
+//! as it is for the Pasta fields. Decoding always rejects non-canonical input
+//! (values greater than or equal to the modulus) instead of reducing it.
+
+use ff::PrimeField;
+
+use crate::error::{Error, Result};
+
+/// Byte order of an encoded field element
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    /// Least significant byte first (native `F::Repr` order)
+    Little,
+    /// Most significant byte first
+    Big,
+}
+
+/// Number of bytes in the canonical encoding of `F`
+#[must_use]
+pub fn byte_len<F: PrimeField>() -> usize {
+    F::Repr::default().as_ref().len()
+}
+
+/// Encode a field element as bytes in the given order
+#[must_use]
+pub fn to_bytes<F: PrimeField>(value: &F, endianness: Endianness) -> Vec<u8> {
+    let mut bytes = value.to_repr().as_ref().to_vec();
+    if endianness == Endianness::Big {
+        bytes.reverse();
+    }
+    bytes
+}
+
+/// Decode a field element from exactly [`byte_len`] bytes in the given order
+///
+/// # Errors
+///
+/// Returns [`Error::Encoding`] if the length is wrong or the value is not
+/// canonical.
+pub fn from_bytes<F: PrimeField>(bytes: &[u8], endianness: Endianness) -> Result<F> {
+    let mut repr = F::Repr::default();
+    let len = repr.as_ref().len();
+    if bytes.len() != len {
+        return Err(Error::Encoding(format!(
+            "expected {len} bytes, found {}",
+            bytes.len()
+        )));
+    }
+
+    repr.as_mut().copy_from_slice(bytes);
+    if endianness == Endianness::Big {
+        repr.as_mut().reverse();
+    }
+
+    Option::from(F::from_repr(repr))
+        .ok_or_else(|| Error::Encoding("value is not a canonical field element".into()))
+}
+
+/// Encode a field element as `0x`-prefixed big-endian hex
+#[must_use]
+pub fn to_hex<F: PrimeField>(value: &F) -> String {
+    let mut hex = String::with_capacity(2 + 2 * byte_len::<F>());
+    hex.push_str("0x");
+    for byte in to_bytes(value, Endianness::Big) {
+        hex.push(char::from(HEX_DIGITS[usize::from(byte >> 4)]));
+        hex.push(char::from(HEX_DIGITS[usize::from(byte & 0x0f)]));
+    }
+    hex
+}
+
+/// Decode a big-endian hex string, with or without `0x` prefix
+///
+/// Shorter strings are left-padded with zeros.
+///
+/// # Errors
+///
+/// Returns [`Error::Encoding`] on invalid digits, oversized input or a
+/// non-canonical value.
+pub fn from_hex<F: PrimeField>(hex: &str) -> Result<F> {
+    let digits = hex.strip_prefix("0x").unwrap_or(hex).as_bytes();
+    let len = byte_len::<F>();
+    if digits.is_empty() || digits.len() > 2 * len {
+        return Err(Error::Encoding(format!(
+            "expected 1 to {} hex digits, found {}",
+            2 * len,
+            digits.len()
+        )));
+    }
+
+    let mut bytes = vec![0u8; len];
+    for (i, digit) in digits.iter().rev().enumerate() {
+        let nibble = hex_value(*digit)
+            .ok_or_else(|| Error::Encoding(format!("invalid hex digit {:?}", char::from(*digit))))?;
+        bytes[len - 1 - i / 2] |= nibble << (4 * (i % 2));
+    }
+
+    from_bytes(&bytes, Endianness::Big)
+}
+
+/// Convert a `u128` into a field element
+#[must_use]
+pub fn from_u128<F: PrimeField>(value: u128) -> F {
+    F::from_u128(value)
+}
+
+/// Convert a field element into a `u128` if it fits
+#[must_use]
+pub fn to_u128<F: PrimeField>(value: &F) -> Option<u128> {
+    let repr = value.to_repr();
+    let bytes = repr.as_ref();
+    if bytes.len() > 16 && bytes[16..].iter().any(|b| *b != 0) {
+        return None;
+    }
+
+    let mut low = [0u8; 16];
+    let n = bytes.len().min(16);
+    low[..n].copy_from_slice(&bytes[..n]);
+    Some(u128::from_le_bytes(low))
+}
+
+/// Pack a field element into little-endian `u64` limbs
+#[must_use]
+pub fn to_u64_limbs<F: PrimeField>(value: &F) -> Vec<u64> {
+    value
+        .to_repr()
+        .as_ref()
+        .chunks(8)
+        .map(|chunk| {
+            let mut limb = [0u8; 8];
+            limb[..chunk.len()].copy_from_slice(chunk);
+            u64::from_le_bytes(limb)
+        })
+        .collect()
+}
+
+/// Unpack a field element from little-endian `u64` limbs
+///
+/// # Errors
+///
+/// Returns [`Error::Encoding`] if the limb count does not match the field or
+/// the value is not canonical.
+pub fn from_u64_limbs<F: PrimeField>(limbs: &[u64]) -> Result<F> {
+    let len = byte_len::<F>();
+    let expected = len.div_ceil(8);
+    if limbs.len() != expected {
+        return Err(Error::Encoding(format!(
+            "expected {expected} limbs, found {}",
+            limbs.len()
+        )));
+    }
+
+    let bytes: Vec<u8> = limbs.iter().flat_map(|limb| limb.to_le_bytes()).collect();
+    if bytes[len..].iter().any(|b| *b != 0) {
+        return Err(Error::Encoding("value does not fit the field encoding".into()));
+    }
+    from_bytes(&bytes[..len], Endianness::Little)
+}
+
+/// Encode raw bytes as lowercase hex without prefix
+#[must_use]
+pub fn encode_hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(2 * bytes.len());
+    for byte in bytes {
+        hex.push(char::from(HEX_DIGITS[usize::from(byte >> 4)]));
+        hex.push(char::from(HEX_DIGITS[usize::from(byte & 0x0f)]));
+    }
+    hex
+}
+
+/// Decode hex without prefix into raw bytes
+///
+/// # Errors
+///
+/// Returns [`Error::Encoding`] on an odd number of digits or an invalid
+/// digit.
+pub fn decode_hex(hex: &str) -> Result<Vec<u8>> {
+    let digits = hex.as_bytes();
+    if digits.len() & 1 == 1 {
+        return Err(Error::Encoding(format!(
+            "odd number of hex digits: {}",
+            digits.len()
+        )));
+    }
+
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let high = hex_value(pair[0]);
+            let low = hex_value(pair[1]);
+            high.zip(low)
+                .map(|(high, low)| high << 4 | low)
+                .ok_or_else(|| {
+                    Error::Encoding(format!(
+                        "invalid hex byte {:?}",
+                        String::from_utf8_lossy(pair)
+                    ))
+                })
+        })
+        .collect()
+}
+
+/// Append `value` as an unsigned LEB128 varint
+pub fn encode_varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        #[allow(clippy::cast_possible_truncation)]
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    out.push(value as u8);
+}
+
+/// Decode an unsigned LEB128 varint from the start of `bytes`
+///
+/// Returns the value and the number of bytes it took. Only the shortest
+/// encoding of each value is accepted.
+///
+/// # Errors
+///
+/// Returns [`Error::Encoding`] if the varint is truncated, overflows `u64`
+/// or is not minimal.
+pub fn decode_varint(bytes: &[u8]) -> Result<(u64, usize)> {
+    let mut value = 0u64;
+    for (i, byte) in bytes.iter().enumerate().take(10) {
+        let bits = u64::from(byte & 0x7f);
+        if i == 9 && bits > 1 {
+            return Err(Error::Encoding("varint overflows u64".into()));
+        }
+        value |= bits << (7 * i);
+        if byte & 0x80 == 0 {
+            if i > 0 && bits == 0 {
+                return Err(Error::Encoding("varint is not minimally encoded".into()));
+            }
+            return Ok((value, i + 1));
+        }
+    }
+    if bytes.len() >= 10 {
+        Err(Error::Encoding("varint overflows u64".into()))
+    } else {
+        Err(Error::Encoding("truncated varint".into()))
+    }
+}
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+fn hex_value(digit: u8) -> Option<u8> {
+    match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        b'A'..=b'F' => Some(digit - b'A' + 10),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ff::Field;
+    use pasta_curves::Fp;
+
+    #[test]
+    fn test_varint_round_trip() {
+        for (value, len) in [(0, 1), (127, 1), (128, 2), (300, 2), (u64::MAX, 10)] {
+            let mut out = Vec::new();
+            encode_varint(value, &mut out);
+            assert_eq!(out.len(), len, "{value}");
+            out.push(0xee);
+            assert_eq!(decode_varint(&out).unwrap(), (value, len));
+        }
+
+        for bad in [&[][..], &[0x80], &[0x80, 0x00], &[0xff; 10], &[0xff; 11]] {
+            assert!(
+                matches!(decode_varint(bad), Err(Error::Encoding(_))),
+                "{bad:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_byte_hex_round_trip() {
+        let bytes = [0x00, 0x7f, 0xab, 0xff];
+        assert_eq!(encode_hex(&bytes), "007fabff");
+        assert_eq!(decode_hex("007FABff").unwrap(), bytes);
+        assert!(decode_hex("abc").is_err());
+        assert!(decode_hex("zz").is_err());
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let value = Fp::from(0x0102_0304_0506_0708u64);
+
+        let le = to_bytes(&value, Endianness::Little);
+        let be = to_bytes(&value, Endianness::Big);
+        assert_eq!(le[0], 0x08);
+        assert_eq!(be[31], 0x08);
+
+        assert_eq!(from_bytes::<Fp>(&le, Endianness::Little).unwrap(), value);
+        assert_eq!(from_bytes::<Fp>(&be, Endianness::Big).unwrap(), value);
+    }
+
+    #[test]
+    fn test_non_canonical_rejected() {
+        assert!(from_bytes::<Fp>(&[0xff; 32], Endianness::Little).is_err());
+        assert!(from_bytes::<Fp>(&[0u8; 31], Endianness::Little).is_err());
+        assert!(from_hex::<Fp>(&"f".repeat(64)).is_err());
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let value = -Fp::ONE;
+        let hex = to_hex(&value);
+        assert_eq!(hex.len(), 66);
+        assert_eq!(from_hex::<Fp>(&hex).unwrap(), value);
+
+        assert_eq!(from_hex::<Fp>("0x2a").unwrap(), Fp::from(42));
+        assert_eq!(from_hex::<Fp>("2A").unwrap(), Fp::from(42));
+        assert!(from_hex::<Fp>("0xzz").is_err());
+    }
+
+    #[test]
+    fn test_u128_round_trip() {
+        let value = u128::MAX - 7;
+        let f: Fp = from_u128(value);
+        assert_eq!(to_u128(&f), Some(value));
+        assert_eq!(to_u128(&-Fp::ONE), None);
+    }
+
+    #[test]
+    fn test_u64_limbs_round_trip() {
+        let value = -Fp::from(5);
+        let limbs = to_u64_limbs(&value);
+        assert_eq!(limbs.len(), 4);
+        assert_eq!(from_u64_limbs::<Fp>(&limbs).unwrap(), value);
+        assert!(from_u64_limbs::<Fp>(&limbs[..3]).is_err());
+    }
+}
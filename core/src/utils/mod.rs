@@ -1,6 +1,18 @@
 //! Utility functions and helpers
 
+pub mod annotation;
+pub mod bits;
+pub mod cbor;
+pub mod encoding;
+pub mod rows;
+
+pub use encoding::Endianness;
+pub use rows::{RowBudget, RowLayout};
+
 /// Calculate the number of rows needed for a given number of elements
+///
+/// This ignores blinding rows; use [`RowBudget`] when choosing k for a real
+/// circuit.
 #[must_use] pub fn calculate_rows(elements: usize) -> usize {
     elements.next_power_of_two()
 }
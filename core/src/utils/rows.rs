@@ -0,0 +1,154 @@
+//! Row budgeting aware of blinding rows and k limits
+
+use ff::PrimeField;
+use halo2_proofs::plonk::ConstraintSystem;
+
+use crate::error::{Error, Result};
+
+/// Largest k the Pasta fields support before the FFT domain runs out
+pub const MAX_K: u32 = 32;
+
+/// Row layout chosen for a circuit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RowLayout {
+    /// Circuit size parameter
+    pub k: u32,
+    /// Total rows, `2^k`
+    pub total_rows: usize,
+    /// Rows the circuit may assign, `2^k - (blinding_rows + 1)`
+    pub usable_rows: usize,
+    /// Rows reserved for blinding factors
+    pub blinding_rows: usize,
+    /// Usable rows left after the requested rows
+    pub spare_rows: usize,
+}
+
+/// Computes how many rows a circuit really gets for a given k
+///
+/// halo2 reserves the last `blinding_factors + 1` rows of every column for
+/// blinding and the `l_last` row, so a circuit needing `n` rows does not fit
+/// in `n.next_power_of_two()` when `n` is close to the power of two.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RowBudget {
+    blinding_rows: usize,
+    max_k: u32,
+}
+
+impl RowBudget {
+    /// Create a budget from a blinding factor count and maximum k
+    #[must_use]
+    pub fn new(blinding_rows: usize, max_k: u32) -> Self {
+        Self {
+            blinding_rows,
+            max_k: max_k.min(MAX_K),
+        }
+    }
+
+    /// Derive the budget from a configured constraint system
+    ///
+    /// The maximum k accounts for the extended domain used for the quotient
+    /// polynomial, which needs `ceil(log2(degree - 1))` extra bits of the
+    /// field's two-adicity.
+    #[must_use]
+    pub fn from_cs<F: PrimeField>(cs: &ConstraintSystem<F>) -> Self {
+        let quotient_degree = cs.degree().saturating_sub(1).max(1);
+        let extended_bits = quotient_degree.next_power_of_two().trailing_zeros();
+        Self::new(cs.blinding_factors(), F::S.saturating_sub(extended_bits))
+    }
+
+    /// Rows reserved for blinding
+    #[must_use]
+    pub fn blinding_rows(&self) -> usize {
+        self.blinding_rows
+    }
+
+    /// Largest supported k
+    #[must_use]
+    pub fn max_k(&self) -> u32 {
+        self.max_k
+    }
+
+    /// Rows available for assignment at size `k`
+    #[must_use]
+    pub fn usable_rows(&self, k: u32) -> usize {
+        (1usize << k).saturating_sub(self.blinding_rows + 1)
+    }
+
+    /// Layout for a fixed `k`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::RowBudgetExceeded`] if `rows` do not fit at `k` or `k`
+    /// exceeds the maximum.
+    pub fn layout(&self, k: u32, rows: usize) -> Result<RowLayout> {
+        let usable_rows = self.usable_rows(k);
+        if k > self.max_k || rows > usable_rows {
+            return Err(Error::RowBudgetExceeded {
+                rows,
+                max_k: self.max_k.min(k),
+            });
+        }
+
+        Ok(RowLayout {
+            k,
+            total_rows: 1 << k,
+            usable_rows,
+            blinding_rows: self.blinding_rows,
+            spare_rows: usable_rows - rows,
+        })
+    }
+
+    /// Smallest layout that fits `rows`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::RowBudgetExceeded`] if no k up to the maximum fits.
+    pub fn min_layout(&self, rows: usize) -> Result<RowLayout> {
+        (1..=self.max_k)
+            .find(|&k| self.usable_rows(k) >= rows)
+            .map_or(
+                Err(Error::RowBudgetExceeded {
+                    rows,
+                    max_k: self.max_k,
+                }),
+                |k| self.layout(k, rows),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pasta_curves::Fp;
+
+    #[test]
+    fn test_blinding_rows_push_k_up() {
+        let budget = RowBudget::new(5, MAX_K);
+
+        // 251 rows would fit in a bare 2^8, but not once 6 rows are reserved.
+        let layout = budget.min_layout(251).unwrap();
+        assert_eq!(layout.k, 9);
+        assert_eq!(layout.usable_rows, 512 - 6);
+        assert_eq!(layout.spare_rows, 512 - 6 - 251);
+
+        assert_eq!(budget.min_layout(256 - 6).unwrap().k, 8);
+    }
+
+    #[test]
+    fn test_max_k_enforced() {
+        let budget = RowBudget::new(5, 10);
+        assert!(budget.min_layout(1 << 10).is_err());
+        assert!(matches!(
+            budget.layout(11, 1),
+            Err(Error::RowBudgetExceeded { max_k: 10, .. })
+        ));
+    }
+
+    #[test]
+    fn test_from_constraint_system() {
+        let cs = ConstraintSystem::<Fp>::default();
+        let budget = RowBudget::from_cs(&cs);
+        assert_eq!(budget.blinding_rows(), cs.blinding_factors());
+        assert!(budget.max_k() <= MAX_K);
+    }
+}
@@ -0,0 +1,246 @@
+//! Canonical verifying key encoding
+//!
+//! Verifying key hashes are pinned on-chain, so the bytes they are computed
+//! over must not depend on the platform, pointer width or build flags.
+//! halo2 0.3 has no verifying key serialization of its own; what it does
+//! have is the pinned representation it hashes into every transcript, which
+//! covers the domain, constraint system, fixed commitments and permutation.
+//! The canonical encoding is exactly that preimage behind a version tag:
+//!
+//! ```text
+//! "ZKV1" || len: u64 LE || pinned: [u8; len]
+//! ```
+//!
+//! Two keys have the same canonical bytes if and only if halo2 treats them as
+//! the same key. [`VkHash::of`] hashes the same pinned string, so it is stable
+//! across platforms too.
+//!
+//! [`VkHash::of`]: crate::proof::VkHash::of
+
+use std::io::Write;
+
+use halo2_proofs::{arithmetic::CurveAffine, plonk::VerifyingKey};
+
+use crate::{
+    error::{Error, Result},
+    proof::VkHash,
+};
+
+/// Tag at the start of every canonical verifying key
+pub const VK_MAGIC: &[u8; 4] = b"ZKV1";
+
+/// Canonical encoding of `vk`
+#[must_use]
+pub fn canonical_vk_bytes<C: CurveAffine>(vk: &VerifyingKey<C>) -> Vec<u8> {
+    let pinned = format!("{:?}", vk.pinned());
+    let mut bytes = Vec::with_capacity(VK_MAGIC.len() + 8 + pinned.len());
+    bytes.extend_from_slice(VK_MAGIC);
+    bytes.extend_from_slice(&(pinned.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(pinned.as_bytes());
+    bytes
+}
+
+/// Write the canonical encoding of `vk` to `writer`
+///
+/// # Errors
+///
+/// Returns [`Error::Io`] if writing fails.
+pub fn write_canonical_vk<C: CurveAffine, W: Write>(
+    vk: &VerifyingKey<C>,
+    mut writer: W,
+) -> Result<()> {
+    writer.write_all(&canonical_vk_bytes(vk))?;
+    Ok(())
+}
+
+/// Check that `bytes` is the canonical encoding of `vk`
+///
+/// # Errors
+///
+/// Returns [`Error::Encoding`] if `bytes` is not a canonical encoding at all
+/// and [`Error::VkMismatch`] if it encodes a different key.
+pub fn check_canonical_vk<C: CurveAffine>(vk: &VerifyingKey<C>, bytes: &[u8]) -> Result<()> {
+    let pinned = pinned_of(bytes)?;
+    let expected = canonical_vk_bytes(vk);
+    if bytes == expected {
+        Ok(())
+    } else {
+        Err(Error::VkMismatch {
+            expected: VkHash::of(vk).to_string(),
+            found: VkHash::of_pinned(pinned).to_string(),
+        })
+    }
+}
+
+/// Pinned representation inside a canonical encoding
+fn pinned_of(bytes: &[u8]) -> Result<&[u8]> {
+    let rest = bytes
+        .strip_prefix(VK_MAGIC)
+        .ok_or_else(|| Error::Encoding("not a canonical verifying key".into()))?;
+    let (len, pinned) = rest
+        .split_first_chunk::<8>()
+        .ok_or_else(|| Error::Encoding("truncated canonical verifying key".into()))?;
+    if u64::from_le_bytes(*len) != pinned.len() as u64 {
+        return Err(Error::Encoding(
+            "canonical verifying key length does not match".into(),
+        ));
+    }
+    Ok(pinned)
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        plonk::{
+            keygen_vk, Advice, Circuit, Column, ConstraintSystem, Error as PlonkError, Fixed,
+            Instance, Selector,
+        },
+        poly::{commitment::Params, Rotation},
+        transcript::{Challenge255, EncodedChallenge, Transcript},
+    };
+    use pasta_curves::{vesta, Fp};
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::utils::encoding::encode_hex;
+
+    /// Golden SHA-256 of the canonical encoding of [`Fixture`]'s key, the
+    /// same on every target CI builds for
+    const CANONICAL_SHA256: &str =
+        "346154478d523c970bd90a86339104605b3c61a007bd2d7a69857ca5f9762137";
+
+    /// Golden [`VkHash`] of [`Fixture`]'s key
+    const VK_HASH: &str = "00b6d17ccec1817ca29eee6dd0a00ef7587ee98b61c8445ec19746e867fa24fa";
+
+    /// Touches every part of the pinned key: fixed and instance columns,
+    /// a gate, a selector and the permutation
+    #[derive(Default)]
+    struct Fixture {
+        a: Value<Fp>,
+    }
+
+    impl Circuit<Fp> for Fixture {
+        type Config = (Column<Advice>, Column<Fixed>, Column<Instance>, Selector);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = meta.advice_column();
+            let fixed = meta.fixed_column();
+            let instance = meta.instance_column();
+            let selector = meta.selector();
+            meta.enable_equality(advice);
+            meta.enable_equality(instance);
+            meta.create_gate("scale", |meta| {
+                let s = meta.query_selector(selector);
+                let a = meta.query_advice(advice, Rotation::cur());
+                let b = meta.query_advice(advice, Rotation::next());
+                let c = meta.query_fixed(fixed);
+                vec![s * (a * c - b)]
+            });
+            (advice, fixed, instance, selector)
+        }
+
+        fn synthesize(
+            &self,
+            (advice, fixed, instance, selector): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> std::result::Result<(), PlonkError> {
+            let b = layouter.assign_region(
+                || "scale",
+                |mut region| {
+                    selector.enable(&mut region, 0)?;
+                    region.assign_fixed(|| "c", fixed, 0, || Value::known(Fp::from(3)))?;
+                    region.assign_advice(|| "a", advice, 0, || self.a)?;
+                    region.assign_advice(|| "b", advice, 1, || self.a * Value::known(Fp::from(3)))
+                },
+            )?;
+            layouter.constrain_instance(b.cell(), instance, 0)
+        }
+    }
+
+    fn fixture_vk() -> VerifyingKey<vesta::Affine> {
+        keygen_vk(&Params::new(4), &Fixture::default()).unwrap()
+    }
+
+    #[test]
+    fn test_golden_encoding() {
+        let vk = fixture_vk();
+        let bytes = canonical_vk_bytes(&vk);
+        assert_eq!(encode_hex(&Sha256::digest(&bytes)), CANONICAL_SHA256);
+        assert_eq!(VkHash::of(&vk).to_string(), VK_HASH);
+    }
+
+    #[test]
+    fn test_deterministic() {
+        assert_eq!(
+            canonical_vk_bytes(&fixture_vk()),
+            canonical_vk_bytes(&fixture_vk())
+        );
+        let mut written = Vec::new();
+        write_canonical_vk(&fixture_vk(), &mut written).unwrap();
+        assert_eq!(written, canonical_vk_bytes(&fixture_vk()));
+    }
+
+    /// Records the scalar a verifying key hashes into a transcript
+    struct Capture(Option<Fp>);
+
+    impl Transcript<vesta::Affine, Challenge255<vesta::Affine>> for Capture {
+        fn squeeze_challenge(&mut self) -> Challenge255<vesta::Affine> {
+            Challenge255::new(&[0; 64])
+        }
+
+        fn common_point(&mut self, _: vesta::Affine) -> std::io::Result<()> {
+            Ok(())
+        }
+
+        fn common_scalar(&mut self, scalar: Fp) -> std::io::Result<()> {
+            self.0 = Some(scalar);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_matches_halo2_transcript_repr() {
+        // halo2 derives the key's transcript scalar from the same preimage
+        let vk = fixture_vk();
+        let bytes = canonical_vk_bytes(&vk);
+        let digest = blake2b_simd::Params::new()
+            .hash_length(64)
+            .personal(b"Halo2-Verify-Key")
+            .hash(&bytes[VK_MAGIC.len()..]);
+        let expected = <Fp as ff::FromUniformBytes<64>>::from_uniform_bytes(digest.as_array());
+
+        let mut capture = Capture(None);
+        vk.hash_into(&mut capture).unwrap();
+        assert_eq!(capture.0, Some(expected));
+    }
+
+    #[test]
+    fn test_check() {
+        let vk = fixture_vk();
+        let bytes = canonical_vk_bytes(&vk);
+        check_canonical_vk(&vk, &bytes).unwrap();
+
+        let other: VerifyingKey<vesta::Affine> =
+            keygen_vk(&Params::new(5), &Fixture::default()).unwrap();
+        assert!(matches!(
+            check_canonical_vk(&other, &bytes),
+            Err(Error::VkMismatch { expected, found })
+                if expected == VkHash::of(&other).to_string() && found == VkHash::of(&vk).to_string()
+        ));
+
+        assert!(matches!(
+            check_canonical_vk(&vk, &bytes[..bytes.len() - 1]),
+            Err(Error::Encoding(_))
+        ));
+        assert!(matches!(
+            check_canonical_vk(&vk, b"ZKV0"),
+            Err(Error::Encoding(_))
+        ));
+    }
+}
@@ -0,0 +1,207 @@
+//! Expansion of `#[derive(CircuitConfig)]`
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    spanned::Spanned, Data, DeriveInput, Expr, Fields, GenericArgument, LitStr, PathArguments, Type,
+};
+
+/// What a field holds, decided from its type
+enum Kind {
+    Advice,
+    Instance,
+    Fixed,
+    Selector,
+    Table,
+    /// Anything else, built with `Default::default()`
+    Other,
+}
+
+/// Options from `#[config(...)]` on a field
+#[derive(Default)]
+struct Options {
+    equality: bool,
+    constant: bool,
+    complex: bool,
+    with: Option<Expr>,
+}
+
+pub(crate) fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.span(),
+            "CircuitConfig can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new(
+            input.span(),
+            "CircuitConfig requires named fields",
+        ));
+    };
+
+    let core = quote!(::zk_proof_core);
+    let allocations = fields
+        .named
+        .iter()
+        .map(allocation)
+        .collect::<syn::Result<Vec<_>>>()?;
+    let idents = fields.named.iter().map(|field| &field.ident);
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #core::circuits::config::CircuitConfig for #name #ty_generics
+        #where_clause
+        {
+            #[allow(unused_variables)]
+            fn allocate<F: #core::prelude::PrimeField>(
+                cs: &mut #core::prelude::ConstraintSystem<F>,
+            ) -> Self {
+                #(#allocations)*
+                Self {
+                    #(#idents),*
+                }
+            }
+        }
+    })
+}
+
+/// Statements that allocate `field` into a local of the same name
+fn allocation(field: &syn::Field) -> syn::Result<TokenStream> {
+    let ident = field.ident.as_ref().expect("named field");
+    let options = options_of(field)?;
+    let (element, len) = match &field.ty {
+        Type::Array(array) => (&*array.elem, Some(&array.len)),
+        ty => (ty, None),
+    };
+    let kind = kind_of(element);
+
+    if options.equality && !matches!(kind, Kind::Advice | Kind::Instance | Kind::Fixed) {
+        return Err(syn::Error::new(
+            field.span(),
+            "`equality` only applies to columns",
+        ));
+    }
+    if options.constant && !matches!(kind, Kind::Fixed) {
+        return Err(syn::Error::new(
+            field.span(),
+            "`constant` only applies to fixed columns",
+        ));
+    }
+    if options.complex && !matches!(kind, Kind::Selector) {
+        return Err(syn::Error::new(
+            field.span(),
+            "`complex` only applies to selectors",
+        ));
+    }
+
+    let allocate = if let Some(with) = &options.with {
+        quote!(#with)
+    } else {
+        let one = match kind {
+            Kind::Advice => quote!(cs.advice_column()),
+            Kind::Instance => quote!(cs.instance_column()),
+            Kind::Fixed => quote!(cs.fixed_column()),
+            Kind::Selector if options.complex => quote!(cs.complex_selector()),
+            Kind::Selector => quote!(cs.selector()),
+            Kind::Table => quote!(cs.lookup_table_column()),
+            Kind::Other => quote!(::core::default::Default::default()),
+        };
+        if len.is_some() {
+            quote!(::core::array::from_fn(|_| #one))
+        } else {
+            one
+        }
+    };
+
+    let mut enable = Vec::new();
+    if options.equality {
+        enable.push(quote!(cs.enable_equality(column);));
+    }
+    if options.constant {
+        enable.push(quote!(cs.enable_constant(column);));
+    }
+    let enable = if enable.is_empty() {
+        quote!()
+    } else if len.is_some() {
+        quote! {
+            for column in #ident {
+                #(#enable)*
+            }
+        }
+    } else {
+        quote! {
+            let column = #ident;
+            #(#enable)*
+        }
+    };
+
+    Ok(quote! {
+        let #ident = #allocate;
+        #enable
+    })
+}
+
+/// Classify a field type by its last path segment
+fn kind_of(ty: &Type) -> Kind {
+    let Type::Path(path) = ty else {
+        return Kind::Other;
+    };
+    let Some(segment) = path.path.segments.last() else {
+        return Kind::Other;
+    };
+
+    match segment.ident.to_string().as_str() {
+        "Selector" => Kind::Selector,
+        "TableColumn" => Kind::Table,
+        "Column" => {
+            let PathArguments::AngleBracketed(args) = &segment.arguments else {
+                return Kind::Other;
+            };
+            let Some(GenericArgument::Type(Type::Path(arg))) = args.args.first() else {
+                return Kind::Other;
+            };
+            match arg
+                .path
+                .segments
+                .last()
+                .map(|s| s.ident.to_string())
+                .as_deref()
+            {
+                Some("Advice") => Kind::Advice,
+                Some("Instance") => Kind::Instance,
+                Some("Fixed") => Kind::Fixed,
+                _ => Kind::Other,
+            }
+        }
+        _ => Kind::Other,
+    }
+}
+
+/// Parse `#[config(equality, constant, complex, with = "expr")]`
+fn options_of(field: &syn::Field) -> syn::Result<Options> {
+    let mut options = Options::default();
+    for attr in &field.attrs {
+        if !attr.path().is_ident("config") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("equality") {
+                options.equality = true;
+            } else if meta.path.is_ident("constant") {
+                options.constant = true;
+            } else if meta.path.is_ident("complex") {
+                options.complex = true;
+            } else if meta.path.is_ident("with") {
+                options.with = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            } else {
+                return Err(
+                    meta.error("expected `equality`, `constant`, `complex` or `with = \"...\"`")
+                );
+            }
+            Ok(())
+        })?;
+    }
+    Ok(options)
+}
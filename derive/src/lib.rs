@@ -0,0 +1,72 @@
+//! Derive macros for the ZK proof system
+//!
+//! Use these through the re-exports in `zk_proof_core`; the generated code
+//! refers to items in that crate.
+
+#![warn(missing_docs)]
+#![warn(clippy::all)]
+#![warn(clippy::pedantic)]
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+mod circuit_config;
+mod public_inputs;
+
+/// Derive `zk_proof_core::PublicInputs` for a struct of field elements
+///
+/// Each field is either a field element or an array of them. Fields are laid
+/// out in declaration order, one row per element, in instance column 0 unless
+/// annotated with `#[instance(column = N)]`. The element type is taken from
+/// the first field.
+///
+/// ```ignore
+/// #[derive(PublicInputs)]
+/// struct Transfer<F: Field> {
+///     root: F,
+///     nullifiers: [F; 2],
+///     #[instance(column = 1)]
+///     amount: F,
+/// }
+/// ```
+#[proc_macro_derive(PublicInputs, attributes(instance))]
+pub fn derive_public_inputs(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    public_inputs::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive `zk_proof_core::circuits::CircuitConfig` for a circuit config struct
+///
+/// Fields are allocated in declaration order from their types: advice,
+/// instance and fixed columns, selectors and lookup table columns, or arrays
+/// of those. Any other field is built with `Default::default()`. Per-field
+/// options go in `#[config(...)]`:
+///
+/// - `equality` enables equality constraints on the column(s)
+/// - `constant` enables a fixed column for constants
+/// - `complex` allocates a complex selector
+/// - `with = "expr"` builds the field from `expr`, which can use `cs` and any
+///   field declared earlier
+///
+/// ```ignore
+/// #[derive(Clone, Debug, CircuitConfig)]
+/// struct MyConfig {
+///     #[config(equality)]
+///     advice: [Column<Advice>; 3],
+///     #[config(equality, constant)]
+///     fixed: Column<Fixed>,
+///     #[config(complex)]
+///     s_range: Selector,
+///     #[config(with = "HashChip::configure(cs, advice)")]
+///     hash: HashConfig,
+/// }
+/// ```
+#[proc_macro_derive(CircuitConfig, attributes(config))]
+pub fn derive_circuit_config(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    circuit_config::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,175 @@
+//! Expansion of `#[derive(PublicInputs)]`
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{spanned::Spanned, Data, DeriveInput, Fields, LitInt, Type};
+
+/// One struct field and where it lands in the instance columns
+struct Slot<'a> {
+    ident: &'a syn::Ident,
+    column: usize,
+    /// Whether the field is an array of elements
+    array: bool,
+    /// Number of rows the field occupies
+    len: TokenStream,
+    /// Row of the first element, as a sum of preceding lengths
+    row: TokenStream,
+}
+
+impl Slot<'_> {
+    fn layout(&self, core: &TokenStream) -> TokenStream {
+        let Self {
+            ident,
+            column,
+            len,
+            row,
+            ..
+        } = self;
+        let name = ident.to_string();
+        quote! {
+            #core::public_inputs::InstanceSlot {
+                name: #name,
+                column: #column,
+                row: #row,
+                len: #len,
+            }
+        }
+    }
+
+    fn push(&self) -> TokenStream {
+        let Self { ident, column, .. } = self;
+        if self.array {
+            quote!(instances[#column].extend_from_slice(&self.#ident);)
+        } else {
+            quote!(instances[#column].push(self.#ident);)
+        }
+    }
+
+    fn read(&self) -> TokenStream {
+        let Self {
+            ident, column, row, ..
+        } = self;
+        if self.array {
+            quote!(#ident: ::core::array::from_fn(|i| instances[#column][#row + i]))
+        } else {
+            quote!(#ident: instances[#column][#row])
+        }
+    }
+}
+
+pub(crate) fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.span(),
+            "PublicInputs can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new(
+            input.span(),
+            "PublicInputs requires named fields",
+        ));
+    };
+    let Some(first) = fields.named.first() else {
+        return Err(syn::Error::new(
+            input.span(),
+            "PublicInputs requires at least one field",
+        ));
+    };
+    let element = element_type(&first.ty);
+
+    // Running row count of each column; its final value is the column height.
+    let mut shape: Vec<TokenStream> = Vec::new();
+    let mut slots = Vec::with_capacity(fields.named.len());
+    for field in &fields.named {
+        let column = column_of(field)?;
+        let (array, len) = match &field.ty {
+            Type::Array(array) => {
+                let len = &array.len;
+                (true, quote!((#len)))
+            }
+            _ => (false, quote!(1usize)),
+        };
+
+        if shape.len() <= column {
+            shape.resize_with(column + 1, || quote!(0usize));
+        }
+        let row = shape[column].clone();
+        shape[column] = quote!(#row + #len);
+        slots.push(Slot {
+            ident: field.ident.as_ref().expect("named field"),
+            column,
+            array,
+            len,
+            row,
+        });
+    }
+
+    let core = quote!(::zk_proof_core);
+    let columns = shape.len();
+    let layout = slots.iter().map(|slot| slot.layout(&core));
+    let push = slots.iter().map(Slot::push);
+    let read = slots.iter().map(Slot::read);
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let mut predicates = where_clause
+        .map(|w| w.predicates.clone())
+        .unwrap_or_default();
+    predicates.push(syn::parse_quote!(#element: #core::prelude::Field));
+
+    Ok(quote! {
+        impl #impl_generics #core::public_inputs::PublicInputs<#element> for #name #ty_generics
+        where
+            #predicates
+        {
+            const SHAPE: &'static [usize] = &[#(#shape),*];
+
+            const LAYOUT: &'static [#core::public_inputs::InstanceSlot] = &[#(#layout),*];
+
+            fn to_instances(&self) -> ::std::vec::Vec<::std::vec::Vec<#element>> {
+                let mut instances: ::std::vec::Vec<::std::vec::Vec<#element>> =
+                    Self::SHAPE.iter().map(|rows| ::std::vec::Vec::with_capacity(*rows)).collect();
+                debug_assert_eq!(instances.len(), #columns);
+                #(#push)*
+                instances
+            }
+
+            fn from_instances(
+                instances: &[::std::vec::Vec<#element>],
+            ) -> #core::Result<Self> {
+                #core::public_inputs::check_shape(Self::SHAPE, instances)?;
+                Ok(Self {
+                    #(#read),*
+                })
+            }
+        }
+    })
+}
+
+/// Element type of a field: the array element type, or the type itself
+fn element_type(ty: &Type) -> &Type {
+    match ty {
+        Type::Array(array) => &array.elem,
+        other => other,
+    }
+}
+
+/// Instance column from `#[instance(column = N)]`, defaulting to 0
+fn column_of(field: &syn::Field) -> syn::Result<usize> {
+    let mut column = 0;
+    for attr in &field.attrs {
+        if !attr.path().is_ident("instance") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("column") {
+                column = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+                Ok(())
+            } else {
+                Err(meta.error("expected `column = N`"))
+            }
+        })?;
+    }
+    Ok(column)
+}
@@ -0,0 +1,98 @@
+//! Per-circuit timing baselines
+//!
+//! ```text
+//! cargo bench -p zk-proof-integration-tests --bench perf -- --save <baseline.json>
+//! cargo bench -p zk-proof-integration-tests --bench perf -- --compare <baseline.json> [--threshold 0.2]
+//! ```
+//!
+//! `--samples <n>` sets the pipeline runs per circuit. Without `--save` or
+//! `--compare` the timings are printed as JSON. Compare mode exits with
+//! status 1 if any phase slowed down past the threshold.
+
+use std::{path::PathBuf, process::ExitCode};
+
+use zk_proof_integration_tests::{
+    perf::{measure, Baseline, DEFAULT_SAMPLES, DEFAULT_THRESHOLD},
+    registry,
+};
+
+enum Mode {
+    Print,
+    Save(PathBuf),
+    Compare(PathBuf),
+}
+
+struct Options {
+    mode: Mode,
+    samples: usize,
+    threshold: f64,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        mode: Mode::Print,
+        samples: DEFAULT_SAMPLES,
+        threshold: DEFAULT_THRESHOLD,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value"));
+        match arg.as_str() {
+            // Passed by `cargo bench` to every bench target
+            "--bench" => {}
+            "--save" => options.mode = Mode::Save(value()?.into()),
+            "--compare" => options.mode = Mode::Compare(value()?.into()),
+            "--samples" => {
+                options.samples = value()?
+                    .parse()
+                    .map_err(|err| format!("--samples: {err}"))?;
+            }
+            "--threshold" => {
+                options.threshold = value()?
+                    .parse()
+                    .map_err(|err| format!("--threshold: {err}"))?;
+            }
+            other => return Err(format!("unknown argument {other}")),
+        }
+    }
+    Ok(options)
+}
+
+fn run(options: &Options) -> Result<bool, String> {
+    let current = measure(&registry(), options.samples).map_err(|err| err.to_string())?;
+
+    match &options.mode {
+        Mode::Print => println!("{}", current.to_json()),
+        Mode::Save(path) => {
+            current.save(path).map_err(|err| err.to_string())?;
+            println!("saved baseline to {}", path.display());
+        }
+        Mode::Compare(path) => {
+            let baseline = Baseline::load(path).map_err(|err| err.to_string())?;
+            let regressions = baseline.compare(&current, options.threshold);
+            for regression in &regressions {
+                println!("regression: {regression}");
+            }
+            if !regressions.is_empty() {
+                return Ok(false);
+            }
+            println!(
+                "no phase slowed down by more than {:.0}%",
+                options.threshold * 100.0
+            );
+        }
+    }
+    Ok(true)
+}
+
+fn main() -> ExitCode {
+    let result = parse_args().and_then(|options| run(&options));
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("perf: {err}");
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,357 @@
+//! Circuits registered with the pipeline
+//!
+//! Each circuit here is built from the core gadgets and exposes its public
+//! inputs through a [`PublicInputs`] struct. The circuits in
+//! `zk_proof_core::circuits` are not registered until they build against
+//! halo2 0.3.
+
+use ff::Field;
+use group::{prime::PrimeCurveAffine, Curve};
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Instance, Selector},
+    poly::Rotation,
+};
+use pasta_curves::{pallas, Fp};
+use zk_proof_core::{
+    gadgets::{
+        BooleanChip, BooleanConfig, EccChip, EccConfig, Gadget, RangeCheckChip, RangeCheckConfig,
+    },
+    PublicInputs,
+};
+
+use crate::Case;
+
+/// Bits checked by [`RangeBitsCircuit`]
+pub const RANGE_BITS: usize = 8;
+
+/// Public inputs of [`RangeBitsCircuit`]
+#[derive(Clone, Debug, PartialEq, PublicInputs)]
+pub struct RangeBitsInstance {
+    /// Value in `[0, 2^RANGE_BITS)`
+    pub value: Fp,
+    /// Boolean flag
+    pub bit: Fp,
+}
+
+/// Range-checks a value and boolean-checks a flag, both public
+#[derive(Clone, Debug, Default)]
+pub struct RangeBitsCircuit {
+    /// Witnessed value
+    pub value: Value<Fp>,
+    /// Witnessed flag
+    pub bit: Value<bool>,
+}
+
+/// Columns of [`RangeBitsCircuit`]
+#[derive(Clone, Debug)]
+pub struct RangeBitsConfig {
+    range: RangeCheckConfig,
+    boolean: BooleanConfig,
+    instance: Column<Instance>,
+}
+
+impl Circuit<Fp> for RangeBitsCircuit {
+    type Config = RangeBitsConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> RangeBitsConfig {
+        let value = meta.advice_column();
+        let bit = meta.advice_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        RangeBitsConfig {
+            range: RangeCheckChip::<Fp, RANGE_BITS>::configure(meta, value),
+            boolean: BooleanChip::configure(meta, bit),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: RangeBitsConfig,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let range = RangeCheckChip::<Fp, RANGE_BITS>::construct(config.range);
+        range.load(&mut layouter)?;
+        let value = range.assign(layouter.namespace(|| "value"), self.value)?;
+
+        let boolean = BooleanChip::construct(config.boolean);
+        let bit = boolean.assign(layouter.namespace(|| "bit"), self.bit)?;
+
+        for (cell, name) in [(value, "value"), (bit, "bit")] {
+            let slot = RangeBitsInstance::slot(name).ok_or(Error::Synthesis)?;
+            layouter.constrain_instance(cell.cell(), config.instance, slot.row)?;
+        }
+        Ok(())
+    }
+}
+
+/// A satisfying [`RangeBitsCircuit`] case
+#[must_use]
+pub fn range_bits_case() -> Case<RangeBitsCircuit> {
+    let instance = RangeBitsInstance {
+        value: Fp::from(173),
+        bit: Fp::from(1),
+    };
+    Case {
+        name: "range-bits",
+        k: 9,
+        circuit: RangeBitsCircuit {
+            value: Value::known(instance.value),
+            bit: Value::known(true),
+        },
+        instances: instance.to_instances(),
+    }
+}
+
+/// Public inputs of [`PointAddCircuit`]
+#[derive(Clone, Debug, PartialEq, PublicInputs)]
+pub struct PointInstance {
+    /// x-coordinate of the sum
+    pub x: Fp,
+    /// y-coordinate of the sum
+    pub y: Fp,
+}
+
+/// Adds two witnessed Pallas points and exposes the sum
+#[derive(Clone, Debug, Default)]
+pub struct PointAddCircuit {
+    /// First summand
+    pub p: Value<pallas::Affine>,
+    /// Second summand
+    pub q: Value<pallas::Affine>,
+}
+
+/// Columns of [`PointAddCircuit`]
+#[derive(Clone, Debug)]
+pub struct PointAddConfig {
+    ecc: EccConfig,
+    instance: Column<Instance>,
+}
+
+impl Circuit<Fp> for PointAddCircuit {
+    type Config = PointAddConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> PointAddConfig {
+        let columns = [
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+        ];
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        PointAddConfig {
+            ecc: EccChip::<pallas::Affine>::configure(meta, columns),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: PointAddConfig,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let ecc = EccChip::<pallas::Affine>::construct(config.ecc);
+        let sum = ecc.assign(layouter.namespace(|| "p + q"), (self.p, self.q))?;
+
+        for (cell, name) in [(sum.x, "x"), (sum.y, "y")] {
+            let slot = PointInstance::slot(name).ok_or(Error::Synthesis)?;
+            layouter.constrain_instance(cell.cell(), config.instance, slot.row)?;
+        }
+        Ok(())
+    }
+}
+
+/// A satisfying [`PointAddCircuit`] case
+///
+/// # Panics
+///
+/// Panics if the chosen multiples of the generator sum to the identity.
+#[must_use]
+pub fn point_add_case() -> Case<PointAddCircuit> {
+    let generator = pallas::Affine::generator();
+    let p = (generator * pallas::Scalar::from(3)).to_affine();
+    let q = (generator * pallas::Scalar::from(5)).to_affine();
+    let sum = (p + q).to_affine();
+    let coordinates = sum.coordinates().unwrap();
+    let instance = PointInstance {
+        x: *coordinates.x(),
+        y: *coordinates.y(),
+    };
+
+    Case {
+        name: "point-add",
+        k: 5,
+        circuit: PointAddCircuit {
+            p: Value::known(p),
+            q: Value::known(q),
+        },
+        instances: instance.to_instances(),
+    }
+}
+
+/// Public inputs of [`MerkleSwapCircuit`]
+#[derive(Clone, Debug, PartialEq, PublicInputs)]
+pub struct MerkleSwapInstance {
+    /// Left input of the next hash
+    pub left: Fp,
+    /// Right input of the next hash
+    pub right: Fp,
+}
+
+/// One Merkle path level: orders a node and its sibling by a direction bit
+///
+/// Replaces the DCI gate that only checked `output = left + right`, which
+/// holds for either order and for any direction value. All cells are raw
+/// witnesses so the known-bad catalogue can replay forged assignments.
+#[derive(Clone, Debug, Default)]
+pub struct MerkleSwapCircuit {
+    /// Node on the path
+    pub node: Value<Fp>,
+    /// Sibling of `node`
+    pub sibling: Value<Fp>,
+    /// 1 if `node` is the right child
+    pub direction: Value<Fp>,
+    /// Claimed left child
+    pub left: Value<Fp>,
+    /// Claimed right child
+    pub right: Value<Fp>,
+}
+
+impl MerkleSwapCircuit {
+    /// Honest witness for `node` and `sibling`
+    #[must_use]
+    pub fn new(node: Fp, sibling: Fp, is_right: bool) -> Self {
+        let (left, right) = if is_right {
+            (sibling, node)
+        } else {
+            (node, sibling)
+        };
+        Self {
+            node: Value::known(node),
+            sibling: Value::known(sibling),
+            direction: Value::known(Fp::from(u64::from(is_right))),
+            left: Value::known(left),
+            right: Value::known(right),
+        }
+    }
+}
+
+/// Columns of [`MerkleSwapCircuit`]
+#[derive(Clone, Debug)]
+pub struct MerkleSwapConfig {
+    /// Node, sibling, direction, left, right
+    advice: [Column<Advice>; 5],
+    q_swap: Selector,
+    instance: Column<Instance>,
+}
+
+impl Circuit<Fp> for MerkleSwapCircuit {
+    type Config = MerkleSwapConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> MerkleSwapConfig {
+        let advice = [(); 5].map(|()| meta.advice_column());
+        let q_swap = meta.selector();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        for column in &advice[3..] {
+            meta.enable_equality(*column);
+        }
+
+        meta.create_gate("merkle swap", |meta| {
+            let q = meta.query_selector(q_swap);
+            let [node, sibling, direction, left, right] =
+                advice.map(|column| meta.query_advice(column, Rotation::cur()));
+            let one = Expression::Constant(Fp::ONE);
+
+            vec![
+                (
+                    "boolean direction",
+                    q.clone() * direction.clone() * (one - direction.clone()),
+                ),
+                (
+                    "left child",
+                    q.clone()
+                        * (left
+                            - (node.clone()
+                                + direction.clone() * (sibling.clone() - node.clone()))),
+                ),
+                (
+                    "right child",
+                    q * (right - (sibling.clone() + direction * (node - sibling))),
+                ),
+            ]
+        });
+
+        MerkleSwapConfig {
+            advice,
+            q_swap,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: MerkleSwapConfig,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let cells = layouter.assign_region(
+            || "merkle swap",
+            |mut region| {
+                config.q_swap.enable(&mut region, 0)?;
+                let values = [
+                    ("node", self.node),
+                    ("sibling", self.sibling),
+                    ("direction", self.direction),
+                    ("left", self.left),
+                    ("right", self.right),
+                ];
+                let mut cells = Vec::with_capacity(values.len());
+                for ((name, value), column) in values.into_iter().zip(config.advice) {
+                    cells.push(region.assign_advice(|| name, column, 0, || value)?);
+                }
+                Ok(cells)
+            },
+        )?;
+
+        for (cell, name) in cells[3..].iter().zip(["left", "right"]) {
+            let slot = MerkleSwapInstance::slot(name).ok_or(Error::Synthesis)?;
+            layouter.constrain_instance(cell.cell(), config.instance, slot.row)?;
+        }
+        Ok(())
+    }
+}
+
+/// A satisfying [`MerkleSwapCircuit`] case
+#[must_use]
+pub fn merkle_swap_case() -> Case<MerkleSwapCircuit> {
+    let (node, sibling) = (Fp::from(3), Fp::from(5));
+    let instance = MerkleSwapInstance {
+        left: sibling,
+        right: node,
+    };
+    Case {
+        name: "merkle-swap",
+        k: 4,
+        circuit: MerkleSwapCircuit::new(node, sibling, true),
+        instances: instance.to_instances(),
+    }
+}
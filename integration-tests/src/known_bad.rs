@@ -0,0 +1,270 @@
+//! Catalogue of witnesses that once broke soundness
+//!
+//! `known-bad.json` records every unsound witness found so far, and the
+//! constraints that must reject it. Replaying the catalogue checks both that
+//! the witness is still rejected and that the right constraint rejects it, so
+//! an unrelated failure cannot hide a regression. When a soundness bug is
+//! fixed, add its witness to the catalogue in the same change.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use ff::{Field, PrimeField};
+use halo2_proofs::{circuit::Value, dev::MockProver, plonk::Circuit};
+use pasta_curves::Fp;
+use serde::Deserialize;
+use zk_proof_core::{Error, Result};
+
+use crate::circuits::{MerkleSwapCircuit, RangeBitsCircuit};
+
+/// The catalogue shipped with this crate
+pub const CATALOGUE: &str = include_str!("../known-bad.json");
+
+/// A witness that a circuit must reject
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KnownBadWitness {
+    /// Unique name of the finding
+    pub id: String,
+    /// Circuit the witness targets
+    pub circuit: String,
+    /// What the witness exploited
+    pub description: String,
+    /// Circuit size parameter
+    pub k: u32,
+    /// Raw cell values by name, as decimal field elements
+    pub witness: BTreeMap<String, String>,
+    /// Instance columns, as decimal field elements
+    pub instances: Vec<Vec<String>>,
+    /// Text each expected `MockProver` failure must contain
+    pub violates: Vec<String>,
+}
+
+/// Parse the shipped catalogue
+///
+/// # Errors
+///
+/// Returns [`Error::Encoding`] if the catalogue is malformed or repeats an id.
+pub fn catalogue() -> Result<Vec<KnownBadWitness>> {
+    parse(CATALOGUE)
+}
+
+/// Parse a catalogue
+///
+/// # Errors
+///
+/// Returns [`Error::Encoding`] if `json` is malformed or repeats an id.
+pub fn parse(json: &str) -> Result<Vec<KnownBadWitness>> {
+    let entries: Vec<KnownBadWitness> = serde_json::from_str(json)
+        .map_err(|err| Error::Encoding(format!("known-bad catalogue: {err}")))?;
+
+    let mut ids = BTreeSet::new();
+    for entry in &entries {
+        if !ids.insert(entry.id.as_str()) {
+            return Err(Error::Encoding(format!(
+                "known-bad catalogue: duplicate id {}",
+                entry.id
+            )));
+        }
+    }
+    Ok(entries)
+}
+
+/// Parse a decimal field element, with an optional leading `-`
+///
+/// # Errors
+///
+/// Returns [`Error::Encoding`] if `value` is not a decimal number.
+pub fn parse_field(value: &str) -> Result<Fp> {
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, value),
+    };
+    let parsed = Fp::from_str_vartime(digits)
+        .ok_or_else(|| Error::Encoding(format!("invalid field element {value:?}")))?;
+    Ok(if negative { -parsed } else { parsed })
+}
+
+impl KnownBadWitness {
+    /// Check that the target circuit still rejects the witness
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Verification`] if the witness is accepted or is
+    /// rejected without violating every expected constraint, and
+    /// [`Error::Encoding`] if the entry does not fit its circuit.
+    pub fn replay(&self) -> Result<()> {
+        match self.circuit.as_str() {
+            "merkle-swap" => {
+                self.expect_cells(&["node", "sibling", "direction", "left", "right"])?;
+                self.check(&MerkleSwapCircuit {
+                    node: self.cell("node")?,
+                    sibling: self.cell("sibling")?,
+                    direction: self.cell("direction")?,
+                    left: self.cell("left")?,
+                    right: self.cell("right")?,
+                })
+            }
+            "range-bits" => {
+                self.expect_cells(&["value", "bit"])?;
+                self.check(&RangeBitsCircuit {
+                    value: self.cell("value")?,
+                    bit: Value::known(self.bit("bit")?),
+                })
+            }
+            other => Err(Error::Encoding(format!(
+                "{}: unknown circuit {other:?}",
+                self.id
+            ))),
+        }
+    }
+
+    fn check<C: Circuit<Fp>>(&self, circuit: &C) -> Result<()> {
+        let instances = self
+            .instances
+            .iter()
+            .map(|column| column.iter().map(|value| parse_field(value)).collect())
+            .collect::<Result<Vec<Vec<Fp>>>>()?;
+
+        let Err(failures) = MockProver::run(self.k, circuit, instances)?.verify() else {
+            return Err(Error::Verification(format!(
+                "{}: witness accepted",
+                self.id
+            )));
+        };
+
+        let failures: Vec<String> = failures.iter().map(ToString::to_string).collect();
+        for expected in &self.violates {
+            if !failures
+                .iter()
+                .any(|failure| failure.contains(expected.as_str()))
+            {
+                return Err(Error::Verification(format!(
+                    "{}: rejected, but not by {expected}: {failures:?}",
+                    self.id
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reject typos that would leave a cell unassigned
+    fn expect_cells(&self, names: &[&str]) -> Result<()> {
+        let found: BTreeSet<&str> = self.witness.keys().map(String::as_str).collect();
+        let expected: BTreeSet<&str> = names.iter().copied().collect();
+        if found == expected {
+            Ok(())
+        } else {
+            Err(Error::Encoding(format!(
+                "{}: {} expects cells {expected:?}, found {found:?}",
+                self.id, self.circuit
+            )))
+        }
+    }
+
+    fn field(&self, name: &str) -> Result<Fp> {
+        let value = self
+            .witness
+            .get(name)
+            .ok_or_else(|| Error::Encoding(format!("{}: missing cell {name}", self.id)))?;
+        parse_field(value)
+    }
+
+    fn cell(&self, name: &str) -> Result<Value<Fp>> {
+        self.field(name).map(Value::known)
+    }
+
+    /// Cells the circuit takes as `bool` cannot hold a forged value
+    fn bit(&self, name: &str) -> Result<bool> {
+        match self.field(name)? {
+            value if value == Fp::ZERO => Ok(false),
+            value if value == Fp::ONE => Ok(true),
+            _ => Err(Error::Encoding(format!(
+                "{}: {name} is a bool in {}",
+                self.id, self.circuit
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(witness: &[(&str, &str)]) -> KnownBadWitness {
+        KnownBadWitness {
+            id: "test".into(),
+            circuit: "merkle-swap".into(),
+            description: String::new(),
+            k: 4,
+            witness: witness
+                .iter()
+                .map(|(name, value)| ((*name).to_owned(), (*value).to_owned()))
+                .collect(),
+            instances: vec![vec!["5".into(), "3".into()]],
+            violates: vec!["'left child'".into()],
+        }
+    }
+
+    const HONEST: [(&str, &str); 5] = [
+        ("node", "3"),
+        ("sibling", "5"),
+        ("direction", "1"),
+        ("left", "5"),
+        ("right", "3"),
+    ];
+
+    const DUPLICATE: &str = r#"{
+        "id": "dup", "circuit": "range-bits", "description": "", "k": 9,
+        "witness": {}, "instances": [], "violates": []
+    }"#;
+
+    #[test]
+    fn test_parse_field() {
+        assert_eq!(parse_field("42").unwrap(), Fp::from(42));
+        assert_eq!(parse_field("-1").unwrap(), -Fp::ONE);
+        assert_eq!(parse_field("0").unwrap(), Fp::ZERO);
+        assert!(parse_field("0x10").is_err());
+        assert!(parse_field("").is_err());
+    }
+
+    #[test]
+    fn test_accepted_witness_fails_replay() {
+        let err = entry(&HONEST).replay().unwrap_err();
+        assert!(matches!(err, Error::Verification(ref msg) if msg.contains("accepted")));
+    }
+
+    #[test]
+    fn test_wrong_constraint_fails_replay() {
+        let mut bad = HONEST;
+        bad[2].1 = "2";
+        bad[3].1 = "7";
+        bad[4].1 = "1";
+        let mut entry = entry(&bad);
+        entry.instances = vec![vec!["7".into(), "1".into()]];
+        let err = entry.replay().unwrap_err();
+        assert!(matches!(err, Error::Verification(ref msg) if msg.contains("not by")));
+    }
+
+    #[test]
+    fn test_malformed_entries_rejected() {
+        assert!(matches!(
+            entry(&HONEST[..4]).replay(),
+            Err(Error::Encoding(_))
+        ));
+
+        let mut unknown = entry(&HONEST);
+        unknown.circuit = "nope".into();
+        assert!(matches!(unknown.replay(), Err(Error::Encoding(_))));
+
+        let json = format!("[{DUPLICATE}, {DUPLICATE}]");
+        assert!(matches!(parse(&json), Err(Error::Encoding(ref msg)) if msg.contains("duplicate")));
+    }
+
+    #[test]
+    fn test_catalogue_parses() {
+        let entries = catalogue().unwrap();
+        assert!(entries
+            .iter()
+            .any(|entry| entry.id == "merkle-add-gate-bypass"));
+    }
+}
@@ -0,0 +1,31 @@
+//! End-to-end tests for the ZK proof system
+//!
+//! Runs every registered circuit through the whole pipeline with real
+//! parameters instead of `MockProver`: params, keygen, proving, envelope
+//! serialization, single and batch verification, and the FFI entry points.
+//! Add a circuit to [`registry`] to cover it.
+//!
+//! Witnesses that once broke soundness are kept in `known-bad.json` and
+//! replayed by [`known_bad`] so a fixed bug cannot quietly come back.
+
+#![warn(missing_docs)]
+#![warn(clippy::all)]
+#![warn(clippy::pedantic)]
+
+pub mod circuits;
+pub mod known_bad;
+pub mod perf;
+pub mod pipeline;
+
+pub use known_bad::KnownBadWitness;
+pub use pipeline::{Case, Pipeline, PipelineReport};
+
+/// Every circuit the pipeline tests run
+#[must_use]
+pub fn registry() -> Vec<Box<dyn Pipeline>> {
+    vec![
+        Box::new(circuits::range_bits_case()),
+        Box::new(circuits::point_add_case()),
+        Box::new(circuits::merkle_swap_case()),
+    ]
+}
@@ -0,0 +1,231 @@
+//! Proving and verification timing baselines
+//!
+//! [`measure`] runs every registered circuit through the pipeline several
+//! times and keeps the median prove and verify times. A [`Baseline`] is
+//! stored as JSON and later compared against a fresh measurement; any phase
+//! that slowed down by more than the threshold is reported as a
+//! [`Regression`]. Baselines only make sense on the machine that recorded
+//! them, so none are checked in.
+
+use std::{collections::BTreeMap, fmt, fs, path::Path, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use zk_proof_core::{Error, Result};
+
+use crate::Pipeline;
+
+/// Default number of pipeline runs per circuit
+pub const DEFAULT_SAMPLES: usize = 5;
+
+/// Default allowed slowdown before a phase counts as a regression
+pub const DEFAULT_THRESHOLD: f64 = 0.2;
+
+/// Median timings of one circuit
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Timing {
+    /// Median proving time in milliseconds
+    pub prove_ms: f64,
+    /// Median verification time in milliseconds
+    pub verify_ms: f64,
+}
+
+/// Timings of every measured circuit
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    /// Pipeline runs behind each median
+    pub samples: usize,
+    /// Timings by circuit name
+    pub circuits: BTreeMap<String, Timing>,
+}
+
+/// A phase that slowed down past the threshold
+#[derive(Clone, Debug, PartialEq)]
+pub struct Regression {
+    /// Circuit name
+    pub circuit: String,
+    /// `"prove"` or `"verify"`
+    pub phase: &'static str,
+    /// Baseline time in milliseconds
+    pub baseline_ms: f64,
+    /// Measured time in milliseconds
+    pub current_ms: f64,
+}
+
+impl fmt::Display for Regression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}: {:.2} ms -> {:.2} ms ({:+.1}%)",
+            self.circuit,
+            self.phase,
+            self.baseline_ms,
+            self.current_ms,
+            (self.current_ms / self.baseline_ms - 1.0) * 100.0
+        )
+    }
+}
+
+/// Time every circuit in `registry`
+///
+/// # Errors
+///
+/// Returns the first pipeline failure, or [`Error::Other`] if `samples` is 0.
+pub fn measure(registry: &[Box<dyn Pipeline>], samples: usize) -> Result<Baseline> {
+    if samples == 0 {
+        return Err(Error::Other("at least one sample is required".into()));
+    }
+
+    let mut circuits = BTreeMap::new();
+    for case in registry {
+        let mut prove = Vec::with_capacity(samples);
+        let mut verify = Vec::with_capacity(samples);
+        for _ in 0..samples {
+            let report = case.run()?;
+            prove.push(report.prove_time);
+            verify.push(report.verify_time);
+        }
+        circuits.insert(
+            case.name().to_owned(),
+            Timing {
+                prove_ms: median_ms(&mut prove),
+                verify_ms: median_ms(&mut verify),
+            },
+        );
+    }
+    Ok(Baseline { samples, circuits })
+}
+
+fn median_ms(times: &mut [Duration]) -> f64 {
+    times.sort_unstable();
+    times[times.len() / 2].as_secs_f64() * 1000.0
+}
+
+impl Baseline {
+    /// Parse a baseline from JSON
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if `json` is not a baseline.
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|err| Error::Encoding(format!("perf baseline: {err}")))
+    }
+
+    /// Encode the baseline as pretty-printed JSON
+    ///
+    /// # Panics
+    ///
+    /// Never panics: every field is plain data.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("baseline is plain data")
+    }
+
+    /// Read a baseline file
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the file cannot be read and [`Error::Encoding`]
+    /// if it is not a baseline.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    /// Write the baseline to a file, creating parent directories
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_json())?;
+        Ok(())
+    }
+
+    /// Phases of `current` slower than this baseline by more than `threshold`
+    ///
+    /// `threshold` is relative: 0.2 allows a 20% slowdown. Circuits missing
+    /// from either side are skipped.
+    #[must_use]
+    pub fn compare(&self, current: &Baseline, threshold: f64) -> Vec<Regression> {
+        let mut regressions = Vec::new();
+        for (circuit, before) in &self.circuits {
+            let Some(after) = current.circuits.get(circuit) else {
+                continue;
+            };
+            let phases = [
+                ("prove", before.prove_ms, after.prove_ms),
+                ("verify", before.verify_ms, after.verify_ms),
+            ];
+            for (phase, baseline_ms, current_ms) in phases {
+                if current_ms > baseline_ms * (1.0 + threshold) {
+                    regressions.push(Regression {
+                        circuit: circuit.clone(),
+                        phase,
+                        baseline_ms,
+                        current_ms,
+                    });
+                }
+            }
+        }
+        regressions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn baseline(prove_ms: f64, verify_ms: f64) -> Baseline {
+        Baseline {
+            samples: 3,
+            circuits: [(
+                "circuit".to_owned(),
+                Timing {
+                    prove_ms,
+                    verify_ms,
+                },
+            )]
+            .into(),
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let baseline = baseline(12.5, 3.25);
+        assert_eq!(Baseline::from_json(&baseline.to_json()).unwrap(), baseline);
+        assert!(matches!(
+            Baseline::from_json("{\"samples\": 1}"),
+            Err(Error::Encoding(_))
+        ));
+    }
+
+    #[test]
+    fn test_compare() {
+        let before = baseline(100.0, 10.0);
+        assert!(before.compare(&baseline(119.0, 5.0), 0.2).is_empty());
+
+        let regressions = before.compare(&baseline(121.0, 13.0), 0.2);
+        let phases: Vec<_> = regressions.iter().map(|r| r.phase).collect();
+        assert_eq!(phases, ["prove", "verify"]);
+        assert_eq!(
+            regressions[0].to_string(),
+            "circuit prove: 100.00 ms -> 121.00 ms (+21.0%)"
+        );
+
+        assert!(before.compare(&Baseline::default(), 0.2).is_empty());
+    }
+
+    #[test]
+    fn test_median() {
+        let mut times = [30, 10, 20].map(Duration::from_millis);
+        assert!((median_ms(&mut times) - 20.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_measure_requires_samples() {
+        assert!(measure(&crate::registry(), 0).is_err());
+    }
+}
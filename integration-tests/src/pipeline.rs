@@ -0,0 +1,233 @@
+//! The end-to-end pipeline run for each registered circuit
+
+use std::{
+    fs,
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use ff::Field;
+use halo2_proofs::{
+    plonk::{
+        create_proof, keygen_pk, keygen_vk, verify_proof, BatchVerifier, Circuit, SingleVerifier,
+        VerifyingKey,
+    },
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255},
+};
+use pasta_curves::{vesta, Fp};
+use rand::rngs::OsRng;
+use zk_proof_bindings::{cgo, ffi, ErrorCode};
+use zk_proof_core::{
+    ArtifactStore, Error, ProofEnvelope, ProofMetadata, Result, ResultExt, VkHash,
+};
+use zk_proof_verifier::EnvelopeVersions;
+
+static STORE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// What a pipeline run produced
+#[derive(Clone, Debug)]
+pub struct PipelineReport {
+    /// Circuit name
+    pub name: &'static str,
+    /// Size of the raw halo2 proof
+    pub proof_len: usize,
+    /// Size of the encoded envelope
+    pub envelope_len: usize,
+    /// Time spent in `create_proof`
+    pub prove_time: Duration,
+    /// Time spent verifying one proof
+    pub verify_time: Duration,
+}
+
+/// A circuit that can be run end to end
+pub trait Pipeline {
+    /// Name used in failure messages
+    fn name(&self) -> &'static str;
+
+    /// Run the full pipeline
+    ///
+    /// # Errors
+    ///
+    /// Returns the first step that failed, with the circuit name and step as
+    /// context.
+    fn run(&self) -> Result<PipelineReport>;
+}
+
+/// A registered circuit with a satisfying witness and its public inputs
+#[derive(Debug)]
+pub struct Case<C> {
+    /// Circuit name
+    pub name: &'static str,
+    /// Circuit size parameter
+    pub k: u32,
+    /// Circuit with witness
+    pub circuit: C,
+    /// Instance columns for `circuit`
+    pub instances: Vec<Vec<Fp>>,
+}
+
+impl<C: Circuit<Fp>> Pipeline for Case<C> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn run(&self) -> Result<PipelineReport> {
+        let context = |step: &str| format!("{}: {step}", self.name);
+
+        let params = round_trip_params(self.k).with_context(|| context("params"))?;
+
+        let empty = self.circuit.without_witnesses();
+        let vk = keygen_vk(&params, &empty).with_context(|| context("keygen vk"))?;
+        let pk = keygen_pk(&params, vk.clone(), &empty).with_context(|| context("keygen pk"))?;
+
+        let instances = self.instance_slices();
+        let mut transcript = Blake2bWrite::<_, vesta::Affine, Challenge255<_>>::init(vec![]);
+        let started = Instant::now();
+        create_proof(
+            &params,
+            &pk,
+            std::slice::from_ref(&self.circuit),
+            &[&instances],
+            OsRng,
+            &mut transcript,
+        )
+        .with_context(|| context("prove"))?;
+        let prove_time = started.elapsed();
+        let proof = transcript.finalize();
+
+        let envelope = ProofEnvelope::new(&vk, proof.clone())
+            .with_metadata(ProofMetadata::now().with_circuit_version(self.name));
+        let bytes = envelope.to_bytes();
+        let decoded = EnvelopeVersions::all()
+            .read(&bytes)
+            .with_context(|| context("decode envelope"))?;
+        let opened = decoded
+            .open(&VkHash::of(&vk))
+            .with_context(|| context("open envelope"))?;
+        if opened != proof.as_slice() {
+            return Err(Error::Encoding(context("envelope changed the proof")));
+        }
+
+        let started = Instant::now();
+        verify_single(&params, &vk, &instances, opened)
+            .with_context(|| context("single verification"))?;
+        let verify_time = started.elapsed();
+        self.verify_batch(&params, &vk, opened)
+            .with_context(|| context("batch verification"))?;
+
+        let mut tampered = self.instances.clone();
+        if let Some(cell) = tampered
+            .iter_mut()
+            .flat_map(|column| column.iter_mut())
+            .next()
+        {
+            *cell += Fp::ONE;
+            let tampered: Vec<&[Fp]> = tampered.iter().map(Vec::as_slice).collect();
+            if verify_single(&params, &vk, &tampered, opened).is_ok() {
+                return Err(Error::Verification(context("accepted tampered instances")));
+            }
+        }
+
+        let keyed = zk_proof_verifier::single::SingleVerifier::new(params.clone(), vk.clone())
+            .with_instances(self.instances.clone());
+        ffi_round_trip(&bytes, opened, cgo::ZkVerifier::new(keyed))
+            .with_context(|| context("ffi"))?;
+
+        Ok(PipelineReport {
+            name: self.name,
+            proof_len: proof.len(),
+            envelope_len: bytes.len(),
+            prove_time,
+            verify_time,
+        })
+    }
+}
+
+impl<C: Circuit<Fp>> Case<C> {
+    fn instance_slices(&self) -> Vec<&[Fp]> {
+        self.instances.iter().map(Vec::as_slice).collect()
+    }
+
+    fn verify_batch(
+        &self,
+        params: &Params<vesta::Affine>,
+        vk: &VerifyingKey<vesta::Affine>,
+        proof: &[u8],
+    ) -> Result<()> {
+        let mut batch = BatchVerifier::new();
+        for _ in 0..2 {
+            batch.add_proof(vec![self.instances.clone()], proof.to_vec());
+        }
+        if batch.finalize(params, vk) {
+            Ok(())
+        } else {
+            Err(Error::Verification("batch rejected valid proofs".into()))
+        }
+    }
+}
+
+/// Generate params, store them, and load them back
+fn round_trip_params(k: u32) -> Result<Params<vesta::Affine>> {
+    let root = std::env::temp_dir().join(format!(
+        "zk-integration-{}-{}",
+        std::process::id(),
+        STORE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = store_and_load(&root, k);
+    let _ = fs::remove_dir_all(&root);
+    result
+}
+
+fn store_and_load(root: &Path, k: u32) -> Result<Params<vesta::Affine>> {
+    let store = ArtifactStore::open(root)?;
+    store.put_params(&Params::<vesta::Affine>::new(k))?;
+    store
+        .get_params(k)?
+        .ok_or_else(|| Error::Other(format!("params for k = {k} missing after store")))
+}
+
+fn verify_single(
+    params: &Params<vesta::Affine>,
+    vk: &VerifyingKey<vesta::Affine>,
+    instances: &[&[Fp]],
+    proof: &[u8],
+) -> Result<()> {
+    let mut transcript = Blake2bRead::<_, vesta::Affine, Challenge255<_>>::init(proof);
+    verify_proof(
+        params,
+        vk,
+        SingleVerifier::new(params),
+        &[instances],
+        &mut transcript,
+    )?;
+    Ok(())
+}
+
+/// Pass the envelope through the C entry point and the proof through a
+/// keyed cgo verifier handle
+///
+/// The C entry point does not check the proof yet, so for it this only
+/// covers marshalling; tighten it once it calls into the verifier.
+fn ffi_round_trip(envelope: &[u8], proof: &[u8], verifier: cgo::ZkVerifier) -> Result<()> {
+    // SAFETY: `envelope` is a live slice of `envelope.len()` bytes.
+    let status = unsafe { ffi::zk_proof_verify(envelope.as_ptr(), envelope.len()) };
+    if status != 0 {
+        return Err(Error::Verification(format!(
+            "zk_proof_verify returned {status}"
+        )));
+    }
+
+    let verifier = Box::into_raw(Box::new(verifier));
+    // SAFETY: `verifier` is a fresh handle and `proof` is a live slice.
+    let code = unsafe { cgo::zk_verifier_verify(verifier, proof.as_ptr(), proof.len()) };
+    // SAFETY: `verifier` came from `Box::into_raw` and is freed once.
+    unsafe { cgo::zk_verifier_free(verifier) };
+    if code != ErrorCode::Success as i32 {
+        return Err(Error::Verification(format!(
+            "zk_verifier_verify returned {code}"
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,15 @@
+//! Replays every known-bad witness against its circuit
+
+use zk_proof_integration_tests::known_bad::catalogue;
+
+#[test]
+fn test_known_bad_witnesses_rejected() {
+    let catalogue = catalogue().unwrap();
+    assert!(!catalogue.is_empty());
+
+    for entry in &catalogue {
+        entry
+            .replay()
+            .unwrap_or_else(|err| panic!("{} regressed: {err}", entry.id));
+    }
+}
@@ -0,0 +1,17 @@
+//! Runs every registered circuit through the full pipeline
+
+use zk_proof_integration_tests::registry;
+
+#[test]
+fn test_registered_circuits() {
+    let registry = registry();
+    assert!(!registry.is_empty());
+
+    for case in &registry {
+        let report = case
+            .run()
+            .unwrap_or_else(|err| panic!("{} failed: {err}", case.name()));
+        assert!(report.proof_len > 0);
+        assert!(report.envelope_len > report.proof_len);
+    }
+}
@@ -17,7 +17,7 @@ Write-Host "Building WASM for bindings module..." -ForegroundColor Yellow
 # Build WASM package
 Push-Location bindings
 try {
-    wasm-pack build --target web --out-dir "../$wasmDir" --release
+    wasm-pack build --target web --out-dir "../$wasmDir" --release -- --no-default-features
     Write-Host "WASM build successful!" -ForegroundColor Green
 } catch {
     Write-Host "WASM build failed: $_" -ForegroundColor Red
@@ -41,18 +41,26 @@ $htmlContent = @'
 <body>
     <h1>ZK Proof System WASM Interface</h1>
     <script type="module">
-        import init, { create_proof, verify_proof } from './wasm-artifacts/zk_proof_bindings.js';
+        import init, { WasmProver } from './wasm-artifacts/zk_proof_bindings.js';
         
         async function run() {
             await init();
             
-            // Test proof creation
-            const input = new Uint8Array([1, 2, 3, 4]);
-            const proof = create_proof(input);
+            // Params and keys are generated once per prover
+            const prover = new WasmProver(11);
+            
+            // Test proof creation; real callers pass their note's tree path
+            prover.buildDciWitness({
+                secretKey: new Uint8Array(32),
+                balance: 100n,
+                leafIndex: 0,
+                siblings: Array.from({ length: 20 }, () => new Uint8Array(32)),
+            });
+            const proof = await prover.prove();
             console.log('Proof created:', proof);
             
-            // Test verification
-            const isValid = verify_proof(proof);
+            // Test verification against the root and nullifier
+            const isValid = prover.verify(proof, proof.slice(0, 64));
             console.log('Proof valid:', isValid);
         }
         
@@ -0,0 +1,571 @@
+//! Aggregating finished proofs into one
+//!
+//! An [`Aggregator`] collects proofs of one circuit over the curve `C`,
+//! checking each with a [`SingleVerifier`] as it arrives.
+//! [`Aggregator::aggregate`] folds their leading commitments into an
+//! [`Accumulator`] and proves a [`CommitmentFolder`] performing the same
+//! fold. That circuit runs over the base field of `C`, so it is proven with
+//! params of the other Pasta curve `O`: proofs made with Vesta params are
+//! aggregated with Pallas params and vice versa.
+//!
+//! The folding circuit checks none of the inner proofs, so an
+//! [`AggregatedProof`] carries them with their instances and the hash of
+//! the verifying key they were checked against. [`AggregatedProof::verify`]
+//! checks every inner proof natively, then the outer proof, and then runs
+//! the decider, which replays the fold of the inner proofs' commitments and
+//! requires it to land on the accumulator the outer proof ends at. The
+//! outer proof only attests to the fold; it does not stand in for the
+//! inner checks.
+//!
+//! [`Aggregator::estimate`] predicts the size, rows and proving time of an
+//! aggregation without proving it, so callers can choose between, say, a
+//! depth-2 tree of 16-proof aggregations and one flat batch. Rows are
+//! extrapolated from layouts of the circuit itself, and bytes and time
+//! from an [`AggregationCalibration`].
+
+use ff::FromUniformBytes;
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    plonk::{keygen_pk, keygen_vk, Circuit},
+    poly::commitment::Params,
+};
+use zk_proof_core::{
+    circuits::RowUsage,
+    recursion::{fold_proofs, proof_commitment, recursion_instance, Accumulator, CommitmentFolder},
+    write_proof, Error, Randomness, Result, VkHash,
+};
+
+use crate::single::SingleVerifier;
+
+/// Default outer proof bytes independent of `k`
+const PROOF_BYTES_BASE: usize = 2048;
+
+/// Default outer proof bytes per unit of `k`: the two points of each inner
+/// product round
+const PROOF_BYTES_PER_K: usize = 64;
+
+/// Default keygen and proving time per row of the domain, a rough figure
+/// for a desktop CPU
+const NS_PER_ROW: u64 = 60_000;
+
+/// Figures [`Aggregator::estimate_with`] extrapolates from
+///
+/// [`AggregationCalibration::measure`] fills in the rows from the circuit.
+/// The proof size and time default to rough figures; the `recursion_depth`
+/// benchmark in `zk-proof-core` reports both for the host it runs on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AggregationCalibration {
+    /// Rows of the aggregation circuit besides those of each proof
+    pub base_rows: usize,
+    /// Rows each folded proof adds
+    pub rows_per_proof: usize,
+    /// Rows reserved at the end of every column for blinding
+    pub blinding_rows: usize,
+    /// Outer proof bytes independent of `k`
+    pub proof_bytes_base: usize,
+    /// Outer proof bytes per unit of `k`
+    pub proof_bytes_per_k: usize,
+    /// Keygen and proving time per row of the domain, in nanoseconds
+    pub ns_per_row: u64,
+}
+
+impl AggregationCalibration {
+    /// Lay out the aggregation circuit over `C` for one and two proofs and
+    /// fit its rows, with the default size and time figures
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`RowUsage::measure`].
+    pub fn measure<C: CurveAffine>() -> Result<Self> {
+        let one = measure_rows::<C>(1)?;
+        let two = measure_rows::<C>(2)?;
+        let rows_per_proof = two.used_rows.saturating_sub(one.used_rows);
+        Ok(Self {
+            base_rows: one.used_rows.saturating_sub(rows_per_proof),
+            rows_per_proof,
+            blinding_rows: one.blinding_rows,
+            proof_bytes_base: PROOF_BYTES_BASE,
+            proof_bytes_per_k: PROOF_BYTES_PER_K,
+            ns_per_row: NS_PER_ROW,
+        })
+    }
+
+    /// Replace the proof size figures
+    #[must_use]
+    pub fn with_proof_bytes(mut self, base: usize, per_k: usize) -> Self {
+        self.proof_bytes_base = base;
+        self.proof_bytes_per_k = per_k;
+        self
+    }
+
+    /// Replace the time figure
+    #[must_use]
+    pub fn with_ns_per_row(mut self, ns_per_row: u64) -> Self {
+        self.ns_per_row = ns_per_row;
+        self
+    }
+
+    /// Rows taken by folding `num_proofs` proofs
+    #[must_use]
+    pub fn rows(&self, num_proofs: usize) -> usize {
+        self.base_rows + self.rows_per_proof * num_proofs
+    }
+
+    /// Most proofs one aggregation with params of size `k` can fold
+    #[must_use]
+    pub fn max_proofs(&self, k: u32) -> usize {
+        let usable = (1usize << k).saturating_sub(self.blinding_rows + 1);
+        usable.saturating_sub(self.base_rows) / self.rows_per_proof.max(1)
+    }
+}
+
+/// Predicted cost of one aggregation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AggregationEstimate {
+    /// Bytes of the outer proof
+    pub proof_size: usize,
+    /// Rows the aggregation circuit assigns
+    pub constraints: usize,
+    /// Keygen and proving time in milliseconds
+    pub expected_ms: u64,
+}
+
+/// Row usage of the aggregation circuit over `C` folding `num_proofs`
+fn measure_rows<C: CurveAffine>(num_proofs: usize) -> Result<RowUsage> {
+    let circuit = CommitmentFolder::<C>::new(Accumulator::new(), vec![Vec::new(); num_proofs])
+        .without_witnesses();
+    RowUsage::measure(&circuit)
+}
+
+/// Collects checked proofs of one circuit and aggregates them
+#[derive(Debug)]
+pub struct Aggregator<'params, C: CurveAffine, O: CurveAffine> {
+    inner: SingleVerifier<C>,
+    params: &'params Params<O>,
+    instances: Vec<Vec<Vec<C::Scalar>>>,
+    proofs: Vec<Vec<u8>>,
+}
+
+impl<'params, C, O> Aggregator<'params, C, O>
+where
+    C: CurveAffine,
+    C::Scalar: FromUniformBytes<64>,
+    C::Base: FromUniformBytes<64>,
+    O: CurveAffine<ScalarExt = C::Base>,
+{
+    /// Aggregator for proofs `inner` accepts, proving with `params`
+    ///
+    /// `params` must have enough rows for the number of proofs aggregated;
+    /// one proof takes `k = 11` and every doubling about one more.
+    /// [`Aggregator::max_proofs`] gives the exact limit.
+    #[must_use]
+    pub fn new(inner: SingleVerifier<C>, params: &'params Params<O>) -> Self {
+        Self {
+            inner,
+            params,
+            instances: Vec::new(),
+            proofs: Vec::new(),
+        }
+    }
+
+    /// Check `proof` against `instances` and queue it for aggregation
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`SingleVerifier::check`] for a proof that does
+    /// not verify, and [`Error::Encoding`] for one that does not start with
+    /// a commitment to fold.
+    pub fn add(&mut self, instances: &[Vec<C::Scalar>], proof: Vec<u8>) -> Result<()> {
+        self.inner.check(instances, &proof)?;
+        if proof_commitment::<C>(&proof).is_none() {
+            return Err(Error::Encoding(
+                "proof has no valid leading commitment".into(),
+            ));
+        }
+        self.instances.push(instances.to_vec());
+        self.proofs.push(proof);
+        Ok(())
+    }
+
+    /// Predict aggregating `num_proofs` proofs with params of size `k`
+    ///
+    /// Lays out the circuit for one and two proofs only, so it is cheap next
+    /// to proving.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Aggregator::estimate_with`] and
+    /// [`AggregationCalibration::measure`].
+    pub fn estimate(k: u32, num_proofs: usize) -> Result<AggregationEstimate> {
+        Self::estimate_with(&AggregationCalibration::measure::<C>()?, k, num_proofs)
+    }
+
+    /// [`Aggregator::estimate`] from `calibration`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Other`] if `num_proofs` is zero and
+    /// [`Error::RowBudgetExceeded`] if the proofs do not fit at `k`, as
+    /// [`Aggregator::aggregate`] would.
+    pub fn estimate_with(
+        calibration: &AggregationCalibration,
+        k: u32,
+        num_proofs: usize,
+    ) -> Result<AggregationEstimate> {
+        if num_proofs == 0 {
+            return Err(Error::Other("no proofs to aggregate".into()));
+        }
+        let constraints = calibration.rows(num_proofs);
+        if num_proofs > calibration.max_proofs(k) {
+            return Err(Error::RowBudgetExceeded {
+                rows: constraints,
+                max_k: k,
+            });
+        }
+        Ok(AggregationEstimate {
+            proof_size: calibration.proof_bytes_base + calibration.proof_bytes_per_k * k as usize,
+            constraints,
+            expected_ms: calibration.ns_per_row.saturating_mul(1 << k) / 1_000_000,
+        })
+    }
+
+    /// Most proofs one aggregation with this aggregator's params can fold
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`AggregationCalibration::measure`].
+    pub fn max_proofs(&self) -> Result<usize> {
+        Ok(AggregationCalibration::measure::<C>()?.max_proofs(self.params.k()))
+    }
+
+    /// Number of proofs queued
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.proofs.len()
+    }
+
+    /// Whether no proofs are queued
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.proofs.is_empty()
+    }
+
+    /// Fold every queued proof and prove the fold
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Other`] if no proofs were added,
+    /// [`Error::RowBudgetExceeded`] if the aggregation circuit does not fit
+    /// the params, and [`Error::Plonk`] if keygen or proving fails.
+    pub fn aggregate(self, randomness: impl Randomness) -> Result<AggregatedProof<C>> {
+        if self.proofs.is_empty() {
+            return Err(Error::Other("no proofs to aggregate".into()));
+        }
+        let start = Accumulator::new();
+        let accumulator = fold_proofs(&start, &self.proofs)?;
+
+        let circuit = CommitmentFolder::new(start.clone(), self.proofs.clone());
+        let usage = RowUsage::measure(&circuit)?;
+        if usage.minimum_k > self.params.k() {
+            return Err(Error::RowBudgetExceeded {
+                rows: usage.used_rows,
+                max_k: self.params.k(),
+            });
+        }
+        let empty = circuit.without_witnesses();
+        let vk = keygen_vk(self.params, &empty)?;
+        let pk = keygen_pk(self.params, vk, &empty)?;
+
+        let instance = recursion_instance(&start, &accumulator);
+        let proof = write_proof(
+            self.params,
+            &pk,
+            &[circuit],
+            &[&[instance.as_slice()]],
+            randomness,
+            Vec::new(),
+        )?;
+        Ok(AggregatedProof {
+            vk_hash: VkHash::of(self.inner.verifying_key()),
+            instances: self.instances,
+            proofs: self.proofs,
+            accumulator,
+            proof,
+        })
+    }
+}
+
+/// Batch of proofs with a proof of their fold
+///
+/// Holds everything [`AggregatedProof::verify`] needs to re-check the
+/// batch, since the outer proof attests only to the fold.
+#[derive(Clone, Debug)]
+pub struct AggregatedProof<C: CurveAffine> {
+    /// Hash of the verifying key the aggregated proofs are for
+    pub vk_hash: VkHash,
+    /// Instances of each aggregated proof, in folding order
+    pub instances: Vec<Vec<Vec<C::Scalar>>>,
+    /// Aggregated proofs, in folding order
+    pub proofs: Vec<Vec<u8>>,
+    /// Accumulator the proofs' commitments fold into from a fresh one
+    pub accumulator: Accumulator<C>,
+    /// Proof of the [`CommitmentFolder`] doing the fold
+    pub proof: Vec<u8>,
+}
+
+impl<C> AggregatedProof<C>
+where
+    C: CurveAffine,
+    C::Scalar: FromUniformBytes<64>,
+    C::Base: FromUniformBytes<64>,
+{
+    /// Number of proofs aggregated
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.proofs.len()
+    }
+
+    /// Whether no proofs were aggregated, which [`Aggregator`] never emits
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.proofs.is_empty()
+    }
+
+    /// Check every aggregated proof with `inner`, the outer proof with
+    /// `params`, then [`AggregatedProof::decide`]
+    ///
+    /// The verifying key of the folding circuit depends only on the number
+    /// of proofs, so it is regenerated here rather than trusted.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Verification`] if `inner` checks another verifying
+    /// key than [`AggregatedProof::vk_hash`] or the proofs and instances do
+    /// not pair up, the errors of [`SingleVerifier::check`] if an inner or
+    /// the outer proof does not verify, [`Error::Plonk`] if keygen fails
+    /// and the errors of [`AggregatedProof::decide`].
+    pub fn verify<O>(&self, inner: &SingleVerifier<C>, params: &Params<O>) -> Result<()>
+    where
+        O: CurveAffine<ScalarExt = C::Base>,
+    {
+        if VkHash::of(inner.verifying_key()) != self.vk_hash {
+            return Err(Error::Verification(
+                "aggregated proofs are for another verifying key".into(),
+            ));
+        }
+        if self.instances.len() != self.proofs.len() {
+            return Err(Error::Verification(format!(
+                "{} instances for {} aggregated proofs",
+                self.instances.len(),
+                self.proofs.len()
+            )));
+        }
+        for (instances, proof) in self.instances.iter().zip(&self.proofs) {
+            inner.check(instances, proof)?;
+        }
+
+        let circuit = CommitmentFolder::<C>::new(Accumulator::new(), vec![Vec::new(); self.len()])
+            .without_witnesses();
+        let vk = keygen_vk(params, &circuit)?;
+        let instance = recursion_instance(&Accumulator::new(), &self.accumulator);
+        SingleVerifier::new(params.clone(), vk).check(&[instance], &self.proof)?;
+        self.decide()
+    }
+
+    /// Replay the fold of the commitments leading
+    /// [`AggregatedProof::proofs`] natively
+    ///
+    /// This checks the fold only, not the proofs.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if a proof has no leading commitment and
+    /// [`Error::Verification`] if the fold does not produce
+    /// [`AggregatedProof::accumulator`].
+    pub fn decide(&self) -> Result<()> {
+        let replay = fold_proofs(&Accumulator::<C>::new(), &self.proofs)?;
+        let accumulator = &self.accumulator;
+        if replay.commitment != accumulator.commitment
+            || replay.challenge != accumulator.challenge
+            || replay.acc_vec != accumulator.acc_vec
+            || replay.proof_count != accumulator.proof_count
+        {
+            return Err(Error::Verification(
+                "accumulator does not match the folded commitments".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        plonk::{Advice, Column, ConstraintSystem, Error as PlonkError, Instance},
+    };
+    use pasta_curves::{pallas, vesta, Fp};
+    use zk_proof_core::DeterministicRandomness;
+
+    use super::*;
+
+    /// Exposes its witness as the only public input
+    #[derive(Default)]
+    struct Echo {
+        value: Value<Fp>,
+    }
+
+    impl Circuit<Fp> for Echo {
+        type Config = (Column<Advice>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(advice);
+            meta.enable_equality(instance);
+            (advice, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (advice, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> std::result::Result<(), PlonkError> {
+            let cell = layouter.assign_region(
+                || "value",
+                |mut region| region.assign_advice(|| "value", advice, 0, || self.value),
+            )?;
+            layouter.constrain_instance(cell.cell(), instance, 0)
+        }
+    }
+
+    /// Verifier for `Echo` and proofs exposing each of `values`
+    fn echo_proofs(values: &[u64]) -> (SingleVerifier<vesta::Affine>, Vec<Vec<u8>>) {
+        let params = Params::<vesta::Affine>::new(4);
+        let vk = keygen_vk(&params, &Echo::default()).unwrap();
+        let pk = keygen_pk(&params, vk.clone(), &Echo::default()).unwrap();
+        let rng = DeterministicRandomness::from_seed([1; 32]);
+        let proofs = values
+            .iter()
+            .map(|&value| {
+                let circuit = Echo {
+                    value: Value::known(Fp::from(value)),
+                };
+                write_proof(
+                    &params,
+                    &pk,
+                    &[circuit],
+                    &[&[&[Fp::from(value)]]],
+                    &rng,
+                    Vec::new(),
+                )
+                .unwrap()
+            })
+            .collect();
+        (SingleVerifier::new(params, vk), proofs)
+    }
+
+    #[test]
+    fn test_aggregate_and_verify() {
+        let (inner, proofs) = echo_proofs(&[3, 4]);
+        let params = Params::<pallas::Affine>::new(12);
+        let mut aggregator = Aggregator::new(inner, &params);
+
+        // A proof for another statement is turned away
+        assert!(aggregator
+            .add(&[vec![Fp::from(9)]], proofs[0].clone())
+            .is_err());
+        for (value, proof) in [3, 4].into_iter().zip(proofs) {
+            aggregator.add(&[vec![Fp::from(value)]], proof).unwrap();
+        }
+        assert_eq!(aggregator.len(), 2);
+
+        let aggregated = aggregator
+            .aggregate(DeterministicRandomness::from_seed([2; 32]))
+            .unwrap();
+        assert_eq!(aggregated.len(), 2);
+        assert_eq!(aggregated.accumulator.proof_count, 2);
+        let (inner, _) = echo_proofs(&[]);
+        aggregated.verify(&inner, &params).unwrap();
+
+        // Claiming another fold fails the outer proof
+        let mut swapped = aggregated.clone();
+        swapped.proofs.reverse();
+        swapped.instances.reverse();
+        swapped.accumulator = fold_proofs(&Accumulator::new(), &swapped.proofs).unwrap();
+        assert!(swapped.decide().is_ok());
+        assert!(swapped.verify(&inner, &params).is_err());
+
+        // Proofs that do not fold into the accumulator fail the decider
+        let mut forged = aggregated.clone();
+        forged.proofs.reverse();
+        assert!(matches!(forged.decide(), Err(Error::Verification(_))));
+
+        // A carried proof is checked against its instances and the key
+        let mut restated = aggregated.clone();
+        restated.instances[0] = vec![vec![Fp::from(9)]];
+        assert!(restated.decide().is_ok());
+        assert!(restated.verify(&inner, &params).is_err());
+        let other = SingleVerifier::new(
+            Params::<vesta::Affine>::new(5),
+            keygen_vk(&Params::new(5), &Echo::default()).unwrap(),
+        );
+        assert!(matches!(
+            aggregated.verify(&other, &params),
+            Err(Error::Verification(_))
+        ));
+    }
+
+    #[test]
+    fn test_estimate_follows_layout() {
+        type Vesta = Aggregator<'static, vesta::Affine, pallas::Affine>;
+        let calibration = AggregationCalibration::measure::<vesta::Affine>().unwrap();
+        assert!(calibration.rows_per_proof > 0);
+
+        let measured = measure_rows::<vesta::Affine>(4).unwrap().used_rows;
+        assert!(calibration.rows(4).abs_diff(measured) <= calibration.rows_per_proof / 10);
+
+        // The limits the aggregation tests run into
+        assert_eq!(calibration.max_proofs(8), 0);
+        assert!(calibration.max_proofs(12) >= 2);
+        let limit = calibration.max_proofs(14);
+        let estimate = Vesta::estimate_with(&calibration, 14, limit).unwrap();
+        assert_eq!(estimate.constraints, calibration.rows(limit));
+        assert!(matches!(
+            Vesta::estimate_with(&calibration, 14, limit + 1),
+            Err(Error::RowBudgetExceeded { max_k: 14, .. })
+        ));
+        assert!(Vesta::estimate_with(&calibration, 14, 0).is_err());
+
+        // A flat batch against a depth-2 tree of 16-proof aggregations
+        let flat = Vesta::estimate(20, 256).unwrap();
+        let node = Vesta::estimate(20, 16).unwrap();
+        assert!(node.constraints < flat.constraints);
+        assert_eq!(node.proof_size, flat.proof_size);
+    }
+
+    #[test]
+    fn test_nothing_or_too_much_to_aggregate() {
+        let params = Params::<pallas::Affine>::new(8);
+        let (inner, _) = echo_proofs(&[]);
+        assert!(matches!(
+            Aggregator::new(inner, &params).aggregate(DeterministicRandomness::from_seed([2; 32])),
+            Err(Error::Other(_))
+        ));
+
+        let (inner, proofs) = echo_proofs(&[3]);
+        let mut aggregator = Aggregator::new(inner, &params);
+        aggregator
+            .add(&[vec![Fp::from(3)]], proofs[0].clone())
+            .unwrap();
+        assert!(matches!(
+            aggregator.aggregate(DeterministicRandomness::from_seed([2; 32])),
+            Err(Error::RowBudgetExceeded { max_k: 8, .. })
+        ));
+    }
+}
@@ -0,0 +1,264 @@
+//! Audit log of verification decisions
+//!
+//! Wrapping a verifier in [`Audited`] hands every decision to an
+//! [`AuditSink`] together with the circuit, the verifying key it was checked
+//! against, how long it took and who asked. The wrapper fails closed: if the
+//! sink cannot record a decision the result is [`VerifierResult::Error`], so
+//! no proof is ever accepted without a record of it.
+
+use std::{
+    fmt,
+    io::Write,
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use zk_proof_core::{Result, VkHash};
+
+use crate::traits::{Verifier, VerifierResult};
+
+/// One verification decision
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditRecord {
+    /// Stable identifier of the circuit
+    pub circuit_id: String,
+    /// Verifying key the proof was checked against
+    pub vk_hash: VkHash,
+    /// Outcome
+    pub result: VerifierResult,
+    /// Wall-clock time spent verifying
+    pub elapsed: Duration,
+    /// Tag identifying the caller, if one was given
+    pub caller: Option<String>,
+    /// When the decision was made
+    pub at: SystemTime,
+}
+
+/// Destination for audit records
+///
+/// Implementations should be append-only and durable by the time
+/// [`AuditSink::record`] returns.
+pub trait AuditSink: Send + Sync {
+    /// Persist one record
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the record could not be stored; the decision it
+    /// describes is then reported as [`VerifierResult::Error`].
+    fn record(&self, record: &AuditRecord) -> Result<()>;
+}
+
+impl<S: AuditSink + ?Sized> AuditSink for &S {
+    fn record(&self, record: &AuditRecord) -> Result<()> {
+        (**self).record(record)
+    }
+}
+
+/// Sink writing one line per record, flushed after each
+///
+/// Lines hold tab-separated fields: milliseconds since the Unix epoch,
+/// quoted circuit id, vk hash, result, elapsed microseconds and quoted
+/// caller tag (`-` if absent).
+#[derive(Debug)]
+pub struct AuditLog<W> {
+    writer: Mutex<W>,
+}
+
+impl<W: Write + Send> AuditLog<W> {
+    /// Append records to `writer`
+    #[must_use]
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+        }
+    }
+
+    /// Unwrap the inner writer
+    #[must_use]
+    pub fn into_inner(self) -> W {
+        self.writer
+            .into_inner()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl<W: Write + Send> AuditSink for AuditLog<W> {
+    fn record(&self, record: &AuditRecord) -> Result<()> {
+        let line = format!("{}\n", Line(record));
+        let mut writer = self
+            .writer
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        writer.write_all(line.as_bytes())?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Log line of a record, without the newline
+struct Line<'a>(&'a AuditRecord);
+
+impl fmt::Display for Line<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let record = self.0;
+        let at = record
+            .at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let result = match record.result {
+            VerifierResult::Valid => "valid",
+            VerifierResult::Invalid => "invalid",
+            VerifierResult::Error => "error",
+        };
+        write!(
+            f,
+            "{at}\t{:?}\t{}\t{result}\t{}\t",
+            record.circuit_id,
+            record.vk_hash,
+            record.elapsed.as_micros()
+        )?;
+        match &record.caller {
+            Some(caller) => write!(f, "{caller:?}"),
+            None => f.write_str("-"),
+        }
+    }
+}
+
+/// Verifier reporting every decision to an [`AuditSink`]
+#[derive(Debug)]
+pub struct Audited<V, S> {
+    verifier: V,
+    sink: S,
+    circuit_id: String,
+    vk_hash: VkHash,
+}
+
+impl<V: Verifier, S: AuditSink> Audited<V, S> {
+    /// Audit `verifier`, which checks proofs of `circuit_id` under `vk_hash`
+    #[must_use]
+    pub fn new(verifier: V, sink: S, circuit_id: impl Into<String>, vk_hash: VkHash) -> Self {
+        Self {
+            verifier,
+            sink,
+            circuit_id: circuit_id.into(),
+            vk_hash,
+        }
+    }
+
+    /// Sink receiving the records
+    #[must_use]
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    /// Verify `proof` on behalf of `caller`
+    #[must_use]
+    pub fn verify_as(&self, proof: &[u8], caller: &str) -> VerifierResult {
+        self.audit(proof, Some(caller))
+    }
+
+    fn audit(&self, proof: &[u8], caller: Option<&str>) -> VerifierResult {
+        let at = SystemTime::now();
+        let started = Instant::now();
+        let result = self.verifier.verify(proof);
+        let record = AuditRecord {
+            circuit_id: self.circuit_id.clone(),
+            vk_hash: self.vk_hash,
+            result,
+            elapsed: started.elapsed(),
+            caller: caller.map(str::to_owned),
+            at,
+        };
+        match self.sink.record(&record) {
+            Ok(()) => result,
+            Err(_) => VerifierResult::Error,
+        }
+    }
+}
+
+impl<V: Verifier, S: AuditSink> Verifier for Audited<V, S> {
+    fn verify(&self, proof: &[u8]) -> VerifierResult {
+        self.audit(proof, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use zk_proof_core::Error;
+
+    use super::*;
+
+    /// Accepts every proof
+    struct Accepting;
+
+    impl Verifier for Accepting {
+        fn verify(&self, _: &[u8]) -> VerifierResult {
+            VerifierResult::Valid
+        }
+    }
+
+    #[derive(Default)]
+    struct Memory(Mutex<Vec<AuditRecord>>);
+
+    impl AuditSink for Memory {
+        fn record(&self, record: &AuditRecord) -> Result<()> {
+            self.0.lock().unwrap().push(record.clone());
+            Ok(())
+        }
+    }
+
+    struct Broken;
+
+    impl AuditSink for Broken {
+        fn record(&self, _: &AuditRecord) -> Result<()> {
+            Err(Error::Io(io::ErrorKind::StorageFull.into()))
+        }
+    }
+
+    #[test]
+    fn test_records_each_decision() {
+        let sink = Memory::default();
+        let verifier = Audited::new(Accepting, &sink, "dci", VkHash::new([1; 32]));
+
+        assert_eq!(verifier.verify(&[0; 8]), VerifierResult::Valid);
+        assert_eq!(verifier.verify_as(&[0; 8], "node-7"), VerifierResult::Valid);
+
+        let records = sink.0.lock().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].circuit_id, "dci");
+        assert_eq!(records[0].vk_hash, VkHash::new([1; 32]));
+        assert_eq!(records[0].caller, None);
+        assert_eq!(records[1].caller.as_deref(), Some("node-7"));
+    }
+
+    #[test]
+    fn test_fails_closed() {
+        let verifier = Audited::new(Accepting, Broken, "dci", VkHash::new([1; 32]));
+        assert_eq!(verifier.verify(&[0; 8]), VerifierResult::Error);
+    }
+
+    #[test]
+    fn test_log_lines() {
+        let log = AuditLog::new(Vec::new());
+        let verifier = Audited::new(Accepting, &log, "dci", VkHash::new([0xab; 32]));
+        assert_eq!(
+            verifier.verify_as(&[0; 8], "tab\there"),
+            VerifierResult::Valid
+        );
+        assert_eq!(verifier.verify(&[0; 8]), VerifierResult::Valid);
+        drop(verifier);
+
+        let text = String::from_utf8(log.into_inner()).unwrap();
+        let lines: Vec<Vec<&str>> = text.lines().map(|l| l.split('\t').collect()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].len(), 6);
+        assert_eq!(lines[0][1], "\"dci\"");
+        assert_eq!(lines[0][2], "ab".repeat(32));
+        assert_eq!(lines[0][3], "valid");
+        assert_eq!(lines[0][5], "\"tab\\there\"");
+        assert_eq!(lines[1][5], "-");
+    }
+}
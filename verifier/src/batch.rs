@@ -1,24 +1,254 @@
 //! Batch verification functionality
+//!
+//! A [`BatchVerifier`] collects proofs, possibly for several verifying keys,
+//! and checks them together. Proofs for the same key go to halo2's batch
+//! verifier, which scales the final multi-scalar multiplication of each
+//! proof by a fresh random scalar and sums them, so a chunk of N proofs
+//! costs one large MSM instead of N separate ones. A batch holding an
+//! invalid proof passes only if the random scalars cancel its error, which
+//! happens with negligible probability. Those scalars are the verifier's
+//! private coins, drawn from the OS, rather than Fiat-Shamir challenges: a
+//! prover cannot predict them, so no [`zk_proof_core::Transcript`] is needed
+//! to derive them, unlike the folding challenges of the recursion code.
+//!
+//! Each chunk holds at most `max_batch_size` proofs, which bounds the size
+//! of one combined MSM. A rejected batch does not say which proof failed;
+//! callers that need to know re-check the proofs one by one with a
+//! [`crate::single::SingleVerifier`].
 
-use crate::traits::{Verifier, VerifierResult};
+use ff::FromUniformBytes;
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    plonk::{self, VerifyingKey},
+    poly::commitment::Params,
+};
+use zk_proof_core::VkHash;
+
+use crate::traits::VerifierResult;
 
 /// Batch verifier for multiple proofs
 #[derive(Debug)]
-pub struct BatchVerifier {
-    /// Maximum batch size
+pub struct BatchVerifier<'a, C: CurveAffine> {
+    /// Maximum number of proofs combined into one check
     pub max_batch_size: usize,
+    params: &'a Params<C>,
+    groups: Vec<Group<'a, C>>,
 }
 
-impl BatchVerifier {
-    /// Create a new batch verifier
-    #[must_use] pub fn new(max_batch_size: usize) -> Self {
-        Self { max_batch_size }
-    }
+/// Proofs collected for one verifying key
+#[derive(Debug)]
+struct Group<'a, C: CurveAffine> {
+    vk_hash: VkHash,
+    vk: &'a VerifyingKey<C>,
+    proofs: Vec<(Vec<Vec<C::Scalar>>, Vec<u8>)>,
 }
 
-impl Verifier for BatchVerifier {
-    fn verify(&self, proof: &[u8]) -> VerifierResult {
-        // Implementation would go here
+impl<'a, C> BatchVerifier<'a, C>
+where
+    C: CurveAffine,
+    C::Scalar: FromUniformBytes<64>,
+{
+    /// Create a new batch verifier for proofs made with `params`
+    ///
+    /// A `max_batch_size` of zero is treated as one.
+    #[must_use]
+    pub fn new(params: &'a Params<C>, max_batch_size: usize) -> Self {
+        Self {
+            max_batch_size,
+            params,
+            groups: Vec::new(),
+        }
+    }
+
+    /// Queue `proof` of the statement `instances` for `vk`
+    pub fn add_proof(
+        &mut self,
+        vk: &'a VerifyingKey<C>,
+        instances: Vec<Vec<C::Scalar>>,
+        proof: Vec<u8>,
+    ) {
+        let vk_hash = VkHash::of(vk);
+        let index = match self.groups.iter().position(|g| g.vk_hash == vk_hash) {
+            Some(index) => index,
+            None => {
+                self.groups.push(Group {
+                    vk_hash,
+                    vk,
+                    proofs: Vec::new(),
+                });
+                self.groups.len() - 1
+            }
+        };
+        self.groups[index].proofs.push((instances, proof));
+    }
+
+    /// Number of queued proofs
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.groups.iter().map(|group| group.proofs.len()).sum()
+    }
+
+    /// Whether no proof is queued
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Verify every queued proof
+    ///
+    /// Returns [`VerifierResult::Valid`] only if all of them verify, which
+    /// includes an empty batch, and stops at the first rejected chunk.
+    #[must_use]
+    pub fn finalize(self) -> VerifierResult {
+        let chunk_size = self.max_batch_size.max(1);
+        for group in self.groups {
+            let mut proofs = group.proofs.into_iter().peekable();
+            while proofs.peek().is_some() {
+                let mut batch = plonk::BatchVerifier::new();
+                for (instances, proof) in proofs.by_ref().take(chunk_size) {
+                    batch.add_proof(vec![instances], proof);
+                }
+                if !batch.finalize(self.params, group.vk) {
+                    return VerifierResult::Invalid;
+                }
+            }
+        }
         VerifierResult::Valid
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        plonk::{
+            keygen_pk, keygen_vk, Advice, Circuit, Column, ConstraintSystem, Error as PlonkError,
+            Instance, ProvingKey,
+        },
+    };
+    use pasta_curves::{vesta, Fp};
+    use zk_proof_core::{write_proof, DeterministicRandomness};
+
+    use super::*;
+
+    /// Exposes its witness as public input row `ROW`
+    #[derive(Default)]
+    struct Echo<const ROW: usize> {
+        value: Value<Fp>,
+    }
+
+    impl<const ROW: usize> Circuit<Fp> for Echo<ROW> {
+        type Config = (Column<Advice>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(advice);
+            meta.enable_equality(instance);
+            (advice, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (advice, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> std::result::Result<(), PlonkError> {
+            let cell = layouter.assign_region(
+                || "value",
+                |mut region| region.assign_advice(|| "value", advice, 0, || self.value),
+            )?;
+            layouter.constrain_instance(cell.cell(), instance, ROW)
+        }
+    }
+
+    fn keys<const ROW: usize>(params: &Params<vesta::Affine>) -> ProvingKey<vesta::Affine> {
+        let vk = keygen_vk(params, &Echo::<ROW>::default()).unwrap();
+        keygen_pk(params, vk, &Echo::<ROW>::default()).unwrap()
+    }
+
+    fn prove<const ROW: usize>(
+        params: &Params<vesta::Affine>,
+        pk: &ProvingKey<vesta::Affine>,
+        value: u64,
+    ) -> (Vec<Vec<Fp>>, Vec<u8>) {
+        let mut column = vec![Fp::ZERO; ROW + 1];
+        column[ROW] = Fp::from(value);
+        let circuit = Echo::<ROW> {
+            value: Value::known(Fp::from(value)),
+        };
+        let proof = write_proof(
+            params,
+            pk,
+            &[circuit],
+            &[&[column.as_slice()]],
+            DeterministicRandomness::from_seed([7; 32]),
+            Vec::new(),
+        )
+        .unwrap();
+        (vec![column], proof)
+    }
+
+    #[test]
+    fn test_batches_in_chunks() {
+        let params = Params::<vesta::Affine>::new(4);
+        let pk = keys::<0>(&params);
+
+        assert_eq!(
+            BatchVerifier::new(&params, 2).finalize(),
+            VerifierResult::Valid
+        );
+
+        let mut batch = BatchVerifier::new(&params, 2);
+        for value in 1..=5 {
+            let (instances, proof) = prove::<0>(&params, &pk, value);
+            batch.add_proof(pk.get_vk(), instances, proof);
+        }
+        assert_eq!(batch.len(), 5);
+        assert_eq!(batch.finalize(), VerifierResult::Valid);
+    }
+
+    #[test]
+    fn test_rejects_one_bad_proof() {
+        let params = Params::<vesta::Affine>::new(4);
+        let pk = keys::<0>(&params);
+
+        let mut batch = BatchVerifier::new(&params, 3);
+        for value in 1..=4 {
+            let (mut instances, proof) = prove::<0>(&params, &pk, value);
+            if value == 4 {
+                instances[0][0] += Fp::ONE;
+            }
+            batch.add_proof(pk.get_vk(), instances, proof);
+        }
+        assert_eq!(batch.finalize(), VerifierResult::Invalid);
+    }
+
+    #[test]
+    fn test_groups_by_key() {
+        let params = Params::<vesta::Affine>::new(4);
+        let first = keys::<0>(&params);
+        let second = keys::<1>(&params);
+
+        let mut batch = BatchVerifier::new(&params, 8);
+        for value in 1..=2 {
+            let (instances, proof) = prove::<0>(&params, &first, value);
+            batch.add_proof(first.get_vk(), instances, proof);
+            let (instances, proof) = prove::<1>(&params, &second, value);
+            batch.add_proof(second.get_vk(), instances, proof);
+        }
+        assert_eq!(batch.groups.len(), 2);
+        assert_eq!(batch.finalize(), VerifierResult::Valid);
+
+        // A proof filed under the wrong key fails the batch
+        let mut batch = BatchVerifier::new(&params, 8);
+        let (instances, proof) = prove::<1>(&params, &second, 3);
+        batch.add_proof(first.get_vk(), instances, proof);
+        assert_eq!(batch.finalize(), VerifierResult::Invalid);
+    }
+}
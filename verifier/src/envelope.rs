@@ -0,0 +1,137 @@
+//! Envelope version negotiation
+//!
+//! A verifier advertises the envelope versions it accepts. Provers pick the
+//! newest version both sides support, and envelopes issued before an upgrade
+//! are migrated to the verifier's preferred version on receipt, so rolling
+//! out a new encoding never invalidates proofs already in flight.
+
+use std::ops::RangeInclusive;
+
+use zk_proof_core::{
+    proof::{self, ProofEnvelope},
+    Error, Result,
+};
+
+/// Envelope versions accepted by a verifier
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvelopeVersions {
+    range: RangeInclusive<u16>,
+}
+
+impl EnvelopeVersions {
+    /// Accept every version this build can read
+    #[must_use]
+    pub fn all() -> Self {
+        Self {
+            range: proof::supported_versions(),
+        }
+    }
+
+    /// Accept only `range`, clamped to what this build can read
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if no version in `range` is readable.
+    pub fn new(range: RangeInclusive<u16>) -> Result<Self> {
+        let supported = proof::supported_versions();
+        let low = (*range.start()).max(*supported.start());
+        let high = (*range.end()).min(*supported.end());
+        if low > high {
+            return Err(Error::Encoding(format!(
+                "no supported envelope version in {}..={}",
+                range.start(),
+                range.end()
+            )));
+        }
+        Ok(Self { range: low..=high })
+    }
+
+    /// Accepted versions, for advertising to provers
+    #[must_use]
+    pub fn range(&self) -> &RangeInclusive<u16> {
+        &self.range
+    }
+
+    /// Version a prover supporting `prover` should emit
+    #[must_use]
+    pub fn negotiate(&self, prover: &RangeInclusive<u16>) -> Option<u16> {
+        proof::negotiate_version(&self.range, prover)
+    }
+
+    /// Decode an envelope, migrating older encodings to the newest accepted
+    /// version first
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Encoding`] if the envelope's version is not accepted
+    /// or it fails to decode.
+    pub fn read(&self, bytes: &[u8]) -> Result<ProofEnvelope> {
+        let version = ProofEnvelope::version_of(bytes)?;
+        if !self.range.contains(&version) {
+            return Err(Error::Encoding(format!(
+                "envelope version {version} not accepted, expected {}..={}",
+                self.range.start(),
+                self.range.end()
+            )));
+        }
+
+        let target = *self.range.end();
+        if version == target {
+            ProofEnvelope::from_bytes(bytes)
+        } else {
+            ProofEnvelope::from_bytes(&proof::migrate(bytes, version, target)?)
+        }
+    }
+}
+
+impl Default for EnvelopeVersions {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zk_proof_core::{ProofMetadata, VkHash, ENVELOPE_VERSION};
+
+    use super::*;
+
+    fn envelope() -> ProofEnvelope {
+        ProofEnvelope {
+            vk_hash: VkHash::new([7; 32]),
+            proof: vec![1, 2, 3],
+            metadata: ProofMetadata::now(),
+        }
+    }
+
+    #[test]
+    fn test_reads_issued_versions() {
+        let versions = EnvelopeVersions::all();
+        let env = envelope();
+
+        let old = env.to_bytes_versioned(1).unwrap();
+        let read = versions.read(&old).unwrap();
+        assert_eq!(read.proof, env.proof);
+        assert_eq!(read.vk_hash, env.vk_hash);
+
+        assert_eq!(versions.read(&env.to_bytes()).unwrap(), env);
+    }
+
+    #[test]
+    fn test_rejects_unaccepted_version() {
+        let versions = EnvelopeVersions::new(2..=ENVELOPE_VERSION).unwrap();
+        let old = envelope().to_bytes_versioned(1).unwrap();
+        assert!(versions.read(&old).is_err());
+    }
+
+    #[test]
+    fn test_negotiation() {
+        let versions = EnvelopeVersions::new(1..=1).unwrap();
+        assert_eq!(versions.negotiate(&(1..=ENVELOPE_VERSION)), Some(1));
+        assert_eq!(
+            EnvelopeVersions::all().negotiate(&(1..=u16::MAX)),
+            Some(ENVELOPE_VERSION)
+        );
+        assert!(EnvelopeVersions::new(100..=200).is_err());
+    }
+}
@@ -1,20 +1,42 @@
 //! Verification module for the ZK proof system
 //!
 //! This module provides verification functionality for zero-knowledge proofs.
+//!
+//! There is no on-chain verifier: proofs are IPA openings over the Pasta
+//! curves, which the EVM has no precompiles for.
 
 #![warn(missing_docs)]
 #![warn(clippy::all)]
 #![warn(clippy::pedantic)]
 
+#[cfg(feature = "aggregate")]
+pub mod aggregate;
+pub mod audit;
 pub mod batch;
+pub mod envelope;
+pub mod limit;
+pub mod policy;
+pub mod registry;
 pub mod single;
 pub mod traits;
 
+#[cfg(feature = "aggregate")]
+pub use aggregate::{AggregatedProof, AggregationCalibration, AggregationEstimate, Aggregator};
+pub use audit::{AuditLog, AuditRecord, AuditSink, Audited};
+pub use envelope::EnvelopeVersions;
+pub use limit::{ConcurrencyLimit, RateLimited, TokenBucket};
+pub use policy::{PolicyVerifier, VerifierPolicy};
+pub use registry::{CircuitRegistry, KeyVersion};
 pub use traits::{Verifier, VerifierResult};
 
 /// Re-export commonly used types
 pub mod prelude {
+    pub use super::audit::{AuditSink, Audited};
     pub use super::batch::BatchVerifier;
+    pub use super::envelope::EnvelopeVersions;
+    pub use super::limit::{ConcurrencyLimit, RateLimited, TokenBucket};
+    pub use super::policy::{PolicyVerifier, VerifierPolicy};
+    pub use super::registry::{CircuitRegistry, KeyVersion};
     pub use super::single::SingleVerifier;
     pub use super::traits::{Verifier, VerifierResult};
 }
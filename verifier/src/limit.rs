@@ -0,0 +1,246 @@
+//! Rate and concurrency limits for verification
+//!
+//! Verifying an aggregated proof can take orders of magnitude longer than
+//! submitting one. [`RateLimited`] charges every verification against a
+//! [`TokenBucket`] and caps how many run at once, turning a flood into
+//! [`Error::RateLimited`] responses instead of a node that stops answering.
+//! Expensive proofs can be charged more than one token.
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use zk_proof_core::{Error, Result};
+
+use crate::traits::{Verifier, VerifierResult};
+
+/// Token bucket refilled at a constant rate
+#[derive(Debug)]
+pub struct TokenBucket {
+    capacity: f64,
+    per_second: f64,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl TokenBucket {
+    /// Full bucket holding `capacity` tokens and gaining `per_second` tokens
+    /// each second
+    ///
+    /// # Panics
+    ///
+    /// Panics if `per_second` is zero.
+    #[must_use]
+    pub fn new(capacity: u32, per_second: u32) -> Self {
+        assert!(per_second > 0, "token bucket must refill");
+        Self {
+            capacity: f64::from(capacity),
+            per_second: f64::from(per_second),
+            state: Mutex::new(BucketState {
+                tokens: f64::from(capacity),
+                refilled: Instant::now(),
+            }),
+        }
+    }
+
+    /// Take `cost` tokens, or report how long until they are available
+    ///
+    /// # Errors
+    ///
+    /// Returns the wait until `cost` tokens will be available. A cost above
+    /// the capacity can never be met and reports [`Duration::MAX`].
+    pub fn try_acquire(&self, cost: u32) -> std::result::Result<(), Duration> {
+        let cost = f64::from(cost);
+        if cost > self.capacity {
+            return Err(Duration::MAX);
+        }
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let now = Instant::now();
+        let gained = now.duration_since(state.refilled).as_secs_f64() * self.per_second;
+        state.tokens = (state.tokens + gained).min(self.capacity);
+        state.refilled = now;
+
+        if state.tokens >= cost {
+            state.tokens -= cost;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (cost - state.tokens) / self.per_second,
+            ))
+        }
+    }
+}
+
+/// Cap on the number of operations in flight
+#[derive(Debug)]
+pub struct ConcurrencyLimit {
+    max: usize,
+    active: AtomicUsize,
+}
+
+impl ConcurrencyLimit {
+    /// Allow at most `max` operations at once
+    #[must_use]
+    pub fn new(max: usize) -> Self {
+        Self {
+            max,
+            active: AtomicUsize::new(0),
+        }
+    }
+
+    /// Operations currently in flight
+    #[must_use]
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::Acquire)
+    }
+
+    /// Claim a slot, released when the permit is dropped
+    #[must_use]
+    pub fn try_enter(&self) -> Option<Permit<'_>> {
+        self.active
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |active| {
+                (active < self.max).then_some(active + 1)
+            })
+            .ok()
+            .map(|_| Permit { limit: self })
+    }
+}
+
+/// Slot held in a [`ConcurrencyLimit`]
+#[derive(Debug)]
+pub struct Permit<'a> {
+    limit: &'a ConcurrencyLimit,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.limit.active.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Verifier guarded by a token bucket and a concurrency cap
+#[derive(Debug)]
+pub struct RateLimited<V> {
+    verifier: V,
+    bucket: TokenBucket,
+    concurrency: ConcurrencyLimit,
+}
+
+impl<V: Verifier> RateLimited<V> {
+    /// Guard `verifier`
+    #[must_use]
+    pub fn new(verifier: V, bucket: TokenBucket, concurrency: ConcurrencyLimit) -> Self {
+        Self {
+            verifier,
+            bucket,
+            concurrency,
+        }
+    }
+
+    /// Verify `proof`, charging `cost` tokens
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::RateLimited`] if the bucket lacks `cost` tokens or
+    /// every slot is busy. Tokens are only charged once a slot is held.
+    pub fn verify_weighted(&self, proof: &[u8], cost: u32) -> Result<VerifierResult> {
+        let _permit = self.concurrency.try_enter().ok_or(Error::RateLimited {
+            retry_after: Duration::ZERO,
+        })?;
+        self.bucket
+            .try_acquire(cost)
+            .map_err(|retry_after| Error::RateLimited { retry_after })?;
+        Ok(self.verifier.verify(proof))
+    }
+
+    /// Verify `proof` at a cost of one token
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::RateLimited`] as for [`RateLimited::verify_weighted`].
+    pub fn verify(&self, proof: &[u8]) -> Result<VerifierResult> {
+        self.verify_weighted(proof, 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Barrier};
+
+    use super::*;
+
+    #[test]
+    fn test_bucket_refills() {
+        let bucket = TokenBucket::new(2, 50);
+        assert!(bucket.try_acquire(1).is_ok());
+        assert!(bucket.try_acquire(1).is_ok());
+        let wait = bucket.try_acquire(1).unwrap_err();
+        assert!(wait > Duration::ZERO && wait <= Duration::from_millis(20));
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(bucket.try_acquire(1).is_ok());
+        assert_eq!(bucket.try_acquire(3), Err(Duration::MAX));
+    }
+
+    #[test]
+    fn test_concurrency_limit() {
+        let limit = ConcurrencyLimit::new(2);
+        let first = limit.try_enter().unwrap();
+        let _second = limit.try_enter().unwrap();
+        assert!(limit.try_enter().is_none());
+        drop(first);
+        assert_eq!(limit.active(), 1);
+        assert!(limit.try_enter().is_some());
+    }
+
+    /// Holds each verification until the test releases it
+    struct Blocking(Arc<Barrier>);
+
+    impl Verifier for Blocking {
+        fn verify(&self, _: &[u8]) -> VerifierResult {
+            self.0.wait();
+            self.0.wait();
+            VerifierResult::Valid
+        }
+    }
+
+    #[test]
+    fn test_rate_limited_verifier() {
+        let barrier = Arc::new(Barrier::new(2));
+        let verifier = Arc::new(RateLimited::new(
+            Blocking(barrier.clone()),
+            TokenBucket::new(4, 1),
+            ConcurrencyLimit::new(1),
+        ));
+
+        let busy = {
+            let verifier = verifier.clone();
+            std::thread::spawn(move || verifier.verify_weighted(&[], 3))
+        };
+        barrier.wait();
+        assert!(matches!(
+            verifier.verify(&[]),
+            Err(Error::RateLimited { retry_after }) if retry_after.is_zero()
+        ));
+        barrier.wait();
+        assert_eq!(busy.join().unwrap().unwrap(), VerifierResult::Valid);
+
+        // One token left, so a second expensive proof has to wait
+        assert!(matches!(
+            verifier.verify_weighted(&[], 3),
+            Err(Error::RateLimited { retry_after }) if retry_after > Duration::from_secs(1)
+        ));
+    }
+}
@@ -0,0 +1,340 @@
+//! Cheap admission checks ahead of verification
+//!
+//! A [`PolicyVerifier`] rejects requests for circuits or verifying keys that
+//! are not on its allow-list, oversized envelopes and requests with too many
+//! public inputs before decoding the proof or touching any curve arithmetic.
+//! Public endpoints put it in front of the real verifier so junk costs a few
+//! comparisons instead of a multi-millisecond pairing or IPA check.
+//!
+//! Keys registered with a circuit version also reject envelopes claiming a
+//! different one. The claim itself is unauthenticated; the inner verifier
+//! must check proofs with [`zk_proof_core::verify_versioned_proof`] against
+//! the registered version, which is what makes a false claim fail.
+
+use std::time::SystemTime;
+
+use zk_proof_core::{Error, Result, VkHash};
+
+use crate::{
+    envelope::EnvelopeVersions,
+    registry::{CircuitRegistry, KeyVersion},
+    traits::{Verifier, VerifierResult},
+};
+
+/// Default limit on the encoded envelope size
+pub const DEFAULT_MAX_PROOF_LEN: usize = 1 << 20;
+
+/// Default limit on the number of public inputs
+pub const DEFAULT_MAX_INSTANCES: usize = 1 << 12;
+
+/// What a verifier is willing to look at
+///
+/// An empty policy rejects everything; circuits are admitted one verifying
+/// key at a time with [`VerifierPolicy::allow`], or with validity windows
+/// through a [`CircuitRegistry`].
+#[derive(Debug, Clone)]
+pub struct VerifierPolicy {
+    registry: CircuitRegistry,
+    max_proof_len: usize,
+    max_instances: usize,
+    versions: EnvelopeVersions,
+}
+
+impl VerifierPolicy {
+    /// Policy with an empty allow-list and the default limits
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            registry: CircuitRegistry::new(),
+            max_proof_len: DEFAULT_MAX_PROOF_LEN,
+            max_instances: DEFAULT_MAX_INSTANCES,
+            versions: EnvelopeVersions::all(),
+        }
+    }
+
+    /// Accept proofs of `circuit_id` made for `vk_hash`, without expiry
+    ///
+    /// A circuit may be allowed under several keys. Allowing the same key
+    /// twice has no effect.
+    #[must_use]
+    pub fn allow(mut self, circuit_id: impl Into<String>, vk_hash: VkHash) -> Self {
+        let circuit_id = circuit_id.into();
+        let known = self
+            .registry
+            .versions(&circuit_id)
+            .iter()
+            .any(|key| key.vk_hash == vk_hash);
+        if !known {
+            let key = KeyVersion::new(self.registry.next_version(&circuit_id), vk_hash);
+            // Cannot collide: the version is fresh and the key is not known
+            let _ = self.registry.register(circuit_id, key);
+        }
+        self
+    }
+
+    /// Accept exactly the keys of `registry`, each within its window
+    #[must_use]
+    pub fn registry(mut self, registry: CircuitRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Registry of accepted keys
+    #[must_use]
+    pub fn circuits(&self) -> &CircuitRegistry {
+        &self.registry
+    }
+
+    /// Reject envelopes longer than `len` bytes
+    #[must_use]
+    pub fn max_proof_len(mut self, len: usize) -> Self {
+        self.max_proof_len = len;
+        self
+    }
+
+    /// Reject requests with more than `count` public inputs
+    #[must_use]
+    pub fn max_instances(mut self, count: usize) -> Self {
+        self.max_instances = count;
+        self
+    }
+
+    /// Accept only these envelope versions
+    #[must_use]
+    pub fn versions(mut self, versions: EnvelopeVersions) -> Self {
+        self.versions = versions;
+        self
+    }
+
+    /// Check the parts of a request that need no decoding
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PolicyRejected`] if the circuit is unknown, the
+    /// envelope is too long or there are too many public inputs.
+    pub fn check_request(&self, circuit_id: &str, len: usize, instances: usize) -> Result<()> {
+        if !self.registry.contains(circuit_id) {
+            return Err(reject(format!("circuit {circuit_id:?} is not allowed")));
+        }
+        if len > self.max_proof_len {
+            return Err(reject(format!(
+                "proof of {len} bytes exceeds the limit of {}",
+                self.max_proof_len
+            )));
+        }
+        if instances > self.max_instances {
+            return Err(reject(format!(
+                "{instances} public inputs exceed the limit of {}",
+                self.max_instances
+            )));
+        }
+        Ok(())
+    }
+
+    /// Key version `vk_hash` is currently accepted under for `circuit_id`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PolicyRejected`] if it is not accepted.
+    pub fn check_vk(&self, circuit_id: &str, vk_hash: &VkHash) -> Result<&KeyVersion> {
+        self.registry
+            .resolve(circuit_id, vk_hash, SystemTime::now())
+            .map_err(|_| {
+                reject(format!(
+                    "vk {vk_hash} is not allowed for circuit {circuit_id:?}"
+                ))
+            })
+    }
+
+    /// Check a proof's claimed circuit version against the one `key` pins
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PolicyRejected`] if `key` pins a version and the
+    /// claim is missing or different.
+    pub fn check_circuit_version(&self, key: &KeyVersion, claimed: Option<&str>) -> Result<()> {
+        match key.circuit_version.as_deref() {
+            Some(pinned) if claimed != Some(pinned) => Err(reject(format!(
+                "proof claims circuit version {claimed:?}, key {} requires {pinned:?}",
+                key.version
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for VerifierPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn reject(reason: String) -> Error {
+    Error::PolicyRejected(reason)
+}
+
+/// Verifier that only sees requests admitted by a [`VerifierPolicy`]
+#[derive(Debug)]
+pub struct PolicyVerifier<V> {
+    policy: VerifierPolicy,
+    verifier: V,
+}
+
+impl<V: Verifier> PolicyVerifier<V> {
+    /// Guard `verifier` with `policy`
+    #[must_use]
+    pub fn new(policy: VerifierPolicy, verifier: V) -> Self {
+        Self { policy, verifier }
+    }
+
+    /// Policy in force
+    #[must_use]
+    pub fn policy(&self) -> &VerifierPolicy {
+        &self.policy
+    }
+
+    /// Verify an encoded envelope for `circuit_id` with `instances` public
+    /// inputs
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PolicyRejected`] if the request breaks the policy and
+    /// [`Error::Encoding`] if the envelope does not decode. Neither reaches
+    /// the inner verifier.
+    pub fn verify(
+        &self,
+        circuit_id: &str,
+        envelope: &[u8],
+        instances: usize,
+    ) -> Result<VerifierResult> {
+        self.policy
+            .check_request(circuit_id, envelope.len(), instances)?;
+        let envelope = self.policy.versions.read(envelope)?;
+        let key = self.policy.check_vk(circuit_id, &envelope.vk_hash)?;
+        self.policy
+            .check_circuit_version(key, envelope.metadata.circuit_version.as_deref())?;
+        Ok(self.verifier.verify(&envelope.proof))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use zk_proof_core::{ProofEnvelope, ProofMetadata};
+
+    use super::*;
+
+    /// Counts how often verification actually ran
+    #[derive(Default)]
+    struct Counting(Cell<usize>);
+
+    impl Verifier for Counting {
+        fn verify(&self, _: &[u8]) -> VerifierResult {
+            self.0.set(self.0.get() + 1);
+            VerifierResult::Valid
+        }
+    }
+
+    fn envelope(vk: u8, len: usize) -> Vec<u8> {
+        versioned(vk, len, None)
+    }
+
+    fn versioned(vk: u8, len: usize, circuit_version: Option<&str>) -> Vec<u8> {
+        ProofEnvelope {
+            vk_hash: VkHash::new([vk; 32]),
+            proof: vec![0; len],
+            metadata: ProofMetadata {
+                circuit_version: circuit_version.map(str::to_owned),
+                ..ProofMetadata::default()
+            },
+        }
+        .to_bytes()
+    }
+
+    fn guarded() -> PolicyVerifier<Counting> {
+        let policy = VerifierPolicy::new()
+            .allow("dci", VkHash::new([1; 32]))
+            .allow("dci", VkHash::new([2; 32]))
+            .max_proof_len(256)
+            .max_instances(4);
+        PolicyVerifier::new(policy, Counting::default())
+    }
+
+    #[test]
+    fn test_admits_allowed_requests() {
+        let verifier = guarded();
+        for vk in [1, 2] {
+            let result = verifier.verify("dci", &envelope(vk, 32), 4).unwrap();
+            assert_eq!(result, VerifierResult::Valid);
+        }
+        assert_eq!(verifier.verifier.0.get(), 2);
+    }
+
+    #[test]
+    fn test_rejects_before_verifying() {
+        let verifier = guarded();
+        let rejected = [
+            verifier.verify("pore", &envelope(1, 32), 1),
+            verifier.verify("dci", &envelope(3, 32), 1),
+            verifier.verify("dci", &envelope(1, 1024), 1),
+            verifier.verify("dci", &envelope(1, 32), 5),
+        ];
+        for result in rejected {
+            assert!(matches!(result, Err(Error::PolicyRejected(_))));
+        }
+        assert!(matches!(
+            verifier.verify("dci", b"junk", 1),
+            Err(Error::Encoding(_))
+        ));
+        assert_eq!(verifier.verifier.0.get(), 0);
+    }
+
+    #[test]
+    fn test_registry_windows() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let mut registry = CircuitRegistry::new();
+        registry
+            .register("dci", KeyVersion::new(1, VkHash::new([1; 32])))
+            .unwrap();
+        let key = KeyVersion::new(2, VkHash::new([2; 32])).valid_from(UNIX_EPOCH);
+        registry.rotate("dci", key, Duration::from_secs(1)).unwrap();
+        let policy = VerifierPolicy::new().registry(registry);
+        let verifier = PolicyVerifier::new(policy, Counting::default());
+
+        assert!(verifier.verify("dci", &envelope(2, 32), 0).is_ok());
+        assert!(matches!(
+            verifier.verify("dci", &envelope(1, 32), 0),
+            Err(Error::PolicyRejected(_))
+        ));
+    }
+
+    #[test]
+    fn test_circuit_version_guard() {
+        let mut registry = CircuitRegistry::new();
+        let key = KeyVersion::new(1, VkHash::new([1; 32])).circuit_version("2.0");
+        registry.register("dci", key).unwrap();
+        let verifier = PolicyVerifier::new(
+            VerifierPolicy::new().registry(registry),
+            Counting::default(),
+        );
+
+        assert!(verifier
+            .verify("dci", &versioned(1, 32, Some("2.0")), 0)
+            .is_ok());
+        for claim in [None, Some("1.9")] {
+            assert!(matches!(
+                verifier.verify("dci", &versioned(1, 32, claim), 0),
+                Err(Error::PolicyRejected(_))
+            ));
+        }
+        assert_eq!(verifier.verifier.0.get(), 1);
+    }
+
+    #[test]
+    fn test_empty_policy_rejects_everything() {
+        let verifier = PolicyVerifier::new(VerifierPolicy::default(), Counting::default());
+        assert!(verifier.verify("dci", &envelope(1, 32), 0).is_err());
+    }
+}
@@ -0,0 +1,266 @@
+//! Verifying keys per circuit, with validity windows for rotation
+//!
+//! Upgrading a circuit changes its verifying key, but proofs made under the
+//! old key may still be in flight. A [`CircuitRegistry`] keeps every key
+//! version of a circuit with the window in which it is accepted.
+//! [`CircuitRegistry::rotate`] registers a new key and retires the previous
+//! one after an overlap, so both verify during the rollover and the old key
+//! stops being accepted once it ends.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use zk_proof_core::{Error, Result, VkHash};
+
+/// One verifying key of a circuit and when it is accepted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyVersion {
+    /// Version number, increasing with each rotation
+    pub version: u32,
+    /// Hash of the verifying key
+    pub vk_hash: VkHash,
+    /// First moment the key is accepted
+    pub valid_from: SystemTime,
+    /// Moment the key stops being accepted, if retired
+    pub valid_until: Option<SystemTime>,
+    /// Circuit version proofs under this key must be made for, if pinned
+    pub circuit_version: Option<String>,
+}
+
+impl KeyVersion {
+    /// Key accepted from the start of time until retired
+    #[must_use]
+    pub fn new(version: u32, vk_hash: VkHash) -> Self {
+        Self {
+            version,
+            vk_hash,
+            valid_from: UNIX_EPOCH,
+            valid_until: None,
+            circuit_version: None,
+        }
+    }
+
+    /// Require proofs under this key to be made for `version`
+    ///
+    /// See [`zk_proof_core::circuit_version`] for how the version is bound
+    /// into proofs.
+    #[must_use]
+    pub fn circuit_version(mut self, version: impl Into<String>) -> Self {
+        self.circuit_version = Some(version.into());
+        self
+    }
+
+    /// Accept the key from `at` on
+    #[must_use]
+    pub fn valid_from(mut self, at: SystemTime) -> Self {
+        self.valid_from = at;
+        self
+    }
+
+    /// Stop accepting the key at `at`
+    #[must_use]
+    pub fn valid_until(mut self, at: SystemTime) -> Self {
+        self.valid_until = Some(at);
+        self
+    }
+
+    /// Whether the key is accepted at `at`
+    #[must_use]
+    pub fn is_valid_at(&self, at: SystemTime) -> bool {
+        self.valid_from <= at && self.valid_until.is_none_or(|until| at < until)
+    }
+}
+
+/// Key versions of every known circuit
+#[derive(Debug, Clone, Default)]
+pub struct CircuitRegistry {
+    circuits: HashMap<String, Vec<KeyVersion>>,
+}
+
+impl CircuitRegistry {
+    /// Empty registry
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a key version for `circuit_id`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Other`] if the circuit already has this version
+    /// number or key.
+    pub fn register(&mut self, circuit_id: impl Into<String>, key: KeyVersion) -> Result<()> {
+        let circuit_id = circuit_id.into();
+        let versions = self.circuits.entry(circuit_id.clone()).or_default();
+        if let Some(existing) = versions
+            .iter()
+            .find(|v| v.version == key.version || v.vk_hash == key.vk_hash)
+        {
+            return Err(Error::Other(format!(
+                "circuit {circuit_id:?} already has key version {} with vk {}",
+                existing.version, existing.vk_hash
+            )));
+        }
+        versions.push(key);
+        versions.sort_by_key(|v| v.version);
+        Ok(())
+    }
+
+    /// Register `key` for `circuit_id` and retire every older version
+    /// `overlap` after `key` becomes valid
+    ///
+    /// Build `key` with [`CircuitRegistry::next_version`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Other`] if the circuit already has this version
+    /// number or key.
+    pub fn rotate(
+        &mut self,
+        circuit_id: impl Into<String>,
+        key: KeyVersion,
+        overlap: Duration,
+    ) -> Result<()> {
+        let circuit_id = circuit_id.into();
+        let (version, at) = (key.version, key.valid_from);
+        self.register(circuit_id.clone(), key)?;
+
+        // An overlap past the end of representable time never retires
+        if let Some(retire) = at.checked_add(overlap) {
+            for old in self.circuits.get_mut(&circuit_id).into_iter().flatten() {
+                if old.version < version {
+                    old.valid_until =
+                        Some(old.valid_until.map_or(retire, |until| until.min(retire)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Version number a new key of `circuit_id` would get
+    #[must_use]
+    pub fn next_version(&self, circuit_id: &str) -> u32 {
+        self.versions(circuit_id)
+            .last()
+            .map_or(1, |latest| latest.version + 1)
+    }
+
+    /// Whether any key was ever registered for `circuit_id`
+    #[must_use]
+    pub fn contains(&self, circuit_id: &str) -> bool {
+        self.circuits.contains_key(circuit_id)
+    }
+
+    /// Every key version of `circuit_id`, oldest first
+    #[must_use]
+    pub fn versions(&self, circuit_id: &str) -> &[KeyVersion] {
+        self.circuits.get(circuit_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Newest key of `circuit_id` valid at `at`, the one provers should use
+    #[must_use]
+    pub fn current(&self, circuit_id: &str, at: SystemTime) -> Option<&KeyVersion> {
+        self.versions(circuit_id)
+            .iter()
+            .rev()
+            .find(|key| key.is_valid_at(at))
+    }
+
+    /// Key version a proof made for `vk_hash` verifies under at `at`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::VkMismatch`] if `vk_hash` is not a key of
+    /// `circuit_id` valid at `at`.
+    pub fn resolve(
+        &self,
+        circuit_id: &str,
+        vk_hash: &VkHash,
+        at: SystemTime,
+    ) -> Result<&KeyVersion> {
+        self.versions(circuit_id)
+            .iter()
+            .find(|key| key.vk_hash == *vk_hash && key.is_valid_at(at))
+            .ok_or_else(|| Error::VkMismatch {
+                expected: self
+                    .current(circuit_id, at)
+                    .map_or_else(|| "none".into(), |key| key.vk_hash.to_string()),
+                found: vk_hash.to_string(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_rollover_window() {
+        let (old, new) = (VkHash::new([1; 32]), VkHash::new([2; 32]));
+        let mut registry = CircuitRegistry::new();
+        registry
+            .register("dci", KeyVersion::new(1, old).valid_from(at(0)))
+            .unwrap();
+        let key = KeyVersion::new(registry.next_version("dci"), new).valid_from(at(1000));
+        assert_eq!(key.version, 2);
+        registry
+            .rotate("dci", key, Duration::from_secs(100))
+            .unwrap();
+
+        // Before the rotation only the old key is accepted
+        assert_eq!(registry.resolve("dci", &old, at(999)).unwrap().version, 1);
+        assert!(registry.resolve("dci", &new, at(999)).is_err());
+
+        // During the rollover both are, and provers move to the new one
+        assert_eq!(registry.resolve("dci", &old, at(1050)).unwrap().version, 1);
+        assert_eq!(registry.resolve("dci", &new, at(1050)).unwrap().version, 2);
+        assert_eq!(registry.current("dci", at(1050)).unwrap().vk_hash, new);
+
+        // Afterwards the old key is retired
+        assert!(matches!(
+            registry.resolve("dci", &old, at(1100)),
+            Err(Error::VkMismatch { expected, .. }) if expected == new.to_string()
+        ));
+        assert!(registry.resolve("dci", &new, at(5000)).is_ok());
+    }
+
+    #[test]
+    fn test_register_rejects_duplicates() {
+        let mut registry = CircuitRegistry::new();
+        registry
+            .register("dci", KeyVersion::new(1, VkHash::new([1; 32])))
+            .unwrap();
+        assert!(registry
+            .register("dci", KeyVersion::new(1, VkHash::new([2; 32])))
+            .is_err());
+        assert!(registry
+            .register("dci", KeyVersion::new(2, VkHash::new([1; 32])))
+            .is_err());
+        assert!(registry
+            .rotate(
+                "dci",
+                KeyVersion::new(2, VkHash::new([1; 32])),
+                Duration::ZERO
+            )
+            .is_err());
+        assert_eq!(registry.versions("dci").len(), 1);
+    }
+
+    #[test]
+    fn test_unknown_circuit() {
+        let registry = CircuitRegistry::new();
+        assert!(!registry.contains("dci"));
+        assert!(registry.current("dci", at(0)).is_none());
+        assert!(matches!(
+            registry.resolve("dci", &VkHash::new([1; 32]), at(0)),
+            Err(Error::VkMismatch { expected, .. }) if expected == "none"
+        ));
+    }
+}
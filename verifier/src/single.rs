@@ -1,27 +1,306 @@
 //! Single proof verification
+//!
+//! A [`SingleVerifier`] owns the params and verifying key of one circuit and
+//! runs the halo2 verifier on each proof. [`SingleVerifier::check`] reports
+//! why a proof was rejected; [`Verifier::verify`] folds that into a
+//! [`VerifierResult`]: proofs that fail the verifier's checks or do not
+//! decode are [`VerifierResult::Invalid`], while requests the verifier cannot
+//! judge, such as instances of the wrong shape, are
+//! [`VerifierResult::Error`].
+//!
+//! [`SingleVerifier::verify_stream`] reads the proof from a [`Read`]
+//! as the transcript consumes it, a point or scalar at a time, so a proof
+//! of several megabytes never has to sit in memory at once.
+
+use std::io::{BufReader, Read};
+
+use ff::FromUniformBytes;
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    plonk::{self, verify_proof, Error as PlonkError, VerifyingKey},
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Challenge255},
+};
+use zk_proof_core::{Error, ErrorCategory, Result};
 
 use crate::traits::{Verifier, VerifierResult};
 
-/// Single proof verifier
+/// Bytes buffered from a streamed proof, enough for a hundred points
+pub const STREAM_BUFFER: usize = 4096;
+
+/// Verifier for proofs of one circuit
 #[derive(Debug)]
-pub struct SingleVerifier;
+pub struct SingleVerifier<C: CurveAffine> {
+    params: Params<C>,
+    vk: VerifyingKey<C>,
+    instances: Vec<Vec<C::Scalar>>,
+}
+
+impl<C> SingleVerifier<C>
+where
+    C: CurveAffine,
+    C::Scalar: FromUniformBytes<64>,
+{
+    /// Verifier for proofs made with `params` for `vk`
+    ///
+    /// [`Verifier::verify`] checks proofs against no instance columns at
+    /// all until [`SingleVerifier::with_instances`] sets the statement.
+    #[must_use]
+    pub fn new(params: Params<C>, vk: VerifyingKey<C>) -> Self {
+        Self {
+            params,
+            vk,
+            instances: Vec::new(),
+        }
+    }
+
+    /// Check proofs passed to [`Verifier::verify`] against `instances`
+    #[must_use]
+    pub fn with_instances(mut self, instances: Vec<Vec<C::Scalar>>) -> Self {
+        self.instances = instances;
+        self
+    }
+
+    /// Verifying key proofs are checked against
+    #[must_use]
+    pub fn verifying_key(&self) -> &VerifyingKey<C> {
+        &self.vk
+    }
+
+    /// Verify `proof` for the statement given by `instances`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Plonk`] if the proof does not verify or `instances`
+    /// does not match the circuit's instance columns, and [`Error::Io`] if
+    /// the proof is truncated or holds an invalid point.
+    pub fn check(&self, instances: &[Vec<C::Scalar>], proof: &[u8]) -> Result<()> {
+        self.check_transcript(instances, proof)
+    }
 
-impl SingleVerifier {
-    /// Create a new single verifier
-    #[must_use] pub fn new() -> Self {
-        Self
+    /// [`SingleVerifier::check`] reading the proof from `reader`
+    ///
+    /// The proof is read through a buffer of [`STREAM_BUFFER`] bytes as the
+    /// verifier needs it, so up to that many bytes past the end of the
+    /// proof may be consumed from `reader`.
+    ///
+    /// # Errors
+    ///
+    /// As [`SingleVerifier::check`]; a reader that fails part way through
+    /// is reported like a truncated proof, as [`Error::Io`].
+    pub fn check_stream(&self, instances: &[Vec<C::Scalar>], reader: impl Read) -> Result<()> {
+        self.check_transcript(instances, BufReader::with_capacity(STREAM_BUFFER, reader))
+    }
+
+    /// Verify a proof read from `reader` against the instances set with
+    /// [`SingleVerifier::with_instances`]
+    ///
+    /// The streaming counterpart of [`Verifier::verify`], see
+    /// [`SingleVerifier::check_stream`].
+    #[must_use]
+    pub fn verify_stream(&self, reader: impl Read) -> VerifierResult {
+        result(self.check_stream(&self.instances, reader))
+    }
+
+    fn check_transcript(&self, instances: &[Vec<C::Scalar>], reader: impl Read) -> Result<()> {
+        let columns: Vec<&[C::Scalar]> = instances.iter().map(Vec::as_slice).collect();
+        let mut transcript = Blake2bRead::<_, C, Challenge255<_>>::init(reader);
+        verify_proof(
+            &self.params,
+            &self.vk,
+            plonk::SingleVerifier::new(&self.params),
+            &[&columns],
+            &mut transcript,
+        )
+        .map_err(|err| match err {
+            PlonkError::Transcript(err) => Error::Io(err),
+            err => Error::Plonk(err),
+        })
+    }
+
+    /// [`SingleVerifier::check`] as a [`VerifierResult`]
+    #[must_use]
+    pub fn verify_instances(&self, instances: &[Vec<C::Scalar>], proof: &[u8]) -> VerifierResult {
+        result(self.check(instances, proof))
     }
 }
 
-impl Default for SingleVerifier {
-    fn default() -> Self {
-        Self::new()
+fn result(checked: Result<()>) -> VerifierResult {
+    match checked {
+        Ok(()) => VerifierResult::Valid,
+        Err(err) => match err.category() {
+            ErrorCategory::Verification | ErrorCategory::Io => VerifierResult::Invalid,
+            _ => VerifierResult::Error,
+        },
     }
 }
 
-impl Verifier for SingleVerifier {
+impl<C> Verifier for SingleVerifier<C>
+where
+    C: CurveAffine,
+    C::Scalar: FromUniformBytes<64>,
+{
     fn verify(&self, proof: &[u8]) -> VerifierResult {
-        // Implementation would go here
-        VerifierResult::Valid
+        self.verify_instances(&self.instances, proof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        plonk::{keygen_pk, keygen_vk, Advice, Circuit, Column, ConstraintSystem, Instance},
+    };
+    use pasta_curves::{vesta, Fp};
+    use zk_proof_core::{write_proof, DeterministicRandomness};
+
+    use super::*;
+
+    /// Exposes its witness as the only public input
+    #[derive(Default)]
+    struct Echo {
+        value: Value<Fp>,
+    }
+
+    impl Circuit<Fp> for Echo {
+        type Config = (Column<Advice>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(advice);
+            meta.enable_equality(instance);
+            (advice, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (advice, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> std::result::Result<(), PlonkError> {
+            let cell = layouter.assign_region(
+                || "value",
+                |mut region| region.assign_advice(|| "value", advice, 0, || self.value),
+            )?;
+            layouter.constrain_instance(cell.cell(), instance, 0)
+        }
+    }
+
+    fn prove(value: u64) -> (SingleVerifier<vesta::Affine>, Vec<u8>) {
+        let params = Params::<vesta::Affine>::new(4);
+        let vk = keygen_vk(&params, &Echo::default()).unwrap();
+        let pk = keygen_pk(&params, vk, &Echo::default()).unwrap();
+        let circuit = Echo {
+            value: Value::known(Fp::from(value)),
+        };
+        let proof = write_proof(
+            &params,
+            &pk,
+            &[circuit],
+            &[&[&[Fp::from(value)]]],
+            DeterministicRandomness::from_seed([1; 32]),
+            Vec::new(),
+        )
+        .unwrap();
+        let vk = keygen_vk(&params, &Echo::default()).unwrap();
+        (SingleVerifier::new(params, vk), proof)
+    }
+
+    #[test]
+    fn test_valid_proof() {
+        let (verifier, proof) = prove(5);
+        assert!(verifier.check(&[vec![Fp::from(5)]], &proof).is_ok());
+
+        let verifier = verifier.with_instances(vec![vec![Fp::from(5)]]);
+        assert_eq!(verifier.verify(&proof), VerifierResult::Valid);
+    }
+
+    #[test]
+    fn test_wrong_statement_is_invalid() {
+        let (verifier, proof) = prove(5);
+        assert!(matches!(
+            verifier.check(&[vec![Fp::from(6)]], &proof),
+            Err(Error::Plonk(_))
+        ));
+
+        let verifier = verifier.with_instances(vec![vec![Fp::from(6)]]);
+        assert_eq!(verifier.verify(&proof), VerifierResult::Invalid);
+    }
+
+    #[test]
+    fn test_malformed_proof_is_invalid() {
+        let (verifier, proof) = prove(5);
+        let instances = [vec![Fp::from(5)]];
+        assert!(matches!(
+            verifier.check(&instances, &proof[..proof.len() / 2]),
+            Err(Error::Io(_))
+        ));
+        assert_eq!(
+            verifier.verify_instances(&instances, &[0; 32]),
+            VerifierResult::Invalid
+        );
+    }
+
+    /// Hands out at most `chunk` bytes per read and counts the bytes read
+    struct Trickle<'a> {
+        data: &'a [u8],
+        chunk: usize,
+        read: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = buf.len().min(self.chunk).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            self.read += n;
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_verify_stream() {
+        let (verifier, proof) = prove(5);
+        let verifier = verifier.with_instances(vec![vec![Fp::from(5)]]);
+        let mut trickle = Trickle {
+            data: &proof,
+            chunk: 7,
+            read: 0,
+        };
+        assert_eq!(verifier.verify_stream(&mut trickle), VerifierResult::Valid);
+        assert_eq!(trickle.read, proof.len());
+
+        // Reading stops within a buffer of the end of the proof
+        let mut padded = proof.clone();
+        padded.resize(proof.len() + 3 * STREAM_BUFFER, 0);
+        let mut reader = padded.as_slice();
+        assert_eq!(verifier.verify_stream(&mut reader), VerifierResult::Valid);
+        assert!(!reader.is_empty());
+
+        assert_eq!(
+            verifier.verify_stream(&proof[..proof.len() - 1]),
+            VerifierResult::Invalid
+        );
+        assert!(matches!(
+            verifier.check_stream(&[vec![Fp::from(6)]], proof.as_slice()),
+            Err(Error::Plonk(_))
+        ));
+    }
+
+    #[test]
+    fn test_instance_shape_is_an_error() {
+        let (verifier, proof) = prove(5);
+        let instances = [vec![Fp::from(5)], vec![Fp::from(5)]];
+        assert_eq!(
+            verifier.verify_instances(&instances, &proof),
+            VerifierResult::Error
+        );
+        // Without a statement there are no instance columns at all
+        assert_eq!(verifier.verify(&proof), VerifierResult::Error);
     }
 }